@@ -180,6 +180,11 @@ pub const STRUCTURE_TYPE_PHYSICAL_DEVICE_SPARSE_IMAGE_FORMAT_INFO_2_KHR: u32 = 1
 pub const STRUCTURE_TYPE_VI_SURFACE_CREATE_INFO_NN: u32 = 1000062000;
 pub const STRUCTURE_TYPE_PHYSICAL_DEVICE_PUSH_DESCRIPTOR_PROPERTIES_KHR: u32 = 1000080000;
 pub const STRUCTURE_TYPE_DESCRIPTOR_UPDATE_TEMPLATE_CREATE_INFO_KHR: u32 = 1000085000;
+pub const STRUCTURE_TYPE_DESCRIPTOR_SET_LAYOUT_BINDING_FLAGS_CREATE_INFO_EXT: u32 = 1000161000;
+pub const STRUCTURE_TYPE_PHYSICAL_DEVICE_DESCRIPTOR_INDEXING_FEATURES_EXT: u32 = 1000161001;
+pub const STRUCTURE_TYPE_PHYSICAL_DEVICE_DESCRIPTOR_INDEXING_PROPERTIES_EXT: u32 = 1000161002;
+pub const STRUCTURE_TYPE_DESCRIPTOR_SET_VARIABLE_DESCRIPTOR_COUNT_ALLOCATE_INFO_EXT: u32 = 1000161003;
+pub const STRUCTURE_TYPE_DESCRIPTOR_SET_VARIABLE_DESCRIPTOR_COUNT_LAYOUT_SUPPORT_EXT: u32 = 1000161004;
 
 pub type SystemAllocationScope = u32;
 pub const SYSTEM_ALLOCATION_SCOPE_COMMAND: u32 = 0;
@@ -822,6 +827,7 @@ pub type DescriptorSetLayoutCreateFlags = Flags;
 
 pub type DescriptorPoolCreateFlagBits = u32;
 pub const DESCRIPTOR_POOL_CREATE_FREE_DESCRIPTOR_SET_BIT: u32 = 0x00000001;
+pub const DESCRIPTOR_POOL_CREATE_UPDATE_AFTER_BIND_BIT_EXT: u32 = 0x00000002;
 pub type DescriptorPoolCreateFlags = Flags;
 pub type DescriptorPoolResetFlags = Flags;
 pub type FramebufferCreateFlags = Flags;
@@ -1007,6 +1013,14 @@ pub type IOSSurfaceCreateFlagsMVK = u32;
 
 pub type DescriptorSetLayoutCreateFlagBits = u32;
 pub const DESCRIPTOR_SET_LAYOUT_CREATE_PUSH_DESCRIPTOR_BIT_KHR: u32 = 0x00000001;
+pub const DESCRIPTOR_SET_LAYOUT_CREATE_UPDATE_AFTER_BIND_POOL_BIT_EXT: u32 = 0x00000002;
+
+pub type DescriptorBindingFlagBitsEXT = u32;
+pub const DESCRIPTOR_BINDING_UPDATE_AFTER_BIND_BIT_EXT: u32 = 0x00000001;
+pub const DESCRIPTOR_BINDING_UPDATE_UNUSED_WHILE_PENDING_BIT_EXT: u32 = 0x00000002;
+pub const DESCRIPTOR_BINDING_PARTIALLY_BOUND_BIT_EXT: u32 = 0x00000004;
+pub const DESCRIPTOR_BINDING_VARIABLE_DESCRIPTOR_COUNT_BIT_EXT: u32 = 0x00000008;
+pub type DescriptorBindingFlagsEXT = Flags;
 
 pub type DescriptorUpdateTemplateTypeKHR = u32;
 pub const DESCRIPTOR_UPDATE_TEMPLATE_TYPE_DESCRIPTOR_SET_KHR: u32 = 0;
@@ -2584,6 +2598,77 @@ pub struct DescriptorUpdateTemplateCreateInfoKHR {
     pub set: u32,
 }
 
+#[repr(C)]
+pub struct PhysicalDeviceDescriptorIndexingFeaturesEXT {
+    pub sType: StructureType,
+    pub pNext: *const c_void,
+    pub shaderInputAttachmentArrayDynamicIndexing: Bool32,
+    pub shaderUniformTexelBufferArrayDynamicIndexing: Bool32,
+    pub shaderStorageTexelBufferArrayDynamicIndexing: Bool32,
+    pub shaderUniformBufferArrayNonUniformIndexing: Bool32,
+    pub shaderSampledImageArrayNonUniformIndexing: Bool32,
+    pub shaderStorageBufferArrayNonUniformIndexing: Bool32,
+    pub shaderStorageImageArrayNonUniformIndexing: Bool32,
+    pub shaderInputAttachmentArrayNonUniformIndexing: Bool32,
+    pub shaderUniformTexelBufferArrayNonUniformIndexing: Bool32,
+    pub shaderStorageTexelBufferArrayNonUniformIndexing: Bool32,
+    pub descriptorBindingUniformBufferUpdateAfterBind: Bool32,
+    pub descriptorBindingSampledImageUpdateAfterBind: Bool32,
+    pub descriptorBindingStorageImageUpdateAfterBind: Bool32,
+    pub descriptorBindingStorageBufferUpdateAfterBind: Bool32,
+    pub descriptorBindingUniformTexelBufferUpdateAfterBind: Bool32,
+    pub descriptorBindingStorageTexelBufferUpdateAfterBind: Bool32,
+    pub descriptorBindingUpdateUnusedWhilePending: Bool32,
+    pub descriptorBindingPartiallyBound: Bool32,
+    pub descriptorBindingVariableDescriptorCount: Bool32,
+    pub runtimeDescriptorArray: Bool32,
+}
+
+#[repr(C)]
+pub struct PhysicalDeviceDescriptorIndexingPropertiesEXT {
+    pub sType: StructureType,
+    pub pNext: *const c_void,
+    pub maxUpdateAfterBindDescriptorsInAllPools: u32,
+    pub shaderUniformBufferArrayNonUniformIndexingNative: Bool32,
+    pub shaderSampledImageArrayNonUniformIndexingNative: Bool32,
+    pub shaderStorageBufferArrayNonUniformIndexingNative: Bool32,
+    pub shaderStorageImageArrayNonUniformIndexingNative: Bool32,
+    pub shaderInputAttachmentArrayNonUniformIndexingNative: Bool32,
+    pub robustBufferAccessUpdateAfterBind: Bool32,
+    pub quadDivergentImplicitLod: Bool32,
+    pub maxPerStageDescriptorUpdateAfterBindSamplers: u32,
+    pub maxPerStageDescriptorUpdateAfterBindUniformBuffers: u32,
+    pub maxPerStageDescriptorUpdateAfterBindStorageBuffers: u32,
+    pub maxPerStageDescriptorUpdateAfterBindSampledImages: u32,
+    pub maxPerStageDescriptorUpdateAfterBindStorageImages: u32,
+    pub maxPerStageDescriptorUpdateAfterBindInputAttachments: u32,
+    pub maxPerStageUpdateAfterBindResources: u32,
+    pub maxDescriptorSetUpdateAfterBindSamplers: u32,
+    pub maxDescriptorSetUpdateAfterBindUniformBuffers: u32,
+    pub maxDescriptorSetUpdateAfterBindUniformBuffersDynamic: u32,
+    pub maxDescriptorSetUpdateAfterBindStorageBuffers: u32,
+    pub maxDescriptorSetUpdateAfterBindStorageBuffersDynamic: u32,
+    pub maxDescriptorSetUpdateAfterBindSampledImages: u32,
+    pub maxDescriptorSetUpdateAfterBindStorageImages: u32,
+    pub maxDescriptorSetUpdateAfterBindInputAttachments: u32,
+}
+
+#[repr(C)]
+pub struct DescriptorSetLayoutBindingFlagsCreateInfoEXT {
+    pub sType: StructureType,
+    pub pNext: *const c_void,
+    pub bindingCount: u32,
+    pub pBindingFlags: *const DescriptorBindingFlagsEXT,
+}
+
+#[repr(C)]
+pub struct DescriptorSetVariableDescriptorCountAllocateInfoEXT {
+    pub sType: StructureType,
+    pub pNext: *const c_void,
+    pub descriptorSetCount: u32,
+    pub pDescriptorCounts: *const u32,
+}
+
 
 macro_rules! ptrs {
     ($struct_name:ident, { $($name:ident => ($($param_n:ident: $param_ty:ty),*) -> $ret:ty,)+ }) => (
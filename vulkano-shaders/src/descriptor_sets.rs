@@ -9,10 +9,11 @@
 
 use std::cmp;
 
+use Error;
 use enums;
 use parse;
 
-pub fn write_descriptor_sets(doc: &parse::Spirv) -> String {
+pub fn write_descriptor_sets(doc: &parse::Spirv) -> Result<String, Error> {
     // TODO: not implemented correctly
 
     // Finding all the descriptors.
@@ -63,7 +64,7 @@ pub fn write_descriptor_sets(doc: &parse::Spirv) -> String {
             .expect(&format!("Uniform `{}` is missing a binding", name));
 
         // Find informations about the kind of binding for this descriptor.
-        let (desc_ty, readonly, array_count) = descriptor_infos(doc, pointed_ty, false)
+        let (desc_ty, readonly, array_count) = descriptor_infos(doc, pointed_ty, false)?
             .expect(&format!("Couldn't find relevant type for uniform `{}` (type {}, maybe \
                               unimplemented)",
                              name,
@@ -99,6 +100,9 @@ pub fn write_descriptor_sets(doc: &parse::Spirv) -> String {
     }
 
     // Writing the body of the `descriptor` method.
+    //
+    // The samplers are objects created at runtime, so the generated layout never has any
+    // immutable sampler. They are added with `PipelineLayoutDesc::with_immutable_samplers`.
     let descriptor_body = descriptors
         .iter()
         .map(|d| {
@@ -108,6 +112,7 @@ pub fn write_descriptor_sets(doc: &parse::Spirv) -> String {
             array_count: {array_count},
             stages: self.0.clone(),
             readonly: {readonly},
+            immutable_samplers: Vec::new(),
         }}),",
                 set = d.set,
                 binding = d.binding,
@@ -167,8 +172,13 @@ pub fn write_descriptor_sets(doc: &parse::Spirv) -> String {
         pc_size = push_constants_size
     );
 
-    format!(
+    Ok(format!(
         r#"
+        /// Layout of the descriptors and push constants of the shader.
+        ///
+        /// None of the samplers of this layout are immutable. Use
+        /// `PipelineLayoutDesc::with_immutable_samplers` in order to turn the samplers of a
+        /// descriptor into immutable samplers.
         #[derive(Debug, Clone)]
         pub struct Layout(ShaderStages);
 
@@ -217,7 +227,7 @@ pub fn write_descriptor_sets(doc: &parse::Spirv) -> String {
         descriptor_body = descriptor_body,
         num_push_constants_ranges_body = num_push_constants_ranges_body,
         push_constants_range_body = push_constants_range_body
-    )
+    ))
 }
 
 /// Assumes that `variable` is a variable with a `TypePointer` and returns the id of the pointed
@@ -256,8 +266,8 @@ fn pointer_variable_ty(doc: &parse::Spirv, variable: u32) -> u32 {
 ///
 /// See also section 14.5.2 of the Vulkan specs: Descriptor Set Interface
 fn descriptor_infos(doc: &parse::Spirv, pointed_ty: u32, force_combined_image_sampled: bool)
-                    -> Option<(String, bool, u64)> {
-    doc.instructions.iter().filter_map(|i| {
+                    -> Result<Option<(String, bool, u64)>, Error> {
+    let infos = doc.instructions.iter().filter_map(|i| {
         match i {
             &parse::Instruction::TypeStruct { result_id, .. } if result_id == pointed_ty => {
                 // Determine whether there's a Block or BufferBlock decoration.
@@ -289,7 +299,7 @@ fn descriptor_infos(doc: &parse::Spirv, pointed_ty: u32, force_combined_image_sa
                     content: DescriptorBufferContentDesc::F32,      // FIXME: wrong
                 }})", if is_ssbo { "true" } else { "false "});
 
-                Some((desc, true, 1))
+                Some(Ok((desc, true, 1)))
             },
 
             &parse::Instruction::TypeImage { result_id, ref dim, arrayed, ms, sampled,
@@ -319,7 +329,7 @@ fn descriptor_infos(doc: &parse::Spirv, pointed_ty: u32, force_combined_image_sa
                                             array_layers: {}
                                         }}", ms, arrayed);
 
-                    Some((desc, true, 1))
+                    Some(Ok((desc, true, 1)))
 
                 } else if let &enums::Dim::DimBuffer = dim {
                     // We are a texel buffer.
@@ -328,7 +338,7 @@ fn descriptor_infos(doc: &parse::Spirv, pointed_ty: u32, force_combined_image_sa
                         format: None,       // TODO: specify format if known
                     }}", !sampled);
 
-                    Some((desc, true, 1))
+                    Some(Ok((desc, true, 1)))
 
                 } else {
                     // We are a sampled or storage image.
@@ -352,35 +362,62 @@ fn descriptor_infos(doc: &parse::Spirv, pointed_ty: u32, force_combined_image_sa
                         array_layers: {},
                     }})", ty, sampled, dim, ms, arrayed);
 
-                    Some((desc, true, 1))
+                    Some(Ok((desc, true, 1)))
                 }
             },
 
             &parse::Instruction::TypeSampledImage { result_id, image_type_id }
                                                                 if result_id == pointed_ty =>
             {
-                descriptor_infos(doc, image_type_id, true)
+                match descriptor_infos(doc, image_type_id, true) {
+                    Ok(infos) => infos.map(Ok),
+                    Err(err) => Some(Err(err)),
+                }
             },
 
             &parse::Instruction::TypeSampler { result_id } if result_id == pointed_ty => {
                 let desc = format!("DescriptorDescTy::Sampler");
-                Some((desc, true, 1))
+                Some(Ok((desc, true, 1)))
             },
 
             &parse::Instruction::TypeArray { result_id, type_id, length_id } if result_id == pointed_ty => {
                 let (desc, readonly, arr) = match descriptor_infos(doc, type_id, false) {
-                    None => return None,
-                    Some(v) => v,
+                    Ok(None) => return None,
+                    Ok(Some(v)) => v,
+                    Err(err) => return Some(Err(err)),
                 };
-                assert_eq!(arr, 1);     // TODO: implement?
+                // A set layout binding can only describe a one-dimensional array of descriptors.
+                if arr != 1 {
+                    return Some(Err(Error::NestedDescriptorArray));
+                }
                 let len = doc.instructions.iter().filter_map(|e| {
                     match e { &parse::Instruction::Constant { result_id, ref data, .. } if result_id == length_id => Some(data.clone()), _ => None }
                 }).next().expect("failed to find array length");
                 let len = len.iter().rev().fold(0u64, |a, &b| (a << 32) | b as u64);
-                Some((desc, readonly, len))
+                Some(Ok((desc, readonly, len)))
+            },
+
+            // The actual number of elements of a runtime-sized array is determined by the layout
+            // of the set, for example with a `BindlessTable`.
+            &parse::Instruction::TypeRuntimeArray { result_id, type_id } if result_id == pointed_ty => {
+                let (desc, readonly, arr) = match descriptor_infos(doc, type_id, false) {
+                    Ok(None) => return None,
+                    Ok(Some(v)) => v,
+                    Err(err) => return Some(Err(err)),
+                };
+                if arr != 1 {
+                    return Some(Err(Error::NestedDescriptorArray));
+                }
+                Some(Ok((desc, readonly, 1)))
             },
 
             _ => None,      // TODO: other types
         }
-    }).next()
+    }).next();
+
+    match infos {
+        Some(Ok(infos)) => Ok(Some(infos)),
+        Some(Err(err)) => Err(err),
+        None => Ok(None),
+    }
 }
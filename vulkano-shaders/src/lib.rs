@@ -205,7 +205,7 @@ impl {name} {{
         output.push_str("}");
 
         // descriptor sets
-        output.push_str(&descriptor_sets::write_descriptor_sets(&doc));
+        output.push_str(&descriptor_sets::write_descriptor_sets(&doc)?);
     }
 
     Ok(output)
@@ -215,6 +215,9 @@ impl {name} {{
 pub enum Error {
     IoError(IoError),
     ParseError(ParseError),
+    /// A descriptor is an array of arrays, which a binding of a descriptor set layout can't
+    /// describe.
+    NestedDescriptorArray,
 }
 
 impl From<IoError> for Error {
@@ -7,17 +7,560 @@
 // notice may not be copied, modified, or distributed except
 // according to those terms.
 
+//! Host memory allocation callbacks.
+//!
+//! The Vulkan implementation regularly needs to allocate memory on the host (ie. in RAM) for its
+//! own bookkeeping. By default it uses its own allocator, but you can instead provide an
+//! implementation of the `Alloc` trait when you create an `Instance` with `Instance::with_alloc`.
+//! This allocator is then used for the instance, for all the devices created from it, and for
+//! all the objects that belong to these devices.
+//!
+//! This module also provides the `TrackingAlloc` allocator, which allocates memory from the Rust
+//! allocator and keeps track of the number of bytes that are alive for each allocation scope.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use std::sync::Arc;
+//! use vulkano::alloc::SystemAllocationScope;
+//! use vulkano::alloc::TrackingAlloc;
+//! use vulkano::instance::Instance;
+//! use vulkano::instance::InstanceExtensions;
+//!
+//! let tracking = Arc::new(TrackingAlloc::new());
+//! let instance = Instance::with_alloc(None, &InstanceExtensions::none(), None,
+//!                                     Box::new(tracking.clone())).unwrap();
+//!
+//! println!("Host memory used by the instance: {} bytes",
+//!          tracking.live_bytes(SystemAllocationScope::Instance));
+//! ```
+
+use std::alloc::Layout;
+use std::alloc as rust_alloc;
+use std::cmp;
 use std::mem;
 use std::os::raw::c_void;
+use std::panic;
+use std::ptr;
+use std::sync::Arc;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+
+use vk;
+
+/// Allocator that the Vulkan implementation uses to allocate host memory.
+///
+/// # Safety
+///
+/// - `alloc` and `realloc` must return memory that is aligned to `alignment` and that is valid
+///   for at least `size` bytes, or an error.
+/// - `realloc` and `free` must accept any pointer previously returned by `alloc` or `realloc`
+///   and that hasn't been freed yet.
+/// - The functions can be called from multiple threads at once.
+///
+/// The allocator must be unwind-safe, since it is owned by the `Instance` and shared with all the
+/// objects created from it.
+pub unsafe trait Alloc: panic::UnwindSafe + panic::RefUnwindSafe {
+    /// Allocates `size` bytes of memory aligned to `alignment`.
+    fn alloc(&self, size: usize, alignment: usize, scope: SystemAllocationScope)
+             -> Result<*mut c_void, ()>;
+
+    /// Reallocates memory previously returned by `alloc` or `realloc`.
+    ///
+    /// The content of the memory must be preserved up to the minimum of the old and new sizes.
+    /// If `original` is null, this must behave like `alloc`. If `size` is 0, this must behave
+    /// like `free` and return null.
+    fn realloc(&self, original: *mut c_void, size: usize, alignment: usize,
+               scope: SystemAllocationScope)
+               -> Result<*mut c_void, ()>;
+
+    /// Frees memory previously returned by `alloc` or `realloc`. Can be passed null, in which
+    /// case this function must do nothing.
+    fn free(&self, ptr: *mut c_void);
+
+    /// Called when the implementation allocated memory by itself, without going through `alloc`.
+    #[inline]
+    fn internal_allocation_notification(&self, size: usize, ty: InternalAllocationType,
+                                        scope: SystemAllocationScope) {
+    }
+
+    /// Called when the implementation freed memory that it allocated by itself.
+    #[inline]
+    fn internal_free_notification(&self, size: usize, ty: InternalAllocationType,
+                                  scope: SystemAllocationScope) {
+    }
+}
+
+unsafe impl<T: ?Sized> Alloc for Arc<T>
+    where T: Alloc
+{
+    #[inline]
+    fn alloc(&self, size: usize, alignment: usize, scope: SystemAllocationScope)
+             -> Result<*mut c_void, ()> {
+        (**self).alloc(size, alignment, scope)
+    }
+
+    #[inline]
+    fn realloc(&self, original: *mut c_void, size: usize, alignment: usize,
+               scope: SystemAllocationScope)
+               -> Result<*mut c_void, ()> {
+        (**self).realloc(original, size, alignment, scope)
+    }
+
+    #[inline]
+    fn free(&self, ptr: *mut c_void) {
+        (**self).free(ptr)
+    }
+
+    #[inline]
+    fn internal_allocation_notification(&self, size: usize, ty: InternalAllocationType,
+                                        scope: SystemAllocationScope) {
+        (**self).internal_allocation_notification(size, ty, scope)
+    }
+
+    #[inline]
+    fn internal_free_notification(&self, size: usize, ty: InternalAllocationType,
+                                  scope: SystemAllocationScope) {
+        (**self).internal_free_notification(size, ty, scope)
+    }
+}
+
+/// Lifetime of a host memory allocation requested by the implementation.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[repr(u32)]
+pub enum SystemAllocationScope {
+    /// The allocation is only alive for the duration of a Vulkan command.
+    Command = vk::SYSTEM_ALLOCATION_SCOPE_COMMAND,
+    /// The allocation is alive for as long as the object being created or used.
+    Object = vk::SYSTEM_ALLOCATION_SCOPE_OBJECT,
+    /// The allocation is associated with a pipeline cache.
+    Cache = vk::SYSTEM_ALLOCATION_SCOPE_CACHE,
+    /// The allocation is alive for as long as the device.
+    Device = vk::SYSTEM_ALLOCATION_SCOPE_DEVICE,
+    /// The allocation is alive for as long as the instance.
+    Instance = vk::SYSTEM_ALLOCATION_SCOPE_INSTANCE,
+}
+
+impl SystemAllocationScope {
+    /// Returns the list of all the possible scopes.
+    #[inline]
+    pub fn all() -> [SystemAllocationScope; 5] {
+        [
+            SystemAllocationScope::Command,
+            SystemAllocationScope::Object,
+            SystemAllocationScope::Cache,
+            SystemAllocationScope::Device,
+            SystemAllocationScope::Instance,
+        ]
+    }
+
+    #[inline]
+    fn from_vulkan(scope: vk::SystemAllocationScope) -> SystemAllocationScope {
+        match scope {
+            vk::SYSTEM_ALLOCATION_SCOPE_COMMAND => SystemAllocationScope::Command,
+            vk::SYSTEM_ALLOCATION_SCOPE_OBJECT => SystemAllocationScope::Object,
+            vk::SYSTEM_ALLOCATION_SCOPE_CACHE => SystemAllocationScope::Cache,
+            vk::SYSTEM_ALLOCATION_SCOPE_DEVICE => SystemAllocationScope::Device,
+            vk::SYSTEM_ALLOCATION_SCOPE_INSTANCE => SystemAllocationScope::Instance,
+            // Unknown scopes are accounted to the longest-lived scope.
+            _ => SystemAllocationScope::Instance,
+        }
+    }
+}
+
+/// Purpose of an allocation that the implementation performed by itself.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[repr(u32)]
+pub enum InternalAllocationType {
+    /// The allocation is intended for execution by the host.
+    Executable = vk::INTERNAL_ALLOCATION_TYPE_EXECUTABLE,
+}
+
+impl InternalAllocationType {
+    #[inline]
+    fn from_vulkan(ty: vk::InternalAllocationType) -> Option<InternalAllocationType> {
+        match ty {
+            vk::INTERNAL_ALLOCATION_TYPE_EXECUTABLE => Some(InternalAllocationType::Executable),
+            _ => None,
+        }
+    }
+}
+
+/// Holds the `VkAllocationCallbacks` struct to pass to the Vulkan functions.
+///
+/// Contains `None` if the implementation must use its own allocator.
+pub(crate) struct AllocationCallbacks(Option<vk::AllocationCallbacks>);
+
+impl AllocationCallbacks {
+    /// Builds the callbacks that redirect to `alloc`.
+    ///
+    /// The `Box` must outlive every object created or destroyed with these callbacks. Note that
+    /// we need the double indirection, because a `*const Alloc` is a fat pointer that can't be
+    /// casted to a `*const c_void`.
+    #[inline]
+    pub(crate) fn new(alloc: Option<&Box<Alloc + Send + Sync>>) -> AllocationCallbacks {
+        let alloc = match alloc {
+            Some(a) => a,
+            None => return AllocationCallbacks(None),
+        };
+
+        AllocationCallbacks(Some(vk::AllocationCallbacks {
+                                     pUserData: alloc as *const Box<_> as *const c_void as *mut _,
+                                     pfnAllocation: allocation_callback,
+                                     pfnReallocation: reallocation_callback,
+                                     pfnFree: free_callback,
+                                     pfnInternalAllocation: internal_allocation_callback,
+                                     pfnInternalFree: internal_free_callback,
+                                 }))
+    }
+
+    /// Returns the pointer to pass as the `pAllocator` parameter of Vulkan functions.
+    #[inline]
+    pub(crate) fn as_ptr(&self) -> *const vk::AllocationCallbacks {
+        match self.0 {
+            Some(ref callbacks) => callbacks as *const _,
+            None => ptr::null(),
+        }
+    }
+}
+
+#[inline]
+unsafe fn user_alloc<'a>(user_data: *mut c_void) -> &'a Box<Alloc + Send + Sync> {
+    &*(user_data as *const Box<Alloc + Send + Sync>)
+}
+
+// Panics must not unwind through the Vulkan implementation. Panics in the allocation functions are
+// turned into allocation failures, while panics in the notifications are ignored.
+
+extern "system" fn allocation_callback(user_data: *mut c_void, size: usize, alignment: usize,
+                                       scope: vk::SystemAllocationScope)
+                                       -> *mut c_void {
+    let alloc = unsafe { user_alloc(user_data) };
+    let scope = SystemAllocationScope::from_vulkan(scope);
+
+    match panic::catch_unwind(panic::AssertUnwindSafe(|| alloc.alloc(size, alignment, scope))) {
+        Ok(Ok(ptr)) => ptr,
+        _ => ptr::null_mut(),
+    }
+}
+
+extern "system" fn reallocation_callback(user_data: *mut c_void, original: *mut c_void,
+                                         size: usize, alignment: usize,
+                                         scope: vk::SystemAllocationScope)
+                                         -> *mut c_void {
+    let alloc = unsafe { user_alloc(user_data) };
+    let scope = SystemAllocationScope::from_vulkan(scope);
+
+    match panic::catch_unwind(panic::AssertUnwindSafe(|| {
+                                  alloc.realloc(original, size, alignment, scope)
+                              })) {
+        Ok(Ok(ptr)) => ptr,
+        _ => ptr::null_mut(),
+    }
+}
+
+extern "system" fn free_callback(user_data: *mut c_void, ptr: *mut c_void) {
+    let alloc = unsafe { user_alloc(user_data) };
+    let _ = panic::catch_unwind(panic::AssertUnwindSafe(|| alloc.free(ptr)));
+}
+
+extern "system" fn internal_allocation_callback(user_data: *mut c_void, size: usize,
+                                                ty: vk::InternalAllocationType,
+                                                scope: vk::SystemAllocationScope)
+                                                -> *mut c_void {
+    let alloc = unsafe { user_alloc(user_data) };
+    let scope = SystemAllocationScope::from_vulkan(scope);
+    // Types that are unknown to vulkano can't be reported to the allocator.
+    if let Some(ty) = InternalAllocationType::from_vulkan(ty) {
+        let _ = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+            alloc.internal_allocation_notification(size, ty, scope)
+        }));
+    }
+    ptr::null_mut()
+}
+
+extern "system" fn internal_free_callback(user_data: *mut c_void, size: usize,
+                                          ty: vk::InternalAllocationType,
+                                          scope: vk::SystemAllocationScope)
+                                          -> *mut c_void {
+    let alloc = unsafe { user_alloc(user_data) };
+    let scope = SystemAllocationScope::from_vulkan(scope);
+    // Types that are unknown to vulkano can't be reported to the allocator.
+    if let Some(ty) = InternalAllocationType::from_vulkan(ty) {
+        let _ = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+            alloc.internal_free_notification(size, ty, scope)
+        }));
+    }
+    ptr::null_mut()
+}
+
+/// Allocator that uses the Rust allocator and keeps track of the number of bytes that are alive
+/// for each allocation scope.
+///
+/// Allocations that the implementation performs by itself and reports through the notification
+/// functions are tracked separately.
+///
+/// Since an `Instance` takes ownership of its allocator, you are encouraged to wrap the
+/// `TrackingAlloc` in an `Arc` and pass a clone of it to `Instance::with_alloc`.
+#[derive(Debug)]
+pub struct TrackingAlloc {
+    live_bytes: [AtomicUsize; 5],
+    internal_live_bytes: [AtomicUsize; 5],
+    live_allocations: AtomicUsize,
+}
+
+// Informations stored right before each allocation returned by `TrackingAlloc`.
+#[derive(Debug, Copy, Clone)]
+struct AllocHeader {
+    // Size requested by the implementation.
+    size: usize,
+    // Offset between the start of the Rust allocation and the pointer returned by `alloc`.
+    offset: usize,
+    // Layout of the Rust allocation.
+    align: usize,
+    scope: SystemAllocationScope,
+}
+
+impl TrackingAlloc {
+    /// Builds a new `TrackingAlloc` with all counters set to zero.
+    #[inline]
+    pub fn new() -> TrackingAlloc {
+        TrackingAlloc {
+            live_bytes: [
+                AtomicUsize::new(0),
+                AtomicUsize::new(0),
+                AtomicUsize::new(0),
+                AtomicUsize::new(0),
+                AtomicUsize::new(0),
+            ],
+            internal_live_bytes: [
+                AtomicUsize::new(0),
+                AtomicUsize::new(0),
+                AtomicUsize::new(0),
+                AtomicUsize::new(0),
+                AtomicUsize::new(0),
+            ],
+            live_allocations: AtomicUsize::new(0),
+        }
+    }
+
+    /// Returns the number of bytes that were allocated through this allocator with the given
+    /// scope and that haven't been freed yet.
+    #[inline]
+    pub fn live_bytes(&self, scope: SystemAllocationScope) -> usize {
+        self.live_bytes[scope as usize].load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of bytes that the implementation allocated by itself with the given
+    /// scope and that haven't been freed yet.
+    #[inline]
+    pub fn internal_live_bytes(&self, scope: SystemAllocationScope) -> usize {
+        self.internal_live_bytes[scope as usize].load(Ordering::Relaxed)
+    }
+
+    /// Returns the total number of bytes of host memory that are alive, including internal
+    /// allocations.
+    #[inline]
+    pub fn total_live_bytes(&self) -> usize {
+        SystemAllocationScope::all()
+            .iter()
+            .map(|&scope| self.live_bytes(scope) + self.internal_live_bytes(scope))
+            .sum()
+    }
+
+    /// Returns the number of allocations performed through this allocator that haven't been
+    /// freed yet.
+    #[inline]
+    pub fn live_allocations(&self) -> usize {
+        self.live_allocations.load(Ordering::Relaxed)
+    }
+
+    // Returns the header stored before a pointer returned by `alloc`.
+    #[inline]
+    unsafe fn header(ptr: *mut c_void) -> *mut AllocHeader {
+        (ptr as *mut u8).offset(-(mem::size_of::<AllocHeader>() as isize)) as *mut AllocHeader
+    }
+}
+
+impl Default for TrackingAlloc {
+    #[inline]
+    fn default() -> TrackingAlloc {
+        TrackingAlloc::new()
+    }
+}
+
+unsafe impl Alloc for TrackingAlloc {
+    fn alloc(&self, size: usize, alignment: usize, scope: SystemAllocationScope)
+             -> Result<*mut c_void, ()> {
+        if !alignment.is_power_of_two() {
+            return Err(());
+        }
+
+        // The header is put right before the returned pointer. Since `offset` is a multiple of
+        // `alignment` and of the header's size, the header is always properly aligned.
+        let align = cmp::max(alignment, mem::align_of::<AllocHeader>());
+        let offset = (mem::size_of::<AllocHeader>() + align - 1) & !(align - 1);
+        let layout = match offset.checked_add(size) {
+            Some(total) => Layout::from_size_align(total, align).map_err(|_| ())?,
+            None => return Err(()),
+        };
+
+        unsafe {
+            let base = rust_alloc::alloc(layout);
+            if base.is_null() {
+                return Err(());
+            }
+
+            let ptr = base.offset(offset as isize) as *mut c_void;
+            ptr::write(TrackingAlloc::header(ptr),
+                       AllocHeader {
+                           size: size,
+                           offset: offset,
+                           align: align,
+                           scope: scope,
+                       });
+
+            self.live_bytes[scope as usize].fetch_add(size, Ordering::Relaxed);
+            self.live_allocations.fetch_add(1, Ordering::Relaxed);
+            Ok(ptr)
+        }
+    }
+
+    fn realloc(&self, original: *mut c_void, size: usize, alignment: usize,
+               scope: SystemAllocationScope)
+               -> Result<*mut c_void, ()> {
+        if original.is_null() {
+            return self.alloc(size, alignment, scope);
+        }
+
+        if size == 0 {
+            self.free(original);
+            return Ok(ptr::null_mut());
+        }
+
+        unsafe {
+            let old_size = (*TrackingAlloc::header(original)).size;
+            let new_ptr = self.alloc(size, alignment, scope)?;
+            ptr::copy_nonoverlapping(original as *const u8,
+                                     new_ptr as *mut u8,
+                                     cmp::min(old_size, size));
+            self.free(original);
+            Ok(new_ptr)
+        }
+    }
+
+    fn free(&self, ptr: *mut c_void) {
+        if ptr.is_null() {
+            return;
+        }
+
+        unsafe {
+            let header = ptr::read(TrackingAlloc::header(ptr));
+            self.live_bytes[header.scope as usize].fetch_sub(header.size, Ordering::Relaxed);
+            self.live_allocations.fetch_sub(1, Ordering::Relaxed);
+
+            let base = (ptr as *mut u8).offset(-(header.offset as isize));
+            let layout = Layout::from_size_align_unchecked(header.offset + header.size,
+                                                           header.align);
+            rust_alloc::dealloc(base, layout);
+        }
+    }
+
+    #[inline]
+    fn internal_allocation_notification(&self, size: usize, _: InternalAllocationType,
+                                        scope: SystemAllocationScope) {
+        self.internal_live_bytes[scope as usize].fetch_add(size, Ordering::Relaxed);
+    }
+
+    #[inline]
+    fn internal_free_notification(&self, size: usize, _: InternalAllocationType,
+                                  scope: SystemAllocationScope) {
+        self.internal_live_bytes[scope as usize].fetch_sub(size, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::Alloc;
+    use alloc::InternalAllocationType;
+    use alloc::SystemAllocationScope;
+    use alloc::TrackingAlloc;
+    use instance::Instance;
+    use instance::InstanceExtensions;
+    use std::ptr;
+    use std::sync::Arc;
+
+    #[test]
+    fn tracking_alloc_free() {
+        let alloc = TrackingAlloc::new();
+
+        let a = alloc.alloc(100, 16, SystemAllocationScope::Object).unwrap();
+        let b = alloc.alloc(12, 256, SystemAllocationScope::Device).unwrap();
+        assert_eq!(a as usize % 16, 0);
+        assert_eq!(b as usize % 256, 0);
+        assert_eq!(alloc.live_bytes(SystemAllocationScope::Object), 100);
+        assert_eq!(alloc.live_bytes(SystemAllocationScope::Device), 12);
+        assert_eq!(alloc.live_allocations(), 2);
+
+        alloc.free(a);
+        assert_eq!(alloc.live_bytes(SystemAllocationScope::Object), 0);
+        alloc.free(b);
+        alloc.free(ptr::null_mut());
+        assert_eq!(alloc.total_live_bytes(), 0);
+        assert_eq!(alloc.live_allocations(), 0);
+    }
+
+    #[test]
+    fn tracking_alloc_realloc() {
+        let alloc = TrackingAlloc::new();
+
+        unsafe {
+            let a = alloc.alloc(4, 4, SystemAllocationScope::Command).unwrap();
+            *(a as *mut u32) = 0xdeadbeef;
+
+            let b = alloc
+                .realloc(a, 64, 8, SystemAllocationScope::Command)
+                .unwrap();
+            assert_eq!(*(b as *mut u32), 0xdeadbeef);
+            assert_eq!(alloc.live_bytes(SystemAllocationScope::Command), 64);
 
-pub unsafe trait Alloc {
-    fn alloc(&self, size: usize, alignment: usize) -> Result<*mut c_void, ()>;
+            let c = alloc
+                .realloc(b, 0, 8, SystemAllocationScope::Command)
+                .unwrap();
+            assert!(c.is_null());
+            assert_eq!(alloc.live_allocations(), 0);
+        }
+    }
 
-    fn realloc(&self, original: *mut c_void, size: usize, alignment: usize) -> Result<*mut c_void, ()>;
+    #[test]
+    fn tracking_alloc_internal() {
+        let alloc = TrackingAlloc::new();
+        alloc.internal_allocation_notification(32,
+                                               InternalAllocationType::Executable,
+                                               SystemAllocationScope::Instance);
+        assert_eq!(alloc.internal_live_bytes(SystemAllocationScope::Instance), 32);
+        assert_eq!(alloc.live_bytes(SystemAllocationScope::Instance), 0);
+        assert_eq!(alloc.total_live_bytes(), 32);
+        alloc.internal_free_notification(32,
+                                         InternalAllocationType::Executable,
+                                         SystemAllocationScope::Instance);
+        assert_eq!(alloc.total_live_bytes(), 0);
+    }
 
-    fn free(&self, *mut c_void);
+    #[test]
+    fn instance_with_alloc() {
+        let tracking = Arc::new(TrackingAlloc::new());
 
-    fn internal_free_notification(&self, size: usize);
+        {
+            let _instance = match Instance::with_alloc(None,
+                                                       &InstanceExtensions::none(),
+                                                       None,
+                                                       Box::new(tracking.clone())) {
+                Ok(i) => i,
+                Err(_) => return,
+            };
+        }
 
-    fn internal_allocation_notification(&self, size: usize);
+        assert_eq!(tracking.live_allocations(), 0);
+    }
 }
@@ -12,11 +12,18 @@
 //! The `CpuAccessibleBuffer` is a basic general-purpose buffer. It can be used in any situation
 //! but may not perform as well as other buffer types.
 //!
-//! Each access from the CPU or from the GPU locks the whole buffer for either reading or writing.
-//! You can read the buffer multiple times simultaneously. Trying to read and write simultaneously,
-//! or write and write simultaneously will block.
+//! Each access from the CPU or from the GPU locks a range of the buffer for either reading or
+//! writing. `read()` and `write()` lock the whole buffer, while `read_range()`, `write_range()` and
+//! `BufferSlice`s only lock the part they cover. You can read the same range multiple times
+//! simultaneously. Trying to read and write simultaneously, or write and write simultaneously an
+//! overlapping range will fail.
+//!
+//! When a submission that locks the buffer is flushed by a `FenceSignalFuture`, the buffer
+//! remembers the fence. The `read_blocking` and `write_blocking` methods can then wait for that
+//! fence instead of failing.
 
 use smallvec::SmallVec;
+use std::cmp;
 use std::error;
 use std::fmt;
 use std::marker::PhantomData;
@@ -24,12 +31,12 @@ use std::mem;
 use std::ops::Deref;
 use std::ops::DerefMut;
 use std::ptr;
-use std::sync::atomic::AtomicUsize;
-use std::sync::atomic::Ordering;
+use std::ops::Range;
 use std::sync::Arc;
-use std::sync::RwLock;
-use std::sync::RwLockReadGuard;
-use std::sync::RwLockWriteGuard;
+use std::sync::Condvar;
+use std::sync::Mutex;
+use std::time::Duration;
+use std::time::Instant;
 
 use buffer::BufferUsage;
 use buffer::sys::BufferCreationError;
@@ -49,6 +56,8 @@ use memory::pool::MemoryPool;
 use memory::pool::MemoryPoolAlloc;
 use memory::pool::StdMemoryPoolAlloc;
 use sync::AccessError;
+use sync::Fence;
+use sync::FenceWaitError;
 use sync::Sharing;
 
 use OomError;
@@ -63,10 +72,13 @@ pub struct CpuAccessibleBuffer<T: ?Sized, A = StdMemoryPoolAlloc> {
     memory: A,
 
     // Access pattern of the buffer.
-    // Every time the user tries to read or write the buffer from the CPU, this `RwLock` is kept
-    // locked and its content is checked to verify that we are allowed access. Every time the user
-    // tries to submit this buffer for the GPU, this `RwLock` is briefly locked and modified.
-    access: RwLock<CurrentGpuAccess>,
+    // Every range that is locked by the CPU or by the GPU is recorded here. Every time the user
+    // tries to lock a range, the ranges that overlap it are checked to verify that we are allowed
+    // access.
+    access: Mutex<Vec<RangeLock>>,
+
+    // Notified every time a CPU lock is released.
+    cpu_unlocked: Condvar,
 
     // Queue families allowed to access this buffer.
     queue_families: SmallVec<[u32; 4]>,
@@ -75,16 +87,65 @@ pub struct CpuAccessibleBuffer<T: ?Sized, A = StdMemoryPoolAlloc> {
     marker: PhantomData<Box<T>>,
 }
 
+// A locked range of the buffer.
 #[derive(Debug)]
-enum CurrentGpuAccess {
-    NonExclusive {
-        // Number of non-exclusive GPU accesses. Can be 0.
-        num: AtomicUsize,
-    },
-    Exclusive {
-        // Number of exclusive locks. Cannot be 0. If 0 is reached, we must jump to `NonExclusive`.
-        num: usize,
-    },
+struct RangeLock {
+    // Range of bytes within the buffer.
+    range: Range<usize>,
+
+    kind: LockKind,
+
+    // Number of times this range is locked with this kind. Cannot be 0. If 0 is reached, the
+    // lock must be removed.
+    num: usize,
+
+    // For GPU locks, fences that will be signaled once the submissions that hold the lock are
+    // finished.
+    fences: SmallVec<[Arc<Fence>; 2]>,
+
+    // For GPU locks, number of locks that haven't been given a fence with `attach_fence` yet.
+    // The CPU can't wait for these submissions.
+    unfenced: usize,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum LockKind {
+    CpuRead,
+    CpuWrite,
+    GpuRead,
+    GpuWrite,
+}
+
+impl LockKind {
+    #[inline]
+    fn is_write(&self) -> bool {
+        match *self {
+            LockKind::CpuWrite | LockKind::GpuWrite => true,
+            LockKind::CpuRead | LockKind::GpuRead => false,
+        }
+    }
+
+    #[inline]
+    fn is_gpu(&self) -> bool {
+        match *self {
+            LockKind::GpuRead | LockKind::GpuWrite => true,
+            LockKind::CpuRead | LockKind::CpuWrite => false,
+        }
+    }
+}
+
+impl RangeLock {
+    // Returns true if the range of this lock overlaps `range`.
+    #[inline]
+    fn overlaps(&self, range: &Range<usize>) -> bool {
+        self.range.start < range.end && range.start < self.range.end
+    }
+
+    // Returns true if this lock prevents locking `range` with `kind`.
+    #[inline]
+    fn conflicts(&self, range: &Range<usize>, kind: LockKind) -> bool {
+        self.overlaps(range) && (self.kind.is_write() || kind.is_write())
+    }
 }
 
 impl<T> CpuAccessibleBuffer<T> {
@@ -235,9 +296,8 @@ impl<T: ?Sized> CpuAccessibleBuffer<T> {
         Ok(Arc::new(CpuAccessibleBuffer {
                         inner: buffer,
                         memory: mem,
-                        access: RwLock::new(CurrentGpuAccess::NonExclusive {
-                            num: AtomicUsize::new(0)
-                        }),
+                        access: Mutex::new(Vec::new()),
+                        cpu_unlocked: Condvar::new(),
                         queue_families: queue_families,
                         marker: PhantomData,
                     }))
@@ -268,67 +328,353 @@ impl<T: ?Sized, A> CpuAccessibleBuffer<T, A>
     /// Locks the buffer in order to read its content from the CPU.
     ///
     /// If the buffer is currently used in exclusive mode by the GPU, this function will return
-    /// an error. Similarly if you called `write()` on the buffer and haven't dropped the lock,
-    /// this function will return an error as well.
+    /// an error, unless the submission that uses it is known to be finished. Similarly if you
+    /// called `write()` on the buffer and haven't dropped the lock, this function will return an
+    /// error as well.
     ///
     /// After this function successfully locks the buffer, any attempt to submit a command buffer
     /// that uses it in exclusive mode will fail. You can still submit this buffer for non-exlusive
     /// accesses (ie. reads).
     #[inline]
     pub fn read(&self) -> Result<ReadLock<T>, ReadLockError> {
-        let lock = match self.access.try_read() {
-            Ok(l) => l,
-            // TODO: if a user simultaneously calls .write(), and write() is currently finding out
-            //       that the buffer is in fact GPU locked, then we will return a CpuWriteLocked
-            //       error instead of a GpuWriteLocked ; is this a problem? how do we fix this?
-            Err(_) => return Err(ReadLockError::CpuWriteLocked),
-        };
-
-        if let CurrentGpuAccess::Exclusive { .. } = *lock {
-            return Err(ReadLockError::GpuWriteLocked);
-        }
-
-        let offset = self.memory.offset();
-        let range = offset .. offset + self.inner.size();
+        let range = 0 .. self.inner.size();
+        let lock = self.lock_cpu(range.clone(), LockKind::CpuRead, None)?;
+        Ok(ReadLock {
+               inner: unsafe { self.map_range(range) },
+               lock: lock,
+           })
+    }
 
+    /// Same as `read()`, but blocks the current thread until the GPU has finished writing the
+    /// buffer and until the CPU write locks, if any, are released.
+    ///
+    /// Waiting for the GPU is only possible if all the submissions that write the buffer are
+    /// followed by a `FenceSignalFuture`. If that's not the case, this function returns
+    /// `GpuWriteLocked`. If `timeout` elapses, this function returns `Timeout`. The timeout covers
+    /// both the wait for the CPU locks and the wait for the GPU. If `timeout` is `None`, the wait
+    /// is infinite.
+    ///
+    /// Calling this function while holding a write lock on the same buffer in the same thread
+    /// will deadlock.
+    #[inline]
+    pub fn read_blocking(&self, timeout: Option<Duration>) -> Result<ReadLock<T>, ReadLockError> {
+        let range = 0 .. self.inner.size();
+        let lock = self.lock_cpu(range.clone(), LockKind::CpuRead, Some(timeout))?;
         Ok(ReadLock {
-               inner: unsafe { self.memory.mapped_memory().unwrap().read_write(range) },
+               inner: unsafe { self.map_range(range) },
                lock: lock,
            })
     }
 
     /// Locks the buffer in order to write its content from the CPU.
     ///
-    /// If the buffer is currently in use by the GPU, this function will return an error. Similarly
-    /// if you called `read()` on the buffer and haven't dropped the lock, this function will
-    /// return an error as well.
+    /// If the buffer is currently in use by the GPU, this function will return an error, unless
+    /// the submissions that use it are known to be finished. Similarly if you called `read()` on
+    /// the buffer and haven't dropped the lock, this function will return an error as well.
     ///
     /// After this function successfully locks the buffer, any attempt to submit a command buffer
     /// that uses it and any attempt to call `read()` will return an error.
     #[inline]
     pub fn write(&self) -> Result<WriteLock<T>, WriteLockError> {
-        let lock = match self.access.try_write() {
-            Ok(l) => l,
-            // TODO: if a user simultaneously calls .read() or .write(), and the function is
-            //       currently finding out that the buffer is in fact GPU locked, then we will
-            //       return a CpuLocked error instead of a GpuLocked ; is this a problem?
-            //       how do we fix this?
-            Err(_) => return Err(WriteLockError::CpuLocked),
-        };
+        let range = 0 .. self.inner.size();
+        let lock = self.lock_cpu(range.clone(), LockKind::CpuWrite, None)?;
+        Ok(WriteLock {
+               inner: unsafe { self.map_range(range) },
+               lock: lock,
+           })
+    }
 
-        match *lock {
-            CurrentGpuAccess::NonExclusive { ref num } if num.load(Ordering::SeqCst) == 0 => (),
-            _ => return Err(WriteLockError::GpuLocked),
-        }
+    /// Same as `write()`, but blocks the current thread until the GPU has finished using the
+    /// buffer and until the CPU locks, if any, are released.
+    ///
+    /// Waiting for the GPU is only possible if all the submissions that use the buffer are
+    /// followed by a `FenceSignalFuture`. If that's not the case, this function returns
+    /// `GpuLocked`. If `timeout` elapses, this function returns `Timeout`. The timeout covers both
+    /// the wait for the CPU locks and the wait for the GPU. If `timeout` is `None`, the wait is
+    /// infinite.
+    ///
+    /// Calling this function while holding a lock on the same buffer in the same thread will
+    /// deadlock.
+    #[inline]
+    pub fn write_blocking(&self, timeout: Option<Duration>)
+                          -> Result<WriteLock<T>, WriteLockError> {
+        let range = 0 .. self.inner.size();
+        let lock = self.lock_cpu(range.clone(), LockKind::CpuWrite, Some(timeout))?;
+        Ok(WriteLock {
+               inner: unsafe { self.map_range(range) },
+               lock: lock,
+           })
+    }
 
+    // Gives access to a range of bytes of the buffer, whose content must be a `U`.
+    #[inline]
+    unsafe fn map_range<U: ?Sized>(&self, range: Range<usize>) -> MemCpuAccess<U>
+        where U: Content
+    {
         let offset = self.memory.offset();
-        let range = offset .. offset + self.inner.size();
+        let range = offset + range.start .. offset + range.end;
+        self.memory.mapped_memory().unwrap().read_write(range)
+    }
+}
+
+impl<T, A> CpuAccessibleBuffer<[T], A>
+    where T: Content + 'static,
+          A: MemoryPoolAlloc,
+{
+    /// Locks the elements in `range` in order to read them from the CPU.
+    ///
+    /// Same as `read()`, except that only the given range is locked. The GPU can keep writing the
+    /// rest of the buffer in the meantime.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if the range is out of the buffer.
+    ///
+    #[inline]
+    pub fn read_range(&self, range: Range<usize>) -> Result<ReadLock<[T]>, ReadLockError> {
+        let range = self.byte_range(range);
+        let lock = self.lock_cpu(range.clone(), LockKind::CpuRead, None)?;
+        Ok(ReadLock {
+               inner: unsafe { self.map_range(range) },
+               lock: lock,
+           })
+    }
 
+    /// Locks the elements in `range` in order to write them from the CPU.
+    ///
+    /// Same as `write()`, except that only the given range is locked. The GPU can keep using the
+    /// rest of the buffer in the meantime. If the memory of the buffer isn't host-coherent, only
+    /// this range is flushed when the lock is dropped.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if the range is out of the buffer.
+    ///
+    #[inline]
+    pub fn write_range(&self, range: Range<usize>) -> Result<WriteLock<[T]>, WriteLockError> {
+        let range = self.byte_range(range);
+        let lock = self.lock_cpu(range.clone(), LockKind::CpuWrite, None)?;
         Ok(WriteLock {
-               inner: unsafe { self.memory.mapped_memory().unwrap().read_write(range) },
+               inner: unsafe { self.map_range(range) },
                lock: lock,
            })
     }
+
+    // Turns a range of elements into a range of bytes.
+    #[inline]
+    fn byte_range(&self, range: Range<usize>) -> Range<usize> {
+        let elem_size = mem::size_of::<T>();
+        assert!(range.start <= range.end);
+        assert!(range.end * elem_size <= self.inner.size());
+        range.start * elem_size .. range.end * elem_size
+    }
+}
+
+impl<T: ?Sized, A> CpuAccessibleBuffer<T, A> {
+    /// Returns true if the GPU has finished all the submissions that use this buffer and if the
+    /// CPU doesn't hold a write lock on it.
+    ///
+    /// Contrary to `read()` and `write()`, this function doesn't lock anything.
+    pub fn ready(&self) -> bool {
+        let fences = {
+            let access = self.access.lock().unwrap();
+            if access.iter().any(|l| l.kind == LockKind::CpuWrite) {
+                return false;
+            }
+
+            match gpu_fences(access.iter().filter(|l| l.kind.is_gpu())) {
+                Some(f) => f,
+                None => return false,
+            }
+        };
+
+        fences.is_empty() ||
+            Fence::multi_wait(fences.iter().map(|f| &**f), Some(Duration::from_secs(0))).is_ok()
+    }
+
+    // Locks a range of the buffer for the CPU.
+    //
+    // If `blocking` is `None`, an error is returned if the range is locked by the CPU and the
+    // GPU locks are only checked. Otherwise, the function waits for the CPU locks to be released
+    // and for the GPU locks to be finished. The given timeout covers both waits.
+    fn lock_cpu(&self, range: Range<usize>, kind: LockKind, blocking: Option<Option<Duration>>)
+                -> Result<CpuLockGuard, CpuLockFailure> {
+        debug_assert!(!kind.is_gpu());
+
+        let start = Instant::now();
+        // Returns the part of the timeout that hasn't elapsed yet, or an error if it has.
+        let remaining = |timeout: Duration| -> Result<Duration, CpuLockFailure> {
+            match timeout.checked_sub(start.elapsed()) {
+                Some(r) if r != Duration::from_secs(0) => Ok(r),
+                _ => Err(CpuLockFailure::Timeout),
+            }
+        };
+
+        let fences = {
+            let mut access = self.access.lock().unwrap();
+
+            while access
+                .iter()
+                .any(|l| !l.kind.is_gpu() && l.conflicts(&range, kind))
+            {
+                access = match blocking {
+                    None => return Err(CpuLockFailure::CpuLocked),
+                    Some(None) => self.cpu_unlocked.wait(access).unwrap(),
+                    Some(Some(timeout)) => {
+                        let remaining = remaining(timeout)?;
+                        self.cpu_unlocked.wait_timeout(access, remaining).unwrap().0
+                    },
+                };
+            }
+
+            let fences = gpu_fences(access.iter().filter(|l| {
+                                                               l.kind.is_gpu() &&
+                                                                   l.conflicts(&range, kind)
+                                                           }));
+            let fences = match fences {
+                Some(f) => f,
+                None => return Err(CpuLockFailure::GpuLocked),
+            };
+
+            // From now on, the range can't be locked by new GPU submissions.
+            add_lock(&mut access, range.clone(), kind);
+            fences
+        };
+
+        let guard = CpuLockGuard {
+            access: &self.access,
+            cpu_unlocked: &self.cpu_unlocked,
+            range: range,
+            kind: kind,
+        };
+
+        if !fences.is_empty() {
+            let timeout = match blocking {
+                None => Some(Duration::from_secs(0)),
+                Some(None) => None,
+                Some(Some(timeout)) => Some(remaining(timeout)?),
+            };
+
+            // Dropping the guard releases the lock.
+            match Fence::multi_wait(fences.iter().map(|f| &**f), timeout) {
+                Ok(()) => (),
+                Err(FenceWaitError::Timeout) if blocking.is_some() => {
+                    return Err(CpuLockFailure::Timeout);
+                },
+                Err(_) => return Err(CpuLockFailure::GpuLocked),
+            }
+        }
+
+        Ok(guard)
+    }
+}
+
+// Records a lock of `range` with `kind`.
+fn add_lock(access: &mut Vec<RangeLock>, range: Range<usize>, kind: LockKind) {
+    let pos = access
+        .iter()
+        .position(|l| l.range == range && l.kind == kind);
+
+    let lock = match pos {
+        Some(pos) => {
+            access[pos].num += 1;
+            &mut access[pos]
+        },
+        None => {
+            access.push(RangeLock {
+                            range: range,
+                            kind: kind,
+                            num: 1,
+                            fences: SmallVec::new(),
+                            unfenced: 0,
+                        });
+            access.last_mut().unwrap()
+        },
+    };
+
+    if kind.is_gpu() {
+        lock.unfenced += 1;
+    }
+}
+
+// Removes one lock of `range` with a kind for which `filter` returns true.
+fn remove_lock<F>(access: &mut Vec<RangeLock>, range: &Range<usize>, filter: F)
+    where F: Fn(LockKind) -> bool
+{
+    let pos = access
+        .iter()
+        .position(|l| l.range == *range && filter(l.kind))
+        .expect("Tried to unlock a range that isn't locked");
+
+    access[pos].num -= 1;
+    if access[pos].num == 0 {
+        access.remove(pos);
+    } else {
+        // We don't know which submission released its lock. If it was an unfenced one, the
+        // remaining locks may all be fenced.
+        access[pos].unfenced = cmp::min(access[pos].unfenced, access[pos].num);
+    }
+}
+
+// Returns the fences of all the submissions that hold the given GPU locks, or `None` if one of
+// these submissions hasn't been given a fence.
+fn gpu_fences<'a, I>(locks: I) -> Option<Vec<Arc<Fence>>>
+    where I: Iterator<Item = &'a RangeLock>
+{
+    let mut out = Vec::new();
+    for lock in locks {
+        if lock.unfenced != 0 {
+            return None;
+        }
+        out.extend(lock.fences.iter().cloned());
+    }
+    Some(out)
+}
+
+// Reason why locking a range for the CPU failed.
+enum CpuLockFailure {
+    CpuLocked,
+    GpuLocked,
+    Timeout,
+}
+
+impl From<CpuLockFailure> for ReadLockError {
+    #[inline]
+    fn from(err: CpuLockFailure) -> ReadLockError {
+        match err {
+            CpuLockFailure::CpuLocked => ReadLockError::CpuWriteLocked,
+            CpuLockFailure::GpuLocked => ReadLockError::GpuWriteLocked,
+            CpuLockFailure::Timeout => ReadLockError::Timeout,
+        }
+    }
+}
+
+impl From<CpuLockFailure> for WriteLockError {
+    #[inline]
+    fn from(err: CpuLockFailure) -> WriteLockError {
+        match err {
+            CpuLockFailure::CpuLocked => WriteLockError::CpuLocked,
+            CpuLockFailure::GpuLocked => WriteLockError::GpuLocked,
+            CpuLockFailure::Timeout => WriteLockError::Timeout,
+        }
+    }
+}
+
+// Releases a CPU lock on a range of a `CpuAccessibleBuffer` when destroyed.
+struct CpuLockGuard<'a> {
+    access: &'a Mutex<Vec<RangeLock>>,
+    cpu_unlocked: &'a Condvar,
+    range: Range<usize>,
+    kind: LockKind,
+}
+
+impl<'a> Drop for CpuLockGuard<'a> {
+    #[inline]
+    fn drop(&mut self) {
+        let kind = self.kind;
+        remove_lock(&mut self.access.lock().unwrap(), &self.range, |k| k == kind);
+        self.cpu_unlocked.notify_all();
+    }
 }
 
 unsafe impl<T: ?Sized, A> BufferAccess for CpuAccessibleBuffer<T, A>
@@ -353,96 +699,78 @@ unsafe impl<T: ?Sized, A> BufferAccess for CpuAccessibleBuffer<T, A>
     }
 
     #[inline]
-    fn try_gpu_lock(&self, exclusive_access: bool, _: &Queue) -> Result<(), AccessError> {
-        if exclusive_access {
-            let mut lock = match self.access.try_write() {
-                Ok(lock) => lock,
-                Err(_) => return Err(AccessError::AlreadyInUse),
-            };
+    fn try_gpu_lock(&self, exclusive_access: bool, queue: &Queue) -> Result<(), AccessError> {
+        self.try_gpu_lock_range(exclusive_access, queue, 0, self.inner.size())
+    }
 
-            match *lock {
-                CurrentGpuAccess::NonExclusive { ref num } if num.load(Ordering::SeqCst) == 0 => (),
-                _ => return Err(AccessError::AlreadyInUse),
-            };
+    #[inline]
+    unsafe fn increase_gpu_lock(&self) {
+        self.increase_gpu_lock_range(0, self.inner.size())
+    }
 
-            *lock = CurrentGpuAccess::Exclusive { num: 1 };
-            Ok(())
+    #[inline]
+    unsafe fn unlock(&self) {
+        self.unlock_range(0, self.inner.size())
+    }
 
+    fn try_gpu_lock_range(&self, exclusive_access: bool, _: &Queue, offset: usize,
+                          size: usize)
+                          -> Result<(), AccessError> {
+        let range = offset .. offset + size;
+        let kind = if exclusive_access {
+            LockKind::GpuWrite
         } else {
-            let lock = match self.access.try_read() {
-                Ok(lock) => lock,
-                Err(_) => return Err(AccessError::AlreadyInUse),
-            };
-
-            match *lock {
-                CurrentGpuAccess::Exclusive { .. } => return Err(AccessError::AlreadyInUse),
-                CurrentGpuAccess::NonExclusive { ref num } => {
-                    num.fetch_add(1, Ordering::SeqCst)
-                },
-            };
+            LockKind::GpuRead
+        };
 
-            Ok(())
+        let mut access = self.access.lock().unwrap();
+        if access.iter().any(|l| l.conflicts(&range, kind)) {
+            return Err(AccessError::AlreadyInUse);
         }
+
+        add_lock(&mut access, range, kind);
+        Ok(())
     }
 
-    #[inline]
-    unsafe fn increase_gpu_lock(&self) {
-        // First, handle if we have a non-exclusive access.
-        {
-            // Since the buffer is in use by the GPU, it is invalid to hold a write-lock to
-            // the buffer. The buffer can still be briefly in a write-locked state for the duration
-            // of the check though.
-            let read_lock = self.access.read().unwrap();
-            if let CurrentGpuAccess::NonExclusive { ref num } = *read_lock {
-                let prev = num.fetch_add(1, Ordering::SeqCst);
-                debug_assert!(prev >= 1);
-                return;
-            }
-        }
+    unsafe fn increase_gpu_lock_range(&self, offset: usize, size: usize) {
+        let range = offset .. offset + size;
+        let mut access = self.access.lock().unwrap();
 
-        // If we reach here, this means that `access` contains `CurrentGpuAccess::Exclusive`.
-        {
-            // Same remark as above, but for writing.
-            let mut write_lock = self.access.write().unwrap();
-            if let CurrentGpuAccess::Exclusive { ref mut num } = *write_lock {
-                *num += 1;
-            } else {
-                unreachable!()
-            }
-        }
+        // The range doesn't necessarily match the range that was previously locked. For example
+        // a slice of the buffer can be used after a submission that locked the whole buffer. The
+        // increase is recorded as a new lock of `range`, so that it can be unlocked separately.
+        let kind = access
+            .iter()
+            .filter(|l| l.kind.is_gpu() && l.overlaps(&range))
+            .map(|l| l.kind)
+            .max_by_key(|k| k.is_write())
+            .expect("Tried to increase the lock of a range that isn't locked");
+        add_lock(&mut access, range, kind);
     }
 
     #[inline]
-    unsafe fn unlock(&self) {
-        // First, handle if we had a non-exclusive access.
-        {
-            // Since the buffer is in use by the GPU, it is invalid to hold a write-lock to
-            // the buffer. The buffer can still be briefly in a write-locked state for the duration
-            // of the check though.
-            let read_lock = self.access.read().unwrap();
-            if let CurrentGpuAccess::NonExclusive { ref num } = *read_lock {
-                let prev = num.fetch_sub(1, Ordering::SeqCst);
-                debug_assert!(prev >= 1);
-                return;
-            }
-        }
+    unsafe fn unlock_range(&self, offset: usize, size: usize) {
+        let range = offset .. offset + size;
+        remove_lock(&mut self.access.lock().unwrap(), &range, |k| k.is_gpu());
+    }
 
-        // If we reach here, this means that `access` contains `CurrentGpuAccess::Exclusive`.
-        {
-            // Same remark as above, but for writing.
-            let mut write_lock = self.access.write().unwrap();
-            if let CurrentGpuAccess::Exclusive { ref mut num } = *write_lock {
-                if *num != 1 {
-                    *num -= 1;
-                    return;
-                }
-            } else {
-                // Can happen if we lock in exclusive mode N times, and unlock N+1 times with the
-                // last two unlocks happen simultaneously.
-                panic!()
-            }
+    #[inline]
+    unsafe fn attach_fence(&self, fence: &Arc<Fence>) {
+        self.attach_fence_range(0, self.inner.size(), fence)
+    }
 
-            *write_lock = CurrentGpuAccess::NonExclusive { num: AtomicUsize::new(0) };
+    unsafe fn attach_fence_range(&self, offset: usize, size: usize, fence: &Arc<Fence>) {
+        let range = offset .. offset + size;
+        let mut access = self.access.lock().unwrap();
+
+        let lock = access
+            .iter_mut()
+            .find(|l| l.range == range && l.kind.is_gpu() && l.unfenced != 0);
+        if let Some(lock) = lock {
+            lock.unfenced -= 1;
+            if !lock.fences.iter().any(|f| Arc::ptr_eq(f, fence)) {
+                lock.fences.push(fence.clone());
+            }
         }
     }
 }
@@ -462,11 +790,11 @@ unsafe impl<T: ?Sized, A> DeviceOwned for CpuAccessibleBuffer<T, A> {
 
 /// Object that can be used to read or write the content of a `CpuAccessibleBuffer`.
 ///
-/// Note that this object holds a rwlock read guard on the chunk. If another thread tries to access
-/// this buffer's content or tries to submit a GPU command that uses this buffer, it will block.
+/// Note that this object holds a read lock on a range of the buffer. Until it is destroyed, any
+/// attempt to write this range from the CPU or to submit a GPU command that writes it will fail.
 pub struct ReadLock<'a, T: ?Sized + 'a> {
     inner: MemCpuAccess<'a, T>,
-    lock: RwLockReadGuard<'a, CurrentGpuAccess>,
+    lock: CpuLockGuard<'a>,
 }
 
 impl<'a, T: ?Sized + 'a> ReadLock<'a, T> {
@@ -498,6 +826,8 @@ pub enum ReadLockError {
     CpuWriteLocked,
     /// The buffer is already locked for write mode by the GPU.
     GpuWriteLocked,
+    /// The timeout elapsed before the buffer could be locked.
+    Timeout,
 }
 
 impl error::Error for ReadLockError {
@@ -510,6 +840,9 @@ impl error::Error for ReadLockError {
             ReadLockError::GpuWriteLocked => {
                 "the buffer is already locked for write mode by the GPU"
             },
+            ReadLockError::Timeout => {
+                "the timeout elapsed before the buffer could be locked"
+            },
         }
     }
 }
@@ -523,11 +856,11 @@ impl fmt::Display for ReadLockError {
 
 /// Object that can be used to read or write the content of a `CpuAccessibleBuffer`.
 ///
-/// Note that this object holds a rwlock write guard on the chunk. If another thread tries to access
-/// this buffer's content or tries to submit a GPU command that uses this buffer, it will block.
+/// Note that this object holds a write lock on a range of the buffer. Until it is destroyed, any
+/// attempt to access this range from the CPU or to submit a GPU command that uses it will fail.
 pub struct WriteLock<'a, T: ?Sized + 'a> {
     inner: MemCpuAccess<'a, T>,
-    lock: RwLockWriteGuard<'a, CurrentGpuAccess>,
+    lock: CpuLockGuard<'a>,
 }
 
 impl<'a, T: ?Sized + 'a> WriteLock<'a, T> {
@@ -566,6 +899,8 @@ pub enum WriteLockError {
     CpuLocked,
     /// The buffer is already locked by the GPU.
     GpuLocked,
+    /// The timeout elapsed before the buffer could be locked.
+    Timeout,
 }
 
 impl error::Error for WriteLockError {
@@ -578,6 +913,9 @@ impl error::Error for WriteLockError {
             WriteLockError::GpuLocked => {
                 "the buffer is already locked by the GPU"
             },
+            WriteLockError::Timeout => {
+                "the timeout elapsed before the buffer could be locked"
+            },
         }
     }
 }
@@ -591,7 +929,13 @@ impl fmt::Display for WriteLockError {
 
 #[cfg(test)]
 mod tests {
-    use buffer::{BufferUsage, CpuAccessibleBuffer};
+    use std::time::Duration;
+
+    use buffer::{BufferAccess, BufferUsage, CpuAccessibleBuffer};
+    use buffer::cpu_access::{ReadLockError, WriteLockError};
+    use command_buffer::AutoCommandBufferBuilder;
+    use command_buffer::CommandBuffer;
+    use sync::GpuFuture;
 
     #[test]
     fn create_empty_buffer() {
@@ -604,4 +948,165 @@ mod tests {
                                                Some(queue.family()),
                                                EMPTY.iter());
     }
+
+    #[test]
+    fn read_blocking_waits_for_fence() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let buffer = CpuAccessibleBuffer::from_data(device.clone(),
+                                                    BufferUsage::all(),
+                                                    Some(queue.family()),
+                                                    0u32)
+            .unwrap();
+
+        // The buffer is locked by the GPU when the command buffer is executed, but the fence is
+        // only known once the future is flushed.
+        let future = AutoCommandBufferBuilder::new(device.clone(), queue.family())
+            .unwrap()
+            .fill_buffer(buffer.clone(), 42)
+            .unwrap()
+            .build()
+            .unwrap()
+            .execute(queue.clone())
+            .unwrap();
+        assert!(!buffer.ready());
+        assert!(buffer.read_blocking(Some(Duration::from_secs(0))).is_err());
+
+        let _future = future.then_signal_fence_and_flush().unwrap();
+        assert_eq!(*buffer.read_blocking(None).unwrap(), 42);
+        assert!(buffer.ready());
+        assert!(buffer.write().is_ok());
+    }
+
+    #[test]
+    fn blocking_without_fence() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let buffer = CpuAccessibleBuffer::from_data(device.clone(),
+                                                    BufferUsage::all(),
+                                                    Some(queue.family()),
+                                                    0u32)
+            .unwrap();
+
+        let future = AutoCommandBufferBuilder::new(device.clone(), queue.family())
+            .unwrap()
+            .fill_buffer(buffer.clone(), 42)
+            .unwrap()
+            .build()
+            .unwrap()
+            .execute(queue.clone())
+            .unwrap();
+        future.flush().unwrap();
+
+        assert!(!buffer.ready());
+        assert!(buffer.write_blocking(None).is_err());
+    }
+
+    #[test]
+    fn blocking_cpu_lock_timeout() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let buffer = CpuAccessibleBuffer::from_data(device.clone(),
+                                                    BufferUsage::all(),
+                                                    Some(queue.family()),
+                                                    0u32)
+            .unwrap();
+
+        let lock = buffer.write().unwrap();
+        let timeout = Some(Duration::from_millis(10));
+        assert_eq!(buffer.write_blocking(timeout).err(), Some(WriteLockError::Timeout));
+        assert_eq!(buffer.read_blocking(timeout).err(), Some(ReadLockError::Timeout));
+
+        drop(lock);
+        assert!(buffer.write_blocking(timeout).is_ok());
+    }
+
+    #[test]
+    fn cpu_range_locks() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let buffer = CpuAccessibleBuffer::from_iter(device.clone(),
+                                                    BufferUsage::all(),
+                                                    Some(queue.family()),
+                                                    0 .. 8u32)
+            .unwrap();
+
+        let mut first = buffer.write_range(0 .. 4).unwrap();
+        let mut second = buffer.write_range(4 .. 8).unwrap();
+        first[0] = 12;
+        second[0] = 13;
+
+        assert_eq!(buffer.read_range(3 .. 5).err(), Some(ReadLockError::CpuWriteLocked));
+        assert_eq!(buffer.write().err(), Some(WriteLockError::CpuLocked));
+
+        drop(first);
+        drop(second);
+        assert_eq!(&*buffer.read_range(0 .. 5).unwrap(), &[12, 1, 2, 3, 13]);
+    }
+
+    #[test]
+    fn gpu_range_locks() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let buffer = CpuAccessibleBuffer::from_iter(device.clone(),
+                                                    BufferUsage::all(),
+                                                    Some(queue.family()),
+                                                    0 .. 8u32)
+            .unwrap();
+
+        let future = AutoCommandBufferBuilder::new(device.clone(), queue.family())
+            .unwrap()
+            .fill_buffer(buffer.clone().into_buffer_slice().slice(0 .. 4).unwrap(), 0)
+            .unwrap()
+            .build()
+            .unwrap()
+            .execute(queue.clone())
+            .unwrap();
+        future.flush().unwrap();
+
+        assert!(buffer.write_range(4 .. 8).is_ok());
+        assert_eq!(buffer.write_range(2 .. 6).err(), Some(WriteLockError::GpuLocked));
+        assert_eq!(buffer.read().err(), Some(ReadLockError::GpuWriteLocked));
+    }
+
+    #[test]
+    fn gpu_lock_slice_after_whole_buffer() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let buffer = CpuAccessibleBuffer::from_iter(device.clone(),
+                                                    BufferUsage::all(),
+                                                    Some(queue.family()),
+                                                    0 .. 8u32)
+            .unwrap();
+
+        let whole = AutoCommandBufferBuilder::new(device.clone(), queue.family())
+            .unwrap()
+            .fill_buffer(buffer.clone(), 1)
+            .unwrap()
+            .build()
+            .unwrap();
+
+        let slice = AutoCommandBufferBuilder::new(device.clone(), queue.family())
+            .unwrap()
+            .fill_buffer(buffer.clone().into_buffer_slice().slice(2 .. 4).unwrap(), 2)
+            .unwrap()
+            .build()
+            .unwrap();
+
+        let future = whole
+            .execute(queue.clone())
+            .unwrap()
+            .then_execute_same_queue(slice)
+            .unwrap()
+            .then_signal_fence_and_flush()
+            .unwrap();
+        future.wait(None).unwrap();
+
+        let content = buffer.read_blocking(None).unwrap();
+        assert_eq!(&*content, &[1, 1, 2, 2, 1, 1, 1, 1]);
+        drop(content);
+
+        drop(future);
+        assert!(buffer.write().is_ok());
+    }
 }
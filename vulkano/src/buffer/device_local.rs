@@ -9,13 +9,15 @@
 
 //! Buffer whose content is read-written by the GPU only.
 //!
-//! Each access from the CPU or from the GPU locks the whole buffer for either reading or writing.
+//! Each access from the GPU locks the range of the buffer it uses for either reading or writing.
 //! You can read the buffer multiple times simultaneously from multiple queues. Trying to read and
-//! write simultaneously, or write and write simultaneously will block with a semaphore.
+//! write simultaneously, or write and write simultaneously an overlapping range will block with a
+//! semaphore.
 
 use smallvec::SmallVec;
 use std::marker::PhantomData;
 use std::mem;
+use std::ops::Range;
 use std::sync::Arc;
 use std::sync::Mutex;
 
@@ -58,18 +60,32 @@ pub struct DeviceLocalBuffer<T: ?Sized, A = StdMemoryPoolAlloc> {
     // Queue families allowed to access this buffer.
     queue_families: SmallVec<[u32; 4]>,
 
-    // Number of times this buffer is locked on the GPU side.
-    gpu_lock: Mutex<GpuAccess>,
+    // Ranges of this buffer that are locked on the GPU side.
+    gpu_lock: Mutex<Vec<GpuRangeLock>>,
 
     // Necessary to make it compile.
     marker: PhantomData<Box<T>>,
 }
 
-#[derive(Debug, Copy, Clone)]
-enum GpuAccess {
-    None,
-    NonExclusive { num: u32 },
-    Exclusive { num: u32 },
+// A range of the buffer locked on the GPU side.
+#[derive(Debug, Clone)]
+struct GpuRangeLock {
+    // Range of bytes within the buffer.
+    range: Range<usize>,
+
+    exclusive: bool,
+
+    // Number of times this range is locked. Cannot be 0. If 0 is reached, the lock must be
+    // removed.
+    num: u32,
+}
+
+impl GpuRangeLock {
+    // Returns true if the range of this lock overlaps `range`.
+    #[inline]
+    fn overlaps(&self, range: &Range<usize>) -> bool {
+        self.range.start < range.end && range.start < self.range.end
+    }
 }
 
 impl<T> DeviceLocalBuffer<T> {
@@ -153,7 +169,7 @@ impl<T: ?Sized> DeviceLocalBuffer<T> {
                         inner: buffer,
                         memory: mem,
                         queue_families: queue_families,
-                        gpu_lock: Mutex::new(GpuAccess::None),
+                        gpu_lock: Mutex::new(Vec::new()),
                         marker: PhantomData,
                     }))
     }
@@ -205,62 +221,81 @@ unsafe impl<T: ?Sized, A> BufferAccess for DeviceLocalBuffer<T, A>
     }
 
     #[inline]
-    fn try_gpu_lock(&self, exclusive: bool, _: &Queue) -> Result<(), AccessError> {
-        let mut lock = self.gpu_lock.lock().unwrap();
-        match &mut *lock {
-            a @ &mut GpuAccess::None => {
-                if exclusive {
-                    *a = GpuAccess::Exclusive { num: 1 };
-                } else {
-                    *a = GpuAccess::NonExclusive { num: 1 };
-                }
-
-                Ok(())
-            },
-            &mut GpuAccess::NonExclusive { ref mut num } => {
-                if exclusive {
-                    Err(AccessError::AlreadyInUse)
-                } else {
-                    *num += 1;
-                    Ok(())
-                }
-            },
-            &mut GpuAccess::Exclusive { ref mut num } => {
-                Err(AccessError::AlreadyInUse)
-            }
-        }
+    fn try_gpu_lock(&self, exclusive: bool, queue: &Queue) -> Result<(), AccessError> {
+        self.try_gpu_lock_range(exclusive, queue, 0, self.inner.size())
     }
 
     #[inline]
     unsafe fn increase_gpu_lock(&self) {
-        let mut lock = self.gpu_lock.lock().unwrap();
-        match *lock {
-            GpuAccess::None => panic!(),
-            GpuAccess::NonExclusive { ref mut num } => {
-                debug_assert!(*num >= 1);
-                *num += 1;
-            },
-            GpuAccess::Exclusive { ref mut num } => {
-                debug_assert!(*num >= 1);
-                *num += 1;
-            }
-        }
+        self.increase_gpu_lock_range(0, self.inner.size())
     }
 
     #[inline]
     unsafe fn unlock(&self) {
-        let mut lock = self.gpu_lock.lock().unwrap();
-        match *lock {
-            GpuAccess::None => panic!("Tried to unlock a buffer that isn't locked"),
-            GpuAccess::NonExclusive { ref mut num } => {
-                assert!(*num >= 1);
-                *num -= 1;
-            },
-            GpuAccess::Exclusive { ref mut num } => {
-                assert!(*num >= 1);
-                *num -= 1;
-            },
+        self.unlock_range(0, self.inner.size())
+    }
+
+    fn try_gpu_lock_range(&self, exclusive: bool, _: &Queue, offset: usize, size: usize)
+                          -> Result<(), AccessError> {
+        let range = offset .. offset + size;
+        let mut locks = self.gpu_lock.lock().unwrap();
+
+        if locks
+            .iter()
+            .any(|l| l.overlaps(&range) && (l.exclusive || exclusive))
+        {
+            return Err(AccessError::AlreadyInUse);
         }
+
+        add_gpu_lock(&mut locks, range, exclusive);
+        Ok(())
+    }
+
+    unsafe fn increase_gpu_lock_range(&self, offset: usize, size: usize) {
+        let range = offset .. offset + size;
+        let mut locks = self.gpu_lock.lock().unwrap();
+
+        // The range doesn't necessarily match a range that was previously locked, for example if
+        // a slice is used after the whole buffer. The increase is recorded as a lock of `range`.
+        let exclusive = locks
+            .iter()
+            .filter(|l| l.overlaps(&range))
+            .map(|l| l.exclusive)
+            .max()
+            .expect("Tried to increase the lock of a range that isn't locked");
+        add_gpu_lock(&mut locks, range, exclusive);
+    }
+
+    unsafe fn unlock_range(&self, offset: usize, size: usize) {
+        let range = offset .. offset + size;
+        let mut locks = self.gpu_lock.lock().unwrap();
+
+        let pos = locks
+            .iter()
+            .position(|l| l.range == range)
+            .expect("Tried to unlock a range that isn't locked");
+
+        locks[pos].num -= 1;
+        if locks[pos].num == 0 {
+            locks.remove(pos);
+        }
+    }
+}
+
+// Records a GPU lock of `range`.
+fn add_gpu_lock(locks: &mut Vec<GpuRangeLock>, range: Range<usize>, exclusive: bool) {
+    match locks
+        .iter_mut()
+        .find(|l| l.range == range && l.exclusive == exclusive)
+    {
+        Some(lock) => lock.num += 1,
+        None => {
+            locks.push(GpuRangeLock {
+                           range: range,
+                           exclusive: exclusive,
+                           num: 1,
+                       });
+        },
     }
 }
 
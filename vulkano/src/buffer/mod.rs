@@ -67,6 +67,10 @@ pub use self::cpu_pool::CpuBufferPool;
 pub use self::device_local::DeviceLocalBuffer;
 pub use self::immutable::ImmutableBuffer;
 pub use self::slice::BufferSlice;
+pub use self::sparse::SparseBuffer;
+pub use self::sub_allocator::SubBuffer;
+pub use self::sub_allocator::SubBufferAllocError;
+pub use self::sub_allocator::SubBufferAllocator;
 pub use self::sys::BufferCreationError;
 pub use self::traits::BufferAccess;
 pub use self::traits::BufferInner;
@@ -79,6 +83,8 @@ pub mod cpu_access;
 pub mod cpu_pool;
 pub mod device_local;
 pub mod immutable;
+pub mod sparse;
+pub mod sub_allocator;
 pub mod sys;
 pub mod view;
 
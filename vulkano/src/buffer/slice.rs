@@ -19,6 +19,7 @@ use device::Device;
 use device::DeviceOwned;
 use device::Queue;
 use sync::AccessError;
+use sync::Fence;
 
 /// A subpart of a buffer.
 ///
@@ -79,6 +80,22 @@ impl<T: ?Sized, B> BufferSlice<T, B> {
         }
     }
 
+    /// Builds a slice of `size` bytes starting at `offset` within `r`, whatever the content of
+    /// `r` is.
+    ///
+    /// # Safety
+    ///
+    /// The range must be within `r`, and the memory must be valid for `T`.
+    #[inline]
+    pub(crate) unsafe fn from_raw_parts(r: B, offset: usize, size: usize) -> BufferSlice<T, B> {
+        BufferSlice {
+            marker: PhantomData,
+            resource: r,
+            offset: offset,
+            size: size,
+        }
+    }
+
     /// Returns the buffer that this slice belongs to.
     pub fn buffer(&self) -> &B {
         &self.resource
@@ -214,17 +231,46 @@ unsafe impl<T: ?Sized, B> BufferAccess for BufferSlice<T, B>
 
     #[inline]
     fn try_gpu_lock(&self, exclusive_access: bool, queue: &Queue) -> Result<(), AccessError> {
-        self.resource.try_gpu_lock(exclusive_access, queue)
+        self.resource
+            .try_gpu_lock_range(exclusive_access, queue, self.offset, self.size)
     }
 
     #[inline]
     unsafe fn increase_gpu_lock(&self) {
-        self.resource.increase_gpu_lock()
+        self.resource.increase_gpu_lock_range(self.offset, self.size)
     }
 
     #[inline]
     unsafe fn unlock(&self) {
-        self.resource.unlock()
+        self.resource.unlock_range(self.offset, self.size)
+    }
+
+    #[inline]
+    fn try_gpu_lock_range(&self, exclusive_access: bool, queue: &Queue, offset: usize,
+                          size: usize)
+                          -> Result<(), AccessError> {
+        self.resource
+            .try_gpu_lock_range(exclusive_access, queue, self.offset + offset, size)
+    }
+
+    #[inline]
+    unsafe fn increase_gpu_lock_range(&self, offset: usize, size: usize) {
+        self.resource.increase_gpu_lock_range(self.offset + offset, size)
+    }
+
+    #[inline]
+    unsafe fn unlock_range(&self, offset: usize, size: usize) {
+        self.resource.unlock_range(self.offset + offset, size)
+    }
+
+    #[inline]
+    unsafe fn attach_fence(&self, fence: &Arc<Fence>) {
+        self.resource.attach_fence_range(self.offset, self.size, fence)
+    }
+
+    #[inline]
+    unsafe fn attach_fence_range(&self, offset: usize, size: usize, fence: &Arc<Fence>) {
+        self.resource.attach_fence_range(self.offset + offset, size, fence)
     }
 }
 
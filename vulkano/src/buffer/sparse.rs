@@ -0,0 +1,483 @@
+// Copyright (c) 2017 The vulkano developers
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>,
+// at your option. All files in the project carrying such
+// notice may not be copied, modified, or distributed except
+// according to those terms.
+
+//! Buffer whose memory is bound page by page.
+//!
+//! A `SparseBuffer` is created without any memory. You then call `bind` in order to make some of
+//! its pages resident, and `unbind` in order to release the memory of pages that you no longer
+//! need. Both operations return a future that must be waited upon before the pages are accessed
+//! by the GPU. Accessing a page that isn't resident doesn't crash, but reads return undefined
+//! values and writes are discarded.
+//!
+//! Creating a sparse buffer requires the `sparse_binding` and `sparse_residency_buffer` features
+//! to be enabled on the device, and binding requires a queue whose family supports sparse binding.
+
+use smallvec::SmallVec;
+use std::marker::PhantomData;
+use std::mem;
+use std::ops::Range;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+use buffer::BufferUsage;
+use buffer::sys::BufferCreationError;
+use buffer::sys::SparseLevel;
+use buffer::sys::UnsafeBuffer;
+use buffer::traits::BufferAccess;
+use buffer::traits::BufferInner;
+use buffer::traits::TypedBufferAccess;
+use device::Device;
+use device::DeviceOwned;
+use device::Queue;
+use instance::QueueFamily;
+use memory::pool::AllocLayout;
+use memory::pool::MemoryPool;
+use memory::pool::StdMemoryPoolAlloc;
+use sync::AccessError;
+use sync::GpuFuture;
+use sync::NowFuture;
+use sync::Sharing;
+use sync::SparseBindError;
+use sync::SparseBindFuture;
+use sync::SparseBindOp;
+use sync::SparseGpuLock;
+use sync::bind_sparse;
+use sync::now;
+
+/// Buffer whose memory is bound page by page.
+///
+/// See the documentation of the module for more information.
+#[derive(Debug)]
+pub struct SparseBuffer<T: ?Sized> {
+    // Inner content.
+    inner: UnsafeBuffer,
+
+    // Size in bytes of a page.
+    page_size: usize,
+
+    // Memory type used for the pages.
+    memory_type: u32,
+
+    // Memory bound to each page by the operations submitted so far. `None` if the page is
+    // unbound.
+    pages: Mutex<Vec<Option<Arc<StdMemoryPoolAlloc>>>>,
+
+    // True for each page whose bind operation is known to be finished.
+    resident: Arc<Mutex<Vec<bool>>>,
+
+    // Queue families allowed to access this buffer.
+    queue_families: SmallVec<[u32; 4]>,
+
+    // Number of times this buffer is locked on the GPU side.
+    gpu_lock: SparseGpuLock,
+
+    // Necessary to make it compile.
+    marker: PhantomData<Box<T>>,
+}
+
+impl<T> SparseBuffer<T> {
+    /// Builds a new sparse buffer. Only allowed for sized data.
+    // TODO: unsafe because uninitialized data
+    #[inline]
+    pub fn new<'a, I>(device: Arc<Device>, usage: BufferUsage, queue_families: I)
+                      -> Result<Arc<SparseBuffer<T>>, BufferCreationError>
+        where I: IntoIterator<Item = QueueFamily<'a>>
+    {
+        unsafe { SparseBuffer::raw(device, mem::size_of::<T>(), usage, queue_families) }
+    }
+}
+
+impl<T> SparseBuffer<[T]> {
+    /// Builds a new sparse buffer. Can be used for arrays.
+    // TODO: unsafe because uninitialized data
+    #[inline]
+    pub fn array<'a, I>(device: Arc<Device>, len: usize, usage: BufferUsage, queue_families: I)
+                        -> Result<Arc<SparseBuffer<[T]>>, BufferCreationError>
+        where I: IntoIterator<Item = QueueFamily<'a>>
+    {
+        unsafe { SparseBuffer::raw(device, len * mem::size_of::<T>(), usage, queue_families) }
+    }
+}
+
+impl<T: ?Sized> SparseBuffer<T> {
+    /// Builds a new sparse buffer without checking the size. No page is resident at first.
+    ///
+    /// # Safety
+    ///
+    /// You must ensure that the size that you pass is correct for `T`.
+    ///
+    pub unsafe fn raw<'a, I>(device: Arc<Device>, size: usize, usage: BufferUsage,
+                             queue_families: I)
+                             -> Result<Arc<SparseBuffer<T>>, BufferCreationError>
+        where I: IntoIterator<Item = QueueFamily<'a>>
+    {
+        let queue_families = queue_families
+            .into_iter()
+            .map(|f| f.id())
+            .collect::<SmallVec<[u32; 4]>>();
+
+        let (buffer, mem_reqs) = {
+            let sharing = if queue_families.len() >= 2 {
+                Sharing::Concurrent(queue_families.iter().cloned())
+            } else {
+                Sharing::Exclusive
+            };
+
+            let sparse = SparseLevel {
+                sparse: true,
+                sparse_residency: true,
+                sparse_aliased: false,
+            };
+
+            UnsafeBuffer::new(device.clone(), size, usage, sharing, sparse)?
+        };
+
+        let mem_ty = {
+            let device_local = device
+                .physical_device()
+                .memory_types()
+                .filter(|t| (mem_reqs.memory_type_bits & (1 << t.id())) != 0)
+                .filter(|t| t.is_device_local());
+            let any = device
+                .physical_device()
+                .memory_types()
+                .filter(|t| (mem_reqs.memory_type_bits & (1 << t.id())) != 0);
+            device_local.chain(any).next().unwrap()
+        };
+
+        // For sparse resources, the alignment is the size of a sparse block and the size is
+        // always a multiple of it.
+        debug_assert_eq!(mem_reqs.size % mem_reqs.alignment, 0);
+        let num_pages = mem_reqs.size / mem_reqs.alignment;
+
+        Ok(Arc::new(SparseBuffer {
+                        inner: buffer,
+                        page_size: mem_reqs.alignment,
+                        memory_type: mem_ty.id(),
+                        pages: Mutex::new((0 .. num_pages).map(|_| None).collect()),
+                        resident: Arc::new(Mutex::new(vec![false; num_pages])),
+                        queue_families: queue_families,
+                        gpu_lock: SparseGpuLock::new(),
+                        marker: PhantomData,
+                    }))
+    }
+}
+
+impl<T: ?Sized> SparseBuffer<T> {
+    /// Returns the size in bytes of a page. This is the granularity of `bind` and `unbind`.
+    #[inline]
+    pub fn page_size(&self) -> usize {
+        self.page_size
+    }
+
+    /// Returns the number of pages of the buffer.
+    #[inline]
+    pub fn num_pages(&self) -> usize {
+        self.pages.lock().unwrap().len()
+    }
+
+    /// Returns the range of pages that contain the given range of bytes.
+    #[inline]
+    pub fn pages_of(&self, bytes: Range<usize>) -> Range<usize> {
+        let end = (bytes.end + self.page_size - 1) / self.page_size;
+        (bytes.start / self.page_size) .. end
+    }
+
+    /// Returns true if the given page is resident.
+    ///
+    /// A page becomes resident once the future returned by `bind` is known to be finished, for
+    /// example after waiting for a fence signaled after it. Similarly, it becomes non-resident
+    /// once the future returned by `unbind` is known to be finished.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if `page` is out of range.
+    ///
+    #[inline]
+    pub fn is_resident(&self, page: usize) -> bool {
+        self.resident.lock().unwrap()[page]
+    }
+
+    /// Returns the number of pages that are currently resident.
+    #[inline]
+    pub fn num_resident_pages(&self) -> usize {
+        self.resident
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|&&r| r)
+            .count()
+    }
+
+    /// Returns the queue families this buffer can be used on.
+    // TODO: use a custom iterator
+    #[inline]
+    pub fn queue_families(&self) -> Vec<QueueFamily> {
+        self.queue_families
+            .iter()
+            .map(|&num| {
+                     self.device()
+                         .physical_device()
+                         .queue_family_by_id(num)
+                         .unwrap()
+                 })
+            .collect()
+    }
+}
+
+impl<T: ?Sized> SparseBuffer<T>
+    where T: 'static + Send + Sync
+{
+    /// Allocates memory for the given pages and binds it to the buffer.
+    ///
+    /// Pages that are already resident are left untouched.
+    ///
+    /// > **Note**: This is just a shortcut for `bind_after(vulkano::sync::now(), ...)`.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if the range of pages is out of range.
+    ///
+    #[inline]
+    pub fn bind(me: &Arc<Self>, queue: Arc<Queue>, pages: Range<usize>)
+                -> Result<SparseBindFuture<NowFuture>, SparseBindError> {
+        let device = queue.device().clone();
+        SparseBuffer::bind_after(me, now(device), queue, pages)
+    }
+
+    /// Allocates memory for the given pages and binds it to the buffer, after an existing
+    /// future.
+    ///
+    /// Pages that are already resident are left untouched.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if the range of pages is out of range.
+    /// - Panics if the device of the buffer is not the same as the device of the future.
+    ///
+    pub fn bind_after<F>(me: &Arc<Self>, future: F, queue: Arc<Queue>, pages: Range<usize>)
+                         -> Result<SparseBindFuture<F>, SparseBindError>
+        where F: GpuFuture
+    {
+        let mut resident = me.pages.lock().unwrap();
+        assert!(pages.end <= resident.len());
+
+        let device = me.device();
+        let mem_ty = device
+            .physical_device()
+            .memory_type_by_id(me.memory_type)
+            .unwrap();
+
+        let mut binds = Vec::new();
+        for page in pages.clone() {
+            if resident[page].is_some() {
+                continue;
+            }
+
+            let mem = MemoryPool::alloc(&Device::standard_pool(device),
+                                        mem_ty,
+                                        me.page_size,
+                                        me.page_size,
+                                        AllocLayout::Linear)?;
+            binds.push((page * me.page_size, me.page_size, Some(Arc::new(mem))));
+        }
+
+        let op = SparseBindOp::Buffer {
+            buffer: me.clone(),
+            binds: binds.clone(),
+        };
+        let mut future = bind_sparse(future, queue, op)?;
+
+        let bound = binds
+            .iter()
+            .map(|&(offset, _, _)| offset / me.page_size)
+            .collect::<Vec<_>>();
+        for (offset, _, mem) in binds {
+            resident[offset / me.page_size] = mem;
+        }
+
+        let residency = me.resident.clone();
+        future.on_finished(move || {
+            let mut residency = residency.lock().unwrap();
+            for &page in bound.iter() {
+                residency[page] = true;
+            }
+        });
+
+        Ok(future)
+    }
+
+    /// Unbinds the memory of the given pages. The memory is freed once the GPU has finished
+    /// executing the operation.
+    ///
+    /// Pages that aren't resident are left untouched.
+    ///
+    /// > **Note**: This is just a shortcut for `unbind_after(vulkano::sync::now(), ...)`.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if the range of pages is out of range.
+    ///
+    #[inline]
+    pub fn unbind(me: &Arc<Self>, queue: Arc<Queue>, pages: Range<usize>)
+                  -> Result<SparseBindFuture<NowFuture>, SparseBindError> {
+        let device = queue.device().clone();
+        SparseBuffer::unbind_after(me, now(device), queue, pages)
+    }
+
+    /// Unbinds the memory of the given pages after an existing future. The memory is freed once
+    /// the GPU has finished executing the operation.
+    ///
+    /// Pages that aren't resident are left untouched.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if the range of pages is out of range.
+    /// - Panics if the device of the buffer is not the same as the device of the future.
+    ///
+    pub fn unbind_after<F>(me: &Arc<Self>, future: F, queue: Arc<Queue>, pages: Range<usize>)
+                           -> Result<SparseBindFuture<F>, SparseBindError>
+        where F: GpuFuture
+    {
+        let mut resident = me.pages.lock().unwrap();
+        assert!(pages.end <= resident.len());
+
+        let unbound = pages
+            .clone()
+            .filter(|&page| resident[page].is_some())
+            .collect::<Vec<_>>();
+
+        // The future holds the memory of the unbound pages until the GPU has finished unbinding.
+        let op = SparseBindOp::Buffer {
+            buffer: me.clone(),
+            binds: unbound
+                .iter()
+                .map(|&page| (page * me.page_size, me.page_size, None))
+                .collect(),
+        };
+        let mut future = bind_sparse(future, queue, op)?;
+
+        for &page in unbound.iter() {
+            let mem = resident[page].take();
+            future.keep_alive(mem.unwrap());
+        }
+
+        let residency = me.resident.clone();
+        future.on_finished(move || {
+            let mut residency = residency.lock().unwrap();
+            for &page in unbound.iter() {
+                residency[page] = false;
+            }
+        });
+
+        Ok(future)
+    }
+}
+
+unsafe impl<T: ?Sized> DeviceOwned for SparseBuffer<T> {
+    #[inline]
+    fn device(&self) -> &Arc<Device> {
+        self.inner.device()
+    }
+}
+
+unsafe impl<T: ?Sized> BufferAccess for SparseBuffer<T>
+    where T: 'static + Send + Sync
+{
+    #[inline]
+    fn inner(&self) -> BufferInner {
+        BufferInner {
+            buffer: &self.inner,
+            offset: 0,
+        }
+    }
+
+    #[inline]
+    fn size(&self) -> usize {
+        self.inner.size()
+    }
+
+    #[inline]
+    fn conflict_key(&self, _: usize, _: usize) -> u64 {
+        self.inner.key()
+    }
+
+    #[inline]
+    fn try_gpu_lock(&self, exclusive: bool, _: &Queue) -> Result<(), AccessError> {
+        self.gpu_lock.try_gpu_lock(exclusive)
+    }
+
+    #[inline]
+    unsafe fn increase_gpu_lock(&self) {
+        self.gpu_lock.increase_gpu_lock()
+    }
+
+    #[inline]
+    unsafe fn unlock(&self) {
+        self.gpu_lock.unlock()
+    }
+}
+
+unsafe impl<T: ?Sized> TypedBufferAccess for SparseBuffer<T>
+    where T: 'static + Send + Sync
+{
+    type Content = T;
+}
+
+#[cfg(test)]
+mod tests {
+    use buffer::BufferUsage;
+    use buffer::sys::BufferCreationError;
+    use buffer::sparse::SparseBuffer;
+    use sync::GpuFuture;
+
+    #[test]
+    fn missing_feature() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        match SparseBuffer::<[u8]>::array(device, 1024 * 1024, BufferUsage::all(),
+                                          Some(queue.family())) {
+            Err(BufferCreationError::SparseBindingFeatureNotEnabled) => (),
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn bind_unbind() {
+        let (device, queue) = gfx_dev_and_queue!(sparse_binding, sparse_residency_buffer);
+
+        if !queue.family().supports_sparse_binding() {
+            return;
+        }
+
+        let buffer = SparseBuffer::<[u8]>::array(device, 1024 * 1024, BufferUsage::all(),
+                                                 Some(queue.family()))
+            .unwrap();
+        assert_eq!(buffer.num_resident_pages(), 0);
+
+        let pages = buffer.pages_of(0 .. 1);
+        SparseBuffer::bind(&buffer, queue.clone(), pages.clone())
+            .unwrap()
+            .then_signal_fence_and_flush()
+            .unwrap()
+            .wait(None)
+            .unwrap();
+        assert!(buffer.is_resident(pages.start));
+        assert_eq!(buffer.num_resident_pages(), 1);
+
+        SparseBuffer::unbind(&buffer, queue.clone(), pages.clone())
+            .unwrap()
+            .then_signal_fence_and_flush()
+            .unwrap()
+            .wait(None)
+            .unwrap();
+        assert!(!buffer.is_resident(pages.start));
+        assert_eq!(buffer.num_resident_pages(), 0);
+    }
+}
@@ -0,0 +1,601 @@
+// Copyright (c) 2017 The vulkano developers
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>,
+// at your option. All files in the project carrying such
+// notice may not be copied, modified, or distributed except
+// according to those terms.
+
+//! Sub-allocation of ranges within large device-local buffers.
+//!
+//! Creating one buffer per mesh means binding a different vertex buffer for each draw. A
+//! `SubBufferAllocator` instead owns a few large `DeviceLocalBuffer<[u8]>` blocks and hands out
+//! ranges within them. All the meshes that live in the same block can then be drawn by binding
+//! the block once and passing the `first_element()` of each sub-buffer as the first vertex or
+//! first index of the draw command.
+//!
+//! When a `SubBuffer` is destroyed, its range is given back to the block it was allocated from
+//! and can be reused by later allocations.
+//!
+//! Since each sub-buffer reports its offset within the block, the synchronization performed by
+//! command buffers detects overlaps per range, and each sub-buffer only locks its own range of the
+//! block on the GPU.
+
+use smallvec::SmallVec;
+use std::error;
+use std::fmt;
+use std::iter;
+use std::marker::PhantomData;
+use std::mem;
+use std::ops::Range;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+use buffer::BufferUsage;
+use buffer::device_local::DeviceLocalBuffer;
+use buffer::slice::BufferSlice;
+use buffer::traits::BufferAccess;
+use buffer::traits::BufferInner;
+use buffer::traits::TypedBufferAccess;
+use device::Device;
+use device::DeviceOwned;
+use device::Queue;
+use instance::QueueFamily;
+use sync::AccessError;
+
+use OomError;
+
+/// Allocates ranges of device-local memory from a chain of large buffers.
+///
+/// The first block is created on the first allocation. When no block has enough free space for
+/// an allocation, a new block is added to the chain.
+///
+/// # Example
+///
+/// ```no_run
+/// use vulkano::buffer::SubBufferAllocator;
+/// # let device: std::sync::Arc<vulkano::device::Device> = return;
+/// let allocator = SubBufferAllocator::meshes(device.clone(), 16 * 1024 * 1024);
+/// let vertices = allocator.alloc::<[f32; 3]>(300).unwrap();
+/// // Bind `vertices.buffer().block_slice()` and draw with
+/// // `vertices.buffer().first_element()` as first vertex.
+/// ```
+pub struct SubBufferAllocator {
+    // The device of the blocks.
+    device: Arc<Device>,
+
+    // Minimum size in bytes of each block.
+    block_size: usize,
+
+    // Usage of the blocks.
+    usage: BufferUsage,
+
+    // Queue families allowed to access the blocks.
+    queue_families: SmallVec<[u32; 4]>,
+
+    // Minimum alignment of every allocation, in bytes.
+    min_alignment: usize,
+
+    // The blocks that have been created so far.
+    blocks: Mutex<Vec<Arc<Block>>>,
+}
+
+// One buffer of the allocator.
+struct Block {
+    // The actual buffer.
+    buffer: Arc<DeviceLocalBuffer<[u8]>>,
+
+    // Ranges of bytes that are free, sorted by offset. Two ranges are never contiguous.
+    free: Mutex<Vec<Range<usize>>>,
+}
+
+/// A range of a block allocated from a `SubBufferAllocator`.
+///
+/// When this object is destroyed, the range is automatically given back to the allocator.
+pub struct SubBuffer<T: ?Sized> {
+    // The block this range belongs to.
+    block: Arc<Block>,
+
+    // Offset in bytes of the range within the block.
+    offset: usize,
+
+    // Size in bytes of the range.
+    size: usize,
+
+    // Necessary to make it compile.
+    marker: PhantomData<Box<T>>,
+}
+
+impl SubBufferAllocator {
+    /// Builds a new allocator whose blocks are at least `block_size` bytes large.
+    ///
+    /// No memory is allocated until the first call to `alloc`.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if `block_size` is 0.
+    ///
+    pub fn new<'a, I>(device: Arc<Device>, block_size: usize, usage: BufferUsage,
+                      queue_families: I)
+                      -> SubBufferAllocator
+        where I: IntoIterator<Item = QueueFamily<'a>>
+    {
+        assert!(block_size != 0);
+
+        let queue_families = queue_families
+            .into_iter()
+            .map(|f| f.id())
+            .collect::<SmallVec<[u32; 4]>>();
+
+        let min_alignment = {
+            let limits = device.physical_device().limits();
+            let mut align = 1;
+            if usage.storage_buffer {
+                align = lcm(align, limits.min_storage_buffer_offset_alignment() as usize);
+            }
+            if usage.uniform_buffer {
+                align = lcm(align, limits.min_uniform_buffer_offset_alignment() as usize);
+            }
+            align
+        };
+
+        SubBufferAllocator {
+            device: device,
+            block_size: block_size,
+            usage: usage,
+            queue_families: queue_families,
+            min_alignment: min_alignment,
+            blocks: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Builds an allocator meant for storing meshes.
+    ///
+    /// Shortcut for an allocator whose blocks can be used as vertex, index and storage buffers
+    /// and as transfer destinations, with exclusive queue family accesses.
+    #[inline]
+    pub fn meshes(device: Arc<Device>, block_size: usize) -> SubBufferAllocator {
+        let usage = BufferUsage {
+            transfer_destination: true,
+            storage_buffer: true,
+            index_buffer: true,
+            vertex_buffer: true,
+            ..BufferUsage::none()
+        };
+
+        SubBufferAllocator::new(device, block_size, usage, iter::empty())
+    }
+
+    /// Returns the minimum size in bytes of each block.
+    #[inline]
+    pub fn block_size(&self) -> usize {
+        self.block_size
+    }
+
+    /// Returns the number of blocks that have been created so far.
+    #[inline]
+    pub fn num_blocks(&self) -> usize {
+        self.blocks.lock().unwrap().len()
+    }
+
+    /// Allocates a range that can hold `len` elements of type `T`.
+    ///
+    /// The offset of the range within its block is always a multiple of the size of `T`, so that
+    /// `first_element()` can be used as a first vertex or first index. If no block has enough
+    /// room, a new one is created.
+    ///
+    /// The content of the range is undefined.
+    ///
+    /// Returns `SizeOverflow` if the size in bytes of the range doesn't fit in a `usize`.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if `len` or the size of `T` is 0.
+    ///
+    pub fn alloc<T>(&self, len: usize)
+                    -> Result<BufferSlice<[T], Arc<SubBuffer<[T]>>>, SubBufferAllocError>
+        where T: 'static + Send + Sync
+    {
+        assert!(len != 0);
+        assert!(mem::size_of::<T>() != 0);
+
+        let size = match mem::size_of::<T>().checked_mul(len) {
+            Some(s) => s,
+            None => return Err(SubBufferAllocError::SizeOverflow),
+        };
+        let alignment = lcm(self.min_alignment, mem::size_of::<T>());
+
+        let mut blocks = self.blocks.lock().unwrap();
+
+        for block in blocks.iter() {
+            if let Some(offset) = block.try_alloc(size, alignment) {
+                return Ok(SubBuffer::new(block.clone(), offset, size));
+            }
+        }
+
+        let block_size = if size > self.block_size {
+            size
+        } else {
+            self.block_size
+        };
+
+        let block = Arc::new(Block {
+            buffer: DeviceLocalBuffer::array(self.device.clone(),
+                                             block_size,
+                                             self.usage,
+                                             self.queue_families())?,
+            free: Mutex::new(vec![0 .. block_size]),
+        });
+
+        let offset = block.try_alloc(size, alignment).unwrap();
+        blocks.push(block.clone());
+        Ok(SubBuffer::new(block, offset, size))
+    }
+
+    // Returns the queue families of the blocks.
+    fn queue_families(&self) -> Vec<QueueFamily> {
+        self.queue_families
+            .iter()
+            .map(|&num| {
+                     self.device
+                         .physical_device()
+                         .queue_family_by_id(num)
+                         .unwrap()
+                 })
+            .collect()
+    }
+}
+
+unsafe impl DeviceOwned for SubBufferAllocator {
+    #[inline]
+    fn device(&self) -> &Arc<Device> {
+        &self.device
+    }
+}
+
+impl Block {
+    // Tries to find a free range of `size` bytes whose offset is a multiple of `alignment`.
+    // Returns the offset of the range, which is now considered used.
+    fn try_alloc(&self, size: usize, alignment: usize) -> Option<usize> {
+        let mut free = self.free.lock().unwrap();
+
+        let (index, offset) = match free.iter().enumerate().filter_map(|(num, range)| {
+            let offset = (range.start + alignment - 1) / alignment * alignment;
+            if offset + size <= range.end {
+                Some((num, offset))
+            } else {
+                None
+            }
+        }).next()
+        {
+            Some(v) => v,
+            None => return None,
+        };
+
+        let range = free.remove(index);
+        if offset + size < range.end {
+            free.insert(index, (offset + size) .. range.end);
+        }
+        if range.start < offset {
+            free.insert(index, range.start .. offset);
+        }
+
+        Some(offset)
+    }
+
+    // Gives back a range that was returned by `try_alloc`.
+    fn free(&self, range: Range<usize>) {
+        let mut free = self.free.lock().unwrap();
+
+        let index = match free.binary_search_by_key(&range.start, |r| r.start) {
+            Ok(_) => panic!("Range freed twice"),
+            Err(i) => i,
+        };
+
+        let merge_prev = index >= 1 && free[index - 1].end == range.start;
+        let merge_next = index < free.len() && free[index].start == range.end;
+
+        match (merge_prev, merge_next) {
+            (true, true) => {
+                let next = free.remove(index);
+                free[index - 1].end = next.end;
+            },
+            (true, false) => {
+                free[index - 1].end = range.end;
+            },
+            (false, true) => {
+                free[index].start = range.start;
+            },
+            (false, false) => {
+                free.insert(index, range);
+            },
+        }
+    }
+}
+
+impl<T: ?Sized> SubBuffer<T> {
+    // Wraps an allocated range.
+    fn new(block: Arc<Block>, offset: usize, size: usize) -> BufferSlice<T, Arc<SubBuffer<T>>>
+        where T: 'static + Send + Sync
+    {
+        BufferSlice::from_typed_buffer_access(Arc::new(SubBuffer {
+                                                           block: block,
+                                                           offset: offset,
+                                                           size: size,
+                                                           marker: PhantomData,
+                                                       }))
+    }
+
+    /// Returns the block this sub-buffer was allocated from.
+    #[inline]
+    pub fn block(&self) -> &Arc<DeviceLocalBuffer<[u8]>> {
+        &self.block.buffer
+    }
+
+    /// Returns the offset in bytes of this sub-buffer within its block.
+    #[inline]
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+}
+
+impl<T> SubBuffer<[T]> {
+    /// Returns the index of the first element of this sub-buffer within its block, when the
+    /// block is viewed as an array of `T`.
+    ///
+    /// This is the value to pass as first vertex or first index when drawing from the slice
+    /// returned by `block_slice()`.
+    #[inline]
+    pub fn first_element(&self) -> usize {
+        debug_assert_eq!(self.offset % mem::size_of::<T>(), 0);
+        self.offset / mem::size_of::<T>()
+    }
+
+    /// Returns a slice that covers the whole block, viewed as an array of `T`.
+    ///
+    /// All the sub-buffers of the same type that were allocated from the same block can be
+    /// accessed through this slice, which means that you can bind it once as a vertex or index
+    /// buffer and draw multiple meshes.
+    #[inline]
+    pub fn block_slice(&self) -> BufferSlice<[T], Arc<DeviceLocalBuffer<[u8]>>> {
+        let len = self.block.buffer.size() / mem::size_of::<T>();
+        unsafe {
+            BufferSlice::from_raw_parts(self.block.buffer.clone(), 0, len * mem::size_of::<T>())
+        }
+    }
+}
+
+unsafe impl<T: ?Sized> BufferAccess for SubBuffer<T>
+    where T: 'static + Send + Sync
+{
+    #[inline]
+    fn inner(&self) -> BufferInner {
+        let inner = self.block.buffer.inner();
+        BufferInner {
+            buffer: inner.buffer,
+            offset: inner.offset + self.offset,
+        }
+    }
+
+    #[inline]
+    fn size(&self) -> usize {
+        self.size
+    }
+
+    #[inline]
+    fn conflict_key(&self, self_offset: usize, self_size: usize) -> u64 {
+        self.block
+            .buffer
+            .conflict_key(self.offset + self_offset, self_size)
+    }
+
+    #[inline]
+    fn try_gpu_lock(&self, exclusive_access: bool, queue: &Queue) -> Result<(), AccessError> {
+        self.block
+            .buffer
+            .try_gpu_lock_range(exclusive_access, queue, self.offset, self.size)
+    }
+
+    #[inline]
+    unsafe fn increase_gpu_lock(&self) {
+        self.block
+            .buffer
+            .increase_gpu_lock_range(self.offset, self.size)
+    }
+
+    #[inline]
+    unsafe fn unlock(&self) {
+        self.block.buffer.unlock_range(self.offset, self.size)
+    }
+
+    #[inline]
+    fn try_gpu_lock_range(&self, exclusive_access: bool, queue: &Queue, offset: usize,
+                          size: usize)
+                          -> Result<(), AccessError> {
+        self.block
+            .buffer
+            .try_gpu_lock_range(exclusive_access, queue, self.offset + offset, size)
+    }
+
+    #[inline]
+    unsafe fn increase_gpu_lock_range(&self, offset: usize, size: usize) {
+        self.block
+            .buffer
+            .increase_gpu_lock_range(self.offset + offset, size)
+    }
+
+    #[inline]
+    unsafe fn unlock_range(&self, offset: usize, size: usize) {
+        self.block
+            .buffer
+            .unlock_range(self.offset + offset, size)
+    }
+}
+
+unsafe impl<T: ?Sized> TypedBufferAccess for SubBuffer<T>
+    where T: 'static + Send + Sync
+{
+    type Content = T;
+}
+
+unsafe impl<T: ?Sized> DeviceOwned for SubBuffer<T> {
+    #[inline]
+    fn device(&self) -> &Arc<Device> {
+        self.block.buffer.device()
+    }
+}
+
+impl<T: ?Sized> Drop for SubBuffer<T> {
+    #[inline]
+    fn drop(&mut self) {
+        self.block.free(self.offset .. self.offset + self.size);
+    }
+}
+
+// Least common multiple of two non-zero numbers.
+fn lcm(a: usize, b: usize) -> usize {
+    let (mut x, mut y) = (a, b);
+    while y != 0 {
+        let t = x % y;
+        x = y;
+        y = t;
+    }
+    a / x * b
+}
+
+/// Error that can happen when allocating a range from a `SubBufferAllocator`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SubBufferAllocError {
+    /// Not enough memory to create a new block.
+    OomError(OomError),
+    /// The size in bytes of the requested range overflows a `usize`.
+    SizeOverflow,
+}
+
+impl error::Error for SubBufferAllocError {
+    #[inline]
+    fn description(&self) -> &str {
+        match *self {
+            SubBufferAllocError::OomError(_) => "not enough memory available",
+            SubBufferAllocError::SizeOverflow => {
+                "the size in bytes of the requested range overflows a usize"
+            },
+        }
+    }
+
+    #[inline]
+    fn cause(&self) -> Option<&error::Error> {
+        match *self {
+            SubBufferAllocError::OomError(ref err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for SubBufferAllocError {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(fmt, "{}", error::Error::description(self))
+    }
+}
+
+impl From<OomError> for SubBufferAllocError {
+    #[inline]
+    fn from(err: OomError) -> SubBufferAllocError {
+        SubBufferAllocError::OomError(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use buffer::BufferAccess;
+    use buffer::SubBufferAllocError;
+    use buffer::SubBufferAllocator;
+
+    #[test]
+    fn alloc_offsets() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        let allocator = SubBufferAllocator::meshes(device, 4096);
+        assert_eq!(allocator.num_blocks(), 0);
+
+        let a = allocator.alloc::<[f32; 3]>(10).unwrap();
+        let b = allocator.alloc::<[f32; 3]>(10).unwrap();
+        assert_eq!(allocator.num_blocks(), 1);
+        assert_eq!(a.len(), 10);
+
+        assert_eq!(a.buffer().offset() % 12, 0);
+        assert_eq!(b.buffer().offset() % 12, 0);
+        assert_eq!(b.buffer().first_element() * 12, b.buffer().offset());
+        assert!(!a.conflicts_buffer_all(&b));
+        assert!(a.conflicts_buffer_all(&a.buffer().block_slice()));
+    }
+
+    #[test]
+    fn disjoint_gpu_locks() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let allocator = SubBufferAllocator::meshes(device, 4096);
+        let a = allocator.alloc::<u32>(16).unwrap();
+        let b = allocator.alloc::<u32>(16).unwrap();
+        assert_eq!(allocator.num_blocks(), 1);
+
+        let a = a.buffer();
+        let b = b.buffer();
+        assert!(a.try_gpu_lock(true, &queue).is_ok());
+        assert!(b.try_gpu_lock(true, &queue).is_ok());
+        assert!(a.try_gpu_lock(false, &queue).is_err());
+        assert!(a.block_slice().try_gpu_lock(false, &queue).is_err());
+
+        unsafe {
+            a.unlock();
+            b.unlock();
+        }
+        assert!(a.block_slice().try_gpu_lock(true, &queue).is_ok());
+    }
+
+    #[test]
+    fn reuse_freed_ranges() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        let allocator = SubBufferAllocator::meshes(device, 4096);
+
+        let first_offset = {
+            let a = allocator.alloc::<u32>(1024).unwrap();
+            a.buffer().offset()
+        };
+
+        let b = allocator.alloc::<u32>(1024).unwrap();
+        assert_eq!(b.buffer().offset(), first_offset);
+        assert_eq!(allocator.num_blocks(), 1);
+    }
+
+    #[test]
+    fn grow() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        let allocator = SubBufferAllocator::meshes(device, 4096);
+
+        let _a = allocator.alloc::<u32>(1024).unwrap();
+        let _b = allocator.alloc::<u32>(1024).unwrap();
+        assert_eq!(allocator.num_blocks(), 2);
+
+        let c = allocator.alloc::<u8>(10000).unwrap();
+        assert_eq!(allocator.num_blocks(), 3);
+        assert_eq!(c.buffer().block().size(), 10000);
+    }
+
+    #[test]
+    fn size_overflow() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        let allocator = SubBufferAllocator::meshes(device, 4096);
+
+        match allocator.alloc::<u32>(usize::max_value() / 2) {
+            Err(SubBufferAllocError::SizeOverflow) => (),
+            _ => panic!(),
+        };
+        assert_eq!(allocator.num_blocks(), 0);
+    }
+}
@@ -115,7 +115,7 @@ impl UnsafeBuffer {
             let mut output = mem::uninitialized();
             check_errors(vk.CreateBuffer(device.internal_object(),
                                          &infos,
-                                         ptr::null(),
+                                         device.allocation_callbacks().as_ptr(),
                                          &mut output))?;
             output
         };
@@ -284,7 +284,9 @@ impl Drop for UnsafeBuffer {
     fn drop(&mut self) {
         unsafe {
             let vk = self.device.pointers();
-            vk.DestroyBuffer(self.device.internal_object(), self.buffer, ptr::null());
+            vk.DestroyBuffer(self.device.internal_object(),
+                             self.buffer,
+                             self.device.allocation_callbacks().as_ptr());
         }
     }
 }
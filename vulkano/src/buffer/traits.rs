@@ -8,6 +8,7 @@
 // according to those terms.
 
 use std::ops::Range;
+use std::sync::Arc;
 
 use buffer::BufferSlice;
 use buffer::sys::UnsafeBuffer;
@@ -16,6 +17,7 @@ use device::Queue;
 use image::ImageAccess;
 use memory::Content;
 use sync::AccessError;
+use sync::Fence;
 
 use SafeDeref;
 use VulkanObject;
@@ -202,6 +204,57 @@ pub unsafe trait BufferAccess: DeviceOwned {
     ///
     /// Must only be called once per previous lock.
     unsafe fn unlock(&self);
+
+    /// Same as `try_gpu_lock`, but only locks the range of `size` bytes starting at `offset`
+    /// within this buffer.
+    ///
+    /// Used by objects such as `BufferSlice` that only access a part of the buffer. The default
+    /// implementation locks the whole buffer.
+    ///
+    /// If you call this function, you should call `unlock_range()` with the same range once the
+    /// resource is no longer in use by the GPU.
+    #[inline]
+    fn try_gpu_lock_range(&self, exclusive_access: bool, queue: &Queue, offset: usize,
+                          size: usize)
+                          -> Result<(), AccessError> {
+        self.try_gpu_lock(exclusive_access, queue)
+    }
+
+    /// Same as `increase_gpu_lock`, but for a range previously locked with `try_gpu_lock_range`.
+    ///
+    /// The default implementation calls `increase_gpu_lock`.
+    #[inline]
+    unsafe fn increase_gpu_lock_range(&self, offset: usize, size: usize) {
+        self.increase_gpu_lock()
+    }
+
+    /// Same as `unlock`, but for a range previously locked with `try_gpu_lock_range` or
+    /// `increase_gpu_lock_range`.
+    ///
+    /// The default implementation calls `unlock`.
+    #[inline]
+    unsafe fn unlock_range(&self, offset: usize, size: usize) {
+        self.unlock()
+    }
+
+    /// Informs the buffer that the submission that holds one of its GPU locks has been
+    /// submitted, and that `fence` will be signaled once this submission is finished.
+    ///
+    /// Called by `FenceSignalFuture` after it has been flushed. Buffers that can be accessed by
+    /// the CPU can remember the fence in order to wait for it later. The default implementation
+    /// does nothing.
+    #[inline]
+    unsafe fn attach_fence(&self, fence: &Arc<Fence>) {
+    }
+
+    /// Same as `attach_fence`, but for a range previously locked with `try_gpu_lock_range` or
+    /// `increase_gpu_lock_range`.
+    ///
+    /// The default implementation calls `attach_fence`.
+    #[inline]
+    unsafe fn attach_fence_range(&self, offset: usize, size: usize, fence: &Arc<Fence>) {
+        self.attach_fence(fence)
+    }
 }
 
 /// Inner information about a buffer.
@@ -254,6 +307,33 @@ unsafe impl<T> BufferAccess for T
     unsafe fn unlock(&self) {
         (**self).unlock()
     }
+
+    #[inline]
+    fn try_gpu_lock_range(&self, exclusive_access: bool, queue: &Queue, offset: usize,
+                          size: usize)
+                          -> Result<(), AccessError> {
+        (**self).try_gpu_lock_range(exclusive_access, queue, offset, size)
+    }
+
+    #[inline]
+    unsafe fn increase_gpu_lock_range(&self, offset: usize, size: usize) {
+        (**self).increase_gpu_lock_range(offset, size)
+    }
+
+    #[inline]
+    unsafe fn unlock_range(&self, offset: usize, size: usize) {
+        (**self).unlock_range(offset, size)
+    }
+
+    #[inline]
+    unsafe fn attach_fence(&self, fence: &Arc<Fence>) {
+        (**self).attach_fence(fence)
+    }
+
+    #[inline]
+    unsafe fn attach_fence_range(&self, offset: usize, size: usize, fence: &Arc<Fence>) {
+        (**self).attach_fence_range(offset, size, fence)
+    }
 }
 
 /// Extension trait for `BufferAccess`. Indicates the type of the content of the buffer.
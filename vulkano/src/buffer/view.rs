@@ -49,6 +49,7 @@ use buffer::BufferInner;
 use buffer::TypedBufferAccess;
 use device::Device;
 use device::DeviceOwned;
+use format::Format;
 use format::FormatDesc;
 use format::StrongStorage;
 
@@ -67,6 +68,7 @@ pub struct BufferView<F, B>
     view: vk::BufferView,
     buffer: B,
     marker: PhantomData<F>,
+    format: Format,
     atomic_accesses: bool,
 }
 
@@ -98,12 +100,13 @@ impl<F, B> BufferView<F, B>
         where B: BufferAccess,
               F: FormatDesc + 'static
     {
+        let format = format.format();
+
         let (view, format_props) = {
             let size = org_buffer.size();
             let BufferInner { buffer, offset } = org_buffer.inner();
 
             let device = buffer.device();
-            let format = format.format();
 
             // TODO: check minTexelBufferOffsetAlignment
 
@@ -160,7 +163,7 @@ impl<F, B> BufferView<F, B>
             let mut output = mem::uninitialized();
             check_errors(vk.CreateBufferView(device.internal_object(),
                                              &infos,
-                                             ptr::null(),
+                                             device.allocation_callbacks().as_ptr(),
                                              &mut output))?;
             (output, format_props)
         };
@@ -169,6 +172,7 @@ impl<F, B> BufferView<F, B>
                view: view,
                buffer: org_buffer,
                marker: PhantomData,
+               format: format,
                atomic_accesses: (format_props &
                                      vk::FORMAT_FEATURE_STORAGE_TEXEL_BUFFER_ATOMIC_BIT) !=
                    0,
@@ -181,6 +185,12 @@ impl<F, B> BufferView<F, B>
         &self.buffer
     }
 
+    /// Returns the format of the view.
+    #[inline]
+    pub fn format(&self) -> Format {
+        self.format
+    }
+
     /// Returns true if the buffer view can be used as a uniform texel buffer.
     #[inline]
     pub fn uniform_texel_buffer(&self) -> bool {
@@ -237,10 +247,11 @@ impl<F, B> Drop for BufferView<F, B>
     #[inline]
     fn drop(&mut self) {
         unsafe {
-            let vk = self.buffer.inner().buffer.device().pointers();
-            vk.DestroyBufferView(self.buffer.inner().buffer.device().internal_object(),
+            let device = self.buffer.inner().buffer.device();
+            let vk = device.pointers();
+            vk.DestroyBufferView(device.internal_object(),
                                  self.view,
-                                 ptr::null());
+                                 device.allocation_callbacks().as_ptr());
         }
     }
 }
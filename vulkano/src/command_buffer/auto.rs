@@ -23,6 +23,8 @@ use command_buffer::DrawIndirectCommand;
 use command_buffer::DynamicState;
 use command_buffer::StateCacher;
 use command_buffer::StateCacherOutcome;
+use command_buffer::mipmaps::ComputeDownsample;
+use command_buffer::mipmaps::MipmapsMethod;
 use command_buffer::pool::CommandPoolBuilderAlloc;
 use command_buffer::pool::standard::StandardCommandPoolAlloc;
 use command_buffer::pool::standard::StandardCommandPoolBuilder;
@@ -36,6 +38,7 @@ use command_buffer::sys::UnsafeCommandBufferBuilderBufferImageCopy;
 use command_buffer::sys::UnsafeCommandBufferBuilderColorImageClear;
 use command_buffer::sys::UnsafeCommandBufferBuilderImageAspect;
 use command_buffer::validity::*;
+use descriptor::descriptor_set::DescriptorSet;
 use descriptor::descriptor_set::DescriptorSetsCollection;
 use descriptor::pipeline_layout::PipelineLayoutAbstract;
 use device::Device;
@@ -49,11 +52,14 @@ use image::ImageAccess;
 use image::ImageLayout;
 use instance::QueueFamily;
 use pipeline::ComputePipelineAbstract;
+use pipeline::ComputePipelineCreationError;
 use pipeline::GraphicsPipelineAbstract;
 use pipeline::input_assembly::Index;
+use pipeline::shader::reflect::ReflectedShaderModuleCreationError;
 use pipeline::vertex::VertexSource;
 use sync::AccessCheckError;
 use sync::AccessFlagBits;
+use sync::Fence;
 use sync::GpuFuture;
 use sync::PipelineStages;
 
@@ -293,16 +299,81 @@ impl<P> AutoCommandBufferBuilder<P> {
         }
     }
 
+    /// Adds a command that copies from an image to a buffer.
+    pub fn copy_image_to_buffer<S, D>(self, source: S, destination: D)
+                                      -> Result<Self, CopyImageToBufferError>
+        where S: ImageAccess + Send + Sync + 'static,
+              D: BufferAccess + Send + Sync + 'static
+    {
+        self.ensure_outside_render_pass()?;
+
+        let dims = source.dimensions().width_height_depth();
+        self.copy_image_to_buffer_dimensions(source, destination, [0, 0, 0], dims, 0, 1, 0)
+    }
+
+    /// Adds a command that copies from an image to a buffer.
+    ///
+    /// The texels are tightly packed in the buffer.
+    pub fn copy_image_to_buffer_dimensions<S, D>(
+        mut self, source: S, destination: D, offset: [u32; 3], size: [u32; 3], first_layer: u32,
+        num_layers: u32, mipmap: u32) -> Result<Self, CopyImageToBufferError>
+        where S: ImageAccess + Send + Sync + 'static,
+              D: BufferAccess + Send + Sync + 'static
+    {
+        unsafe {
+            self.ensure_outside_render_pass()?;
+            let infos = check_copy_image_to_buffer(self.device(), &source, &destination, offset,
+                                                   size, first_layer, num_layers, mipmap)?;
+
+            let copy = UnsafeCommandBufferBuilderBufferImageCopy {
+                buffer_offset: 0,
+                buffer_row_length: 0,
+                buffer_image_height: 0,
+                image_aspect: infos.aspect,
+                image_mip_level: mipmap,
+                image_base_array_layer: first_layer,
+                image_layer_count: num_layers,
+                image_offset: [offset[0] as i32, offset[1] as i32, offset[2] as i32],
+                image_extent: size,
+            };
+
+            self.inner.copy_image_to_buffer(source, ImageLayout::TransferSrcOptimal,
+                                            destination, iter::once(copy))?;
+            Ok(self)
+        }
+    }
+
     #[inline]
-    pub fn dispatch<Cp, S, Pc>(mut self, dimensions: [u32; 3], pipeline: Cp, sets: S, constants: Pc)
+    pub fn dispatch<Cp, S, Pc>(self, dimensions: [u32; 3], pipeline: Cp, sets: S, constants: Pc)
                                -> Result<Self, DispatchError>
         where Cp: ComputePipelineAbstract + Send + Sync + 'static + Clone, // TODO: meh for Clone
               S: DescriptorSetsCollection
+    {
+        self.dispatch_with_dynamic_offsets(dimensions, pipeline, sets, constants, iter::empty())
+    }
+
+    /// Same as `dispatch`, but also passes the dynamic offsets of the dynamic uniform buffer and
+    /// dynamic storage buffer descriptors of the sets.
+    ///
+    /// There must be one offset for each array element of each dynamic descriptor, ordered by
+    /// set, then by binding, then by array element. Each offset must be a multiple of the
+    /// `min_uniform_buffer_offset_alignment` or `min_storage_buffer_offset_alignment` limit of
+    /// the device, and must not make the descriptor access memory past the end of its buffer.
+    pub fn dispatch_with_dynamic_offsets<Cp, S, Pc, Do>(mut self, dimensions: [u32; 3],
+                                                        pipeline: Cp, sets: S, constants: Pc,
+                                                        dynamic_offsets: Do)
+                                                        -> Result<Self, DispatchError>
+        where Cp: ComputePipelineAbstract + Send + Sync + 'static + Clone, // TODO: meh for Clone
+              S: DescriptorSetsCollection,
+              Do: IntoIterator<Item = u32>
     {
         unsafe {
             self.ensure_outside_render_pass()?;
             check_push_constants_validity(&pipeline, &constants)?;
             check_descriptor_sets_validity(&pipeline, &sets)?;
+            let sets = sets.into_vec();
+            let dynamic_offsets = dynamic_offsets.into_iter().collect::<Vec<_>>();
+            check_dynamic_offsets(&pipeline, &sets, &dynamic_offsets)?;
             check_dispatch(pipeline.device(), dimensions)?;
 
             if let StateCacherOutcome::NeedChange =
@@ -312,7 +383,7 @@ impl<P> AutoCommandBufferBuilder<P> {
             }
 
             push_constants(&mut self.inner, pipeline.clone(), constants);
-            descriptor_sets(&mut self.inner, false, pipeline.clone(), sets)?;
+            descriptor_sets(&mut self.inner, false, pipeline.clone(), sets, dynamic_offsets)?;
 
             self.inner.dispatch(dimensions);
             Ok(self)
@@ -320,10 +391,26 @@ impl<P> AutoCommandBufferBuilder<P> {
     }
 
     #[inline]
-    pub fn draw<V, Gp, S, Pc>(mut self, pipeline: Gp, dynamic: DynamicState, vertices: V, sets: S,
+    pub fn draw<V, Gp, S, Pc>(self, pipeline: Gp, dynamic: DynamicState, vertices: V, sets: S,
                               constants: Pc) -> Result<Self, DrawError>
         where Gp: GraphicsPipelineAbstract + VertexSource<V> + Send + Sync + 'static + Clone, // TODO: meh for Clone
               S: DescriptorSetsCollection
+    {
+        self.draw_with_dynamic_offsets(pipeline, dynamic, vertices, sets, constants, iter::empty())
+    }
+
+    /// Same as `draw`, but also passes the dynamic offsets of the dynamic uniform buffer and
+    /// dynamic storage buffer descriptors of the sets.
+    ///
+    /// See `dispatch_with_dynamic_offsets` for the requirements on the offsets.
+    pub fn draw_with_dynamic_offsets<V, Gp, S, Pc, Do>(mut self, pipeline: Gp,
+                                                       dynamic: DynamicState, vertices: V,
+                                                       sets: S, constants: Pc,
+                                                       dynamic_offsets: Do)
+                                                       -> Result<Self, DrawError>
+        where Gp: GraphicsPipelineAbstract + VertexSource<V> + Send + Sync + 'static + Clone, // TODO: meh for Clone
+              S: DescriptorSetsCollection,
+              Do: IntoIterator<Item = u32>
     {
         unsafe {
             // TODO: must check that pipeline is compatible with render pass
@@ -332,6 +419,9 @@ impl<P> AutoCommandBufferBuilder<P> {
             check_dynamic_state_validity(&pipeline, &dynamic)?;
             check_push_constants_validity(&pipeline, &constants)?;
             check_descriptor_sets_validity(&pipeline, &sets)?;
+            let sets = sets.into_vec();
+            let dynamic_offsets = dynamic_offsets.into_iter().collect::<Vec<_>>();
+            check_dynamic_offsets(&pipeline, &sets, &dynamic_offsets)?;
             let vb_infos = check_vertex_buffers(&pipeline, vertices)?;
 
             if let StateCacherOutcome::NeedChange =
@@ -344,7 +434,7 @@ impl<P> AutoCommandBufferBuilder<P> {
 
             push_constants(&mut self.inner, pipeline.clone(), constants);
             set_state(&mut self.inner, dynamic);
-            descriptor_sets(&mut self.inner, true, pipeline.clone(), sets)?;
+            descriptor_sets(&mut self.inner, true, pipeline.clone(), sets, dynamic_offsets)?;
             vertex_buffers(&mut self.inner, vb_infos.vertex_buffers)?;
 
             self.inner
@@ -355,13 +445,31 @@ impl<P> AutoCommandBufferBuilder<P> {
 
     #[inline]
     pub fn draw_indexed<V, Gp, S, Pc, Ib, I>(
-        mut self, pipeline: Gp, dynamic: DynamicState, vertices: V, index_buffer: Ib, sets: S,
+        self, pipeline: Gp, dynamic: DynamicState, vertices: V, index_buffer: Ib, sets: S,
         constants: Pc)
         -> Result<Self, DrawIndexedError>
         where Gp: GraphicsPipelineAbstract + VertexSource<V> + Send + Sync + 'static + Clone, // TODO: meh for Clone
               S: DescriptorSetsCollection,
               Ib: BufferAccess + TypedBufferAccess<Content = [I]> + Send + Sync + 'static,
               I: Index + 'static
+    {
+        self.draw_indexed_with_dynamic_offsets(pipeline, dynamic, vertices, index_buffer, sets,
+                                               constants, iter::empty())
+    }
+
+    /// Same as `draw_indexed`, but also passes the dynamic offsets of the dynamic uniform buffer
+    /// and dynamic storage buffer descriptors of the sets.
+    ///
+    /// See `dispatch_with_dynamic_offsets` for the requirements on the offsets.
+    pub fn draw_indexed_with_dynamic_offsets<V, Gp, S, Pc, Ib, I, Do>(
+        mut self, pipeline: Gp, dynamic: DynamicState, vertices: V, index_buffer: Ib, sets: S,
+        constants: Pc, dynamic_offsets: Do)
+        -> Result<Self, DrawIndexedError>
+        where Gp: GraphicsPipelineAbstract + VertexSource<V> + Send + Sync + 'static + Clone, // TODO: meh for Clone
+              S: DescriptorSetsCollection,
+              Ib: BufferAccess + TypedBufferAccess<Content = [I]> + Send + Sync + 'static,
+              I: Index + 'static,
+              Do: IntoIterator<Item = u32>
     {
         unsafe {
             // TODO: must check that pipeline is compatible with render pass
@@ -371,6 +479,9 @@ impl<P> AutoCommandBufferBuilder<P> {
             check_dynamic_state_validity(&pipeline, &dynamic)?;
             check_push_constants_validity(&pipeline, &constants)?;
             check_descriptor_sets_validity(&pipeline, &sets)?;
+            let sets = sets.into_vec();
+            let dynamic_offsets = dynamic_offsets.into_iter().collect::<Vec<_>>();
+            check_dynamic_offsets(&pipeline, &sets, &dynamic_offsets)?;
             let vb_infos = check_vertex_buffers(&pipeline, vertices)?;
 
             if let StateCacherOutcome::NeedChange =
@@ -389,7 +500,7 @@ impl<P> AutoCommandBufferBuilder<P> {
 
             push_constants(&mut self.inner, pipeline.clone(), constants);
             set_state(&mut self.inner, dynamic);
-            descriptor_sets(&mut self.inner, true, pipeline.clone(), sets)?;
+            descriptor_sets(&mut self.inner, true, pipeline.clone(), sets, dynamic_offsets)?;
             vertex_buffers(&mut self.inner, vb_infos.vertex_buffers)?;
             // TODO: how to handle an index out of range of the vertex buffers?
 
@@ -399,7 +510,7 @@ impl<P> AutoCommandBufferBuilder<P> {
     }
 
     #[inline]
-    pub fn draw_indirect<V, Gp, S, Pc, Ib>(mut self, pipeline: Gp, dynamic: DynamicState,
+    pub fn draw_indirect<V, Gp, S, Pc, Ib>(self, pipeline: Gp, dynamic: DynamicState,
                                            vertices: V, indirect_buffer: Ib, sets: S, constants: Pc)
                                            -> Result<Self, DrawIndirectError>
         where Gp: GraphicsPipelineAbstract + VertexSource<V> + Send + Sync + 'static + Clone, // TODO: meh for Clone
@@ -409,6 +520,27 @@ impl<P> AutoCommandBufferBuilder<P> {
                       + Send
                       + Sync
                       + 'static
+    {
+        self.draw_indirect_with_dynamic_offsets(pipeline, dynamic, vertices, indirect_buffer, sets,
+                                                constants, iter::empty())
+    }
+
+    /// Same as `draw_indirect`, but also passes the dynamic offsets of the dynamic uniform buffer
+    /// and dynamic storage buffer descriptors of the sets.
+    ///
+    /// See `dispatch_with_dynamic_offsets` for the requirements on the offsets.
+    pub fn draw_indirect_with_dynamic_offsets<V, Gp, S, Pc, Ib, Do>(
+        mut self, pipeline: Gp, dynamic: DynamicState, vertices: V, indirect_buffer: Ib, sets: S,
+        constants: Pc, dynamic_offsets: Do)
+        -> Result<Self, DrawIndirectError>
+        where Gp: GraphicsPipelineAbstract + VertexSource<V> + Send + Sync + 'static + Clone, // TODO: meh for Clone
+              S: DescriptorSetsCollection,
+              Ib: BufferAccess
+                      + TypedBufferAccess<Content = [DrawIndirectCommand]>
+                      + Send
+                      + Sync
+                      + 'static,
+              Do: IntoIterator<Item = u32>
     {
         unsafe {
             // TODO: must check that pipeline is compatible with render pass
@@ -417,6 +549,9 @@ impl<P> AutoCommandBufferBuilder<P> {
             check_dynamic_state_validity(&pipeline, &dynamic)?;
             check_push_constants_validity(&pipeline, &constants)?;
             check_descriptor_sets_validity(&pipeline, &sets)?;
+            let sets = sets.into_vec();
+            let dynamic_offsets = dynamic_offsets.into_iter().collect::<Vec<_>>();
+            check_dynamic_offsets(&pipeline, &sets, &dynamic_offsets)?;
             let vb_infos = check_vertex_buffers(&pipeline, vertices)?;
 
             let draw_count = indirect_buffer.len() as u32;
@@ -431,7 +566,7 @@ impl<P> AutoCommandBufferBuilder<P> {
 
             push_constants(&mut self.inner, pipeline.clone(), constants);
             set_state(&mut self.inner, dynamic);
-            descriptor_sets(&mut self.inner, true, pipeline.clone(), sets)?;
+            descriptor_sets(&mut self.inner, true, pipeline.clone(), sets, dynamic_offsets)?;
             vertex_buffers(&mut self.inner, vb_infos.vertex_buffers)?;
 
             self.inner.draw_indirect(indirect_buffer,
@@ -490,6 +625,39 @@ impl<P> AutoCommandBufferBuilder<P> {
         }
     }
 
+    /// Adds a command that generates all the mipmap levels of an image from its first level.
+    ///
+    /// Each level is blitted into the next one with a linear filter if the format of the image
+    /// supports it, in which case the image must have the transfer source and transfer
+    /// destination usages. Otherwise the levels are downsampled with a compute shader, which
+    /// requires a two-dimensional image with a floating-point format, the sampled and storage
+    /// usages, and the `shader_storage_image_write_without_format` feature.
+    ///
+    /// The content of the first mipmap level is left untouched, and the content of all the other
+    /// levels is overwritten.
+    pub fn generate_mipmaps<I>(mut self, image: I) -> Result<Self, GenerateMipmapsError>
+        where I: ImageAccess + Send + Sync + 'static
+    {
+        unsafe {
+            self.ensure_outside_render_pass()?;
+
+            match check_generate_mipmaps(self.device(), &image)? {
+                CheckGenerateMipmaps::Blit => {
+                    self.inner.generate_mipmaps(image, MipmapsMethod::Blit)?;
+                },
+                CheckGenerateMipmaps::Compute => {
+                    let downsample = ComputeDownsample::new(&image)?;
+                    self.inner
+                        .generate_mipmaps(image, MipmapsMethod::Compute(downsample))?;
+                    // The compute pipeline and the descriptor sets have been rebound.
+                    self.state_cacher.invalidate();
+                },
+            }
+
+            Ok(self)
+        }
+    }
+
     /// Adds a command that jumps to the next subpass of the current render pass.
     #[inline]
     pub fn next_subpass(mut self, secondary: bool)
@@ -608,19 +776,19 @@ unsafe fn vertex_buffers<P>(destination: &mut SyncCommandBufferBuilder<P>,
     Ok(())
 }
 
-unsafe fn descriptor_sets<P, Pl, S>(destination: &mut SyncCommandBufferBuilder<P>, gfx: bool,
-                                    pipeline: Pl, sets: S)
-                                    -> Result<(), SyncCommandBufferBuilderError>
-    where Pl: PipelineLayoutAbstract + Send + Sync + Clone + 'static,
-          S: DescriptorSetsCollection
+unsafe fn descriptor_sets<P, Pl>(destination: &mut SyncCommandBufferBuilder<P>, gfx: bool,
+                                 pipeline: Pl, sets: Vec<Box<DescriptorSet + Send + Sync>>,
+                                 dynamic_offsets: Vec<u32>)
+                                 -> Result<(), SyncCommandBufferBuilderError>
+    where Pl: PipelineLayoutAbstract + Send + Sync + Clone + 'static
 {
     let mut sets_binder = destination.bind_descriptor_sets();
 
-    for set in sets.into_vec() {
+    for set in sets {
         sets_binder.add(set);
     }
 
-    sets_binder.submit(gfx, pipeline.clone(), 0, iter::empty())?;
+    sets_binder.submit(gfx, pipeline.clone(), 0, dynamic_offsets.into_iter())?;
     Ok(())
 }
 
@@ -642,6 +810,11 @@ unsafe impl<P> CommandBuffer for AutoCommandBuffer<P> {
         self.inner.prepare_submit(future, queue)
     }
 
+    #[inline]
+    unsafe fn attach_fence(&self, fence: &Arc<Fence>) {
+        self.inner.attach_fence(fence)
+    }
+
     #[inline]
     fn check_buffer_access(
         &self, buffer: &BufferAccess, exclusive: bool, queue: &Queue)
@@ -741,15 +914,31 @@ err_gen!(CopyBufferToImageError {
     SyncCommandBufferBuilderError
 });
 
+err_gen!(CopyImageToBufferError {
+    AutoCommandBufferBuilderContextError,
+    CheckCopyImageToBufferError,
+    SyncCommandBufferBuilderError
+});
+
 err_gen!(FillBufferError {
     AutoCommandBufferBuilderContextError,
     CheckFillBufferError
 });
 
+err_gen!(GenerateMipmapsError {
+    AutoCommandBufferBuilderContextError,
+    CheckGenerateMipmapsError,
+    ComputePipelineCreationError,
+    OomError,
+    ReflectedShaderModuleCreationError,
+    SyncCommandBufferBuilderError
+});
+
 err_gen!(DispatchError {
     AutoCommandBufferBuilderContextError,
     CheckPushConstantsValidityError,
     CheckDescriptorSetsValidityError,
+    CheckDynamicOffsetsError,
     CheckDispatchError,
     SyncCommandBufferBuilderError
 });
@@ -759,6 +948,7 @@ err_gen!(DrawError {
     CheckDynamicStateValidityError,
     CheckPushConstantsValidityError,
     CheckDescriptorSetsValidityError,
+    CheckDynamicOffsetsError,
     CheckVertexBufferError,
     SyncCommandBufferBuilderError
 });
@@ -768,6 +958,7 @@ err_gen!(DrawIndexedError {
     CheckDynamicStateValidityError,
     CheckPushConstantsValidityError,
     CheckDescriptorSetsValidityError,
+    CheckDynamicOffsetsError,
     CheckVertexBufferError,
     CheckIndexBufferError,
     SyncCommandBufferBuilderError
@@ -778,6 +969,7 @@ err_gen!(DrawIndirectError {
     CheckDynamicStateValidityError,
     CheckPushConstantsValidityError,
     CheckDescriptorSetsValidityError,
+    CheckDynamicOffsetsError,
     CheckVertexBufferError,
     SyncCommandBufferBuilderError
 });
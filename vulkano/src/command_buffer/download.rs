@@ -0,0 +1,413 @@
+// Copyright (c) 2017 The vulkano developers
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>,
+// at your option. All files in the project carrying such
+// notice may not be copied, modified, or distributed except
+// according to those terms.
+
+//! Helpers that read back the content of a buffer or an image from the CPU.
+//!
+//! Reading the content of a resource that lives in device-local memory requires copying it to a
+//! buffer that the CPU can access, submitting the copy and waiting for it to be finished. The
+//! `download_buffer` and `download_image` functions do all of this and return a
+//! `DownloadFuture` whose `wait()` method returns the content.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use vulkano::command_buffer::download_buffer;
+//! # let buffer: std::sync::Arc<vulkano::buffer::DeviceLocalBuffer<[u32]>> = return;
+//! # let queue: std::sync::Arc<vulkano::device::Queue> = return;
+//! let content: Vec<u32> = download_buffer(buffer, queue).unwrap().wait(None).unwrap();
+//! ```
+
+use std::error;
+use std::fmt;
+use std::iter;
+use std::mem;
+use std::sync::Arc;
+use std::time::Duration;
+
+use buffer::BufferAccess;
+use buffer::BufferUsage;
+use buffer::CpuAccessibleBuffer;
+use buffer::TypedBufferAccess;
+use command_buffer::AutoCommandBuffer;
+use command_buffer::AutoCommandBufferBuilder;
+use command_buffer::CommandBuffer;
+use command_buffer::CommandBufferExecError;
+use command_buffer::CommandBufferExecFuture;
+use command_buffer::auto::BuildError;
+use command_buffer::auto::CopyBufferError;
+use command_buffer::auto::CopyImageToBufferError;
+use command_buffer::submit::SubmitAnyBuilder;
+use command_buffer::validity::CheckCopyImageToBufferError;
+use device::Device;
+use device::DeviceOwned;
+use device::Queue;
+use image::ImageAccess;
+use image::ImageLayout;
+use memory::Content;
+use sync::AccessCheckError;
+use sync::AccessFlagBits;
+use sync::Fence;
+use sync::FenceSignalFuture;
+use sync::FlushError;
+use sync::GpuFuture;
+use sync::NowFuture;
+use sync::PipelineStages;
+
+use OomError;
+
+/// Copies the content of `buffer` to a staging buffer on `queue`.
+///
+/// The returned future is already flushed. Call `wait()` on it to get the content.
+pub fn download_buffer<B, T>(buffer: B, queue: Arc<Queue>)
+                             -> Result<DownloadFuture<[T]>, DownloadError>
+    where B: TypedBufferAccess<Content = [T]> + Send + Sync + 'static,
+          T: Content + Clone + Send + Sync + 'static
+{
+    let device = queue.device().clone();
+    let len = buffer.size() / mem::size_of::<T>();
+
+    let staging = unsafe {
+        CpuAccessibleBuffer::<[T]>::uninitialized_array(device.clone(),
+                                                        len,
+                                                        BufferUsage::transfer_destination(),
+                                                        iter::once(queue.family()))?
+    };
+
+    let cb = AutoCommandBufferBuilder::new(device, queue.family())?
+        .copy_buffer(buffer, staging.clone())?
+        .build()?;
+
+    DownloadFuture::submit(cb, queue, staging)
+}
+
+/// Copies the first mipmap level of all the array layers of `image` to a staging buffer on
+/// `queue`.
+///
+/// The texels are tightly packed, layer after layer. The size of each layer is given by
+/// `format().region_size()`.
+///
+/// The returned future is already flushed. Call `wait()` on it to get the content.
+///
+/// Images whose format has both a depth and a stencil component can't be downloaded, as the
+/// layout of their texels depends on the implementation.
+pub fn download_image<I>(image: I, queue: Arc<Queue>)
+                         -> Result<DownloadFuture<[u8]>, DownloadError>
+    where I: ImageAccess + Send + Sync + 'static
+{
+    let device = queue.device().clone();
+
+    let dims = image.dimensions();
+    let size = dims.width_height_depth();
+    let layers = dims.array_layers();
+    if image.format().block_size().is_none() {
+        let err = CheckCopyImageToBufferError::DepthStencilFormat;
+        return Err(DownloadError::CopyImageToBufferError(err.into()));
+    }
+    let len = image.format().region_size(size).and_then(|len| len.checked_mul(layers as usize));
+    let len = match len {
+        Some(len) => len,
+        None => {
+            let err = CheckCopyImageToBufferError::OutOfRange;
+            return Err(DownloadError::CopyImageToBufferError(err.into()));
+        },
+    };
+
+    let staging = unsafe {
+        CpuAccessibleBuffer::<[u8]>::uninitialized_array(device.clone(),
+                                                         len,
+                                                         BufferUsage::transfer_destination(),
+                                                         iter::once(queue.family()))?
+    };
+
+    let cb = AutoCommandBufferBuilder::new(device, queue.family())?
+        .copy_image_to_buffer_dimensions(image,
+                                         staging.clone(),
+                                         [0, 0, 0],
+                                         size,
+                                         0,
+                                         layers,
+                                         0)?
+        .build()?;
+
+    DownloadFuture::submit(cb, queue, staging)
+}
+
+/// Represents the copy of a resource to a staging buffer.
+///
+/// Call `wait()` to block until the copy is finished and get the content.
+#[must_use = "Dropping this object will immediately block the thread until the GPU has finished \
+              processing the submission"]
+pub struct DownloadFuture<T: ?Sized> {
+    future: FenceSignalFuture<CommandBufferExecFuture<NowFuture, AutoCommandBuffer>>,
+    staging: Arc<CpuAccessibleBuffer<T>>,
+}
+
+impl<T: ?Sized> DownloadFuture<T>
+    where T: Send + Sync + 'static
+{
+    // Submits the command buffer that copies to `staging`.
+    fn submit(cb: AutoCommandBuffer, queue: Arc<Queue>, staging: Arc<CpuAccessibleBuffer<T>>)
+              -> Result<DownloadFuture<T>, DownloadError> {
+        let future = cb.execute(queue)?.then_signal_fence_and_flush()?;
+
+        Ok(DownloadFuture {
+               future: future,
+               staging: staging,
+           })
+    }
+
+    /// Returns the staging buffer that receives the content.
+    ///
+    /// Its content is only valid once the future is finished.
+    #[inline]
+    pub fn staging_buffer(&self) -> &Arc<CpuAccessibleBuffer<T>> {
+        &self.staging
+    }
+}
+
+impl<T> DownloadFuture<[T]>
+    where T: Content + Clone + Send + Sync + 'static
+{
+    /// Blocks the current thread until the copy is finished, then returns the content.
+    ///
+    /// If `timeout` is `None`, then the wait is infinite. Otherwise the thread will unblock after
+    /// the specified timeout has elapsed and an error will be returned. It is possible to call
+    /// this function again after an error.
+    pub fn wait(&self, timeout: Option<Duration>) -> Result<Vec<T>, FlushError> {
+        self.future.wait(timeout)?;
+        let content = self.staging
+            .read()
+            .expect("The staging buffer of a download can't be locked");
+        Ok(content.to_vec())
+    }
+}
+
+unsafe impl<T: ?Sized> GpuFuture for DownloadFuture<T> {
+    #[inline]
+    fn cleanup_finished(&mut self) {
+        self.future.cleanup_finished()
+    }
+
+    #[inline]
+    unsafe fn build_submission(&self) -> Result<SubmitAnyBuilder, FlushError> {
+        self.future.build_submission()
+    }
+
+    #[inline]
+    fn flush(&self) -> Result<(), FlushError> {
+        self.future.flush()
+    }
+
+    #[inline]
+    unsafe fn signal_finished(&self) {
+        self.future.signal_finished()
+    }
+
+    #[inline]
+    unsafe fn attach_fence(&self, fence: &Arc<Fence>) {
+        self.future.attach_fence(fence)
+    }
+
+    #[inline]
+    fn queue_change_allowed(&self) -> bool {
+        self.future.queue_change_allowed()
+    }
+
+    #[inline]
+    fn queue(&self) -> Option<Arc<Queue>> {
+        self.future.queue()
+    }
+
+    #[inline]
+    fn check_buffer_access(
+        &self, buffer: &BufferAccess, exclusive: bool, queue: &Queue)
+        -> Result<Option<(PipelineStages, AccessFlagBits)>, AccessCheckError> {
+        self.future.check_buffer_access(buffer, exclusive, queue)
+    }
+
+    #[inline]
+    fn check_image_access(&self, image: &ImageAccess, layout: ImageLayout, exclusive: bool,
+                          queue: &Queue)
+                          -> Result<Option<(PipelineStages, AccessFlagBits)>, AccessCheckError> {
+        self.future.check_image_access(image, layout, exclusive, queue)
+    }
+}
+
+unsafe impl<T: ?Sized> DeviceOwned for DownloadFuture<T> {
+    #[inline]
+    fn device(&self) -> &Arc<Device> {
+        self.future.device()
+    }
+}
+
+/// Error that can happen when downloading a resource.
+#[derive(Debug, Clone)]
+pub enum DownloadError {
+    /// Not enough memory to create the staging buffer or the command buffer.
+    OomError(OomError),
+    /// Error while recording the copy of a buffer.
+    CopyBufferError(CopyBufferError),
+    /// Error while recording the copy of an image.
+    CopyImageToBufferError(CopyImageToBufferError),
+    /// Error while building the command buffer.
+    BuildError(BuildError),
+    /// Error while executing the command buffer.
+    CommandBufferExecError(CommandBufferExecError),
+    /// Error while submitting the command buffer.
+    FlushError(FlushError),
+}
+
+impl error::Error for DownloadError {
+    #[inline]
+    fn description(&self) -> &str {
+        match *self {
+            DownloadError::OomError(_) => "not enough memory available",
+            DownloadError::CopyBufferError(_) => "error while recording the copy of a buffer",
+            DownloadError::CopyImageToBufferError(_) => {
+                "error while recording the copy of an image"
+            },
+            DownloadError::BuildError(_) => "error while building the command buffer",
+            DownloadError::CommandBufferExecError(_) => {
+                "error while executing the command buffer"
+            },
+            DownloadError::FlushError(_) => "error while submitting the command buffer",
+        }
+    }
+
+    #[inline]
+    fn cause(&self) -> Option<&error::Error> {
+        match *self {
+            DownloadError::OomError(ref err) => Some(err),
+            DownloadError::CopyBufferError(ref err) => Some(err),
+            DownloadError::CopyImageToBufferError(ref err) => Some(err),
+            DownloadError::BuildError(ref err) => Some(err),
+            DownloadError::CommandBufferExecError(ref err) => Some(err),
+            DownloadError::FlushError(ref err) => Some(err),
+        }
+    }
+}
+
+impl fmt::Display for DownloadError {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(fmt, "{}", error::Error::description(self))
+    }
+}
+
+impl From<OomError> for DownloadError {
+    #[inline]
+    fn from(err: OomError) -> DownloadError {
+        DownloadError::OomError(err)
+    }
+}
+
+impl From<CopyBufferError> for DownloadError {
+    #[inline]
+    fn from(err: CopyBufferError) -> DownloadError {
+        DownloadError::CopyBufferError(err)
+    }
+}
+
+impl From<CopyImageToBufferError> for DownloadError {
+    #[inline]
+    fn from(err: CopyImageToBufferError) -> DownloadError {
+        DownloadError::CopyImageToBufferError(err)
+    }
+}
+
+impl From<BuildError> for DownloadError {
+    #[inline]
+    fn from(err: BuildError) -> DownloadError {
+        DownloadError::BuildError(err)
+    }
+}
+
+impl From<CommandBufferExecError> for DownloadError {
+    #[inline]
+    fn from(err: CommandBufferExecError) -> DownloadError {
+        DownloadError::CommandBufferExecError(err)
+    }
+}
+
+impl From<FlushError> for DownloadError {
+    #[inline]
+    fn from(err: FlushError) -> DownloadError {
+        DownloadError::FlushError(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::iter;
+
+    use buffer::BufferAccess;
+    use buffer::BufferUsage;
+    use buffer::ImmutableBuffer;
+    use command_buffer::AutoCommandBufferBuilder;
+    use command_buffer::CommandBuffer;
+    use command_buffer::download_buffer;
+    use command_buffer::download_image;
+    use format::ClearValue;
+    use format::Format;
+    use image::AttachmentImage;
+    use image::ImageUsage;
+    use sync::GpuFuture;
+
+    #[test]
+    fn download_buffer_content() {
+        let (_, queue) = gfx_dev_and_queue!();
+
+        let (buffer, init) = ImmutableBuffer::from_iter((0 .. 128u32).map(|n| n * 3),
+                                                        BufferUsage::transfer_source(),
+                                                        iter::once(queue.family()),
+                                                        queue.clone())
+            .unwrap();
+        init.then_signal_fence_and_flush().unwrap().wait(None).unwrap();
+
+        let content = download_buffer(buffer, queue).unwrap().wait(None).unwrap();
+        assert_eq!(content, (0 .. 128u32).map(|n| n * 3).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn download_image_content() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let usage = ImageUsage {
+            transfer_source: true,
+            transfer_destination: true,
+            ..ImageUsage::none()
+        };
+        let image = AttachmentImage::with_usage(device.clone(),
+                                                [16, 8],
+                                                Format::R8G8B8A8Unorm,
+                                                usage)
+            .unwrap();
+
+        AutoCommandBufferBuilder::new(device.clone(), queue.family())
+            .unwrap()
+            .clear_color_image(image.clone(), ClearValue::Float([1.0, 0.0, 0.0, 1.0]))
+            .unwrap()
+            .build()
+            .unwrap()
+            .execute(queue.clone())
+            .unwrap()
+            .then_signal_fence_and_flush()
+            .unwrap()
+            .wait(None)
+            .unwrap();
+
+        let download = download_image(image, queue).unwrap();
+        assert_eq!(download.staging_buffer().size(), 16 * 8 * 4);
+
+        let content = download.wait(None).unwrap();
+        for texel in content.chunks(4) {
+            assert_eq!(texel, &[255, 0, 0, 255]);
+        }
+    }
+}
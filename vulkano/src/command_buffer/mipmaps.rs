@@ -0,0 +1,552 @@
+// Copyright (c) 2017 The vulkano developers
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>,
+// at your option. All files in the project carrying such
+// notice may not be copied, modified, or distributed except
+// according to those terms.
+
+//! Generation of the mipmap levels of an image from its first level.
+//!
+//! When the format supports it, each level is blitted into the next one with a linear filter.
+//! Each level is transitioned separately from `TransferDstOptimal` to `TransferSrcOptimal` once it
+//! has been written, so that all the levels end up in `TransferSrcOptimal`.
+//!
+//! Otherwise a compute shader reads level N as a sampled image, averages each block of 2x2
+//! texels and writes the result to level N+1 as a storage image. Every array layer is processed
+//! by the same dispatch. All the levels stay in the `General` layout during the whole operation.
+
+use std::iter;
+use std::sync::Arc;
+
+use command_buffer::auto::GenerateMipmapsError;
+use command_buffer::sys::UnsafeCommandBufferBuilder;
+use command_buffer::sys::UnsafeCommandBufferBuilderImageAspect;
+use command_buffer::sys::UnsafeCommandBufferBuilderImageBlit;
+use command_buffer::sys::UnsafeCommandBufferBuilderPipelineBarrier;
+use descriptor::descriptor_set::DescriptorPool;
+use descriptor::descriptor_set::DescriptorPoolAlloc;
+use descriptor::descriptor_set::DescriptorWrite;
+use descriptor::descriptor_set::StdDescriptorPoolAlloc;
+use descriptor::pipeline_layout::PipelineLayout;
+use descriptor::pipeline_layout::PipelineLayoutAbstract;
+use device::Device;
+use image::Dimensions;
+use image::ImageAccess;
+use image::ImageLayout;
+use image::ViewType;
+use image::sys::UnsafeImageView;
+use image::traits::ImageViewAccess;
+use pipeline::ComputePipeline;
+use pipeline::shader::reflect::ReflectedShaderModule;
+use pipeline::shader::reflect::RuntimePipelineLayoutDesc;
+use sampler::Filter;
+use sync::AccessFlagBits;
+use sync::PipelineStages;
+
+/// How the mipmaps of an image are generated.
+pub(crate) enum MipmapsMethod {
+    /// Blits with a linear filter. See `record_blits`.
+    Blit,
+    /// Compute shader. See `ComputeDownsample`.
+    Compute(ComputeDownsample),
+}
+
+/// Records the blits that generate the mipmap levels of `image`, and the barriers between them.
+///
+/// All the mipmap levels of the image must be in the `TransferDstOptimal` layout, and the content
+/// of the first level must be visible to the transfer stage. All the levels end up in the
+/// `TransferSrcOptimal` layout.
+///
+/// # Safety
+///
+/// - The format of the image must support linear blits.
+///
+pub(crate) unsafe fn record_blits<P, I>(out: &mut UnsafeCommandBufferBuilder<P>, image: &I)
+    where I: ?Sized + ImageAccess
+{
+    let dims = image.dimensions();
+    let layers = dims.array_layers();
+    let levels = image.mipmap_levels();
+
+    let transfer = PipelineStages {
+        transfer: true,
+        ..PipelineStages::none()
+    };
+
+    for level in 0 .. levels {
+        // The level has been written, and is now going to be read by the next blit.
+        let mut barrier = UnsafeCommandBufferBuilderPipelineBarrier::new();
+        barrier.add_image_memory_barrier(image,
+                                         level .. level + 1,
+                                         0 .. layers,
+                                         transfer,
+                                         AccessFlagBits {
+                                             transfer_write: true,
+                                             ..AccessFlagBits::none()
+                                         },
+                                         transfer,
+                                         AccessFlagBits {
+                                             transfer_read: true,
+                                             ..AccessFlagBits::none()
+                                         },
+                                         true,
+                                         None,
+                                         ImageLayout::TransferDstOptimal,
+                                         ImageLayout::TransferSrcOptimal);
+        out.pipeline_barrier(&barrier);
+
+        if level + 1 == levels {
+            break;
+        }
+
+        let src = dims.mipmap_dimensions(level).unwrap().width_height_depth();
+        let dst = dims.mipmap_dimensions(level + 1).unwrap().width_height_depth();
+
+        let blit = UnsafeCommandBufferBuilderImageBlit {
+            aspect: UnsafeCommandBufferBuilderImageAspect {
+                color: true,
+                depth: false,
+                stencil: false,
+            },
+            source_mip_level: level,
+            destination_mip_level: level + 1,
+            source_base_array_layer: 0,
+            destination_base_array_layer: 0,
+            layer_count: layers,
+            source_top_left: [0, 0, 0],
+            source_bottom_right: [src[0] as i32, src[1] as i32, src[2] as i32],
+            destination_top_left: [0, 0, 0],
+            destination_bottom_right: [dst[0] as i32, dst[1] as i32, dst[2] as i32],
+        };
+
+        out.blit_image(image,
+                       ImageLayout::TransferSrcOptimal,
+                       image,
+                       ImageLayout::TransferDstOptimal,
+                       iter::once(blit),
+                       Filter::Linear);
+    }
+}
+
+/// Resources needed to generate the mipmaps of an image with a compute shader.
+///
+/// Must be kept alive until the command buffer that uses it has finished executing.
+pub(crate) struct ComputeDownsample {
+    // One view per mipmap level.
+    views: Vec<UnsafeImageView>,
+    // One descriptor set per destination level, ie. `sets[n]` reads level `n` and writes level
+    // `n + 1`.
+    sets: Vec<StdDescriptorPoolAlloc>,
+    pipeline: Arc<DownsamplePipeline>,
+}
+
+/// Compute pipeline that downsamples a mipmap level into the next one.
+pub(crate) type DownsamplePipeline = ComputePipeline<PipelineLayout<RuntimePipelineLayoutDesc>>;
+
+impl ComputeDownsample {
+    /// Builds the pipeline, the views and the descriptor sets for the given image.
+    ///
+    /// The image must be a two-dimensional image with the `sampled` and `storage` usages, and
+    /// the `shader_storage_image_write_without_format` feature must be enabled on the device.
+    pub(crate) fn new<I>(image: &I) -> Result<ComputeDownsample, GenerateMipmapsError>
+        where I: ImageAccess
+    {
+        let inner = image.inner();
+        let device = inner.image.device();
+        let layers = image.dimensions().array_layers();
+
+        let pipeline = downsample_pipeline(device)?;
+
+        let views = (0 .. image.mipmap_levels())
+            .map(|level| unsafe {
+                     UnsafeImageView::raw(inner.image,
+                                          ViewType::Dim2dArray,
+                                          inner.first_mipmap_level as u32 + level ..
+                                              inner.first_mipmap_level as u32 + level + 1,
+                                          inner.first_layer as u32 ..
+                                              inner.first_layer as u32 + layers)
+                 })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let set_layout = pipeline.descriptor_set_layout(0).unwrap();
+        let pool = Device::standard_descriptor_pool(device);
+        let mut sets = Vec::with_capacity(views.len().saturating_sub(1));
+        for level in 1 .. views.len() {
+            let mut set = DescriptorPool::alloc(&pool, set_layout)?;
+            unsafe {
+                let source = LevelView {
+                    image: image,
+                    view: &views[level - 1],
+                };
+                let destination = LevelView {
+                    image: image,
+                    view: &views[level],
+                };
+                let writes = vec![
+                    DescriptorWrite::sampled_image(0, 0, &source),
+                    DescriptorWrite::storage_image(1, 0, &destination),
+                ];
+                set.inner_mut().write(device, writes.into_iter());
+            }
+            sets.push(set);
+        }
+
+        Ok(ComputeDownsample {
+               views: views,
+               sets: sets,
+               pipeline: pipeline,
+           })
+    }
+
+    /// Records the dispatches and the barriers between them.
+    ///
+    /// All the mipmap levels of the image must be in the `General` layout, and the content of the
+    /// first level must be visible to the compute shader stage.
+    ///
+    /// # Safety
+    ///
+    /// - `image` must be the image that was passed to `new`.
+    /// - The compute pipeline and descriptor sets bound on `out` are modified.
+    ///
+    pub(crate) unsafe fn record<P, I>(&self, out: &mut UnsafeCommandBufferBuilder<P>, image: &I)
+        where I: ?Sized + ImageAccess
+    {
+        let dims = image.dimensions();
+        let layers = dims.array_layers();
+
+        out.bind_pipeline_compute(&self.pipeline);
+
+        for (num, set) in self.sets.iter().enumerate() {
+            let level = num as u32 + 1;
+
+            // Wait for the previous dispatch to finish writing the level that we read from.
+            if level >= 2 {
+                let stages = PipelineStages {
+                    compute_shader: true,
+                    ..PipelineStages::none()
+                };
+
+                let mut barrier = UnsafeCommandBufferBuilderPipelineBarrier::new();
+                barrier.add_image_memory_barrier(image,
+                                                 level - 1 .. level,
+                                                 0 .. layers,
+                                                 stages,
+                                                 AccessFlagBits {
+                                                     shader_write: true,
+                                                     ..AccessFlagBits::none()
+                                                 },
+                                                 stages,
+                                                 AccessFlagBits {
+                                                     shader_read: true,
+                                                     ..AccessFlagBits::none()
+                                                 },
+                                                 true,
+                                                 None,
+                                                 ImageLayout::General,
+                                                 ImageLayout::General);
+                out.pipeline_barrier(&barrier);
+            }
+
+            out.bind_descriptor_sets(false,
+                                     &self.pipeline,
+                                     0,
+                                     iter::once(set.inner()),
+                                     iter::empty());
+
+            let extent = dims.mipmap_dimensions(level).unwrap().width_height();
+            out.dispatch([(extent[0] + 7) / 8, (extent[1] + 7) / 8, layers]);
+        }
+    }
+}
+
+// Gives access to a single mipmap level of the image, in order to write it to a descriptor set.
+struct LevelView<'a> {
+    image: &'a ImageAccess,
+    view: &'a UnsafeImageView,
+}
+
+unsafe impl<'a> ImageViewAccess for LevelView<'a> {
+    #[inline]
+    fn parent(&self) -> &ImageAccess {
+        self.image
+    }
+
+    #[inline]
+    fn dimensions(&self) -> Dimensions {
+        let dims = self.image.dimensions();
+        Dimensions::Dim2dArray {
+            width: dims.width(),
+            height: dims.height(),
+            array_layers: dims.array_layers(),
+        }
+    }
+
+    #[inline]
+    fn inner(&self) -> &UnsafeImageView {
+        self.view
+    }
+
+    #[inline]
+    fn descriptor_set_storage_image_layout(&self) -> ImageLayout {
+        ImageLayout::General
+    }
+
+    #[inline]
+    fn descriptor_set_combined_image_sampler_layout(&self) -> ImageLayout {
+        ImageLayout::General
+    }
+
+    #[inline]
+    fn descriptor_set_sampled_image_layout(&self) -> ImageLayout {
+        ImageLayout::General
+    }
+
+    #[inline]
+    fn descriptor_set_input_attachment_layout(&self) -> ImageLayout {
+        ImageLayout::General
+    }
+
+    #[inline]
+    fn identity_swizzle(&self) -> bool {
+        true
+    }
+}
+
+// Returns the downsample pipeline of the device, creating it if necessary.
+//
+// Like the samplers created with `build_cached`, the device only holds a weak reference to the
+// pipeline, which is kept alive by the `ComputeDownsample` objects that use it.
+fn downsample_pipeline(device: &Arc<Device>)
+                       -> Result<Arc<DownsamplePipeline>, GenerateMipmapsError> {
+    let mut cache = device.downsample_pipeline_cache().lock().unwrap();
+
+    if let Some(pipeline) = cache.upgrade() {
+        return Ok(pipeline);
+    }
+
+    let module = ReflectedShaderModule::new(device.clone(), DOWNSAMPLE_SPIRV)?;
+    let entry_point = module
+        .compute_shader_entry_point("main")
+        .expect("the downsample shader has no main entry point");
+    let pipeline = Arc::new(ComputePipeline::new(device.clone(), &entry_point, &())?);
+
+    *cache = Arc::downgrade(&pipeline);
+    Ok(pipeline)
+}
+
+// SPIR-V code of the downsample compute shader. It is equivalent to the following GLSL code:
+//
+// #version 450
+// #extension GL_EXT_samplerless_texture_functions : require
+//
+// layout(local_size_x = 8, local_size_y = 8, local_size_z = 1) in;
+//
+// layout(set = 0, binding = 0) uniform texture2DArray src;
+// layout(set = 0, binding = 1) writeonly uniform image2DArray dst;
+//
+// void main() {
+//     ivec2 id = ivec2(gl_GlobalInvocationID.xy);
+//     ivec2 dst_size = imageSize(dst).xy;
+//     if (id.x >= dst_size.x || id.y >= dst_size.y) {
+//         return;
+//     }
+//
+//     int layer = int(gl_GlobalInvocationID.z);
+//     ivec2 src_max = textureSize(src, 0).xy - ivec2(1, 1);
+//     ivec2 base = id * 2;
+//     ivec2 far = min(base + ivec2(1, 1), src_max);
+//
+//     vec4 sum = texelFetch(src, ivec3(base, layer), 0)
+//              + texelFetch(src, ivec3(far.x, base.y, layer), 0)
+//              + texelFetch(src, ivec3(base.x, far.y, layer), 0)
+//              + texelFetch(src, ivec3(far, layer), 0);
+//     imageStore(dst, ivec3(id, layer), sum * 0.25);
+// }
+const DOWNSAMPLE_SPIRV: &'static [u8] = &[
+    0x03, 0x02, 0x23, 0x07, 0x00, 0x00, 0x01, 0x00, 0x1c, 0x00, 0x00, 0x00,
+    0x54, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x11, 0x00, 0x02, 0x00,
+    0x38, 0x00, 0x00, 0x00, 0x11, 0x00, 0x02, 0x00, 0x01, 0x00, 0x00, 0x00,
+    0x11, 0x00, 0x02, 0x00, 0x32, 0x00, 0x00, 0x00, 0x0b, 0x00, 0x06, 0x00,
+    0x01, 0x00, 0x00, 0x00, 0x47, 0x4c, 0x53, 0x4c, 0x2e, 0x73, 0x74, 0x64,
+    0x2e, 0x34, 0x35, 0x30, 0x00, 0x00, 0x00, 0x00, 0x0e, 0x00, 0x03, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x0f, 0x00, 0x06, 0x00,
+    0x05, 0x00, 0x00, 0x00, 0x13, 0x00, 0x00, 0x00, 0x6d, 0x61, 0x69, 0x6e,
+    0x00, 0x00, 0x00, 0x00, 0x10, 0x00, 0x00, 0x00, 0x10, 0x00, 0x06, 0x00,
+    0x13, 0x00, 0x00, 0x00, 0x11, 0x00, 0x00, 0x00, 0x08, 0x00, 0x00, 0x00,
+    0x08, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x47, 0x00, 0x04, 0x00,
+    0x0b, 0x00, 0x00, 0x00, 0x22, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x47, 0x00, 0x04, 0x00, 0x0b, 0x00, 0x00, 0x00, 0x21, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x47, 0x00, 0x03, 0x00, 0x0d, 0x00, 0x00, 0x00,
+    0x19, 0x00, 0x00, 0x00, 0x47, 0x00, 0x04, 0x00, 0x0d, 0x00, 0x00, 0x00,
+    0x22, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x47, 0x00, 0x04, 0x00,
+    0x0d, 0x00, 0x00, 0x00, 0x21, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00,
+    0x47, 0x00, 0x04, 0x00, 0x10, 0x00, 0x00, 0x00, 0x0b, 0x00, 0x00, 0x00,
+    0x1c, 0x00, 0x00, 0x00, 0x13, 0x00, 0x02, 0x00, 0x02, 0x00, 0x00, 0x00,
+    0x16, 0x00, 0x03, 0x00, 0x04, 0x00, 0x00, 0x00, 0x20, 0x00, 0x00, 0x00,
+    0x19, 0x00, 0x09, 0x00, 0x03, 0x00, 0x00, 0x00, 0x04, 0x00, 0x00, 0x00,
+    0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x19, 0x00, 0x09, 0x00, 0x05, 0x00, 0x00, 0x00, 0x04, 0x00, 0x00, 0x00,
+    0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x15, 0x00, 0x04, 0x00, 0x07, 0x00, 0x00, 0x00, 0x20, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x17, 0x00, 0x04, 0x00, 0x06, 0x00, 0x00, 0x00,
+    0x07, 0x00, 0x00, 0x00, 0x03, 0x00, 0x00, 0x00, 0x14, 0x00, 0x02, 0x00,
+    0x08, 0x00, 0x00, 0x00, 0x15, 0x00, 0x04, 0x00, 0x0a, 0x00, 0x00, 0x00,
+    0x20, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x17, 0x00, 0x04, 0x00,
+    0x09, 0x00, 0x00, 0x00, 0x0a, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00,
+    0x20, 0x00, 0x04, 0x00, 0x0c, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x03, 0x00, 0x00, 0x00, 0x3b, 0x00, 0x04, 0x00, 0x0c, 0x00, 0x00, 0x00,
+    0x0b, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x20, 0x00, 0x04, 0x00,
+    0x0e, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x05, 0x00, 0x00, 0x00,
+    0x3b, 0x00, 0x04, 0x00, 0x0e, 0x00, 0x00, 0x00, 0x0d, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x20, 0x00, 0x04, 0x00, 0x11, 0x00, 0x00, 0x00,
+    0x01, 0x00, 0x00, 0x00, 0x06, 0x00, 0x00, 0x00, 0x3b, 0x00, 0x04, 0x00,
+    0x11, 0x00, 0x00, 0x00, 0x10, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00,
+    0x21, 0x00, 0x03, 0x00, 0x14, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00,
+    0x29, 0x00, 0x03, 0x00, 0x08, 0x00, 0x00, 0x00, 0x17, 0x00, 0x00, 0x00,
+    0x2b, 0x00, 0x04, 0x00, 0x0a, 0x00, 0x00, 0x00, 0x18, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x2b, 0x00, 0x04, 0x00, 0x0a, 0x00, 0x00, 0x00,
+    0x19, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x2c, 0x00, 0x05, 0x00,
+    0x09, 0x00, 0x00, 0x00, 0x1a, 0x00, 0x00, 0x00, 0x19, 0x00, 0x00, 0x00,
+    0x19, 0x00, 0x00, 0x00, 0x2b, 0x00, 0x04, 0x00, 0x0a, 0x00, 0x00, 0x00,
+    0x1b, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0x2b, 0x00, 0x04, 0x00,
+    0x04, 0x00, 0x00, 0x00, 0x1c, 0x00, 0x00, 0x00, 0x00, 0x00, 0x80, 0x3e,
+    0x20, 0x00, 0x04, 0x00, 0x1e, 0x00, 0x00, 0x00, 0x07, 0x00, 0x00, 0x00,
+    0x08, 0x00, 0x00, 0x00, 0x2e, 0x00, 0x03, 0x00, 0x08, 0x00, 0x00, 0x00,
+    0x1f, 0x00, 0x00, 0x00, 0x17, 0x00, 0x04, 0x00, 0x21, 0x00, 0x00, 0x00,
+    0x07, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0x17, 0x00, 0x04, 0x00,
+    0x3d, 0x00, 0x00, 0x00, 0x04, 0x00, 0x00, 0x00, 0x04, 0x00, 0x00, 0x00,
+    0x17, 0x00, 0x04, 0x00, 0x3e, 0x00, 0x00, 0x00, 0x0a, 0x00, 0x00, 0x00,
+    0x03, 0x00, 0x00, 0x00, 0x36, 0x00, 0x05, 0x00, 0x02, 0x00, 0x00, 0x00,
+    0x13, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x14, 0x00, 0x00, 0x00,
+    0xf8, 0x00, 0x02, 0x00, 0x0f, 0x00, 0x00, 0x00, 0x3b, 0x00, 0x05, 0x00,
+    0x1e, 0x00, 0x00, 0x00, 0x1d, 0x00, 0x00, 0x00, 0x07, 0x00, 0x00, 0x00,
+    0x1f, 0x00, 0x00, 0x00, 0x3d, 0x00, 0x04, 0x00, 0x06, 0x00, 0x00, 0x00,
+    0x12, 0x00, 0x00, 0x00, 0x10, 0x00, 0x00, 0x00, 0x3d, 0x00, 0x04, 0x00,
+    0x03, 0x00, 0x00, 0x00, 0x15, 0x00, 0x00, 0x00, 0x0b, 0x00, 0x00, 0x00,
+    0x3d, 0x00, 0x04, 0x00, 0x05, 0x00, 0x00, 0x00, 0x16, 0x00, 0x00, 0x00,
+    0x0d, 0x00, 0x00, 0x00, 0xf9, 0x00, 0x02, 0x00, 0x20, 0x00, 0x00, 0x00,
+    0xf8, 0x00, 0x02, 0x00, 0x20, 0x00, 0x00, 0x00, 0x68, 0x00, 0x04, 0x00,
+    0x06, 0x00, 0x00, 0x00, 0x22, 0x00, 0x00, 0x00, 0x16, 0x00, 0x00, 0x00,
+    0x4f, 0x00, 0x07, 0x00, 0x21, 0x00, 0x00, 0x00, 0x23, 0x00, 0x00, 0x00,
+    0x22, 0x00, 0x00, 0x00, 0x22, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x01, 0x00, 0x00, 0x00, 0x51, 0x00, 0x05, 0x00, 0x07, 0x00, 0x00, 0x00,
+    0x24, 0x00, 0x00, 0x00, 0x12, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x51, 0x00, 0x05, 0x00, 0x07, 0x00, 0x00, 0x00, 0x25, 0x00, 0x00, 0x00,
+    0x23, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xae, 0x00, 0x05, 0x00,
+    0x08, 0x00, 0x00, 0x00, 0x26, 0x00, 0x00, 0x00, 0x24, 0x00, 0x00, 0x00,
+    0x25, 0x00, 0x00, 0x00, 0xa8, 0x00, 0x04, 0x00, 0x08, 0x00, 0x00, 0x00,
+    0x27, 0x00, 0x00, 0x00, 0x26, 0x00, 0x00, 0x00, 0xf7, 0x00, 0x03, 0x00,
+    0x28, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xfa, 0x00, 0x04, 0x00,
+    0x27, 0x00, 0x00, 0x00, 0x29, 0x00, 0x00, 0x00, 0x2a, 0x00, 0x00, 0x00,
+    0xf8, 0x00, 0x02, 0x00, 0x29, 0x00, 0x00, 0x00, 0x51, 0x00, 0x05, 0x00,
+    0x07, 0x00, 0x00, 0x00, 0x2b, 0x00, 0x00, 0x00, 0x12, 0x00, 0x00, 0x00,
+    0x01, 0x00, 0x00, 0x00, 0x51, 0x00, 0x05, 0x00, 0x07, 0x00, 0x00, 0x00,
+    0x2c, 0x00, 0x00, 0x00, 0x23, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00,
+    0xae, 0x00, 0x05, 0x00, 0x08, 0x00, 0x00, 0x00, 0x2d, 0x00, 0x00, 0x00,
+    0x2b, 0x00, 0x00, 0x00, 0x2c, 0x00, 0x00, 0x00, 0x3e, 0x00, 0x03, 0x00,
+    0x1d, 0x00, 0x00, 0x00, 0x2d, 0x00, 0x00, 0x00, 0xf9, 0x00, 0x02, 0x00,
+    0x28, 0x00, 0x00, 0x00, 0xf8, 0x00, 0x02, 0x00, 0x2a, 0x00, 0x00, 0x00,
+    0x3e, 0x00, 0x03, 0x00, 0x1d, 0x00, 0x00, 0x00, 0x17, 0x00, 0x00, 0x00,
+    0xf9, 0x00, 0x02, 0x00, 0x28, 0x00, 0x00, 0x00, 0xf8, 0x00, 0x02, 0x00,
+    0x28, 0x00, 0x00, 0x00, 0x3d, 0x00, 0x04, 0x00, 0x08, 0x00, 0x00, 0x00,
+    0x2e, 0x00, 0x00, 0x00, 0x1d, 0x00, 0x00, 0x00, 0xf7, 0x00, 0x03, 0x00,
+    0x2f, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xfa, 0x00, 0x04, 0x00,
+    0x2e, 0x00, 0x00, 0x00, 0x30, 0x00, 0x00, 0x00, 0x2f, 0x00, 0x00, 0x00,
+    0xf8, 0x00, 0x02, 0x00, 0x30, 0x00, 0x00, 0x00, 0xfd, 0x00, 0x01, 0x00,
+    0xf8, 0x00, 0x02, 0x00, 0x2f, 0x00, 0x00, 0x00, 0x67, 0x00, 0x05, 0x00,
+    0x06, 0x00, 0x00, 0x00, 0x31, 0x00, 0x00, 0x00, 0x15, 0x00, 0x00, 0x00,
+    0x18, 0x00, 0x00, 0x00, 0x4f, 0x00, 0x07, 0x00, 0x21, 0x00, 0x00, 0x00,
+    0x32, 0x00, 0x00, 0x00, 0x31, 0x00, 0x00, 0x00, 0x31, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x7c, 0x00, 0x04, 0x00,
+    0x09, 0x00, 0x00, 0x00, 0x33, 0x00, 0x00, 0x00, 0x32, 0x00, 0x00, 0x00,
+    0x82, 0x00, 0x05, 0x00, 0x09, 0x00, 0x00, 0x00, 0x34, 0x00, 0x00, 0x00,
+    0x33, 0x00, 0x00, 0x00, 0x1a, 0x00, 0x00, 0x00, 0x4f, 0x00, 0x07, 0x00,
+    0x21, 0x00, 0x00, 0x00, 0x35, 0x00, 0x00, 0x00, 0x12, 0x00, 0x00, 0x00,
+    0x12, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00,
+    0x7c, 0x00, 0x04, 0x00, 0x09, 0x00, 0x00, 0x00, 0x36, 0x00, 0x00, 0x00,
+    0x35, 0x00, 0x00, 0x00, 0x50, 0x00, 0x05, 0x00, 0x09, 0x00, 0x00, 0x00,
+    0x38, 0x00, 0x00, 0x00, 0x1b, 0x00, 0x00, 0x00, 0x1b, 0x00, 0x00, 0x00,
+    0x84, 0x00, 0x05, 0x00, 0x09, 0x00, 0x00, 0x00, 0x37, 0x00, 0x00, 0x00,
+    0x36, 0x00, 0x00, 0x00, 0x38, 0x00, 0x00, 0x00, 0x80, 0x00, 0x05, 0x00,
+    0x09, 0x00, 0x00, 0x00, 0x39, 0x00, 0x00, 0x00, 0x37, 0x00, 0x00, 0x00,
+    0x1a, 0x00, 0x00, 0x00, 0x0c, 0x00, 0x07, 0x00, 0x09, 0x00, 0x00, 0x00,
+    0x3a, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x27, 0x00, 0x00, 0x00,
+    0x39, 0x00, 0x00, 0x00, 0x34, 0x00, 0x00, 0x00, 0x51, 0x00, 0x05, 0x00,
+    0x07, 0x00, 0x00, 0x00, 0x3b, 0x00, 0x00, 0x00, 0x12, 0x00, 0x00, 0x00,
+    0x02, 0x00, 0x00, 0x00, 0x7c, 0x00, 0x04, 0x00, 0x0a, 0x00, 0x00, 0x00,
+    0x3c, 0x00, 0x00, 0x00, 0x3b, 0x00, 0x00, 0x00, 0x50, 0x00, 0x05, 0x00,
+    0x3e, 0x00, 0x00, 0x00, 0x3f, 0x00, 0x00, 0x00, 0x37, 0x00, 0x00, 0x00,
+    0x3c, 0x00, 0x00, 0x00, 0x5f, 0x00, 0x07, 0x00, 0x3d, 0x00, 0x00, 0x00,
+    0x40, 0x00, 0x00, 0x00, 0x15, 0x00, 0x00, 0x00, 0x3f, 0x00, 0x00, 0x00,
+    0x02, 0x00, 0x00, 0x00, 0x18, 0x00, 0x00, 0x00, 0x51, 0x00, 0x05, 0x00,
+    0x0a, 0x00, 0x00, 0x00, 0x41, 0x00, 0x00, 0x00, 0x3a, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x51, 0x00, 0x05, 0x00, 0x0a, 0x00, 0x00, 0x00,
+    0x42, 0x00, 0x00, 0x00, 0x37, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00,
+    0x50, 0x00, 0x05, 0x00, 0x09, 0x00, 0x00, 0x00, 0x43, 0x00, 0x00, 0x00,
+    0x41, 0x00, 0x00, 0x00, 0x42, 0x00, 0x00, 0x00, 0x50, 0x00, 0x05, 0x00,
+    0x3e, 0x00, 0x00, 0x00, 0x44, 0x00, 0x00, 0x00, 0x43, 0x00, 0x00, 0x00,
+    0x3c, 0x00, 0x00, 0x00, 0x5f, 0x00, 0x07, 0x00, 0x3d, 0x00, 0x00, 0x00,
+    0x45, 0x00, 0x00, 0x00, 0x15, 0x00, 0x00, 0x00, 0x44, 0x00, 0x00, 0x00,
+    0x02, 0x00, 0x00, 0x00, 0x18, 0x00, 0x00, 0x00, 0x81, 0x00, 0x05, 0x00,
+    0x3d, 0x00, 0x00, 0x00, 0x46, 0x00, 0x00, 0x00, 0x40, 0x00, 0x00, 0x00,
+    0x45, 0x00, 0x00, 0x00, 0x51, 0x00, 0x05, 0x00, 0x0a, 0x00, 0x00, 0x00,
+    0x47, 0x00, 0x00, 0x00, 0x37, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x51, 0x00, 0x05, 0x00, 0x0a, 0x00, 0x00, 0x00, 0x48, 0x00, 0x00, 0x00,
+    0x3a, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x50, 0x00, 0x05, 0x00,
+    0x09, 0x00, 0x00, 0x00, 0x49, 0x00, 0x00, 0x00, 0x47, 0x00, 0x00, 0x00,
+    0x48, 0x00, 0x00, 0x00, 0x50, 0x00, 0x05, 0x00, 0x3e, 0x00, 0x00, 0x00,
+    0x4a, 0x00, 0x00, 0x00, 0x49, 0x00, 0x00, 0x00, 0x3c, 0x00, 0x00, 0x00,
+    0x5f, 0x00, 0x07, 0x00, 0x3d, 0x00, 0x00, 0x00, 0x4b, 0x00, 0x00, 0x00,
+    0x15, 0x00, 0x00, 0x00, 0x4a, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00,
+    0x18, 0x00, 0x00, 0x00, 0x81, 0x00, 0x05, 0x00, 0x3d, 0x00, 0x00, 0x00,
+    0x4c, 0x00, 0x00, 0x00, 0x46, 0x00, 0x00, 0x00, 0x4b, 0x00, 0x00, 0x00,
+    0x50, 0x00, 0x05, 0x00, 0x3e, 0x00, 0x00, 0x00, 0x4d, 0x00, 0x00, 0x00,
+    0x3a, 0x00, 0x00, 0x00, 0x3c, 0x00, 0x00, 0x00, 0x5f, 0x00, 0x07, 0x00,
+    0x3d, 0x00, 0x00, 0x00, 0x4e, 0x00, 0x00, 0x00, 0x15, 0x00, 0x00, 0x00,
+    0x4d, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0x18, 0x00, 0x00, 0x00,
+    0x81, 0x00, 0x05, 0x00, 0x3d, 0x00, 0x00, 0x00, 0x4f, 0x00, 0x00, 0x00,
+    0x4c, 0x00, 0x00, 0x00, 0x4e, 0x00, 0x00, 0x00, 0x4f, 0x00, 0x07, 0x00,
+    0x21, 0x00, 0x00, 0x00, 0x50, 0x00, 0x00, 0x00, 0x12, 0x00, 0x00, 0x00,
+    0x12, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00,
+    0x7c, 0x00, 0x04, 0x00, 0x09, 0x00, 0x00, 0x00, 0x51, 0x00, 0x00, 0x00,
+    0x50, 0x00, 0x00, 0x00, 0x8e, 0x00, 0x05, 0x00, 0x3d, 0x00, 0x00, 0x00,
+    0x52, 0x00, 0x00, 0x00, 0x4f, 0x00, 0x00, 0x00, 0x1c, 0x00, 0x00, 0x00,
+    0x50, 0x00, 0x05, 0x00, 0x3e, 0x00, 0x00, 0x00, 0x53, 0x00, 0x00, 0x00,
+    0x51, 0x00, 0x00, 0x00, 0x3c, 0x00, 0x00, 0x00, 0x63, 0x00, 0x04, 0x00,
+    0x16, 0x00, 0x00, 0x00, 0x53, 0x00, 0x00, 0x00, 0x52, 0x00, 0x00, 0x00,
+    0xfd, 0x00, 0x01, 0x00, 0x38, 0x00, 0x01, 0x00,
+];
+
+#[cfg(test)]
+mod tests {
+    use descriptor::descriptor::DescriptorDescTy;
+    use descriptor::pipeline_layout::PipelineLayoutDesc;
+    use pipeline::shader::reflect::EntryPointType;
+    use pipeline::shader::reflect::ShaderReflection;
+    use super::DOWNSAMPLE_SPIRV;
+
+    #[test]
+    fn downsample_shader_reflection() {
+        let reflection = ShaderReflection::new(DOWNSAMPLE_SPIRV).unwrap();
+        assert!(reflection.required_features().shader_storage_image_write_without_format);
+
+        let entry_point = reflection.entry_point("main").unwrap();
+        assert_eq!(entry_point.ty(), EntryPointType::Compute);
+
+        let layout = entry_point.layout();
+        assert_eq!(layout.num_sets(), 1);
+        assert_eq!(layout.num_bindings_in_set(0), Some(2));
+        match layout.descriptor(0, 0).unwrap().ty {
+            DescriptorDescTy::Image(ref desc) => assert!(desc.sampled),
+            _ => panic!(),
+        }
+        match layout.descriptor(0, 1).unwrap().ty {
+            DescriptorDescTy::Image(ref desc) => assert!(!desc.sampled),
+            _ => panic!(),
+        }
+    }
+}
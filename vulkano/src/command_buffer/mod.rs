@@ -75,6 +75,10 @@
 
 pub use self::auto::AutoCommandBuffer;
 pub use self::auto::AutoCommandBufferBuilder;
+pub use self::download::DownloadError;
+pub use self::download::DownloadFuture;
+pub use self::download::download_buffer;
+pub use self::download::download_image;
 pub use self::state_cacher::StateCacher;
 pub use self::state_cacher::StateCacherOutcome;
 pub use self::traits::CommandBuffer;
@@ -82,6 +86,11 @@ pub use self::traits::CommandBufferBuild;
 pub use self::traits::CommandBufferExecError;
 pub use self::traits::CommandBufferExecFuture;
 
+pub(crate) use self::auto::BuildError;
+pub(crate) use self::auto::CopyBufferToImageError;
+pub(crate) use self::auto::GenerateMipmapsError;
+pub(crate) use self::mipmaps::DownsamplePipeline;
+
 use pipeline::viewport::Scissor;
 use pipeline::viewport::Viewport;
 
@@ -92,6 +101,8 @@ pub mod sys;
 pub mod validity;
 
 mod auto;
+mod download;
+mod mipmaps;
 mod state_cacher;
 mod traits;
 
@@ -96,7 +96,7 @@ impl UnsafeCommandPool {
             let mut output = mem::uninitialized();
             check_errors(vk.CreateCommandPool(device.internal_object(),
                                               &infos,
-                                              ptr::null(),
+                                              device.allocation_callbacks().as_ptr(),
                                               &mut output))?;
             output
         };
@@ -236,7 +236,9 @@ impl Drop for UnsafeCommandPool {
     fn drop(&mut self) {
         unsafe {
             let vk = self.device.pointers();
-            vk.DestroyCommandPool(self.device.internal_object(), self.pool, ptr::null());
+            vk.DestroyCommandPool(self.device.internal_object(),
+                                  self.pool,
+                                  self.device.allocation_callbacks().as_ptr());
         }
     }
 }
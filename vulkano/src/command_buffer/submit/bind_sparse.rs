@@ -14,6 +14,7 @@ use std::marker::PhantomData;
 use std::ptr;
 
 use buffer::sys::UnsafeBuffer;
+use command_buffer::sys::UnsafeCommandBufferBuilderImageAspect;
 use device::Queue;
 use image::sys::UnsafeImage;
 use memory::DeviceMemory;
@@ -136,6 +137,25 @@ impl<'a> SubmitBindSparseBuilder<'a> {
         Ok(())
     }
 
+    /// Merges all the batches of this builder into a single batch.
+    ///
+    /// The resulting batch waits for the wait semaphores of all the batches before starting, and
+    /// signals their signal semaphores once all the operations are finished. This is useful in
+    /// order to wait for the end of all the operations of a builder with a single semaphore.
+    ///
+    /// The fence of the builder, if any, is discarded.
+    pub fn into_single_batch(self) -> SubmitBindSparseBatchBuilder<'a> {
+        let mut batch = SubmitBindSparseBatchBuilder::new();
+        for info in self.infos {
+            batch.wait_semaphores.extend(info.wait_semaphores);
+            batch.buffer_binds.extend(info.buffer_binds);
+            batch.image_opaque_binds.extend(info.image_opaque_binds);
+            batch.image_binds.extend(info.image_binds);
+            batch.signal_semaphores.extend(info.signal_semaphores);
+        }
+        batch
+    }
+
     /// Submits the command. Calls `vkQueueBindSparse`.
     pub fn submit(self, queue: &Queue) -> Result<(), SubmitBindSparseError> {
         unsafe {
@@ -449,7 +469,60 @@ impl<'a> SubmitBindSparseImageBindBuilder<'a> {
         }
     }
 
-    // TODO: finish
+    /// Adds an operation that binds memory to a region of a mipmap level of an array layer.
+    ///
+    /// `offset` and `extent` are in texels and must be multiples of the sparse image block size,
+    /// except when the region touches the edge of the mipmap level.
+    pub unsafe fn add_bind(&mut self, aspect: UnsafeCommandBufferBuilderImageAspect,
+                           mip_level: u32, array_layer: u32, offset: [u32; 3], extent: [u32; 3],
+                           memory: &DeviceMemory, memory_offset: usize) {
+        self.binds.push(vk::SparseImageMemoryBind {
+                            subresource: vk::ImageSubresource {
+                                aspectMask: aspect.to_vk_bits(),
+                                mipLevel: mip_level,
+                                arrayLayer: array_layer,
+                            },
+                            offset: vk::Offset3D {
+                                x: offset[0] as i32,
+                                y: offset[1] as i32,
+                                z: offset[2] as i32,
+                            },
+                            extent: vk::Extent3D {
+                                width: extent[0],
+                                height: extent[1],
+                                depth: extent[2],
+                            },
+                            memory: memory.internal_object(),
+                            memoryOffset: memory_offset as vk::DeviceSize,
+                            flags: 0,
+                        });
+    }
+
+    /// Adds an operation that unbinds the memory of a region of a mipmap level of an array layer.
+    pub unsafe fn add_unbind(&mut self, aspect: UnsafeCommandBufferBuilderImageAspect,
+                             mip_level: u32, array_layer: u32, offset: [u32; 3],
+                             extent: [u32; 3]) {
+        self.binds.push(vk::SparseImageMemoryBind {
+                            subresource: vk::ImageSubresource {
+                                aspectMask: aspect.to_vk_bits(),
+                                mipLevel: mip_level,
+                                arrayLayer: array_layer,
+                            },
+                            offset: vk::Offset3D {
+                                x: offset[0] as i32,
+                                y: offset[1] as i32,
+                                z: offset[2] as i32,
+                            },
+                            extent: vk::Extent3D {
+                                width: extent[0],
+                                height: extent[1],
+                                depth: extent[2],
+                            },
+                            memory: 0,
+                            memoryOffset: 0,
+                            flags: 0,
+                        });
+    }
 }
 
 /// Error that can happen when submitting the present prototype.
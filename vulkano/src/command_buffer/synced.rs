@@ -9,19 +9,25 @@
 
 use fnv::FnvHashMap;
 use smallvec::SmallVec;
+use std::cmp;
 use std::collections::hash_map::Entry;
 use std::error;
 use std::fmt;
 use std::hash::{Hash, Hasher};
 use std::mem;
+use std::ops::Range;
 use std::ptr;
 use std::sync::Arc;
 use std::sync::Mutex;
 
 use OomError;
+use VulkanObject;
 use buffer::BufferAccess;
+use buffer::BufferInner;
 use command_buffer::CommandBuffer;
 use command_buffer::CommandBufferExecError;
+use command_buffer::mipmaps;
+use command_buffer::mipmaps::MipmapsMethod;
 use command_buffer::pool::CommandPool;
 use command_buffer::pool::CommandPoolAlloc;
 use command_buffer::pool::CommandPoolBuilderAlloc;
@@ -32,7 +38,10 @@ use command_buffer::sys::UnsafeCommandBufferBuilder;
 use command_buffer::sys::UnsafeCommandBufferBuilderBindVertexBuffer;
 use command_buffer::sys::UnsafeCommandBufferBuilderBufferImageCopy;
 use command_buffer::sys::UnsafeCommandBufferBuilderColorImageClear;
+use command_buffer::sys::UnsafeCommandBufferBuilderImageBlit;
 use command_buffer::sys::UnsafeCommandBufferBuilderPipelineBarrier;
+use descriptor::descriptor::DescriptorDesc;
+use descriptor::descriptor::DescriptorType;
 use descriptor::descriptor::ShaderStages;
 use descriptor::descriptor_set::DescriptorSet;
 use descriptor::pipeline_layout::PipelineLayoutAbstract;
@@ -50,10 +59,12 @@ use pipeline::GraphicsPipelineAbstract;
 use pipeline::input_assembly::IndexType;
 use pipeline::viewport::Scissor;
 use pipeline::viewport::Viewport;
+use sampler::Filter;
 use sync::AccessCheckError;
 use sync::AccessError;
 use sync::AccessFlagBits;
 use sync::Event;
+use sync::Fence;
 use sync::GpuFuture;
 use sync::PipelineStages;
 
@@ -66,6 +77,10 @@ use sync::PipelineStages;
 /// Since the implementation needs to cache commands in a `Vec`, most methods have additional
 /// `Send + Sync + 'static` trait requirements on their generics.
 ///
+/// The layout and the accesses of images are tracked separately for each mipmap level and each
+/// array layer, and pipeline barriers only cover the subresources that need them. For example it
+/// is possible to blit a mipmap level of an image into the next level of the same image.
+///
 /// If this builder finds out that a command isn't valid because of synchronization reasons (eg.
 /// trying to copy from a buffer to an image which share the same memory), then an error is
 /// returned.
@@ -259,43 +274,110 @@ impl<P> Hash for BuilderKey<P> {
 }
 
 // Current state of a resource during the building of the command buffer.
+//
+// Images are tracked per subresource, so that different mipmap levels or array layers of the same
+// image can be in different layouts and be accessed by different commands without any
+// synchronization between them. Buffers always have a single subresource.
 #[derive(Debug, Clone)]
 struct ResourceState {
-    // Stages of the command that last used this resource.
-    stages: PipelineStages,
-    // Access for the command that last used this resource.
-    access: AccessFlagBits,
-
     // True if the resource was used in exclusive mode at any point during the building of the
     // command buffer.
     exclusive_any: bool,
 
-    // True if the last command that used this resource used it in exclusive mode.
-    exclusive: bool,
-
     // Layout at the first use of the resource by the command buffer. Can be `Undefined` if we
     // don't care.
     initial_layout: ImageLayout,
 
+    // Number of array layers of the resource. Always 1 for buffers.
+    array_layers: u32,
+
+    // State of each subresource. The state of the array layer `l` of the mipmap level `m` is at
+    // index `m * array_layers + l`.
+    subresources: Vec<SubresourceState>,
+}
+
+// Current state of a single array layer of a single mipmap level of an image, or of a whole
+// buffer.
+#[derive(Debug, Clone)]
+struct SubresourceState {
+    // Index of the last command that used this subresource, or `None` if the subresource hasn't
+    // been used by the command buffer yet.
+    command_id: Option<usize>,
+
+    // Stages of the commands that last used this subresource.
+    stages: PipelineStages,
+    // Access for the commands that last used this subresource.
+    access: AccessFlagBits,
+
+    // True if the last command that used this subresource used it in exclusive mode.
+    exclusive: bool,
+
     // Current layout at this stage of the building.
     current_layout: ImageLayout,
 }
 
 impl ResourceState {
     // Turns this `ResourceState` into a `ResourceFinalState`. Called when the command buffer is
-    // being built.
-    #[inline]
+    // being built, after all the images have been transitioned to their final layout.
     fn finalize(self) -> ResourceFinalState {
+        let mut final_stages = PipelineStages::none();
+        let mut final_access = AccessFlagBits::none();
+
+        for sub in self.subresources.iter().filter(|s| s.command_id.is_some()) {
+            final_stages = final_stages | sub.stages;
+            final_access = final_access | sub.access;
+        }
+
+        let final_layout = self.subresources[0].current_layout;
+        debug_assert!(self.subresources
+                          .iter()
+                          .all(|s| s.current_layout == final_layout));
+
         ResourceFinalState {
-            final_stages: self.stages,
-            final_access: self.access,
+            final_stages: final_stages,
+            final_access: final_access,
             exclusive: self.exclusive_any,
             initial_layout: self.initial_layout,
-            final_layout: self.current_layout,
+            final_layout: final_layout,
         }
     }
 }
 
+// Range of subresources of a resource that must be synchronized or transitioned by a pipeline
+// barrier, and their state before the barrier.
+struct SubresourcesBarrier {
+    mipmaps: Range<u32>,
+    layers: Range<u32>,
+    stages: PipelineStages,
+    access: AccessFlagBits,
+    layout: ImageLayout,
+}
+
+impl SubresourcesBarrier {
+    // Adds a subresource to `list`. Merges it with the last element of the list if it is the
+    // next array layer of the same mipmap level and if their states are identical.
+    fn push(list: &mut SmallVec<[SubresourcesBarrier; 4]>, level: u32, layer: u32,
+            state: &SubresourceState) {
+        if let Some(last) = list.last_mut() {
+            if last.mipmaps.start == level && last.layers.end == layer &&
+                last.stages == state.stages && last.access == state.access &&
+                last.layout == state.current_layout
+            {
+                last.layers.end += 1;
+                return;
+            }
+        }
+
+        list.push(SubresourcesBarrier {
+                      mipmaps: level .. level + 1,
+                      layers: layer .. layer + 1,
+                      stages: state.stages,
+                      access: state.access,
+                      layout: state.current_layout,
+                  });
+    }
+}
+
 impl<P> SyncCommandBufferBuilder<P> {
     /// Builds a new `SyncCommandBufferBuilder`. The parameters are the same as the
     /// `UnsafeCommandBufferBuilder::new` function.
@@ -341,15 +423,55 @@ impl<P> SyncCommandBufferBuilder<P> {
     // After a command is added to the list of pending commands, this function must be called for
     // each resource used by the command that has just been added.
     // The function will take care of handling the pipeline barrier or flushing.
+    //
+    // Images are considered to be entirely accessed by the command. Use
+    // `prev_cmd_image_subresources` if the command only accesses some of the mipmap levels or
+    // array layers.
+    #[inline]
     fn prev_cmd_resource(&mut self, resource_ty: KeyTy, resource_index: usize, exclusive: bool,
                          stages: PipelineStages, access: AccessFlagBits,
                          start_layout: ImageLayout, end_layout: ImageLayout)
                          -> Result<(), SyncCommandBufferBuilderError> {
+        self.prev_cmd_subresources(resource_ty,
+                                   resource_index,
+                                   None,
+                                   exclusive,
+                                   stages,
+                                   access,
+                                   start_layout,
+                                   end_layout)
+    }
+
+    // Same as `prev_cmd_resource`, but for an image of which the command only accesses some
+    // subresources. `ranges` is a list of ranges of mipmap levels and array layers, which can
+    // overlap. The other subresources of the image keep their state.
+    #[inline]
+    fn prev_cmd_image_subresources(&mut self, resource_index: usize,
+                                   ranges: &[(Range<u32>, Range<u32>)], exclusive: bool,
+                                   stages: PipelineStages, access: AccessFlagBits,
+                                   start_layout: ImageLayout, end_layout: ImageLayout)
+                                   -> Result<(), SyncCommandBufferBuilderError> {
+        self.prev_cmd_subresources(KeyTy::Image,
+                                   resource_index,
+                                   Some(ranges),
+                                   exclusive,
+                                   stages,
+                                   access,
+                                   start_layout,
+                                   end_layout)
+    }
+
+    fn prev_cmd_subresources(&mut self, resource_ty: KeyTy, resource_index: usize,
+                             ranges: Option<&[(Range<u32>, Range<u32>)]>, exclusive: bool,
+                             stages: PipelineStages, access: AccessFlagBits,
+                             start_layout: ImageLayout, end_layout: ImageLayout)
+                             -> Result<(), SyncCommandBufferBuilderError> {
         debug_assert!(exclusive || start_layout == end_layout);
         debug_assert!(access.is_compatible_with(&stages));
         debug_assert!(resource_ty != KeyTy::Image || end_layout != ImageLayout::Undefined);
         debug_assert!(resource_ty != KeyTy::Buffer || start_layout == ImageLayout::Undefined);
         debug_assert!(resource_ty != KeyTy::Buffer || end_layout == ImageLayout::Undefined);
+        debug_assert!(resource_ty != KeyTy::Buffer || ranges.is_none());
         debug_assert_ne!(end_layout, ImageLayout::Preinitialized);
 
         let (first_unflushed, latest_command_id) = {
@@ -358,6 +480,21 @@ impl<P> SyncCommandBufferBuilder<P> {
             (commands_lock.first_unflushed, commands_lock.commands.len() - 1)
         };
 
+        // Number of mipmap levels and array layers of the resource, and layout of the
+        // subresources that haven't been used yet.
+        let (mipmap_levels, array_layers, untouched_layout) = {
+            let commands_lock = self.commands.lock().unwrap();
+            match resource_ty {
+                KeyTy::Buffer => (1, 1, ImageLayout::Undefined),
+                KeyTy::Image => {
+                    let img = commands_lock.commands[latest_command_id].image(resource_index);
+                    (img.mipmap_levels(),
+                     img.dimensions().array_layers(),
+                     img.initial_layout_requirement())
+                },
+            }
+        };
+
         let key = BuilderKey {
             commands: self.commands.clone(),
             command_id: latest_command_id,
@@ -365,141 +502,196 @@ impl<P> SyncCommandBufferBuilder<P> {
             resource_index,
         };
 
-        match self.resources.entry(key) {
-            Entry::Occupied(entry) => {
-                let collision_command_id = entry.key().command_id;
-                debug_assert!(collision_command_id <= latest_command_id);
-                if collision_command_id == latest_command_id {
-                    return Err(SyncCommandBufferBuilderError::Conflict);
-                }
-
-                let mut entry = entry.into_mut();
-
-                // Find out if we have a collision with the pending commands.
-                if exclusive || entry.exclusive || entry.current_layout != start_layout {
-                    // Collision found.
-
-                    // We now want to modify the current pipeline barrier in order to include the
-                    // transition. But since the pipeline barrier is going to be submitted before
-                    // the flushed commands, it would be a mistake if the command we transition
-                    // from hasn't been flushed yet.
-                    if collision_command_id >= first_unflushed {
-                        // Flush.
-                        unsafe {
-                            self.inner.pipeline_barrier(&self.pending_barrier);
-                            self.pending_barrier = UnsafeCommandBufferBuilderPipelineBarrier::new();
-                            {
-                                let mut commands_lock = self.commands.lock().unwrap();
-                                let f = commands_lock.first_unflushed;
-                                for command in &mut commands_lock.commands[f .. latest_command_id] {
-                                    command.send(&mut self.inner);
-                                }
-                                commands_lock.first_unflushed = latest_command_id;
-                            }
-                        }
-                    }
+        let state = match self.resources.entry(key) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                // The resource hasn't been accessed yet by this command buffer. Waiting for
+                // `bottom_of_pipe` with no access doesn't wait for anything, as the accesses
+                // before the command buffer are synchronized by the submission.
+                let untouched = SubresourceState {
+                    command_id: None,
+                    stages: PipelineStages {
+                        bottom_of_pipe: true,
+                        ..PipelineStages::none()
+                    },
+                    access: AccessFlagBits::none(),
+                    exclusive: false,
+                    current_layout: untouched_layout,
+                };
 
-                    // Modify the pipeline barrier to include the transition.
-                    unsafe {
-                        let commands_lock = self.commands.lock().unwrap();
-                        match resource_ty {
-                            KeyTy::Buffer => {
-                                let buf = commands_lock.commands[latest_command_id]
-                                    .buffer(resource_index);
-
-                                let b = &mut self.pending_barrier;
-                                b.add_buffer_memory_barrier(buf,
-                                                            entry.stages,
-                                                            entry.access,
-                                                            stages,
-                                                            access,
-                                                            true,
-                                                            None,
-                                                            0,
-                                                            buf.size());
-                            },
-
-                            KeyTy::Image => {
-                                let img = commands_lock.commands[latest_command_id]
-                                    .image(resource_index);
-
-                                let b = &mut self.pending_barrier;
-                                b.add_image_memory_barrier(img,
-                                                           0 .. img.mipmap_levels(),
-                                                           0 .. img.dimensions().array_layers(),
-                                                           entry.stages,
-                                                           entry.access,
-                                                           stages,
-                                                           access,
-                                                           true,
-                                                           None,
-                                                           entry.current_layout,
-                                                           start_layout);
-                            },
-                        };
-                    }
+                entry.insert(ResourceState {
+                                 exclusive_any: false,
+                                 initial_layout: start_layout,
+                                 array_layers: array_layers,
+                                 subresources: vec![untouched;
+                                                    (mipmap_levels * array_layers) as usize],
+                             })
+            },
+        };
 
-                    // Update state.
-                    entry.stages = stages;
-                    entry.access = access;
-                    entry.exclusive_any = true;
-                    entry.exclusive = exclusive;
-                    if exclusive {
-                        // Only modify the layout in case of a write, because buffer operations
-                        // pass `Undefined` for the layout. While a buffer write *must* set the
-                        // layout to `Undefined`, a buffer read must not touch it.
-                        entry.current_layout = end_layout;
+        // Mipmap levels and array layers of the subresources accessed by the command. A buffer
+        // and an image that share the same memory share the same entry, in which case the whole
+        // resource is considered as accessed.
+        let indices = match ranges {
+            Some(ranges) if state.subresources.len() == (mipmap_levels * array_layers) as usize => {
+                let mut indices = Vec::new();
+                for &(ref mipmaps, ref layers) in ranges {
+                    debug_assert!(mipmaps.start < mipmaps.end && mipmaps.end <= mipmap_levels);
+                    debug_assert!(layers.start < layers.end && layers.end <= array_layers);
+                    for level in mipmaps.clone() {
+                        indices.extend(layers.clone().map(|layer| (level, layer)));
                     }
-
-                } else {
-                    entry.stages = entry.stages | stages;
-                    entry.access = entry.access | access;
                 }
+                indices.sort();
+                indices.dedup();
+                indices
             },
+            _ => {
+                let state_layers = state.array_layers;
+                (0 .. state.subresources.len() as u32)
+                    .map(|n| (n / state_layers, n % state_layers))
+                    .collect::<Vec<_>>()
+            },
+        };
 
-            Entry::Vacant(entry) => {
-                let mut actually_exclusive = exclusive;
+        // A command can't use the same subresource twice.
+        for &(level, layer) in indices.iter() {
+            let sub = &state.subresources[(level * state.array_layers + layer) as usize];
+            debug_assert!(sub.command_id.map(|id| id <= latest_command_id).unwrap_or(true));
+            if sub.command_id == Some(latest_command_id) {
+                return Err(SyncCommandBufferBuilderError::Conflict);
+            }
+        }
 
+        // Find out which subresources have a collision with the previous commands, and update
+        // their state.
+        let mut barriers: SmallVec<[SubresourcesBarrier; 4]> = SmallVec::new();
+        let mut needs_flush = false;
+
+        for &(level, layer) in indices.iter() {
+            let sub = &mut state.subresources[(level * state.array_layers + layer) as usize];
+
+            let collision = match sub.command_id {
+                Some(id) => {
+                    let collision = exclusive || sub.exclusive || sub.current_layout != start_layout;
+                    if collision && id >= first_unflushed {
+                        needs_flush = true;
+                    }
+                    collision
+                },
                 // Handle the case when the initial layout requirement of the image is different
                 // from the first layout usage.
-                if resource_ty == KeyTy::Image && start_layout != ImageLayout::Undefined &&
-                    start_layout != ImageLayout::Preinitialized
-                {
-                    let commands_lock = self.commands.lock().unwrap();
-                    let img = commands_lock.commands[latest_command_id].image(resource_index);
+                None => {
+                    start_layout != ImageLayout::Undefined &&
+                        start_layout != ImageLayout::Preinitialized &&
+                        sub.current_layout != start_layout
+                },
+            };
 
-                    if img.initial_layout_requirement() != start_layout {
-                        actually_exclusive = true;
-
-                        unsafe {
-                            let b = &mut self.pending_barrier;
-                            b.add_image_memory_barrier(img,
-                                                       0 .. img.mipmap_levels(),
-                                                       0 .. img.dimensions().array_layers(),
-                                                       PipelineStages {
-                                                           bottom_of_pipe: true,
-                                                           ..PipelineStages::none()
-                                                       }, // TODO:?
-                                                       AccessFlagBits::none(), // TODO: ?
-                                                       stages,
-                                                       access,
-                                                       true,
-                                                       None,
-                                                       img.initial_layout_requirement(),
-                                                       start_layout);
-                        }
+            if collision {
+                SubresourcesBarrier::push(&mut barriers, level, layer, sub);
+
+                // A layout transition is a write, so the next command that uses the subresource
+                // must wait for it even if both commands only read.
+                let transition = resource_ty == KeyTy::Image && sub.current_layout != start_layout;
+                sub.stages = stages;
+                sub.access = access;
+                sub.exclusive = exclusive || transition;
+                if exclusive || transition {
+                    // Only modify the layout in case of a write, because buffer operations
+                    // pass `Undefined` for the layout. While a buffer write *must* set the
+                    // layout to `Undefined`, a buffer read must not touch it.
+                    sub.current_layout = end_layout;
+                }
+                state.exclusive_any = true;
+
+            } else if sub.command_id.is_some() {
+                sub.stages = sub.stages | stages;
+                sub.access = sub.access | access;
+
+            } else {
+                sub.stages = stages;
+                sub.access = access;
+                sub.exclusive = exclusive;
+                if resource_ty == KeyTy::Image {
+                    sub.current_layout = end_layout;     // TODO: what if we reach the end with Undefined? that's not correct?
+                }
+                state.exclusive_any |= exclusive;
+            }
+
+            sub.command_id = Some(latest_command_id);
+        }
+
+        if barriers.is_empty() {
+            return Ok(());
+        }
+
+        // We now want to modify the current pipeline barrier in order to include the
+        // transitions. But since the pipeline barrier is going to be submitted before the
+        // flushed commands, it would be a mistake if a command we transition from hasn't been
+        // flushed yet.
+        if needs_flush {
+            // Flush.
+            unsafe {
+                self.inner.pipeline_barrier(&self.pending_barrier);
+                self.pending_barrier = UnsafeCommandBufferBuilderPipelineBarrier::new();
+                {
+                    let mut commands_lock = self.commands.lock().unwrap();
+                    let f = commands_lock.first_unflushed;
+                    for command in &mut commands_lock.commands[f .. latest_command_id] {
+                        command.send(&mut self.inner);
                     }
+                    commands_lock.first_unflushed = latest_command_id;
                 }
+            }
+        }
 
-                entry.insert(ResourceState {
-                    stages: stages,
-                    access: access,
-                    exclusive_any: actually_exclusive,
-                    exclusive: actually_exclusive,
-                    initial_layout: start_layout,
-                    current_layout: end_layout,     // TODO: what if we reach the end with Undefined? that's not correct?
-                });
-            },
+        // Modify the pipeline barrier to include the transitions.
+        unsafe {
+            let commands_lock = self.commands.lock().unwrap();
+            let b = &mut self.pending_barrier;
+
+            for barrier in barriers {
+                match resource_ty {
+                    KeyTy::Buffer => {
+                        let buf = commands_lock.commands[latest_command_id].buffer(resource_index);
+                        b.add_buffer_memory_barrier(buf,
+                                                    barrier.stages,
+                                                    barrier.access,
+                                                    stages,
+                                                    access,
+                                                    true,
+                                                    None,
+                                                    0,
+                                                    buf.size());
+                    },
+
+                    KeyTy::Image => {
+                        let img = commands_lock.commands[latest_command_id].image(resource_index);
+
+                        // If the entry is shared with a buffer, the whole image is transitioned.
+                        let (mipmaps, layers) = if state.subresources.len() ==
+                            (mipmap_levels * array_layers) as usize
+                        {
+                            (barrier.mipmaps, barrier.layers)
+                        } else {
+                            (0 .. mipmap_levels, 0 .. array_layers)
+                        };
+
+                        b.add_image_memory_barrier(img,
+                                                   mipmaps,
+                                                   layers,
+                                                   barrier.stages,
+                                                   barrier.access,
+                                                   stages,
+                                                   access,
+                                                   true,
+                                                   None,
+                                                   barrier.layout,
+                                                   start_layout);
+                    },
+                };
+            }
         }
 
         Ok(())
@@ -525,31 +717,55 @@ impl<P> SyncCommandBufferBuilder<P> {
         unsafe {
             let mut barrier = UnsafeCommandBufferBuilderPipelineBarrier::new();
 
-            for (key, mut state) in &mut self.resources {
+            for (key, state) in &mut self.resources {
                 if key.resource_ty != KeyTy::Image {
                     continue;
                 }
 
                 let img = commands_lock.commands[key.command_id].image(key.resource_index);
-                if img.final_layout_requirement() == state.current_layout {
+                let final_layout = img.final_layout_requirement();
+
+                // This includes the subresources that haven't been used by the command buffer, so
+                // that the whole image ends up in its final layout.
+                let mut transitions: SmallVec<[SubresourcesBarrier; 4]> = SmallVec::new();
+                for (num, sub) in state.subresources.iter_mut().enumerate() {
+                    if sub.current_layout == final_layout {
+                        continue;
+                    }
+
+                    let num = num as u32;
+                    SubresourcesBarrier::push(&mut transitions,
+                                              num / state.array_layers,
+                                              num % state.array_layers,
+                                              sub);
+                    sub.current_layout = final_layout;
+                }
+
+                if transitions.is_empty() {
                     continue;
                 }
 
+                // Nothing in this command buffer waits for the final transitions. The commands
+                // submitted afterwards are synchronized with the end of the command buffer by
+                // semaphores or fences, hence `bottom_of_pipe` with no access. There is no queue
+                // family ownership transfer.
                 state.exclusive_any = true;
-                barrier.add_image_memory_barrier(img,
-                                                 0 .. img.mipmap_levels(),
-                                                 0 .. img.dimensions().array_layers(),
-                                                 state.stages,
-                                                 state.access,
-                                                 PipelineStages {
-                                                     bottom_of_pipe: true,
-                                                     ..PipelineStages::none()
-                                                 }, // TODO:?
-                                                 AccessFlagBits::none(),
-                                                 true,
-                                                 None, // TODO: access?
-                                                 state.current_layout,
-                                                 img.final_layout_requirement());
+                for transition in transitions {
+                    barrier.add_image_memory_barrier(img,
+                                                     transition.mipmaps,
+                                                     transition.layers,
+                                                     transition.stages,
+                                                     transition.access,
+                                                     PipelineStages {
+                                                         bottom_of_pipe: true,
+                                                         ..PipelineStages::none()
+                                                     },
+                                                     AccessFlagBits::none(),
+                                                     true,
+                                                     None,
+                                                     transition.layout,
+                                                     final_layout);
+                }
             }
 
             self.inner.pipeline_barrier(&barrier);
@@ -802,6 +1018,133 @@ impl<P> SyncCommandBufferBuilder<P> {
         }
     }
 
+    /// Calls `vkCmdBlitImage` on the builder.
+    ///
+    /// Does nothing if the list of regions is empty, as it would be a no-op and isn't a valid
+    /// usage of the command anyway.
+    ///
+    /// The source and the destination can't be the same image. Use `generate_mipmaps` to blit
+    /// between the mipmap levels of an image.
+    #[inline]
+    pub unsafe fn blit_image<S, D, R>(&mut self, source: S, source_layout: ImageLayout,
+                                      destination: D, destination_layout: ImageLayout,
+                                      regions: R, filter: Filter)
+                                      -> Result<(), SyncCommandBufferBuilderError>
+        where S: ImageAccess + Send + Sync + 'static,
+              D: ImageAccess + Send + Sync + 'static,
+              R: Iterator<Item = UnsafeCommandBufferBuilderImageBlit> + Send + Sync + 'static
+    {
+        struct Cmd<S, D, R> {
+            source: Option<S>,
+            source_layout: ImageLayout,
+            destination: Option<D>,
+            destination_layout: ImageLayout,
+            regions: Option<R>,
+            filter: Filter,
+        }
+
+        impl<P, S, D, R> Command<P> for Cmd<S, D, R>
+            where S: ImageAccess + Send + Sync + 'static,
+                  D: ImageAccess + Send + Sync + 'static,
+                  R: Iterator<Item = UnsafeCommandBufferBuilderImageBlit>
+        {
+            unsafe fn send(&mut self, out: &mut UnsafeCommandBufferBuilder<P>) {
+                out.blit_image(self.source.as_ref().unwrap(),
+                               self.source_layout,
+                               self.destination.as_ref().unwrap(),
+                               self.destination_layout,
+                               self.regions.take().unwrap(),
+                               self.filter);
+            }
+
+            fn into_final_command(mut self: Box<Self>) -> Box<FinalCommand + Send + Sync> {
+                struct Fin<S, D>(S, D);
+                impl<S, D> FinalCommand for Fin<S, D>
+                    where S: ImageAccess + Send + Sync + 'static,
+                          D: ImageAccess + Send + Sync + 'static
+                {
+                    fn image(&self, num: usize) -> &ImageAccess {
+                        if num == 0 {
+                            &self.0
+                        } else if num == 1 {
+                            &self.1
+                        } else {
+                            panic!()
+                        }
+                    }
+                }
+
+                // Note: borrow checker somehow doesn't accept `self.source` and `self.destination`
+                // without using an Option.
+                Box::new(Fin(self.source.take().unwrap(),
+                             self.destination.take().unwrap()))
+            }
+
+            fn image(&self, num: usize) -> &ImageAccess {
+                if num == 0 {
+                    self.source.as_ref().unwrap()
+                } else if num == 1 {
+                    self.destination.as_ref().unwrap()
+                } else {
+                    panic!()
+                }
+            }
+        }
+
+        let regions = regions.collect::<Vec<_>>();
+        let source_ranges = image_ranges(&source,
+                                         regions.iter().map(|r| {
+                                             (r.source_mip_level .. r.source_mip_level + 1,
+                                              r.source_base_array_layer ..
+                                                  r.source_base_array_layer + r.layer_count)
+                                         }));
+        let destination_ranges = image_ranges(&destination,
+                                              regions.iter().map(|r| {
+                                                  (r.destination_mip_level ..
+                                                       r.destination_mip_level + 1,
+                                                   r.destination_base_array_layer ..
+                                                       r.destination_base_array_layer +
+                                                           r.layer_count)
+                                              }));
+
+        self.commands.lock().unwrap().commands.push(Box::new(Cmd {
+                                                                 source: Some(source),
+                                                                 source_layout: source_layout,
+                                                                 destination: Some(destination),
+                                                                 destination_layout:
+                                                                     destination_layout,
+                                                                 regions: Some(regions.into_iter()),
+                                                                 filter: filter,
+                                                             }));
+        self.prev_cmd_image_subresources(0,
+                                         &source_ranges,
+                                         false,
+                                         PipelineStages {
+                                             transfer: true,
+                                             ..PipelineStages::none()
+                                         },
+                                         AccessFlagBits {
+                                             transfer_read: true,
+                                             ..AccessFlagBits::none()
+                                         },
+                                         source_layout,
+                                         source_layout)?;
+        self.prev_cmd_image_subresources(1,
+                                         &destination_ranges,
+                                         true,
+                                         PipelineStages {
+                                             transfer: true,
+                                             ..PipelineStages::none()
+                                         },
+                                         AccessFlagBits {
+                                             transfer_write: true,
+                                             ..AccessFlagBits::none()
+                                         },
+                                         destination_layout,
+                                         destination_layout)?;
+        Ok(())
+    }
+
     /// Calls `vkCmdClearColorImage` on the builder.
     ///
     /// Does nothing if the list of regions is empty, as it would be a no-op and isn't a valid
@@ -849,25 +1192,32 @@ impl<P> SyncCommandBufferBuilder<P> {
             }
         }
 
+        let regions = regions.collect::<Vec<_>>();
+        let ranges = image_ranges(&image,
+                                  regions.iter().map(|r| {
+                                      (r.base_mip_level .. r.base_mip_level + r.level_count,
+                                       r.base_array_layer .. r.base_array_layer + r.layer_count)
+                                  }));
+
         self.commands.lock().unwrap().commands.push(Box::new(Cmd {
                                                                  image: Some(image),
                                                                  layout,
                                                                  color,
-                                                                 regions: Some(regions),
+                                                                 regions: Some(regions.into_iter()),
                                                              }));
-        self.prev_cmd_resource(KeyTy::Image,
-                               0,
-                               true,
-                               PipelineStages {
-                                   transfer: true,
-                                   ..PipelineStages::none()
-                               },
-                               AccessFlagBits {
-                                   transfer_write: true,
-                                   ..AccessFlagBits::none()
-                               },
-                               layout,
-                               layout)?;
+        self.prev_cmd_image_subresources(0,
+                                         &ranges,
+                                         true,
+                                         PipelineStages {
+                                             transfer: true,
+                                             ..PipelineStages::none()
+                                         },
+                                         AccessFlagBits {
+                                             transfer_write: true,
+                                             ..AccessFlagBits::none()
+                                         },
+                                         layout,
+                                         layout)?;
         Ok(())
     }
 
@@ -1027,11 +1377,19 @@ impl<P> SyncCommandBufferBuilder<P> {
             }
         }
 
+        let regions = regions.collect::<Vec<_>>();
+        let ranges = image_ranges(&destination,
+                                  regions.iter().map(|r| {
+                                      (r.image_mip_level .. r.image_mip_level + 1,
+                                       r.image_base_array_layer ..
+                                           r.image_base_array_layer + r.image_layer_count)
+                                  }));
+
         self.commands.lock().unwrap().commands.push(Box::new(Cmd {
                                                                  source: Some(source),
                                                                  destination: Some(destination),
             destination_layout: destination_layout,
-                                                                 regions: Some(regions),
+                                                                 regions: Some(regions.into_iter()),
                                                              }));
         self.prev_cmd_resource(KeyTy::Buffer,
                                0,
@@ -1046,7 +1404,115 @@ impl<P> SyncCommandBufferBuilder<P> {
                                },
                                ImageLayout::Undefined,
                                ImageLayout::Undefined)?;
-        self.prev_cmd_resource(KeyTy::Image,
+        self.prev_cmd_image_subresources(0,
+                                         &ranges,
+                                         true,
+                                         PipelineStages {
+                                             transfer: true,
+                                             ..PipelineStages::none()
+                                         },
+                                         AccessFlagBits {
+                                             transfer_write: true,
+                                             ..AccessFlagBits::none()
+                                         },
+                                         destination_layout,
+                                         destination_layout)?;
+        Ok(())
+    }
+
+    /// Calls `vkCmdCopyImageToBuffer` on the builder.
+    ///
+    /// Does nothing if the list of regions is empty, as it would be a no-op and isn't a valid
+    /// usage of the command anyway.
+    #[inline]
+    pub unsafe fn copy_image_to_buffer<S, D, R>(&mut self, source: S, source_layout: ImageLayout,
+                                                destination: D, regions: R)
+                                                -> Result<(), SyncCommandBufferBuilderError>
+        where S: ImageAccess + Send + Sync + 'static,
+              D: BufferAccess + Send + Sync + 'static,
+              R: Iterator<Item = UnsafeCommandBufferBuilderBufferImageCopy> + Send + Sync + 'static
+    {
+        struct Cmd<S, D, R> {
+            source: Option<S>,
+            source_layout: ImageLayout,
+            destination: Option<D>,
+            regions: Option<R>,
+        }
+
+        impl<P, S, D, R> Command<P> for Cmd<S, D, R>
+            where S: ImageAccess + Send + Sync + 'static,
+                  D: BufferAccess + Send + Sync + 'static,
+                  R: Iterator<Item = UnsafeCommandBufferBuilderBufferImageCopy>
+        {
+            unsafe fn send(&mut self, out: &mut UnsafeCommandBufferBuilder<P>) {
+                out.copy_image_to_buffer(self.source.as_ref().unwrap(),
+                                         self.source_layout,
+                                         self.destination.as_ref().unwrap(),
+                                         self.regions.take().unwrap());
+            }
+
+            fn into_final_command(mut self: Box<Self>) -> Box<FinalCommand + Send + Sync> {
+                struct Fin<S, D>(S, D);
+                impl<S, D> FinalCommand for Fin<S, D>
+                    where S: ImageAccess + Send + Sync + 'static,
+                          D: BufferAccess + Send + Sync + 'static
+                {
+                    fn buffer(&self, num: usize) -> &BufferAccess {
+                        assert_eq!(num, 0);
+                        &self.1
+                    }
+
+                    fn image(&self, num: usize) -> &ImageAccess {
+                        assert_eq!(num, 0);
+                        &self.0
+                    }
+                }
+
+                // Note: borrow checker somehow doesn't accept `self.source` and `self.destination`
+                // without using an Option.
+                Box::new(Fin(self.source.take().unwrap(),
+                             self.destination.take().unwrap()))
+            }
+
+            fn buffer(&self, num: usize) -> &BufferAccess {
+                assert_eq!(num, 0);
+                self.destination.as_ref().unwrap()
+            }
+
+            fn image(&self, num: usize) -> &ImageAccess {
+                assert_eq!(num, 0);
+                self.source.as_ref().unwrap()
+            }
+        }
+
+        let regions = regions.collect::<Vec<_>>();
+        let ranges = image_ranges(&source,
+                                  regions.iter().map(|r| {
+                                      (r.image_mip_level .. r.image_mip_level + 1,
+                                       r.image_base_array_layer ..
+                                           r.image_base_array_layer + r.image_layer_count)
+                                  }));
+
+        self.commands.lock().unwrap().commands.push(Box::new(Cmd {
+                                                                 source: Some(source),
+                                                                 source_layout: source_layout,
+                                                                 destination: Some(destination),
+                                                                 regions: Some(regions.into_iter()),
+                                                             }));
+        self.prev_cmd_image_subresources(0,
+                                         &ranges,
+                                         false,
+                                         PipelineStages {
+                                             transfer: true,
+                                             ..PipelineStages::none()
+                                         },
+                                         AccessFlagBits {
+                                             transfer_read: true,
+                                             ..AccessFlagBits::none()
+                                         },
+                                         source_layout,
+                                         source_layout)?;
+        self.prev_cmd_resource(KeyTy::Buffer,
                                0,
                                true,
                                PipelineStages {
@@ -1057,8 +1523,8 @@ impl<P> SyncCommandBufferBuilder<P> {
                                    transfer_write: true,
                                    ..AccessFlagBits::none()
                                },
-                               destination_layout,
-                               destination_layout)?;
+                               ImageLayout::Undefined,
+                               ImageLayout::Undefined)?;
         Ok(())
     }
 
@@ -1404,6 +1870,100 @@ impl<P> SyncCommandBufferBuilder<P> {
             .unwrap();
     }
 
+    /// Generates all the mipmap levels of `image` from its first level.
+    ///
+    /// Contrary to the other methods, this records multiple Vulkan commands with pipeline
+    /// barriers between them, because each level needs to be synchronized separately. The image
+    /// as a whole goes from `TransferDstOptimal` to `TransferSrcOptimal` with blits, and stays in
+    /// `General` with the compute shader. See the `mipmaps` module.
+    pub(crate) unsafe fn generate_mipmaps<I>(&mut self, image: I, method: MipmapsMethod)
+                                             -> Result<(), SyncCommandBufferBuilderError>
+        where I: ImageAccess + Send + Sync + 'static
+    {
+        struct Cmd<I> {
+            image: Option<I>,
+            method: Option<MipmapsMethod>,
+        }
+
+        impl<P, I> Command<P> for Cmd<I>
+            where I: ImageAccess + Send + Sync + 'static
+        {
+            unsafe fn send(&mut self, out: &mut UnsafeCommandBufferBuilder<P>) {
+                let image = self.image.as_ref().unwrap();
+                match *self.method.as_ref().unwrap() {
+                    MipmapsMethod::Blit => mipmaps::record_blits(out, image),
+                    MipmapsMethod::Compute(ref downsample) => downsample.record(out, image),
+                }
+            }
+
+            fn into_final_command(mut self: Box<Self>) -> Box<FinalCommand + Send + Sync> {
+                // The method is declared first so that the views are destroyed before the image.
+                struct Fin<I>(MipmapsMethod, I);
+                impl<I> FinalCommand for Fin<I>
+                    where I: ImageAccess + Send + Sync + 'static
+                {
+                    fn image(&self, num: usize) -> &ImageAccess {
+                        assert_eq!(num, 0);
+                        &self.1
+                    }
+                }
+
+                Box::new(Fin(self.method.take().unwrap(), self.image.take().unwrap()))
+            }
+
+            fn image(&self, num: usize) -> &ImageAccess {
+                assert_eq!(num, 0);
+                self.image.as_ref().unwrap()
+            }
+        }
+
+        let (stages, access, layout_before, layout_after) = match method {
+            MipmapsMethod::Blit => {
+                (PipelineStages {
+                     transfer: true,
+                     ..PipelineStages::none()
+                 },
+                 AccessFlagBits {
+                     transfer_read: true,
+                     transfer_write: true,
+                     ..AccessFlagBits::none()
+                 },
+                 ImageLayout::TransferDstOptimal,
+                 ImageLayout::TransferSrcOptimal)
+            },
+            MipmapsMethod::Compute(_) => {
+                (PipelineStages {
+                     compute_shader: true,
+                     ..PipelineStages::none()
+                 },
+                 AccessFlagBits {
+                     shader_read: true,
+                     shader_write: true,
+                     ..AccessFlagBits::none()
+                 },
+                 ImageLayout::General,
+                 ImageLayout::General)
+            },
+        };
+
+        // Each level is read and then written by the command, so all the levels and layers of
+        // the image are accessed.
+        let ranges = [(0 .. image.mipmap_levels(), 0 .. image.dimensions().array_layers())];
+
+        self.commands.lock().unwrap().commands.push(Box::new(Cmd {
+                                                                 image: Some(image),
+                                                                 method: Some(method),
+                                                             }));
+        self.prev_cmd_image_subresources(0,
+                                         &ranges,
+                                         true,
+                                         stages,
+                                         access,
+                                         layout_before,
+                                         layout_after)?;
+        Ok(())
+    }
+
     /// Calls `vkCmdNextSubpass` on the builder.
     #[inline]
     pub unsafe fn next_subpass(&mut self, subpass_contents: SubpassContents) {
@@ -1765,9 +2325,23 @@ unsafe impl<P> DeviceOwned for SyncCommandBufferBuilder<P> {
     }
 }
 
+// Collects the ranges of mipmap levels and array layers of `image` that are accessed by the
+// regions of a command. If the command doesn't have any region, the whole image is considered as
+// accessed.
+fn image_ranges<I, R>(image: &I, ranges: R) -> SmallVec<[(Range<u32>, Range<u32>); 4]>
+    where I: ?Sized + ImageAccess,
+          R: Iterator<Item = (Range<u32>, Range<u32>)>
+{
+    let mut list: SmallVec<[(Range<u32>, Range<u32>); 4]> = ranges.collect();
+    if list.is_empty() {
+        list.push((0 .. image.mipmap_levels(), 0 .. image.dimensions().array_layers()));
+    }
+    list
+}
+
 pub struct SyncCommandBufferBuilderBindDescriptorSets<'b, P: 'b> {
     builder: &'b mut SyncCommandBufferBuilder<P>,
-    inner: SmallVec<[Box<DescriptorSet + Send + Sync>; 12]>,
+    inner: SmallVec<[Arc<DescriptorSet + Send + Sync>; 12]>,
 }
 
 impl<'b, P> SyncCommandBufferBuilderBindDescriptorSets<'b, P> {
@@ -1776,9 +2350,15 @@ impl<'b, P> SyncCommandBufferBuilderBindDescriptorSets<'b, P> {
     pub fn add<S>(&mut self, set: S)
         where S: DescriptorSet + Send + Sync + 'static
     {
-        self.inner.push(Box::new(set));
+        self.inner.push(Arc::new(set));
     }
 
+    /// Binds the descriptor sets, starting at set `first_binding` of the pipeline layout.
+    ///
+    /// `dynamic_offsets` must contain one offset for each array element of each dynamic uniform
+    /// buffer or dynamic storage buffer descriptor of the sets. The buffers of these descriptors
+    /// are tracked with the range that is actually accessed if the sets know about them, and as a
+    /// whole otherwise.
     #[inline]
     pub unsafe fn submit<Pl, I>(self, graphics: bool, pipeline_layout: Pl, first_binding: u32,
                                 dynamic_offsets: I)
@@ -1786,35 +2366,318 @@ impl<'b, P> SyncCommandBufferBuilderBindDescriptorSets<'b, P> {
         where Pl: PipelineLayoutAbstract + Send + Sync + 'static,
               I: Iterator<Item = u32> + Send + Sync + 'static
     {
-        struct Cmd<Pl, I> {
-            inner: SmallVec<[Box<DescriptorSet + Send + Sync>; 12]>,
+        struct Cmd<Pl> {
+            inner: SmallVec<[Arc<DescriptorSet + Send + Sync>; 12]>,
             graphics: bool,
             pipeline_layout: Pl,
             first_binding: u32,
-            dynamic_offsets: Option<I>,
+            dynamic_offsets: SmallVec<[u32; 8]>,
+            buffers: SmallVec<[DescriptorBuffer; 4]>,
+            images: SmallVec<[DescriptorImage; 4]>,
         }
 
-        impl<P, Pl, I> Command<P> for Cmd<Pl, I>
-            where Pl: PipelineLayoutAbstract,
-                  I: Iterator<Item = u32>
+        impl<P, Pl> Command<P> for Cmd<Pl>
+            where Pl: PipelineLayoutAbstract
         {
             unsafe fn send(&mut self, out: &mut UnsafeCommandBufferBuilder<P>) {
                 out.bind_descriptor_sets(self.graphics,
                                          &self.pipeline_layout,
                                          self.first_binding,
                                          self.inner.iter().map(|s| s.inner()),
-                                         self.dynamic_offsets.take().unwrap());
+                                         self.dynamic_offsets.iter().cloned());
             }
 
             fn into_final_command(self: Box<Self>) -> Box<FinalCommand + Send + Sync> {
-                struct Fin(SmallVec<[Box<DescriptorSet + Send + Sync>; 12]>);
+                struct Fin(SmallVec<[Arc<DescriptorSet + Send + Sync>; 12]>,
+                           SmallVec<[DescriptorBuffer; 4]>,
+                           SmallVec<[DescriptorImage; 4]>);
                 impl FinalCommand for Fin {
-                    /* TODO: implement buffer() and image() */
+                    fn buffer(&self, num: usize) -> &BufferAccess {
+                        &self.1[num]
+                    }
+
+                    fn image(&self, num: usize) -> &ImageAccess {
+                        self.2[num].image()
+                    }
+                }
+                Box::new(Fin(self.inner, self.buffers, self.images))
+            }
+
+            fn buffer(&self, num: usize) -> &BufferAccess {
+                &self.buffers[num]
+            }
+
+            fn image(&self, num: usize) -> &ImageAccess {
+                self.images[num].image()
+            }
+        }
+
+        let dynamic_offsets = dynamic_offsets.collect::<SmallVec<[u32; 8]>>();
+
+        // Buffers of the descriptors, alongside with how they are accessed.
+        let mut buffers: SmallVec<[DescriptorBuffer; 4]> = SmallVec::new();
+        let mut accesses: SmallVec<[(bool, PipelineStages, AccessFlagBits); 4]> = SmallVec::new();
+
+        {
+            let mut offsets = dynamic_offsets.iter();
+
+            for (num, set) in self.inner.iter().enumerate() {
+                let set_num = first_binding as usize + num;
+
+                // One entry per dynamic offset of this set.
+                let mut descs: SmallVec<[(usize, bool, PipelineStages, AccessFlagBits); 8]> =
+                    SmallVec::new();
+
+                for binding in 0 .. pipeline_layout.num_bindings_in_set(set_num).unwrap_or(0) {
+                    let desc = match pipeline_layout.descriptor(set_num, binding) {
+                        Some(desc) => desc,
+                        None => continue,
+                    };
+
+                    let (exclusive, access) = match desc.ty.ty() {
+                        Some(DescriptorType::UniformBufferDynamic) => {
+                            (false,
+                             AccessFlagBits {
+                                 uniform_read: true,
+                                 ..AccessFlagBits::none()
+                             })
+                        },
+                        Some(DescriptorType::StorageBufferDynamic) => {
+                            (!desc.readonly,
+                             AccessFlagBits {
+                                 shader_read: true,
+                                 shader_write: !desc.readonly,
+                                 ..AccessFlagBits::none()
+                             })
+                        },
+                        _ => continue,
+                    };
+
+                    for _ in 0 .. desc.array_count {
+                        let offset = match offsets.next() {
+                            Some(&offset) => offset as usize,
+                            None => break,
+                        };
+
+                        descs.push((offset, exclusive, desc.stages.into(), access));
+                    }
                 }
-                Box::new(Fin(self.inner))
+
+                if descs.is_empty() {
+                    continue;
+                }
+
+                let set_buffers = set.dynamic_buffers().collect::<SmallVec<[_; 8]>>();
+
+                if set_buffers.len() != descs.len() {
+                    // The set doesn't know which buffers are written to its dynamic descriptors.
+                    // All the buffers of the set are considered to be accessed as a whole, in all
+                    // the ways that the dynamic descriptors allow.
+                    let (exclusive, stages, access) = descs
+                        .iter()
+                        .fold((false, PipelineStages::none(), AccessFlagBits::none()),
+                              |(e, s, a), &(_, exclusive, stages, access)| {
+                                  (e | exclusive, s | stages, a | access)
+                              });
+
+                    for (index, buffer) in set.buffers_list().enumerate() {
+                        let buffer = DescriptorBuffer {
+                            set: set.clone(),
+                            index: index,
+                            dynamic: false,
+                            offset: 0,
+                            size: buffer.size(),
+                        };
+                        DescriptorBuffer::push(&mut buffers,
+                                            &mut accesses,
+                                            buffer,
+                                            (exclusive, stages, access));
+                    }
+
+                    continue;
+                }
+
+                for (index, (offset, exclusive, stages, access)) in descs.into_iter().enumerate() {
+                    let buffer = DescriptorBuffer {
+                        set: set.clone(),
+                        index: index,
+                        dynamic: true,
+                        offset: offset,
+                        size: set_buffers[index].size(),
+                    };
+                    DescriptorBuffer::push(&mut buffers,
+                                        &mut accesses,
+                                        buffer,
+                                        (exclusive, stages, access));
+                }
+            }
+        }
+
+        for (num, set) in self.inner.iter().enumerate() {
+            let set_num = first_binding as usize + num;
+
+            // The buffers of the non-dynamic descriptors are accessed as a whole, in all the ways
+            // that the non-dynamic buffer descriptors of the set allow.
+            let mut all: Option<(bool, PipelineStages, AccessFlagBits)> = None;
+            for binding in 0 .. pipeline_layout.num_bindings_in_set(set_num).unwrap_or(0) {
+                let desc = match pipeline_layout.descriptor(set_num, binding) {
+                    Some(desc) => desc,
+                    None => continue,
+                };
+
+                let (exclusive, access) = match buffer_descriptor_access(&desc) {
+                    Some(a) => a,
+                    None => continue,
+                };
+
+                let stages = desc.stages.into();
+                all = Some(match all {
+                               Some((e, s, a)) => (e | exclusive, s | stages, a | access),
+                               None => (exclusive, stages, access),
+                           });
+            }
+
+            let (exclusive, stages, access) = match all {
+                Some(all) => all,
+                None => continue,
+            };
+
+            // The buffers that the set reports as dynamic have already been handled above.
+            let dynamic = set.dynamic_buffers()
+                .map(|b| (b.inner().buffer.internal_object(), b.inner().offset))
+                .collect::<SmallVec<[_; 8]>>();
+
+            for (index, buffer) in set.buffers_list().enumerate() {
+                let key = (buffer.inner().buffer.internal_object(), buffer.inner().offset);
+                if dynamic.contains(&key) {
+                    continue;
+                }
+
+                let buffer = DescriptorBuffer {
+                    set: set.clone(),
+                    index: index,
+                    dynamic: false,
+                    offset: 0,
+                    size: buffer.size(),
+                };
+                DescriptorBuffer::push(&mut buffers,
+                                       &mut accesses,
+                                       buffer,
+                                       (exclusive, stages, access));
             }
         }
 
+        // Images of the descriptors, alongside with how they are accessed.
+        let mut images: SmallVec<[DescriptorImage; 4]> = SmallVec::new();
+        let mut image_accesses: SmallVec<[(bool, PipelineStages, AccessFlagBits, ImageLayout);
+                                         4]> = SmallVec::new();
+
+        for (num, set) in self.inner.iter().enumerate() {
+            let set_num = first_binding as usize + num;
+
+            let views = set.image_views().collect::<SmallVec<[_; 8]>>();
+
+            if views.is_empty() {
+                // The set doesn't know which views it contains. The whole images are considered
+                // to be accessed in all the ways that the image descriptors of the set allow.
+                let mut all: Option<(bool, PipelineStages, AccessFlagBits)> = None;
+                for binding in 0 .. pipeline_layout.num_bindings_in_set(set_num).unwrap_or(0) {
+                    let desc = match pipeline_layout.descriptor(set_num, binding) {
+                        Some(desc) => desc,
+                        None => continue,
+                    };
+
+                    let (exclusive, access) = match image_descriptor_access(&desc) {
+                        Some(a) => a,
+                        None => continue,
+                    };
+
+                    let stages = desc.stages.into();
+                    all = Some(match all {
+                                   Some((e, s, a)) => (e | exclusive, s | stages, a | access),
+                                   None => (exclusive, stages, access),
+                               });
+                }
+
+                let (exclusive, stages, access) = match all {
+                    Some(all) => all,
+                    None => continue,
+                };
+
+                for (index, image) in set.images_list().enumerate() {
+                    let layout = image.initial_layout_requirement();
+                    let image = DescriptorImage {
+                        set: set.clone(),
+                        index: index,
+                        view: false,
+                        mipmaps: 0 .. image.mipmap_levels(),
+                        layers: 0 .. image.dimensions().array_layers(),
+                    };
+                    DescriptorImage::push(&mut images,
+                                          &mut image_accesses,
+                                          image,
+                                          (exclusive, stages, access, layout));
+                }
+
+                continue;
+            }
+
+            for (index, &(binding, view)) in views.iter().enumerate() {
+                let desc = match pipeline_layout.descriptor(set_num, binding) {
+                    Some(desc) => desc,
+                    None => continue,
+                };
+
+                let (exclusive, access) = match image_descriptor_access(&desc) {
+                    Some(a) => a,
+                    None => continue,
+                };
+
+                let layout = match desc.ty.ty() {
+                    Some(DescriptorType::StorageImage) => {
+                        view.descriptor_set_storage_image_layout()
+                    },
+                    Some(DescriptorType::SampledImage) => {
+                        view.descriptor_set_sampled_image_layout()
+                    },
+                    Some(DescriptorType::CombinedImageSampler) => {
+                        view.descriptor_set_combined_image_sampler_layout()
+                    },
+                    _ => view.descriptor_set_input_attachment_layout(),
+                };
+
+                // The ranges of the view are relative to the `UnsafeImage`, while the
+                // subresources of a resource are relative to the `ImageAccess`.
+                let (mipmaps, layers) = {
+                    let parent = view.parent().inner();
+                    let first_mipmap = parent.first_mipmap_level as u32;
+                    let first_layer = parent.first_layer as u32;
+                    let view_mipmaps = view.inner().mipmap_levels();
+                    let view_layers = view.inner().array_layers();
+                    ((view_mipmaps.start - first_mipmap) .. (view_mipmaps.end - first_mipmap),
+                     (view_layers.start - first_layer) .. (view_layers.end - first_layer))
+                };
+
+                let image = DescriptorImage {
+                    set: set.clone(),
+                    index: index,
+                    view: true,
+                    mipmaps: mipmaps,
+                    layers: layers,
+                };
+
+                DescriptorImage::push(&mut images,
+                                      &mut image_accesses,
+                                      image,
+                                      (exclusive, desc.stages.into(), access, layout));
+            }
+        }
+
+        let image_ranges = images
+            .iter()
+            .map(|i| [(i.mipmaps.clone(), i.layers.clone())])
+            .collect::<SmallVec<[_; 4]>>();
+
         self.builder
             .commands
             .lock()
@@ -1825,15 +2688,296 @@ impl<'b, P> SyncCommandBufferBuilderBindDescriptorSets<'b, P> {
                                graphics,
                                pipeline_layout,
                                first_binding,
-                               dynamic_offsets: Some(dynamic_offsets),
+                               dynamic_offsets,
+                               buffers,
+                               images,
                            }));
 
-        // FIXME: add resources
+        for (num, (exclusive, stages, access)) in accesses.into_iter().enumerate() {
+            self.builder
+                .prev_cmd_resource(KeyTy::Buffer,
+                                   num,
+                                   exclusive,
+                                   stages,
+                                   access,
+                                   ImageLayout::Undefined,
+                                   ImageLayout::Undefined)?;
+        }
+
+        for (num, (exclusive, stages, access, layout)) in image_accesses.into_iter().enumerate() {
+            self.builder
+                .prev_cmd_image_subresources(num,
+                                             &image_ranges[num],
+                                             exclusive,
+                                             stages,
+                                             access,
+                                             layout,
+                                             layout)?;
+        }
 
         Ok(())
     }
 }
 
+// Returns whether the buffer of a non-dynamic descriptor can be written, and how it is accessed.
+// Returns `None` if the descriptor isn't a non-dynamic buffer descriptor.
+fn buffer_descriptor_access(desc: &DescriptorDesc) -> Option<(bool, AccessFlagBits)> {
+    match desc.ty.ty() {
+        Some(DescriptorType::UniformBuffer) => {
+            Some((false,
+                  AccessFlagBits {
+                      uniform_read: true,
+                      ..AccessFlagBits::none()
+                  }))
+        },
+        Some(DescriptorType::UniformTexelBuffer) => {
+            Some((false,
+                  AccessFlagBits {
+                      shader_read: true,
+                      ..AccessFlagBits::none()
+                  }))
+        },
+        Some(DescriptorType::StorageBuffer) |
+        Some(DescriptorType::StorageTexelBuffer) => {
+            Some((!desc.readonly,
+                  AccessFlagBits {
+                      shader_read: true,
+                      shader_write: !desc.readonly,
+                      ..AccessFlagBits::none()
+                  }))
+        },
+        _ => None,
+    }
+}
+
+// Returns whether the image of a descriptor can be written, and how it is accessed. Returns
+// `None` if the descriptor isn't an image descriptor.
+fn image_descriptor_access(desc: &DescriptorDesc) -> Option<(bool, AccessFlagBits)> {
+    match desc.ty.ty() {
+        Some(DescriptorType::StorageImage) => {
+            Some((!desc.readonly,
+                  AccessFlagBits {
+                      shader_read: true,
+                      shader_write: !desc.readonly,
+                      ..AccessFlagBits::none()
+                  }))
+        },
+        Some(DescriptorType::SampledImage) |
+        Some(DescriptorType::CombinedImageSampler) => {
+            Some((false,
+                  AccessFlagBits {
+                      shader_read: true,
+                      ..AccessFlagBits::none()
+                  }))
+        },
+        Some(DescriptorType::InputAttachment) => {
+            Some((false,
+                  AccessFlagBits {
+                      input_attachment_read: true,
+                      ..AccessFlagBits::none()
+                  }))
+        },
+        _ => None,
+    }
+}
+
+// An image of a descriptor set. Only covers the subresources that are accessible through the
+// view written in the set, if the set knows about its views.
+struct DescriptorImage {
+    // The set that holds the image.
+    set: Arc<DescriptorSet + Send + Sync>,
+    // Index of the view within the image views of the set if `view` is true, or of the image
+    // within the images of the set otherwise.
+    index: usize,
+    view: bool,
+    // Mipmap levels and array layers accessed, relative to the image.
+    mipmaps: Range<u32>,
+    layers: Range<u32>,
+}
+
+impl DescriptorImage {
+    // Returns the image written in the set.
+    #[inline]
+    fn image(&self) -> &ImageAccess {
+        if self.view {
+            self.set.image_views().nth(self.index).unwrap().1.parent()
+        } else {
+            self.set.images_list().nth(self.index).unwrap()
+        }
+    }
+
+    // Adds `image` to `images` alongside with its access. A command can't use the same
+    // subresource twice, so an image that covers the same subresources in the same layout as an
+    // existing entry is merged with it instead. Views that cover distinct subresources of the
+    // same image, for example two mipmap levels, are tracked separately.
+    fn push(images: &mut SmallVec<[DescriptorImage; 4]>,
+            accesses: &mut SmallVec<[(bool, PipelineStages, AccessFlagBits, ImageLayout); 4]>,
+            image: DescriptorImage,
+            (exclusive, stages, access, layout): (bool, PipelineStages, AccessFlagBits,
+                                                  ImageLayout)) {
+        let existing = images
+            .iter()
+            .zip(accesses.iter())
+            .position(|(i, a)| i.same_subresources(&image) && a.3 == layout);
+        match existing {
+            Some(n) => {
+                accesses[n].0 |= exclusive;
+                accesses[n].1 = accesses[n].1 | stages;
+                accesses[n].2 = accesses[n].2 | access;
+            },
+            None => {
+                images.push(image);
+                accesses.push((exclusive, stages, access, layout));
+            },
+        }
+    }
+
+    // Returns true if `other` covers the same subresources of the same image.
+    fn same_subresources(&self, other: &DescriptorImage) -> bool {
+        let (a, b) = (self.image().inner(), other.image().inner());
+        a.image.internal_object() == b.image.internal_object() &&
+            a.first_mipmap_level == b.first_mipmap_level && a.first_layer == b.first_layer &&
+            self.mipmaps == other.mipmaps && self.layers == other.layers
+    }
+}
+
+// A buffer of a descriptor set. For a dynamic descriptor, only covers the range that is accessed
+// by the shaders once the dynamic offset has been applied, if the set knows about its dynamic
+// buffers. The buffers of the other descriptors are covered as a whole.
+struct DescriptorBuffer {
+    // The set that holds the buffer.
+    set: Arc<DescriptorSet + Send + Sync>,
+    // Index of the buffer within the dynamic buffers of the set if `dynamic` is true, or within
+    // all the buffers of the set otherwise.
+    index: usize,
+    dynamic: bool,
+    // Offset of the accessed range, relative to the buffer written in the set.
+    offset: usize,
+    // Size of the accessed range.
+    size: usize,
+}
+
+impl DescriptorBuffer {
+    // Returns the buffer written in the set.
+    #[inline]
+    fn buffer(&self) -> &BufferAccess {
+        if self.dynamic {
+            self.set.dynamic_buffers().nth(self.index).unwrap()
+        } else {
+            self.set.buffers_list().nth(self.index).unwrap()
+        }
+    }
+
+    // Adds `buffer` to `buffers` alongside with its access. A command can't use the same
+    // resource twice, so a range that overlaps an existing entry is merged with it instead.
+    fn push(buffers: &mut SmallVec<[DescriptorBuffer; 4]>,
+            accesses: &mut SmallVec<[(bool, PipelineStages, AccessFlagBits); 4]>,
+            buffer: DescriptorBuffer,
+            (exclusive, stages, access): (bool, PipelineStages, AccessFlagBits)) {
+        let existing = buffers.iter().position(|b| b.overlaps(&buffer));
+        match existing {
+            Some(n) => {
+                buffers[n].merge(&buffer);
+                accesses[n].0 |= exclusive;
+                accesses[n].1 = accesses[n].1 | stages;
+                accesses[n].2 = accesses[n].2 | access;
+            },
+            None => {
+                buffers.push(buffer);
+                accesses.push((exclusive, stages, access));
+            },
+        }
+    }
+
+    // Returns true if `other` was written at the same location and overlaps this range.
+    fn overlaps(&self, other: &DescriptorBuffer) -> bool {
+        let (a, b) = (self.buffer().inner(), other.buffer().inner());
+        a.buffer.internal_object() == b.buffer.internal_object() && a.offset == b.offset &&
+            self.offset < other.offset + other.size && other.offset < self.offset + self.size
+    }
+
+    // Extends this range so that it also covers `other`. Must only be called if `overlaps`
+    // returned true.
+    fn merge(&mut self, other: &DescriptorBuffer) {
+        let end = cmp::max(self.offset + self.size, other.offset + other.size);
+        self.offset = cmp::min(self.offset, other.offset);
+        self.size = end - self.offset;
+    }
+}
+
+unsafe impl BufferAccess for DescriptorBuffer {
+    #[inline]
+    fn inner(&self) -> BufferInner {
+        let inner = self.buffer().inner();
+        BufferInner {
+            buffer: inner.buffer,
+            offset: inner.offset + self.offset,
+        }
+    }
+
+    #[inline]
+    fn size(&self) -> usize {
+        self.size
+    }
+
+    #[inline]
+    fn conflict_key(&self, self_offset: usize, self_size: usize) -> u64 {
+        self.buffer().conflict_key(self.offset + self_offset, self_size)
+    }
+
+    #[inline]
+    fn try_gpu_lock(&self, exclusive_access: bool, queue: &Queue) -> Result<(), AccessError> {
+        self.buffer()
+            .try_gpu_lock_range(exclusive_access, queue, self.offset, self.size)
+    }
+
+    #[inline]
+    unsafe fn increase_gpu_lock(&self) {
+        self.buffer().increase_gpu_lock_range(self.offset, self.size)
+    }
+
+    #[inline]
+    unsafe fn unlock(&self) {
+        self.buffer().unlock_range(self.offset, self.size)
+    }
+
+    #[inline]
+    fn try_gpu_lock_range(&self, exclusive_access: bool, queue: &Queue, offset: usize,
+                          size: usize)
+                          -> Result<(), AccessError> {
+        self.buffer()
+            .try_gpu_lock_range(exclusive_access, queue, self.offset + offset, size)
+    }
+
+    #[inline]
+    unsafe fn increase_gpu_lock_range(&self, offset: usize, size: usize) {
+        self.buffer()
+            .increase_gpu_lock_range(self.offset + offset, size)
+    }
+
+    #[inline]
+    unsafe fn unlock_range(&self, offset: usize, size: usize) {
+        self.buffer().unlock_range(self.offset + offset, size)
+    }
+
+    #[inline]
+    unsafe fn attach_fence(&self, fence: &Arc<Fence>) {
+        self.buffer().attach_fence_range(self.offset, self.size, fence)
+    }
+
+    #[inline]
+    unsafe fn attach_fence_range(&self, offset: usize, size: usize, fence: &Arc<Fence>) {
+        self.buffer().attach_fence_range(self.offset + offset, size, fence)
+    }
+}
+
+unsafe impl DeviceOwned for DescriptorBuffer {
+    #[inline]
+    fn device(&self) -> &Arc<Device> {
+        self.buffer().device()
+    }
+}
+
 /// Prototype for a `vkCmdBindVertexBuffers`.
 pub struct SyncCommandBufferBuilderBindVertexBuffer<'a, P: 'a> {
     builder: &'a mut SyncCommandBufferBuilder<P>,
@@ -2210,6 +3354,32 @@ unsafe impl<P> CommandBuffer for SyncCommandBuffer<P> {
         Ok(())
     }
 
+    unsafe fn attach_fence(&self, fence: &Arc<Fence>) {
+        let commands_lock = self.commands.lock().unwrap();
+
+        for key in self.resources.keys() {
+            let (command_id, resource_ty, resource_index) = match *key {
+                CbKey::Command {
+                    command_id,
+                    resource_ty,
+                    resource_index,
+                    ..
+                } => {
+                    (command_id, resource_ty, resource_index)
+                },
+                _ => unreachable!(),
+            };
+
+            match resource_ty {
+                KeyTy::Buffer => {
+                    let cmd = &commands_lock[command_id];
+                    cmd.buffer(resource_index).attach_fence(fence);
+                },
+                KeyTy::Image => (),
+            }
+        }
+    }
+
     #[inline]
     fn check_buffer_access(
         &self, buffer: &BufferAccess, exclusive: bool, queue: &Queue)
@@ -2293,3 +3463,149 @@ impl<P> Drop for SyncCommandBuffer<P> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::iter;
+    use std::sync::Arc;
+
+    use command_buffer::CommandBuffer;
+    use command_buffer::synced::SyncCommandBufferBuilder;
+    use command_buffer::synced::SyncCommandBufferBuilderError;
+    use command_buffer::sys::Flags;
+    use command_buffer::sys::Kind;
+    use command_buffer::sys::UnsafeCommandBufferBuilderColorImageClear;
+    use command_buffer::sys::UnsafeCommandBufferBuilderImageAspect;
+    use command_buffer::sys::UnsafeCommandBufferBuilderImageBlit;
+    use device::Device;
+    use format::ClearValue;
+    use format::Format;
+    use image::Dimensions;
+    use image::ImageLayout;
+    use image::ImmutableImage;
+    use image::MipmapsCount;
+    use sampler::Filter;
+    use sync::GpuFuture;
+
+    // Blit of the whole mipmap level `level` into the level `level + 1` of a 16x16 image.
+    fn level_blit(level: u32) -> UnsafeCommandBufferBuilderImageBlit {
+        let src = (16 >> level) as i32;
+        let dst = (16 >> (level + 1)) as i32;
+
+        UnsafeCommandBufferBuilderImageBlit {
+            aspect: UnsafeCommandBufferBuilderImageAspect {
+                color: true,
+                depth: false,
+                stencil: false,
+            },
+            source_mip_level: level,
+            destination_mip_level: level + 1,
+            source_base_array_layer: 0,
+            destination_base_array_layer: 0,
+            layer_count: 1,
+            source_top_left: [0, 0, 0],
+            source_bottom_right: [src, src, 1],
+            destination_top_left: [0, 0, 0],
+            destination_bottom_right: [dst, dst, 1],
+        }
+    }
+
+    #[test]
+    fn blit_between_mipmap_levels() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let (_image, init) = unsafe {
+            ImmutableImage::uninitialized(device.clone(),
+                                          Dimensions::Dim2d {
+                                              width: 16,
+                                              height: 16,
+                                          },
+                                          Format::R8G8B8A8Unorm,
+                                          MipmapsCount::Log2,
+                                          Some(queue.family()))
+                .unwrap()
+        };
+        let init = Arc::new(init);
+
+        let command_buffer = unsafe {
+            let pool = Device::standard_command_pool(&device, queue.family());
+            let mut builder = SyncCommandBufferBuilder::new(&pool, Kind::primary(), Flags::None)
+                .unwrap();
+
+            let clear = UnsafeCommandBufferBuilderColorImageClear {
+                base_mip_level: 0,
+                level_count: 1,
+                base_array_layer: 0,
+                layer_count: 1,
+            };
+            builder
+                .clear_color_image(init.clone(),
+                                   ImageLayout::TransferDstOptimal,
+                                   ClearValue::Float([1.0, 0.0, 0.0, 1.0]),
+                                   iter::once(clear))
+                .unwrap();
+
+            // Each blit reads a level while writing the next one of the same image.
+            for level in 0 .. 4 {
+                builder
+                    .blit_image(init.clone(),
+                                ImageLayout::TransferSrcOptimal,
+                                init.clone(),
+                                ImageLayout::TransferDstOptimal,
+                                iter::once(level_blit(level)),
+                                Filter::Linear)
+                    .unwrap();
+            }
+
+            builder.build().unwrap()
+        };
+
+        command_buffer
+            .execute(queue.clone())
+            .unwrap()
+            .then_signal_fence_and_flush()
+            .unwrap()
+            .wait(None)
+            .unwrap();
+    }
+
+    #[test]
+    fn blit_same_subresource_conflict() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let (_image, init) = unsafe {
+            ImmutableImage::uninitialized(device.clone(),
+                                          Dimensions::Dim2d {
+                                              width: 16,
+                                              height: 16,
+                                          },
+                                          Format::R8G8B8A8Unorm,
+                                          MipmapsCount::Log2,
+                                          Some(queue.family()))
+                .unwrap()
+        };
+        let init = Arc::new(init);
+
+        unsafe {
+            let pool = Device::standard_command_pool(&device, queue.family());
+            let mut builder = SyncCommandBufferBuilder::new(&pool, Kind::primary(), Flags::None)
+                .unwrap();
+
+            let blit = UnsafeCommandBufferBuilderImageBlit {
+                destination_mip_level: 0,
+                destination_bottom_right: [16, 16, 1],
+                ..level_blit(0)
+            };
+
+            match builder.blit_image(init.clone(),
+                                     ImageLayout::TransferSrcOptimal,
+                                     init.clone(),
+                                     ImageLayout::TransferDstOptimal,
+                                     iter::once(blit),
+                                     Filter::Linear) {
+                Err(SyncCommandBufferBuilderError::Conflict) => (),
+                _ => panic!(),
+            }
+        }
+    }
+}
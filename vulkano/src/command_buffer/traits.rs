@@ -28,6 +28,7 @@ use image::ImageLayout;
 use sync::AccessCheckError;
 use sync::AccessError;
 use sync::AccessFlagBits;
+use sync::Fence;
 use sync::FlushError;
 use sync::GpuFuture;
 use sync::NowFuture;
@@ -142,6 +143,15 @@ pub unsafe trait CommandBuffer: DeviceOwned {
                           queue: &Queue)
                           -> Result<Option<(PipelineStages, AccessFlagBits)>, AccessCheckError>;
 
+    /// Informs the command buffer that the submission that contains it has been submitted, and
+    /// that `fence` will be signaled once the GPU has finished executing it.
+    ///
+    /// The command buffer should forward the fence to the resources it locked in
+    /// `prepare_submit`. The default implementation does nothing.
+    #[inline]
+    unsafe fn attach_fence(&self, fence: &Arc<Fence>) {
+    }
+
     // FIXME: lots of other methods
 }
 
@@ -186,6 +196,11 @@ unsafe impl<T> CommandBuffer for T
                           -> Result<Option<(PipelineStages, AccessFlagBits)>, AccessCheckError> {
         (**self).check_image_access(image, layout, exclusive, queue)
     }
+
+    #[inline]
+    unsafe fn attach_fence(&self, fence: &Arc<Fence>) {
+        (**self).attach_fence(fence)
+    }
 }
 
 /// Represents a command buffer being executed by the GPU and the moment when the execution
@@ -272,6 +287,12 @@ unsafe impl<F, Cb> GpuFuture for CommandBufferExecFuture<F, Cb>
         self.previous.signal_finished();
     }
 
+    #[inline]
+    unsafe fn attach_fence(&self, fence: &Arc<Fence>) {
+        self.command_buffer.attach_fence(fence);
+        self.previous.attach_fence(fence);
+    }
+
     #[inline]
     fn queue_change_allowed(&self) -> bool {
         false
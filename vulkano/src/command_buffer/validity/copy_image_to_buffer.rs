@@ -0,0 +1,279 @@
+// Copyright (c) 2017 The vulkano developers
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>,
+// at your option. All files in the project carrying such
+// notice may not be copied, modified, or distributed except
+// according to those terms.
+
+use std::error;
+use std::fmt;
+
+use VulkanObject;
+use buffer::BufferAccess;
+use command_buffer::sys::UnsafeCommandBufferBuilderImageAspect;
+use device::Device;
+use device::DeviceOwned;
+use image::ImageAccess;
+
+/// Checks whether a copy image to buffer command is valid.
+///
+/// The texels are supposed to be tightly packed in the buffer, layer after layer.
+///
+/// # Panic
+///
+/// - Panics if the source and destination were not created with `device`.
+///
+pub fn check_copy_image_to_buffer<S, D>(device: &Device, source: &S, destination: &D,
+                                        offset: [u32; 3], size: [u32; 3], first_layer: u32,
+                                        num_layers: u32, mipmap: u32)
+                                        -> Result<CheckCopyImageToBuffer,
+                                                  CheckCopyImageToBufferError>
+    where S: ?Sized + ImageAccess,
+          D: ?Sized + BufferAccess
+{
+    assert_eq!(source.inner().image.device().internal_object(),
+               device.internal_object());
+    assert_eq!(destination.inner().buffer.device().internal_object(),
+               device.internal_object());
+
+    if !source.inner().image.usage_transfer_source() {
+        return Err(CheckCopyImageToBufferError::SourceMissingTransferUsage);
+    }
+
+    if !destination.inner().buffer.usage_transfer_destination() {
+        return Err(CheckCopyImageToBufferError::DestinationMissingTransferUsage);
+    }
+
+    let aspect = if source.has_color() {
+        UnsafeCommandBufferBuilderImageAspect {
+            color: true,
+            depth: false,
+            stencil: false,
+        }
+    } else if source.has_depth() && source.has_stencil() {
+        return Err(CheckCopyImageToBufferError::DepthStencilFormat);
+    } else {
+        UnsafeCommandBufferBuilderImageAspect {
+            color: false,
+            depth: source.has_depth(),
+            stencil: source.has_stencil(),
+        }
+    };
+
+    if mipmap >= source.mipmap_levels() {
+        return Err(CheckCopyImageToBufferError::OutOfRange);
+    }
+
+    let dimensions = match source.dimensions().mipmap_dimensions(mipmap) {
+        Some(d) => d,
+        None => return Err(CheckCopyImageToBufferError::OutOfRange),
+    };
+
+    match first_layer.checked_add(num_layers) {
+        Some(end) if num_layers != 0 && end <= dimensions.array_layers() => (),
+        _ => return Err(CheckCopyImageToBufferError::OutOfRange),
+    }
+
+    let extent = dimensions.width_height_depth();
+    for n in 0 .. 3 {
+        match offset[n].checked_add(size[n]) {
+            Some(end) if size[n] != 0 && end <= extent[n] => (),
+            _ => return Err(CheckCopyImageToBufferError::OutOfRange),
+        }
+    }
+
+    if source.format().block_size().is_none() {
+        return Err(CheckCopyImageToBufferError::DepthStencilFormat);
+    }
+    let copy_size = source
+        .format()
+        .region_size(size)
+        .and_then(|s| s.checked_mul(num_layers as usize));
+    let copy_size = match copy_size {
+        Some(s) => s,
+        None => return Err(CheckCopyImageToBufferError::OutOfRange),
+    };
+
+    if destination.size() < copy_size {
+        return Err(CheckCopyImageToBufferError::BufferTooSmall {
+                       required_size: copy_size,
+                       actual_size: destination.size(),
+                   });
+    }
+
+    Ok(CheckCopyImageToBuffer {
+           aspect: aspect,
+           copy_size: copy_size,
+       })
+}
+
+/// Information returned if `check_copy_image_to_buffer` succeeds.
+pub struct CheckCopyImageToBuffer {
+    /// The aspect of the image to copy.
+    pub aspect: UnsafeCommandBufferBuilderImageAspect,
+    /// Number of bytes written to the buffer.
+    pub copy_size: usize,
+}
+
+/// Error that can happen from `check_copy_image_to_buffer`.
+#[derive(Debug, Copy, Clone)]
+pub enum CheckCopyImageToBufferError {
+    /// The source image is missing the transfer source usage.
+    SourceMissingTransferUsage,
+    /// The destination buffer is missing the transfer destination usage.
+    DestinationMissingTransferUsage,
+    /// The image has both a depth and a stencil component. Copying them in a single command isn't
+    /// supported.
+    DepthStencilFormat,
+    /// The mipmap level, the array layers or the region are out of the range of the image.
+    OutOfRange,
+    /// The destination buffer is too small to hold the copied texels.
+    BufferTooSmall {
+        /// Number of bytes written by the copy.
+        required_size: usize,
+        /// Size of the destination buffer.
+        actual_size: usize,
+    },
+}
+
+impl error::Error for CheckCopyImageToBufferError {
+    #[inline]
+    fn description(&self) -> &str {
+        match *self {
+            CheckCopyImageToBufferError::SourceMissingTransferUsage => {
+                "the source image is missing the transfer source usage"
+            },
+            CheckCopyImageToBufferError::DestinationMissingTransferUsage => {
+                "the destination buffer is missing the transfer destination usage"
+            },
+            CheckCopyImageToBufferError::DepthStencilFormat => {
+                "the image has both a depth and a stencil component"
+            },
+            CheckCopyImageToBufferError::OutOfRange => {
+                "the mipmap level, the array layers or the region are out of range"
+            },
+            CheckCopyImageToBufferError::BufferTooSmall { .. } => {
+                "the destination buffer is too small to hold the copied texels"
+            },
+        }
+    }
+}
+
+impl fmt::Display for CheckCopyImageToBufferError {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(fmt, "{}", error::Error::description(self))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::iter;
+
+    use buffer::BufferUsage;
+    use buffer::CpuAccessibleBuffer;
+    use format::Format;
+    use image::AttachmentImage;
+    use image::ImageUsage;
+    use super::*;
+
+    #[test]
+    fn depth_stencil() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let usage = ImageUsage {
+            transfer_source: true,
+            ..ImageUsage::none()
+        };
+        let image = AttachmentImage::with_usage(device.clone(), [16, 16],
+                                                Format::D24Unorm_S8Uint, usage);
+        let image = match image {
+            Ok(i) => i,
+            Err(_) => return,       // Format not supported.
+        };
+        let buffer = CpuAccessibleBuffer::from_iter(device.clone(), BufferUsage::all(),
+                                                    iter::once(queue.family()),
+                                                    (0 .. 16 * 16 * 4).map(|_| 0u8)).unwrap();
+
+        match check_copy_image_to_buffer(&device, &image, &buffer, [0, 0, 0], [16, 16, 1], 0, 1,
+                                         0) {
+            Err(CheckCopyImageToBufferError::DepthStencilFormat) => (),
+            _ => panic!()
+        }
+    }
+
+    #[test]
+    fn buffer_too_small() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let usage = ImageUsage {
+            transfer_source: true,
+            ..ImageUsage::none()
+        };
+        let image = AttachmentImage::with_usage(device.clone(), [16, 16],
+                                                Format::R8G8B8A8Unorm, usage).unwrap();
+        let buffer = CpuAccessibleBuffer::from_iter(device.clone(), BufferUsage::all(),
+                                                    iter::once(queue.family()),
+                                                    (0 .. 16 * 16).map(|_| 0u8)).unwrap();
+
+        match check_copy_image_to_buffer(&device, &image, &buffer, [0, 0, 0], [16, 16, 1], 0, 1,
+                                         0) {
+            Err(CheckCopyImageToBufferError::BufferTooSmall {
+                    required_size: 1024,
+                    actual_size: 256,
+                }) => (),
+            _ => panic!()
+        }
+    }
+
+    #[test]
+    fn out_of_range_overflow() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let usage = ImageUsage {
+            transfer_source: true,
+            ..ImageUsage::none()
+        };
+        let image = AttachmentImage::with_usage(device.clone(), [16, 16],
+                                                Format::R8G8B8A8Unorm, usage).unwrap();
+        let buffer = CpuAccessibleBuffer::from_iter(device.clone(), BufferUsage::all(),
+                                                    iter::once(queue.family()),
+                                                    (0 .. 16 * 16 * 4).map(|_| 0u8)).unwrap();
+
+        match check_copy_image_to_buffer(&device, &image, &buffer, [u32::max_value(), 0, 0],
+                                         [16, 16, 1], 0, 1, 0) {
+            Err(CheckCopyImageToBufferError::OutOfRange) => (),
+            _ => panic!()
+        }
+
+        match check_copy_image_to_buffer(&device, &image, &buffer, [0, 0, 0], [16, 16, 1],
+                                         u32::max_value(), 2, 0) {
+            Err(CheckCopyImageToBufferError::OutOfRange) => (),
+            _ => panic!()
+        }
+    }
+
+    #[test]
+    fn missing_usage() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let usage = ImageUsage {
+            transfer_source: true,
+            ..ImageUsage::none()
+        };
+        let image = AttachmentImage::with_usage(device.clone(), [16, 16],
+                                                Format::R8G8B8A8Unorm, usage).unwrap();
+        let buffer = CpuAccessibleBuffer::from_iter(device.clone(),
+                                                    BufferUsage::transfer_source(),
+                                                    iter::once(queue.family()),
+                                                    (0 .. 16 * 16 * 4).map(|_| 0u8)).unwrap();
+
+        match check_copy_image_to_buffer(&device, &image, &buffer, [0, 0, 0], [16, 16, 1], 0, 1,
+                                         0) {
+            Err(CheckCopyImageToBufferError::DestinationMissingTransferUsage) => (),
+            _ => panic!()
+        }
+    }
+}
@@ -0,0 +1,282 @@
+// Copyright (c) 2017 The vulkano developers
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>,
+// at your option. All files in the project carrying such
+// notice may not be copied, modified, or distributed except
+// according to those terms.
+
+use smallvec::SmallVec;
+use std::error;
+use std::fmt;
+
+use descriptor::descriptor::DescriptorType;
+use descriptor::descriptor_set::DescriptorSet;
+use descriptor::pipeline_layout::PipelineLayoutAbstract;
+
+/// Checks whether the dynamic offsets passed when binding descriptor sets are valid.
+///
+/// The descriptor sets are bound starting from set 0 of the pipeline layout. There must be one
+/// offset for each array element of each dynamic uniform buffer or dynamic storage buffer
+/// descriptor of these sets, ordered by set, then by binding, then by array element.
+///
+/// The range accessed by each descriptor is only checked if the descriptor set knows which
+/// buffers it contains. See `DescriptorSet::dynamic_buffers`.
+pub fn check_dynamic_offsets<Pl, S>(pipeline: &Pl, sets: &[S], dynamic_offsets: &[u32])
+                                    -> Result<(), CheckDynamicOffsetsError>
+    where Pl: ?Sized + PipelineLayoutAbstract,
+          S: DescriptorSet
+{
+    let limits = pipeline.device().physical_device().limits();
+
+    // For each dynamic offset that we expect, the set it belongs to and its required alignment.
+    let mut expected: SmallVec<[(usize, u32); 8]> = SmallVec::new();
+    for set_num in 0 .. sets.len() {
+        for binding in 0 .. pipeline.num_bindings_in_set(set_num).unwrap_or(0) {
+            let desc = match pipeline.descriptor(set_num, binding) {
+                Some(desc) => desc,
+                None => continue,
+            };
+
+            let alignment = match desc.ty.ty() {
+                Some(DescriptorType::UniformBufferDynamic) => {
+                    limits.min_uniform_buffer_offset_alignment() as u32
+                },
+                Some(DescriptorType::StorageBufferDynamic) => {
+                    limits.min_storage_buffer_offset_alignment() as u32
+                },
+                _ => continue,
+            };
+
+            for _ in 0 .. desc.array_count {
+                expected.push((set_num, alignment));
+            }
+        }
+    }
+
+    if expected.len() != dynamic_offsets.len() {
+        return Err(CheckDynamicOffsetsError::WrongDynamicOffsetsCount {
+                       expected: expected.len(),
+                       obtained: dynamic_offsets.len(),
+                   });
+    }
+
+    let mut pos = 0;
+    for (set_num, set) in sets.iter().enumerate() {
+        let num = expected[pos ..]
+            .iter()
+            .take_while(|&&(s, _)| s == set_num)
+            .count();
+        let buffers = set.dynamic_buffers().collect::<SmallVec<[_; 8]>>();
+
+        for n in 0 .. num {
+            let offset = dynamic_offsets[pos + n];
+            let alignment = expected[pos + n].1;
+
+            if offset % alignment != 0 {
+                return Err(CheckDynamicOffsetsError::UnalignedDynamicOffset {
+                               offset: offset,
+                               alignment: alignment,
+                           });
+            }
+
+            // The set doesn't know its buffers.
+            if buffers.len() != num {
+                continue;
+            }
+
+            let buffer = buffers[n];
+            let max_offset = buffer.inner().buffer.size() - buffer.inner().offset - buffer.size();
+            if offset as usize > max_offset {
+                return Err(CheckDynamicOffsetsError::DynamicOffsetOutOfRange {
+                               offset: offset,
+                               max_offset: max_offset,
+                           });
+            }
+        }
+
+        pos += num;
+    }
+
+    Ok(())
+}
+
+/// Error that can happen when checking the dynamic offsets of descriptor sets.
+#[derive(Debug, Copy, Clone)]
+pub enum CheckDynamicOffsetsError {
+    /// The number of dynamic offsets doesn't match the number of dynamic descriptors.
+    WrongDynamicOffsetsCount {
+        /// Number of dynamic descriptors in the bound sets.
+        expected: usize,
+        /// Number of dynamic offsets that were passed.
+        obtained: usize,
+    },
+
+    /// A dynamic offset is not a multiple of the minimum offset alignment of the device.
+    UnalignedDynamicOffset {
+        /// The offset.
+        offset: u32,
+        /// The required alignment.
+        alignment: u32,
+    },
+
+    /// A dynamic offset would make the descriptor access memory past the end of the buffer.
+    DynamicOffsetOutOfRange {
+        /// The offset.
+        offset: u32,
+        /// The maximum offset that can be used with this descriptor.
+        max_offset: usize,
+    },
+}
+
+impl error::Error for CheckDynamicOffsetsError {
+    #[inline]
+    fn description(&self) -> &str {
+        match *self {
+            CheckDynamicOffsetsError::WrongDynamicOffsetsCount { .. } => {
+                "the number of dynamic offsets doesn't match the number of dynamic descriptors"
+            },
+            CheckDynamicOffsetsError::UnalignedDynamicOffset { .. } => {
+                "a dynamic offset is not a multiple of the minimum offset alignment"
+            },
+            CheckDynamicOffsetsError::DynamicOffsetOutOfRange { .. } => {
+                "a dynamic offset would make the descriptor access memory past the end of the \
+                 buffer"
+            },
+        }
+    }
+}
+
+impl fmt::Display for CheckDynamicOffsetsError {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(fmt, "{}", error::Error::description(self))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::iter;
+    use std::sync::Arc;
+
+    use buffer::BufferUsage;
+    use buffer::CpuAccessibleBuffer;
+    use command_buffer::validity::dynamic_offsets::*;
+    use descriptor::descriptor_set::PersistentDescriptorSet;
+    use descriptor::pipeline_layout::PipelineLayoutDesc;
+    use tests::TestLayout;
+    use tests::test_descriptor;
+    use tests::uniform_buffer_ty;
+
+    // Layout with a single dynamic uniform buffer at binding 0 of set 0.
+    fn test_layout() -> TestLayout {
+        TestLayout::new(vec![Some(test_descriptor(uniform_buffer_ty(true), 1))])
+    }
+
+    #[test]
+    fn valid() {
+        let (device, queue) = gfx_dev_and_queue!();
+        let layout = Arc::new(test_layout().build(device.clone()).unwrap());
+
+        let buffer = CpuAccessibleBuffer::from_data(device.clone(),
+                                                    BufferUsage::all(),
+                                                    iter::once(queue.family()),
+                                                    0.0f32)
+            .unwrap();
+
+        let set = PersistentDescriptorSet::start(layout.clone(), 0)
+            .add_buffer(buffer)
+            .unwrap()
+            .build()
+            .unwrap();
+
+        assert!(check_dynamic_offsets(&layout, &[set], &[0]).is_ok());
+    }
+
+    #[test]
+    fn wrong_count() {
+        let (device, queue) = gfx_dev_and_queue!();
+        let layout = Arc::new(test_layout().build(device.clone()).unwrap());
+
+        let buffer = CpuAccessibleBuffer::from_data(device.clone(),
+                                                    BufferUsage::all(),
+                                                    iter::once(queue.family()),
+                                                    0.0f32)
+            .unwrap();
+
+        let set = PersistentDescriptorSet::start(layout.clone(), 0)
+            .add_buffer(buffer)
+            .unwrap()
+            .build()
+            .unwrap();
+
+        match check_dynamic_offsets(&layout, &[set], &[]) {
+            Err(CheckDynamicOffsetsError::WrongDynamicOffsetsCount {
+                    expected: 1,
+                    obtained: 0,
+                }) => (),
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn unaligned() {
+        let (device, queue) = gfx_dev_and_queue!();
+        let layout = Arc::new(test_layout().build(device.clone()).unwrap());
+
+        let alignment = device
+            .physical_device()
+            .limits()
+            .min_uniform_buffer_offset_alignment() as u32;
+        if alignment == 1 {
+            return;
+        }
+
+        let buffer = CpuAccessibleBuffer::from_data(device.clone(),
+                                                    BufferUsage::all(),
+                                                    iter::once(queue.family()),
+                                                    0.0f32)
+            .unwrap();
+
+        let set = PersistentDescriptorSet::start(layout.clone(), 0)
+            .add_buffer(buffer)
+            .unwrap()
+            .build()
+            .unwrap();
+
+        match check_dynamic_offsets(&layout, &[set], &[1]) {
+            Err(CheckDynamicOffsetsError::UnalignedDynamicOffset { offset: 1, .. }) => (),
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn out_of_range() {
+        let (device, queue) = gfx_dev_and_queue!();
+        let layout = Arc::new(test_layout().build(device.clone()).unwrap());
+
+        let alignment = device
+            .physical_device()
+            .limits()
+            .min_uniform_buffer_offset_alignment() as u32;
+
+        // The set uses the whole buffer, so any non-zero offset is out of range.
+        let buffer = CpuAccessibleBuffer::from_data(device.clone(),
+                                                    BufferUsage::all(),
+                                                    iter::once(queue.family()),
+                                                    0.0f32)
+            .unwrap();
+
+        let set = PersistentDescriptorSet::start(layout.clone(), 0)
+            .add_buffer(buffer)
+            .unwrap()
+            .build()
+            .unwrap();
+
+        match check_dynamic_offsets(&layout, &[set], &[alignment]) {
+            Err(CheckDynamicOffsetsError::DynamicOffsetOutOfRange { max_offset: 0, .. }) => (),
+            _ => panic!(),
+        }
+    }
+}
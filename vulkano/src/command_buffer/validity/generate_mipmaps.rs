@@ -0,0 +1,145 @@
+// Copyright (c) 2017 The vulkano developers
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>,
+// at your option. All files in the project carrying such
+// notice may not be copied, modified, or distributed except
+// according to those terms.
+
+use std::error;
+use std::fmt;
+
+use VulkanObject;
+use device::Device;
+use format::FormatTy;
+use image::ImageAccess;
+use image::ImageDimensions;
+
+/// Checks whether a generate mipmaps command is valid, and determines how the mipmaps are going
+/// to be generated.
+///
+/// Only floating-point formats are supported, as integer formats can't be filtered. Blits with a
+/// linear filter are used whenever the format of the image supports them. Otherwise the mipmaps
+/// are generated with a compute shader, which requires a two-dimensional image with the `sampled`
+/// and `storage` usages and the `shader_storage_image_write_without_format` feature.
+///
+/// # Panic
+///
+/// - Panics if the image was not created with `device`.
+///
+pub fn check_generate_mipmaps<I>(device: &Device, image: &I)
+                                 -> Result<CheckGenerateMipmaps, CheckGenerateMipmapsError>
+    where I: ?Sized + ImageAccess
+{
+    assert_eq!(image.inner().image.device().internal_object(),
+               device.internal_object());
+
+    if image.samples() != 1 {
+        return Err(CheckGenerateMipmapsError::Multisampled);
+    }
+
+    // Neither linear blits nor the compute shader can average integer texels.
+    if image.format().ty() != FormatTy::Float {
+        return Err(CheckGenerateMipmapsError::UnsupportedFormat);
+    }
+
+    let inner = image.inner().image;
+
+    if inner.supports_blit_source() && inner.supports_blit_destination() &&
+        inner.supports_linear_filtering()
+    {
+        if !inner.usage_transfer_source() || !inner.usage_transfer_destination() {
+            return Err(CheckGenerateMipmapsError::MissingTransferUsage);
+        }
+
+        return Ok(CheckGenerateMipmaps::Blit);
+    }
+
+    match image.dimensions() {
+        ImageDimensions::Dim2d { .. } => (),
+        _ => return Err(CheckGenerateMipmapsError::UnsupportedDimensions),
+    }
+
+    // The levels are written through a storage image view.
+    if !inner.supports_storage() {
+        return Err(CheckGenerateMipmapsError::UnsupportedFormat);
+    }
+
+    if !inner.usage_sampled() || !inner.usage_storage() {
+        return Err(CheckGenerateMipmapsError::MissingComputeUsage);
+    }
+
+    if !device.enabled_features().shader_storage_image_write_without_format {
+        return Err(CheckGenerateMipmapsError::StorageImageWriteWithoutFormatFeatureNotEnabled);
+    }
+
+    Ok(CheckGenerateMipmaps::Compute)
+}
+
+/// Information returned if `check_generate_mipmaps` succeeds.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CheckGenerateMipmaps {
+    /// Each level is blitted into the next one with a linear filter.
+    Blit,
+    /// Each level is downsampled into the next one with a compute shader.
+    Compute,
+}
+
+/// Error that can happen from `check_generate_mipmaps`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CheckGenerateMipmapsError {
+    /// The image is multisampled.
+    Multisampled,
+    /// The format of the image supports neither linear blits nor the compute shader fallback.
+    UnsupportedFormat,
+    /// The format doesn't support linear blits, and the compute shader fallback only supports
+    /// two-dimensional images.
+    UnsupportedDimensions,
+    /// The format supports linear blits, but the image is missing the transfer source or
+    /// transfer destination usage.
+    MissingTransferUsage,
+    /// The format doesn't support linear blits, and the image is missing the sampled or storage
+    /// usage required by the compute shader fallback.
+    MissingComputeUsage,
+    /// The format doesn't support linear blits, and the
+    /// `shader_storage_image_write_without_format` feature required by the compute shader
+    /// fallback isn't enabled.
+    StorageImageWriteWithoutFormatFeatureNotEnabled,
+}
+
+impl error::Error for CheckGenerateMipmapsError {
+    #[inline]
+    fn description(&self) -> &str {
+        match *self {
+            CheckGenerateMipmapsError::Multisampled => {
+                "the image is multisampled"
+            },
+            CheckGenerateMipmapsError::UnsupportedFormat => {
+                "the format of the image supports neither linear blits nor the compute shader \
+                 fallback"
+            },
+            CheckGenerateMipmapsError::UnsupportedDimensions => {
+                "the format doesn't support linear blits, and the compute shader fallback only \
+                 supports two-dimensional images"
+            },
+            CheckGenerateMipmapsError::MissingTransferUsage => {
+                "the image is missing the transfer source or transfer destination usage"
+            },
+            CheckGenerateMipmapsError::MissingComputeUsage => {
+                "the image is missing the sampled or storage usage"
+            },
+            CheckGenerateMipmapsError::StorageImageWriteWithoutFormatFeatureNotEnabled => {
+                "the `shader_storage_image_write_without_format` feature must be enabled to \
+                 generate mipmaps with a compute shader"
+            },
+        }
+    }
+}
+
+impl fmt::Display for CheckGenerateMipmapsError {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(fmt, "{}", error::Error::description(self))
+    }
+}
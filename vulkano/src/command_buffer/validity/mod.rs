@@ -11,10 +11,14 @@
 
 pub use self::clear_color_image::{check_clear_color_image, CheckClearColorImageError};
 pub use self::copy_buffer::{CheckCopyBufferError, check_copy_buffer, CheckCopyBuffer};
+pub use self::copy_image_to_buffer::{check_copy_image_to_buffer, CheckCopyImageToBuffer};
+pub use self::copy_image_to_buffer::CheckCopyImageToBufferError;
 pub use self::descriptor_sets::{check_descriptor_sets_validity, CheckDescriptorSetsValidityError};
 pub use self::dispatch::{check_dispatch, CheckDispatchError};
+pub use self::dynamic_offsets::{check_dynamic_offsets, CheckDynamicOffsetsError};
 pub use self::dynamic_state::{CheckDynamicStateValidityError, check_dynamic_state_validity};
 pub use self::fill_buffer::{CheckFillBufferError, check_fill_buffer};
+pub use self::generate_mipmaps::{check_generate_mipmaps, CheckGenerateMipmaps, CheckGenerateMipmapsError};
 pub use self::index_buffer::{check_index_buffer, CheckIndexBuffer, CheckIndexBufferError};
 pub use self::push_constants::{check_push_constants_validity, CheckPushConstantsValidityError};
 pub use self::update_buffer::{CheckUpdateBufferError, check_update_buffer};
@@ -22,10 +26,13 @@ pub use self::vertex_buffers::{check_vertex_buffers, CheckVertexBuffer, CheckVer
 
 mod clear_color_image;
 mod copy_buffer;
+mod copy_image_to_buffer;
 mod descriptor_sets;
 mod dispatch;
+mod dynamic_offsets;
 mod dynamic_state;
 mod fill_buffer;
+mod generate_mipmaps;
 mod index_buffer;
 mod push_constants;
 mod update_buffer;
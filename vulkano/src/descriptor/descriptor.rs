@@ -42,9 +42,13 @@
 //!
 
 use format::Format;
+use sampler::Sampler;
 use std::cmp;
 use std::ops::BitOr;
+use std::sync::Arc;
+use sync::PipelineStages;
 use vk;
+use VulkanObject;
 
 /// Contains the exact description of a single descriptor.
 ///
@@ -66,6 +70,14 @@ pub struct DescriptorDesc {
 
     /// True if the attachment is only ever read by the shader. False if it is also written.
     pub readonly: bool,
+
+    /// Samplers that are baked into the descriptor set layout, or an empty list if the
+    /// descriptor doesn't use immutable samplers.
+    ///
+    /// Only valid if `ty` is `Sampler` or `CombinedImageSampler`, in which case the list must
+    /// contain exactly `array_count` samplers. The samplers of such a descriptor never have to be
+    /// written in a descriptor set.
+    pub immutable_samplers: Vec<Arc<Sampler>>,
 }
 
 impl DescriptorDesc {
@@ -78,7 +90,9 @@ impl DescriptorDesc {
     #[inline]
     pub fn is_superset_of(&self, other: &DescriptorDesc) -> bool {
         self.ty.is_superset_of(&other.ty) && self.array_count >= other.array_count &&
-            self.stages.is_superset_of(&other.stages) && (!self.readonly || other.readonly)
+            self.stages.is_superset_of(&other.stages) && (!self.readonly || other.readonly) &&
+            (other.immutable_samplers.is_empty() ||
+                 same_samplers(&self.immutable_samplers, &other.immutable_samplers))
     }
 
     /// Builds a `DescriptorDesc` that is the union of `self` and `other`, if possible.
@@ -92,15 +106,35 @@ impl DescriptorDesc {
             return None;
         }
 
+        let immutable_samplers = if other.immutable_samplers.is_empty() {
+            self.immutable_samplers.clone()
+        } else if self.immutable_samplers.is_empty() ||
+                   same_samplers(&self.immutable_samplers, &other.immutable_samplers)
+        {
+            other.immutable_samplers.clone()
+        } else {
+            return None;
+        };
+
         Some(DescriptorDesc {
                  ty: self.ty.clone(),
                  array_count: cmp::max(self.array_count, other.array_count),
                  stages: self.stages | other.stages,
                  readonly: self.readonly && other.readonly,
+                 immutable_samplers: immutable_samplers,
              })
     }
 }
 
+// Returns true if both lists contain the same sampler objects in the same order.
+#[inline]
+fn same_samplers(a: &[Arc<Sampler>], b: &[Arc<Sampler>]) -> bool {
+    a.len() == b.len() &&
+        a.iter()
+            .zip(b.iter())
+            .all(|(a, b)| a.internal_object() == b.internal_object())
+}
+
 /// Describes the content and layout of each array element of a descriptor.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum DescriptorDescTy {
@@ -455,6 +489,21 @@ impl BitOr for ShaderStages {
     }
 }
 
+impl From<ShaderStages> for PipelineStages {
+    #[inline]
+    fn from(stages: ShaderStages) -> PipelineStages {
+        PipelineStages {
+            vertex_shader: stages.vertex,
+            tessellation_control_shader: stages.tessellation_control,
+            tessellation_evaluation_shader: stages.tessellation_evaluation,
+            geometry_shader: stages.geometry,
+            fragment_shader: stages.fragment,
+            compute_shader: stages.compute,
+            ..PipelineStages::none()
+        }
+    }
+}
+
 #[doc(hidden)]
 impl Into<vk::ShaderStageFlags> for ShaderStages {
     #[inline]
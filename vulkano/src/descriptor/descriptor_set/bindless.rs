@@ -0,0 +1,797 @@
+// Copyright (c) 2017 The vulkano developers
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>,
+// at your option. All files in the project carrying such
+// notice may not be copied, modified, or distributed except
+// according to those terms.
+
+use std::cmp;
+use std::error;
+use std::fmt;
+use std::iter;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::Weak;
+
+use OomError;
+use VulkanObject;
+use buffer::BufferAccess;
+use descriptor::descriptor::DescriptorBufferContentDesc;
+use descriptor::descriptor::DescriptorBufferDesc;
+use descriptor::descriptor::DescriptorDesc;
+use descriptor::descriptor::DescriptorDescTy;
+use descriptor::descriptor::DescriptorImageDesc;
+use descriptor::descriptor::DescriptorImageDescArray;
+use descriptor::descriptor::DescriptorImageDescDimensions;
+use descriptor::descriptor::ShaderStages;
+use descriptor::descriptor_set::DescriptorPoolAllocError;
+use descriptor::descriptor_set::DescriptorSet;
+use descriptor::descriptor_set::DescriptorSetDesc;
+use descriptor::descriptor_set::DescriptorWrite;
+use descriptor::descriptor_set::DescriptorsCount;
+use descriptor::descriptor_set::UnsafeDescriptorPool;
+use descriptor::descriptor_set::UnsafeDescriptorSet;
+use descriptor::descriptor_set::UnsafeDescriptorSetLayout;
+use device::Device;
+use device::DeviceOwned;
+use image::Dimensions;
+use image::ImageAccess;
+use image::ImageLayout;
+use image::ImageViewAccess;
+
+/// Descriptor set that contains a large array of sampled images and a large array of storage
+/// buffers, whose elements can be filled and freed individually after the set has been created.
+///
+/// This is typically used to put all the textures of a scene in a single descriptor set, and to
+/// refer to them by an integer index in the shaders. Adding a resource to the table returns a
+/// handle that contains the index of the resource within its array.
+///
+/// The sampled images are at binding 0 of the set, and must be two-dimensional non-arrayed
+/// images. The storage buffers are at binding 1 of the set. In GLSL, this corresponds to
+/// something like this:
+///
+/// ```notrust
+/// layout(set = 0, binding = 0) uniform texture2D textures[];
+/// layout(set = 0, binding = 1) buffer Data { float data[]; } buffers[];
+/// ```
+///
+/// # Requirements
+///
+/// The `VK_EXT_descriptor_indexing` extension, the `descriptor_binding_partially_bound` and
+/// `descriptor_binding_update_unused_while_pending` features, and the
+/// `descriptor_binding_sampled_image_update_after_bind` and/or
+/// `descriptor_binding_storage_buffer_update_after_bind` features must have been enabled on the
+/// device. Your shaders will most likely also need the `runtime_descriptor_array` feature.
+///
+/// # Usage
+///
+/// The layout of the table must be part of the pipeline layout of your pipelines. Use
+/// `with_bindless_table` on the description of the pipeline layout to do that.
+///
+/// Modifying the table doesn't affect the command buffers that use it. To use the table in a
+/// command buffer, call `snapshot` and pass the snapshot to the draw or dispatch command. The
+/// snapshot keeps alive the resources that were in the table when it was created.
+///
+/// The index of a resource that has been removed is only reused once all the snapshots that
+/// contain this resource have been destroyed. Therefore the descriptors that the device may
+/// still be reading are never modified.
+///
+/// # Example
+///
+/// ```
+/// # use std::sync::Arc;
+/// # use vulkano::device::Device;
+/// # use vulkano::image::ImmutableImage;
+/// # use vulkano::format::R8G8B8A8Unorm;
+/// use vulkano::descriptor::descriptor::ShaderStages;
+/// use vulkano::descriptor::descriptor_set::BindlessTable;
+///
+/// # let device: Arc<Device> = return;
+/// # let texture: Arc<ImmutableImage<R8G8B8A8Unorm>> = return;
+/// let table = BindlessTable::new(device.clone(), ShaderStages::all(), 4096, 0).unwrap();
+///
+/// // The index to pass to the shader, for example with push constants.
+/// let texture_id = table.add_image(texture.clone()).unwrap();
+/// let index = texture_id.index();
+///
+/// // Then for each command buffer:
+/// let set = table.snapshot();
+/// ```
+pub struct BindlessTable {
+    // The layout of the set.
+    layout: Arc<UnsafeDescriptorSetLayout>,
+    // The set, written to while `state` is locked.
+    set: UnsafeDescriptorSet,
+    // Pool the set was allocated from. Destroying the pool frees the set.
+    pool: UnsafeDescriptorPool,
+    // Stages that can access the descriptors.
+    stages: ShaderStages,
+    // Number of elements of the images array.
+    images_capacity: u32,
+    // Number of elements of the buffers array.
+    buffers_capacity: u32,
+    // The content of the table.
+    state: Mutex<BindlessTableState>,
+}
+
+struct BindlessTableState {
+    // The images of the table, indexed by their element in the array.
+    images: Vec<Option<Arc<ImageViewAccess + Send + Sync>>>,
+    // Removed images whose index can be reused once all the snapshots containing them are gone.
+    removed_images: Vec<(u32, Weak<ImageViewAccess + Send + Sync>)>,
+    // Indices of the images array that can be written.
+    free_images: Vec<u32>,
+    // Same as above, for buffers.
+    buffers: Vec<Option<Arc<BufferAccess + Send + Sync>>>,
+    removed_buffers: Vec<(u32, Weak<BufferAccess + Send + Sync>)>,
+    free_buffers: Vec<u32>,
+    // The written images and buffers, shared by the snapshots that have been created since the
+    // last modification of the table. `None` if the table has been modified since then.
+    snapshot: Option<(Arc<Vec<Arc<ImageViewAccess + Send + Sync>>>,
+                      Arc<Vec<Arc<BufferAccess + Send + Sync>>>)>,
+}
+
+impl BindlessTable {
+    /// Builds a new table whose arrays have the given number of elements. The descriptors are
+    /// accessible from `stages`.
+    ///
+    /// If the capacity of an array is 0, then the corresponding binding is left empty.
+    ///
+    /// Returns an error if the capacities exceed the update-after-bind limits of the physical
+    /// device. These limits are only known if the `khr_get_physical_device_properties2`
+    /// extension was enabled on the instance.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if both capacities are 0.
+    ///
+    pub fn new(device: Arc<Device>, stages: ShaderStages, images_capacity: u32,
+               buffers_capacity: u32)
+               -> Result<Arc<BindlessTable>, BindlessTableCreationError> {
+        assert!(images_capacity != 0 || buffers_capacity != 0);
+
+        if !device.loaded_extensions().ext_descriptor_indexing {
+            return Err(BindlessTableCreationError::ExtensionNotEnabled);
+        }
+
+        {
+            let features = device.enabled_features();
+            if !features.descriptor_binding_partially_bound {
+                return Err(BindlessTableCreationError::PartiallyBoundFeatureNotEnabled);
+            }
+            if !features.descriptor_binding_update_unused_while_pending {
+                return Err(BindlessTableCreationError::UpdateUnusedWhilePendingFeatureNotEnabled);
+            }
+            if images_capacity != 0 &&
+                !features.descriptor_binding_sampled_image_update_after_bind
+            {
+                let err = BindlessTableCreationError::SampledImageUpdateAfterBindFeatureNotEnabled;
+                return Err(err);
+            }
+            if buffers_capacity != 0 &&
+                !features.descriptor_binding_storage_buffer_update_after_bind
+            {
+                let err = BindlessTableCreationError::StorageBufferUpdateAfterBindFeatureNotEnabled;
+                return Err(err);
+            }
+        }
+
+        // The limits are unknown, and thus considered to be 0, if the properties of
+        // `VK_EXT_descriptor_indexing` couldn't be queried.
+        {
+            let limits = device.physical_device().limits();
+
+            let max_images =
+                cmp::min(limits.max_descriptor_set_update_after_bind_sampled_images(),
+                         limits.max_per_stage_descriptor_update_after_bind_sampled_images());
+            if images_capacity > max_images.unwrap_or(0) {
+                return Err(BindlessTableCreationError::MaxSampledImagesLimitExceeded);
+            }
+
+            let max_buffers =
+                cmp::min(limits.max_descriptor_set_update_after_bind_storage_buffers(),
+                         limits.max_per_stage_descriptor_update_after_bind_storage_buffers());
+            if buffers_capacity > max_buffers.unwrap_or(0) {
+                return Err(BindlessTableCreationError::MaxStorageBuffersLimitExceeded);
+            }
+
+            let max_resources = cmp::min(limits.max_per_stage_update_after_bind_resources(),
+                                         limits.max_update_after_bind_descriptors_in_all_pools());
+            if images_capacity as u64 + buffers_capacity as u64 >
+                max_resources.unwrap_or(0) as u64
+            {
+                return Err(BindlessTableCreationError::MaxResourcesLimitExceeded);
+            }
+        }
+
+        let layout = {
+            let descs = (0 .. 2).map(|binding| descriptor(stages, images_capacity,
+                                                          buffers_capacity, binding));
+            Arc::new(UnsafeDescriptorSetLayout::new_update_after_bind(device.clone(), descs)?)
+        };
+
+        let count = DescriptorsCount {
+            sampled_image: images_capacity,
+            storage_buffer: buffers_capacity,
+            ..DescriptorsCount::zero()
+        };
+
+        let mut pool = UnsafeDescriptorPool::new_update_after_bind(device.clone(), &count, 1)?;
+
+        let set = unsafe {
+            match pool.alloc(iter::once(&*layout)) {
+                Ok(mut sets) => sets.next().unwrap(),
+                Err(DescriptorPoolAllocError::OutOfHostMemory) => {
+                    return Err(BindlessTableCreationError::OomError(OomError::OutOfHostMemory));
+                },
+                Err(DescriptorPoolAllocError::OutOfDeviceMemory) => {
+                    return Err(BindlessTableCreationError::OomError(OomError::OutOfDeviceMemory));
+                },
+                // The implementation couldn't fit the set in a pool that is sized for it.
+                Err(DescriptorPoolAllocError::FragmentedPool) |
+                Err(DescriptorPoolAllocError::OutOfPoolMemory) => {
+                    return Err(BindlessTableCreationError::OomError(OomError::OutOfDeviceMemory));
+                },
+            }
+        };
+
+        Ok(Arc::new(BindlessTable {
+                        layout: layout,
+                        set: set,
+                        pool: pool,
+                        stages: stages,
+                        images_capacity: images_capacity,
+                        buffers_capacity: buffers_capacity,
+                        state: Mutex::new(BindlessTableState {
+                                              images: vec![None; images_capacity as usize],
+                                              removed_images: Vec::new(),
+                                              free_images: (0 .. images_capacity).rev().collect(),
+                                              buffers: vec![None; buffers_capacity as usize],
+                                              removed_buffers: Vec::new(),
+                                              free_buffers: (0 .. buffers_capacity)
+                                                  .rev()
+                                                  .collect(),
+                                              snapshot: None,
+                                          }),
+                    }))
+    }
+
+    /// Returns the layout of the set. The pipeline layout of the pipelines that use the table
+    /// must use this layout.
+    #[inline]
+    pub fn layout(&self) -> &Arc<UnsafeDescriptorSetLayout> {
+        &self.layout
+    }
+
+    /// Returns the number of elements of the images array.
+    #[inline]
+    pub fn images_capacity(&self) -> u32 {
+        self.images_capacity
+    }
+
+    /// Returns the number of elements of the buffers array.
+    #[inline]
+    pub fn buffers_capacity(&self) -> u32 {
+        self.buffers_capacity
+    }
+
+    /// Returns the description of a binding of the table.
+    #[inline]
+    pub fn descriptor(&self, binding: usize) -> Option<DescriptorDesc> {
+        descriptor(self.stages, self.images_capacity, self.buffers_capacity, binding)
+    }
+
+    /// Writes an image to a free element of the images array, and returns its index.
+    ///
+    /// The image must be a two-dimensional, non-arrayed and non-multisampled image that can be
+    /// sampled.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if the image doesn't belong to the same device as the table.
+    ///
+    pub fn add_image<T>(&self, image: T) -> Result<BindlessImageIndex, BindlessTableError>
+        where T: ImageViewAccess + Send + Sync + 'static
+    {
+        assert_eq!(self.layout.device().internal_object(),
+                   image.parent().inner().image.device().internal_object());
+
+        if !image.parent().inner().image.usage_sampled() {
+            return Err(BindlessTableError::MissingUsage);
+        }
+
+        match image.dimensions() {
+            Dimensions::Dim2d { .. } if image.samples() == 1 => (),
+            _ => return Err(BindlessTableError::ImageViewTypeMismatch),
+        }
+
+        match image.descriptor_set_sampled_image_layout() {
+            ImageLayout::General |
+            ImageLayout::ShaderReadOnlyOptimal |
+            ImageLayout::DepthStencilReadOnlyOptimal => (),
+            layout => return Err(BindlessTableError::InvalidImageLayout { obtained: layout }),
+        }
+
+        let mut state = self.state.lock().unwrap();
+
+        let index = {
+            let state = &mut *state;
+            match next_free(&mut state.free_images, &mut state.removed_images) {
+                Some(index) => index,
+                None => return Err(BindlessTableError::Full),
+            }
+        };
+
+        unsafe {
+            let write = DescriptorWrite::sampled_image(0, index, &image);
+            self.set.alias().write(self.layout.device(), iter::once(write));
+        }
+
+        state.images[index as usize] = Some(Arc::new(image));
+        state.snapshot = None;
+        Ok(BindlessImageIndex(index))
+    }
+
+    /// Removes an image from the table, and returns it.
+    ///
+    /// The index can be reused by another image once all the snapshots that contain this image
+    /// have been destroyed.
+    pub fn remove_image(&self, index: BindlessImageIndex)
+                        -> Option<Arc<ImageViewAccess + Send + Sync>> {
+        let mut state = self.state.lock().unwrap();
+        let image = match state.images.get_mut(index.0 as usize) {
+            Some(slot) => slot.take(),
+            None => None,
+        };
+
+        if let Some(ref image) = image {
+            state.removed_images.push((index.0, Arc::downgrade(image)));
+            state.snapshot = None;
+        }
+
+        image
+    }
+
+    /// Writes a buffer to a free element of the buffers array, and returns its index.
+    ///
+    /// The buffer must have the storage buffer usage.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if the buffer doesn't belong to the same device as the table.
+    ///
+    pub fn add_buffer<T>(&self, buffer: T) -> Result<BindlessBufferIndex, BindlessTableError>
+        where T: BufferAccess + Send + Sync + 'static
+    {
+        assert_eq!(self.layout.device().internal_object(),
+                   buffer.inner().buffer.device().internal_object());
+
+        if !buffer.inner().buffer.usage_storage_buffer() {
+            return Err(BindlessTableError::MissingUsage);
+        }
+
+        let mut state = self.state.lock().unwrap();
+
+        let index = {
+            let state = &mut *state;
+            match next_free(&mut state.free_buffers, &mut state.removed_buffers) {
+                Some(index) => index,
+                None => return Err(BindlessTableError::Full),
+            }
+        };
+
+        unsafe {
+            let write = DescriptorWrite::storage_buffer(1, index, &buffer);
+            self.set.alias().write(self.layout.device(), iter::once(write));
+        }
+
+        state.buffers[index as usize] = Some(Arc::new(buffer));
+        state.snapshot = None;
+        Ok(BindlessBufferIndex(index))
+    }
+
+    /// Removes a buffer from the table, and returns it.
+    ///
+    /// The index can be reused by another buffer once all the snapshots that contain this buffer
+    /// have been destroyed.
+    pub fn remove_buffer(&self, index: BindlessBufferIndex)
+                         -> Option<Arc<BufferAccess + Send + Sync>> {
+        let mut state = self.state.lock().unwrap();
+        let buffer = match state.buffers.get_mut(index.0 as usize) {
+            Some(slot) => slot.take(),
+            None => None,
+        };
+
+        if let Some(ref buffer) = buffer {
+            state.removed_buffers.push((index.0, Arc::downgrade(buffer)));
+            state.snapshot = None;
+        }
+
+        buffer
+    }
+
+    /// Builds a descriptor set that can be passed to a draw or dispatch command, and that holds
+    /// the current content of the table.
+    ///
+    /// The snapshot only holds the elements of the arrays that have been written. The list of
+    /// these elements is built on the first call after the table has been modified, and is then
+    /// shared by the next snapshots.
+    pub fn snapshot(self: &Arc<Self>) -> BindlessTableSnapshot {
+        let mut state = self.state.lock().unwrap();
+
+        let (images, buffers) = {
+            let state = &mut *state;
+            let images = &state.images;
+            let buffers = &state.buffers;
+            state
+                .snapshot
+                .get_or_insert_with(|| {
+                    let images = images.iter().filter_map(|image| image.clone()).collect();
+                    let buffers = buffers.iter().filter_map(|buffer| buffer.clone()).collect();
+                    (Arc::new(images), Arc::new(buffers))
+                })
+                .clone()
+        };
+
+        BindlessTableSnapshot {
+            table: self.clone(),
+            images: images,
+            buffers: buffers,
+        }
+    }
+}
+
+unsafe impl DeviceOwned for BindlessTable {
+    #[inline]
+    fn device(&self) -> &Arc<Device> {
+        self.layout.device()
+    }
+}
+
+impl fmt::Debug for BindlessTable {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        fmt.debug_struct("BindlessTable")
+            .field("layout", &self.layout)
+            .field("set", &self.set)
+            .field("pool", &self.pool)
+            .field("images_capacity", &self.images_capacity)
+            .field("buffers_capacity", &self.buffers_capacity)
+            .finish()
+    }
+}
+
+// Returns the description of a binding of a table.
+fn descriptor(stages: ShaderStages, images_capacity: u32, buffers_capacity: u32, binding: usize)
+              -> Option<DescriptorDesc> {
+    match binding {
+        0 if images_capacity != 0 => {
+            let desc = DescriptorImageDesc {
+                sampled: true,
+                dimensions: DescriptorImageDescDimensions::TwoDimensional,
+                format: None,
+                multisampled: false,
+                array_layers: DescriptorImageDescArray::NonArrayed,
+            };
+
+            Some(DescriptorDesc {
+                     ty: DescriptorDescTy::Image(desc),
+                     array_count: images_capacity,
+                     stages: stages,
+                     readonly: true,
+                     immutable_samplers: Vec::new(),
+                 })
+        },
+        1 if buffers_capacity != 0 => {
+            Some(DescriptorDesc {
+                     ty: DescriptorDescTy::Buffer(DescriptorBufferDesc {
+                                                      dynamic: Some(false),
+                                                      storage: true,
+                                                      content: DescriptorBufferContentDesc::F32,
+                                                  }),
+                     array_count: buffers_capacity,
+                     stages: stages,
+                     readonly: false,
+                     immutable_samplers: Vec::new(),
+                 })
+        },
+        _ => None,
+    }
+}
+
+// Returns an index that can be written, or `None` if the array is full.
+fn next_free<T: ?Sized>(free: &mut Vec<u32>, removed: &mut Vec<(u32, Weak<T>)>) -> Option<u32> {
+    if free.is_empty() {
+        // The indices of removed resources that are no longer in any snapshot can be reused.
+        removed.retain(|&(index, ref weak)| if weak.upgrade().is_none() {
+                           free.push(index);
+                           false
+                       } else {
+                           true
+                       });
+    }
+
+    free.pop()
+}
+
+/// Index of an image within a `BindlessTable`.
+#[derive(Debug, PartialEq, Eq, Hash)]
+pub struct BindlessImageIndex(u32);
+
+impl BindlessImageIndex {
+    /// Returns the index of the image within the images array.
+    #[inline]
+    pub fn index(&self) -> u32 {
+        self.0
+    }
+}
+
+/// Index of a buffer within a `BindlessTable`.
+#[derive(Debug, PartialEq, Eq, Hash)]
+pub struct BindlessBufferIndex(u32);
+
+impl BindlessBufferIndex {
+    /// Returns the index of the buffer within the buffers array.
+    #[inline]
+    pub fn index(&self) -> u32 {
+        self.0
+    }
+}
+
+/// Descriptor set that holds the content of a `BindlessTable` at the time of its creation.
+///
+/// See `BindlessTable::snapshot`.
+pub struct BindlessTableSnapshot {
+    table: Arc<BindlessTable>,
+    // The written elements of the arrays of the table.
+    images: Arc<Vec<Arc<ImageViewAccess + Send + Sync>>>,
+    buffers: Arc<Vec<Arc<BufferAccess + Send + Sync>>>,
+}
+
+impl BindlessTableSnapshot {
+    /// Returns the table this snapshot was created from.
+    #[inline]
+    pub fn table(&self) -> &Arc<BindlessTable> {
+        &self.table
+    }
+}
+
+unsafe impl DescriptorSet for BindlessTableSnapshot {
+    #[inline]
+    fn inner(&self) -> &UnsafeDescriptorSet {
+        &self.table.set
+    }
+
+    #[inline]
+    fn buffers_list<'a>(&'a self) -> Box<Iterator<Item = &'a BufferAccess> + 'a> {
+        Box::new(self.buffers.iter().map(|buffer| &**buffer as &BufferAccess))
+    }
+
+    #[inline]
+    fn images_list<'a>(&'a self) -> Box<Iterator<Item = &'a ImageAccess> + 'a> {
+        Box::new(self.images.iter().map(|image| image.parent()))
+    }
+
+    #[inline]
+    fn image_views<'a>(&'a self) -> Box<Iterator<Item = (usize, &'a ImageViewAccess)> + 'a> {
+        Box::new(self.images
+                     .iter()
+                     .map(|image| (0, &**image as &ImageViewAccess)))
+    }
+}
+
+unsafe impl DescriptorSetDesc for BindlessTableSnapshot {
+    #[inline]
+    fn num_bindings(&self) -> usize {
+        2
+    }
+
+    #[inline]
+    fn descriptor(&self, binding: usize) -> Option<DescriptorDesc> {
+        self.table.descriptor(binding)
+    }
+}
+
+unsafe impl DeviceOwned for BindlessTableSnapshot {
+    #[inline]
+    fn device(&self) -> &Arc<Device> {
+        self.table.device()
+    }
+}
+
+/// Error that can happen when creating a `BindlessTable`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BindlessTableCreationError {
+    /// Not enough memory.
+    OomError(OomError),
+
+    /// The `VK_EXT_descriptor_indexing` extension was not enabled.
+    ExtensionNotEnabled,
+
+    /// The `descriptor_binding_partially_bound` feature was not enabled.
+    PartiallyBoundFeatureNotEnabled,
+
+    /// The `descriptor_binding_update_unused_while_pending` feature was not enabled.
+    UpdateUnusedWhilePendingFeatureNotEnabled,
+
+    /// The table contains images, but the `descriptor_binding_sampled_image_update_after_bind`
+    /// feature was not enabled.
+    SampledImageUpdateAfterBindFeatureNotEnabled,
+
+    /// The table contains buffers, but the `descriptor_binding_storage_buffer_update_after_bind`
+    /// feature was not enabled.
+    StorageBufferUpdateAfterBindFeatureNotEnabled,
+
+    /// The capacity of the images array exceeds the limits of update-after-bind sampled images.
+    MaxSampledImagesLimitExceeded,
+
+    /// The capacity of the buffers array exceeds the limits of update-after-bind storage buffers.
+    MaxStorageBuffersLimitExceeded,
+
+    /// The total capacity of the arrays exceeds the limits of update-after-bind resources.
+    MaxResourcesLimitExceeded,
+}
+
+impl error::Error for BindlessTableCreationError {
+    #[inline]
+    fn description(&self) -> &str {
+        match *self {
+            BindlessTableCreationError::OomError(_) => "not enough memory available",
+            BindlessTableCreationError::ExtensionNotEnabled =>
+                "the `VK_EXT_descriptor_indexing` extension was not enabled",
+            BindlessTableCreationError::PartiallyBoundFeatureNotEnabled =>
+                "the `descriptor_binding_partially_bound` feature was not enabled",
+            BindlessTableCreationError::UpdateUnusedWhilePendingFeatureNotEnabled =>
+                "the `descriptor_binding_update_unused_while_pending` feature was not enabled",
+            BindlessTableCreationError::SampledImageUpdateAfterBindFeatureNotEnabled =>
+                "the `descriptor_binding_sampled_image_update_after_bind` feature was not enabled",
+            BindlessTableCreationError::StorageBufferUpdateAfterBindFeatureNotEnabled =>
+                "the `descriptor_binding_storage_buffer_update_after_bind` feature was not enabled",
+            BindlessTableCreationError::MaxSampledImagesLimitExceeded =>
+                "the capacity of the images array exceeds the limits of update-after-bind sampled \
+                 images",
+            BindlessTableCreationError::MaxStorageBuffersLimitExceeded =>
+                "the capacity of the buffers array exceeds the limits of update-after-bind \
+                 storage buffers",
+            BindlessTableCreationError::MaxResourcesLimitExceeded =>
+                "the total capacity of the arrays exceeds the limits of update-after-bind \
+                 resources",
+        }
+    }
+
+    #[inline]
+    fn cause(&self) -> Option<&error::Error> {
+        match *self {
+            BindlessTableCreationError::OomError(ref err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for BindlessTableCreationError {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(fmt, "{}", error::Error::description(self))
+    }
+}
+
+impl From<OomError> for BindlessTableCreationError {
+    #[inline]
+    fn from(err: OomError) -> BindlessTableCreationError {
+        BindlessTableCreationError::OomError(err)
+    }
+}
+
+/// Error that can happen when adding a resource to a `BindlessTable`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum BindlessTableError {
+    /// All the elements of the array are in use.
+    Full,
+
+    /// The resource is missing a usage flag.
+    MissingUsage,
+
+    /// The image view isn't a two-dimensional, non-arrayed and non-multisampled view.
+    ImageViewTypeMismatch,
+
+    /// The image view isn't in the correct layout.
+    InvalidImageLayout {
+        /// The layout that was obtained.
+        obtained: ImageLayout,
+    },
+}
+
+impl error::Error for BindlessTableError {
+    #[inline]
+    fn description(&self) -> &str {
+        match *self {
+            BindlessTableError::Full => "all the elements of the array are in use",
+            BindlessTableError::MissingUsage => "the resource is missing a usage flag",
+            BindlessTableError::ImageViewTypeMismatch =>
+                "the image view isn't a two-dimensional, non-arrayed and non-multisampled view",
+            BindlessTableError::InvalidImageLayout { .. } =>
+                "the image view isn't in the correct layout",
+        }
+    }
+}
+
+impl fmt::Display for BindlessTableError {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(fmt, "{}", error::Error::description(self))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use buffer::BufferUsage;
+    use buffer::CpuAccessibleBuffer;
+    use descriptor::descriptor::ShaderStages;
+    use descriptor::descriptor_set::BindlessTable;
+    use descriptor::descriptor_set::BindlessTableCreationError;
+    use descriptor::descriptor_set::BindlessTableError;
+    use descriptor::descriptor_set::DescriptorSet;
+    use std::iter;
+
+    #[test]
+    fn check_extension_when_create() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        match BindlessTable::new(device, ShaderStages::all(), 16, 16) {
+            Err(BindlessTableCreationError::ExtensionNotEnabled) => (),
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn zero_capacity() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        assert_should_panic!({
+            let _ = BindlessTable::new(device, ShaderStages::all(), 0, 0);
+        });
+    }
+
+    #[test]
+    fn add_and_remove_buffers() {
+        let (device, queue) = gfx_dev_and_queue!(
+            extensions: [ext_descriptor_indexing, khr_maintenance3];
+            descriptor_binding_partially_bound,
+            descriptor_binding_update_unused_while_pending,
+            descriptor_binding_storage_buffer_update_after_bind);
+
+        match BindlessTable::new(device.clone(), ShaderStages::all(), 0, u32::max_value()) {
+            Err(BindlessTableCreationError::MaxStorageBuffersLimitExceeded) => (),
+            _ => panic!(),
+        }
+
+        let table = BindlessTable::new(device.clone(), ShaderStages::all(), 0, 2).unwrap();
+        let buffer = || {
+            let usage = BufferUsage {
+                storage_buffer: true,
+                ..BufferUsage::none()
+            };
+            CpuAccessibleBuffer::from_data(device.clone(), usage, iter::once(queue.family()), 0u32)
+                .unwrap()
+        };
+
+        let first = table.add_buffer(buffer()).unwrap();
+        let second = table.add_buffer(buffer()).unwrap();
+        assert!(first.index() != second.index());
+        assert_eq!(table.add_buffer(buffer()), Err(BindlessTableError::Full));
+
+        // Snapshots only contain the buffers that were in the table when they were created.
+        let snapshot = table.snapshot();
+        assert!(table.remove_buffer(first).is_some());
+        assert_eq!(snapshot.buffers_list().count(), 2);
+        assert_eq!(table.snapshot().buffers_list().count(), 1);
+
+        // The index of the removed buffer is reused once the snapshot is gone.
+        assert_eq!(table.add_buffer(buffer()), Err(BindlessTableError::Full));
+        drop(snapshot);
+        let third = table.add_buffer(buffer()).unwrap();
+        assert!(third.index() != second.index());
+        assert_eq!(table.snapshot().buffers_list().count(), 2);
+    }
+}
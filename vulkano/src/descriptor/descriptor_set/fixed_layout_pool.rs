@@ -0,0 +1,313 @@
+// Copyright (c) 2017 The vulkano developers
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>,
+// at your option. All files in the project carrying such
+// notice may not be copied, modified, or distributed except
+// according to those terms.
+
+use std::cmp;
+use std::iter;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+use OomError;
+use VulkanObject;
+use descriptor::descriptor_set::DescriptorPool;
+use descriptor::descriptor_set::DescriptorPoolAlloc;
+use descriptor::descriptor_set::DescriptorPoolAllocError;
+use descriptor::descriptor_set::UnsafeDescriptorPool;
+use descriptor::descriptor_set::UnsafeDescriptorSet;
+use descriptor::descriptor_set::UnsafeDescriptorSetLayout;
+use device::Device;
+use device::DeviceOwned;
+
+// Number of sets allocated by the first batch. Each new batch is twice as large as the previous
+// one, up to `MAX_BATCH_SIZE`.
+const FIRST_BATCH_SIZE: u32 = 16;
+const MAX_BATCH_SIZE: u32 = 1024;
+
+/// Descriptor pool that only allocates descriptor sets of a single layout.
+///
+/// Contrary to `StdDescriptorPool`, this pool never frees individual descriptor sets. Instead it
+/// allocates sets in batches from Vulkan pools that are sized exactly for the layout, and keeps
+/// the sets that are no longer used in a free list. Allocating a set is usually just a matter of
+/// popping from this list, which makes this pool suitable for creating descriptor sets every
+/// frame or for every draw command.
+///
+/// The pool can work in two modes:
+///
+/// - In the mode created with `new`, a set is put back in the free list as soon as its
+///   `FixedLayoutDescriptorPoolAlloc` is destroyed.
+/// - In the mode created with `per_frame`, destroying an allocation doesn't do anything. Instead
+///   you are expected to call `reset` once per frame, which makes all the sets available again at
+///   once by resetting the Vulkan pools.
+///
+/// # Example
+///
+/// ```
+/// # use std::sync::Arc;
+/// # use vulkano::descriptor::pipeline_layout::PipelineLayoutAbstract;
+/// use vulkano::descriptor::descriptor_set::FixedLayoutDescriptorPool;
+/// use vulkano::descriptor::descriptor_set::PersistentDescriptorSet;
+///
+/// # let layout: Arc<PipelineLayoutAbstract + Send + Sync> = return;
+/// let set_layout = layout.descriptor_set_layout(0).unwrap().clone();
+/// let pool = Arc::new(FixedLayoutDescriptorPool::new(set_layout));
+///
+/// // Then for each draw command:
+/// let set = PersistentDescriptorSet::start(layout.clone(), 0)
+///     // .add_buffer(...) etc.
+///     .build_with_pool(&pool);
+/// ```
+pub struct FixedLayoutDescriptorPool {
+    device: Arc<Device>,
+    layout: Arc<UnsafeDescriptorSetLayout>,
+    // If true, the sets are put back in the free list when their allocation is destroyed.
+    recycle: bool,
+    state: Mutex<State>,
+}
+
+struct State {
+    // The Vulkan pools, with the number of sets that each of them can hold.
+    pools: Vec<(UnsafeDescriptorPool, u32)>,
+    // Index within `pools` of the next pool to allocate a batch from. All the pools before this
+    // one are full. After a reset, the existing pools are reused before new ones are created.
+    next_pool: usize,
+    // Sets that have been allocated from the Vulkan pools and that are not in use.
+    free: Vec<UnsafeDescriptorSet>,
+}
+
+impl FixedLayoutDescriptorPool {
+    /// Builds a new pool that allocates descriptor sets of the given layout, and that recycles
+    /// the sets whose allocation has been destroyed.
+    #[inline]
+    pub fn new(layout: Arc<UnsafeDescriptorSetLayout>) -> FixedLayoutDescriptorPool {
+        FixedLayoutDescriptorPool::with_mode(layout, true)
+    }
+
+    /// Builds a new pool that allocates descriptor sets of the given layout, and whose sets only
+    /// become available again when `reset` is called.
+    #[inline]
+    pub fn per_frame(layout: Arc<UnsafeDescriptorSetLayout>) -> FixedLayoutDescriptorPool {
+        FixedLayoutDescriptorPool::with_mode(layout, false)
+    }
+
+    fn with_mode(layout: Arc<UnsafeDescriptorSetLayout>, recycle: bool)
+                 -> FixedLayoutDescriptorPool {
+        FixedLayoutDescriptorPool {
+            device: layout.device().clone(),
+            layout: layout,
+            recycle: recycle,
+            state: Mutex::new(State {
+                                  pools: Vec::new(),
+                                  next_pool: 0,
+                                  free: Vec::new(),
+                              }),
+        }
+    }
+
+    /// Returns the layout of the descriptor sets allocated by this pool.
+    #[inline]
+    pub fn layout(&self) -> &Arc<UnsafeDescriptorSetLayout> {
+        &self.layout
+    }
+
+    /// Makes all the descriptor sets allocated by this pool available again.
+    ///
+    /// This resets the underlying Vulkan pools, which is cheaper than recycling the sets one by
+    /// one. Since each allocation holds a reference to the pool, taking `&mut self` guarantees
+    /// that none of the sets are still in use. With an `Arc<FixedLayoutDescriptorPool>`, use
+    /// `Arc::get_mut` once all the sets of the previous frame have been destroyed.
+    pub fn reset(&mut self) -> Result<(), OomError> {
+        let state = self.state.get_mut().unwrap();
+
+        // The sets of the free list are destroyed along with the ones in use.
+        state.free.clear();
+
+        for &mut (ref mut pool, _) in state.pools[.. state.next_pool].iter_mut() {
+            unsafe {
+                pool.reset()?;
+            }
+        }
+
+        state.next_pool = 0;
+        Ok(())
+    }
+}
+
+impl State {
+    // Allocates a new batch of sets and puts them in the free list. Reuses a pool that has been
+    // reset if there is one, otherwise creates a new pool.
+    fn alloc_batch(&mut self, device: &Arc<Device>, layout: &UnsafeDescriptorSetLayout)
+                   -> Result<(), OomError> {
+        loop {
+            let new_pool = self.next_pool == self.pools.len();
+            if new_pool {
+                let size = match self.pools.last() {
+                    Some(&(_, size)) => cmp::min(size * 2, MAX_BATCH_SIZE),
+                    None => FIRST_BATCH_SIZE,
+                };
+
+                let count = layout.descriptors_count().clone() * size;
+                let pool = UnsafeDescriptorPool::new(device.clone(), &count, size, false)?;
+                self.pools.push((pool, size));
+            }
+
+            let (ref mut pool, size) = self.pools[self.next_pool];
+            self.next_pool += 1;
+
+            unsafe {
+                match pool.alloc(iter::repeat(layout).take(size as usize)) {
+                    Ok(sets) => {
+                        self.free.extend(sets);
+                        return Ok(());
+                    },
+                    Err(DescriptorPoolAllocError::OutOfHostMemory) => {
+                        return Err(OomError::OutOfHostMemory);
+                    },
+                    Err(DescriptorPoolAllocError::OutOfDeviceMemory) => {
+                        return Err(OomError::OutOfDeviceMemory);
+                    },
+                    // The implementation couldn't fit the batch in a pool that is sized for it.
+                    // Retrying would fail the same way.
+                    Err(DescriptorPoolAllocError::FragmentedPool) |
+                    Err(DescriptorPoolAllocError::OutOfPoolMemory) if new_pool => {
+                        return Err(OomError::OutOfDeviceMemory);
+                    },
+                    // A pool that has been reset can still be fragmented. We skip it, like
+                    // `StdDescriptorPool` does, and allocate from the next one or from a new one.
+                    // It will be reset again along with the others.
+                    Err(DescriptorPoolAllocError::FragmentedPool) |
+                    Err(DescriptorPoolAllocError::OutOfPoolMemory) => (),
+                }
+            }
+        }
+    }
+}
+
+unsafe impl DescriptorPool for Arc<FixedLayoutDescriptorPool> {
+    type Alloc = FixedLayoutDescriptorPoolAlloc;
+
+    /// Allocates a descriptor set.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if `layout` is not the layout that was passed when creating the pool.
+    ///
+    fn alloc(&self, layout: &UnsafeDescriptorSetLayout)
+             -> Result<FixedLayoutDescriptorPoolAlloc, OomError> {
+        assert_eq!(layout.internal_object(),
+                   self.layout.internal_object(),
+                   "Tried to allocate from a fixed layout pool with a different layout");
+
+        let mut state = self.state.lock().unwrap();
+
+        if state.free.is_empty() {
+            state.alloc_batch(&self.device, &self.layout)?;
+        }
+
+        Ok(FixedLayoutDescriptorPoolAlloc {
+               pool: self.clone(),
+               set: state.free.pop(),
+           })
+    }
+}
+
+unsafe impl DeviceOwned for FixedLayoutDescriptorPool {
+    #[inline]
+    fn device(&self) -> &Arc<Device> {
+        &self.device
+    }
+}
+
+/// A descriptor set allocated from a `FixedLayoutDescriptorPool`.
+pub struct FixedLayoutDescriptorPoolAlloc {
+    pool: Arc<FixedLayoutDescriptorPool>,
+    // The set. Inside an option so that we can extract it in the destructor.
+    set: Option<UnsafeDescriptorSet>,
+}
+
+impl DescriptorPoolAlloc for FixedLayoutDescriptorPoolAlloc {
+    #[inline]
+    fn inner(&self) -> &UnsafeDescriptorSet {
+        self.set.as_ref().unwrap()
+    }
+
+    #[inline]
+    fn inner_mut(&mut self) -> &mut UnsafeDescriptorSet {
+        self.set.as_mut().unwrap()
+    }
+}
+
+impl Drop for FixedLayoutDescriptorPoolAlloc {
+    // In per-frame mode, the set is only made available again when the pool is reset.
+    fn drop(&mut self) {
+        if self.pool.recycle {
+            let mut state = self.pool.state.lock().unwrap();
+            state.free.push(self.set.take().unwrap());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use VulkanObject;
+    use descriptor::descriptor_set::DescriptorPool;
+    use descriptor::descriptor_set::DescriptorPoolAlloc;
+    use descriptor::descriptor_set::FixedLayoutDescriptorPool;
+    use tests::uniform_buffer_layout;
+
+    #[test]
+    fn recycles_sets() {
+        let (device, _) = gfx_dev_and_queue!();
+        let layout = uniform_buffer_layout(device);
+        let pool = Arc::new(FixedLayoutDescriptorPool::new(layout.clone()));
+
+        let first = pool.alloc(&layout).unwrap().inner().internal_object();
+        let second = pool.alloc(&layout).unwrap().inner().internal_object();
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn multiple_batches() {
+        let (device, _) = gfx_dev_and_queue!();
+        let layout = uniform_buffer_layout(device);
+        let pool = Arc::new(FixedLayoutDescriptorPool::new(layout.clone()));
+
+        let sets = (0 .. 100)
+            .map(|_| pool.alloc(&layout).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(sets.len(), 100);
+    }
+
+    #[test]
+    fn per_frame_reset() {
+        let (device, _) = gfx_dev_and_queue!();
+        let layout = uniform_buffer_layout(device);
+        let mut pool = Arc::new(FixedLayoutDescriptorPool::per_frame(layout.clone()));
+
+        let first = pool.alloc(&layout).unwrap().inner().internal_object();
+        let second = pool.alloc(&layout).unwrap().inner().internal_object();
+        assert_ne!(first, second);
+
+        Arc::get_mut(&mut pool).unwrap().reset().unwrap();
+        let _ = pool.alloc(&layout).unwrap();
+    }
+
+    #[test]
+    fn wrong_layout() {
+        let (device, _) = gfx_dev_and_queue!();
+        let layout1 = uniform_buffer_layout(device.clone());
+        let layout2 = uniform_buffer_layout(device);
+        let pool = Arc::new(FixedLayoutDescriptorPool::new(layout1));
+
+        assert_should_panic!("Tried to allocate from a fixed layout pool with a different layout",
+                             {
+                                 let _ = pool.alloc(&layout2);
+                             });
+    }
+}
@@ -27,23 +27,58 @@
 //! - The `DescriptorPool` trait can be implemented on types from which you can allocate and free
 //!   descriptor sets. However it is different from Vulkan descriptor pools in the sense that an
 //!   implementation of the `DescriptorPool` trait can manage multiple Vulkan descriptor pools.
-//! - The `StdDescriptorPool` type is a default implementation of the `DescriptorPool` trait.
+//! - The `StdDescriptorPool` type is a default implementation of the `DescriptorPool` trait. Its
+//!   sizing can be tuned with `StdDescriptorPoolConfig`.
+//! - The `FixedLayoutDescriptorPool` type is an implementation of the `DescriptorPool` trait that
+//!   only allocates sets of a single layout and recycles them, which is faster when sets are
+//!   created very often.
 //! - The `DescriptorSet` trait is implemented on types that wrap around Vulkan descriptor sets in
 //!   a safe way. A Vulkan descriptor set is inherently unsafe, so we need safe wrappers around
 //!   them.
 //! - The `SimpleDescriptorSet` type is a default implementation of the `DescriptorSet` trait.
+//! - The `PersistentDescriptorSet` type is an implementation of the `DescriptorSet` trait that
+//!   checks the resources against the layout and is meant to be built once and reused.
 //! - The `DescriptorSetsCollection` trait is implemented on collections of types that implement
 //!   `DescriptorSet`. It is what you pass to the draw functions.
+//! - The `DescriptorUpdateTemplate` type describes how to write a descriptor set from a block of
+//!   memory, which is faster than writing it with a list of `DescriptorWrite`s. It requires the
+//!   `VK_KHR_descriptor_update_template` extension.
+//! - The `BindlessTable` type is a descriptor set containing large arrays of images and buffers
+//!   whose elements can be added and removed individually, and that are addressed by index in the
+//!   shaders. It requires the `VK_EXT_descriptor_indexing` extension.
 
 use SafeDeref;
 use buffer::BufferAccess;
 use descriptor::descriptor::DescriptorDesc;
 use image::ImageAccess;
+use image::ImageViewAccess;
+use std::iter;
 
+pub use self::bindless::BindlessBufferIndex;
+pub use self::bindless::BindlessImageIndex;
+pub use self::bindless::BindlessTable;
+pub use self::bindless::BindlessTableCreationError;
+pub use self::bindless::BindlessTableError;
+pub use self::bindless::BindlessTableSnapshot;
 pub use self::collection::DescriptorSetsCollection;
+pub use self::fixed_layout_pool::FixedLayoutDescriptorPool;
+pub use self::fixed_layout_pool::FixedLayoutDescriptorPoolAlloc;
+pub use self::persistent::PersistentDescriptorSet;
+pub use self::persistent::PersistentDescriptorSetBuf;
+pub use self::persistent::PersistentDescriptorSetBufView;
+pub use self::persistent::PersistentDescriptorSetBuildError;
+pub use self::persistent::PersistentDescriptorSetBuilder;
+pub use self::persistent::PersistentDescriptorSetBuilderArray;
+pub use self::persistent::PersistentDescriptorSetError;
+pub use self::persistent::PersistentDescriptorSetImg;
+pub use self::persistent::PersistentDescriptorSetResources;
+pub use self::persistent::PersistentDescriptorSetSampler;
 pub use self::simple::*;
 pub use self::std_pool::StdDescriptorPool;
 pub use self::std_pool::StdDescriptorPoolAlloc;
+pub use self::std_pool::StdDescriptorPoolConfig;
+pub use self::std_pool::StdDescriptorPoolStats;
+pub use self::std_pool::StdDescriptorPoolStrategy;
 pub use self::sys::DescriptorPool;
 pub use self::sys::DescriptorPoolAlloc;
 pub use self::sys::DescriptorPoolAllocError;
@@ -53,13 +88,20 @@ pub use self::sys::UnsafeDescriptorPool;
 pub use self::sys::UnsafeDescriptorPoolAllocIter;
 pub use self::sys::UnsafeDescriptorSet;
 pub use self::unsafe_layout::UnsafeDescriptorSetLayout;
+pub use self::update_template::DescriptorUpdateTemplate;
+pub use self::update_template::DescriptorUpdateTemplateCreationError;
+pub use self::update_template::DescriptorUpdateTemplateEntry;
 
 pub mod collection;
 
+mod bindless;
+mod fixed_layout_pool;
+mod persistent;
 mod simple;
 mod std_pool;
 mod sys;
 mod unsafe_layout;
+mod update_template;
 
 /// Trait for objects that contain a collection of resources that will be accessible by shaders.
 ///
@@ -75,6 +117,31 @@ pub unsafe trait DescriptorSet: DescriptorSetDesc {
     /// Returns the list of images used by this descriptor set. Includes image views.
     // TODO: meh for boxing
     fn images_list<'a>(&'a self) -> Box<Iterator<Item = &'a ImageAccess> + 'a>;
+
+    /// Returns the buffers written to the dynamic uniform buffer and dynamic storage buffer
+    /// descriptors of this set, in the order in which their dynamic offsets must be passed when
+    /// binding the set. In other words, ordered by binding and then by array element.
+    ///
+    /// The default implementation returns an empty list, which means that the buffers are
+    /// unknown. In that case the range that is accessed when binding the set can't be checked,
+    /// and the whole buffers returned by `buffers_list` are considered to be accessed.
+    // TODO: meh for boxing
+    #[inline]
+    fn dynamic_buffers<'a>(&'a self) -> Box<Iterator<Item = &'a BufferAccess> + 'a> {
+        Box::new(iter::empty())
+    }
+
+    /// Returns the image views written to the image descriptors of this set, alongside with the
+    /// binding that each of them is written to.
+    ///
+    /// The default implementation returns an empty list, which means that the views are unknown.
+    /// In that case the whole images returned by `images_list` are considered to be accessed
+    /// when binding the set.
+    // TODO: meh for boxing
+    #[inline]
+    fn image_views<'a>(&'a self) -> Box<Iterator<Item = (usize, &'a ImageViewAccess)> + 'a> {
+        Box::new(iter::empty())
+    }
 }
 
 unsafe impl<T> DescriptorSet for T
@@ -95,6 +162,16 @@ unsafe impl<T> DescriptorSet for T
     fn images_list<'a>(&'a self) -> Box<Iterator<Item = &'a ImageAccess> + 'a> {
         (**self).images_list()
     }
+
+    #[inline]
+    fn dynamic_buffers<'a>(&'a self) -> Box<Iterator<Item = &'a BufferAccess> + 'a> {
+        (**self).dynamic_buffers()
+    }
+
+    #[inline]
+    fn image_views<'a>(&'a self) -> Box<Iterator<Item = (usize, &'a ImageViewAccess)> + 'a> {
+        (**self).image_views()
+    }
 }
 
 /// Trait for objects that describe the layout of the descriptors of a set.
@@ -0,0 +1,1555 @@
+// Copyright (c) 2017 The vulkano developers
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>,
+// at your option. All files in the project carrying such
+// notice may not be copied, modified, or distributed except
+// according to those terms.
+
+use std::error;
+use std::fmt;
+use std::sync::Arc;
+
+use buffer::BufferAccess;
+use buffer::BufferView;
+use descriptor::descriptor::DescriptorBufferDesc;
+use descriptor::descriptor::DescriptorDesc;
+use descriptor::descriptor::DescriptorDescTy;
+use descriptor::descriptor::DescriptorImageDesc;
+use descriptor::descriptor::DescriptorImageDescArray;
+use descriptor::descriptor::DescriptorImageDescDimensions;
+use descriptor::descriptor::DescriptorType;
+use descriptor::descriptor_set::DescriptorPool;
+use descriptor::descriptor_set::DescriptorPoolAlloc;
+use descriptor::descriptor_set::DescriptorSet;
+use descriptor::descriptor_set::DescriptorSetDesc;
+use descriptor::descriptor_set::DescriptorUpdateTemplateCreationError;
+use descriptor::descriptor_set::DescriptorWrite;
+use descriptor::descriptor_set::StdDescriptorPoolAlloc;
+use descriptor::descriptor_set::UnsafeDescriptorSet;
+use descriptor::pipeline_layout::PipelineLayoutAbstract;
+use device::Device;
+use device::DeviceOwned;
+use format::Format;
+use image::Dimensions;
+use image::ImageAccess;
+use image::ImageLayout;
+use image::ImageViewAccess;
+use sampler::Sampler;
+
+use OomError;
+use VulkanObject;
+
+/// An immutable descriptor set that is expected to be long-lived.
+///
+/// Creating a persistent descriptor set allocates from a pool and checks every resource against
+/// the layout, and the set can't be modified once created. You are therefore encouraged to create
+/// them once at initialization and to reuse them for every draw or dispatch, instead of creating
+/// them in performance-critical paths.
+///
+/// In order to build a `PersistentDescriptorSet`, call `PersistentDescriptorSet::start()` and add
+/// the resources one by one. Each `add_*` method fills the current binding and moves to the next
+/// one. You can also jump to a specific binding with `binding()` or `binding_by_name()`. In order
+/// to fill a descriptor that is an array, call `enter_array()`, add one resource per element, then
+/// call `leave_array()`.
+///
+/// The template parameter of the `PersistentDescriptorSet` is complex, and you shouldn't try to
+/// express it explicitely. If you want to store your descriptor set in a struct or in a `Vec` for
+/// example, you are encouraged to turn the `PersistentDescriptorSet` into a `Box<DescriptorSet>`
+/// or a `Arc<DescriptorSet>`.
+///
+/// # Example
+///
+/// ```
+/// # use std::sync::Arc;
+/// # use vulkano::buffer::BufferAccess;
+/// # use vulkano::descriptor::pipeline_layout::PipelineLayoutAbstract;
+/// # use vulkano::sampler::Sampler;
+/// use vulkano::descriptor::descriptor_set::PersistentDescriptorSet;
+///
+/// # let layout: Arc<PipelineLayoutAbstract + Send + Sync> = return;
+/// # let uniform_buffer: Arc<BufferAccess + Send + Sync> = return;
+/// # let sampler: Arc<Sampler> = return;
+/// // Binding 0 is a uniform buffer, and binding 1 is an array of two samplers.
+/// let set = PersistentDescriptorSet::start(layout.clone(), 0)
+///     .add_buffer(uniform_buffer.clone()).unwrap()
+///     .enter_array().unwrap()
+///         .add_sampler(sampler.clone()).unwrap()
+///         .add_sampler(sampler.clone()).unwrap()
+///     .leave_array().unwrap()
+///     .build().unwrap();
+/// ```
+pub struct PersistentDescriptorSet<L, R, P = StdDescriptorPoolAlloc> {
+    inner: P,
+    resources: R,
+    layout: L,
+    set_id: usize,
+    // Indices within the buffers of `resources` of the buffers written to dynamic descriptors,
+    // in the order of their dynamic offsets.
+    dynamic_buffers: Vec<usize>,
+    // Binding of each image view of `resources`.
+    image_bindings: Vec<usize>,
+}
+
+impl<L> PersistentDescriptorSet<L, ()>
+    where L: PipelineLayoutAbstract
+{
+    /// Starts the process of building a `PersistentDescriptorSet`. Requires a reference to a
+    /// pipeline layout, and the id of the set within the layout.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if the set id is out of range.
+    ///
+    pub fn start(layout: L, set_id: usize) -> PersistentDescriptorSetBuilder<L, ()> {
+        assert!(layout.num_sets() > set_id);
+
+        let num_bindings = layout.num_bindings_in_set(set_id).unwrap_or(0);
+
+        PersistentDescriptorSetBuilder {
+            layout: layout,
+            set_id: set_id,
+            binding_id: 0,
+            writes: Vec::with_capacity(num_bindings),
+            filled: vec![false; num_bindings],
+            resources: (),
+            buffers_count: 0,
+            dynamic_buffers: Vec::new(),
+            image_bindings: Vec::new(),
+        }
+    }
+}
+
+impl<L, R, P> PersistentDescriptorSet<L, R, P> {
+    /// Returns the pipeline layout used to create this descriptor set.
+    #[inline]
+    pub fn layout(&self) -> &L {
+        &self.layout
+    }
+
+    /// Returns the id of this descriptor set within the pipeline layout.
+    #[inline]
+    pub fn set_id(&self) -> usize {
+        self.set_id
+    }
+}
+
+unsafe impl<L, R, P> DescriptorSet for PersistentDescriptorSet<L, R, P>
+    where L: PipelineLayoutAbstract,
+          R: PersistentDescriptorSetResources,
+          P: DescriptorPoolAlloc
+{
+    #[inline]
+    fn inner(&self) -> &UnsafeDescriptorSet {
+        self.inner.inner()
+    }
+
+    #[inline]
+    fn buffers_list<'a>(&'a self) -> Box<Iterator<Item = &'a BufferAccess> + 'a> {
+        let mut list = Vec::new();
+        self.resources.buffers(&mut list);
+        Box::new(list.into_iter())
+    }
+
+    #[inline]
+    fn images_list<'a>(&'a self) -> Box<Iterator<Item = &'a ImageAccess> + 'a> {
+        let mut list = Vec::new();
+        self.resources.images(&mut list);
+        Box::new(list.into_iter())
+    }
+
+    #[inline]
+    fn dynamic_buffers<'a>(&'a self) -> Box<Iterator<Item = &'a BufferAccess> + 'a> {
+        let mut list = Vec::new();
+        self.resources.buffers(&mut list);
+        Box::new(self.dynamic_buffers
+                     .iter()
+                     .map(|&index| list[index])
+                     .collect::<Vec<_>>()
+                     .into_iter())
+    }
+
+    #[inline]
+    fn image_views<'a>(&'a self) -> Box<Iterator<Item = (usize, &'a ImageViewAccess)> + 'a> {
+        let mut list = Vec::new();
+        self.resources.image_views(&mut list);
+        Box::new(self.image_bindings
+                     .iter()
+                     .cloned()
+                     .zip(list.into_iter())
+                     .collect::<Vec<_>>()
+                     .into_iter())
+    }
+}
+
+unsafe impl<L, R, P> DescriptorSetDesc for PersistentDescriptorSet<L, R, P>
+    where L: PipelineLayoutAbstract
+{
+    #[inline]
+    fn num_bindings(&self) -> usize {
+        self.layout.num_bindings_in_set(self.set_id).unwrap_or(0)
+    }
+
+    #[inline]
+    fn descriptor(&self, binding: usize) -> Option<DescriptorDesc> {
+        self.layout.descriptor(self.set_id, binding)
+    }
+}
+
+unsafe impl<L, R, P> DeviceOwned for PersistentDescriptorSet<L, R, P>
+    where L: PipelineLayoutAbstract
+{
+    #[inline]
+    fn device(&self) -> &Arc<Device> {
+        self.layout.device()
+    }
+}
+
+/// Prototype of a `PersistentDescriptorSet`.
+///
+/// The template parameter `L` is the pipeline layout to use, and the template parameter `R` is
+/// an unspecified type that represents the list of resources.
+///
+/// See the docs of `PersistentDescriptorSet` for an example.
+pub struct PersistentDescriptorSetBuilder<L, R> {
+    // The pipeline layout.
+    layout: L,
+    // Id of the set within the pipeline layout.
+    set_id: usize,
+    // Binding that the next resource is going to be written to.
+    binding_id: usize,
+    // The writes to perform on a descriptor set in order to put the resources in it.
+    writes: Vec<DescriptorWrite>,
+    // For each binding of the set, true if it has already been filled.
+    filled: Vec<bool>,
+    // Holds the resources alive.
+    resources: R,
+    // Number of buffers and buffer views in `resources`.
+    buffers_count: usize,
+    // For each buffer written to a dynamic descriptor, its binding, its array element and its
+    // index within the buffers of `resources`.
+    dynamic_buffers: Vec<(usize, u32, usize)>,
+    // Binding of each image view of `resources`.
+    image_bindings: Vec<usize>,
+}
+
+impl<L, R> PersistentDescriptorSetBuilder<L, R>
+    where L: PipelineLayoutAbstract
+{
+    /// Moves to the binding with the given index. The next resource will be written to it.
+    #[inline]
+    pub fn binding(mut self, binding_id: usize) -> Self {
+        self.binding_id = binding_id;
+        self
+    }
+
+    /// Moves to the binding with the given name. The next resource will be written to it.
+    ///
+    /// Returns an error if the layout doesn't have a descriptor with this name in the set.
+    pub fn binding_by_name(mut self, name: &str) -> Result<Self, PersistentDescriptorSetError> {
+        match self.layout.descriptor_by_name(name) {
+            Some((set_id, binding_id)) if set_id == self.set_id => {
+                self.binding_id = binding_id;
+                Ok(self)
+            },
+            Some((set_id, _)) => {
+                Err(PersistentDescriptorSetError::WrongSet {
+                        expected: self.set_id,
+                        obtained: set_id,
+                    })
+            },
+            None => Err(PersistentDescriptorSetError::NameNotFound),
+        }
+    }
+
+    /// Skips the current binding, which must either not contain any descriptor in the layout, or
+    /// be a sampler descriptor whose samplers are immutable.
+    pub fn add_empty(mut self) -> Result<Self, PersistentDescriptorSetError> {
+        if self.binding_id >= self.filled.len() {
+            return Err(PersistentDescriptorSetError::NoDescriptor);
+        }
+
+        match self.layout.descriptor(self.set_id, self.binding_id) {
+            None => (),
+            Some(ref desc) if is_immutable_sampler(desc) => (),
+            Some(_) => return Err(PersistentDescriptorSetError::EmptyExpected),
+        }
+
+        self.binding_id += 1;
+        Ok(self)
+    }
+
+    /// Starts filling the current binding, which is an array descriptor. Each `add_*` method of
+    /// the returned object fills one element of the array.
+    ///
+    /// This can also be used with descriptors that aren't arrays, in which case there is exactly
+    /// one element to fill.
+    pub fn enter_array(self)
+                       -> Result<PersistentDescriptorSetBuilderArray<L, R>,
+                                 PersistentDescriptorSetError> {
+        if self.binding_id >= self.filled.len() {
+            return Err(PersistentDescriptorSetError::NoDescriptor);
+        }
+
+        let desc = match self.layout.descriptor(self.set_id, self.binding_id) {
+            Some(desc) => desc,
+            None => return Err(PersistentDescriptorSetError::NoDescriptor),
+        };
+
+        if self.filled[self.binding_id] {
+            return Err(PersistentDescriptorSetError::AlreadyFilled);
+        }
+
+        Ok(PersistentDescriptorSetBuilderArray {
+               builder: self,
+               array_element: 0,
+               desc: desc,
+           })
+    }
+
+    /// Fills the current binding with a buffer, and moves to the next binding.
+    #[inline]
+    pub fn add_buffer<T>(self, buffer: T)
+        -> Result<PersistentDescriptorSetBuilder<L, (R, PersistentDescriptorSetBuf<T>)>,
+                  PersistentDescriptorSetError>
+        where T: BufferAccess
+    {
+        self.enter_array()?.add_buffer(buffer)?.leave_array()
+    }
+
+    /// Fills the current binding with a buffer view, and moves to the next binding.
+    #[inline]
+    pub fn add_buffer_view<F, B>(self, view: Arc<BufferView<F, B>>)
+        -> Result<PersistentDescriptorSetBuilder<L, (R, PersistentDescriptorSetBufView<F, B>)>,
+                  PersistentDescriptorSetError>
+        where B: BufferAccess + 'static,
+              F: 'static + Send + Sync
+    {
+        self.enter_array()?.add_buffer_view(view)?.leave_array()
+    }
+
+    /// Fills the current binding with an image view, and moves to the next binding.
+    #[inline]
+    pub fn add_image<T>(self, image: T)
+        -> Result<PersistentDescriptorSetBuilder<L, (R, PersistentDescriptorSetImg<T>)>,
+                  PersistentDescriptorSetError>
+        where T: ImageViewAccess
+    {
+        self.enter_array()?.add_image(image)?.leave_array()
+    }
+
+    /// Fills the current binding with an image view and a sampler, and moves to the next
+    /// binding.
+    #[inline]
+    pub fn add_sampled_image<T>(self, image: T, sampler: Arc<Sampler>)
+        -> Result<PersistentDescriptorSetBuilder<L, (R, (PersistentDescriptorSetImg<T>,
+                                                         PersistentDescriptorSetSampler))>,
+                  PersistentDescriptorSetError>
+        where T: ImageViewAccess
+    {
+        self.enter_array()?.add_sampled_image(image, sampler)?.leave_array()
+    }
+
+    /// Fills the current binding with a sampler, and moves to the next binding.
+    #[inline]
+    pub fn add_sampler(self, sampler: Arc<Sampler>)
+        -> Result<PersistentDescriptorSetBuilder<L, (R, PersistentDescriptorSetSampler)>,
+                  PersistentDescriptorSetError> {
+        self.enter_array()?.add_sampler(sampler)?.leave_array()
+    }
+
+    /// Builds a `PersistentDescriptorSet` from the builder, allocating it from the standard
+    /// descriptor pool of the device.
+    #[inline]
+    pub fn build(self)
+                 -> Result<PersistentDescriptorSet<L, R>, PersistentDescriptorSetBuildError> {
+        let pool = Device::standard_descriptor_pool(self.layout.device());
+        self.build_with_pool(&pool)
+    }
+
+    /// Builds a `PersistentDescriptorSet` from the builder, allocating it from `pool`.
+    ///
+    /// Returns an error if a binding of the layout has not been filled. Sampler descriptors whose
+    /// samplers are immutable don't need to be filled.
+    pub fn build_with_pool<P>(self, pool: &P)
+                              -> Result<PersistentDescriptorSet<L, R, P::Alloc>,
+                                        PersistentDescriptorSetBuildError>
+        where P: ?Sized + DescriptorPool
+    {
+        for (binding_id, &filled) in self.filled.iter().enumerate() {
+            if filled {
+                continue;
+            }
+
+            match self.layout.descriptor(self.set_id, binding_id) {
+                Some(ref desc) if !is_immutable_sampler(desc) => {
+                    return Err(PersistentDescriptorSetBuildError::MissingDescriptor {
+                                   binding: binding_id as u32,
+                               });
+                },
+                _ => (),
+            }
+        }
+
+        let (mut set, template) = {
+            // `start()` checked that the set exists.
+            let set_layout = self.layout.descriptor_set_layout(self.set_id).unwrap();
+            (pool.alloc(set_layout)?, set_layout.update_template()?)
+        };
+
+        // Writing with a template is faster, but is only possible if the extension is enabled.
+        let template_data = template
+            .as_ref()
+            .and_then(|template| template.data(self.writes.iter()));
+
+        unsafe {
+            match (template, template_data) {
+                (Some(template), Some(data)) => {
+                    set.inner_mut()
+                        .write_with_template(pool.device(), &template, &data[..]);
+                },
+                _ => {
+                    set.inner_mut().write(pool.device(), self.writes.into_iter());
+                },
+            }
+        }
+
+        // Dynamic offsets are passed in the order of the bindings, then of the array elements.
+        let mut dynamic_buffers = self.dynamic_buffers;
+        dynamic_buffers.sort();
+
+        Ok(PersistentDescriptorSet {
+               inner: set,
+               resources: self.resources,
+               layout: self.layout,
+               set_id: self.set_id,
+               dynamic_buffers: dynamic_buffers
+                   .into_iter()
+                   .map(|(_, _, index)| index)
+                   .collect(),
+               image_bindings: self.image_bindings,
+           })
+    }
+}
+
+/// In the middle of building a `PersistentDescriptorSet`, this represents an array descriptor
+/// whose elements are being filled.
+pub struct PersistentDescriptorSetBuilderArray<L, R> {
+    // The builder of the set.
+    builder: PersistentDescriptorSetBuilder<L, R>,
+    // Element of the array that is going to be filled next.
+    array_element: u32,
+    // Description of the descriptor being filled.
+    desc: DescriptorDesc,
+}
+
+impl<L, R> PersistentDescriptorSetBuilderArray<L, R>
+    where L: PipelineLayoutAbstract
+{
+    /// Leaves the array and moves to the next binding.
+    ///
+    /// Returns an error if not all the elements of the array have been filled.
+    pub fn leave_array(mut self)
+                       -> Result<PersistentDescriptorSetBuilder<L, R>,
+                                 PersistentDescriptorSetError> {
+        if self.array_element != self.desc.array_count {
+            return Err(PersistentDescriptorSetError::MissingArrayElements {
+                           expected: self.desc.array_count,
+                           obtained: self.array_element,
+                       });
+        }
+
+        self.builder.filled[self.builder.binding_id] = true;
+        self.builder.binding_id += 1;
+        Ok(self.builder)
+    }
+
+    /// Fills the next element of the array with a buffer.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if the buffer doesn't belong to the same device as the pipeline layout.
+    ///
+    pub fn add_buffer<T>(mut self, buffer: T)
+        -> Result<PersistentDescriptorSetBuilderArray<L, (R, PersistentDescriptorSetBuf<T>)>,
+                  PersistentDescriptorSetError>
+        where T: BufferAccess
+    {
+        assert_eq!(self.builder.layout.device().internal_object(),
+                   buffer.device().internal_object());
+
+        if self.array_element >= self.desc.array_count {
+            return Err(PersistentDescriptorSetError::ArrayOutOfBounds);
+        }
+
+        let binding = self.builder.binding_id as u32;
+
+        let write = match self.desc.ty {
+            DescriptorDescTy::Buffer(ref buffer_desc) => {
+                let dynamic = buffer_desc.dynamic.unwrap_or(false);
+
+                if buffer_desc.storage {
+                    if !buffer.inner().buffer.usage_storage_buffer() {
+                        return Err(PersistentDescriptorSetError::MissingUsage);
+                    }
+
+                    unsafe {
+                        if dynamic {
+                            DescriptorWrite::dynamic_storage_buffer(binding,
+                                                                    self.array_element,
+                                                                    &buffer)
+                        } else {
+                            DescriptorWrite::storage_buffer(binding, self.array_element, &buffer)
+                        }
+                    }
+                } else {
+                    if !buffer.inner().buffer.usage_uniform_buffer() {
+                        return Err(PersistentDescriptorSetError::MissingUsage);
+                    }
+
+                    unsafe {
+                        if dynamic {
+                            DescriptorWrite::dynamic_uniform_buffer(binding,
+                                                                    self.array_element,
+                                                                    &buffer)
+                        } else {
+                            DescriptorWrite::uniform_buffer(binding, self.array_element, &buffer)
+                        }
+                    }
+                }
+            },
+            ref ty => {
+                return Err(PersistentDescriptorSetError::WrongDescriptorTy {
+                               expected: descriptor_ty(ty),
+                           })
+            },
+        };
+
+        if let DescriptorDescTy::Buffer(DescriptorBufferDesc { dynamic: Some(true), .. }) =
+            self.desc.ty
+        {
+            self.builder.dynamic_buffers.push((self.builder.binding_id,
+                                               self.array_element,
+                                               self.builder.buffers_count));
+        }
+
+        self.builder.buffers_count += 1;
+        Ok(self.push(write, PersistentDescriptorSetBuf { buffer: buffer }))
+    }
+
+    /// Fills the next element of the array with a buffer view.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if the buffer view doesn't belong to the same device as the pipeline layout.
+    ///
+    pub fn add_buffer_view<F, B>(mut self, view: Arc<BufferView<F, B>>)
+        -> Result<PersistentDescriptorSetBuilderArray<L, (R, PersistentDescriptorSetBufView<F, B>)>,
+                  PersistentDescriptorSetError>
+        where B: BufferAccess + 'static,
+              F: 'static + Send + Sync
+    {
+        assert_eq!(self.builder.layout.device().internal_object(),
+                   view.device().internal_object());
+
+        if self.array_element >= self.desc.array_count {
+            return Err(PersistentDescriptorSetError::ArrayOutOfBounds);
+        }
+
+        let binding = self.builder.binding_id as u32;
+
+        if let DescriptorDescTy::TexelBuffer { format: Some(format), .. } = self.desc.ty {
+            if view.format() != format {
+                return Err(PersistentDescriptorSetError::BufferViewFormatMismatch {
+                               expected: format,
+                               obtained: view.format(),
+                           });
+            }
+        }
+
+        let write = match self.desc.ty {
+            DescriptorDescTy::TexelBuffer { storage: true, .. } => {
+                if !view.storage_texel_buffer() {
+                    return Err(PersistentDescriptorSetError::MissingUsage);
+                }
+
+                DescriptorWrite::storage_texel_buffer(binding, self.array_element, &view)
+            },
+            DescriptorDescTy::TexelBuffer { storage: false, .. } => {
+                if !view.uniform_texel_buffer() {
+                    return Err(PersistentDescriptorSetError::MissingUsage);
+                }
+
+                DescriptorWrite::uniform_texel_buffer(binding, self.array_element, &view)
+            },
+            ref ty => {
+                return Err(PersistentDescriptorSetError::WrongDescriptorTy {
+                               expected: descriptor_ty(ty),
+                           })
+            },
+        };
+
+        self.builder.buffers_count += 1;
+        Ok(self.push(write, PersistentDescriptorSetBufView { view: view }))
+    }
+
+    /// Fills the next element of the array with an image view.
+    ///
+    /// If the descriptor is a combined image sampler whose samplers are immutable, the image is
+    /// combined with the immutable sampler of the array element.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if the image view doesn't belong to the same device as the pipeline layout.
+    ///
+    pub fn add_image<T>(mut self, image: T)
+        -> Result<PersistentDescriptorSetBuilderArray<L, (R, PersistentDescriptorSetImg<T>)>,
+                  PersistentDescriptorSetError>
+        where T: ImageViewAccess
+    {
+        assert_eq!(self.builder.layout.device().internal_object(),
+                   image.parent().inner().image.device().internal_object());
+
+        if self.array_element >= self.desc.array_count {
+            return Err(PersistentDescriptorSetError::ArrayOutOfBounds);
+        }
+
+        let binding = self.builder.binding_id as u32;
+
+        let write = match self.desc.ty {
+            DescriptorDescTy::Image(ref desc) => {
+                image_match_desc(&image, desc)?;
+
+                if desc.sampled {
+                    check_read_layout(image.descriptor_set_sampled_image_layout())?;
+                    DescriptorWrite::sampled_image(binding, self.array_element, &image)
+                } else {
+                    if !image.identity_swizzle() {
+                        return Err(PersistentDescriptorSetError::NotIdentitySwizzled);
+                    }
+
+                    let layout = image.descriptor_set_storage_image_layout();
+                    if layout != ImageLayout::General {
+                        return Err(PersistentDescriptorSetError::InvalidImageLayout {
+                                       obtained: layout,
+                                   });
+                    }
+
+                    DescriptorWrite::storage_image(binding, self.array_element, &image)
+                }
+            },
+            DescriptorDescTy::InputAttachment {
+                multisampled,
+                array_layers,
+            } => {
+                if !image.inner().usage_input_attachment() {
+                    return Err(PersistentDescriptorSetError::MissingUsage);
+                }
+
+                if !image.identity_swizzle() {
+                    return Err(PersistentDescriptorSetError::NotIdentitySwizzled);
+                }
+
+                check_multisampled(&image, multisampled)?;
+                check_dimensions(image.dimensions(),
+                                 DescriptorImageDescDimensions::TwoDimensional,
+                                 array_layers)?;
+                check_read_layout(image.descriptor_set_input_attachment_layout())?;
+                DescriptorWrite::input_attachment(binding, self.array_element, &image)
+            },
+            DescriptorDescTy::CombinedImageSampler(ref desc) if
+                !self.desc.immutable_samplers.is_empty() => {
+                // The sampler is ignored by the implementation, but we still have to pass a
+                // valid one.
+                let sampler = &self.desc.immutable_samplers[self.array_element as usize];
+
+                image_match_desc(&image, desc)?;
+                check_read_layout(image.descriptor_set_combined_image_sampler_layout())?;
+
+                if !image.can_be_sampled(sampler) {
+                    return Err(PersistentDescriptorSetError::IncompatibleImageViewSampler);
+                }
+
+                DescriptorWrite::combined_image_sampler(binding,
+                                                        self.array_element,
+                                                        sampler,
+                                                        &image)
+            },
+            ref ty => {
+                return Err(PersistentDescriptorSetError::WrongDescriptorTy {
+                               expected: descriptor_ty(ty),
+                           })
+            },
+        };
+
+        self.builder.image_bindings.push(self.builder.binding_id);
+        Ok(self.push(write, PersistentDescriptorSetImg { image: image }))
+    }
+
+    /// Fills the next element of the array with an image view and a sampler.
+    ///
+    /// Returns an error if the samplers of the descriptor are immutable. Use `add_image` instead.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if the image view or the sampler doesn't belong to the same device as the
+    ///   pipeline layout.
+    ///
+    pub fn add_sampled_image<T>(mut self, image: T, sampler: Arc<Sampler>)
+        -> Result<PersistentDescriptorSetBuilderArray<L, (R, (PersistentDescriptorSetImg<T>,
+                                                              PersistentDescriptorSetSampler))>,
+                  PersistentDescriptorSetError>
+        where T: ImageViewAccess
+    {
+        assert_eq!(self.builder.layout.device().internal_object(),
+                   image.parent().inner().image.device().internal_object());
+        assert_eq!(self.builder.layout.device().internal_object(),
+                   sampler.device().internal_object());
+
+        if self.array_element >= self.desc.array_count {
+            return Err(PersistentDescriptorSetError::ArrayOutOfBounds);
+        }
+
+        if !self.desc.immutable_samplers.is_empty() {
+            return Err(PersistentDescriptorSetError::SamplerIsImmutable);
+        }
+
+        let binding = self.builder.binding_id as u32;
+
+        let write = match self.desc.ty {
+            DescriptorDescTy::CombinedImageSampler(ref desc) => {
+                image_match_desc(&image, desc)?;
+                check_read_layout(image.descriptor_set_combined_image_sampler_layout())?;
+
+                if !image.can_be_sampled(&sampler) {
+                    return Err(PersistentDescriptorSetError::IncompatibleImageViewSampler);
+                }
+
+                DescriptorWrite::combined_image_sampler(binding,
+                                                        self.array_element,
+                                                        &sampler,
+                                                        &image)
+            },
+            ref ty => {
+                return Err(PersistentDescriptorSetError::WrongDescriptorTy {
+                               expected: descriptor_ty(ty),
+                           })
+            },
+        };
+
+        self.builder.image_bindings.push(self.builder.binding_id);
+        Ok(self.push(write,
+                     (PersistentDescriptorSetImg { image: image },
+                      PersistentDescriptorSetSampler { sampler: sampler })))
+    }
+
+    /// Fills the next element of the array with a sampler.
+    ///
+    /// Returns an error if the samplers of the descriptor are immutable. Immutable samplers
+    /// don't need to be written, so you can skip the binding with `add_empty` instead.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if the sampler doesn't belong to the same device as the pipeline layout.
+    ///
+    pub fn add_sampler(self, sampler: Arc<Sampler>)
+        -> Result<PersistentDescriptorSetBuilderArray<L, (R, PersistentDescriptorSetSampler)>,
+                  PersistentDescriptorSetError> {
+        assert_eq!(self.builder.layout.device().internal_object(),
+                   sampler.device().internal_object());
+
+        if self.array_element >= self.desc.array_count {
+            return Err(PersistentDescriptorSetError::ArrayOutOfBounds);
+        }
+
+        if !self.desc.immutable_samplers.is_empty() {
+            return Err(PersistentDescriptorSetError::SamplerIsImmutable);
+        }
+
+        let binding = self.builder.binding_id as u32;
+
+        let write = match self.desc.ty {
+            DescriptorDescTy::Sampler => {
+                DescriptorWrite::sampler(binding, self.array_element, &sampler)
+            },
+            ref ty => {
+                return Err(PersistentDescriptorSetError::WrongDescriptorTy {
+                               expected: descriptor_ty(ty),
+                           })
+            },
+        };
+
+        Ok(self.push(write, PersistentDescriptorSetSampler { sampler: sampler }))
+    }
+
+    // Adds a write and the resource that it uses, and moves to the next element of the array.
+    fn push<T>(mut self, write: DescriptorWrite, resource: T)
+               -> PersistentDescriptorSetBuilderArray<L, (R, T)> {
+        self.builder.writes.push(write);
+
+        PersistentDescriptorSetBuilderArray {
+            builder: PersistentDescriptorSetBuilder {
+                layout: self.builder.layout,
+                set_id: self.builder.set_id,
+                binding_id: self.builder.binding_id,
+                writes: self.builder.writes,
+                filled: self.builder.filled,
+                resources: (self.builder.resources, resource),
+                buffers_count: self.builder.buffers_count,
+                dynamic_buffers: self.builder.dynamic_buffers,
+                image_bindings: self.builder.image_bindings,
+            },
+            array_element: self.array_element + 1,
+            desc: self.desc,
+        }
+    }
+}
+
+// Returns true if the descriptor is a sampler descriptor whose samplers are baked into the layout,
+// in which case nothing needs to be written to the descriptor set.
+#[inline]
+fn is_immutable_sampler(desc: &DescriptorDesc) -> bool {
+    match desc.ty {
+        DescriptorDescTy::Sampler => !desc.immutable_samplers.is_empty(),
+        _ => false,
+    }
+}
+
+// Returns the type of a descriptor. Buffers that aren't known to be dynamic are considered as
+// non-dynamic, like the builder does.
+fn descriptor_ty(ty: &DescriptorDescTy) -> DescriptorType {
+    match ty.ty() {
+        Some(ty) => ty,
+        None => match *ty {
+            DescriptorDescTy::Buffer(ref desc) if desc.storage => DescriptorType::StorageBuffer,
+            _ => DescriptorType::UniformBuffer,
+        },
+    }
+}
+
+// Checks whether an image view can be written to a sampled, combined image sampler or storage
+// image descriptor.
+fn image_match_desc<I>(image: &I, desc: &DescriptorImageDesc)
+                       -> Result<(), PersistentDescriptorSetError>
+    where I: ?Sized + ImageViewAccess
+{
+    if desc.sampled && !image.inner().usage_sampled() {
+        return Err(PersistentDescriptorSetError::MissingUsage);
+    } else if !desc.sampled && !image.inner().usage_storage() {
+        return Err(PersistentDescriptorSetError::MissingUsage);
+    }
+
+    if let Some(format) = desc.format {
+        if image.format() != format {
+            return Err(PersistentDescriptorSetError::ImageViewFormatMismatch {
+                           expected: format,
+                           obtained: image.format(),
+                       });
+        }
+    }
+
+    check_multisampled(image, desc.multisampled)?;
+    check_dimensions(image.dimensions(), desc.dimensions, desc.array_layers)
+}
+
+// Checks whether the view is multisampled if and only if the descriptor expects it.
+fn check_multisampled<I>(image: &I, expected: bool) -> Result<(), PersistentDescriptorSetError>
+    where I: ?Sized + ImageViewAccess
+{
+    let obtained = image.samples() > 1;
+
+    if obtained != expected {
+        return Err(PersistentDescriptorSetError::MultisampledMismatch {
+                       expected: expected,
+                       obtained: obtained,
+                   });
+    }
+
+    Ok(())
+}
+
+// Checks whether the type of view matches the dimensions and the arrayed-ness of the descriptor.
+fn check_dimensions(dimensions: Dimensions, expected: DescriptorImageDescDimensions,
+                    array_layers: DescriptorImageDescArray)
+                    -> Result<(), PersistentDescriptorSetError> {
+    let (ty, arrayed, num_layers) = match dimensions {
+        Dimensions::Dim1d { .. } => (DescriptorImageDescDimensions::OneDimensional, false, 1),
+        Dimensions::Dim1dArray { array_layers, .. } => {
+            (DescriptorImageDescDimensions::OneDimensional, true, array_layers)
+        },
+        Dimensions::Dim2d { .. } => (DescriptorImageDescDimensions::TwoDimensional, false, 1),
+        Dimensions::Dim2dArray { array_layers, .. } => {
+            (DescriptorImageDescDimensions::TwoDimensional, true, array_layers)
+        },
+        Dimensions::Dim3d { .. } => (DescriptorImageDescDimensions::ThreeDimensional, false, 1),
+        Dimensions::Cubemap { .. } => (DescriptorImageDescDimensions::Cube, false, 1),
+        Dimensions::CubemapArray { array_layers, .. } => {
+            (DescriptorImageDescDimensions::Cube, true, array_layers)
+        },
+    };
+
+    let expected_arrayed = match array_layers {
+        DescriptorImageDescArray::NonArrayed => false,
+        DescriptorImageDescArray::Arrayed { .. } => true,
+    };
+
+    if ty != expected || arrayed != expected_arrayed {
+        return Err(PersistentDescriptorSetError::ImageViewTypeMismatch {
+                       expected: expected,
+                       arrayed: expected_arrayed,
+                       obtained: dimensions,
+                   });
+    }
+
+    if let DescriptorImageDescArray::Arrayed { max_layers: Some(max_layers) } = array_layers {
+        if num_layers > max_layers {
+            return Err(PersistentDescriptorSetError::ArrayLayersMismatch {
+                           expected: max_layers,
+                           obtained: num_layers,
+                       });
+        }
+    }
+
+    Ok(())
+}
+
+// Checks whether a layout can be used by a descriptor that only reads from the image.
+fn check_read_layout(layout: ImageLayout) -> Result<(), PersistentDescriptorSetError> {
+    match layout {
+        ImageLayout::General |
+        ImageLayout::ShaderReadOnlyOptimal |
+        ImageLayout::DepthStencilReadOnlyOptimal => Ok(()),
+        _ => Err(PersistentDescriptorSetError::InvalidImageLayout { obtained: layout }),
+    }
+}
+
+/// Internal trait related to the `PersistentDescriptorSet` system. Implemented on the list of
+/// resources held by a set.
+pub unsafe trait PersistentDescriptorSetResources {
+    /// Appends the buffers and buffer views of the list to `out`.
+    fn buffers<'a>(&'a self, out: &mut Vec<&'a BufferAccess>);
+
+    /// Appends the images and image views of the list to `out`.
+    fn images<'a>(&'a self, out: &mut Vec<&'a ImageAccess>);
+
+    /// Appends the image views of the list to `out`, in the same order as `images`.
+    fn image_views<'a>(&'a self, out: &mut Vec<&'a ImageViewAccess>);
+}
+
+unsafe impl PersistentDescriptorSetResources for () {
+    #[inline]
+    fn buffers<'a>(&'a self, _: &mut Vec<&'a BufferAccess>) {
+    }
+
+    #[inline]
+    fn images<'a>(&'a self, _: &mut Vec<&'a ImageAccess>) {
+    }
+
+    #[inline]
+    fn image_views<'a>(&'a self, _: &mut Vec<&'a ImageViewAccess>) {
+    }
+}
+
+unsafe impl<A, B> PersistentDescriptorSetResources for (A, B)
+    where A: PersistentDescriptorSetResources,
+          B: PersistentDescriptorSetResources
+{
+    #[inline]
+    fn buffers<'a>(&'a self, out: &mut Vec<&'a BufferAccess>) {
+        self.0.buffers(out);
+        self.1.buffers(out);
+    }
+
+    #[inline]
+    fn images<'a>(&'a self, out: &mut Vec<&'a ImageAccess>) {
+        self.0.images(out);
+        self.1.images(out);
+    }
+
+    #[inline]
+    fn image_views<'a>(&'a self, out: &mut Vec<&'a ImageViewAccess>) {
+        self.0.image_views(out);
+        self.1.image_views(out);
+    }
+}
+
+/// Internal object related to the `PersistentDescriptorSet` system.
+pub struct PersistentDescriptorSetBuf<B> {
+    buffer: B,
+}
+
+unsafe impl<B> PersistentDescriptorSetResources for PersistentDescriptorSetBuf<B>
+    where B: BufferAccess
+{
+    #[inline]
+    fn buffers<'a>(&'a self, out: &mut Vec<&'a BufferAccess>) {
+        out.push(&self.buffer);
+    }
+
+    #[inline]
+    fn images<'a>(&'a self, _: &mut Vec<&'a ImageAccess>) {
+    }
+
+    #[inline]
+    fn image_views<'a>(&'a self, _: &mut Vec<&'a ImageViewAccess>) {
+    }
+}
+
+/// Internal object related to the `PersistentDescriptorSet` system.
+pub struct PersistentDescriptorSetBufView<F, B>
+    where B: BufferAccess
+{
+    view: Arc<BufferView<F, B>>,
+}
+
+unsafe impl<F, B> PersistentDescriptorSetResources for PersistentDescriptorSetBufView<F, B>
+    where B: BufferAccess
+{
+    #[inline]
+    fn buffers<'a>(&'a self, out: &mut Vec<&'a BufferAccess>) {
+        out.push(self.view.buffer());
+    }
+
+    #[inline]
+    fn images<'a>(&'a self, _: &mut Vec<&'a ImageAccess>) {
+    }
+
+    #[inline]
+    fn image_views<'a>(&'a self, _: &mut Vec<&'a ImageViewAccess>) {
+    }
+}
+
+/// Internal object related to the `PersistentDescriptorSet` system.
+pub struct PersistentDescriptorSetImg<I> {
+    image: I,
+}
+
+unsafe impl<I> PersistentDescriptorSetResources for PersistentDescriptorSetImg<I>
+    where I: ImageViewAccess
+{
+    #[inline]
+    fn buffers<'a>(&'a self, _: &mut Vec<&'a BufferAccess>) {
+    }
+
+    #[inline]
+    fn images<'a>(&'a self, out: &mut Vec<&'a ImageAccess>) {
+        out.push(self.image.parent());
+    }
+
+    #[inline]
+    fn image_views<'a>(&'a self, out: &mut Vec<&'a ImageViewAccess>) {
+        out.push(&self.image);
+    }
+}
+
+/// Internal object related to the `PersistentDescriptorSet` system.
+pub struct PersistentDescriptorSetSampler {
+    sampler: Arc<Sampler>,
+}
+
+unsafe impl PersistentDescriptorSetResources for PersistentDescriptorSetSampler {
+    #[inline]
+    fn buffers<'a>(&'a self, _: &mut Vec<&'a BufferAccess>) {
+    }
+
+    #[inline]
+    fn images<'a>(&'a self, _: &mut Vec<&'a ImageAccess>) {
+    }
+
+    #[inline]
+    fn image_views<'a>(&'a self, _: &mut Vec<&'a ImageViewAccess>) {
+    }
+}
+
+/// Error related to a resource added to a `PersistentDescriptorSetBuilder`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PersistentDescriptorSetError {
+    /// The current binding doesn't exist or doesn't contain any descriptor in the layout.
+    NoDescriptor,
+
+    /// The layout doesn't have any descriptor with this name.
+    NameNotFound,
+
+    /// The descriptor with this name belongs to another set of the layout.
+    WrongSet {
+        /// The set being built.
+        expected: usize,
+        /// The set that the descriptor belongs to.
+        obtained: usize,
+    },
+
+    /// `add_empty` was called on a binding that contains a descriptor.
+    EmptyExpected,
+
+    /// The binding has already been filled.
+    AlreadyFilled,
+
+    /// The resource can't be written to this kind of descriptor.
+    WrongDescriptorTy {
+        /// The type of the descriptor.
+        expected: DescriptorType,
+    },
+
+    /// All the elements of the array have already been filled.
+    ArrayOutOfBounds,
+
+    /// `leave_array` was called before all the elements of the array were filled.
+    MissingArrayElements {
+        /// Number of elements of the array.
+        expected: u32,
+        /// Number of elements that were added.
+        obtained: u32,
+    },
+
+    /// The buffer or image is missing the usage that the descriptor requires.
+    MissingUsage,
+
+    /// The format of the buffer view doesn't match the format of the descriptor.
+    BufferViewFormatMismatch {
+        /// Format expected by the descriptor.
+        expected: Format,
+        /// Format of the view.
+        obtained: Format,
+    },
+
+    /// The format of the image view doesn't match the format of the descriptor.
+    ImageViewFormatMismatch {
+        /// Format expected by the descriptor.
+        expected: Format,
+        /// Format of the view.
+        obtained: Format,
+    },
+
+    /// The type of the image view doesn't match the descriptor.
+    ImageViewTypeMismatch {
+        /// Dimensions expected by the descriptor.
+        expected: DescriptorImageDescDimensions,
+        /// True if the descriptor expects an array view.
+        arrayed: bool,
+        /// Dimensions of the view.
+        obtained: Dimensions,
+    },
+
+    /// The image view has more array layers than the descriptor allows.
+    ArrayLayersMismatch {
+        /// Maximum number of layers of the descriptor.
+        expected: u32,
+        /// Number of layers of the view.
+        obtained: u32,
+    },
+
+    /// The image view is multisampled but the descriptor isn't, or the opposite.
+    MultisampledMismatch {
+        /// True if the descriptor is multisampled.
+        expected: bool,
+        /// True if the view is multisampled.
+        obtained: bool,
+    },
+
+    /// Storage images and input attachments must use identity swizzling.
+    NotIdentitySwizzled,
+
+    /// The image view would be in a layout that the descriptor can't use.
+    InvalidImageLayout {
+        /// The layout of the view.
+        obtained: ImageLayout,
+    },
+
+    /// The image view can't be used with the sampler.
+    IncompatibleImageViewSampler,
+
+    /// The samplers of the descriptor are immutable and can't be written.
+    SamplerIsImmutable,
+}
+
+impl error::Error for PersistentDescriptorSetError {
+    #[inline]
+    fn description(&self) -> &str {
+        match *self {
+            PersistentDescriptorSetError::NoDescriptor => {
+                "the binding doesn't contain any descriptor"
+            },
+            PersistentDescriptorSetError::NameNotFound => {
+                "no descriptor with this name was found in the layout"
+            },
+            PersistentDescriptorSetError::WrongSet { .. } => {
+                "the descriptor with this name belongs to another set"
+            },
+            PersistentDescriptorSetError::EmptyExpected => {
+                "expected an empty binding but it contains a descriptor"
+            },
+            PersistentDescriptorSetError::AlreadyFilled => {
+                "the binding has already been filled"
+            },
+            PersistentDescriptorSetError::WrongDescriptorTy { .. } => {
+                "the resource can't be written to this kind of descriptor"
+            },
+            PersistentDescriptorSetError::ArrayOutOfBounds => {
+                "all the elements of the array have already been filled"
+            },
+            PersistentDescriptorSetError::MissingArrayElements { .. } => {
+                "not all the elements of the array have been filled"
+            },
+            PersistentDescriptorSetError::MissingUsage => {
+                "the resource is missing the usage required by the descriptor"
+            },
+            PersistentDescriptorSetError::BufferViewFormatMismatch { .. } => {
+                "the format of the buffer view doesn't match the descriptor"
+            },
+            PersistentDescriptorSetError::ImageViewFormatMismatch { .. } => {
+                "the format of the image view doesn't match the descriptor"
+            },
+            PersistentDescriptorSetError::ImageViewTypeMismatch { .. } => {
+                "the type of the image view doesn't match the descriptor"
+            },
+            PersistentDescriptorSetError::ArrayLayersMismatch { .. } => {
+                "the image view has too many array layers"
+            },
+            PersistentDescriptorSetError::MultisampledMismatch { .. } => {
+                "the multisampling of the image view doesn't match the descriptor"
+            },
+            PersistentDescriptorSetError::NotIdentitySwizzled => {
+                "the image view doesn't use identity swizzling"
+            },
+            PersistentDescriptorSetError::InvalidImageLayout { .. } => {
+                "the layout of the image view can't be used with this descriptor"
+            },
+            PersistentDescriptorSetError::IncompatibleImageViewSampler => {
+                "the image view can't be used with the sampler"
+            },
+            PersistentDescriptorSetError::SamplerIsImmutable => {
+                "the samplers of the descriptor are immutable and can't be written"
+            },
+        }
+    }
+}
+
+impl fmt::Display for PersistentDescriptorSetError {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(fmt, "{}", error::Error::description(self))
+    }
+}
+
+/// Error when building a persistent descriptor set.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PersistentDescriptorSetBuildError {
+    /// Out of memory.
+    OomError(OomError),
+
+    /// A binding of the layout has not been filled.
+    MissingDescriptor {
+        /// The binding that is missing.
+        binding: u32,
+    },
+
+    /// Error while creating the update template of the layout.
+    UpdateTemplateCreationError(DescriptorUpdateTemplateCreationError),
+}
+
+impl error::Error for PersistentDescriptorSetBuildError {
+    #[inline]
+    fn description(&self) -> &str {
+        match *self {
+            PersistentDescriptorSetBuildError::OomError(_) => "not enough memory available",
+            PersistentDescriptorSetBuildError::MissingDescriptor { .. } => {
+                "a binding of the layout has not been filled"
+            },
+            PersistentDescriptorSetBuildError::UpdateTemplateCreationError(_) => {
+                "error while creating the update template of the layout"
+            },
+        }
+    }
+
+    #[inline]
+    fn cause(&self) -> Option<&error::Error> {
+        match *self {
+            PersistentDescriptorSetBuildError::OomError(ref err) => Some(err),
+            PersistentDescriptorSetBuildError::UpdateTemplateCreationError(ref err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for PersistentDescriptorSetBuildError {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(fmt, "{}", error::Error::description(self))
+    }
+}
+
+impl From<OomError> for PersistentDescriptorSetBuildError {
+    #[inline]
+    fn from(err: OomError) -> PersistentDescriptorSetBuildError {
+        PersistentDescriptorSetBuildError::OomError(err)
+    }
+}
+
+impl From<DescriptorUpdateTemplateCreationError> for PersistentDescriptorSetBuildError {
+    #[inline]
+    fn from(err: DescriptorUpdateTemplateCreationError) -> PersistentDescriptorSetBuildError {
+        PersistentDescriptorSetBuildError::UpdateTemplateCreationError(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::iter;
+    use std::sync::Arc;
+
+    use buffer::BufferUsage;
+    use buffer::BufferView;
+    use buffer::CpuAccessibleBuffer;
+    use descriptor::descriptor::DescriptorDescTy;
+    use descriptor::descriptor::DescriptorType;
+    use descriptor::descriptor_set::PersistentDescriptorSet;
+    use descriptor::descriptor_set::PersistentDescriptorSetBuildError;
+    use descriptor::descriptor_set::PersistentDescriptorSetError;
+    use descriptor::pipeline_layout::PipelineLayoutDesc;
+    use format::Format;
+    use format::R32Sfloat;
+    use sampler::Sampler;
+    use tests::TestLayout;
+    use tests::test_descriptor;
+    use tests::uniform_buffer_ty;
+
+    // Layout with a uniform buffer at binding 0, nothing at binding 1 and an array of two
+    // samplers at binding 2 of set 0.
+    fn test_layout() -> TestLayout {
+        TestLayout {
+            descriptors: vec![Some(test_descriptor(uniform_buffer_ty(false), 1)),
+                              None,
+                              Some(test_descriptor(DescriptorDescTy::Sampler, 2))],
+            names: vec![("uniforms", 0), ("samplers", 2)],
+        }
+    }
+
+    // Layout with a uniform texel buffer of `R32Uint` at binding 0 of set 0.
+    fn texel_buffer_layout() -> TestLayout {
+        let ty = DescriptorDescTy::TexelBuffer {
+            storage: false,
+            format: Some(Format::R32Uint),
+        };
+        TestLayout::new(vec![Some(test_descriptor(ty, 1))])
+    }
+
+    #[test]
+    fn build_with_array() {
+        let (device, queue) = gfx_dev_and_queue!();
+        let layout = test_layout().build(device.clone()).unwrap();
+
+        let buffer = CpuAccessibleBuffer::from_data(device.clone(),
+                                                    BufferUsage::all(),
+                                                    iter::once(queue.family()),
+                                                    0.0f32)
+            .unwrap();
+        let sampler = Sampler::simple_repeat_linear(device.clone());
+
+        let _set = PersistentDescriptorSet::start(layout, 0)
+            .add_buffer(buffer)
+            .unwrap()
+            .add_empty()
+            .unwrap()
+            .enter_array()
+            .unwrap()
+            .add_sampler(sampler.clone())
+            .unwrap()
+            .add_sampler(sampler)
+            .unwrap()
+            .leave_array()
+            .unwrap()
+            .build()
+            .unwrap();
+    }
+
+    #[test]
+    fn bindings_by_name() {
+        let (device, queue) = gfx_dev_and_queue!();
+        let layout = test_layout().build(device.clone()).unwrap();
+
+        let buffer = CpuAccessibleBuffer::from_data(device.clone(),
+                                                    BufferUsage::all(),
+                                                    iter::once(queue.family()),
+                                                    0.0f32)
+            .unwrap();
+        let sampler = Sampler::simple_repeat_linear(device.clone());
+
+        let _set = PersistentDescriptorSet::start(layout, 0)
+            .binding_by_name("samplers")
+            .unwrap()
+            .enter_array()
+            .unwrap()
+            .add_sampler(sampler.clone())
+            .unwrap()
+            .add_sampler(sampler)
+            .unwrap()
+            .leave_array()
+            .unwrap()
+            .binding_by_name("uniforms")
+            .unwrap()
+            .add_buffer(buffer)
+            .unwrap()
+            .build()
+            .unwrap();
+    }
+
+    #[test]
+    fn name_not_found() {
+        let (device, queue) = gfx_dev_and_queue!();
+        let layout = test_layout().build(device.clone()).unwrap();
+
+        match PersistentDescriptorSet::start(layout, 0).binding_by_name("foo") {
+            Err(PersistentDescriptorSetError::NameNotFound) => (),
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn wrong_descriptor_ty() {
+        let (device, queue) = gfx_dev_and_queue!();
+        let layout = test_layout().build(device.clone()).unwrap();
+        let sampler = Sampler::simple_repeat_linear(device.clone());
+
+        match PersistentDescriptorSet::start(layout, 0).add_sampler(sampler) {
+            Err(PersistentDescriptorSetError::WrongDescriptorTy {
+                    expected: DescriptorType::UniformBuffer,
+                }) => (),
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn array_out_of_bounds() {
+        let (device, queue) = gfx_dev_and_queue!();
+        let layout = test_layout().build(device.clone()).unwrap();
+        let sampler = Sampler::simple_repeat_linear(device.clone());
+
+        let array = PersistentDescriptorSet::start(layout, 0)
+            .binding(2)
+            .enter_array()
+            .unwrap()
+            .add_sampler(sampler.clone())
+            .unwrap()
+            .add_sampler(sampler.clone())
+            .unwrap();
+
+        match array.add_sampler(sampler) {
+            Err(PersistentDescriptorSetError::ArrayOutOfBounds) => (),
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn missing_array_elements() {
+        let (device, queue) = gfx_dev_and_queue!();
+        let layout = test_layout().build(device.clone()).unwrap();
+        let sampler = Sampler::simple_repeat_linear(device.clone());
+
+        // Binding 2 is an array of two samplers.
+        match PersistentDescriptorSet::start(layout, 0).binding(2).add_sampler(sampler) {
+            Err(PersistentDescriptorSetError::MissingArrayElements {
+                    expected: 2,
+                    obtained: 1,
+                }) => (),
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn missing_descriptor() {
+        let (device, queue) = gfx_dev_and_queue!();
+        let layout = test_layout().build(device.clone()).unwrap();
+
+        let buffer = CpuAccessibleBuffer::from_data(device.clone(),
+                                                    BufferUsage::all(),
+                                                    iter::once(queue.family()),
+                                                    0.0f32)
+            .unwrap();
+
+        let r = PersistentDescriptorSet::start(layout, 0)
+            .add_buffer(buffer)
+            .unwrap()
+            .build();
+
+        match r {
+            Err(PersistentDescriptorSetBuildError::MissingDescriptor { binding: 2 }) => (),
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn missing_usage() {
+        let (device, queue) = gfx_dev_and_queue!();
+        let layout = test_layout().build(device.clone()).unwrap();
+
+        let buffer = CpuAccessibleBuffer::from_data(device.clone(),
+                                                    BufferUsage::transfer_source(),
+                                                    iter::once(queue.family()),
+                                                    0.0f32)
+            .unwrap();
+
+        match PersistentDescriptorSet::start(layout, 0).add_buffer(buffer) {
+            Err(PersistentDescriptorSetError::MissingUsage) => (),
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn buffer_view_format_mismatch() {
+        let (device, queue) = gfx_dev_and_queue!();
+        let layout = texel_buffer_layout().build(device.clone()).unwrap();
+
+        let buffer = CpuAccessibleBuffer::from_iter(device.clone(),
+                                                    BufferUsage::all(),
+                                                    iter::once(queue.family()),
+                                                    (0 .. 16).map(|_| 0.0f32))
+            .unwrap();
+        let view = Arc::new(BufferView::new(buffer, R32Sfloat).unwrap());
+
+        match PersistentDescriptorSet::start(layout, 0).add_buffer_view(view) {
+            Err(PersistentDescriptorSetError::BufferViewFormatMismatch {
+                    expected: Format::R32Uint,
+                    obtained: Format::R32Sfloat,
+                }) => (),
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn immutable_samplers_skipped() {
+        let (device, queue) = gfx_dev_and_queue!();
+        let sampler = Sampler::simple_repeat_linear(device.clone());
+        let layout = test_layout()
+            .with_immutable_samplers(0, 2, vec![sampler.clone(), sampler])
+            .build(device.clone())
+            .unwrap();
+
+        let buffer = CpuAccessibleBuffer::from_data(device.clone(),
+                                                    BufferUsage::all(),
+                                                    iter::once(queue.family()),
+                                                    0.0f32)
+            .unwrap();
+
+        let _set = PersistentDescriptorSet::start(layout, 0)
+            .add_buffer(buffer)
+            .unwrap()
+            .build()
+            .unwrap();
+    }
+
+    #[test]
+    fn sampler_is_immutable() {
+        let (device, queue) = gfx_dev_and_queue!();
+        let sampler = Sampler::simple_repeat_linear(device.clone());
+        let layout = test_layout()
+            .with_immutable_samplers(0, 2, vec![sampler.clone(), sampler.clone()])
+            .build(device.clone())
+            .unwrap();
+
+        match PersistentDescriptorSet::start(layout, 0).binding(2).add_sampler(sampler) {
+            Err(PersistentDescriptorSetError::SamplerIsImmutable) => (),
+            _ => panic!(),
+        }
+    }
+}
@@ -6,9 +6,9 @@
 // at your option. All files in the project carrying such
 // notice may not be copied, modified, or distributed except
 // according to those terms.
-
+
 use std::sync::Arc;
-
+
 use buffer::BufferAccess;
 use buffer::BufferViewRef;
 use descriptor::descriptor::DescriptorDesc;
@@ -23,7 +23,6 @@ use descriptor::descriptor_set::UnsafeDescriptorSet;
 use descriptor::descriptor_set::UnsafeDescriptorSetLayout;
 use descriptor::pipeline_layout::PipelineLayoutAbstract;
 use device::Device;
-use device::DeviceOwned;
 use image::ImageAccess;
 use image::ImageLayout;
 use image::ImageViewAccess;
@@ -66,7 +65,8 @@ impl<R, P> SimpleDescriptorSet<R, P>
 }
 
 unsafe impl<R, P> DescriptorSet for SimpleDescriptorSet<R, P>
-    where P: DescriptorPool
+    where P: DescriptorPool,
+          R: SimpleDescriptorSetResourcesCollection
 {
     #[inline]
     fn inner(&self) -> &UnsafeDescriptorSet {
@@ -75,12 +75,23 @@ unsafe impl<R, P> DescriptorSet for SimpleDescriptorSet<R, P>
 
     #[inline]
     fn buffers_list<'a>(&'a self) -> Box<Iterator<Item = &'a BufferAccess> + 'a> {
-        unimplemented!()
+        let mut list = Vec::new();
+        self.resources.buffers(&mut list);
+        Box::new(list.into_iter())
     }
 
     #[inline]
     fn images_list<'a>(&'a self) -> Box<Iterator<Item = &'a ImageAccess> + 'a> {
-        unimplemented!()
+        let mut list = Vec::new();
+        self.resources.image_views(&mut list);
+        Box::new(list.into_iter().map(|(_, view)| view.parent()))
+    }
+
+    #[inline]
+    fn image_views<'a>(&'a self) -> Box<Iterator<Item = (usize, &'a ImageViewAccess)> + 'a> {
+        let mut list = Vec::new();
+        self.resources.image_views(&mut list);
+        Box::new(list.into_iter())
     }
 }
 
@@ -101,35 +112,35 @@ unsafe impl<R, P> DescriptorSetDesc for SimpleDescriptorSet<R, P>
 /// Builds a descriptor set in the form of a `SimpleDescriptorSet` object.
 // TODO: more doc
 #[macro_export]
-macro_rules! simple_descriptor_set {
-    ($layout:expr, $set_num:expr, {$($name:ident: $val:expr),*$(,)*}) => ({
-        #[allow(unused_imports)]
-        use $crate::descriptor::descriptor_set::SimpleDescriptorSetBuilder;
-        #[allow(unused_imports)]
-        use $crate::descriptor::descriptor_set::SimpleDescriptorSetBufferExt;
-        #[allow(unused_imports)]
-        use $crate::descriptor::descriptor_set::SimpleDescriptorSetImageExt;
-
-        // We build an empty `SimpleDescriptorSetBuilder` struct, then adds each element one by
-        // one. When done, we call `build()` on the builder.
-
-        let builder = SimpleDescriptorSetBuilder::new($layout, $set_num);
-
-        $(
-            // Here `$val` can be either a buffer or an image. However we can't create an extension
-            // trait for both buffers and image, because `impl<T: ImageAccess> ExtTrait for T {}` would
-            // conflict with `impl<T: BufferAccess> ExtTrait for T {}`.
-            //
-            // Therefore we use a trick: we create two traits, one for buffers
-            // (`SimpleDescriptorSetBufferExt`) and one for images (`SimpleDescriptorSetImageExt`),
-            // that both have a method named `add_me`. We import these two traits in scope and
-            // call `add_me` on the value, letting Rust dispatch to the right trait. A compilation
-            // error will happen if `$val` is both a buffer and an image.
-            let builder = $val.add_me(builder, stringify!($name));
-        )*
-
-        builder.build()
-    });
+macro_rules! simple_descriptor_set {
+    ($layout:expr, $set_num:expr, {$($name:ident: $val:expr),*$(,)*}) => ({
+        #[allow(unused_imports)]
+        use $crate::descriptor::descriptor_set::SimpleDescriptorSetBuilder;
+        #[allow(unused_imports)]
+        use $crate::descriptor::descriptor_set::SimpleDescriptorSetBufferExt;
+        #[allow(unused_imports)]
+        use $crate::descriptor::descriptor_set::SimpleDescriptorSetImageExt;
+
+        // We build an empty `SimpleDescriptorSetBuilder` struct, then adds each element one by
+        // one. When done, we call `build()` on the builder.
+
+        let builder = SimpleDescriptorSetBuilder::new($layout, $set_num);
+
+        $(
+            // Here `$val` can be either a buffer or an image. However we can't create an extension
+            // trait for both buffers and image, because `impl<T: ImageAccess> ExtTrait for T {}` would
+            // conflict with `impl<T: BufferAccess> ExtTrait for T {}`.
+            //
+            // Therefore we use a trick: we create two traits, one for buffers
+            // (`SimpleDescriptorSetBufferExt`) and one for images (`SimpleDescriptorSetImageExt`),
+            // that both have a method named `add_me`. We import these two traits in scope and
+            // call `add_me` on the value, letting Rust dispatch to the right trait. A compilation
+            // error will happen if `$val` is both a buffer and an image.
+            let builder = $val.add_me(builder, stringify!($name));
+        )*
+
+        builder.build()
+    });
 }
 
 /// Prototype of a `SimpleDescriptorSet`.
@@ -181,9 +192,20 @@ impl<L, R> SimpleDescriptorSetBuilder<L, R>
     where L: PipelineLayoutAbstract
 {
     /// Builds a `SimpleDescriptorSet` from the builder.
+    #[inline]
     pub fn build(self) -> SimpleDescriptorSet<R, Arc<StdDescriptorPool>> {
-        // TODO: check that we filled everything
         let pool = Device::standard_descriptor_pool(self.layout.device());
+        self.build_with_pool(&pool)
+    }
+
+    /// Builds a `SimpleDescriptorSet` from the builder, allocating it from `pool`.
+    ///
+    /// If you create descriptor sets very often, consider passing an
+    /// `Arc<FixedLayoutDescriptorPool>`.
+    pub fn build_with_pool<P>(self, pool: &P) -> SimpleDescriptorSet<R, P>
+        where P: DescriptorPool
+    {
+        // TODO: check that we filled everything
         let set_layout = self.layout
             .descriptor_set_layout(self.set_id)
             .unwrap()
@@ -227,15 +249,15 @@ unsafe impl<L, R, T> SimpleDescriptorSetBufferExt<L, R> for T
         assert_eq!(set_id, i.set_id); // TODO: Result instead
         let desc = i.layout.descriptor(set_id, binding_id).unwrap(); // TODO: Result instead
 
-        assert!(desc.array_count == 1); // not implemented
-        i.writes.push(match desc.ty.ty().unwrap() {
+        // If the descriptor is an array, only its first element is written.
+        i.writes.push(match desc.ty.ty().unwrap() {
                           DescriptorType::UniformBuffer => unsafe {
                               DescriptorWrite::uniform_buffer(binding_id as u32, 0, &self)
-                          },
+                          },
                           DescriptorType::StorageBuffer => unsafe {
                               DescriptorWrite::storage_buffer(binding_id as u32, 0, &self)
-                          },
-                          _ => panic!(),
+                          },
+                          _ => panic!(),
                       });
 
         SimpleDescriptorSetBuilder {
@@ -276,18 +298,26 @@ unsafe impl<L, R, T> SimpleDescriptorSetImageExt<L, R> for T
         assert_eq!(set_id, i.set_id); // TODO: Result instead
         let desc = i.layout.descriptor(set_id, binding_id).unwrap(); // TODO: Result instead
 
-        assert!(desc.array_count == 1); // not implemented
-        i.writes.push(match desc.ty.ty().unwrap() {
+        // If the descriptor is an array, only its first element is written.
+        i.writes.push(match desc.ty.ty().unwrap() {
                           DescriptorType::SampledImage => {
                               DescriptorWrite::sampled_image(binding_id as u32, 0, &self)
-                          },
+                          },
                           DescriptorType::StorageImage => {
                               DescriptorWrite::storage_image(binding_id as u32, 0, &self)
-                          },
+                          },
                           DescriptorType::InputAttachment => {
                               DescriptorWrite::input_attachment(binding_id as u32, 0, &self)
-                          },
-                          _ => panic!(),
+                          },
+                          DescriptorType::CombinedImageSampler
+                              if !desc.immutable_samplers.is_empty() => {
+                              // The sampler is ignored by the implementation.
+                              DescriptorWrite::combined_image_sampler(binding_id as u32,
+                                                                      0,
+                                                                      &desc.immutable_samplers[0],
+                                                                      &self)
+                          },
+                          _ => panic!(),
                       });
 
         SimpleDescriptorSetBuilder {
@@ -297,6 +327,7 @@ unsafe impl<L, R, T> SimpleDescriptorSetImageExt<L, R> for T
             resources: (i.resources,
                         SimpleDescriptorSetImg {
                             image: self,
+                            binding: binding_id,
                             sampler: None,
                             write: !desc.readonly,
                             first_mipmap: 0, // FIXME:
@@ -325,15 +356,16 @@ unsafe impl<L, R, T> SimpleDescriptorSetImageExt<L, R> for (T, Arc<Sampler>)
         assert_eq!(set_id, i.set_id); // TODO: Result instead
         let desc = i.layout.descriptor(set_id, binding_id).unwrap(); // TODO: Result instead
 
-        assert!(desc.array_count == 1); // not implemented
-        i.writes.push(match desc.ty.ty().unwrap() {
+        // If the descriptor is an array, only its first element is written.
+        assert!(desc.immutable_samplers.is_empty()); // TODO: Result instead
+        i.writes.push(match desc.ty.ty().unwrap() {
                           DescriptorType::CombinedImageSampler => {
                               DescriptorWrite::combined_image_sampler(binding_id as u32,
                                                                       0,
                                                                       &self.1,
                                                                       &image_view)
-                          },
-                          _ => panic!(),
+                          },
+                          _ => panic!(),
                       });
 
         SimpleDescriptorSetBuilder {
@@ -343,6 +375,7 @@ unsafe impl<L, R, T> SimpleDescriptorSetImageExt<L, R> for (T, Arc<Sampler>)
             resources: (i.resources,
                         SimpleDescriptorSetImg {
                             image: image_view,
+                            binding: binding_id,
                             sampler: Some(self.1),
                             write: !desc.readonly,
                             first_mipmap: 0, // FIXME:
@@ -371,21 +404,23 @@ unsafe impl<L, R, T> SimpleDescriptorSetImageExt<L, R> for Vec<(T, Arc<Sampler>)
         let desc = i.layout.descriptor(set_id, binding_id).unwrap(); // TODO: Result instead
 
         assert_eq!(desc.array_count as usize, self.len()); // not implemented
+        assert!(desc.immutable_samplers.is_empty()); // TODO: Result instead
 
         let mut imgs = Vec::new();
         for (num, (img, sampler)) in self.into_iter().enumerate() {
-            i.writes.push(match desc.ty.ty().unwrap() {
+            i.writes.push(match desc.ty.ty().unwrap() {
                               DescriptorType::CombinedImageSampler => {
                                   DescriptorWrite::combined_image_sampler(binding_id as u32,
                                                                           num as u32,
                                                                           &sampler,
                                                                           &img)
-                              },
-                              _ => panic!(),
+                              },
+                              _ => panic!(),
                           });
 
             imgs.push(SimpleDescriptorSetImg {
                           image: img,
+                          binding: binding_id,
                           sampler: Some(sampler),
                           write: !desc.readonly,
                           first_mipmap: 0, // FIXME:
@@ -407,18 +442,60 @@ unsafe impl<L, R, T> SimpleDescriptorSetImageExt<L, R> for Vec<(T, Arc<Sampler>)
     }
 }
 
-/*
-/// Internal trait related to the `SimpleDescriptorSet` system.
-pub unsafe trait SimpleDescriptorSetResourcesCollection {
-    #[inline]
-    fn add_transition<'a>(&'a self, sink: &mut CommandsListSink<'a>);
-}
-
-unsafe impl SimpleDescriptorSetResourcesCollection for () {
-    #[inline]
-    fn add_transition<'a>(&'a self, _: &mut CommandsListSink<'a>) {
-    }
-}*/
+/// Internal trait related to the `SimpleDescriptorSet` system. Implemented on the list of
+/// resources of a set.
+pub unsafe trait SimpleDescriptorSetResourcesCollection {
+    /// Appends the buffers of the list to `out`.
+    fn buffers<'a>(&'a self, out: &mut Vec<&'a BufferAccess>);
+
+    /// Appends the image views of the list to `out`, alongside with their binding.
+    fn image_views<'a>(&'a self, out: &mut Vec<(usize, &'a ImageViewAccess)>);
+}
+
+unsafe impl SimpleDescriptorSetResourcesCollection for () {
+    #[inline]
+    fn buffers<'a>(&'a self, _: &mut Vec<&'a BufferAccess>) {
+    }
+
+    #[inline]
+    fn image_views<'a>(&'a self, _: &mut Vec<(usize, &'a ImageViewAccess)>) {
+    }
+}
+
+unsafe impl<A, B> SimpleDescriptorSetResourcesCollection for (A, B)
+    where A: SimpleDescriptorSetResourcesCollection,
+          B: SimpleDescriptorSetResourcesCollection
+{
+    #[inline]
+    fn buffers<'a>(&'a self, out: &mut Vec<&'a BufferAccess>) {
+        self.0.buffers(out);
+        self.1.buffers(out);
+    }
+
+    #[inline]
+    fn image_views<'a>(&'a self, out: &mut Vec<(usize, &'a ImageViewAccess)>) {
+        self.0.image_views(out);
+        self.1.image_views(out);
+    }
+}
+
+unsafe impl<T> SimpleDescriptorSetResourcesCollection for Vec<T>
+    where T: SimpleDescriptorSetResourcesCollection
+{
+    #[inline]
+    fn buffers<'a>(&'a self, out: &mut Vec<&'a BufferAccess>) {
+        for elem in self {
+            elem.buffers(out);
+        }
+    }
+
+    #[inline]
+    fn image_views<'a>(&'a self, out: &mut Vec<(usize, &'a ImageViewAccess)>) {
+        for elem in self {
+            elem.image_views(out);
+        }
+    }
+}
 
 /// Internal object related to the `SimpleDescriptorSet` system.
 pub struct SimpleDescriptorSetBuf<B> {
@@ -428,28 +505,18 @@ pub struct SimpleDescriptorSetBuf<B> {
     access: AccessFlagBits,
 }
 
-/*unsafe impl<B> SimpleDescriptorSetResourcesCollection for SimpleDescriptorSetBuf<B>
-    where B: BufferAccess
-{
-    #[inline]
-    fn add_transition<'a>(&'a self, sink: &mut CommandsListSink<'a>) {
-        // TODO: wrong values
-        let stages = PipelineStages {
-            compute_shader: true,
-            all_graphics: true,
-            .. PipelineStages::none()
-        };
-        
-        let access = AccessFlagBits {
-            uniform_read: true,
-            shader_read: true,
-            shader_write: true,
-            .. AccessFlagBits::none()
-        };
-
-        sink.add_buffer_transition(&self.buffer, 0, self.buffer.size(), self.write, stages, access);
-    }
-}*/
+unsafe impl<B> SimpleDescriptorSetResourcesCollection for SimpleDescriptorSetBuf<B>
+    where B: BufferAccess
+{
+    #[inline]
+    fn buffers<'a>(&'a self, out: &mut Vec<&'a BufferAccess>) {
+        out.push(&self.buffer);
+    }
+
+    #[inline]
+    fn image_views<'a>(&'a self, _: &mut Vec<(usize, &'a ImageViewAccess)>) {
+    }
+}
 
 /// Internal object related to the `SimpleDescriptorSet` system.
 pub struct SimpleDescriptorSetBufView<V>
@@ -461,33 +528,24 @@ pub struct SimpleDescriptorSetBufView<V>
     access: AccessFlagBits,
 }
 
-/*unsafe impl<V> SimpleDescriptorSetResourcesCollection for SimpleDescriptorSetBufView<V>
-    where V: BufferViewRef, V::BufferAccess: BufferAccess
-{
-    #[inline]
-    fn add_transition<'a>(&'a self, sink: &mut CommandsListSink<'a>) {
-        // TODO: wrong values
-        let stages = PipelineStages {
-            compute_shader: true,
-            all_graphics: true,
-            .. PipelineStages::none()
-        };
-        
-        let access = AccessFlagBits {
-            uniform_read: true,
-            shader_read: true,
-            shader_write: true,
-            .. AccessFlagBits::none()
-        };
-
-        sink.add_buffer_transition(self.view.view().buffer(), 0, self.view.view().buffer().size(),
-                                   self.write, stages, access);
-    }
-}*/
+unsafe impl<V> SimpleDescriptorSetResourcesCollection for SimpleDescriptorSetBufView<V>
+    where V: BufferViewRef,
+          V::BufferAccess: BufferAccess
+{
+    #[inline]
+    fn buffers<'a>(&'a self, out: &mut Vec<&'a BufferAccess>) {
+        out.push(self.view.view().buffer());
+    }
+
+    #[inline]
+    fn image_views<'a>(&'a self, _: &mut Vec<(usize, &'a ImageViewAccess)>) {
+    }
+}
 
 /// Internal object related to the `SimpleDescriptorSet` system.
 pub struct SimpleDescriptorSetImg<I> {
     image: I,
+    binding: usize,
     sampler: Option<Arc<Sampler>>,
     write: bool,
     first_mipmap: u32,
@@ -499,40 +557,15 @@ pub struct SimpleDescriptorSetImg<I> {
     access: AccessFlagBits,
 }
 
-/*unsafe impl<I> SimpleDescriptorSetResourcesCollection for SimpleDescriptorSetImg<I>
-    where I: ImageViewAccess
-{
-    #[inline]
-    fn add_transition<'a>(&'a self, sink: &mut CommandsListSink<'a>) {
-        // TODO: wrong values
-        let stages = PipelineStages {
-            compute_shader: true,
-            all_graphics: true,
-            .. PipelineStages::none()
-        };
-        
-        let access = AccessFlagBits {
-            uniform_read: true,
-            input_attachment_read: true,
-            shader_read: true,
-            shader_write: true,
-            .. AccessFlagBits::none()
-        };
-
-        // FIXME: adjust layers & mipmaps with the view's parameters
-        sink.add_image_transition(self.image.parent(), self.first_layer, self.num_layers,
-                                  self.first_mipmap, self.num_mipmaps, self.write,
-                                  self.layout, stages, access);
-    }
-}
-
-unsafe impl<A, B> SimpleDescriptorSetResourcesCollection for (A, B)
-    where A: SimpleDescriptorSetResourcesCollection,
-          B: SimpleDescriptorSetResourcesCollection
-{
-    #[inline]
-    fn add_transition<'a>(&'a self, sink: &mut CommandsListSink<'a>) {
-        self.0.add_transition(sink);
-        self.1.add_transition(sink);
-    }
-}*/
+unsafe impl<I> SimpleDescriptorSetResourcesCollection for SimpleDescriptorSetImg<I>
+    where I: ImageViewAccess
+{
+    #[inline]
+    fn buffers<'a>(&'a self, _: &mut Vec<&'a BufferAccess>) {
+    }
+
+    #[inline]
+    fn image_views<'a>(&'a self, out: &mut Vec<(usize, &'a ImageViewAccess)>) {
+        out.push((self.binding, &self.image));
+    }
+}
@@ -24,27 +24,171 @@ use device::DeviceOwned;
 /// Standard implementation of a descriptor pool.
 ///
 /// Whenever a set is allocated, this implementation will try to find a pool that has some space
-/// for it. If there is one, allocate from it. If there is none, create a new pool whose capacity
-/// is 40 sets and 40 times the requested descriptors. This number is arbitrary.
+/// for it. If there is one, allocate from it. If there is none, create a new pool. By default,
+/// the capacity of the new pool is 40 sets and 40 times the requested descriptors. These numbers
+/// are arbitrary, and can be modified with `StdDescriptorPoolConfig`.
+///
+/// The Vulkan pools are never destroyed automatically, even when all of their sets have been
+/// freed. Call `trim` in order to destroy the pools that no longer contain any set, for example
+/// after a loading screen.
 pub struct StdDescriptorPool {
     device: Arc<Device>,
+    config: StdDescriptorPoolConfig,
     pools: Mutex<Vec<Arc<Mutex<Pool>>>>,
 }
 
 struct Pool {
     pool: UnsafeDescriptorPool,
+    // Total capacity of the pool.
+    capacity: DescriptorsCount,
+    sets_capacity: u32,
+    // Capacity that can still be allocated.
     remaining_capacity: DescriptorsCount,
     remaining_sets_count: u32,
+    // Number of sets allocated from this pool that haven't been destroyed.
+    live_sets: u32,
+}
+
+/// Configuration of a `StdDescriptorPool`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct StdDescriptorPoolConfig {
+    /// Maximum number of sets that each Vulkan pool can hold.
+    pub sets_per_pool: u32,
+    /// When a new Vulkan pool is created, its capacity of descriptors is the number of
+    /// descriptors of the requested layout multiplied by this value.
+    pub descriptors_multiplier: u32,
+    /// How the sets are made available again after they have been destroyed.
+    pub strategy: StdDescriptorPoolStrategy,
+}
+
+impl Default for StdDescriptorPoolConfig {
+    #[inline]
+    fn default() -> StdDescriptorPoolConfig {
+        StdDescriptorPoolConfig {
+            sets_per_pool: 40,
+            descriptors_multiplier: 40,
+            strategy: StdDescriptorPoolStrategy::FreeIndividually,
+        }
+    }
+}
+
+/// How a `StdDescriptorPool` reclaims the space of the sets that have been destroyed.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum StdDescriptorPoolStrategy {
+    /// Each set is freed as soon as it is destroyed.
+    FreeIndividually,
+
+    /// Destroying a set doesn't free it. Instead you are expected to call `reset` regularly,
+    /// for example once per frame, which resets at once all the Vulkan pools whose sets have all
+    /// been destroyed. This is cheaper than freeing the sets one by one, and avoids
+    /// fragmentation.
+    Reset,
+}
+
+/// Usage statistics of a `StdDescriptorPool`. Returned by `StdDescriptorPool::stats`.
+#[derive(Debug, Copy, Clone)]
+pub struct StdDescriptorPoolStats {
+    /// Number of Vulkan pools.
+    pub pools: usize,
+    /// Number of sets that are currently allocated and not destroyed.
+    pub allocated_sets: u32,
+    /// Total number of sets that the Vulkan pools can hold.
+    pub sets_capacity: u32,
+    /// Total number of descriptors of each type that the Vulkan pools can hold.
+    pub capacity: DescriptorsCount,
+    /// Number of descriptors of each type that can still be allocated without creating a new
+    /// Vulkan pool.
+    pub remaining_capacity: DescriptorsCount,
 }
 
 impl StdDescriptorPool {
-    /// Builds a new `StdDescriptorPool`.
+    /// Builds a new `StdDescriptorPool` with the default configuration.
+    #[inline]
     pub fn new(device: Arc<Device>) -> StdDescriptorPool {
+        StdDescriptorPool::with_config(device, StdDescriptorPoolConfig::default())
+    }
+
+    /// Builds a new `StdDescriptorPool` with the given configuration.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if `sets_per_pool` or `descriptors_multiplier` is 0.
+    ///
+    pub fn with_config(device: Arc<Device>, config: StdDescriptorPoolConfig)
+                       -> StdDescriptorPool {
+        assert_ne!(config.sets_per_pool, 0);
+        assert_ne!(config.descriptors_multiplier, 0);
+
         StdDescriptorPool {
             device: device,
+            config: config,
             pools: Mutex::new(Vec::new()),
         }
     }
+
+    /// Returns the configuration of the pool.
+    #[inline]
+    pub fn config(&self) -> &StdDescriptorPoolConfig {
+        &self.config
+    }
+
+    /// Returns the usage statistics of the pool.
+    pub fn stats(&self) -> StdDescriptorPoolStats {
+        let pools = self.pools.lock().unwrap();
+
+        let mut stats = StdDescriptorPoolStats {
+            pools: pools.len(),
+            allocated_sets: 0,
+            sets_capacity: 0,
+            capacity: DescriptorsCount::zero(),
+            remaining_capacity: DescriptorsCount::zero(),
+        };
+
+        for pool in pools.iter() {
+            let pool = pool.lock().unwrap();
+            stats.allocated_sets += pool.live_sets;
+            stats.sets_capacity += pool.sets_capacity;
+            stats.capacity += pool.capacity;
+            stats.remaining_capacity += pool.remaining_capacity;
+        }
+
+        stats
+    }
+
+    /// Destroys the Vulkan pools that don't contain any set, and returns the number of pools
+    /// that have been destroyed.
+    pub fn trim(&self) -> usize {
+        let mut pools = self.pools.lock().unwrap();
+        let before = pools.len();
+        pools.retain(|pool| pool.lock().unwrap().live_sets != 0);
+        before - pools.len()
+    }
+
+    /// Resets the Vulkan pools whose sets have all been destroyed, which makes their whole
+    /// capacity available again.
+    ///
+    /// This is mostly useful with `StdDescriptorPoolStrategy::Reset`, as otherwise the sets are
+    /// already freed individually.
+    pub fn reset(&self) -> Result<(), OomError> {
+        let pools = self.pools.lock().unwrap();
+
+        for pool in pools.iter() {
+            let mut pool = pool.lock().unwrap();
+
+            if pool.live_sets != 0 || pool.remaining_sets_count == pool.sets_capacity {
+                continue;
+            }
+
+            unsafe {
+                pool.pool.reset()?;
+            }
+
+            pool.remaining_capacity = pool.capacity;
+            pool.remaining_sets_count = pool.sets_capacity;
+        }
+
+        Ok(())
+    }
 }
 
 /// A descriptor set allocated from a `StdDescriptorPool`.
@@ -54,6 +198,8 @@ pub struct StdDescriptorPoolAlloc {
     set: Option<UnsafeDescriptorSet>,
     // We need to keep track of this count in order to add it back to the capacity when freeing.
     descriptors: DescriptorsCount,
+    // If false, the set is not freed when the allocation is destroyed.
+    free: bool,
 }
 
 unsafe impl DescriptorPool for Arc<StdDescriptorPool> {
@@ -63,6 +209,7 @@ unsafe impl DescriptorPool for Arc<StdDescriptorPool> {
     fn alloc(&self, layout: &UnsafeDescriptorSetLayout)
              -> Result<StdDescriptorPoolAlloc, OomError> {
         let mut pools = self.pools.lock().unwrap();
+        let free = self.config.strategy == StdDescriptorPoolStrategy::FreeIndividually;
 
         // Try find an existing pool with some free space.
         for pool_arc in pools.iter_mut() {
@@ -92,19 +239,23 @@ unsafe impl DescriptorPool for Arc<StdDescriptorPool> {
                 }
             };
 
+            pool.live_sets += 1;
+
             return Ok(StdDescriptorPoolAlloc {
                           pool: pool_arc.clone(),
                           set: Some(alloc),
                           descriptors: *layout.descriptors_count(),
+                          free: free,
                       });
         }
 
         // No existing pool can be used. Create a new one.
-        // We use an arbitrary number of 40 sets and 40 times the requested descriptors.
-        let count = layout.descriptors_count().clone() * 40;
+        let sets_count = self.config.sets_per_pool;
+        let count = layout.descriptors_count().clone() * self.config.descriptors_multiplier;
         // Failure to allocate a new pool results in an error for the whole function because
         // there's no way we can recover from that.
-        let mut new_pool = UnsafeDescriptorPool::new(self.device.clone(), &count, 40, true)?;
+        let mut new_pool = UnsafeDescriptorPool::new(self.device.clone(), &count, sets_count,
+                                                     free)?;
 
         let alloc = unsafe {
             match new_pool.alloc(Some(layout)) {
@@ -124,9 +275,12 @@ unsafe impl DescriptorPool for Arc<StdDescriptorPool> {
 
         let pool_obj = Arc::new(Mutex::new(Pool {
                                                pool: new_pool,
+                                               capacity: count,
+                                               sets_capacity: sets_count,
                                                remaining_capacity: count -
                                                    *layout.descriptors_count(),
-                                               remaining_sets_count: 40 - 1,
+                                               remaining_sets_count: sets_count - 1,
+                                               live_sets: 1,
                                            }));
 
         pools.push(pool_obj.clone());
@@ -135,6 +289,7 @@ unsafe impl DescriptorPool for Arc<StdDescriptorPool> {
                pool: pool_obj,
                set: Some(alloc),
                descriptors: *layout.descriptors_count(),
+               free: free,
            })
     }
 }
@@ -161,12 +316,103 @@ impl DescriptorPoolAlloc for StdDescriptorPoolAlloc {
 impl Drop for StdDescriptorPoolAlloc {
     // This is the destructor of a single allocation (not of the whole pool).
     fn drop(&mut self) {
+        let mut pool = self.pool.lock().unwrap();
+        pool.live_sets -= 1;
+
+        // With the reset strategy, the capacity is only added back when the pool is reset.
+        if !self.free {
+            return;
+        }
+
         unsafe {
-            let mut pool = self.pool.lock().unwrap();
             pool.pool.free(self.set.take()).unwrap();
-            // Add back the capacity only after freeing, in case of a panic during the free.
-            pool.remaining_sets_count += 1;
-            pool.remaining_capacity += self.descriptors;
         }
+
+        // Add back the capacity only after freeing, in case of a panic during the free.
+        pool.remaining_sets_count += 1;
+        pool.remaining_capacity += self.descriptors;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use descriptor::descriptor_set::DescriptorPool;
+    use descriptor::descriptor_set::StdDescriptorPool;
+    use descriptor::descriptor_set::StdDescriptorPoolConfig;
+    use descriptor::descriptor_set::StdDescriptorPoolStrategy;
+    use tests::uniform_buffer_layout;
+
+    #[test]
+    fn config() {
+        let (device, _) = gfx_dev_and_queue!();
+        let layout = uniform_buffer_layout(device.clone());
+        let config = StdDescriptorPoolConfig {
+            sets_per_pool: 4,
+            descriptors_multiplier: 8,
+            ..StdDescriptorPoolConfig::default()
+        };
+        let pool = Arc::new(StdDescriptorPool::with_config(device, config));
+
+        let sets = (0 .. 5)
+            .map(|_| pool.alloc(&layout).unwrap())
+            .collect::<Vec<_>>();
+
+        let stats = pool.stats();
+        assert_eq!(stats.pools, 2);
+        assert_eq!(stats.allocated_sets, sets.len() as u32);
+        assert_eq!(stats.sets_capacity, 8);
+        assert_eq!(stats.capacity.uniform_buffer, 16);
+        assert_eq!(stats.remaining_capacity.uniform_buffer, 11);
+    }
+
+    #[test]
+    fn trim() {
+        let (device, _) = gfx_dev_and_queue!();
+        let layout = uniform_buffer_layout(device.clone());
+        let pool = Arc::new(StdDescriptorPool::new(device));
+
+        let set = pool.alloc(&layout).unwrap();
+        assert_eq!(pool.trim(), 0);
+        assert_eq!(pool.stats().pools, 1);
+
+        drop(set);
+        assert_eq!(pool.trim(), 1);
+        assert_eq!(pool.stats().pools, 0);
+    }
+
+    #[test]
+    fn reset_strategy() {
+        let (device, _) = gfx_dev_and_queue!();
+        let layout = uniform_buffer_layout(device.clone());
+        let config = StdDescriptorPoolConfig {
+            sets_per_pool: 1,
+            strategy: StdDescriptorPoolStrategy::Reset,
+            ..StdDescriptorPoolConfig::default()
+        };
+        let pool = Arc::new(StdDescriptorPool::with_config(device, config));
+
+        drop(pool.alloc(&layout).unwrap());
+        assert_eq!(pool.stats().remaining_capacity.uniform_buffer, 39);
+
+        pool.reset().unwrap();
+        assert_eq!(pool.stats().remaining_capacity.uniform_buffer, 40);
+
+        let _ = pool.alloc(&layout).unwrap();
+        assert_eq!(pool.stats().pools, 1);
+    }
+
+    #[test]
+    fn zero_sets_per_pool() {
+        let (device, _) = gfx_dev_and_queue!();
+        let config = StdDescriptorPoolConfig {
+            sets_per_pool: 0,
+            ..StdDescriptorPoolConfig::default()
+        };
+
+        assert_should_panic!({
+            let _ = StdDescriptorPool::with_config(device, config);
+        });
     }
 }
@@ -21,6 +21,7 @@ use buffer::BufferAccess;
 use buffer::BufferInner;
 use buffer::BufferView;
 use descriptor::descriptor::DescriptorType;
+use descriptor::descriptor_set::DescriptorUpdateTemplate;
 use descriptor::descriptor_set::UnsafeDescriptorSetLayout;
 use device::Device;
 use device::DeviceOwned;
@@ -221,6 +222,8 @@ descriptors_count! {
 pub struct UnsafeDescriptorPool {
     pool: vk::DescriptorPool,
     device: Arc<Device>,
+    // True if sets whose layout can be updated after being bound can be allocated from the pool.
+    update_after_bind: bool,
 }
 
 impl UnsafeDescriptorPool {
@@ -237,10 +240,36 @@ impl UnsafeDescriptorPool {
     /// - Panics if all the descriptors count are 0.
     /// - Panics if `max_sets` is 0.
     ///
+    #[inline]
     pub fn new(device: Arc<Device>, count: &DescriptorsCount, max_sets: u32,
                free_descriptor_set_bit: bool)
                -> Result<UnsafeDescriptorPool, OomError> {
+        let flags = if free_descriptor_set_bit {
+            vk::DESCRIPTOR_POOL_CREATE_FREE_DESCRIPTOR_SET_BIT
+        } else {
+            0
+        };
+
+        UnsafeDescriptorPool::new_impl(device, count, max_sets, flags)
+    }
+
+    // Same as `new`, but sets whose layout was created with
+    // `UnsafeDescriptorSetLayout::new_update_after_bind` can be allocated from the pool.
+    // Individual sets can't be free'd.
+    #[inline]
+    pub(crate) fn new_update_after_bind(device: Arc<Device>, count: &DescriptorsCount,
+                                        max_sets: u32)
+                                        -> Result<UnsafeDescriptorPool, OomError> {
+        UnsafeDescriptorPool::new_impl(device,
+                                       count,
+                                       max_sets,
+                                       vk::DESCRIPTOR_POOL_CREATE_UPDATE_AFTER_BIND_BIT_EXT)
+    }
+
+    fn new_impl(device: Arc<Device>, count: &DescriptorsCount, max_sets: u32, flags: u32)
+                -> Result<UnsafeDescriptorPool, OomError> {
         let vk = device.pointers();
+        let update_after_bind = (flags & vk::DESCRIPTOR_POOL_CREATE_UPDATE_AFTER_BIND_BIT_EXT) != 0;
 
         assert_ne!(max_sets, 0, "The maximum number of sets can't be 0");
 
@@ -281,11 +310,7 @@ impl UnsafeDescriptorPool {
             let infos = vk::DescriptorPoolCreateInfo {
                 sType: vk::STRUCTURE_TYPE_DESCRIPTOR_POOL_CREATE_INFO,
                 pNext: ptr::null(),
-                flags: if free_descriptor_set_bit {
-                    vk::DESCRIPTOR_POOL_CREATE_FREE_DESCRIPTOR_SET_BIT
-                } else {
-                    0
-                },
+                flags: flags,
                 maxSets: max_sets,
                 poolSizeCount: pool_sizes.len() as u32,
                 pPoolSizes: pool_sizes.as_ptr(),
@@ -294,7 +319,7 @@ impl UnsafeDescriptorPool {
             let mut output = mem::uninitialized();
             check_errors(vk.CreateDescriptorPool(device.internal_object(),
                                                  &infos,
-                                                 ptr::null(),
+                                                 device.allocation_callbacks().as_ptr(),
                                                  &mut output))?;
             output
         };
@@ -302,6 +327,7 @@ impl UnsafeDescriptorPool {
         Ok(UnsafeDescriptorPool {
                pool: pool,
                device: device.clone(),
+               update_after_bind: update_after_bind,
            })
     }
 
@@ -314,6 +340,8 @@ impl UnsafeDescriptorPool {
     /// # Panic
     ///
     /// - Panics if one of the layouts wasn't created with the same device as the pool.
+    /// - Panics if one of the layouts can be updated after being bound, as such layouts can only
+    ///   be allocated from pools that are created internally by vulkano.
     ///
     /// # Safety
     ///
@@ -336,6 +364,9 @@ impl UnsafeDescriptorPool {
                                 l.device().internal_object(),
                                 "Tried to allocate from a pool with a set layout of a different \
                                  device");
+                     assert!(!l.update_after_bind() || self.update_after_bind,
+                             "Tried to allocate a set whose layout can be updated after being \
+                              bound from a regular pool");
                      l.internal_object()
                  })
             .collect();
@@ -458,7 +489,9 @@ impl Drop for UnsafeDescriptorPool {
     fn drop(&mut self) {
         unsafe {
             let vk = self.device.pointers();
-            vk.DestroyDescriptorPool(self.device.internal_object(), self.pool, ptr::null());
+            vk.DestroyDescriptorPool(self.device.internal_object(),
+                                     self.pool,
+                                     self.device.allocation_callbacks().as_ptr());
         }
     }
 }
@@ -536,6 +569,14 @@ pub struct UnsafeDescriptorSet {
 }
 
 impl UnsafeDescriptorSet {
+    // Builds another object that refers to the same Vulkan descriptor set. Used by objects that
+    // write to a set that they share with other objects. The caller must ensure that the set
+    // isn't written from multiple threads at once.
+    #[inline]
+    pub(crate) unsafe fn alias(&self) -> UnsafeDescriptorSet {
+        UnsafeDescriptorSet { set: self.set }
+    }
+
     // TODO: add copying from other descriptor sets
     //       add a `copy` method that just takes a copy, and an `update` method that takes both
     //       writes and copies and that actually performs the operation
@@ -719,6 +760,40 @@ impl UnsafeDescriptorSet {
                                     ptr::null());
         }
     }
+
+    /// Modifies a descriptor set by using a descriptor update template. This is faster than
+    /// `write`, as the implementation doesn't need to go through a list of writes.
+    ///
+    /// For each entry of the template, the implementation reads the descriptors from `data` at
+    /// the offset of the entry, then at the offset plus the stride, and so on. Depending on the
+    /// type of the entry, each descriptor is a `VkDescriptorImageInfo`, a
+    /// `VkDescriptorBufferInfo` or a `VkBufferView`. You can use `DescriptorUpdateTemplate::data`
+    /// to build such data from a list of `DescriptorWrite`s.
+    ///
+    /// # Safety
+    ///
+    /// - The `Device` must be the device the pool of this set was created with.
+    /// - The template must have been created with the layout this set was created with, or with
+    ///   a compatible layout.
+    /// - `data` must contain valid descriptors at the locations described by the template.
+    /// - The same safety rules as `write` apply to the descriptors contained in `data`.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if `data` is smaller than `template.data_size()`.
+    ///
+    pub unsafe fn write_with_template<D>(&mut self, device: &Device,
+                                         template: &DescriptorUpdateTemplate, data: &D)
+        where D: ?Sized
+    {
+        assert!(mem::size_of_val(data) >= template.data_size());
+
+        let vk = device.pointers();
+        vk.UpdateDescriptorSetWithTemplateKHR(device.internal_object(),
+                                              self.set,
+                                              template.internal_object(),
+                                              data as *const D as *const _);
+    }
 }
 
 unsafe impl VulkanObject for UnsafeDescriptorSet {
@@ -966,6 +1041,72 @@ impl DescriptorWrite {
             DescriptorWriteInner::InputAttachment(_, _) => DescriptorType::InputAttachment,
         }
     }
+
+    // Returns the binding that this write modifies.
+    #[inline]
+    pub(crate) fn binding(&self) -> u32 {
+        self.binding
+    }
+
+    // Returns the first array element that this write modifies.
+    #[inline]
+    pub(crate) fn first_array_element(&self) -> u32 {
+        self.first_array_element
+    }
+
+    // Returns the number of descriptors that this write modifies.
+    #[inline]
+    pub(crate) fn num_descriptors(&self) -> u32 {
+        self.inner.len() as u32
+    }
+
+    // Writes the descriptor `index` of this write at `dst`, in the format expected by descriptor
+    // update templates. `dst` doesn't need to be aligned.
+    pub(crate) unsafe fn write_raw(&self, index: usize, dst: *mut u8) {
+        match self.inner[index] {
+            DescriptorWriteInner::UniformBuffer(buffer, offset, size) |
+            DescriptorWriteInner::StorageBuffer(buffer, offset, size) |
+            DescriptorWriteInner::DynamicUniformBuffer(buffer, offset, size) |
+            DescriptorWriteInner::DynamicStorageBuffer(buffer, offset, size) => {
+                ptr::write_unaligned(dst as *mut vk::DescriptorBufferInfo,
+                                     vk::DescriptorBufferInfo {
+                                         buffer: buffer,
+                                         offset: offset as u64,
+                                         range: size as u64,
+                                     });
+            },
+            DescriptorWriteInner::Sampler(sampler) => {
+                ptr::write_unaligned(dst as *mut vk::DescriptorImageInfo,
+                                     vk::DescriptorImageInfo {
+                                         sampler: sampler,
+                                         imageView: 0,
+                                         imageLayout: 0,
+                                     });
+            },
+            DescriptorWriteInner::CombinedImageSampler(sampler, view, layout) => {
+                ptr::write_unaligned(dst as *mut vk::DescriptorImageInfo,
+                                     vk::DescriptorImageInfo {
+                                         sampler: sampler,
+                                         imageView: view,
+                                         imageLayout: layout,
+                                     });
+            },
+            DescriptorWriteInner::StorageImage(view, layout) |
+            DescriptorWriteInner::SampledImage(view, layout) |
+            DescriptorWriteInner::InputAttachment(view, layout) => {
+                ptr::write_unaligned(dst as *mut vk::DescriptorImageInfo,
+                                     vk::DescriptorImageInfo {
+                                         sampler: 0,
+                                         imageView: view,
+                                         imageLayout: layout,
+                                     });
+            },
+            DescriptorWriteInner::UniformTexelBuffer(view) |
+            DescriptorWriteInner::StorageTexelBuffer(view) => {
+                ptr::write_unaligned(dst as *mut vk::BufferView, view);
+            },
+        }
+    }
 }
 
 #[cfg(test)]
@@ -1026,6 +1167,7 @@ mod tests {
             array_count: 1,
             stages: ShaderStages::all_graphics(),
             readonly: true,
+            immutable_samplers: Vec::new(),
         };
 
         let set_layout = UnsafeDescriptorSetLayout::new(device.clone(), iter::once(Some(layout)))
@@ -1057,6 +1199,7 @@ mod tests {
             array_count: 1,
             stages: ShaderStages::all_graphics(),
             readonly: true,
+            immutable_samplers: Vec::new(),
         };
 
         let set_layout = UnsafeDescriptorSetLayout::new(device1, iter::once(Some(layout))).unwrap();
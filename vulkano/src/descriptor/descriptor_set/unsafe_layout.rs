@@ -12,6 +12,7 @@ use std::fmt;
 use std::mem;
 use std::ptr;
 use std::sync::Arc;
+use std::sync::Mutex;
 
 use OomError;
 use VulkanObject;
@@ -19,9 +20,15 @@ use check_errors;
 use vk;
 
 use descriptor::descriptor::DescriptorDesc;
+use descriptor::descriptor::DescriptorType;
+use descriptor::descriptor_set::DescriptorUpdateTemplate;
+use descriptor::descriptor_set::DescriptorUpdateTemplateCreationError;
+use descriptor::descriptor_set::DescriptorUpdateTemplateEntry;
 use descriptor::descriptor_set::DescriptorsCount;
+use descriptor::descriptor_set::update_template;
 use device::Device;
 use device::DeviceOwned;
+use sampler::Sampler;
 
 /// Describes to the Vulkan implementation the layout of all descriptors within a descriptor set.
 ///
@@ -35,6 +42,15 @@ pub struct UnsafeDescriptorSetLayout {
     device: Arc<Device>,
     // Number of descriptors.
     descriptors_count: DescriptorsCount,
+    // Immutable samplers baked into the layout. Kept alive for as long as the layout exists.
+    samplers: Vec<Arc<Sampler>>,
+    // For each binding, the type and array count of its descriptor, and whether it is a sampler
+    // descriptor whose samplers are immutable. `None` if the binding is empty.
+    bindings: Vec<Option<(DescriptorType, u32, bool)>>,
+    // True if the layout was created with `new_update_after_bind`.
+    update_after_bind: bool,
+    // Template that writes all the descriptors of the layout. Created on first use.
+    update_template: Mutex<Option<Arc<DescriptorUpdateTemplate>>>,
 }
 
 impl UnsafeDescriptorSetLayout {
@@ -43,44 +59,150 @@ impl UnsafeDescriptorSetLayout {
     /// The descriptors must be passed in the order of the bindings. In order words, descriptor
     /// at bind point 0 first, then descriptor at bind point 1, and so on. If a binding must remain
     /// empty, you can make the iterator yield `None` for an element.
+    ///
+    /// The immutable samplers of the descriptors are baked into the layout and are kept alive for
+    /// as long as the layout is alive.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if the type of a descriptor is unknown, which is the case of a buffer descriptor
+    ///   that doesn't specify whether it is dynamic.
+    /// - Panics if a descriptor has immutable samplers but is not a sampler or a combined image
+    ///   sampler, or if the number of immutable samplers is not equal to its array count.
+    /// - Panics if an immutable sampler doesn't belong to `device`.
+    ///
+    #[inline]
     pub fn new<I>(device: Arc<Device>, descriptors: I)
                   -> Result<UnsafeDescriptorSetLayout, OomError>
         where I: IntoIterator<Item = Option<DescriptorDesc>>
+    {
+        UnsafeDescriptorSetLayout::new_impl(device, descriptors, false)
+    }
+
+    // Same as `new`, but the descriptors of the layout are partially bound and can be updated
+    // after the set has been bound, as long as they aren't used by the pending command buffers.
+    // Sets with this layout must be allocated from a pool created with `new_update_after_bind`.
+    //
+    // Requires the corresponding features of `VK_EXT_descriptor_indexing`, which the caller must
+    // check.
+    #[inline]
+    pub(crate) fn new_update_after_bind<I>(device: Arc<Device>, descriptors: I)
+                                           -> Result<UnsafeDescriptorSetLayout, OomError>
+        where I: IntoIterator<Item = Option<DescriptorDesc>>
+    {
+        UnsafeDescriptorSetLayout::new_impl(device, descriptors, true)
+    }
+
+    fn new_impl<I>(device: Arc<Device>, descriptors: I, update_after_bind: bool)
+                   -> Result<UnsafeDescriptorSetLayout, OomError>
+        where I: IntoIterator<Item = Option<DescriptorDesc>>
     {
         let mut descriptors_count = DescriptorsCount::zero();
+        let mut samplers = Vec::new();
+        let mut layout_bindings = Vec::new();
 
-        let bindings = descriptors
-            .into_iter()
-            .enumerate()
-            .filter_map(|(binding, desc)| {
-                let desc = match desc {
-                    Some(d) => d,
-                    None => return None,
-                };
+        // The list of descriptors, alongside their type and the offset of their immutable
+        // samplers within `samplers`. We can't take pointers to `sampler_handles` before it is
+        // fully built.
+        let mut descs: SmallVec<[_; 32]> = SmallVec::new();
+        for (binding, desc) in descriptors.into_iter().enumerate() {
+            let desc = match desc {
+                Some(d) => d,
+                None => {
+                    layout_bindings.push(None);
+                    continue;
+                },
+            };
+
+            let ty = match desc.ty.ty() {
+                Some(ty) => ty,
+                None => panic!("The type of the descriptor at binding {} is unknown", binding),
+            };
+
+            let immutable_sampler = ty == DescriptorType::Sampler &&
+                !desc.immutable_samplers.is_empty();
+            layout_bindings.push(Some((ty, desc.array_count, immutable_sampler)));
+
+            if !desc.immutable_samplers.is_empty() {
+                match ty {
+                    DescriptorType::Sampler |
+                    DescriptorType::CombinedImageSampler => (),
+                    _ => panic!("Immutable samplers can only be used with sampler or combined \
+                                 image sampler descriptors"),
+                }
 
+                assert_eq!(desc.immutable_samplers.len(), desc.array_count as usize);
+
+                for sampler in desc.immutable_samplers.iter() {
+                    assert_eq!(sampler.device().internal_object(), device.internal_object());
+                }
+            }
+
+            let offset = samplers.len();
+            samplers.extend(desc.immutable_samplers.iter().cloned());
+            descs.push((binding, desc, ty, offset));
+        }
+
+        let sampler_handles = samplers
+            .iter()
+            .map(|s| s.internal_object())
+            .collect::<SmallVec<[_; 16]>>();
+
+        let bindings = descs
+            .iter()
+            .map(|&(binding, ref desc, ty, offset)| {
                 // FIXME: it is not legal to pass eg. the TESSELLATION_SHADER bit when the device
                 //        doesn't have tess shaders enabled
 
-                let ty = desc.ty.ty().unwrap(); // TODO: shouldn't panic
                 descriptors_count.add_one(ty);
 
-                Some(vk::DescriptorSetLayoutBinding {
-                         binding: binding as u32,
-                         descriptorType: ty as u32,
-                         descriptorCount: desc.array_count,
-                         stageFlags: desc.stages.into(),
-                         pImmutableSamplers: ptr::null(), // FIXME: not yet implemented
-                     })
+                let immutable_samplers = if desc.immutable_samplers.is_empty() {
+                    ptr::null()
+                } else {
+                    sampler_handles[offset ..].as_ptr()
+                };
+
+                vk::DescriptorSetLayoutBinding {
+                    binding: binding as u32,
+                    descriptorType: ty as u32,
+                    descriptorCount: desc.array_count,
+                    stageFlags: desc.stages.into(),
+                    pImmutableSamplers: immutable_samplers,
+                }
             })
             .collect::<SmallVec<[_; 32]>>();
 
         // Note that it seems legal to have no descriptor at all in the set.
 
         let layout = unsafe {
+            let binding_flags = bindings
+                .iter()
+                .map(|_| {
+                    vk::DESCRIPTOR_BINDING_UPDATE_AFTER_BIND_BIT_EXT |
+                        vk::DESCRIPTOR_BINDING_UPDATE_UNUSED_WHILE_PENDING_BIT_EXT |
+                        vk::DESCRIPTOR_BINDING_PARTIALLY_BOUND_BIT_EXT
+                })
+                .collect::<SmallVec<[_; 32]>>();
+
+            let binding_flags_infos = vk::DescriptorSetLayoutBindingFlagsCreateInfoEXT {
+                sType: vk::STRUCTURE_TYPE_DESCRIPTOR_SET_LAYOUT_BINDING_FLAGS_CREATE_INFO_EXT,
+                pNext: ptr::null(),
+                bindingCount: binding_flags.len() as u32,
+                pBindingFlags: binding_flags.as_ptr(),
+            };
+
             let infos = vk::DescriptorSetLayoutCreateInfo {
                 sType: vk::STRUCTURE_TYPE_DESCRIPTOR_SET_LAYOUT_CREATE_INFO,
-                pNext: ptr::null(),
-                flags: 0, // reserved
+                pNext: if update_after_bind {
+                    &binding_flags_infos as *const _ as *const _
+                } else {
+                    ptr::null()
+                },
+                flags: if update_after_bind {
+                    vk::DESCRIPTOR_SET_LAYOUT_CREATE_UPDATE_AFTER_BIND_POOL_BIT_EXT
+                } else {
+                    0
+                },
                 bindingCount: bindings.len() as u32,
                 pBindings: bindings.as_ptr(),
             };
@@ -89,7 +211,7 @@ impl UnsafeDescriptorSetLayout {
             let vk = device.pointers();
             check_errors(vk.CreateDescriptorSetLayout(device.internal_object(),
                                                       &infos,
-                                                      ptr::null(),
+                                                      device.allocation_callbacks().as_ptr(),
                                                       &mut output))?;
             output
         };
@@ -98,6 +220,10 @@ impl UnsafeDescriptorSetLayout {
                layout: layout,
                device: device,
                descriptors_count: descriptors_count,
+               samplers: samplers,
+               bindings: layout_bindings,
+               update_after_bind: update_after_bind,
+               update_template: Mutex::new(None),
            })
     }
 
@@ -106,6 +232,72 @@ impl UnsafeDescriptorSetLayout {
     pub fn descriptors_count(&self) -> &DescriptorsCount {
         &self.descriptors_count
     }
+
+    // Returns true if the layout was created with `new_update_after_bind`.
+    #[inline]
+    pub(crate) fn update_after_bind(&self) -> bool {
+        self.update_after_bind
+    }
+
+    // Returns the type and array count of the descriptor of a binding, or `None` if the binding
+    // is empty.
+    #[inline]
+    pub(crate) fn binding(&self, binding: u32) -> Option<(DescriptorType, u32)> {
+        match self.bindings.get(binding as usize) {
+            Some(&Some((ty, array_count, _))) => Some((ty, array_count)),
+            _ => None,
+        }
+    }
+
+    // Returns a template that writes all the descriptors of the layout, except for the sampler
+    // descriptors whose samplers are immutable. The descriptors are packed in the data in the
+    // order of the bindings, then of the array elements.
+    //
+    // Returns `None` if the `VK_KHR_descriptor_update_template` extension isn't enabled or if
+    // there is nothing to write, in which case the caller is expected to fall back to regular
+    // writes.
+    pub(crate) fn update_template(&self)
+                                  -> Result<Option<Arc<DescriptorUpdateTemplate>>,
+                                            DescriptorUpdateTemplateCreationError> {
+        if !self.device.loaded_extensions().khr_descriptor_update_template {
+            return Ok(None);
+        }
+
+        let mut update_template = self.update_template.lock().unwrap();
+        if let Some(ref template) = *update_template {
+            return Ok(Some(template.clone()));
+        }
+
+        let mut offset = 0;
+        let entries = self.bindings
+            .iter()
+            .enumerate()
+            .filter_map(|(binding, desc)| match *desc {
+                Some((ty, array_count, false)) if array_count != 0 => {
+                    let stride = update_template::descriptor_size(ty);
+                    let entry = DescriptorUpdateTemplateEntry {
+                        binding: binding as u32,
+                        first_array_element: 0,
+                        descriptor_count: array_count,
+                        ty: ty,
+                        offset: offset,
+                        stride: stride,
+                    };
+                    offset += stride * array_count as usize;
+                    Some(entry)
+                },
+                _ => None,
+            })
+            .collect::<SmallVec<[_; 32]>>();
+
+        if entries.is_empty() {
+            return Ok(None);
+        }
+
+        let template = Arc::new(DescriptorUpdateTemplate::new(self, entries)?);
+        *update_template = Some(template.clone());
+        Ok(Some(template))
+    }
 }
 
 unsafe impl DeviceOwned for UnsafeDescriptorSetLayout {
@@ -138,7 +330,9 @@ impl Drop for UnsafeDescriptorSetLayout {
     fn drop(&mut self) {
         unsafe {
             let vk = self.device.pointers();
-            vk.DestroyDescriptorSetLayout(self.device.internal_object(), self.layout, ptr::null());
+            vk.DestroyDescriptorSetLayout(self.device.internal_object(),
+                                          self.layout,
+                                          self.device.allocation_callbacks().as_ptr());
         }
     }
 }
@@ -152,6 +346,7 @@ mod tests {
     use descriptor::descriptor::ShaderStages;
     use descriptor::descriptor_set::DescriptorsCount;
     use descriptor::descriptor_set::UnsafeDescriptorSetLayout;
+    use sampler::Sampler;
     use std::iter;
 
     #[test]
@@ -173,6 +368,7 @@ mod tests {
             array_count: 1,
             stages: ShaderStages::all_graphics(),
             readonly: true,
+            immutable_samplers: Vec::new(),
         };
 
         let sl = UnsafeDescriptorSetLayout::new(device.clone(), iter::once(Some(layout))).unwrap();
@@ -183,4 +379,66 @@ mod tests {
                        ..DescriptorsCount::zero()
                    });
     }
+
+    #[test]
+    fn immutable_samplers() {
+        let (device, _) = gfx_dev_and_queue!();
+        let sampler = Sampler::simple_repeat_linear(device.clone());
+
+        let layout = DescriptorDesc {
+            ty: DescriptorDescTy::Sampler,
+            array_count: 2,
+            stages: ShaderStages::all_graphics(),
+            readonly: true,
+            immutable_samplers: vec![sampler.clone(), sampler],
+        };
+
+        let sl = UnsafeDescriptorSetLayout::new(device.clone(), iter::once(Some(layout))).unwrap();
+
+        assert_eq!(sl.descriptors_count(),
+                   &DescriptorsCount {
+                       sampler: 2,
+                       ..DescriptorsCount::zero()
+                   });
+    }
+
+    #[test]
+    fn immutable_samplers_wrong_count() {
+        let (device, _) = gfx_dev_and_queue!();
+        let sampler = Sampler::simple_repeat_linear(device.clone());
+
+        let layout = DescriptorDesc {
+            ty: DescriptorDescTy::Sampler,
+            array_count: 2,
+            stages: ShaderStages::all_graphics(),
+            readonly: true,
+            immutable_samplers: vec![sampler],
+        };
+
+        assert_should_panic!({
+            let _ = UnsafeDescriptorSetLayout::new(device, iter::once(Some(layout)));
+        });
+    }
+
+    #[test]
+    fn unknown_descriptor_type() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        let layout = DescriptorDesc {
+            ty: DescriptorDescTy::Buffer(DescriptorBufferDesc {
+                                             dynamic: None,
+                                             storage: false,
+                                             content: DescriptorBufferContentDesc::F32,
+                                         }),
+            array_count: 1,
+            stages: ShaderStages::all_graphics(),
+            readonly: true,
+            immutable_samplers: Vec::new(),
+        };
+
+        let descs = iter::once(None).chain(iter::once(Some(layout)));
+        assert_should_panic!("The type of the descriptor at binding 1 is unknown", {
+            let _ = UnsafeDescriptorSetLayout::new(device, descs);
+        });
+    }
 }
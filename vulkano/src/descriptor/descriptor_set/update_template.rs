@@ -0,0 +1,495 @@
+// Copyright (c) 2017 The vulkano developers
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>,
+// at your option. All files in the project carrying such
+// notice may not be copied, modified, or distributed except
+// according to those terms.
+
+use smallvec::SmallVec;
+use std::error;
+use std::fmt;
+use std::mem;
+use std::ptr;
+use std::sync::Arc;
+
+use Error;
+use OomError;
+use VulkanObject;
+use check_errors;
+use vk;
+
+use descriptor::descriptor::DescriptorType;
+use descriptor::descriptor_set::DescriptorWrite;
+use descriptor::descriptor_set::UnsafeDescriptorSetLayout;
+use device::Device;
+use device::DeviceOwned;
+
+/// Describes to the Vulkan implementation how to read the descriptors to write to a descriptor
+/// set from a block of memory.
+///
+/// Updating a descriptor set with a template, with `UnsafeDescriptorSet::write_with_template`,
+/// is cheaper than updating it with a list of `DescriptorWrite`s. The builder of
+/// `PersistentDescriptorSet` automatically uses templates if the
+/// `VK_KHR_descriptor_update_template` extension is enabled.
+pub struct DescriptorUpdateTemplate {
+    // The template.
+    template: vk::DescriptorUpdateTemplateKHR,
+    // The device this template belongs to.
+    device: Arc<Device>,
+    // The entries of the template.
+    entries: SmallVec<[DescriptorUpdateTemplateEntry; 8]>,
+    // Minimum size in bytes of the data passed when writing with this template.
+    data_size: usize,
+}
+
+impl DescriptorUpdateTemplate {
+    /// Builds a new `DescriptorUpdateTemplate` that writes to descriptor sets with the given
+    /// layout.
+    ///
+    /// Each entry describes the location within the data of the descriptors of a range of array
+    /// elements of a binding.
+    ///
+    /// The `VK_KHR_descriptor_update_template` extension must have been enabled on the device.
+    /// Otherwise an error is returned.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if `entries` is empty.
+    ///
+    pub fn new<I>(layout: &UnsafeDescriptorSetLayout, entries: I)
+                  -> Result<DescriptorUpdateTemplate, DescriptorUpdateTemplateCreationError>
+        where I: IntoIterator<Item = DescriptorUpdateTemplateEntry>
+    {
+        let device = layout.device().clone();
+
+        if !device.loaded_extensions().khr_descriptor_update_template {
+            return Err(DescriptorUpdateTemplateCreationError::ExtensionNotEnabled);
+        }
+
+        let entries = entries.into_iter().collect::<SmallVec<[_; 8]>>();
+        assert!(!entries.is_empty());
+
+        let mut data_size = 0;
+
+        for entry in entries.iter() {
+            let (ty, array_count) = match layout.binding(entry.binding) {
+                Some(b) => b,
+                None => {
+                    return Err(DescriptorUpdateTemplateCreationError::NoDescriptor {
+                                   binding: entry.binding,
+                               });
+                },
+            };
+
+            if ty != entry.ty {
+                return Err(DescriptorUpdateTemplateCreationError::WrongDescriptorType {
+                               binding: entry.binding,
+                           });
+            }
+
+            if entry.first_array_element + entry.descriptor_count > array_count {
+                return Err(DescriptorUpdateTemplateCreationError::ArrayOutOfRange {
+                               binding: entry.binding,
+                           });
+            }
+
+            if entry.descriptor_count != 0 {
+                let end = entry.offset + (entry.descriptor_count as usize - 1) * entry.stride +
+                    descriptor_size(entry.ty);
+                if end > data_size {
+                    data_size = end;
+                }
+            }
+        }
+
+        let raw_entries = entries
+            .iter()
+            .map(|entry| {
+                vk::DescriptorUpdateTemplateEntryKHR {
+                    dstBinding: entry.binding,
+                    dstArrayElement: entry.first_array_element,
+                    descriptorCount: entry.descriptor_count,
+                    descriptorType: entry.ty as u32,
+                    offset: entry.offset,
+                    stride: entry.stride,
+                }
+            })
+            .collect::<SmallVec<[_; 8]>>();
+
+        let template = unsafe {
+            let infos = vk::DescriptorUpdateTemplateCreateInfoKHR {
+                sType: vk::STRUCTURE_TYPE_DESCRIPTOR_UPDATE_TEMPLATE_CREATE_INFO_KHR,
+                pNext: ptr::null(),
+                flags: 0, // reserved
+                descriptorUpdateEntryCount: raw_entries.len() as u32,
+                pDescriptorUpdateEntries: raw_entries.as_ptr(),
+                templateType: vk::DESCRIPTOR_UPDATE_TEMPLATE_TYPE_DESCRIPTOR_SET_KHR,
+                descriptorSetLayout: layout.internal_object(),
+                // The next three fields are only used for push descriptors.
+                pipelineBindPoint: 0,
+                pipelineLayout: 0,
+                set: 0,
+            };
+
+            let mut output = mem::uninitialized();
+            let vk = device.pointers();
+            check_errors(vk.CreateDescriptorUpdateTemplateKHR(device.internal_object(),
+                                                              &infos,
+                                                              device
+                                                                  .allocation_callbacks()
+                                                                  .as_ptr(),
+                                                              &mut output))?;
+            output
+        };
+
+        Ok(DescriptorUpdateTemplate {
+               template: template,
+               device: device,
+               entries: entries,
+               data_size: data_size,
+           })
+    }
+
+    /// Returns the entries of the template.
+    #[inline]
+    pub fn entries(&self) -> &[DescriptorUpdateTemplateEntry] {
+        &self.entries
+    }
+
+    /// Returns the minimum size in bytes of the data to pass when writing with this template.
+    #[inline]
+    pub fn data_size(&self) -> usize {
+        self.data_size
+    }
+
+    /// Builds the data to pass to `UnsafeDescriptorSet::write_with_template` in order to perform
+    /// the given writes.
+    ///
+    /// The data is returned as a list of `u64`s so that the descriptors are correctly aligned.
+    ///
+    /// Returns `None` if the writes don't match the template, in other words if a written
+    /// descriptor isn't part of any entry, or if some descriptors of the entries aren't written.
+    pub fn data<'a, I>(&self, writes: I) -> Option<Vec<u64>>
+        where I: IntoIterator<Item = &'a DescriptorWrite>
+    {
+        let mut data = vec![0u64; (self.data_size + 7) / 8];
+
+        // For each entry, the index of its first descriptor in `written`.
+        let mut first_written = SmallVec::<[usize; 8]>::new();
+        let mut num_descriptors = 0;
+        for entry in self.entries.iter() {
+            first_written.push(num_descriptors);
+            num_descriptors += entry.descriptor_count as usize;
+        }
+
+        // For each descriptor of each entry, true if it has been written.
+        let mut written = vec![false; num_descriptors];
+
+        for write in writes {
+            for index in 0 .. write.num_descriptors() {
+                let array_element = write.first_array_element() + index;
+
+                let entry_id = self.entries.iter().position(|entry| {
+                    entry.binding == write.binding() && entry.ty == write.ty() &&
+                        array_element >= entry.first_array_element &&
+                        array_element < entry.first_array_element + entry.descriptor_count
+                });
+
+                let entry_id = match entry_id {
+                    Some(id) => id,
+                    None => return None,
+                };
+
+                let entry = &self.entries[entry_id];
+                let element = (array_element - entry.first_array_element) as usize;
+                let offset = entry.offset + element * entry.stride;
+
+                unsafe {
+                    let dst = (data.as_mut_ptr() as *mut u8).offset(offset as isize);
+                    write.write_raw(index as usize, dst);
+                }
+
+                written[first_written[entry_id] + element] = true;
+            }
+        }
+
+        if written.iter().any(|&w| !w) {
+            return None;
+        }
+
+        Some(data)
+    }
+}
+
+unsafe impl DeviceOwned for DescriptorUpdateTemplate {
+    #[inline]
+    fn device(&self) -> &Arc<Device> {
+        &self.device
+    }
+}
+
+impl fmt::Debug for DescriptorUpdateTemplate {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        fmt.debug_struct("DescriptorUpdateTemplate")
+            .field("raw", &self.template)
+            .field("device", &self.device)
+            .field("entries", &self.entries)
+            .finish()
+    }
+}
+
+unsafe impl VulkanObject for DescriptorUpdateTemplate {
+    type Object = vk::DescriptorUpdateTemplateKHR;
+
+    #[inline]
+    fn internal_object(&self) -> vk::DescriptorUpdateTemplateKHR {
+        self.template
+    }
+}
+
+impl Drop for DescriptorUpdateTemplate {
+    #[inline]
+    fn drop(&mut self) {
+        unsafe {
+            let vk = self.device.pointers();
+            vk.DestroyDescriptorUpdateTemplateKHR(self.device.internal_object(),
+                                                  self.template,
+                                                  self.device.allocation_callbacks().as_ptr());
+        }
+    }
+}
+
+/// Describes where the descriptors of a range of array elements of a binding are located within
+/// the data passed when writing with a `DescriptorUpdateTemplate`.
+///
+/// Depending on the type of the descriptor, each descriptor is read as a `VkDescriptorImageInfo`,
+/// a `VkDescriptorBufferInfo` or a `VkBufferView`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct DescriptorUpdateTemplateEntry {
+    /// The binding to write to.
+    pub binding: u32,
+    /// The first array element of the binding to write to.
+    pub first_array_element: u32,
+    /// The number of consecutive array elements to write.
+    pub descriptor_count: u32,
+    /// The type of the descriptors. Must match the type of the binding in the layout.
+    pub ty: DescriptorType,
+    /// Offset in bytes of the first descriptor within the data.
+    pub offset: usize,
+    /// Distance in bytes between two consecutive descriptors within the data.
+    pub stride: usize,
+}
+
+// Returns the size in bytes of a descriptor of the given type within the data of a template.
+#[inline]
+pub(crate) fn descriptor_size(ty: DescriptorType) -> usize {
+    match ty {
+        DescriptorType::Sampler |
+        DescriptorType::CombinedImageSampler |
+        DescriptorType::SampledImage |
+        DescriptorType::StorageImage |
+        DescriptorType::InputAttachment => mem::size_of::<vk::DescriptorImageInfo>(),
+        DescriptorType::UniformTexelBuffer |
+        DescriptorType::StorageTexelBuffer => mem::size_of::<vk::BufferView>(),
+        DescriptorType::UniformBuffer |
+        DescriptorType::StorageBuffer |
+        DescriptorType::UniformBufferDynamic |
+        DescriptorType::StorageBufferDynamic => mem::size_of::<vk::DescriptorBufferInfo>(),
+    }
+}
+
+/// Error that can happen when creating a `DescriptorUpdateTemplate`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DescriptorUpdateTemplateCreationError {
+    /// Not enough memory.
+    OomError(OomError),
+
+    /// The `KHR_descriptor_update_template` extension was not enabled.
+    ExtensionNotEnabled,
+
+    /// An entry refers to a binding that doesn't contain any descriptor in the layout.
+    NoDescriptor {
+        /// The binding of the entry.
+        binding: u32,
+    },
+
+    /// The type of an entry doesn't match the type of its binding in the layout.
+    WrongDescriptorType {
+        /// The binding of the entry.
+        binding: u32,
+    },
+
+    /// An entry covers array elements that are out of range of its binding.
+    ArrayOutOfRange {
+        /// The binding of the entry.
+        binding: u32,
+    },
+}
+
+impl error::Error for DescriptorUpdateTemplateCreationError {
+    #[inline]
+    fn description(&self) -> &str {
+        match *self {
+            DescriptorUpdateTemplateCreationError::OomError(_) => "not enough memory available",
+            DescriptorUpdateTemplateCreationError::ExtensionNotEnabled =>
+                "the `KHR_descriptor_update_template` extension was not enabled",
+            DescriptorUpdateTemplateCreationError::NoDescriptor { .. } =>
+                "an entry refers to a binding that doesn't contain any descriptor",
+            DescriptorUpdateTemplateCreationError::WrongDescriptorType { .. } =>
+                "the type of an entry doesn't match the type of its binding",
+            DescriptorUpdateTemplateCreationError::ArrayOutOfRange { .. } =>
+                "an entry covers array elements that are out of range of its binding",
+        }
+    }
+
+    #[inline]
+    fn cause(&self) -> Option<&error::Error> {
+        match *self {
+            DescriptorUpdateTemplateCreationError::OomError(ref err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for DescriptorUpdateTemplateCreationError {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(fmt, "{}", error::Error::description(self))
+    }
+}
+
+impl From<OomError> for DescriptorUpdateTemplateCreationError {
+    #[inline]
+    fn from(err: OomError) -> DescriptorUpdateTemplateCreationError {
+        DescriptorUpdateTemplateCreationError::OomError(err)
+    }
+}
+
+impl From<Error> for DescriptorUpdateTemplateCreationError {
+    #[inline]
+    fn from(err: Error) -> DescriptorUpdateTemplateCreationError {
+        match err {
+            err @ Error::OutOfHostMemory => {
+                DescriptorUpdateTemplateCreationError::OomError(OomError::from(err))
+            },
+            err @ Error::OutOfDeviceMemory => {
+                DescriptorUpdateTemplateCreationError::OomError(OomError::from(err))
+            },
+            _ => panic!("unexpected error: {:?}", err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use buffer::BufferUsage;
+    use buffer::CpuAccessibleBuffer;
+    use descriptor::descriptor::DescriptorBufferContentDesc;
+    use descriptor::descriptor::DescriptorBufferDesc;
+    use descriptor::descriptor::DescriptorDesc;
+    use descriptor::descriptor::DescriptorDescTy;
+    use descriptor::descriptor::DescriptorType;
+    use descriptor::descriptor::ShaderStages;
+    use descriptor::descriptor_set::DescriptorUpdateTemplate;
+    use descriptor::descriptor_set::DescriptorUpdateTemplateCreationError;
+    use descriptor::descriptor_set::DescriptorUpdateTemplateEntry;
+    use descriptor::descriptor_set::DescriptorWrite;
+    use descriptor::descriptor_set::DescriptorsCount;
+    use descriptor::descriptor_set::UnsafeDescriptorPool;
+    use descriptor::descriptor_set::UnsafeDescriptorSetLayout;
+    use descriptor::descriptor_set::update_template;
+    use std::iter;
+
+    #[test]
+    fn check_extension_when_create() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        let desc = DescriptorDesc {
+            ty: DescriptorDescTy::Buffer(DescriptorBufferDesc {
+                                             dynamic: Some(false),
+                                             storage: false,
+                                             content: DescriptorBufferContentDesc::F32,
+                                         }),
+            array_count: 1,
+            stages: ShaderStages::all_graphics(),
+            readonly: true,
+            immutable_samplers: Vec::new(),
+        };
+
+        let layout = UnsafeDescriptorSetLayout::new(device, iter::once(Some(desc))).unwrap();
+
+        let entry = DescriptorUpdateTemplateEntry {
+            binding: 0,
+            first_array_element: 0,
+            descriptor_count: 1,
+            ty: DescriptorType::UniformBuffer,
+            offset: 0,
+            stride: 0,
+        };
+
+        match DescriptorUpdateTemplate::new(&layout, iter::once(entry)) {
+            Err(DescriptorUpdateTemplateCreationError::ExtensionNotEnabled) => (),
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn create_and_write() {
+        let (device, queue) = gfx_dev_and_queue!(extensions: [khr_descriptor_update_template];);
+
+        let desc = DescriptorDesc {
+            ty: DescriptorDescTy::Buffer(DescriptorBufferDesc {
+                                             dynamic: Some(false),
+                                             storage: false,
+                                             content: DescriptorBufferContentDesc::F32,
+                                         }),
+            array_count: 2,
+            stages: ShaderStages::all_graphics(),
+            readonly: true,
+            immutable_samplers: Vec::new(),
+        };
+
+        let layout = UnsafeDescriptorSetLayout::new(device.clone(), iter::once(Some(desc)))
+            .unwrap();
+
+        // The template of the layout writes the two elements of the array.
+        let template = layout.update_template().unwrap().unwrap();
+        let stride = update_template::descriptor_size(DescriptorType::UniformBuffer);
+        assert_eq!(template.entries(),
+                   &[DescriptorUpdateTemplateEntry {
+                        binding: 0,
+                        first_array_element: 0,
+                        descriptor_count: 2,
+                        ty: DescriptorType::UniformBuffer,
+                        offset: 0,
+                        stride: stride,
+                    }]);
+        assert_eq!(template.data_size(), stride * 2);
+
+        let buffer = CpuAccessibleBuffer::from_data(device.clone(),
+                                                    BufferUsage::all(),
+                                                    iter::once(queue.family()),
+                                                    0.0f32)
+            .unwrap();
+        let writes = unsafe {
+            vec![DescriptorWrite::uniform_buffer(0, 0, &buffer),
+                 DescriptorWrite::uniform_buffer(0, 1, &buffer)]
+        };
+
+        // All the descriptors of the template must be written.
+        assert!(template.data(writes[.. 1].iter()).is_none());
+        let data = template.data(writes.iter()).unwrap();
+
+        let count = DescriptorsCount {
+            uniform_buffer: 2,
+            ..DescriptorsCount::zero()
+        };
+        let mut pool = UnsafeDescriptorPool::new(device.clone(), &count, 1, false).unwrap();
+        unsafe {
+            let mut set = pool.alloc(iter::once(&layout)).unwrap().next().unwrap();
+            set.write_with_template(&device, &template, &data[..]);
+        }
+    }
+}
@@ -0,0 +1,104 @@
+// Copyright (c) 2017 The vulkano developers
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>,
+// at your option. All files in the project carrying such
+// notice may not be copied, modified, or distributed except
+// according to those terms.
+
+use descriptor::descriptor::DescriptorDesc;
+use descriptor::descriptor_set::BindlessTable;
+use descriptor::descriptor_set::UnsafeDescriptorSetLayout;
+use descriptor::pipeline_layout::PipelineLayoutDesc;
+use descriptor::pipeline_layout::PipelineLayoutDescNames;
+use descriptor::pipeline_layout::PipelineLayoutDescPcRange;
+use std::cmp;
+use std::sync::Arc;
+
+/// Wraps around a pipeline layout description and replaces one of its sets with the layout of a
+/// `BindlessTable`.
+///
+/// The shader macros can't know the size of the runtime-sized arrays of a shader, therefore the
+/// layouts they generate must be adjusted before they can be used with a bindless table.
+///
+/// # Example
+///
+/// ```ignore
+/// let layout = fs::Layout(ShaderStages::all()).with_bindless_table(1, &table);
+/// ```
+pub struct PipelineLayoutDescBindlessTable<L> {
+    layout: L,
+    set: usize,
+    table: Arc<BindlessTable>,
+}
+
+impl<L> PipelineLayoutDescBindlessTable<L>
+    where L: PipelineLayoutDesc
+{
+    /// Builds a new wrapper that uses the layout of `table` for the given set.
+    #[inline]
+    pub fn new(layout: L, set: usize, table: &Arc<BindlessTable>)
+               -> PipelineLayoutDescBindlessTable<L> {
+        PipelineLayoutDescBindlessTable {
+            layout: layout,
+            set: set,
+            table: table.clone(),
+        }
+    }
+}
+
+unsafe impl<L> PipelineLayoutDesc for PipelineLayoutDescBindlessTable<L>
+    where L: PipelineLayoutDesc
+{
+    #[inline]
+    fn num_sets(&self) -> usize {
+        cmp::max(self.layout.num_sets(), self.set + 1)
+    }
+
+    #[inline]
+    fn num_bindings_in_set(&self, set: usize) -> Option<usize> {
+        if set == self.set {
+            Some(2)
+        } else {
+            self.layout.num_bindings_in_set(set)
+        }
+    }
+
+    #[inline]
+    fn descriptor(&self, set: usize, binding: usize) -> Option<DescriptorDesc> {
+        if set == self.set {
+            self.table.descriptor(binding)
+        } else {
+            self.layout.descriptor(set, binding)
+        }
+    }
+
+    #[inline]
+    fn provided_set_layout(&self, set: usize) -> Option<Arc<UnsafeDescriptorSetLayout>> {
+        if set == self.set {
+            Some(self.table.layout().clone())
+        } else {
+            self.layout.provided_set_layout(set)
+        }
+    }
+
+    #[inline]
+    fn num_push_constants_ranges(&self) -> usize {
+        self.layout.num_push_constants_ranges()
+    }
+
+    #[inline]
+    fn push_constants_range(&self, num: usize) -> Option<PipelineLayoutDescPcRange> {
+        self.layout.push_constants_range(num)
+    }
+}
+
+unsafe impl<L> PipelineLayoutDescNames for PipelineLayoutDescBindlessTable<L>
+    where L: PipelineLayoutDescNames
+{
+    #[inline]
+    fn descriptor_by_name(&self, name: &str) -> Option<(usize, usize)> {
+        self.layout.descriptor_by_name(name)
+    }
+}
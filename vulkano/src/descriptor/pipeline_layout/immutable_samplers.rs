@@ -0,0 +1,130 @@
+// Copyright (c) 2017 The vulkano developers
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>,
+// at your option. All files in the project carrying such
+// notice may not be copied, modified, or distributed except
+// according to those terms.
+
+use descriptor::descriptor::DescriptorDesc;
+use descriptor::descriptor::DescriptorDescTy;
+use descriptor::descriptor_set::UnsafeDescriptorSetLayout;
+use descriptor::pipeline_layout::PipelineLayoutDesc;
+use descriptor::pipeline_layout::PipelineLayoutDescNames;
+use descriptor::pipeline_layout::PipelineLayoutDescPcRange;
+use sampler::Sampler;
+use std::sync::Arc;
+
+/// Wraps around a pipeline layout description and turns the samplers of one of its descriptors
+/// into immutable samplers.
+///
+/// This is typically used with the layouts generated by the shader macros, as the shader itself
+/// has no way to indicate which samplers are immutable. Use `with_immutable_samplers` multiple
+/// times in order to mark multiple descriptors.
+///
+/// # Example
+///
+/// ```ignore
+/// let layout = fs::Layout(ShaderStages::all())
+///     .with_immutable_samplers(0, 1, vec![sampler.clone()]);
+/// ```
+pub struct PipelineLayoutDescImmutableSamplers<L> {
+    layout: L,
+    set: usize,
+    binding: usize,
+    samplers: Vec<Arc<Sampler>>,
+}
+
+impl<L> PipelineLayoutDescImmutableSamplers<L>
+    where L: PipelineLayoutDesc
+{
+    /// Builds a new wrapper that uses `samplers` as the immutable samplers of the given binding.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if `layout` doesn't have a sampler or combined image sampler descriptor at this
+    ///   binding.
+    /// - Panics if the number of samplers is not equal to the array count of the descriptor.
+    ///
+    pub fn new(layout: L, set: usize, binding: usize, samplers: Vec<Arc<Sampler>>)
+               -> PipelineLayoutDescImmutableSamplers<L> {
+        {
+            let desc = layout
+                .descriptor(set, binding)
+                .expect("No descriptor at the binding of the immutable samplers");
+
+            match desc.ty {
+                DescriptorDescTy::Sampler |
+                DescriptorDescTy::CombinedImageSampler(_) => (),
+                _ => panic!("Immutable samplers can only be used with sampler or combined image \
+                             sampler descriptors"),
+            }
+
+            assert_eq!(samplers.len(), desc.array_count as usize);
+        }
+
+        PipelineLayoutDescImmutableSamplers {
+            layout: layout,
+            set: set,
+            binding: binding,
+            samplers: samplers,
+        }
+    }
+}
+
+unsafe impl<L> PipelineLayoutDesc for PipelineLayoutDescImmutableSamplers<L>
+    where L: PipelineLayoutDesc
+{
+    #[inline]
+    fn num_sets(&self) -> usize {
+        self.layout.num_sets()
+    }
+
+    #[inline]
+    fn num_bindings_in_set(&self, set: usize) -> Option<usize> {
+        self.layout.num_bindings_in_set(set)
+    }
+
+    #[inline]
+    fn descriptor(&self, set: usize, binding: usize) -> Option<DescriptorDesc> {
+        let mut desc = self.layout.descriptor(set, binding);
+
+        if set == self.set && binding == self.binding {
+            if let Some(ref mut desc) = desc {
+                desc.immutable_samplers = self.samplers.clone();
+            }
+        }
+
+        desc
+    }
+
+    #[inline]
+    fn provided_set_layout(&self, set: usize) -> Option<Arc<UnsafeDescriptorSetLayout>> {
+        // A layout provided by the inner description wouldn't contain the immutable samplers.
+        if set == self.set {
+            None
+        } else {
+            self.layout.provided_set_layout(set)
+        }
+    }
+
+    #[inline]
+    fn num_push_constants_ranges(&self) -> usize {
+        self.layout.num_push_constants_ranges()
+    }
+
+    #[inline]
+    fn push_constants_range(&self, num: usize) -> Option<PipelineLayoutDescPcRange> {
+        self.layout.push_constants_range(num)
+    }
+}
+
+unsafe impl<L> PipelineLayoutDescNames for PipelineLayoutDescImmutableSamplers<L>
+    where L: PipelineLayoutDescNames
+{
+    #[inline]
+    fn descriptor_by_name(&self, name: &str) -> Option<(usize, usize)> {
+        self.layout.descriptor_by_name(name)
+    }
+}
@@ -49,7 +49,9 @@
 //!
 //! TODO: write this section
 
+pub use self::bindless::PipelineLayoutDescBindlessTable;
 pub use self::empty::EmptyPipelineDesc;
+pub use self::immutable_samplers::PipelineLayoutDescImmutableSamplers;
 pub use self::sys::PipelineLayout;
 pub use self::sys::PipelineLayoutCreationError;
 pub use self::sys::PipelineLayoutSys;
@@ -63,7 +65,9 @@ pub use self::traits::PipelineLayoutSetsCompatible;
 pub use self::traits::PipelineLayoutSuperset;
 pub use self::union::PipelineLayoutDescUnion;
 
+mod bindless;
 mod empty;
+mod immutable_samplers;
 mod sys;
 mod traits;
 mod union;
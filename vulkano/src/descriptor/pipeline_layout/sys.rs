@@ -48,6 +48,7 @@ impl<L> PipelineLayout<L>
     ///
     /// - Panics if one of the layout returned by `provided_set_layout()` belongs to a different
     ///   device than the one passed as parameter.
+    /// - Panics if the type of one of the descriptors of `desc` is unknown.
     #[inline]
     pub fn new(device: Arc<Device>, desc: L)
                -> Result<PipelineLayout<L>, PipelineLayoutCreationError> {
@@ -156,7 +157,7 @@ impl<L> PipelineLayout<L>
             let mut output = mem::uninitialized();
             check_errors(vk.CreatePipelineLayout(device.internal_object(),
                                                  &infos,
-                                                 ptr::null(),
+                                                 device.allocation_callbacks().as_ptr(),
                                                  &mut output))?;
             output
         };
@@ -256,7 +257,9 @@ impl<L> Drop for PipelineLayout<L> {
     fn drop(&mut self) {
         unsafe {
             let vk = self.device.pointers();
-            vk.DestroyPipelineLayout(self.device.internal_object(), self.layout, ptr::null());
+            vk.DestroyPipelineLayout(self.device.internal_object(),
+                                     self.layout,
+                                     self.device.allocation_callbacks().as_ptr());
         }
     }
 }
@@ -15,14 +15,18 @@ use std::sync::Arc;
 use SafeDeref;
 use descriptor::descriptor::DescriptorDesc;
 use descriptor::descriptor::ShaderStages;
+use descriptor::descriptor_set::BindlessTable;
 use descriptor::descriptor_set::DescriptorSetsCollection;
 use descriptor::descriptor_set::UnsafeDescriptorSetLayout;
 use descriptor::pipeline_layout::PipelineLayout;
 use descriptor::pipeline_layout::PipelineLayoutCreationError;
+use descriptor::pipeline_layout::PipelineLayoutDescBindlessTable;
+use descriptor::pipeline_layout::PipelineLayoutDescImmutableSamplers;
 use descriptor::pipeline_layout::PipelineLayoutDescUnion;
 use descriptor::pipeline_layout::PipelineLayoutSys;
 use device::Device;
 use device::DeviceOwned;
+use sampler::Sampler;
 
 /// Trait for objects that describe the layout of the descriptors and push constants of a pipeline.
 // TODO: meh for PipelineLayoutDescNames ; the `Names` thing shouldn't be mandatory
@@ -100,6 +104,28 @@ pub unsafe trait PipelineLayoutDesc {
         PipelineLayoutDescUnion::new(self, other)
     }
 
+    /// Turns the samplers of a descriptor of this layout into immutable samplers.
+    ///
+    /// See the documentation of `PipelineLayoutDescImmutableSamplers` for more info.
+    #[inline]
+    fn with_immutable_samplers(self, set: usize, binding: usize, samplers: Vec<Arc<Sampler>>)
+                               -> PipelineLayoutDescImmutableSamplers<Self>
+        where Self: Sized
+    {
+        PipelineLayoutDescImmutableSamplers::new(self, set, binding, samplers)
+    }
+
+    /// Replaces a set of this layout with the layout of a `BindlessTable`.
+    ///
+    /// See the documentation of `PipelineLayoutDescBindlessTable` for more info.
+    #[inline]
+    fn with_bindless_table(self, set: usize, table: &Arc<BindlessTable>)
+                           -> PipelineLayoutDescBindlessTable<Self>
+        where Self: Sized
+    {
+        PipelineLayoutDescBindlessTable::new(self, set, table)
+    }
+
     /// Turns the layout description into a `PipelineLayout` object that can be used by Vulkan.
     ///
     /// > **Note**: This is just a shortcut for `PipelineLayout::new`.
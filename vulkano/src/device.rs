@@ -104,6 +104,7 @@ use std::sync::Mutex;
 use std::sync::MutexGuard;
 use std::sync::Weak;
 
+use command_buffer::DownsamplePipeline;
 use command_buffer::pool::StandardCommandPool;
 use descriptor::descriptor_set::StdDescriptorPool;
 use instance::Features;
@@ -111,10 +112,13 @@ use instance::Instance;
 use instance::PhysicalDevice;
 use instance::QueueFamily;
 use memory::pool::StdMemoryPool;
+use sampler::Sampler;
+use sampler::SamplerKey;
 
 use Error;
 use OomError;
 use SynchronizedVulkanObject;
+use alloc::AllocationCallbacks;
 use VulkanObject;
 use check_errors;
 use vk;
@@ -131,6 +135,8 @@ pub struct Device {
     standard_descriptor_pool: Mutex<Weak<StdDescriptorPool>>,
     standard_command_pools:
         Mutex<HashMap<u32, Weak<StandardCommandPool>, BuildHasherDefault<FnvHasher>>>,
+    samplers: Mutex<HashMap<SamplerKey, Weak<Sampler>, BuildHasherDefault<FnvHasher>>>,
+    downsample_pipeline: Mutex<Weak<DownsamplePipeline>>,
     features: Features,
     extensions: DeviceExtensions,
 }
@@ -175,6 +181,15 @@ impl Device {
             return Err(DeviceCreationError::FeatureNotPresent);
         }
 
+        let extensions = extensions.into();
+
+        // The features of `VK_EXT_descriptor_indexing` can only be enabled alongside the extension.
+        if requested_features.has_descriptor_indexing() &&
+            !DeviceExtensions::from(&extensions).ext_descriptor_indexing
+        {
+            return Err(DeviceCreationError::FeatureNotPresent);
+        }
+
         let vk_i = phys.instance().pointers();
 
         // this variable will contain the queue family ID and queue ID of each requested queue
@@ -194,7 +209,6 @@ impl Device {
             .map(|layer| layer.as_ptr())
             .collect::<SmallVec<[_; 16]>>();
 
-        let extensions = extensions.into();
         let extensions_list = extensions
             .iter()
             .map(|extension| extension.as_ptr())
@@ -261,9 +275,15 @@ impl Device {
                 features
             };
 
+            let descriptor_indexing = requested_features.descriptor_indexing();
+
             let infos = vk::DeviceCreateInfo {
                 sType: vk::STRUCTURE_TYPE_DEVICE_CREATE_INFO,
-                pNext: ptr::null(),
+                pNext: if requested_features.has_descriptor_indexing() {
+                    &descriptor_indexing as *const _ as *const _
+                } else {
+                    ptr::null()
+                },
                 flags: 0, // reserved
                 queueCreateInfoCount: queues.len() as u32,
                 pQueueCreateInfos: queues.as_ptr(),
@@ -277,7 +297,7 @@ impl Device {
             let mut output = mem::uninitialized();
             check_errors(vk_i.CreateDevice(phys.internal_object(),
                                            &infos,
-                                           ptr::null(),
+                                           phys.instance().allocation_callbacks().as_ptr(),
                                            &mut output))?;
             output
         };
@@ -296,6 +316,8 @@ impl Device {
                                   standard_pool: Mutex::new(Weak::new()),
                                   standard_descriptor_pool: Mutex::new(Weak::new()),
                                   standard_command_pools: Mutex::new(Default::default()),
+                                  samplers: Mutex::new(Default::default()),
+                                  downsample_pipeline: Mutex::new(Weak::new()),
                                   features: requested_features.clone(),
                                   extensions: (&extensions).into(),
                               });
@@ -316,6 +338,15 @@ impl Device {
         &self.vk
     }
 
+    /// Returns the host allocation callbacks to pass to the Vulkan functions that create or
+    /// destroy objects that belong to this device.
+    ///
+    /// These are the callbacks of the allocator that was passed when creating the instance.
+    #[inline]
+    pub(crate) fn allocation_callbacks(&self) -> AllocationCallbacks {
+        self.instance.allocation_callbacks()
+    }
+
     /// Waits until all work on this device has finished. You should never need to call
     /// this function, but it can be useful for debugging or benchmarking purposes.
     ///
@@ -411,6 +442,19 @@ impl Device {
             },
         }
     }
+
+    /// Returns the samplers created with `SamplerBuilder::build_cached`.
+    #[inline]
+    pub(crate) fn sampler_cache(&self)
+        -> &Mutex<HashMap<SamplerKey, Weak<Sampler>, BuildHasherDefault<FnvHasher>>> {
+        &self.samplers
+    }
+
+    /// Returns the pipeline used to generate mipmaps with a compute shader.
+    #[inline]
+    pub(crate) fn downsample_pipeline_cache(&self) -> &Mutex<Weak<DownsamplePipeline>> {
+        &self.downsample_pipeline
+    }
 }
 
 impl fmt::Debug for Device {
@@ -434,7 +478,8 @@ impl Drop for Device {
     fn drop(&mut self) {
         unsafe {
             self.vk.DeviceWaitIdle(self.device);
-            self.vk.DestroyDevice(self.device, ptr::null());
+            self.vk
+                .DestroyDevice(self.device, self.instance.allocation_callbacks().as_ptr());
         }
     }
 }
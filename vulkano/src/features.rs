@@ -7,16 +7,29 @@
 // notice may not be copied, modified, or distributed except
 // according to those terms.
 
+use std::ptr;
+
 use vk;
 
 macro_rules! features {
-    ($($name:ident => $vk:ident,)+) => (
+    (
+        core: { $($name:ident => $vk:ident,)+ },
+        descriptor_indexing: { $($di_name:ident => $di_vk:ident,)+ },
+    ) => (
         /// Represents all the features that are available on a physical device or enabled on
         /// a logical device.
         ///
         /// Note that the `robust_buffer_access` is guaranteed to be supported by all Vulkan
         /// implementations.
         ///
+        /// The features whose names start with `descriptor_binding_`, the
+        /// `runtime_descriptor_array` feature, the dynamic indexing features of input attachments
+        /// and texel buffers, and the non-uniform indexing features belong to the
+        /// `VK_EXT_descriptor_indexing` extension.
+        /// They can only be enabled if this extension is enabled as well. They are only reported
+        /// as supported if the `VK_KHR_get_physical_device_properties2` extension is enabled on
+        /// the instance.
+        ///
         /// # Example
         ///
         /// ```
@@ -46,6 +59,9 @@ macro_rules! features {
             $(
                 pub $name: bool,
             )+
+            $(
+                pub $di_name: bool,
+            )+
         }
 
         impl Features {
@@ -55,6 +71,9 @@ macro_rules! features {
                     $(
                         $name: false,
                     )+
+                    $(
+                        $di_name: false,
+                    )+
                 }
             }
 
@@ -67,6 +86,9 @@ macro_rules! features {
                     $(
                         $name: true,
                     )+
+                    $(
+                        $di_name: true,
+                    )+
                 }
             }
 
@@ -75,7 +97,8 @@ macro_rules! features {
             /// That is, for each feature of the parameter that is true, the corresponding value
             /// in self is true as well.
             pub fn superset_of(&self, other: &Features) -> bool {
-                $((self.$name == true || other.$name == false))&&+
+                $((self.$name == true || other.$name == false))&&+ &&
+                $((self.$di_name == true || other.$di_name == false))&&+
             }
 
             /// Builds a `Features` that is the intersection of `self` and another `Features`
@@ -87,6 +110,9 @@ macro_rules! features {
                     $(
                         $name: self.$name && other.$name,
                     )+
+                    $(
+                        $di_name: self.$di_name && other.$di_name,
+                    )+
                 }
             }
 
@@ -98,6 +124,9 @@ macro_rules! features {
                     $(
                         $name: self.$name && !other.$name,
                     )+
+                    $(
+                        $di_name: self.$di_name && !other.$di_name,
+                    )+
                 }
             }
         }
@@ -109,6 +138,9 @@ macro_rules! features {
                     $(
                         $name: features.$vk != 0,
                     )+
+                    $(
+                        $di_name: false,
+                    )+
                 }
             }
         }
@@ -123,63 +155,134 @@ macro_rules! features {
                 }
             }
         }
+
+        impl Features {
+            // Returns true if one of the features of `VK_EXT_descriptor_indexing` is true.
+            #[inline]
+            pub(crate) fn has_descriptor_indexing(&self) -> bool {
+                $(self.$di_name)||+
+            }
+
+            // Sets the features of `VK_EXT_descriptor_indexing` to the values returned by Vulkan.
+            #[inline]
+            pub(crate) fn set_descriptor_indexing(
+                &mut self, features: &vk::PhysicalDeviceDescriptorIndexingFeaturesEXT)
+            {
+                $(
+                    self.$di_name = features.$di_vk != 0;
+                )+
+            }
+
+            // Builds the Vulkan struct that enables the features of `VK_EXT_descriptor_indexing`.
+            #[inline]
+            pub(crate) fn descriptor_indexing(&self)
+                -> vk::PhysicalDeviceDescriptorIndexingFeaturesEXT
+            {
+                vk::PhysicalDeviceDescriptorIndexingFeaturesEXT {
+                    sType: vk::STRUCTURE_TYPE_PHYSICAL_DEVICE_DESCRIPTOR_INDEXING_FEATURES_EXT,
+                    pNext: ptr::null(),
+                    $(
+                        $di_vk: if self.$di_name { vk::TRUE } else { vk::FALSE },
+                    )+
+                }
+            }
+        }
     )
 }
 
 features!{
-    robust_buffer_access => robustBufferAccess,
-    full_draw_index_uint32 => fullDrawIndexUint32,
-    image_cube_array => imageCubeArray,
-    independent_blend => independentBlend,
-    geometry_shader => geometryShader,
-    tessellation_shader => tessellationShader,
-    sample_rate_shading => sampleRateShading,
-    dual_src_blend => dualSrcBlend,
-    logic_op => logicOp,
-    multi_draw_indirect => multiDrawIndirect,
-    draw_indirect_first_instance => drawIndirectFirstInstance,
-    depth_clamp => depthClamp,
-    depth_bias_clamp => depthBiasClamp,
-    fill_mode_non_solid => fillModeNonSolid,
-    depth_bounds => depthBounds,
-    wide_lines => wideLines,
-    large_points => largePoints,
-    alpha_to_one => alphaToOne,
-    multi_viewport => multiViewport,
-    sampler_anisotropy => samplerAnisotropy,
-    texture_compression_etc2 => textureCompressionETC2,
-    texture_compression_astc_ldr => textureCompressionASTC_LDR,
-    texture_compression_bc => textureCompressionBC,
-    occlusion_query_precise => occlusionQueryPrecise,
-    pipeline_statistics_query => pipelineStatisticsQuery,
-    vertex_pipeline_stores_and_atomics => vertexPipelineStoresAndAtomics,
-    fragment_stores_and_atomics => fragmentStoresAndAtomics,
-    shader_tessellation_and_geometry_point_size => shaderTessellationAndGeometryPointSize,
-    shader_image_gather_extended => shaderImageGatherExtended,
-    shader_storage_image_extended_formats => shaderStorageImageExtendedFormats,
-    shader_storage_image_multisample => shaderStorageImageMultisample,
-    shader_storage_image_read_without_format => shaderStorageImageReadWithoutFormat,
-    shader_storage_image_write_without_format => shaderStorageImageWriteWithoutFormat,
-    shader_uniform_buffer_array_dynamic_indexing => shaderUniformBufferArrayDynamicIndexing,
-    shader_sampled_image_array_dynamic_indexing => shaderSampledImageArrayDynamicIndexing,
-    shader_storage_buffer_array_dynamic_indexing => shaderStorageBufferArrayDynamicIndexing,
-    shader_storage_image_array_dynamic_indexing => shaderStorageImageArrayDynamicIndexing,
-    shader_clip_distance => shaderClipDistance,
-    shader_cull_distance => shaderCullDistance,
-    shader_f3264 => shaderf3264,
-    shader_int64 => shaderInt64,
-    shader_int16 => shaderInt16,
-    shader_resource_residency => shaderResourceResidency,
-    shader_resource_min_lod => shaderResourceMinLod,
-    sparse_binding => sparseBinding,
-    sparse_residency_buffer => sparseResidencyBuffer,
-    sparse_residency_image2d => sparseResidencyImage2D,
-    sparse_residency_image3d => sparseResidencyImage3D,
-    sparse_residency2_samples => sparseResidency2Samples,
-    sparse_residency4_samples => sparseResidency4Samples,
-    sparse_residency8_samples => sparseResidency8Samples,
-    sparse_residency16_samples => sparseResidency16Samples,
-    sparse_residency_aliased => sparseResidencyAliased,
-    variable_multisample_rate => variableMultisampleRate,
-    inherited_queries => inheritedQueries,
+    core: {
+        robust_buffer_access => robustBufferAccess,
+        full_draw_index_uint32 => fullDrawIndexUint32,
+        image_cube_array => imageCubeArray,
+        independent_blend => independentBlend,
+        geometry_shader => geometryShader,
+        tessellation_shader => tessellationShader,
+        sample_rate_shading => sampleRateShading,
+        dual_src_blend => dualSrcBlend,
+        logic_op => logicOp,
+        multi_draw_indirect => multiDrawIndirect,
+        draw_indirect_first_instance => drawIndirectFirstInstance,
+        depth_clamp => depthClamp,
+        depth_bias_clamp => depthBiasClamp,
+        fill_mode_non_solid => fillModeNonSolid,
+        depth_bounds => depthBounds,
+        wide_lines => wideLines,
+        large_points => largePoints,
+        alpha_to_one => alphaToOne,
+        multi_viewport => multiViewport,
+        sampler_anisotropy => samplerAnisotropy,
+        texture_compression_etc2 => textureCompressionETC2,
+        texture_compression_astc_ldr => textureCompressionASTC_LDR,
+        texture_compression_bc => textureCompressionBC,
+        occlusion_query_precise => occlusionQueryPrecise,
+        pipeline_statistics_query => pipelineStatisticsQuery,
+        vertex_pipeline_stores_and_atomics => vertexPipelineStoresAndAtomics,
+        fragment_stores_and_atomics => fragmentStoresAndAtomics,
+        shader_tessellation_and_geometry_point_size => shaderTessellationAndGeometryPointSize,
+        shader_image_gather_extended => shaderImageGatherExtended,
+        shader_storage_image_extended_formats => shaderStorageImageExtendedFormats,
+        shader_storage_image_multisample => shaderStorageImageMultisample,
+        shader_storage_image_read_without_format => shaderStorageImageReadWithoutFormat,
+        shader_storage_image_write_without_format => shaderStorageImageWriteWithoutFormat,
+        shader_uniform_buffer_array_dynamic_indexing => shaderUniformBufferArrayDynamicIndexing,
+        shader_sampled_image_array_dynamic_indexing => shaderSampledImageArrayDynamicIndexing,
+        shader_storage_buffer_array_dynamic_indexing => shaderStorageBufferArrayDynamicIndexing,
+        shader_storage_image_array_dynamic_indexing => shaderStorageImageArrayDynamicIndexing,
+        shader_clip_distance => shaderClipDistance,
+        shader_cull_distance => shaderCullDistance,
+        shader_f3264 => shaderf3264,
+        shader_int64 => shaderInt64,
+        shader_int16 => shaderInt16,
+        shader_resource_residency => shaderResourceResidency,
+        shader_resource_min_lod => shaderResourceMinLod,
+        sparse_binding => sparseBinding,
+        sparse_residency_buffer => sparseResidencyBuffer,
+        sparse_residency_image2d => sparseResidencyImage2D,
+        sparse_residency_image3d => sparseResidencyImage3D,
+        sparse_residency2_samples => sparseResidency2Samples,
+        sparse_residency4_samples => sparseResidency4Samples,
+        sparse_residency8_samples => sparseResidency8Samples,
+        sparse_residency16_samples => sparseResidency16Samples,
+        sparse_residency_aliased => sparseResidencyAliased,
+        variable_multisample_rate => variableMultisampleRate,
+        inherited_queries => inheritedQueries,
+    },
+    descriptor_indexing: {
+        shader_input_attachment_array_dynamic_indexing => shaderInputAttachmentArrayDynamicIndexing,
+        shader_uniform_texel_buffer_array_dynamic_indexing =>
+            shaderUniformTexelBufferArrayDynamicIndexing,
+        shader_storage_texel_buffer_array_dynamic_indexing =>
+            shaderStorageTexelBufferArrayDynamicIndexing,
+        shader_uniform_buffer_array_non_uniform_indexing =>
+            shaderUniformBufferArrayNonUniformIndexing,
+        shader_sampled_image_array_non_uniform_indexing =>
+            shaderSampledImageArrayNonUniformIndexing,
+        shader_storage_buffer_array_non_uniform_indexing =>
+            shaderStorageBufferArrayNonUniformIndexing,
+        shader_storage_image_array_non_uniform_indexing =>
+            shaderStorageImageArrayNonUniformIndexing,
+        shader_input_attachment_array_non_uniform_indexing =>
+            shaderInputAttachmentArrayNonUniformIndexing,
+        shader_uniform_texel_buffer_array_non_uniform_indexing =>
+            shaderUniformTexelBufferArrayNonUniformIndexing,
+        shader_storage_texel_buffer_array_non_uniform_indexing =>
+            shaderStorageTexelBufferArrayNonUniformIndexing,
+        descriptor_binding_uniform_buffer_update_after_bind =>
+            descriptorBindingUniformBufferUpdateAfterBind,
+        descriptor_binding_sampled_image_update_after_bind =>
+            descriptorBindingSampledImageUpdateAfterBind,
+        descriptor_binding_storage_image_update_after_bind =>
+            descriptorBindingStorageImageUpdateAfterBind,
+        descriptor_binding_storage_buffer_update_after_bind =>
+            descriptorBindingStorageBufferUpdateAfterBind,
+        descriptor_binding_uniform_texel_buffer_update_after_bind =>
+            descriptorBindingUniformTexelBufferUpdateAfterBind,
+        descriptor_binding_storage_texel_buffer_update_after_bind =>
+            descriptorBindingStorageTexelBufferUpdateAfterBind,
+        descriptor_binding_update_unused_while_pending => descriptorBindingUpdateUnusedWhilePending,
+        descriptor_binding_partially_bound => descriptorBindingPartiallyBound,
+        descriptor_binding_variable_descriptor_count => descriptorBindingVariableDescriptorCount,
+        runtime_descriptor_array => runtimeDescriptorArray,
+    },
 }
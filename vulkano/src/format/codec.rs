@@ -0,0 +1,636 @@
+// Copyright (c) 2017 The vulkano developers
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>,
+// at your option. All files in the project carrying such
+// notice may not be copied, modified, or distributed except
+// according to those terms.
+
+//! Conversion between texels and their representation in memory.
+//!
+//! The `encode` and `decode` functions convert a texel expressed as `[f32; 4]` to and from the
+//! bytes of a texel of an uncompressed format. This is useful to generate the content of an image
+//! on the CPU, or to inspect the content of an image that has been downloaded.
+//!
+//! The components of a texel are in the order red, green, blue and alpha for color formats, and
+//! depth and stencil for depth and stencil formats, like `Format::components()`. Components that
+//! are absent from the format are ignored when encoding. They are decoded as `0.0`, except for
+//! the alpha component of color formats which is decoded as `1.0`.
+//!
+//! The conversions follow the rules of the Vulkan specifications:
+//!
+//! - `Unorm` and `Snorm` values are clamped to their range and rounded to the nearest integer.
+//! - `Srgb` values are linear, and are converted from and to the sRGB color space. The alpha
+//!   component is handled like `Unorm`.
+//! - `Uscaled`, `Sscaled`, `Uint` and `Sint` values, and the stencil component, are integers
+//!   stored as floating points. They are rounded and clamped to the range of the component. Note
+//!   that a `f32` can't represent all 32 bits and 64 bits integers exactly.
+//! - `Sfloat` and `Ufloat` values are rounded to the nearest representable value. Negative values
+//!   are clamped to `0.0` for `Ufloat` formats.
+//!
+//! Compressed formats and formats that have both a depth and a stencil component aren't
+//! supported, as the latter don't have a defined representation in memory.
+//!
+//! # Example
+//!
+//! ```
+//! use vulkano::format::Format;
+//! use vulkano::format::codec;
+//!
+//! let mut texel = [0; 4];
+//! codec::encode(Format::B8G8R8A8Unorm, [1.0, 0.0, 0.0, 1.0], &mut texel).unwrap();
+//! assert_eq!(texel, [0, 0, 255, 255]);
+//!
+//! let decoded = codec::decode(Format::B8G8R8A8Unorm, &texel).unwrap();
+//! assert_eq!(decoded, [1.0, 0.0, 0.0, 1.0]);
+//! ```
+
+use std::cmp;
+use std::error;
+use std::f64::INFINITY;
+use std::f64::NAN;
+use std::fmt;
+
+use format::Format;
+use format::FormatTy;
+use format::NumericType;
+
+/// Writes the texel `texel` in the representation of `format` at the start of `data`.
+///
+/// # Panic
+///
+/// - Panics if `data` is smaller than the size of a texel of `format`.
+///
+pub fn encode(format: Format, texel: [f32; 4], data: &mut [u8]) -> Result<(), CodecError> {
+    let size = texel_size(format)?;
+    assert!(data.len() >= size);
+
+    let layout = format.bit_layout();
+    let shared_exponent = if layout.iter().any(|&(c, _)| c == Component::E) {
+        Some(encode_shared_exponent([texel[0], texel[1], texel[2]]))
+    } else {
+        None
+    };
+
+    let mut offset = 0;
+    for &(component, bits) in layout {
+        let raw = match (component.index(), shared_exponent) {
+            (Some(index), Some((mantissas, _))) => mantissas[index],
+            (Some(index), None) => {
+                encode_component(component_type(format, component), bits, texel[index])
+            },
+            (None, Some((_, exponent))) if component == Component::E => exponent,
+            (None, _) => 0,
+        };
+
+        write_bits(data, offset, bits, raw);
+        offset += bits as usize;
+    }
+
+    Ok(())
+}
+
+/// Reads a texel in the representation of `format` from the start of `data`.
+///
+/// # Panic
+///
+/// - Panics if `data` is smaller than the size of a texel of `format`.
+///
+pub fn decode(format: Format, data: &[u8]) -> Result<[f32; 4], CodecError> {
+    let size = texel_size(format)?;
+    assert!(data.len() >= size);
+
+    let layout = format.bit_layout();
+    let mut texel = if format.aspects().color {
+        [0.0, 0.0, 0.0, 1.0]
+    } else {
+        [0.0; 4]
+    };
+
+    let mut exponent = None;
+    let mut offset = 0;
+    for &(component, bits) in layout {
+        if component == Component::E {
+            exponent = Some(read_bits(data, offset, bits));
+        }
+        offset += bits as usize;
+    }
+
+    let mut offset = 0;
+    for &(component, bits) in layout {
+        let raw = read_bits(data, offset, bits);
+        offset += bits as usize;
+
+        let index = match component.index() {
+            Some(i) => i,
+            None => continue,
+        };
+
+        texel[index] = match exponent {
+            Some(exponent) => decode_shared_exponent(raw, exponent),
+            None => decode_component(component_type(format, component), bits, raw),
+        };
+    }
+
+    Ok(texel)
+}
+
+/// Error that can happen when encoding or decoding a texel.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum CodecError {
+    /// The format is compressed, or has both a depth and a stencil component.
+    UnsupportedFormat(Format),
+}
+
+impl error::Error for CodecError {
+    #[inline]
+    fn description(&self) -> &str {
+        match *self {
+            CodecError::UnsupportedFormat(_) => {
+                "the format doesn't have a representation in memory that can be converted"
+            },
+        }
+    }
+}
+
+impl fmt::Display for CodecError {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(fmt, "{}", error::Error::description(self))
+    }
+}
+
+/// A component of a texel, as written in the table of formats.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum Component {
+    R,
+    G,
+    B,
+    A,
+    D,
+    S,
+    /// Exponent shared between the other components.
+    E,
+    /// Unused bits.
+    X,
+}
+
+impl Component {
+    // Returns the index of the component in a texel.
+    #[inline]
+    fn index(&self) -> Option<usize> {
+        match *self {
+            Component::R | Component::D => Some(0),
+            Component::G | Component::S => Some(1),
+            Component::B => Some(2),
+            Component::A => Some(3),
+            Component::E | Component::X => None,
+        }
+    }
+}
+
+// Returns the size of a texel of `format`, or an error if the format isn't supported.
+fn texel_size(format: Format) -> Result<usize, CodecError> {
+    match (format.ty(), format.size()) {
+        (FormatTy::Compressed, _) | (_, None) => Err(CodecError::UnsupportedFormat(format)),
+        (_, Some(size)) => Ok(size),
+    }
+}
+
+// Returns the numeric type of a component of `format`.
+fn component_type(format: Format, component: Component) -> NumericType {
+    match (component, format.numeric_type()) {
+        (Component::S, _) => NumericType::Uint,
+        (Component::A, NumericType::Srgb) => NumericType::Unorm,
+        (_, ty) => ty,
+    }
+}
+
+fn encode_component(ty: NumericType, bits: u8, value: f32) -> u64 {
+    match ty {
+        NumericType::Unorm => encode_unorm(value, bits),
+        NumericType::Snorm => encode_snorm(value, bits),
+        NumericType::Uscaled | NumericType::Uint => encode_uint(value, bits),
+        NumericType::Sscaled | NumericType::Sint => encode_sint(value, bits),
+        NumericType::Srgb => encode_unorm(linear_to_srgb(value), bits),
+        NumericType::Ufloat => encode_small_float(value, bits - 5, false),
+        NumericType::Sfloat => {
+            match bits {
+                16 => encode_small_float(value, 10, true),
+                32 => value.to_bits() as u64,
+                64 => (value as f64).to_bits(),
+                _ => unreachable!(),
+            }
+        },
+    }
+}
+
+fn decode_component(ty: NumericType, bits: u8, raw: u64) -> f32 {
+    match ty {
+        NumericType::Unorm => raw as f32 / max_unsigned(bits) as f32,
+        NumericType::Snorm => {
+            let max = max_unsigned(bits - 1) as f32;
+            (sign_extend(raw, bits) as f32 / max).max(-1.0)
+        },
+        NumericType::Uscaled | NumericType::Uint => raw as f32,
+        NumericType::Sscaled | NumericType::Sint => sign_extend(raw, bits) as f32,
+        NumericType::Srgb => srgb_to_linear(raw as f32 / max_unsigned(bits) as f32),
+        NumericType::Ufloat => decode_small_float(raw, bits - 5, false),
+        NumericType::Sfloat => {
+            match bits {
+                16 => decode_small_float(raw, 10, true),
+                32 => f32::from_bits(raw as u32),
+                64 => f64::from_bits(raw) as f32,
+                _ => unreachable!(),
+            }
+        },
+    }
+}
+
+fn encode_unorm(value: f32, bits: u8) -> u64 {
+    let value = if value >= 0.0 { value.min(1.0) } else { 0.0 };
+    (value as f64 * max_unsigned(bits) as f64).round() as u64
+}
+
+fn encode_snorm(value: f32, bits: u8) -> u64 {
+    let value = if value.is_nan() { 0.0 } else { value.max(-1.0).min(1.0) };
+    let value = (value as f64 * max_unsigned(bits - 1) as f64).round() as i64;
+    value as u64 & max_unsigned(bits)
+}
+
+fn encode_uint(value: f32, bits: u8) -> u64 {
+    let max = max_unsigned(bits);
+    if !(value > 0.0) {
+        0
+    } else if value as f64 >= max as f64 {
+        max
+    } else {
+        value.round() as u64
+    }
+}
+
+fn encode_sint(value: f32, bits: u8) -> u64 {
+    let max = max_unsigned(bits - 1) as i64;
+    let value = if value.is_nan() {
+        0
+    } else if value as f64 >= max as f64 {
+        max
+    } else if value as f64 <= (-max - 1) as f64 {
+        -max - 1
+    } else {
+        value.round() as i64
+    };
+
+    value as u64 & max_unsigned(bits)
+}
+
+// Encodes a floating point with a 5 bits exponent and `mantissa_bits` bits of mantissa, and
+// optionally a sign bit.
+fn encode_small_float(value: f32, mantissa_bits: u8, signed: bool) -> u64 {
+    let infinity = 0x1f << mantissa_bits;
+    let sign = if signed && value.is_sign_negative() {
+        1 << (mantissa_bits + 5)
+    } else {
+        0
+    };
+
+    if value.is_nan() {
+        return sign | infinity | (1 << (mantissa_bits - 1));
+    }
+    if !signed && value < 0.0 {
+        return 0;
+    }
+
+    let value = (value as f64).abs();
+    let scale = (1u64 << mantissa_bits) as f64;
+    let magnitude = if value.is_infinite() {
+        infinity
+    } else if value < 2.0f64.powi(-14) {
+        // Denormalized values.
+        round_half_even(value * 2.0f64.powi(14) * scale) as u64
+    } else {
+        let exponent = floor_log2(value);
+        if exponent > 15 {
+            infinity
+        } else {
+            let mantissa = round_half_even((value / 2.0f64.powi(exponent) - 1.0) * scale);
+            // A mantissa that is rounded up to `scale` carries into the exponent.
+            (((exponent + 15) as u64) << mantissa_bits) + mantissa as u64
+        }
+    };
+
+    sign | cmp::min(magnitude, infinity)
+}
+
+fn decode_small_float(raw: u64, mantissa_bits: u8, signed: bool) -> f32 {
+    let scale = 1u64 << mantissa_bits;
+    let exponent = ((raw >> mantissa_bits) & 0x1f) as i32;
+    let mantissa = (raw & (scale - 1)) as f64 / scale as f64;
+
+    let magnitude = match exponent {
+        0 => mantissa * 2.0f64.powi(-14),
+        0x1f if mantissa == 0.0 => INFINITY,
+        0x1f => NAN,
+        e => (1.0 + mantissa) * 2.0f64.powi(e - 15),
+    };
+
+    if signed && (raw >> (mantissa_bits + 5)) & 1 != 0 {
+        -magnitude as f32
+    } else {
+        magnitude as f32
+    }
+}
+
+// Encodes the red, green and blue components of a `E5B9G9R9UfloatPack32` texel. Returns the three
+// 9 bits mantissas and the shared exponent.
+fn encode_shared_exponent(rgb: [f32; 3]) -> ([u64; 3], u64) {
+    const MANTISSA_BITS: i32 = 9;
+    const BIAS: i32 = 15;
+
+    let max = (511.0 / 512.0) * 2.0f64.powi(16);
+    let clamp = |v: f32| if v > 0.0 { (v as f64).min(max) } else { 0.0 };
+    let rgb = [clamp(rgb[0]), clamp(rgb[1]), clamp(rgb[2])];
+    let max_value = rgb[0].max(rgb[1]).max(rgb[2]);
+
+    let exponent = if max_value == 0.0 {
+        0
+    } else {
+        cmp::max(-BIAS - 1, floor_log2(max_value)) + 1 + BIAS
+    };
+    let max_mantissa = (max_value / 2.0f64.powi(exponent - BIAS - MANTISSA_BITS) + 0.5).floor();
+    let exponent = if max_mantissa == 512.0 {
+        exponent + 1
+    } else {
+        exponent
+    };
+
+    let scale = 2.0f64.powi(exponent - BIAS - MANTISSA_BITS);
+    let mantissa = |v: f64| (v / scale + 0.5).floor() as u64;
+    ([mantissa(rgb[0]), mantissa(rgb[1]), mantissa(rgb[2])], exponent as u64)
+}
+
+#[inline]
+fn decode_shared_exponent(mantissa: u64, exponent: u64) -> f32 {
+    (mantissa as f64 * 2.0f64.powi(exponent as i32 - 15 - 9)) as f32
+}
+
+fn linear_to_srgb(value: f32) -> f32 {
+    if !(value > 0.0031308) {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+fn srgb_to_linear(value: f32) -> f32 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+// Returns the exponent of the highest power of two that is lower than or equal to `value`, which
+// must be finite and strictly positive.
+fn floor_log2(value: f64) -> i32 {
+    let mut exponent = value.log2().floor() as i32;
+    // `log2` isn't exact, so we adjust the result.
+    if 2.0f64.powi(exponent) > value {
+        exponent -= 1;
+    } else if 2.0f64.powi(exponent + 1) <= value {
+        exponent += 1;
+    }
+    exponent
+}
+
+fn round_half_even(value: f64) -> f64 {
+    let rounded = value.round();
+    if (rounded - value).abs() == 0.5 {
+        2.0 * (value / 2.0).round()
+    } else {
+        rounded
+    }
+}
+
+#[inline]
+fn max_unsigned(bits: u8) -> u64 {
+    if bits >= 64 { !0 } else { (1 << bits) - 1 }
+}
+
+#[inline]
+fn sign_extend(raw: u64, bits: u8) -> i64 {
+    ((raw << (64 - bits)) as i64) >> (64 - bits)
+}
+
+// Reads `bits` bits starting at bit `offset` of `data`, in little endian.
+fn read_bits(data: &[u8], offset: usize, bits: u8) -> u64 {
+    let mut value = 0;
+    for n in 0 .. bits as usize {
+        let bit = offset + n;
+        if data[bit / 8] & (1 << (bit % 8)) != 0 {
+            value |= 1 << n;
+        }
+    }
+    value
+}
+
+// Writes the `bits` lowest bits of `value` starting at bit `offset` of `data`, in little endian.
+fn write_bits(data: &mut [u8], offset: usize, bits: u8, value: u64) {
+    for n in 0 .. bits as usize {
+        let bit = offset + n;
+        if value & (1 << n) != 0 {
+            data[bit / 8] |= 1 << (bit % 8);
+        } else {
+            data[bit / 8] &= !(1 << (bit % 8));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use format::Format;
+    use format::codec::CodecError;
+    use format::codec::decode;
+    use format::codec::encode;
+
+    fn encode_u32(format: Format, texel: [f32; 4]) -> u32 {
+        let mut data = [0; 4];
+        encode(format, texel, &mut data).unwrap();
+        data[0] as u32 | (data[1] as u32) << 8 | (data[2] as u32) << 16 | (data[3] as u32) << 24
+    }
+
+    fn decode_u32(format: Format, raw: u32) -> [f32; 4] {
+        let data = [raw as u8, (raw >> 8) as u8, (raw >> 16) as u8, (raw >> 24) as u8];
+        decode(format, &data).unwrap()
+    }
+
+    fn assert_approx(a: [f32; 4], b: [f32; 4]) {
+        for n in 0 .. 4 {
+            assert!((a[n] - b[n]).abs() < 0.005, "{:?} != {:?}", a, b);
+        }
+    }
+
+    #[test]
+    fn unorm() {
+        let mut data = [0; 4];
+        encode(Format::R8G8B8A8Unorm, [1.0, 0.0, 0.5, 2.0], &mut data).unwrap();
+        assert_eq!(data, [255, 0, 128, 255]);
+        assert_eq!(decode(Format::R8G8B8A8Unorm, &[255, 0, 51, 255]).unwrap(),
+                   [1.0, 0.0, 0.2, 1.0]);
+
+        let mut data = [0; 4];
+        encode(Format::B8G8R8A8Unorm, [1.0, 0.0, 0.0, 1.0], &mut data).unwrap();
+        assert_eq!(data, [0, 0, 255, 255]);
+
+        let mut data = [0; 2];
+        encode(Format::R5G6B5UnormPack16, [0.0, 1.0, 0.0, 0.0], &mut data).unwrap();
+        assert_eq!(data, [0xe0, 0x07]);
+    }
+
+    #[test]
+    fn missing_components() {
+        assert_eq!(decode(Format::R8Unorm, &[255]).unwrap(), [1.0, 0.0, 0.0, 1.0]);
+        assert_eq!(decode(Format::R8G8Uint, &[3, 4]).unwrap(), [3.0, 4.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn packed_a2b10g10r10() {
+        assert_eq!(encode_u32(Format::A2B10G10R10UnormPack32, [1.0, 0.0, 0.0, 1.0]),
+                   0x3ff | 3 << 30);
+        assert_eq!(encode_u32(Format::A2B10G10R10UnormPack32, [0.0, 0.0, 1.0, 0.0]),
+                   0x3ff << 20);
+        assert_eq!(decode_u32(Format::A2B10G10R10UnormPack32, 0x3ff << 10),
+                   [0.0, 1.0, 0.0, 0.0]);
+        assert_eq!(decode_u32(Format::A2B10G10R10SnormPack32, 0x1ff | 1 << 30),
+                   [1.0, 0.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn snorm() {
+        let mut data = [0; 2];
+        encode(Format::R8G8Snorm, [-1.0, 1.0, 0.0, 0.0], &mut data).unwrap();
+        assert_eq!(data, [0x81, 0x7f]);
+        assert_eq!(decode(Format::R8G8Snorm, &[0x80, 0x81]).unwrap(),
+                   [-1.0, -1.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn integers() {
+        let mut data = [0; 2];
+        encode(Format::R8G8Uint, [300.0, -5.0, 0.0, 0.0], &mut data).unwrap();
+        assert_eq!(data, [255, 0]);
+        encode(Format::R8G8Sint, [-200.0, 7.0, 0.0, 0.0], &mut data).unwrap();
+        assert_eq!(data, [0x80, 7]);
+        encode(Format::R8G8Uscaled, [12.4, 12.6, 0.0, 0.0], &mut data).unwrap();
+        assert_eq!(data, [12, 13]);
+
+        assert_eq!(decode(Format::R16Sint, &[0xff, 0xff]).unwrap(), [-1.0, 0.0, 0.0, 1.0]);
+        assert_eq!(decode_u32(Format::R32Uint, 4000000000), [4000000000.0, 0.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn srgb() {
+        let mut data = [0; 4];
+        encode(Format::R8G8B8A8Srgb, [0.5, 1.0, 0.0, 0.5], &mut data).unwrap();
+        assert_eq!(data, [188, 255, 0, 128]);
+        assert_approx(decode(Format::R8G8B8A8Srgb, &data).unwrap(), [0.5, 1.0, 0.0, 0.5]);
+    }
+
+    #[test]
+    fn half_float() {
+        let encode_half = |v: f32| {
+            let mut data = [0; 2];
+            encode(Format::R16Sfloat, [v, 0.0, 0.0, 0.0], &mut data).unwrap();
+            data[0] as u16 | (data[1] as u16) << 8
+        };
+
+        assert_eq!(encode_half(1.0), 0x3c00);
+        assert_eq!(encode_half(-2.0), 0xc000);
+        assert_eq!(encode_half(0.333251953125), 0x3555);
+        assert_eq!(encode_half(65504.0), 0x7bff);
+        assert_eq!(encode_half(1.0e6), 0x7c00);
+        assert_eq!(encode_half(5.9604645e-8), 0x0001);
+        assert_eq!(encode_half(-0.0), 0x8000);
+        assert_eq!(encode_half(1.0 + 1.0 / 2048.0), 0x3c00);
+
+        assert_eq!(decode(Format::R16Sfloat, &[0x00, 0x3c]).unwrap()[0], 1.0);
+        assert_eq!(decode(Format::R16Sfloat, &[0xff, 0xfb]).unwrap()[0], -65504.0);
+        assert_eq!(decode(Format::R16Sfloat, &[0x01, 0x00]).unwrap()[0], 5.9604645e-8);
+        assert!(decode(Format::R16Sfloat, &[0x00, 0x7c]).unwrap()[0].is_infinite());
+        assert!(decode(Format::R16Sfloat, &[0x01, 0x7c]).unwrap()[0].is_nan());
+
+        let texel = [0.5, -3.25, 1024.0, 0.125];
+        let mut data = [0; 8];
+        encode(Format::R16G16B16A16Sfloat, texel, &mut data).unwrap();
+        assert_eq!(decode(Format::R16G16B16A16Sfloat, &data).unwrap(), texel);
+    }
+
+    #[test]
+    fn wide_floats() {
+        let texel = [0.1, -7.5, 1.0e20, 3.0];
+        let mut data = [0; 16];
+        encode(Format::R32G32B32A32Sfloat, texel, &mut data).unwrap();
+        assert_eq!(decode(Format::R32G32B32A32Sfloat, &data).unwrap(), texel);
+
+        let mut data = [0; 32];
+        encode(Format::R64G64B64A64Sfloat, texel, &mut data).unwrap();
+        assert_eq!(decode(Format::R64G64B64A64Sfloat, &data).unwrap(), texel);
+    }
+
+    #[test]
+    fn b10g11r11() {
+        let one = 0x3c0 | 0x3c0 << 11 | 0x1e0 << 22;
+        assert_eq!(encode_u32(Format::B10G11R11UfloatPack32, [1.0, 1.0, 1.0, 0.0]), one);
+        assert_eq!(encode_u32(Format::B10G11R11UfloatPack32, [-1.0, 0.0, 0.0, 0.0]), 0);
+        assert_eq!(decode_u32(Format::B10G11R11UfloatPack32, one), [1.0, 1.0, 1.0, 1.0]);
+
+        let texel = [0.25, 3.5, 49152.0, 1.0];
+        let raw = encode_u32(Format::B10G11R11UfloatPack32, texel);
+        assert_eq!(decode_u32(Format::B10G11R11UfloatPack32, raw), texel);
+    }
+
+    #[test]
+    fn e5b9g9r9() {
+        let raw = encode_u32(Format::E5B9G9R9UfloatPack32, [1.0, 0.5, 0.0, 0.0]);
+        assert_eq!(raw, 256 | 128 << 9 | 16 << 27);
+        assert_eq!(decode_u32(Format::E5B9G9R9UfloatPack32, raw), [1.0, 0.5, 0.0, 1.0]);
+        assert_eq!(encode_u32(Format::E5B9G9R9UfloatPack32, [0.0; 4]), 0);
+
+        let texel = [300.0, 2.0, 0.0, 1.0];
+        let raw = encode_u32(Format::E5B9G9R9UfloatPack32, texel);
+        assert_eq!(decode_u32(Format::E5B9G9R9UfloatPack32, raw), texel);
+    }
+
+    #[test]
+    fn depth_stencil() {
+        let mut data = [0; 2];
+        encode(Format::D16Unorm, [1.0, 0.0, 0.0, 0.0], &mut data).unwrap();
+        assert_eq!(data, [0xff, 0xff]);
+
+        assert_eq!(encode_u32(Format::X8_D24UnormPack32, [0.5, 0.0, 0.0, 0.0]), 0x800000);
+        assert_eq!(decode_u32(Format::X8_D24UnormPack32, 0xffffffff), [1.0, 0.0, 0.0, 0.0]);
+
+        assert_eq!(encode_u32(Format::D32Sfloat, [0.25, 0.0, 0.0, 0.0]), 0.25f32.to_bits());
+
+        let mut data = [0];
+        encode(Format::S8Uint, [0.0, 200.0, 0.0, 0.0], &mut data).unwrap();
+        assert_eq!(data, [200]);
+        assert_eq!(decode(Format::S8Uint, &data).unwrap(), [0.0, 200.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn unsupported_formats() {
+        let mut data = [0; 16];
+        assert_eq!(encode(Format::BC1_RGBUnormBlock, [0.0; 4], &mut data),
+                   Err(CodecError::UnsupportedFormat(Format::BC1_RGBUnormBlock)));
+        assert_eq!(decode(Format::D24Unorm_S8Uint, &data),
+                   Err(CodecError::UnsupportedFormat(Format::D24Unorm_S8Uint)));
+    }
+
+    #[test]
+    #[should_panic]
+    fn data_too_small() {
+        let _ = decode(Format::R32G32Sfloat, &[0; 4]);
+    }
+}
@@ -0,0 +1,261 @@
+// Copyright (c) 2017 The vulkano developers
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>,
+// at your option. All files in the project carrying such
+// notice may not be copied, modified, or distributed except
+// according to those terms.
+
+use std::ops::BitOr;
+use vk;
+
+/// Properties of a format, as supported by a physical device.
+///
+/// Can be obtained with `PhysicalDevice::format_properties()`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct FormatProperties {
+    /// Features supported by images of this format that use linear tiling.
+    pub linear_tiling_features: FormatFeatures,
+
+    /// Features supported by images of this format that use optimal tiling.
+    pub optimal_tiling_features: FormatFeatures,
+
+    /// Features supported by buffers when their content is interpreted with this format.
+    pub buffer_features: FormatFeatures,
+}
+
+impl FormatProperties {
+    #[inline]
+    pub(crate) fn from_vulkan(val: &vk::FormatProperties) -> FormatProperties {
+        FormatProperties {
+            linear_tiling_features: FormatFeatures::from_bits(val.linearTilingFeatures),
+            optimal_tiling_features: FormatFeatures::from_bits(val.optimalTilingFeatures),
+            buffer_features: FormatFeatures::from_bits(val.bufferFeatures),
+        }
+    }
+}
+
+/// Describes what can be done with a format.
+///
+/// All the values default to false. If a value is false, then using the format in the
+/// corresponding way will produce an error.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct FormatFeatures {
+    /// Can be used for sampled images.
+    pub sampled_image: bool,
+
+    /// Can be used for storage images.
+    pub storage_image: bool,
+
+    /// Storage images of this format support atomic operations.
+    pub storage_image_atomic: bool,
+
+    /// Can be used for uniform texel buffers.
+    pub uniform_texel_buffer: bool,
+
+    /// Can be used for storage texel buffers.
+    pub storage_texel_buffer: bool,
+
+    /// Storage texel buffers of this format support atomic operations.
+    pub storage_texel_buffer_atomic: bool,
+
+    /// Can be used for vertex attributes.
+    pub vertex_buffer: bool,
+
+    /// Can be used as a color attachment and as an input attachment.
+    pub color_attachment: bool,
+
+    /// Color attachments of this format support blending.
+    pub color_attachment_blend: bool,
+
+    /// Can be used as a depth-stencil attachment and as an input attachment.
+    pub depth_stencil_attachment: bool,
+
+    /// Can be used as the source of a blit.
+    pub blit_source: bool,
+
+    /// Can be used as the destination of a blit.
+    pub blit_destination: bool,
+
+    /// Sampled images of this format can be sampled with a linear filter.
+    pub sampled_image_filter_linear: bool,
+
+    /// Can be used as the source of a transfer.
+    ///
+    /// Only reported if the `khr_maintenance1` extension is supported. Without this extension, all
+    /// the formats can be used as the source of a transfer.
+    pub transfer_source: bool,
+
+    /// Can be used as the destination of a transfer.
+    ///
+    /// Only reported if the `khr_maintenance1` extension is supported. Without this extension, all
+    /// the formats can be used as the destination of a transfer.
+    pub transfer_destination: bool,
+}
+
+impl FormatFeatures {
+    /// Builds a `FormatFeatures` with all values set to false.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use vulkano::format::FormatFeatures;
+    ///
+    /// let _features = FormatFeatures {
+    ///     depth_stencil_attachment: true,
+    ///     .. FormatFeatures::none()
+    /// };
+    /// ```
+    #[inline]
+    pub fn none() -> FormatFeatures {
+        FormatFeatures {
+            sampled_image: false,
+            storage_image: false,
+            storage_image_atomic: false,
+            uniform_texel_buffer: false,
+            storage_texel_buffer: false,
+            storage_texel_buffer_atomic: false,
+            vertex_buffer: false,
+            color_attachment: false,
+            color_attachment_blend: false,
+            depth_stencil_attachment: false,
+            blit_source: false,
+            blit_destination: false,
+            sampled_image_filter_linear: false,
+            transfer_source: false,
+            transfer_destination: false,
+        }
+    }
+
+    /// Returns true if all the features of `other` are also in `self`.
+    #[inline]
+    pub fn is_superset_of(&self, other: &FormatFeatures) -> bool {
+        let other = other.to_bits();
+        (self.to_bits() & other) == other
+    }
+
+    #[inline]
+    pub(crate) fn to_bits(&self) -> vk::FormatFeatureFlagBits {
+        let mut result = 0;
+        if self.sampled_image {
+            result |= vk::FORMAT_FEATURE_SAMPLED_IMAGE_BIT;
+        }
+        if self.storage_image {
+            result |= vk::FORMAT_FEATURE_STORAGE_IMAGE_BIT;
+        }
+        if self.storage_image_atomic {
+            result |= vk::FORMAT_FEATURE_STORAGE_IMAGE_ATOMIC_BIT;
+        }
+        if self.uniform_texel_buffer {
+            result |= vk::FORMAT_FEATURE_UNIFORM_TEXEL_BUFFER_BIT;
+        }
+        if self.storage_texel_buffer {
+            result |= vk::FORMAT_FEATURE_STORAGE_TEXEL_BUFFER_BIT;
+        }
+        if self.storage_texel_buffer_atomic {
+            result |= vk::FORMAT_FEATURE_STORAGE_TEXEL_BUFFER_ATOMIC_BIT;
+        }
+        if self.vertex_buffer {
+            result |= vk::FORMAT_FEATURE_VERTEX_BUFFER_BIT;
+        }
+        if self.color_attachment {
+            result |= vk::FORMAT_FEATURE_COLOR_ATTACHMENT_BIT;
+        }
+        if self.color_attachment_blend {
+            result |= vk::FORMAT_FEATURE_COLOR_ATTACHMENT_BLEND_BIT;
+        }
+        if self.depth_stencil_attachment {
+            result |= vk::FORMAT_FEATURE_DEPTH_STENCIL_ATTACHMENT_BIT;
+        }
+        if self.blit_source {
+            result |= vk::FORMAT_FEATURE_BLIT_SRC_BIT;
+        }
+        if self.blit_destination {
+            result |= vk::FORMAT_FEATURE_BLIT_DST_BIT;
+        }
+        if self.sampled_image_filter_linear {
+            result |= vk::FORMAT_FEATURE_SAMPLED_IMAGE_FILTER_LINEAR_BIT;
+        }
+        if self.transfer_source {
+            result |= vk::FORMAT_FEATURE_TRANSFER_SRC_BIT_KHR;
+        }
+        if self.transfer_destination {
+            result |= vk::FORMAT_FEATURE_TRANSFER_DST_BIT_KHR;
+        }
+        result
+    }
+
+    #[inline]
+    pub(crate) fn from_bits(val: vk::FormatFeatureFlags) -> FormatFeatures {
+        FormatFeatures {
+            sampled_image: (val & vk::FORMAT_FEATURE_SAMPLED_IMAGE_BIT) != 0,
+            storage_image: (val & vk::FORMAT_FEATURE_STORAGE_IMAGE_BIT) != 0,
+            storage_image_atomic: (val & vk::FORMAT_FEATURE_STORAGE_IMAGE_ATOMIC_BIT) != 0,
+            uniform_texel_buffer: (val & vk::FORMAT_FEATURE_UNIFORM_TEXEL_BUFFER_BIT) != 0,
+            storage_texel_buffer: (val & vk::FORMAT_FEATURE_STORAGE_TEXEL_BUFFER_BIT) != 0,
+            storage_texel_buffer_atomic: (val & vk::FORMAT_FEATURE_STORAGE_TEXEL_BUFFER_ATOMIC_BIT) !=
+                0,
+            vertex_buffer: (val & vk::FORMAT_FEATURE_VERTEX_BUFFER_BIT) != 0,
+            color_attachment: (val & vk::FORMAT_FEATURE_COLOR_ATTACHMENT_BIT) != 0,
+            color_attachment_blend: (val & vk::FORMAT_FEATURE_COLOR_ATTACHMENT_BLEND_BIT) != 0,
+            depth_stencil_attachment: (val & vk::FORMAT_FEATURE_DEPTH_STENCIL_ATTACHMENT_BIT) != 0,
+            blit_source: (val & vk::FORMAT_FEATURE_BLIT_SRC_BIT) != 0,
+            blit_destination: (val & vk::FORMAT_FEATURE_BLIT_DST_BIT) != 0,
+            sampled_image_filter_linear: (val & vk::FORMAT_FEATURE_SAMPLED_IMAGE_FILTER_LINEAR_BIT) !=
+                0,
+            transfer_source: (val & vk::FORMAT_FEATURE_TRANSFER_SRC_BIT_KHR) != 0,
+            transfer_destination: (val & vk::FORMAT_FEATURE_TRANSFER_DST_BIT_KHR) != 0,
+        }
+    }
+}
+
+impl Default for FormatFeatures {
+    #[inline]
+    fn default() -> FormatFeatures {
+        FormatFeatures::none()
+    }
+}
+
+impl BitOr for FormatFeatures {
+    type Output = Self;
+
+    #[inline]
+    fn bitor(self, rhs: Self) -> Self {
+        FormatFeatures::from_bits(self.to_bits() | rhs.to_bits())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use format::FormatFeatures;
+
+    #[test]
+    fn bits_round_trip() {
+        let features = FormatFeatures {
+            sampled_image: true,
+            depth_stencil_attachment: true,
+            transfer_destination: true,
+            ..FormatFeatures::none()
+        };
+
+        assert_eq!(FormatFeatures::from_bits(features.to_bits()), features);
+        assert_eq!(FormatFeatures::from_bits(0), FormatFeatures::none());
+    }
+
+    #[test]
+    fn superset() {
+        let depth = FormatFeatures {
+            depth_stencil_attachment: true,
+            ..FormatFeatures::none()
+        };
+        let sampled = FormatFeatures {
+            sampled_image: true,
+            ..FormatFeatures::none()
+        };
+
+        assert!((depth | sampled).is_superset_of(&depth));
+        assert!(!depth.is_superset_of(&sampled));
+        assert!(depth.is_superset_of(&FormatFeatures::none()));
+    }
+}
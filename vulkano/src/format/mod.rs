@@ -11,11 +11,12 @@
 //!
 //! # Content of this module
 //!
-//! This module contains three things:
+//! This module contains four things:
 //!
 //! - The `Format` enumeration, which contains all the available formats.
 //! - The `FormatDesc` trait.
 //! - One struct for each format.
+//! - The `codec` module, which converts texels to and from their representation in memory.
 //!
 //! # Formats
 //!
@@ -105,6 +106,12 @@
 use std::vec::IntoIter as VecIntoIter;
 use vk;
 
+pub use self::features::FormatFeatures;
+pub use self::features::FormatProperties;
+
+pub mod codec;
+mod features;
+
 // TODO: add enumerations for color, depth, stencil and depthstencil formats
 
 /// Some data whose type must be known by the library.
@@ -132,8 +139,19 @@ unsafe impl Data for u8 {
     }
 }
 
+// Each line of the `formats!` table contains:
+//
+// - The name of the format and the corresponding Vulkan constant.
+// - The size in bytes of a texel, or `None` if it isn't relevant.
+// - The type of the format, and the number of components for color formats.
+// - The type that stores a texel in memory, if any.
+// - Between parentheses, the numeric type of the components, then the components and their
+//   width in bits, starting from the least significant bits of a texel in little endian. For
+//   compressed formats each component has a width of 1, and the extent in texels and the size in
+//   bytes of a block are added at the end.
 macro_rules! formats {
-    ($($name:ident => $vk:ident [$sz:expr] [$($f_ty:tt)*] {$($d_ty:tt)*},)+) => (
+    ($($name:ident => $vk:ident [$sz:expr] [$($f_ty:tt)*] {$($d_ty:tt)*}
+       ($num:ident; $($comp:ident $bits:tt),+ $(; $block:tt $block_size:tt)*),)+) => (
         /// An enumeration of all the possible formats.
         #[derive(Copy, Clone, Debug, PartialEq, Eq)]
         #[repr(u32)]
@@ -162,6 +180,79 @@ macro_rules! formats {
                 }
             }
 
+            /// Returns the width and height in texels of a block of this format. The depth is
+            /// always 1.
+            ///
+            /// Compressed formats store blocks of texels, for example 4x4 texels for the BC
+            /// formats. The other formats have blocks of a single texel.
+            #[inline]
+            pub fn block_extent(&self) -> [u32; 3] {
+                match *self {
+                    $(
+                        Format::$name => formats!(__block_extent__ $($block)*),
+                    )+
+                }
+            }
+
+            /// Returns the size in bytes of a block of this format. This is the same as `size()`
+            /// for uncompressed formats.
+            ///
+            /// Returns `None` for formats that have both a depth and a stencil component, as their
+            /// layout in memory depends on the implementation.
+            #[inline]
+            pub fn block_size(&self) -> Option<usize> {
+                match *self {
+                    $(
+                        Format::$name => formats!(__block_size__ $sz; $($block_size)*),
+                    )+
+                }
+            }
+
+            /// Returns the numeric type of the components of this format.
+            ///
+            /// For formats that have both a depth and a stencil component, this is the type of
+            /// the depth component. The stencil component is always `Uint`.
+            #[inline]
+            pub fn numeric_type(&self) -> NumericType {
+                match *self {
+                    $(
+                        Format::$name => NumericType::$num,
+                    )+
+                }
+            }
+
+            /// Returns the width in bits of each component of this format, in the order
+            /// red, green, blue and alpha for color formats, and depth and stencil for depth and
+            /// stencil formats. A width of 0 means that the component is absent.
+            ///
+            /// Compressed formats don't have a fixed width per component, and return 1 for each
+            /// component that is present. Padding bits and shared exponents aren't included.
+            #[inline]
+            pub fn components(&self) -> [u8; 4] {
+                match *self {
+                    $(
+                        Format::$name => {
+                            let mut components = [0; 4];
+                            $(
+                                formats!(__component__ components $comp $bits);
+                            )+
+                            components
+                        },
+                    )+
+                }
+            }
+
+            /// Returns the components of a texel of this format and their width in bits, starting
+            /// from the least significant bits of the texel in little endian.
+            #[inline]
+            pub(crate) fn bit_layout(&self) -> &'static [(codec::Component, u8)] {
+                match *self {
+                    $(
+                        Format::$name => &[$((codec::Component::$comp, $bits)),+],
+                    )+
+                }
+            }
+
             /// Returns the `Format` corresponding to a Vulkan constant.
             #[doc(hidden)]
             pub fn from_num(val: u32) -> Option<Format> {
@@ -357,6 +448,21 @@ macro_rules! formats {
     (__inner_ty__ $name:ident compressed=$f:tt) => { FormatTy::Compressed };
 
 
+    (__block_extent__) => { [1, 1, 1] };
+    (__block_extent__ [$w:expr, $h:expr]) => { [$w, $h, 1] };
+
+    (__block_size__ $sz:expr;) => { $sz };
+    (__block_size__ $sz:expr; $block_size:expr) => { Some($block_size) };
+
+    (__component__ $c:ident R $bits:expr) => { $c[0] = $bits; };
+    (__component__ $c:ident G $bits:expr) => { $c[1] = $bits; };
+    (__component__ $c:ident B $bits:expr) => { $c[2] = $bits; };
+    (__component__ $c:ident A $bits:expr) => { $c[3] = $bits; };
+    (__component__ $c:ident D $bits:expr) => { $c[0] = $bits; };
+    (__component__ $c:ident S $bits:expr) => { $c[1] = $bits; };
+    (__component__ $c:ident E $bits:expr) => {};
+    (__component__ $c:ident X $bits:expr) => {};
+
     (__inner_strongstorage__ $name:ident $ty:ty) => {
         unsafe impl StrongStorage for $name {
             type Pixel = $ty;
@@ -366,190 +472,283 @@ macro_rules! formats {
 }
 
 formats! {
-    R4G4UnormPack8 => FORMAT_R4G4_UNORM_PACK8 [Some(1)] [float=2] {u8},
-    R4G4B4A4UnormPack16 => FORMAT_R4G4B4A4_UNORM_PACK16 [Some(2)] [float=4] {u16},
-    B4G4R4A4UnormPack16 => FORMAT_B4G4R4A4_UNORM_PACK16 [Some(2)] [float=4] {u16},
-    R5G6B5UnormPack16 => FORMAT_R5G6B5_UNORM_PACK16 [Some(2)] [float=3] {u16},
-    B5G6R5UnormPack16 => FORMAT_B5G6R5_UNORM_PACK16 [Some(2)] [float=3] {u16},
-    R5G5B5A1UnormPack16 => FORMAT_R5G5B5A1_UNORM_PACK16 [Some(2)] [float=4] {u16},
-    B5G5R5A1UnormPack16 => FORMAT_B5G5R5A1_UNORM_PACK16 [Some(2)] [float=4] {u16},
-    A1R5G5B5UnormPack16 => FORMAT_A1R5G5B5_UNORM_PACK16 [Some(2)] [float=4] {u16},
-    R8Unorm => FORMAT_R8_UNORM [Some(1)] [float=1] {u8},
-    R8Snorm => FORMAT_R8_SNORM [Some(1)] [float=1] {i8},
-    R8Uscaled => FORMAT_R8_USCALED [Some(1)] [float=1] {u8},
-    R8Sscaled => FORMAT_R8_SSCALED [Some(1)] [float=1] {i8},
-    R8Uint => FORMAT_R8_UINT [Some(1)] [uint=1] {u8},
-    R8Sint => FORMAT_R8_SINT [Some(1)] [sint=1] {i8},
-    R8Srgb => FORMAT_R8_SRGB [Some(1)] [float=1] {u8},
-    R8G8Unorm => FORMAT_R8G8_UNORM [Some(2)] [float=2] {[u8; 2]},
-    R8G8Snorm => FORMAT_R8G8_SNORM [Some(2)] [float=2] {[i8; 2]},
-    R8G8Uscaled => FORMAT_R8G8_USCALED [Some(2)] [float=2] {[u8; 2]},
-    R8G8Sscaled => FORMAT_R8G8_SSCALED [Some(2)] [float=2] {[i8; 2]},
-    R8G8Uint => FORMAT_R8G8_UINT [Some(2)] [uint=2] {[u8; 2]},
-    R8G8Sint => FORMAT_R8G8_SINT [Some(2)] [sint=2] {[i8; 2]},
-    R8G8Srgb => FORMAT_R8G8_SRGB [Some(2)] [float=2] {[u8; 2]},
-    R8G8B8Unorm => FORMAT_R8G8B8_UNORM [Some(3)] [float=3] {[u8; 3]},
-    R8G8B8Snorm => FORMAT_R8G8B8_SNORM [Some(3)] [float=3] {[i8; 3]},
-    R8G8B8Uscaled => FORMAT_R8G8B8_USCALED [Some(3)] [float=3] {[u8; 3]},
-    R8G8B8Sscaled => FORMAT_R8G8B8_SSCALED [Some(3)] [float=3] {[i8; 3]},
-    R8G8B8Uint => FORMAT_R8G8B8_UINT [Some(3)] [uint=3] {[u8; 3]},
-    R8G8B8Sint => FORMAT_R8G8B8_SINT [Some(3)] [sint=3] {[i8; 3]},
-    R8G8B8Srgb => FORMAT_R8G8B8_SRGB [Some(3)] [float=3] {[u8; 3]},
-    B8G8R8Unorm => FORMAT_B8G8R8_UNORM [Some(3)] [float=3] {[u8; 3]},
-    B8G8R8Snorm => FORMAT_B8G8R8_SNORM [Some(3)] [float=3] {[i8; 3]},
-    B8G8R8Uscaled => FORMAT_B8G8R8_USCALED [Some(3)] [float=3] {[u8; 3]},
-    B8G8R8Sscaled => FORMAT_B8G8R8_SSCALED [Some(3)] [float=3] {[i8; 3]},
-    B8G8R8Uint => FORMAT_B8G8R8_UINT [Some(3)] [uint=3] {[u8; 3]},
-    B8G8R8Sint => FORMAT_B8G8R8_SINT [Some(3)] [sint=3] {[i8; 3]},
-    B8G8R8Srgb => FORMAT_B8G8R8_SRGB [Some(3)] [float=3] {[u8; 3]},
-    R8G8B8A8Unorm => FORMAT_R8G8B8A8_UNORM [Some(4)] [float=4] {[u8; 4]},
-    R8G8B8A8Snorm => FORMAT_R8G8B8A8_SNORM [Some(4)] [float=4] {[i8; 4]},
-    R8G8B8A8Uscaled => FORMAT_R8G8B8A8_USCALED [Some(4)] [float=4] {[u8; 4]},
-    R8G8B8A8Sscaled => FORMAT_R8G8B8A8_SSCALED [Some(4)] [float=4] {[i8; 4]},
-    R8G8B8A8Uint => FORMAT_R8G8B8A8_UINT [Some(4)] [uint=4] {[u8; 4]},
-    R8G8B8A8Sint => FORMAT_R8G8B8A8_SINT [Some(4)] [sint=4] {[i8; 4]},
-    R8G8B8A8Srgb => FORMAT_R8G8B8A8_SRGB [Some(4)] [float=4] {[u8; 4]},
-    B8G8R8A8Unorm => FORMAT_B8G8R8A8_UNORM [Some(4)] [float=4] {[u8; 4]},
-    B8G8R8A8Snorm => FORMAT_B8G8R8A8_SNORM [Some(4)] [float=4] {[i8; 4]},
-    B8G8R8A8Uscaled => FORMAT_B8G8R8A8_USCALED [Some(4)] [float=4] {[u8; 4]},
-    B8G8R8A8Sscaled => FORMAT_B8G8R8A8_SSCALED [Some(4)] [float=4] {[i8; 4]},
-    B8G8R8A8Uint => FORMAT_B8G8R8A8_UINT [Some(4)] [uint=4] {[u8; 4]},
-    B8G8R8A8Sint => FORMAT_B8G8R8A8_SINT [Some(4)] [sint=4] {[i8; 4]},
-    B8G8R8A8Srgb => FORMAT_B8G8R8A8_SRGB [Some(4)] [float=4] {[u8; 4]},
-    A8B8G8R8UnormPack32 => FORMAT_A8B8G8R8_UNORM_PACK32 [Some(4)] [float=4] {[u8; 4]},
-    A8B8G8R8SnormPack32 => FORMAT_A8B8G8R8_SNORM_PACK32 [Some(4)] [float=4] {[i8; 4]},
-    A8B8G8R8UscaledPack32 => FORMAT_A8B8G8R8_USCALED_PACK32 [Some(4)] [float=4] {[u8; 4]},
-    A8B8G8R8SscaledPack32 => FORMAT_A8B8G8R8_SSCALED_PACK32 [Some(4)] [float=4] {[i8; 4]},
-    A8B8G8R8UintPack32 => FORMAT_A8B8G8R8_UINT_PACK32 [Some(4)] [uint=4] {[u8; 4]},
-    A8B8G8R8SintPack32 => FORMAT_A8B8G8R8_SINT_PACK32 [Some(4)] [sint=4] {[i8; 4]},
-    A8B8G8R8SrgbPack32 => FORMAT_A8B8G8R8_SRGB_PACK32 [Some(4)] [float=4] {[u8; 4]},
-    A2R10G10B10UnormPack32 => FORMAT_A2R10G10B10_UNORM_PACK32 [Some(4)] [float=4] {u32},
-    A2R10G10B10SnormPack32 => FORMAT_A2R10G10B10_SNORM_PACK32 [Some(4)] [float=4] {u32},
-    A2R10G10B10UscaledPack32 => FORMAT_A2R10G10B10_USCALED_PACK32 [Some(4)] [float=4] {u32},
-    A2R10G10B10SscaledPack32 => FORMAT_A2R10G10B10_SSCALED_PACK32 [Some(4)] [float=4] {u32},
-    A2R10G10B10UintPack32 => FORMAT_A2R10G10B10_UINT_PACK32 [Some(4)] [uint=4] {u32},
-    A2R10G10B10SintPack32 => FORMAT_A2R10G10B10_SINT_PACK32 [Some(4)] [sint=4] {u32},
-    A2B10G10R10UnormPack32 => FORMAT_A2B10G10R10_UNORM_PACK32 [Some(4)] [float=4] {u32},
-    A2B10G10R10SnormPack32 => FORMAT_A2B10G10R10_SNORM_PACK32 [Some(4)] [float=4] {u32},
-    A2B10G10R10UscaledPack32 => FORMAT_A2B10G10R10_USCALED_PACK32 [Some(4)] [float=4] {u32},
-    A2B10G10R10SscaledPack32 => FORMAT_A2B10G10R10_SSCALED_PACK32 [Some(4)] [float=4] {u32},
-    A2B10G10R10UintPack32 => FORMAT_A2B10G10R10_UINT_PACK32 [Some(4)] [uint=4] {u32},
-    A2B10G10R10SintPack32 => FORMAT_A2B10G10R10_SINT_PACK32 [Some(4)] [sint=4] {u32},
-    R16Unorm => FORMAT_R16_UNORM [Some(2)] [float=1] {u16},
-    R16Snorm => FORMAT_R16_SNORM [Some(2)] [float=1] {i16},
-    R16Uscaled => FORMAT_R16_USCALED [Some(2)] [float=1] {u16},
-    R16Sscaled => FORMAT_R16_SSCALED [Some(2)] [float=1] {i16},
-    R16Uint => FORMAT_R16_UINT [Some(2)] [uint=1] {u16},
-    R16Sint => FORMAT_R16_SINT [Some(2)] [sint=1] {i16},
-    R16Sfloat => FORMAT_R16_SFLOAT [Some(2)] [float=1] {},
-    R16G16Unorm => FORMAT_R16G16_UNORM [Some(4)] [float=2] {[u16; 2]},
-    R16G16Snorm => FORMAT_R16G16_SNORM [Some(4)] [float=2] {[i16; 2]},
-    R16G16Uscaled => FORMAT_R16G16_USCALED [Some(4)] [float=2] {[u16; 2]},
-    R16G16Sscaled => FORMAT_R16G16_SSCALED [Some(4)] [float=2] {[i16; 2]},
-    R16G16Uint => FORMAT_R16G16_UINT [Some(4)] [uint=2] {[u16; 2]},
-    R16G16Sint => FORMAT_R16G16_SINT [Some(4)] [sint=2] {[i16; 2]},
-    R16G16Sfloat => FORMAT_R16G16_SFLOAT [Some(4)] [float=2] {},
-    R16G16B16Unorm => FORMAT_R16G16B16_UNORM [Some(6)] [float=3] {[u16; 3]},
-    R16G16B16Snorm => FORMAT_R16G16B16_SNORM [Some(6)] [float=3] {[i16; 3]},
-    R16G16B16Uscaled => FORMAT_R16G16B16_USCALED [Some(6)] [float=3] {[u16; 3]},
-    R16G16B16Sscaled => FORMAT_R16G16B16_SSCALED [Some(6)] [float=3] {[i16; 3]},
-    R16G16B16Uint => FORMAT_R16G16B16_UINT [Some(6)] [uint=3] {[u16; 3]},
-    R16G16B16Sint => FORMAT_R16G16B16_SINT [Some(6)] [sint=3] {[i16; 3]},
-    R16G16B16Sfloat => FORMAT_R16G16B16_SFLOAT [Some(6)] [float=3] {},
-    R16G16B16A16Unorm => FORMAT_R16G16B16A16_UNORM [Some(8)] [float=4] {[u16; 4]},
-    R16G16B16A16Snorm => FORMAT_R16G16B16A16_SNORM [Some(8)] [float=4] {[i16; 4]},
-    R16G16B16A16Uscaled => FORMAT_R16G16B16A16_USCALED [Some(8)] [float=4] {[u16; 4]},
-    R16G16B16A16Sscaled => FORMAT_R16G16B16A16_SSCALED [Some(8)] [float=4] {[i16; 4]},
-    R16G16B16A16Uint => FORMAT_R16G16B16A16_UINT [Some(8)] [uint=4] {[u16; 4]},
-    R16G16B16A16Sint => FORMAT_R16G16B16A16_SINT [Some(8)] [sint=4] {[i16; 4]},
-    R16G16B16A16Sfloat => FORMAT_R16G16B16A16_SFLOAT [Some(8)] [float=4] {},
-    R32Uint => FORMAT_R32_UINT [Some(4)] [uint=1] {u32},
-    R32Sint => FORMAT_R32_SINT [Some(4)] [sint=1] {i32},
-    R32Sfloat => FORMAT_R32_SFLOAT [Some(4)] [float=1] {f32},
-    R32G32Uint => FORMAT_R32G32_UINT [Some(8)] [uint=2] {[u32; 2]},
-    R32G32Sint => FORMAT_R32G32_SINT [Some(8)] [sint=2] {[i32; 2]},
-    R32G32Sfloat => FORMAT_R32G32_SFLOAT [Some(8)] [float=2] {[f32; 2]},
-    R32G32B32Uint => FORMAT_R32G32B32_UINT [Some(12)] [uint=3] {[u32; 3]},
-    R32G32B32Sint => FORMAT_R32G32B32_SINT [Some(12)] [sint=3] {[i32; 3]},
-    R32G32B32Sfloat => FORMAT_R32G32B32_SFLOAT [Some(12)] [float=3] {[f32; 3]},
-    R32G32B32A32Uint => FORMAT_R32G32B32A32_UINT [Some(16)] [uint=4] {[u32; 4]},
-    R32G32B32A32Sint => FORMAT_R32G32B32A32_SINT [Some(16)] [sint=4] {[i32; 4]},
-    R32G32B32A32Sfloat => FORMAT_R32G32B32A32_SFLOAT [Some(16)] [float=4] {[f32; 4]},
-    R64Uint => FORMAT_R64_UINT [Some(8)] [uint=1] {u64},
-    R64Sint => FORMAT_R64_SINT [Some(8)] [sint=1] {i64},
-    R64Sfloat => FORMAT_R64_SFLOAT [Some(8)] [float=1] {f64},
-    R64G64Uint => FORMAT_R64G64_UINT [Some(16)] [uint=2] {[u64; 2]},
-    R64G64Sint => FORMAT_R64G64_SINT [Some(16)] [sint=2] {[i64; 2]},
-    R64G64Sfloat => FORMAT_R64G64_SFLOAT [Some(16)] [float=2] {[f64; 2]},
-    R64G64B64Uint => FORMAT_R64G64B64_UINT [Some(24)] [uint=3] {[u64; 3]},
-    R64G64B64Sint => FORMAT_R64G64B64_SINT [Some(24)] [sint=3] {[i64; 3]},
-    R64G64B64Sfloat => FORMAT_R64G64B64_SFLOAT [Some(24)] [float=3] {[f64; 3]},
-    R64G64B64A64Uint => FORMAT_R64G64B64A64_UINT [Some(32)] [uint=4] {[u64; 4]},
-    R64G64B64A64Sint => FORMAT_R64G64B64A64_SINT [Some(32)] [sint=4] {[i64; 4]},
-    R64G64B64A64Sfloat => FORMAT_R64G64B64A64_SFLOAT [Some(32)] [float=4] {[f64; 4]},
-    B10G11R11UfloatPack32 => FORMAT_B10G11R11_UFLOAT_PACK32 [Some(4)] [float=3] {u32},
-    E5B9G9R9UfloatPack32 => FORMAT_E5B9G9R9_UFLOAT_PACK32 [Some(4)] [float=3] {u32},
-    D16Unorm => FORMAT_D16_UNORM [Some(2)] [depth] {},
-    X8_D24UnormPack32 => FORMAT_X8_D24_UNORM_PACK32 [Some(4)] [depth] {},
-    D32Sfloat => FORMAT_D32_SFLOAT [Some(4)] [depth] {},
-    S8Uint => FORMAT_S8_UINT [Some(1)] [stencil] {},
-    D16Unorm_S8Uint => FORMAT_D16_UNORM_S8_UINT [None] [depthstencil] {},
-    D24Unorm_S8Uint => FORMAT_D24_UNORM_S8_UINT [None] [depthstencil] {},
-    D32Sfloat_S8Uint => FORMAT_D32_SFLOAT_S8_UINT [None] [depthstencil] {},
-    BC1_RGBUnormBlock => FORMAT_BC1_RGB_UNORM_BLOCK [None] [compressed=texture_compression_bc] {},
-    BC1_RGBSrgbBlock => FORMAT_BC1_RGB_SRGB_BLOCK [None] [compressed=texture_compression_bc] {},
-    BC1_RGBAUnormBlock => FORMAT_BC1_RGBA_UNORM_BLOCK [None] [compressed=texture_compression_bc] {},
-    BC1_RGBASrgbBlock => FORMAT_BC1_RGBA_SRGB_BLOCK [None] [compressed=texture_compression_bc] {},
-    BC2UnormBlock => FORMAT_BC2_UNORM_BLOCK [None] [compressed=texture_compression_bc] {},
-    BC2SrgbBlock => FORMAT_BC2_SRGB_BLOCK [None] [compressed=texture_compression_bc] {},
-    BC3UnormBlock => FORMAT_BC3_UNORM_BLOCK [None] [compressed=texture_compression_bc] {},
-    BC3SrgbBlock => FORMAT_BC3_SRGB_BLOCK [None] [compressed=texture_compression_bc] {},
-    BC4UnormBlock => FORMAT_BC4_UNORM_BLOCK [None] [compressed=texture_compression_bc] {},
-    BC4SnormBlock => FORMAT_BC4_SNORM_BLOCK [None] [compressed=texture_compression_bc] {},
-    BC5UnormBlock => FORMAT_BC5_UNORM_BLOCK [None] [compressed=texture_compression_bc] {},
-    BC5SnormBlock => FORMAT_BC5_SNORM_BLOCK [None] [compressed=texture_compression_bc] {},
-    BC6HUfloatBlock => FORMAT_BC6H_UFLOAT_BLOCK [None] [compressed=texture_compression_bc] {},
-    BC6HSfloatBlock => FORMAT_BC6H_SFLOAT_BLOCK [None] [compressed=texture_compression_bc] {},
-    BC7UnormBlock => FORMAT_BC7_UNORM_BLOCK [None] [compressed=texture_compression_bc] {},
-    BC7SrgbBlock => FORMAT_BC7_SRGB_BLOCK [None] [compressed=texture_compression_bc] {},
-    ETC2_R8G8B8UnormBlock => FORMAT_ETC2_R8G8B8_UNORM_BLOCK [None] [compressed=texture_compression_etc2] {},
-    ETC2_R8G8B8SrgbBlock => FORMAT_ETC2_R8G8B8_SRGB_BLOCK [None] [compressed=texture_compression_etc2] {},
-    ETC2_R8G8B8A1UnormBlock => FORMAT_ETC2_R8G8B8A1_UNORM_BLOCK [None] [compressed=texture_compression_etc2] {},
-    ETC2_R8G8B8A1SrgbBlock => FORMAT_ETC2_R8G8B8A1_SRGB_BLOCK [None] [compressed=texture_compression_etc2] {},
-    ETC2_R8G8B8A8UnormBlock => FORMAT_ETC2_R8G8B8A8_UNORM_BLOCK [None] [compressed=texture_compression_etc2] {},
-    ETC2_R8G8B8A8SrgbBlock => FORMAT_ETC2_R8G8B8A8_SRGB_BLOCK [None] [compressed=texture_compression_etc2] {},
-    EAC_R11UnormBlock => FORMAT_EAC_R11_UNORM_BLOCK [None] [compressed=texture_compression_etc2] {},
-    EAC_R11SnormBlock => FORMAT_EAC_R11_SNORM_BLOCK [None] [compressed=texture_compression_etc2] {},
-    EAC_R11G11UnormBlock => FORMAT_EAC_R11G11_UNORM_BLOCK [None] [compressed=texture_compression_etc2] {},
-    EAC_R11G11SnormBlock => FORMAT_EAC_R11G11_SNORM_BLOCK [None] [compressed=texture_compression_etc2] {},
-    ASTC_4x4UnormBlock => FORMAT_ASTC_4x4_UNORM_BLOCK [None] [compressed=texture_compression_astc_ldr] {},
-    ASTC_4x4SrgbBlock => FORMAT_ASTC_4x4_SRGB_BLOCK [None] [compressed=texture_compression_astc_ldr] {},
-    ASTC_5x4UnormBlock => FORMAT_ASTC_5x4_UNORM_BLOCK [None] [compressed=texture_compression_astc_ldr] {},
-    ASTC_5x4SrgbBlock => FORMAT_ASTC_5x4_SRGB_BLOCK [None] [compressed=texture_compression_astc_ldr] {},
-    ASTC_5x5UnormBlock => FORMAT_ASTC_5x5_UNORM_BLOCK [None] [compressed=texture_compression_astc_ldr] {},
-    ASTC_5x5SrgbBlock => FORMAT_ASTC_5x5_SRGB_BLOCK [None] [compressed=texture_compression_astc_ldr] {},
-    ASTC_6x5UnormBlock => FORMAT_ASTC_6x5_UNORM_BLOCK [None] [compressed=texture_compression_astc_ldr] {},
-    ASTC_6x5SrgbBlock => FORMAT_ASTC_6x5_SRGB_BLOCK [None] [compressed=texture_compression_astc_ldr] {},
-    ASTC_6x6UnormBlock => FORMAT_ASTC_6x6_UNORM_BLOCK [None] [compressed=texture_compression_astc_ldr] {},
-    ASTC_6x6SrgbBlock => FORMAT_ASTC_6x6_SRGB_BLOCK [None] [compressed=texture_compression_astc_ldr] {},
-    ASTC_8x5UnormBlock => FORMAT_ASTC_8x5_UNORM_BLOCK [None] [compressed=texture_compression_astc_ldr] {},
-    ASTC_8x5SrgbBlock => FORMAT_ASTC_8x5_SRGB_BLOCK [None] [compressed=texture_compression_astc_ldr] {},
-    ASTC_8x6UnormBlock => FORMAT_ASTC_8x6_UNORM_BLOCK [None] [compressed=texture_compression_astc_ldr] {},
-    ASTC_8x6SrgbBlock => FORMAT_ASTC_8x6_SRGB_BLOCK [None] [compressed=texture_compression_astc_ldr] {},
-    ASTC_8x8UnormBlock => FORMAT_ASTC_8x8_UNORM_BLOCK [None] [compressed=texture_compression_astc_ldr] {},
-    ASTC_8x8SrgbBlock => FORMAT_ASTC_8x8_SRGB_BLOCK [None] [compressed=texture_compression_astc_ldr] {},
-    ASTC_10x5UnormBlock => FORMAT_ASTC_10x5_UNORM_BLOCK [None] [compressed=texture_compression_astc_ldr] {},
-    ASTC_10x5SrgbBlock => FORMAT_ASTC_10x5_SRGB_BLOCK [None] [compressed=texture_compression_astc_ldr] {},
-    ASTC_10x6UnormBlock => FORMAT_ASTC_10x6_UNORM_BLOCK [None] [compressed=texture_compression_astc_ldr] {},
-    ASTC_10x6SrgbBlock => FORMAT_ASTC_10x6_SRGB_BLOCK [None] [compressed=texture_compression_astc_ldr] {},
-    ASTC_10x8UnormBlock => FORMAT_ASTC_10x8_UNORM_BLOCK [None] [compressed=texture_compression_astc_ldr] {},
-    ASTC_10x8SrgbBlock => FORMAT_ASTC_10x8_SRGB_BLOCK [None] [compressed=texture_compression_astc_ldr] {},
-    ASTC_10x10UnormBlock => FORMAT_ASTC_10x10_UNORM_BLOCK [None] [compressed=texture_compression_astc_ldr] {},
-    ASTC_10x10SrgbBlock => FORMAT_ASTC_10x10_SRGB_BLOCK [None] [compressed=texture_compression_astc_ldr] {},
-    ASTC_12x10UnormBlock => FORMAT_ASTC_12x10_UNORM_BLOCK [None] [compressed=texture_compression_astc_ldr] {},
-    ASTC_12x10SrgbBlock => FORMAT_ASTC_12x10_SRGB_BLOCK [None] [compressed=texture_compression_astc_ldr] {},
-    ASTC_12x12UnormBlock => FORMAT_ASTC_12x12_UNORM_BLOCK [None] [compressed=texture_compression_astc_ldr] {},
-    ASTC_12x12SrgbBlock => FORMAT_ASTC_12x12_SRGB_BLOCK [None] [compressed=texture_compression_astc_ldr] {},
+    R4G4UnormPack8 => FORMAT_R4G4_UNORM_PACK8 [Some(1)] [float=2] {u8} (Unorm; G 4, R 4),
+    R4G4B4A4UnormPack16 => FORMAT_R4G4B4A4_UNORM_PACK16 [Some(2)] [float=4] {u16} (Unorm; A 4, B 4, G 4, R 4),
+    B4G4R4A4UnormPack16 => FORMAT_B4G4R4A4_UNORM_PACK16 [Some(2)] [float=4] {u16} (Unorm; A 4, R 4, G 4, B 4),
+    R5G6B5UnormPack16 => FORMAT_R5G6B5_UNORM_PACK16 [Some(2)] [float=3] {u16} (Unorm; B 5, G 6, R 5),
+    B5G6R5UnormPack16 => FORMAT_B5G6R5_UNORM_PACK16 [Some(2)] [float=3] {u16} (Unorm; R 5, G 6, B 5),
+    R5G5B5A1UnormPack16 => FORMAT_R5G5B5A1_UNORM_PACK16 [Some(2)] [float=4] {u16} (Unorm; A 1, B 5, G 5, R 5),
+    B5G5R5A1UnormPack16 => FORMAT_B5G5R5A1_UNORM_PACK16 [Some(2)] [float=4] {u16} (Unorm; A 1, R 5, G 5, B 5),
+    A1R5G5B5UnormPack16 => FORMAT_A1R5G5B5_UNORM_PACK16 [Some(2)] [float=4] {u16} (Unorm; B 5, G 5, R 5, A 1),
+    R8Unorm => FORMAT_R8_UNORM [Some(1)] [float=1] {u8} (Unorm; R 8),
+    R8Snorm => FORMAT_R8_SNORM [Some(1)] [float=1] {i8} (Snorm; R 8),
+    R8Uscaled => FORMAT_R8_USCALED [Some(1)] [float=1] {u8} (Uscaled; R 8),
+    R8Sscaled => FORMAT_R8_SSCALED [Some(1)] [float=1] {i8} (Sscaled; R 8),
+    R8Uint => FORMAT_R8_UINT [Some(1)] [uint=1] {u8} (Uint; R 8),
+    R8Sint => FORMAT_R8_SINT [Some(1)] [sint=1] {i8} (Sint; R 8),
+    R8Srgb => FORMAT_R8_SRGB [Some(1)] [float=1] {u8} (Srgb; R 8),
+    R8G8Unorm => FORMAT_R8G8_UNORM [Some(2)] [float=2] {[u8; 2]} (Unorm; R 8, G 8),
+    R8G8Snorm => FORMAT_R8G8_SNORM [Some(2)] [float=2] {[i8; 2]} (Snorm; R 8, G 8),
+    R8G8Uscaled => FORMAT_R8G8_USCALED [Some(2)] [float=2] {[u8; 2]} (Uscaled; R 8, G 8),
+    R8G8Sscaled => FORMAT_R8G8_SSCALED [Some(2)] [float=2] {[i8; 2]} (Sscaled; R 8, G 8),
+    R8G8Uint => FORMAT_R8G8_UINT [Some(2)] [uint=2] {[u8; 2]} (Uint; R 8, G 8),
+    R8G8Sint => FORMAT_R8G8_SINT [Some(2)] [sint=2] {[i8; 2]} (Sint; R 8, G 8),
+    R8G8Srgb => FORMAT_R8G8_SRGB [Some(2)] [float=2] {[u8; 2]} (Srgb; R 8, G 8),
+    R8G8B8Unorm => FORMAT_R8G8B8_UNORM [Some(3)] [float=3] {[u8; 3]} (Unorm; R 8, G 8, B 8),
+    R8G8B8Snorm => FORMAT_R8G8B8_SNORM [Some(3)] [float=3] {[i8; 3]} (Snorm; R 8, G 8, B 8),
+    R8G8B8Uscaled => FORMAT_R8G8B8_USCALED [Some(3)] [float=3] {[u8; 3]} (Uscaled; R 8, G 8, B 8),
+    R8G8B8Sscaled => FORMAT_R8G8B8_SSCALED [Some(3)] [float=3] {[i8; 3]} (Sscaled; R 8, G 8, B 8),
+    R8G8B8Uint => FORMAT_R8G8B8_UINT [Some(3)] [uint=3] {[u8; 3]} (Uint; R 8, G 8, B 8),
+    R8G8B8Sint => FORMAT_R8G8B8_SINT [Some(3)] [sint=3] {[i8; 3]} (Sint; R 8, G 8, B 8),
+    R8G8B8Srgb => FORMAT_R8G8B8_SRGB [Some(3)] [float=3] {[u8; 3]} (Srgb; R 8, G 8, B 8),
+    B8G8R8Unorm => FORMAT_B8G8R8_UNORM [Some(3)] [float=3] {[u8; 3]} (Unorm; B 8, G 8, R 8),
+    B8G8R8Snorm => FORMAT_B8G8R8_SNORM [Some(3)] [float=3] {[i8; 3]} (Snorm; B 8, G 8, R 8),
+    B8G8R8Uscaled => FORMAT_B8G8R8_USCALED [Some(3)] [float=3] {[u8; 3]} (Uscaled; B 8, G 8, R 8),
+    B8G8R8Sscaled => FORMAT_B8G8R8_SSCALED [Some(3)] [float=3] {[i8; 3]} (Sscaled; B 8, G 8, R 8),
+    B8G8R8Uint => FORMAT_B8G8R8_UINT [Some(3)] [uint=3] {[u8; 3]} (Uint; B 8, G 8, R 8),
+    B8G8R8Sint => FORMAT_B8G8R8_SINT [Some(3)] [sint=3] {[i8; 3]} (Sint; B 8, G 8, R 8),
+    B8G8R8Srgb => FORMAT_B8G8R8_SRGB [Some(3)] [float=3] {[u8; 3]} (Srgb; B 8, G 8, R 8),
+    R8G8B8A8Unorm => FORMAT_R8G8B8A8_UNORM [Some(4)] [float=4] {[u8; 4]} (Unorm; R 8, G 8, B 8, A 8),
+    R8G8B8A8Snorm => FORMAT_R8G8B8A8_SNORM [Some(4)] [float=4] {[i8; 4]} (Snorm; R 8, G 8, B 8, A 8),
+    R8G8B8A8Uscaled => FORMAT_R8G8B8A8_USCALED [Some(4)] [float=4] {[u8; 4]} (Uscaled; R 8, G 8, B 8, A 8),
+    R8G8B8A8Sscaled => FORMAT_R8G8B8A8_SSCALED [Some(4)] [float=4] {[i8; 4]} (Sscaled; R 8, G 8, B 8, A 8),
+    R8G8B8A8Uint => FORMAT_R8G8B8A8_UINT [Some(4)] [uint=4] {[u8; 4]} (Uint; R 8, G 8, B 8, A 8),
+    R8G8B8A8Sint => FORMAT_R8G8B8A8_SINT [Some(4)] [sint=4] {[i8; 4]} (Sint; R 8, G 8, B 8, A 8),
+    R8G8B8A8Srgb => FORMAT_R8G8B8A8_SRGB [Some(4)] [float=4] {[u8; 4]} (Srgb; R 8, G 8, B 8, A 8),
+    B8G8R8A8Unorm => FORMAT_B8G8R8A8_UNORM [Some(4)] [float=4] {[u8; 4]} (Unorm; B 8, G 8, R 8, A 8),
+    B8G8R8A8Snorm => FORMAT_B8G8R8A8_SNORM [Some(4)] [float=4] {[i8; 4]} (Snorm; B 8, G 8, R 8, A 8),
+    B8G8R8A8Uscaled => FORMAT_B8G8R8A8_USCALED [Some(4)] [float=4] {[u8; 4]} (Uscaled; B 8, G 8, R 8, A 8),
+    B8G8R8A8Sscaled => FORMAT_B8G8R8A8_SSCALED [Some(4)] [float=4] {[i8; 4]} (Sscaled; B 8, G 8, R 8, A 8),
+    B8G8R8A8Uint => FORMAT_B8G8R8A8_UINT [Some(4)] [uint=4] {[u8; 4]} (Uint; B 8, G 8, R 8, A 8),
+    B8G8R8A8Sint => FORMAT_B8G8R8A8_SINT [Some(4)] [sint=4] {[i8; 4]} (Sint; B 8, G 8, R 8, A 8),
+    B8G8R8A8Srgb => FORMAT_B8G8R8A8_SRGB [Some(4)] [float=4] {[u8; 4]} (Srgb; B 8, G 8, R 8, A 8),
+    A8B8G8R8UnormPack32 => FORMAT_A8B8G8R8_UNORM_PACK32 [Some(4)] [float=4] {[u8; 4]} (Unorm; R 8, G 8, B 8, A 8),
+    A8B8G8R8SnormPack32 => FORMAT_A8B8G8R8_SNORM_PACK32 [Some(4)] [float=4] {[i8; 4]} (Snorm; R 8, G 8, B 8, A 8),
+    A8B8G8R8UscaledPack32 => FORMAT_A8B8G8R8_USCALED_PACK32 [Some(4)] [float=4] {[u8; 4]} (Uscaled; R 8, G 8, B 8, A 8),
+    A8B8G8R8SscaledPack32 => FORMAT_A8B8G8R8_SSCALED_PACK32 [Some(4)] [float=4] {[i8; 4]} (Sscaled; R 8, G 8, B 8, A 8),
+    A8B8G8R8UintPack32 => FORMAT_A8B8G8R8_UINT_PACK32 [Some(4)] [uint=4] {[u8; 4]} (Uint; R 8, G 8, B 8, A 8),
+    A8B8G8R8SintPack32 => FORMAT_A8B8G8R8_SINT_PACK32 [Some(4)] [sint=4] {[i8; 4]} (Sint; R 8, G 8, B 8, A 8),
+    A8B8G8R8SrgbPack32 => FORMAT_A8B8G8R8_SRGB_PACK32 [Some(4)] [float=4] {[u8; 4]} (Srgb; R 8, G 8, B 8, A 8),
+    A2R10G10B10UnormPack32 => FORMAT_A2R10G10B10_UNORM_PACK32 [Some(4)] [float=4] {u32} (Unorm; B 10, G 10, R 10, A 2),
+    A2R10G10B10SnormPack32 => FORMAT_A2R10G10B10_SNORM_PACK32 [Some(4)] [float=4] {u32} (Snorm; B 10, G 10, R 10, A 2),
+    A2R10G10B10UscaledPack32 => FORMAT_A2R10G10B10_USCALED_PACK32 [Some(4)] [float=4] {u32} (Uscaled; B 10, G 10, R 10, A 2),
+    A2R10G10B10SscaledPack32 => FORMAT_A2R10G10B10_SSCALED_PACK32 [Some(4)] [float=4] {u32} (Sscaled; B 10, G 10, R 10, A 2),
+    A2R10G10B10UintPack32 => FORMAT_A2R10G10B10_UINT_PACK32 [Some(4)] [uint=4] {u32} (Uint; B 10, G 10, R 10, A 2),
+    A2R10G10B10SintPack32 => FORMAT_A2R10G10B10_SINT_PACK32 [Some(4)] [sint=4] {u32} (Sint; B 10, G 10, R 10, A 2),
+    A2B10G10R10UnormPack32 => FORMAT_A2B10G10R10_UNORM_PACK32 [Some(4)] [float=4] {u32} (Unorm; R 10, G 10, B 10, A 2),
+    A2B10G10R10SnormPack32 => FORMAT_A2B10G10R10_SNORM_PACK32 [Some(4)] [float=4] {u32} (Snorm; R 10, G 10, B 10, A 2),
+    A2B10G10R10UscaledPack32 => FORMAT_A2B10G10R10_USCALED_PACK32 [Some(4)] [float=4] {u32} (Uscaled; R 10, G 10, B 10, A 2),
+    A2B10G10R10SscaledPack32 => FORMAT_A2B10G10R10_SSCALED_PACK32 [Some(4)] [float=4] {u32} (Sscaled; R 10, G 10, B 10, A 2),
+    A2B10G10R10UintPack32 => FORMAT_A2B10G10R10_UINT_PACK32 [Some(4)] [uint=4] {u32} (Uint; R 10, G 10, B 10, A 2),
+    A2B10G10R10SintPack32 => FORMAT_A2B10G10R10_SINT_PACK32 [Some(4)] [sint=4] {u32} (Sint; R 10, G 10, B 10, A 2),
+    R16Unorm => FORMAT_R16_UNORM [Some(2)] [float=1] {u16} (Unorm; R 16),
+    R16Snorm => FORMAT_R16_SNORM [Some(2)] [float=1] {i16} (Snorm; R 16),
+    R16Uscaled => FORMAT_R16_USCALED [Some(2)] [float=1] {u16} (Uscaled; R 16),
+    R16Sscaled => FORMAT_R16_SSCALED [Some(2)] [float=1] {i16} (Sscaled; R 16),
+    R16Uint => FORMAT_R16_UINT [Some(2)] [uint=1] {u16} (Uint; R 16),
+    R16Sint => FORMAT_R16_SINT [Some(2)] [sint=1] {i16} (Sint; R 16),
+    R16Sfloat => FORMAT_R16_SFLOAT [Some(2)] [float=1] {} (Sfloat; R 16),
+    R16G16Unorm => FORMAT_R16G16_UNORM [Some(4)] [float=2] {[u16; 2]} (Unorm; R 16, G 16),
+    R16G16Snorm => FORMAT_R16G16_SNORM [Some(4)] [float=2] {[i16; 2]} (Snorm; R 16, G 16),
+    R16G16Uscaled => FORMAT_R16G16_USCALED [Some(4)] [float=2] {[u16; 2]} (Uscaled; R 16, G 16),
+    R16G16Sscaled => FORMAT_R16G16_SSCALED [Some(4)] [float=2] {[i16; 2]} (Sscaled; R 16, G 16),
+    R16G16Uint => FORMAT_R16G16_UINT [Some(4)] [uint=2] {[u16; 2]} (Uint; R 16, G 16),
+    R16G16Sint => FORMAT_R16G16_SINT [Some(4)] [sint=2] {[i16; 2]} (Sint; R 16, G 16),
+    R16G16Sfloat => FORMAT_R16G16_SFLOAT [Some(4)] [float=2] {} (Sfloat; R 16, G 16),
+    R16G16B16Unorm => FORMAT_R16G16B16_UNORM [Some(6)] [float=3] {[u16; 3]} (Unorm; R 16, G 16, B 16),
+    R16G16B16Snorm => FORMAT_R16G16B16_SNORM [Some(6)] [float=3] {[i16; 3]} (Snorm; R 16, G 16, B 16),
+    R16G16B16Uscaled => FORMAT_R16G16B16_USCALED [Some(6)] [float=3] {[u16; 3]} (Uscaled; R 16, G 16, B 16),
+    R16G16B16Sscaled => FORMAT_R16G16B16_SSCALED [Some(6)] [float=3] {[i16; 3]} (Sscaled; R 16, G 16, B 16),
+    R16G16B16Uint => FORMAT_R16G16B16_UINT [Some(6)] [uint=3] {[u16; 3]} (Uint; R 16, G 16, B 16),
+    R16G16B16Sint => FORMAT_R16G16B16_SINT [Some(6)] [sint=3] {[i16; 3]} (Sint; R 16, G 16, B 16),
+    R16G16B16Sfloat => FORMAT_R16G16B16_SFLOAT [Some(6)] [float=3] {} (Sfloat; R 16, G 16, B 16),
+    R16G16B16A16Unorm => FORMAT_R16G16B16A16_UNORM [Some(8)] [float=4] {[u16; 4]} (Unorm; R 16, G 16, B 16, A 16),
+    R16G16B16A16Snorm => FORMAT_R16G16B16A16_SNORM [Some(8)] [float=4] {[i16; 4]} (Snorm; R 16, G 16, B 16, A 16),
+    R16G16B16A16Uscaled => FORMAT_R16G16B16A16_USCALED [Some(8)] [float=4] {[u16; 4]} (Uscaled; R 16, G 16, B 16, A 16),
+    R16G16B16A16Sscaled => FORMAT_R16G16B16A16_SSCALED [Some(8)] [float=4] {[i16; 4]} (Sscaled; R 16, G 16, B 16, A 16),
+    R16G16B16A16Uint => FORMAT_R16G16B16A16_UINT [Some(8)] [uint=4] {[u16; 4]} (Uint; R 16, G 16, B 16, A 16),
+    R16G16B16A16Sint => FORMAT_R16G16B16A16_SINT [Some(8)] [sint=4] {[i16; 4]} (Sint; R 16, G 16, B 16, A 16),
+    R16G16B16A16Sfloat => FORMAT_R16G16B16A16_SFLOAT [Some(8)] [float=4] {} (Sfloat; R 16, G 16, B 16, A 16),
+    R32Uint => FORMAT_R32_UINT [Some(4)] [uint=1] {u32} (Uint; R 32),
+    R32Sint => FORMAT_R32_SINT [Some(4)] [sint=1] {i32} (Sint; R 32),
+    R32Sfloat => FORMAT_R32_SFLOAT [Some(4)] [float=1] {f32} (Sfloat; R 32),
+    R32G32Uint => FORMAT_R32G32_UINT [Some(8)] [uint=2] {[u32; 2]} (Uint; R 32, G 32),
+    R32G32Sint => FORMAT_R32G32_SINT [Some(8)] [sint=2] {[i32; 2]} (Sint; R 32, G 32),
+    R32G32Sfloat => FORMAT_R32G32_SFLOAT [Some(8)] [float=2] {[f32; 2]} (Sfloat; R 32, G 32),
+    R32G32B32Uint => FORMAT_R32G32B32_UINT [Some(12)] [uint=3] {[u32; 3]} (Uint; R 32, G 32, B 32),
+    R32G32B32Sint => FORMAT_R32G32B32_SINT [Some(12)] [sint=3] {[i32; 3]} (Sint; R 32, G 32, B 32),
+    R32G32B32Sfloat => FORMAT_R32G32B32_SFLOAT [Some(12)] [float=3] {[f32; 3]} (Sfloat; R 32, G 32, B 32),
+    R32G32B32A32Uint => FORMAT_R32G32B32A32_UINT [Some(16)] [uint=4] {[u32; 4]} (Uint; R 32, G 32, B 32, A 32),
+    R32G32B32A32Sint => FORMAT_R32G32B32A32_SINT [Some(16)] [sint=4] {[i32; 4]} (Sint; R 32, G 32, B 32, A 32),
+    R32G32B32A32Sfloat => FORMAT_R32G32B32A32_SFLOAT [Some(16)] [float=4] {[f32; 4]} (Sfloat; R 32, G 32, B 32, A 32),
+    R64Uint => FORMAT_R64_UINT [Some(8)] [uint=1] {u64} (Uint; R 64),
+    R64Sint => FORMAT_R64_SINT [Some(8)] [sint=1] {i64} (Sint; R 64),
+    R64Sfloat => FORMAT_R64_SFLOAT [Some(8)] [float=1] {f64} (Sfloat; R 64),
+    R64G64Uint => FORMAT_R64G64_UINT [Some(16)] [uint=2] {[u64; 2]} (Uint; R 64, G 64),
+    R64G64Sint => FORMAT_R64G64_SINT [Some(16)] [sint=2] {[i64; 2]} (Sint; R 64, G 64),
+    R64G64Sfloat => FORMAT_R64G64_SFLOAT [Some(16)] [float=2] {[f64; 2]} (Sfloat; R 64, G 64),
+    R64G64B64Uint => FORMAT_R64G64B64_UINT [Some(24)] [uint=3] {[u64; 3]} (Uint; R 64, G 64, B 64),
+    R64G64B64Sint => FORMAT_R64G64B64_SINT [Some(24)] [sint=3] {[i64; 3]} (Sint; R 64, G 64, B 64),
+    R64G64B64Sfloat => FORMAT_R64G64B64_SFLOAT [Some(24)] [float=3] {[f64; 3]} (Sfloat; R 64, G 64, B 64),
+    R64G64B64A64Uint => FORMAT_R64G64B64A64_UINT [Some(32)] [uint=4] {[u64; 4]} (Uint; R 64, G 64, B 64, A 64),
+    R64G64B64A64Sint => FORMAT_R64G64B64A64_SINT [Some(32)] [sint=4] {[i64; 4]} (Sint; R 64, G 64, B 64, A 64),
+    R64G64B64A64Sfloat => FORMAT_R64G64B64A64_SFLOAT [Some(32)] [float=4] {[f64; 4]} (Sfloat; R 64, G 64, B 64, A 64),
+    B10G11R11UfloatPack32 => FORMAT_B10G11R11_UFLOAT_PACK32 [Some(4)] [float=3] {u32} (Ufloat; R 11, G 11, B 10),
+    E5B9G9R9UfloatPack32 => FORMAT_E5B9G9R9_UFLOAT_PACK32 [Some(4)] [float=3] {u32} (Ufloat; R 9, G 9, B 9, E 5),
+    D16Unorm => FORMAT_D16_UNORM [Some(2)] [depth] {} (Unorm; D 16),
+    X8_D24UnormPack32 => FORMAT_X8_D24_UNORM_PACK32 [Some(4)] [depth] {} (Unorm; D 24, X 8),
+    D32Sfloat => FORMAT_D32_SFLOAT [Some(4)] [depth] {} (Sfloat; D 32),
+    S8Uint => FORMAT_S8_UINT [Some(1)] [stencil] {} (Uint; S 8),
+    D16Unorm_S8Uint => FORMAT_D16_UNORM_S8_UINT [None] [depthstencil] {} (Unorm; D 16, S 8),
+    D24Unorm_S8Uint => FORMAT_D24_UNORM_S8_UINT [None] [depthstencil] {} (Unorm; D 24, S 8),
+    D32Sfloat_S8Uint => FORMAT_D32_SFLOAT_S8_UINT [None] [depthstencil] {} (Sfloat; D 32, S 8),
+    BC1_RGBUnormBlock => FORMAT_BC1_RGB_UNORM_BLOCK [None] [compressed=texture_compression_bc] {} (Unorm; R 1, G 1, B 1; [4, 4] 8),
+    BC1_RGBSrgbBlock => FORMAT_BC1_RGB_SRGB_BLOCK [None] [compressed=texture_compression_bc] {} (Srgb; R 1, G 1, B 1; [4, 4] 8),
+    BC1_RGBAUnormBlock => FORMAT_BC1_RGBA_UNORM_BLOCK [None] [compressed=texture_compression_bc] {} (Unorm; R 1, G 1, B 1, A 1; [4, 4] 8),
+    BC1_RGBASrgbBlock => FORMAT_BC1_RGBA_SRGB_BLOCK [None] [compressed=texture_compression_bc] {} (Srgb; R 1, G 1, B 1, A 1; [4, 4] 8),
+    BC2UnormBlock => FORMAT_BC2_UNORM_BLOCK [None] [compressed=texture_compression_bc] {} (Unorm; R 1, G 1, B 1, A 1; [4, 4] 16),
+    BC2SrgbBlock => FORMAT_BC2_SRGB_BLOCK [None] [compressed=texture_compression_bc] {} (Srgb; R 1, G 1, B 1, A 1; [4, 4] 16),
+    BC3UnormBlock => FORMAT_BC3_UNORM_BLOCK [None] [compressed=texture_compression_bc] {} (Unorm; R 1, G 1, B 1, A 1; [4, 4] 16),
+    BC3SrgbBlock => FORMAT_BC3_SRGB_BLOCK [None] [compressed=texture_compression_bc] {} (Srgb; R 1, G 1, B 1, A 1; [4, 4] 16),
+    BC4UnormBlock => FORMAT_BC4_UNORM_BLOCK [None] [compressed=texture_compression_bc] {} (Unorm; R 1; [4, 4] 8),
+    BC4SnormBlock => FORMAT_BC4_SNORM_BLOCK [None] [compressed=texture_compression_bc] {} (Snorm; R 1; [4, 4] 8),
+    BC5UnormBlock => FORMAT_BC5_UNORM_BLOCK [None] [compressed=texture_compression_bc] {} (Unorm; R 1, G 1; [4, 4] 16),
+    BC5SnormBlock => FORMAT_BC5_SNORM_BLOCK [None] [compressed=texture_compression_bc] {} (Snorm; R 1, G 1; [4, 4] 16),
+    BC6HUfloatBlock => FORMAT_BC6H_UFLOAT_BLOCK [None] [compressed=texture_compression_bc] {} (Ufloat; R 1, G 1, B 1; [4, 4] 16),
+    BC6HSfloatBlock => FORMAT_BC6H_SFLOAT_BLOCK [None] [compressed=texture_compression_bc] {} (Sfloat; R 1, G 1, B 1; [4, 4] 16),
+    BC7UnormBlock => FORMAT_BC7_UNORM_BLOCK [None] [compressed=texture_compression_bc] {} (Unorm; R 1, G 1, B 1, A 1; [4, 4] 16),
+    BC7SrgbBlock => FORMAT_BC7_SRGB_BLOCK [None] [compressed=texture_compression_bc] {} (Srgb; R 1, G 1, B 1, A 1; [4, 4] 16),
+    ETC2_R8G8B8UnormBlock => FORMAT_ETC2_R8G8B8_UNORM_BLOCK [None] [compressed=texture_compression_etc2] {} (Unorm; R 1, G 1, B 1; [4, 4] 8),
+    ETC2_R8G8B8SrgbBlock => FORMAT_ETC2_R8G8B8_SRGB_BLOCK [None] [compressed=texture_compression_etc2] {} (Srgb; R 1, G 1, B 1; [4, 4] 8),
+    ETC2_R8G8B8A1UnormBlock => FORMAT_ETC2_R8G8B8A1_UNORM_BLOCK [None] [compressed=texture_compression_etc2] {} (Unorm; R 1, G 1, B 1, A 1; [4, 4] 8),
+    ETC2_R8G8B8A1SrgbBlock => FORMAT_ETC2_R8G8B8A1_SRGB_BLOCK [None] [compressed=texture_compression_etc2] {} (Srgb; R 1, G 1, B 1, A 1; [4, 4] 8),
+    ETC2_R8G8B8A8UnormBlock => FORMAT_ETC2_R8G8B8A8_UNORM_BLOCK [None] [compressed=texture_compression_etc2] {} (Unorm; R 1, G 1, B 1, A 1; [4, 4] 16),
+    ETC2_R8G8B8A8SrgbBlock => FORMAT_ETC2_R8G8B8A8_SRGB_BLOCK [None] [compressed=texture_compression_etc2] {} (Srgb; R 1, G 1, B 1, A 1; [4, 4] 16),
+    EAC_R11UnormBlock => FORMAT_EAC_R11_UNORM_BLOCK [None] [compressed=texture_compression_etc2] {} (Unorm; R 1; [4, 4] 8),
+    EAC_R11SnormBlock => FORMAT_EAC_R11_SNORM_BLOCK [None] [compressed=texture_compression_etc2] {} (Snorm; R 1; [4, 4] 8),
+    EAC_R11G11UnormBlock => FORMAT_EAC_R11G11_UNORM_BLOCK [None] [compressed=texture_compression_etc2] {} (Unorm; R 1, G 1; [4, 4] 16),
+    EAC_R11G11SnormBlock => FORMAT_EAC_R11G11_SNORM_BLOCK [None] [compressed=texture_compression_etc2] {} (Snorm; R 1, G 1; [4, 4] 16),
+    ASTC_4x4UnormBlock => FORMAT_ASTC_4x4_UNORM_BLOCK [None] [compressed=texture_compression_astc_ldr] {} (Unorm; R 1, G 1, B 1, A 1; [4, 4] 16),
+    ASTC_4x4SrgbBlock => FORMAT_ASTC_4x4_SRGB_BLOCK [None] [compressed=texture_compression_astc_ldr] {} (Srgb; R 1, G 1, B 1, A 1; [4, 4] 16),
+    ASTC_5x4UnormBlock => FORMAT_ASTC_5x4_UNORM_BLOCK [None] [compressed=texture_compression_astc_ldr] {} (Unorm; R 1, G 1, B 1, A 1; [5, 4] 16),
+    ASTC_5x4SrgbBlock => FORMAT_ASTC_5x4_SRGB_BLOCK [None] [compressed=texture_compression_astc_ldr] {} (Srgb; R 1, G 1, B 1, A 1; [5, 4] 16),
+    ASTC_5x5UnormBlock => FORMAT_ASTC_5x5_UNORM_BLOCK [None] [compressed=texture_compression_astc_ldr] {} (Unorm; R 1, G 1, B 1, A 1; [5, 5] 16),
+    ASTC_5x5SrgbBlock => FORMAT_ASTC_5x5_SRGB_BLOCK [None] [compressed=texture_compression_astc_ldr] {} (Srgb; R 1, G 1, B 1, A 1; [5, 5] 16),
+    ASTC_6x5UnormBlock => FORMAT_ASTC_6x5_UNORM_BLOCK [None] [compressed=texture_compression_astc_ldr] {} (Unorm; R 1, G 1, B 1, A 1; [6, 5] 16),
+    ASTC_6x5SrgbBlock => FORMAT_ASTC_6x5_SRGB_BLOCK [None] [compressed=texture_compression_astc_ldr] {} (Srgb; R 1, G 1, B 1, A 1; [6, 5] 16),
+    ASTC_6x6UnormBlock => FORMAT_ASTC_6x6_UNORM_BLOCK [None] [compressed=texture_compression_astc_ldr] {} (Unorm; R 1, G 1, B 1, A 1; [6, 6] 16),
+    ASTC_6x6SrgbBlock => FORMAT_ASTC_6x6_SRGB_BLOCK [None] [compressed=texture_compression_astc_ldr] {} (Srgb; R 1, G 1, B 1, A 1; [6, 6] 16),
+    ASTC_8x5UnormBlock => FORMAT_ASTC_8x5_UNORM_BLOCK [None] [compressed=texture_compression_astc_ldr] {} (Unorm; R 1, G 1, B 1, A 1; [8, 5] 16),
+    ASTC_8x5SrgbBlock => FORMAT_ASTC_8x5_SRGB_BLOCK [None] [compressed=texture_compression_astc_ldr] {} (Srgb; R 1, G 1, B 1, A 1; [8, 5] 16),
+    ASTC_8x6UnormBlock => FORMAT_ASTC_8x6_UNORM_BLOCK [None] [compressed=texture_compression_astc_ldr] {} (Unorm; R 1, G 1, B 1, A 1; [8, 6] 16),
+    ASTC_8x6SrgbBlock => FORMAT_ASTC_8x6_SRGB_BLOCK [None] [compressed=texture_compression_astc_ldr] {} (Srgb; R 1, G 1, B 1, A 1; [8, 6] 16),
+    ASTC_8x8UnormBlock => FORMAT_ASTC_8x8_UNORM_BLOCK [None] [compressed=texture_compression_astc_ldr] {} (Unorm; R 1, G 1, B 1, A 1; [8, 8] 16),
+    ASTC_8x8SrgbBlock => FORMAT_ASTC_8x8_SRGB_BLOCK [None] [compressed=texture_compression_astc_ldr] {} (Srgb; R 1, G 1, B 1, A 1; [8, 8] 16),
+    ASTC_10x5UnormBlock => FORMAT_ASTC_10x5_UNORM_BLOCK [None] [compressed=texture_compression_astc_ldr] {} (Unorm; R 1, G 1, B 1, A 1; [10, 5] 16),
+    ASTC_10x5SrgbBlock => FORMAT_ASTC_10x5_SRGB_BLOCK [None] [compressed=texture_compression_astc_ldr] {} (Srgb; R 1, G 1, B 1, A 1; [10, 5] 16),
+    ASTC_10x6UnormBlock => FORMAT_ASTC_10x6_UNORM_BLOCK [None] [compressed=texture_compression_astc_ldr] {} (Unorm; R 1, G 1, B 1, A 1; [10, 6] 16),
+    ASTC_10x6SrgbBlock => FORMAT_ASTC_10x6_SRGB_BLOCK [None] [compressed=texture_compression_astc_ldr] {} (Srgb; R 1, G 1, B 1, A 1; [10, 6] 16),
+    ASTC_10x8UnormBlock => FORMAT_ASTC_10x8_UNORM_BLOCK [None] [compressed=texture_compression_astc_ldr] {} (Unorm; R 1, G 1, B 1, A 1; [10, 8] 16),
+    ASTC_10x8SrgbBlock => FORMAT_ASTC_10x8_SRGB_BLOCK [None] [compressed=texture_compression_astc_ldr] {} (Srgb; R 1, G 1, B 1, A 1; [10, 8] 16),
+    ASTC_10x10UnormBlock => FORMAT_ASTC_10x10_UNORM_BLOCK [None] [compressed=texture_compression_astc_ldr] {} (Unorm; R 1, G 1, B 1, A 1; [10, 10] 16),
+    ASTC_10x10SrgbBlock => FORMAT_ASTC_10x10_SRGB_BLOCK [None] [compressed=texture_compression_astc_ldr] {} (Srgb; R 1, G 1, B 1, A 1; [10, 10] 16),
+    ASTC_12x10UnormBlock => FORMAT_ASTC_12x10_UNORM_BLOCK [None] [compressed=texture_compression_astc_ldr] {} (Unorm; R 1, G 1, B 1, A 1; [12, 10] 16),
+    ASTC_12x10SrgbBlock => FORMAT_ASTC_12x10_SRGB_BLOCK [None] [compressed=texture_compression_astc_ldr] {} (Srgb; R 1, G 1, B 1, A 1; [12, 10] 16),
+    ASTC_12x12UnormBlock => FORMAT_ASTC_12x12_UNORM_BLOCK [None] [compressed=texture_compression_astc_ldr] {} (Unorm; R 1, G 1, B 1, A 1; [12, 12] 16),
+    ASTC_12x12SrgbBlock => FORMAT_ASTC_12x12_SRGB_BLOCK [None] [compressed=texture_compression_astc_ldr] {} (Srgb; R 1, G 1, B 1, A 1; [12, 12] 16),
+}
+
+impl Format {
+    /// Returns which aspects images of this format have.
+    #[inline]
+    pub fn aspects(&self) -> FormatAspects {
+        match self.ty() {
+            FormatTy::Float | FormatTy::Uint | FormatTy::Sint | FormatTy::Compressed => {
+                FormatAspects {
+                    color: true,
+                    depth: false,
+                    stencil: false,
+                }
+            },
+            FormatTy::Depth => {
+                FormatAspects {
+                    color: false,
+                    depth: true,
+                    stencil: false,
+                }
+            },
+            FormatTy::Stencil => {
+                FormatAspects {
+                    color: false,
+                    depth: false,
+                    stencil: true,
+                }
+            },
+            FormatTy::DepthStencil => {
+                FormatAspects {
+                    color: false,
+                    depth: true,
+                    stencil: true,
+                }
+            },
+        }
+    }
+
+    /// Returns true if the color components of this format are in the sRGB color space.
+    #[inline]
+    pub fn is_srgb(&self) -> bool {
+        self.numeric_type() == NumericType::Srgb
+    }
+
+    /// Returns the number of bytes that a region of `[width, height, depth]` texels of this format
+    /// occupies when tightly packed.
+    ///
+    /// For compressed formats, the region is rounded up to a whole number of blocks. Returns
+    /// `None` if `block_size()` returns `None`, or if the size overflows a `usize`.
+    #[inline]
+    pub fn region_size(&self, extent: [u32; 3]) -> Option<usize> {
+        let block_size = match self.block_size() {
+            Some(s) => s,
+            None => return None,
+        };
+
+        let block_extent = self.block_extent();
+        let blocks = (0 .. 3).fold(Some(1usize), |total, n| {
+            let blocks = (extent[n] as u64 + block_extent[n] as u64 - 1) / block_extent[n] as u64;
+            if blocks > usize::max_value() as u64 {
+                return None;
+            }
+            total.and_then(|t| t.checked_mul(blocks as usize))
+        });
+        blocks.and_then(|b| b.checked_mul(block_size))
+    }
+}
+
+/// Numeric type of the components of a format.
+///
+/// See the documentation of the module for the meaning of each type.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum NumericType {
+    Unorm,
+    Snorm,
+    Uscaled,
+    Sscaled,
+    Uint,
+    Sint,
+    Ufloat,
+    Sfloat,
+    Srgb,
+}
+
+/// Aspects of an image of a given format.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct FormatAspects {
+    /// The image contains color components.
+    pub color: bool,
+    /// The image contains a depth component.
+    pub depth: bool,
+    /// The image contains a stencil component.
+    pub stencil: bool,
 }
 
 pub unsafe trait FormatDesc {
@@ -856,3 +1055,66 @@ macro_rules! impl_clear_values_tuple {
 }
 
 impl_clear_values_tuple!(A B C D E F G H I J K L M N O P Q R S T U V W X Y Z);
+
+#[cfg(test)]
+mod tests {
+    use format::Format;
+    use format::FormatAspects;
+    use format::NumericType;
+
+    #[test]
+    fn block_metadata() {
+        assert_eq!(Format::R8G8B8A8Unorm.block_extent(), [1, 1, 1]);
+        assert_eq!(Format::R8G8B8A8Unorm.block_size(), Some(4));
+        assert_eq!(Format::BC1_RGBUnormBlock.block_extent(), [4, 4, 1]);
+        assert_eq!(Format::BC1_RGBUnormBlock.block_size(), Some(8));
+        assert_eq!(Format::BC7SrgbBlock.block_size(), Some(16));
+        assert_eq!(Format::ASTC_10x6UnormBlock.block_extent(), [10, 6, 1]);
+        assert_eq!(Format::D24Unorm_S8Uint.block_size(), None);
+    }
+
+    #[test]
+    fn region_size() {
+        assert_eq!(Format::R8G8B8A8Unorm.region_size([3, 5, 2]), Some(3 * 5 * 2 * 4));
+        // 3x2 blocks of 8 bytes.
+        assert_eq!(Format::BC1_RGBAUnormBlock.region_size([10, 6, 1]), Some(48));
+        // A single block, even for a region smaller than the block.
+        assert_eq!(Format::ASTC_12x12SrgbBlock.region_size([1, 1, 1]), Some(16));
+        assert_eq!(Format::D32Sfloat_S8Uint.region_size([1, 1, 1]), None);
+        assert_eq!(Format::BC1_RGBAUnormBlock.region_size([u32::max_value(), 1, 1]),
+                   Some(((u32::max_value() as usize + 3) / 4) * 8));
+        assert_eq!(Format::R32G32B32A32Sfloat.region_size([u32::max_value(); 3]), None);
+    }
+
+    #[test]
+    fn components() {
+        assert_eq!(Format::B8G8R8A8Srgb.components(), [8, 8, 8, 8]);
+        assert_eq!(Format::A2B10G10R10UnormPack32.components(), [10, 10, 10, 2]);
+        assert_eq!(Format::B10G11R11UfloatPack32.components(), [11, 11, 10, 0]);
+        assert_eq!(Format::E5B9G9R9UfloatPack32.components(), [9, 9, 9, 0]);
+        assert_eq!(Format::X8_D24UnormPack32.components(), [24, 0, 0, 0]);
+        assert_eq!(Format::D16Unorm_S8Uint.components(), [16, 8, 0, 0]);
+        assert_eq!(Format::BC5SnormBlock.components(), [1, 1, 0, 0]);
+    }
+
+    #[test]
+    fn numeric_type_and_aspects() {
+        assert_eq!(Format::R16G16Sfloat.numeric_type(), NumericType::Sfloat);
+        assert_eq!(Format::D24Unorm_S8Uint.numeric_type(), NumericType::Unorm);
+        assert!(Format::ETC2_R8G8B8A8SrgbBlock.is_srgb());
+        assert!(!Format::R8G8B8A8Unorm.is_srgb());
+
+        assert_eq!(Format::BC3UnormBlock.aspects(),
+                   FormatAspects {
+                       color: true,
+                       depth: false,
+                       stencil: false,
+                   });
+        assert_eq!(Format::D32Sfloat_S8Uint.aspects(),
+                   FormatAspects {
+                       color: false,
+                       depth: true,
+                       stencil: true,
+                   });
+    }
+}
@@ -175,50 +175,49 @@ impl<Rp, A> FramebufferBuilder<Rp, A>
 
         let img_dims = attachment.dimensions();
         debug_assert_eq!(img_dims.depth(), 1);
+        let img_layers = img_dims.array_layers_with_cube();
 
         let dimensions = match self.dimensions {
             FramebufferBuilderDimensions::AutoIdentical(None) => {
-                let dims = [img_dims.width(), img_dims.height(), img_dims.array_layers()];
+                let dims = [img_dims.width(), img_dims.height(), img_layers];
                 FramebufferBuilderDimensions::AutoIdentical(Some(dims))
             },
             FramebufferBuilderDimensions::AutoIdentical(Some(current)) => {
                 if img_dims.width() != current[0] || img_dims.height() != current[1] ||
-                    img_dims.array_layers() != current[2]
+                    img_layers != current[2]
                 {
                     return Err(FramebufferCreationError::AttachmentDimensionsIncompatible {
                         expected: current,
-                        obtained: [img_dims.width(), img_dims.height(), img_dims.array_layers()],
+                        obtained: [img_dims.width(), img_dims.height(), img_layers],
                     });
                 }
 
                 FramebufferBuilderDimensions::AutoIdentical(Some(current))
             },
             FramebufferBuilderDimensions::AutoSmaller(None) => {
-                let dims = [img_dims.width(), img_dims.height(), img_dims.array_layers()];
+                let dims = [img_dims.width(), img_dims.height(), img_layers];
                 FramebufferBuilderDimensions::AutoSmaller(Some(dims))
             },
             FramebufferBuilderDimensions::AutoSmaller(Some(current)) => {
                 let new_dims = [
                     cmp::min(current[0], img_dims.width()),
                     cmp::min(current[1], img_dims.height()),
-                    cmp::min(current[2], img_dims.array_layers()),
+                    cmp::min(current[2], img_layers),
                 ];
 
                 FramebufferBuilderDimensions::AutoSmaller(Some(new_dims))
             },
             FramebufferBuilderDimensions::Specific(current) => {
                 if img_dims.width() < current[0] || img_dims.height() < current[1] ||
-                    img_dims.array_layers() < current[2]
+                    img_layers < current[2]
                 {
                     return Err(FramebufferCreationError::AttachmentDimensionsIncompatible {
                         expected: current,
-                        obtained: [img_dims.width(), img_dims.height(), img_dims.array_layers()],
+                        obtained: [img_dims.width(), img_dims.height(), img_layers],
                     });
                 }
 
-                FramebufferBuilderDimensions::Specific(
-                    [img_dims.width(), img_dims.height(), img_dims.array_layers()],
-                )
+                FramebufferBuilderDimensions::Specific(current)
             },
         };
 
@@ -308,7 +307,7 @@ impl<Rp, A> FramebufferBuilder<Rp, A>
             let mut output = mem::uninitialized();
             check_errors(vk.CreateFramebuffer(device.internal_object(),
                                               &infos,
-                                              ptr::null(),
+                                              device.allocation_callbacks().as_ptr(),
                                               &mut output))?;
             output
         };
@@ -445,7 +444,9 @@ impl<Rp, A> Drop for Framebuffer<Rp, A> {
     fn drop(&mut self) {
         unsafe {
             let vk = self.device.pointers();
-            vk.DestroyFramebuffer(self.device.internal_object(), self.framebuffer, ptr::null());
+            vk.DestroyFramebuffer(self.device.internal_object(),
+                                  self.framebuffer,
+                                  self.device.allocation_callbacks().as_ptr());
         }
     }
 }
@@ -658,6 +659,53 @@ mod tests {
             .unwrap();
     }
 
+    #[test]
+    fn layered_attachments() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        let render_pass = Arc::new(
+            single_pass_renderpass!(device.clone(),
+            attachments: {
+                color: {
+                    load: Clear,
+                    store: DontCare,
+                    format: Format::R8G8B8A8Unorm,
+                    samples: 1,
+                }
+            },
+            pass: {
+                color: [color],
+                depth_stencil: {}
+            }
+        ).unwrap(),
+        );
+
+        let array = AttachmentImage::array(device.clone(), [256, 256], 4, Format::R8G8B8A8Unorm)
+            .unwrap();
+        let fb = Framebuffer::start(render_pass.clone())
+            .add(array.clone())
+            .unwrap()
+            .build()
+            .unwrap();
+        assert_eq!(fb.dimensions(), [256, 256, 4]);
+
+        let fb = Framebuffer::with_dimensions(render_pass.clone(), [256, 256, 2])
+            .add(array)
+            .unwrap()
+            .build()
+            .unwrap();
+        assert_eq!(fb.dimensions(), [256, 256, 2]);
+
+        let cube = AttachmentImage::cubemap(device.clone(), 128, Format::R8G8B8A8Unorm).unwrap();
+        let face = AttachmentImage::layer_view(&cube, 3).unwrap();
+        let fb = Framebuffer::start(render_pass)
+            .add(face)
+            .unwrap()
+            .build()
+            .unwrap();
+        assert_eq!(fb.dimensions(), [128, 128, 1]);
+    }
+
     #[test]
     fn attachment_dims_smaller_than_specified() {
         let (device, _) = gfx_dev_and_queue!();
@@ -346,7 +346,7 @@ impl<D> RenderPass<D>
             let mut output = mem::uninitialized();
             check_errors(vk.CreateRenderPass(device.internal_object(),
                                              &infos,
-                                             ptr::null(),
+                                             device.allocation_callbacks().as_ptr(),
                                              &mut output))?;
             output
         };
@@ -484,7 +484,9 @@ impl<D> Drop for RenderPass<D> {
     fn drop(&mut self) {
         unsafe {
             let vk = self.device.pointers();
-            vk.DestroyRenderPass(self.device.internal_object(), self.render_pass, ptr::null());
+            vk.DestroyRenderPass(self.device.internal_object(),
+                                 self.render_pass,
+                                 self.device.allocation_callbacks().as_ptr());
         }
     }
 }
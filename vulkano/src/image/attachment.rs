@@ -23,6 +23,8 @@ use image::ImageDimensions;
 use image::ImageInner;
 use image::ImageLayout;
 use image::ImageUsage;
+use image::ImageView;
+use image::ImageViewCreationError;
 use image::ViewType;
 use image::sys::ImageCreationError;
 use image::sys::UnsafeImage;
@@ -66,6 +68,13 @@ use sync::Sharing;
 /// or a depth buffer that is only used once) then use a transient image as it may improve
 /// performances.
 ///
+/// # Arrays and cubemaps
+///
+/// `AttachmentImage::array` and `AttachmentImage::cubemap` create images with multiple array
+/// layers. The image itself can be used as a layered framebuffer attachment, in which case the
+/// framebuffer has as many layers as the image. In order to render to a single layer or face,
+/// use a view returned by `AttachmentImage::layer_view` instead.
+///
 // TODO: forbid reading transient images outside render passes?
 #[derive(Debug)]
 pub struct AttachmentImage<F = Format, A = StdMemoryPoolAlloc> {
@@ -99,7 +108,7 @@ impl<F> AttachmentImage<F> {
                -> Result<Arc<AttachmentImage<F>>, ImageCreationError>
         where F: FormatDesc
     {
-        AttachmentImage::new_impl(device, dimensions, format, ImageUsage::none(), 1)
+        AttachmentImage::new_impl(device, dimensions, 1, false, format, ImageUsage::none(), 1)
     }
 
     /// Same as `new`, but creates a multisampled image.
@@ -111,7 +120,13 @@ impl<F> AttachmentImage<F> {
                         -> Result<Arc<AttachmentImage<F>>, ImageCreationError>
         where F: FormatDesc
     {
-        AttachmentImage::new_impl(device, dimensions, format, ImageUsage::none(), samples)
+        AttachmentImage::new_impl(device,
+                                  dimensions,
+                                  1,
+                                  false,
+                                  format,
+                                  ImageUsage::none(),
+                                  samples)
     }
 
     /// Same as `new`, but lets you specify additional usages.
@@ -120,7 +135,7 @@ impl<F> AttachmentImage<F> {
                       -> Result<Arc<AttachmentImage<F>>, ImageCreationError>
         where F: FormatDesc
     {
-        AttachmentImage::new_impl(device, dimensions, format, usage, 1)
+        AttachmentImage::new_impl(device, dimensions, 1, false, format, usage, 1)
     }
 
     /// Same as `with_usage`, but creates a multisampled image.
@@ -133,7 +148,7 @@ impl<F> AttachmentImage<F> {
                                    -> Result<Arc<AttachmentImage<F>>, ImageCreationError>
         where F: FormatDesc
     {
-        AttachmentImage::new_impl(device, dimensions, format, usage, samples)
+        AttachmentImage::new_impl(device, dimensions, 1, false, format, usage, samples)
     }
 
     /// Same as `new`, except that the image will be transient.
@@ -150,7 +165,7 @@ impl<F> AttachmentImage<F> {
             ..ImageUsage::none()
         };
 
-        AttachmentImage::new_impl(device, dimensions, format, base_usage, 1)
+        AttachmentImage::new_impl(device, dimensions, 1, false, format, base_usage, 1)
     }
 
     /// Same as `transient`, but creates a multisampled image.
@@ -168,11 +183,63 @@ impl<F> AttachmentImage<F> {
             ..ImageUsage::none()
         };
 
-        AttachmentImage::new_impl(device, dimensions, format, base_usage, samples)
+        AttachmentImage::new_impl(device, dimensions, 1, false, format, base_usage, samples)
+    }
+
+    /// Creates a new image with the given dimensions, number of array layers and format.
+    ///
+    /// The whole image can be used as a layered framebuffer attachment, and `layer_view` returns
+    /// a view of an individual layer.
+    #[inline]
+    pub fn array(device: Arc<Device>, dimensions: [u32; 2], array_layers: u32, format: F)
+                 -> Result<Arc<AttachmentImage<F>>, ImageCreationError>
+        where F: FormatDesc
+    {
+        AttachmentImage::new_impl(device,
+                                  dimensions,
+                                  array_layers,
+                                  false,
+                                  format,
+                                  ImageUsage::none(),
+                                  1)
+    }
+
+    /// Same as `array`, but lets you specify additional usages.
+    #[inline]
+    pub fn array_with_usage(device: Arc<Device>, dimensions: [u32; 2], array_layers: u32,
+                            format: F, usage: ImageUsage)
+                            -> Result<Arc<AttachmentImage<F>>, ImageCreationError>
+        where F: FormatDesc
+    {
+        AttachmentImage::new_impl(device, dimensions, array_layers, false, format, usage, 1)
+    }
+
+    /// Creates a new cubemap-compatible image with six array layers, one for each face, whose
+    /// faces are `size` texels wide and high.
+    ///
+    /// The whole image can be used as a framebuffer attachment with six layers, and `layer_view`
+    /// returns a view of an individual face. In order to sample the image as a cubemap, create it
+    /// with `cubemap_with_usage` and the `sampled` usage, then build an `ImageView` of type
+    /// `ViewType::Cubemap`.
+    #[inline]
+    pub fn cubemap(device: Arc<Device>, size: u32, format: F)
+                   -> Result<Arc<AttachmentImage<F>>, ImageCreationError>
+        where F: FormatDesc
+    {
+        AttachmentImage::new_impl(device, [size, size], 6, true, format, ImageUsage::none(), 1)
+    }
+
+    /// Same as `cubemap`, but lets you specify additional usages.
+    #[inline]
+    pub fn cubemap_with_usage(device: Arc<Device>, size: u32, format: F, usage: ImageUsage)
+                              -> Result<Arc<AttachmentImage<F>>, ImageCreationError>
+        where F: FormatDesc
+    {
+        AttachmentImage::new_impl(device, [size, size], 6, true, format, usage, 1)
     }
 
-    fn new_impl(device: Arc<Device>, dimensions: [u32; 2], format: F, base_usage: ImageUsage,
-                samples: u32)
+    fn new_impl(device: Arc<Device>, dimensions: [u32; 2], array_layers: u32,
+                cubemap_compatible: bool, format: F, base_usage: ImageUsage, samples: u32)
                 -> Result<Arc<AttachmentImage<F>>, ImageCreationError>
         where F: FormatDesc
     {
@@ -196,8 +263,8 @@ impl<F> AttachmentImage<F> {
             let dims = ImageDimensions::Dim2d {
                 width: dimensions[0],
                 height: dimensions[1],
-                array_layers: 1,
-                cubemap_compatible: false,
+                array_layers: array_layers,
+                cubemap_compatible: cubemap_compatible,
             };
 
             UnsafeImage::new(device.clone(),
@@ -234,7 +301,14 @@ impl<F> AttachmentImage<F> {
             image.bind_memory(mem.memory(), mem.offset())?;
         }
 
-        let view = unsafe { UnsafeImageView::raw(&image, ViewType::Dim2d, 0 .. 1, 0 .. 1)? };
+        let view = unsafe {
+            let ty = if array_layers == 1 {
+                ViewType::Dim2d
+            } else {
+                ViewType::Dim2dArray
+            };
+            UnsafeImageView::raw(&image, ty, 0 .. 1, 0 .. array_layers)?
+        };
 
         Ok(Arc::new(AttachmentImage {
                         image: image,
@@ -258,6 +332,28 @@ impl<F, A> AttachmentImage<F, A> {
         let dims = self.image.dimensions();
         [dims.width(), dims.height()]
     }
+
+    /// Returns the number of array layers of the image. This is 6 for cubemaps.
+    #[inline]
+    pub fn array_layers(&self) -> u32 {
+        self.image.dimensions().array_layers()
+    }
+}
+
+impl<F, A> AttachmentImage<F, A>
+    where F: 'static + Send + Sync,
+          A: 'static + Send + Sync
+{
+    /// Builds a view of a single array layer of `image`, for example in order to render to one
+    /// face of a cubemap or to one layer of an array.
+    ///
+    /// Returns an error if `layer` is out of range.
+    #[inline]
+    pub fn layer_view(image: &Arc<AttachmentImage<F, A>>, layer: u32)
+                      -> Result<Arc<ImageView<Arc<AttachmentImage<F, A>>>>,
+                                ImageViewCreationError> {
+        ImageView::new(image.clone(), ViewType::Dim2d, 0 .. 1, layer .. layer + 1)
+    }
 }
 
 unsafe impl<F, A> ImageAccess for AttachmentImage<F, A>
@@ -340,9 +436,17 @@ unsafe impl<F, A> ImageViewAccess for AttachmentImage<F, A>
     #[inline]
     fn dimensions(&self) -> Dimensions {
         let dims = self.image.dimensions();
-        Dimensions::Dim2d {
-            width: dims.width(),
-            height: dims.height(),
+        if dims.array_layers() == 1 {
+            Dimensions::Dim2d {
+                width: dims.width(),
+                height: dims.height(),
+            }
+        } else {
+            Dimensions::Dim2dArray {
+                width: dims.width(),
+                height: dims.height(),
+                array_layers: dims.array_layers(),
+            }
         }
     }
 
@@ -381,6 +485,9 @@ unsafe impl<F, A> ImageViewAccess for AttachmentImage<F, A>
 mod tests {
     use super::AttachmentImage;
     use format::Format;
+    use image::Dimensions;
+    use image::ImageViewCreationError;
+    use image::traits::ImageViewAccess;
 
     #[test]
     fn create_regular() {
@@ -394,6 +501,44 @@ mod tests {
         let _img = AttachmentImage::transient(device, [32, 32], Format::R8G8B8A8Unorm).unwrap();
     }
 
+    #[test]
+    fn create_array() {
+        let (device, _) = gfx_dev_and_queue!();
+        let img = AttachmentImage::array(device, [32, 32], 4, Format::R8G8B8A8Unorm).unwrap();
+        assert_eq!(img.array_layers(), 4);
+        assert_eq!(ImageViewAccess::dimensions(&img).array_layers(), 4);
+    }
+
+    #[test]
+    fn create_cubemap() {
+        let (device, _) = gfx_dev_and_queue!();
+        let img = AttachmentImage::cubemap(device, 32, Format::R8G8B8A8Unorm).unwrap();
+        assert_eq!(img.array_layers(), 6);
+        assert_eq!(AttachmentImage::dimensions(&img), [32, 32]);
+    }
+
+    #[test]
+    fn layer_views() {
+        let (device, _) = gfx_dev_and_queue!();
+        let img = AttachmentImage::cubemap(device, 32, Format::R8G8B8A8Unorm).unwrap();
+
+        for face in 0 .. 6 {
+            let view = AttachmentImage::layer_view(&img, face).unwrap();
+            assert_eq!(view.dimensions(), Dimensions::Dim2d { width: 32, height: 32 });
+        }
+    }
+
+    #[test]
+    fn layer_view_out_of_range() {
+        let (device, _) = gfx_dev_and_queue!();
+        let img = AttachmentImage::array(device, [32, 32], 2, Format::R8G8B8A8Unorm).unwrap();
+
+        match AttachmentImage::layer_view(&img, 2) {
+            Err(ImageViewCreationError::ArrayLayersOutOfRange) => (),
+            _ => panic!(),
+        }
+    }
+
     #[test]
     fn d16_unorm_always_supported() {
         let (device, _) = gfx_dev_and_queue!();
@@ -8,8 +8,27 @@
 // according to those terms.
 
 use smallvec::SmallVec;
+use std::error;
+use std::fmt;
+use std::iter;
 use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
 
+use buffer::BufferAccess;
+use buffer::BufferSlice;
+use buffer::BufferUsage;
+use buffer::CpuAccessibleBuffer;
+use command_buffer::AutoCommandBuffer;
+use command_buffer::AutoCommandBufferBuilder;
+use command_buffer::BuildError;
+use command_buffer::CommandBuffer;
+use command_buffer::CommandBufferExecError;
+use command_buffer::CommandBufferExecFuture;
+use command_buffer::CopyBufferToImageError;
+use command_buffer::GenerateMipmapsError;
+use command_buffer::validity::CheckGenerateMipmapsError;
+use command_buffer::validity::check_generate_mipmaps;
 use device::Device;
 use device::Queue;
 use format::FormatDesc;
@@ -30,8 +49,11 @@ use memory::pool::MemoryPool;
 use memory::pool::MemoryPoolAlloc;
 use memory::pool::StdMemoryPool;
 use sync::AccessError;
+use sync::NowFuture;
 use sync::Sharing;
 
+use OomError;
+
 /// Image whose purpose is to be used for read-only purposes. You can write to the image once,
 /// but then you must only ever read from it. TODO: clarify because of blit operations
 // TODO: type (2D, 3D, array, etc.) as template parameter
@@ -44,8 +66,15 @@ pub struct ImmutableImage<F, A = Arc<StdMemoryPool>>
     dimensions: Dimensions,
     memory: A::Alloc,
     format: F,
+
+    // True if the `ImmutableImageInitialization` object was used by the GPU then dropped.
+    // This means that the `ImmutableImage` can be used as much as we want without any restriction.
+    initialized: AtomicBool,
 }
 
+// TODO: make this prettier
+type ImmutableImageFromBufferFuture = CommandBufferExecFuture<NowFuture, AutoCommandBuffer>;
+
 impl<F> ImmutableImage<F> {
     /// Builds a new immutable image.
     // TODO: one mipmap is probably not a great default
@@ -63,12 +92,166 @@ impl<F> ImmutableImage<F> {
     }
 
     /// Builds a new immutable image with the given number of mipmaps.
+    ///
+    /// The content of the image is undefined. Prefer `from_iter` or `from_buffer`, which upload
+    /// the initial content of the image.
     pub fn with_mipmaps<'a, I, M>(device: Arc<Device>, dimensions: Dimensions, format: F,
                                   mipmaps: M, queue_families: I)
                                   -> Result<Arc<ImmutableImage<F>>, ImageCreationError>
         where F: FormatDesc,
               I: IntoIterator<Item = QueueFamily<'a>>,
               M: Into<MipmapsCount>
+    {
+        unsafe {
+            let (image, _) = ImmutableImage::uninitialized(device,
+                                                           dimensions,
+                                                           format,
+                                                           mipmaps,
+                                                           queue_families)?;
+            image.initialized.store(true, Ordering::Relaxed);
+            Ok(image)
+        }
+    }
+
+    /// Builds an `ImmutableImage` from an iterator of texels.
+    ///
+    /// The texels are the content of the first mipmap level, tightly packed, array layer after
+    /// array layer. This function builds a memory-mapped intermediate buffer, writes the texels
+    /// to it, then calls `from_buffer`. If `mipmaps` requests more than one level, the other
+    /// levels are generated from the first one. Pass `MipmapsCount::One` if you don't need them.
+    ///
+    /// This function returns two objects: the newly-created image, and a future representing
+    /// the initial upload operation. In order to be allowed to use the `ImmutableImage`, you must
+    /// either submit your operation after this future, or execute this future and wait for it to
+    /// be finished before submitting your own operation.
+    ///
+    /// Returns an error if the iterator doesn't produce enough data for the first mipmap level.
+    pub fn from_iter<P, I, M>(iter: I, dimensions: Dimensions, mipmaps: M, format: F,
+                              queue: Arc<Queue>)
+                              -> Result<(Arc<ImmutableImage<F>>, ImmutableImageFromBufferFuture),
+                                        ImmutableImageFromBufferError>
+        where P: Send + Sync + 'static,
+              I: ExactSizeIterator<Item = P>,
+              M: Into<MipmapsCount>,
+              F: FormatDesc + 'static + Send + Sync
+    {
+        let source = CpuAccessibleBuffer::from_iter(queue.device().clone(),
+                                                    BufferUsage::transfer_source(),
+                                                    iter::once(queue.family()),
+                                                    iter)?;
+        ImmutableImage::from_buffer(source, dimensions, mipmaps, format, queue)
+    }
+
+    /// Builds an `ImmutableImage` that copies its content from a buffer.
+    ///
+    /// The buffer contains either the first mipmap level only, or every mipmap level one after
+    /// the other. Within a mipmap level the texels are tightly packed, array layer after array
+    /// layer. If the buffer only contains the first level, the other levels are generated from
+    /// it with `generate_mipmaps`, and an error is returned if the format of the image doesn't
+    /// allow it.
+    ///
+    /// The copy is recorded in a command buffer that also transitions the image to the
+    /// `ShaderReadOnlyOptimal` layout.
+    ///
+    /// This function returns two objects: the newly-created image, and a future representing
+    /// the initial upload operation. In order to be allowed to use the `ImmutableImage`, you must
+    /// either submit your operation after this future, or execute this future and wait for it to
+    /// be finished before submitting your own operation.
+    ///
+    /// Formats that have both a depth and a stencil component aren't supported, as the layout of
+    /// their texels in a buffer depends on the implementation.
+    pub fn from_buffer<B, M>(source: B, dimensions: Dimensions, mipmaps: M, format: F,
+                             queue: Arc<Queue>)
+                             -> Result<(Arc<ImmutableImage<F>>, ImmutableImageFromBufferFuture),
+                                       ImmutableImageFromBufferError>
+        where B: BufferAccess + Clone + 'static + Send + Sync,
+              M: Into<MipmapsCount>,
+              F: FormatDesc + 'static + Send + Sync
+    {
+        let raw_format = format.format();
+        if raw_format.block_size().is_none() {
+            return Err(ImmutableImageFromBufferError::DepthStencilFormat);
+        }
+
+        let (image, init) = unsafe {
+            ImmutableImage::uninitialized(source.device().clone(),
+                                          dimensions,
+                                          format,
+                                          mipmaps,
+                                          iter::once(queue.family()))?
+        };
+
+        // Extent of each mipmap level, and number of bytes that all the array layers of this
+        // level occupy when tightly packed.
+        let image_dims = image.image.dimensions();
+        let levels = (0 .. image.image.mipmap_levels())
+            .map(|level| {
+                let extent = image_dims.mipmap_dimensions(level).unwrap().width_height_depth();
+                let size = raw_format.region_size(extent).unwrap() *
+                    image_dims.array_layers() as usize;
+                (extent, size)
+            })
+            .collect::<SmallVec<[_; 16]>>();
+
+        let total_size = levels.iter().map(|&(_, size)| size).sum::<usize>();
+        let num_levels = if source.size() >= total_size {
+            levels.len()
+        } else if source.size() >= levels[0].1 {
+            1
+        } else {
+            return Err(ImmutableImageFromBufferError::BufferTooSmall {
+                           required_size: levels[0].1,
+                           actual_size: source.size(),
+                       });
+        };
+
+        let generate_mipmaps = num_levels < levels.len();
+        if generate_mipmaps {
+            check_generate_mipmaps(source.device(), &init)?;
+        }
+
+        let mut cb = AutoCommandBufferBuilder::new(source.device().clone(), queue.family())?;
+        let mut offset = 0;
+        for (level, &(extent, size)) in levels.iter().take(num_levels).enumerate() {
+            let slice = unsafe { BufferSlice::<[u8], _>::from_raw_parts(source.clone(), offset, size) };
+            cb = cb.copy_buffer_to_image_dimensions(slice,
+                                                    init.clone(),
+                                                    [0, 0, 0],
+                                                    extent,
+                                                    0,
+                                                    image_dims.array_layers(),
+                                                    level as u32)?;
+            offset += size;
+        }
+        if generate_mipmaps {
+            cb = cb.generate_mipmaps(init.clone())?;
+        }
+        let future = cb.build()?.execute(queue)?;
+        Ok((image, future))
+    }
+
+    /// Builds a new immutable image with undefined content.
+    ///
+    /// Returns two things: the image, and a special access that should be used for the initial
+    /// upload to the image.
+    ///
+    /// You will get an error if you try to use the image before using the initial upload access.
+    /// However this function doesn't check whether you actually used this initial upload to fill
+    /// the image like you're supposed to do.
+    ///
+    /// # Safety
+    ///
+    /// - The `ImmutableImageInitialization` should be used to fill the image with some initial
+    ///   data, otherwise the content is undefined.
+    ///
+    pub unsafe fn uninitialized<'a, I, M>(device: Arc<Device>, dimensions: Dimensions, format: F,
+                                          mipmaps: M, queue_families: I)
+                                          -> Result<(Arc<ImmutableImage<F>>,
+                                                     ImmutableImageInitialization<F>),
+                                                    ImageCreationError>
+        where F: FormatDesc,
+              I: IntoIterator<Item = QueueFamily<'a>>,
+              M: Into<MipmapsCount>
     {
         let usage = ImageUsage {
             transfer_source: true, // for blits
@@ -82,7 +265,7 @@ impl<F> ImmutableImage<F> {
             .map(|f| f.id())
             .collect::<SmallVec<[u32; 4]>>();
 
-        let (image, mem_reqs) = unsafe {
+        let (image, mem_reqs) = {
             let sharing = if queue_families.len() >= 2 {
                 Sharing::Concurrent(queue_families.iter().cloned())
             } else {
@@ -119,24 +302,28 @@ impl<F> ImmutableImage<F> {
                                     mem_reqs.alignment,
                                     AllocLayout::Optimal)?;
         debug_assert!((mem.offset() % mem_reqs.alignment) == 0);
-        unsafe {
-            image.bind_memory(mem.memory(), mem.offset())?;
-        }
+        image.bind_memory(mem.memory(), mem.offset())?;
 
-        let view = unsafe {
-            UnsafeImageView::raw(&image,
-                                 dimensions.to_view_type(),
-                                 0 .. image.mipmap_levels(),
-                                 0 .. image.dimensions().array_layers())?
+        let view = UnsafeImageView::raw(&image,
+                                        dimensions.to_view_type(),
+                                        0 .. image.mipmap_levels(),
+                                        0 .. image.dimensions().array_layers())?;
+
+        let image = Arc::new(ImmutableImage {
+                                 image: image,
+                                 view: view,
+                                 memory: mem,
+                                 dimensions: dimensions,
+                                 format: format,
+                                 initialized: AtomicBool::new(false),
+                             });
+
+        let init = ImmutableImageInitialization {
+            image: image.clone(),
+            used: Arc::new(AtomicBool::new(false)),
         };
 
-        Ok(Arc::new(ImmutableImage {
-                        image: image,
-                        view: view,
-                        memory: mem,
-                        dimensions: dimensions,
-                        format: format,
-                    }))
+        Ok((image, init))
     }
 }
 
@@ -188,6 +375,12 @@ unsafe impl<F, A> ImageAccess for ImmutableImage<F, A>
 
     #[inline]
     fn try_gpu_lock(&self, exclusive_access: bool, queue: &Queue) -> Result<(), AccessError> {
+        if !self.initialized.load(Ordering::Relaxed) {
+            return Err(AccessError::ImageNotInitialized {
+                           requested: ImageLayout::ShaderReadOnlyOptimal,
+                       });
+        }
+
         Ok(()) // FIXME:
     }
 
@@ -202,6 +395,75 @@ unsafe impl<F, A> ImageAccess for ImmutableImage<F, A>
     }
 }
 
+/// Access to the immutable image that can be used for the initial upload.
+pub struct ImmutableImageInitialization<F, A = Arc<StdMemoryPool>>
+    where A: MemoryPool
+{
+    image: Arc<ImmutableImage<F, A>>,
+    used: Arc<AtomicBool>,
+}
+
+// We need to implement `Clone` manually, otherwise the derive adds a `F: Clone` requirement.
+impl<F, A> Clone for ImmutableImageInitialization<F, A>
+    where A: MemoryPool
+{
+    #[inline]
+    fn clone(&self) -> ImmutableImageInitialization<F, A> {
+        ImmutableImageInitialization {
+            image: self.image.clone(),
+            used: self.used.clone(),
+        }
+    }
+}
+
+unsafe impl<F, A> ImageAccess for ImmutableImageInitialization<F, A>
+    where F: 'static + Send + Sync,
+          A: MemoryPool
+{
+    #[inline]
+    fn inner(&self) -> ImageInner {
+        ImageAccess::inner(&*self.image)
+    }
+
+    #[inline]
+    fn initial_layout_requirement(&self) -> ImageLayout {
+        ImageLayout::Undefined
+    }
+
+    #[inline]
+    fn final_layout_requirement(&self) -> ImageLayout {
+        ImageLayout::ShaderReadOnlyOptimal
+    }
+
+    #[inline]
+    fn conflict_key(&self, _: u32, _: u32, _: u32, _: u32) -> u64 {
+        self.image.image.key()
+    }
+
+    #[inline]
+    fn try_gpu_lock(&self, _: bool, _: &Queue) -> Result<(), AccessError> {
+        if self.image.initialized.load(Ordering::Relaxed) {
+            return Err(AccessError::AlreadyInUse);
+        }
+
+        if !self.used.compare_and_swap(false, true, Ordering::Relaxed) {
+            Ok(())
+        } else {
+            Err(AccessError::AlreadyInUse)
+        }
+    }
+
+    #[inline]
+    unsafe fn increase_gpu_lock(&self) {
+        debug_assert!(self.used.load(Ordering::Relaxed));
+    }
+
+    #[inline]
+    unsafe fn unlock(&self) {
+        self.image.initialized.store(true, Ordering::Relaxed);
+    }
+}
+
 unsafe impl<P, F, A> ImageContent<P> for ImmutableImage<F, A>
     where F: 'static + Send + Sync,
           A: MemoryPool
@@ -256,3 +518,312 @@ unsafe impl<F: 'static, A> ImageViewAccess for ImmutableImage<F, A>
         true
     }
 }
+
+/// Error that can happen when creating an `ImmutableImage` from a buffer or an iterator.
+#[derive(Debug, Clone)]
+pub enum ImmutableImageFromBufferError {
+    /// Error while creating the image.
+    ImageCreationError(ImageCreationError),
+    /// Not enough memory to create the intermediate buffer or the command buffer.
+    OomError(OomError),
+    /// The format has both a depth and a stencil component.
+    DepthStencilFormat,
+    /// The buffer doesn't contain enough data for the first mipmap level.
+    BufferTooSmall {
+        /// Number of bytes occupied by the first mipmap level.
+        required_size: usize,
+        /// Size of the buffer.
+        actual_size: usize,
+    },
+    /// Error while recording the copy of a mipmap level.
+    CopyBufferToImageError(CopyBufferToImageError),
+    /// The buffer only contains the first mipmap level, and the other levels can't be generated.
+    MipmapsGenerationNotSupported(CheckGenerateMipmapsError),
+    /// Error while recording the generation of the mipmaps.
+    GenerateMipmapsError(GenerateMipmapsError),
+    /// Error while building the command buffer.
+    BuildError(BuildError),
+    /// Error while executing the command buffer.
+    CommandBufferExecError(CommandBufferExecError),
+}
+
+impl error::Error for ImmutableImageFromBufferError {
+    #[inline]
+    fn description(&self) -> &str {
+        match *self {
+            ImmutableImageFromBufferError::ImageCreationError(_) => {
+                "error while creating the image"
+            },
+            ImmutableImageFromBufferError::OomError(_) => "not enough memory available",
+            ImmutableImageFromBufferError::DepthStencilFormat => {
+                "the format has both a depth and a stencil component"
+            },
+            ImmutableImageFromBufferError::BufferTooSmall { .. } => {
+                "the buffer doesn't contain enough data for the first mipmap level"
+            },
+            ImmutableImageFromBufferError::CopyBufferToImageError(_) => {
+                "error while recording the copy of a mipmap level"
+            },
+            ImmutableImageFromBufferError::MipmapsGenerationNotSupported(_) => {
+                "the buffer only contains the first mipmap level, and the other levels can't be \
+                 generated"
+            },
+            ImmutableImageFromBufferError::GenerateMipmapsError(_) => {
+                "error while recording the generation of the mipmaps"
+            },
+            ImmutableImageFromBufferError::BuildError(_) => {
+                "error while building the command buffer"
+            },
+            ImmutableImageFromBufferError::CommandBufferExecError(_) => {
+                "error while executing the command buffer"
+            },
+        }
+    }
+
+    #[inline]
+    fn cause(&self) -> Option<&error::Error> {
+        match *self {
+            ImmutableImageFromBufferError::ImageCreationError(ref err) => Some(err),
+            ImmutableImageFromBufferError::OomError(ref err) => Some(err),
+            ImmutableImageFromBufferError::CopyBufferToImageError(ref err) => Some(err),
+            ImmutableImageFromBufferError::MipmapsGenerationNotSupported(ref err) => Some(err),
+            ImmutableImageFromBufferError::GenerateMipmapsError(ref err) => Some(err),
+            ImmutableImageFromBufferError::BuildError(ref err) => Some(err),
+            ImmutableImageFromBufferError::CommandBufferExecError(ref err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ImmutableImageFromBufferError {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(fmt, "{}", error::Error::description(self))
+    }
+}
+
+impl From<ImageCreationError> for ImmutableImageFromBufferError {
+    #[inline]
+    fn from(err: ImageCreationError) -> ImmutableImageFromBufferError {
+        ImmutableImageFromBufferError::ImageCreationError(err)
+    }
+}
+
+impl From<OomError> for ImmutableImageFromBufferError {
+    #[inline]
+    fn from(err: OomError) -> ImmutableImageFromBufferError {
+        ImmutableImageFromBufferError::OomError(err)
+    }
+}
+
+impl From<CopyBufferToImageError> for ImmutableImageFromBufferError {
+    #[inline]
+    fn from(err: CopyBufferToImageError) -> ImmutableImageFromBufferError {
+        ImmutableImageFromBufferError::CopyBufferToImageError(err)
+    }
+}
+
+impl From<CheckGenerateMipmapsError> for ImmutableImageFromBufferError {
+    #[inline]
+    fn from(err: CheckGenerateMipmapsError) -> ImmutableImageFromBufferError {
+        ImmutableImageFromBufferError::MipmapsGenerationNotSupported(err)
+    }
+}
+
+impl From<GenerateMipmapsError> for ImmutableImageFromBufferError {
+    #[inline]
+    fn from(err: GenerateMipmapsError) -> ImmutableImageFromBufferError {
+        ImmutableImageFromBufferError::GenerateMipmapsError(err)
+    }
+}
+
+impl From<BuildError> for ImmutableImageFromBufferError {
+    #[inline]
+    fn from(err: BuildError) -> ImmutableImageFromBufferError {
+        ImmutableImageFromBufferError::BuildError(err)
+    }
+}
+
+impl From<CommandBufferExecError> for ImmutableImageFromBufferError {
+    #[inline]
+    fn from(err: CommandBufferExecError) -> ImmutableImageFromBufferError {
+        ImmutableImageFromBufferError::CommandBufferExecError(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ImmutableImage;
+    use super::ImmutableImageFromBufferError;
+    use buffer::BufferUsage;
+    use buffer::CpuAccessibleBuffer;
+    use command_buffer::AutoCommandBufferBuilder;
+    use command_buffer::CommandBuffer;
+    use command_buffer::download_image;
+    use format::Format;
+    use image::Dimensions;
+    use image::ImageAccess;
+    use image::MipmapsCount;
+    use sync::AccessError;
+    use sync::GpuFuture;
+
+    #[test]
+    fn from_iter_content() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let data = (0 .. 8 * 4 * 4).map(|n| n as u8).collect::<Vec<_>>();
+        let (image, future) = ImmutableImage::from_iter(data.iter().cloned(),
+                                                        Dimensions::Dim2d {
+                                                            width: 8,
+                                                            height: 4,
+                                                        },
+                                                        MipmapsCount::One,
+                                                        Format::R8G8B8A8Unorm,
+                                                        queue.clone())
+            .unwrap();
+
+        future
+            .then_signal_fence_and_flush()
+            .unwrap()
+            .wait(None)
+            .unwrap();
+
+        let content = download_image(image, queue).unwrap().wait(None).unwrap();
+        assert_eq!(content, data);
+    }
+
+    #[test]
+    fn from_buffer_all_mipmaps() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        // Levels of 4x4, 2x2 and 1x1 texels.
+        let data = (0 .. (16 + 4 + 1) * 4).map(|n| n as u8).collect::<Vec<_>>();
+        let source = CpuAccessibleBuffer::from_iter(device.clone(),
+                                                    BufferUsage::all(),
+                                                    Some(queue.family()),
+                                                    data.iter().cloned())
+            .unwrap();
+
+        let (image, future) = ImmutableImage::from_buffer(source,
+                                                          Dimensions::Dim2d {
+                                                              width: 4,
+                                                              height: 4,
+                                                          },
+                                                          MipmapsCount::Log2,
+                                                          Format::R8G8B8A8Unorm,
+                                                          queue.clone())
+            .unwrap();
+        assert_eq!(image.mipmap_levels(), 3);
+
+        future
+            .then_signal_fence_and_flush()
+            .unwrap()
+            .wait(None)
+            .unwrap();
+
+        // Read back each level and compare it with the part of the buffer it was copied from.
+        let mut offset = 0;
+        for (level, &side) in [4, 2, 1].iter().enumerate() {
+            let size = side * side * 4;
+            let dest = CpuAccessibleBuffer::from_iter(device.clone(),
+                                                      BufferUsage::all(),
+                                                      Some(queue.family()),
+                                                      (0 .. size).map(|_| 0u8))
+                .unwrap();
+
+            AutoCommandBufferBuilder::new(device.clone(), queue.family())
+                .unwrap()
+                .copy_image_to_buffer_dimensions(image.clone(),
+                                                 dest.clone(),
+                                                 [0, 0, 0],
+                                                 [side as u32, side as u32, 1],
+                                                 0,
+                                                 1,
+                                                 level as u32)
+                .unwrap()
+                .build()
+                .unwrap()
+                .execute(queue.clone())
+                .unwrap()
+                .then_signal_fence_and_flush()
+                .unwrap()
+                .wait(None)
+                .unwrap();
+
+            assert_eq!(&*dest.read().unwrap(), &data[offset .. offset + size]);
+            offset += size;
+        }
+    }
+
+    #[test]
+    fn from_buffer_too_small() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let source = CpuAccessibleBuffer::from_iter(device.clone(),
+                                                    BufferUsage::all(),
+                                                    Some(queue.family()),
+                                                    (0 .. 4 * 4).map(|_| 0u8))
+            .unwrap();
+
+        let result = ImmutableImage::from_buffer(source,
+                                                 Dimensions::Dim2d {
+                                                     width: 4,
+                                                     height: 4,
+                                                 },
+                                                 MipmapsCount::One,
+                                                 Format::R8G8B8A8Unorm,
+                                                 queue.clone());
+        match result {
+            Err(ImmutableImageFromBufferError::BufferTooSmall {
+                    required_size: 64,
+                    actual_size: 16,
+                }) => (),
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn from_iter_generate_mipmaps() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let data = (0 .. 16 * 16 * 4).map(|n| n as u8).collect::<Vec<_>>();
+        let (image, future) = ImmutableImage::from_iter(data.into_iter(),
+                                                        Dimensions::Dim2d {
+                                                            width: 16,
+                                                            height: 16,
+                                                        },
+                                                        MipmapsCount::Log2,
+                                                        Format::R8G8B8A8Unorm,
+                                                        queue.clone())
+            .unwrap();
+        assert_eq!(image.mipmap_levels(), 5);
+
+        future
+            .then_signal_fence_and_flush()
+            .unwrap()
+            .wait(None)
+            .unwrap();
+    }
+
+    #[test]
+    fn not_initialized() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let (image, _init) = unsafe {
+            ImmutableImage::uninitialized(device,
+                                          Dimensions::Dim2d {
+                                              width: 4,
+                                              height: 4,
+                                          },
+                                          Format::R8G8B8A8Unorm,
+                                          MipmapsCount::One,
+                                          Some(queue.family()))
+                .unwrap()
+        };
+
+        match image.try_gpu_lock(false, &queue) {
+            Err(AccessError::ImageNotInitialized { .. }) => (),
+            _ => panic!(),
+        }
+    }
+}
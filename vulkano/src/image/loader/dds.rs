@@ -0,0 +1,376 @@
+// Copyright (c) 2017 The vulkano developers
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>,
+// at your option. All files in the project carrying such
+// notice may not be copied, modified, or distributed except
+// according to those terms.
+
+//! Parsing of DDS files.
+//!
+//! See https://learn.microsoft.com/en-us/windows/win32/direct3ddds/dx-graphics-dds-pguide.
+
+use format::Format;
+
+use super::LoadError;
+use super::Reader;
+use super::Texture;
+use super::texture_dimensions;
+
+pub(super) const DDS_MAGIC: [u8; 4] = [0x44, 0x44, 0x53, 0x20];
+
+const DDSD_MIPMAPCOUNT: u32 = 0x20000;
+const DDPF_ALPHAPIXELS: u32 = 0x1;
+const DDPF_FOURCC: u32 = 0x4;
+const DDPF_RGB: u32 = 0x40;
+const DDPF_LUMINANCE: u32 = 0x20000;
+const DDSCAPS2_CUBEMAP: u32 = 0x200;
+const DDSCAPS2_CUBEMAP_ALLFACES: u32 = 0xfc00;
+const DDSCAPS2_VOLUME: u32 = 0x200000;
+const D3D10_RESOURCE_DIMENSION_TEXTURE1D: u32 = 2;
+const D3D10_RESOURCE_DIMENSION_TEXTURE2D: u32 = 3;
+const D3D10_RESOURCE_DIMENSION_TEXTURE3D: u32 = 4;
+const D3D10_RESOURCE_MISC_TEXTURECUBE: u32 = 0x4;
+
+/// Parses a DDS file.
+///
+/// The format of the texture is determined from the `DDS_HEADER_DXT10` header if there is one,
+/// and otherwise from the FourCC code or the bit masks of the pixel format.
+pub fn parse_dds(bytes: &[u8]) -> Result<Texture, LoadError> {
+    if !bytes.starts_with(&DDS_MAGIC) {
+        return Err(LoadError::UnrecognizedContainer);
+    }
+
+    let reader = Reader::new(bytes);
+
+    if reader.u32(4)? != 124 {
+        return Err(LoadError::InvalidHeader);
+    }
+
+    let flags = reader.u32(8)?;
+    let height = reader.u32(12)?;
+    let width = reader.u32(16)?;
+    let depth = reader.u32(24)?;
+    let mipmap_levels = if flags & DDSD_MIPMAPCOUNT != 0 {
+        ::std::cmp::max(reader.u32(28)?, 1)
+    } else {
+        1
+    };
+    let pixel_format_flags = reader.u32(80)?;
+    let four_cc = reader.bytes(84, 4)?;
+    let caps2 = reader.u32(112)?;
+
+    let (format, dimensions, data_offset) = if pixel_format_flags & DDPF_FOURCC != 0 &&
+        four_cc == b"DX10"
+    {
+        let format = dxgi_format_to_format(reader.u32(128)?)
+            .ok_or(LoadError::UnsupportedFormat)?;
+        let misc_flag = reader.u32(136)?;
+        let array_size = reader.u32(140)?;
+
+        let layers = if array_size >= 2 { array_size } else { 0 };
+        let faces = if misc_flag & D3D10_RESOURCE_MISC_TEXTURECUBE != 0 {
+            6
+        } else {
+            1
+        };
+
+        let dimensions = match reader.u32(132)? {
+            D3D10_RESOURCE_DIMENSION_TEXTURE1D => texture_dimensions(width, 0, 0, layers, faces)?,
+            D3D10_RESOURCE_DIMENSION_TEXTURE2D => {
+                texture_dimensions(width, height, 0, layers, faces)?
+            },
+            D3D10_RESOURCE_DIMENSION_TEXTURE3D => {
+                texture_dimensions(width, height, depth, layers, faces)?
+            },
+            _ => return Err(LoadError::InvalidHeader),
+        };
+
+        (format, dimensions, 148)
+
+    } else {
+        let format = legacy_format(&reader)?.ok_or(LoadError::UnsupportedFormat)?;
+
+        let dimensions = if caps2 & DDSCAPS2_CUBEMAP != 0 {
+            // Cubemaps with only some of the faces can't be represented in Vulkan.
+            if caps2 & DDSCAPS2_CUBEMAP_ALLFACES != DDSCAPS2_CUBEMAP_ALLFACES {
+                return Err(LoadError::InvalidHeader);
+            }
+            texture_dimensions(width, height, 0, 0, 6)?
+        } else if caps2 & DDSCAPS2_VOLUME != 0 {
+            texture_dimensions(width, height, depth, 0, 1)?
+        } else {
+            texture_dimensions(width, height, 0, 0, 1)?
+        };
+
+        (format, dimensions, 128)
+    };
+
+    let mut texture = Texture::new(format, dimensions, mipmap_levels)?;
+
+    // The file contains the complete mipmap chain of the first layer (or face), then the complete
+    // mipmap chain of the second layer, and so on. The layers are regrouped by level.
+    // The sizes are checked against the remaining input before anything is allocated.
+    let layers = dimensions.array_layers_with_cube() as usize;
+    let level_sizes = (0 .. mipmap_levels)
+        .map(|level| texture.level_size(level))
+        .collect::<Result<Vec<_>, _>>()?;
+    let total_size = level_sizes
+        .iter()
+        .fold(Some(0usize), |total, &size| total.and_then(|t| t.checked_add(size)))
+        .ok_or(LoadError::InvalidHeader)?;
+    if total_size > bytes.len().saturating_sub(data_offset) {
+        return Err(LoadError::Truncated);
+    }
+
+    let mut levels = level_sizes
+        .iter()
+        .map(|&size| Vec::with_capacity(size))
+        .collect::<Vec<_>>();
+    let mut offset = data_offset;
+    for _ in 0 .. layers {
+        for (data, &level_size) in levels.iter_mut().zip(level_sizes.iter()) {
+            let size = level_size / layers;
+            data.extend_from_slice(reader.bytes(offset, size)?);
+            offset += size;
+        }
+    }
+
+    for data in levels {
+        texture.push_level(&data);
+    }
+
+    Ok(texture)
+}
+
+// Determines the format of a DDS file without a `DDS_HEADER_DXT10` header.
+fn legacy_format(reader: &Reader) -> Result<Option<Format>, LoadError> {
+    let flags = reader.u32(80)?;
+    let four_cc = reader.bytes(84, 4)?;
+    let bit_count = reader.u32(88)?;
+    let r_mask = reader.u32(92)?;
+    let g_mask = reader.u32(96)?;
+    let b_mask = reader.u32(100)?;
+    let a_mask = if flags & DDPF_ALPHAPIXELS != 0 {
+        reader.u32(104)?
+    } else {
+        0
+    };
+
+    if flags & DDPF_FOURCC != 0 {
+        return Ok(match four_cc {
+                      b"DXT1" => Some(Format::BC1_RGBAUnormBlock),
+                      b"DXT2" | b"DXT3" => Some(Format::BC2UnormBlock),
+                      b"DXT4" | b"DXT5" => Some(Format::BC3UnormBlock),
+                      b"ATI1" | b"BC4U" => Some(Format::BC4UnormBlock),
+                      b"BC4S" => Some(Format::BC4SnormBlock),
+                      b"ATI2" | b"BC5U" => Some(Format::BC5UnormBlock),
+                      b"BC5S" => Some(Format::BC5SnormBlock),
+                      // Some `D3DFORMAT` values are stored directly in the FourCC field.
+                      _ => {
+                          match reader.u32(84)? {
+                              36 => Some(Format::R16G16B16A16Unorm),
+                              110 => Some(Format::R16G16B16A16Snorm),
+                              111 => Some(Format::R16Sfloat),
+                              112 => Some(Format::R16G16Sfloat),
+                              113 => Some(Format::R16G16B16A16Sfloat),
+                              114 => Some(Format::R32Sfloat),
+                              115 => Some(Format::R32G32Sfloat),
+                              116 => Some(Format::R32G32B32A32Sfloat),
+                              _ => None,
+                          }
+                      },
+                  });
+    }
+
+    if flags & DDPF_RGB != 0 {
+        return Ok(match (bit_count, r_mask, g_mask, b_mask, a_mask) {
+                      (32, 0xff, 0xff00, 0xff0000, 0xff000000) => Some(Format::R8G8B8A8Unorm),
+                      (32, 0xff0000, 0xff00, 0xff, 0xff000000) => Some(Format::B8G8R8A8Unorm),
+                      (32, 0x3ff, 0xffc00, 0x3ff00000, 0xc0000000) => {
+                          Some(Format::A2B10G10R10UnormPack32)
+                      },
+                      (32, 0xffff, 0xffff0000, 0, 0) => Some(Format::R16G16Unorm),
+                      (24, 0xff, 0xff00, 0xff0000, 0) => Some(Format::R8G8B8Unorm),
+                      (24, 0xff0000, 0xff00, 0xff, 0) => Some(Format::B8G8R8Unorm),
+                      (16, 0xf800, 0x7e0, 0x1f, 0) => Some(Format::R5G6B5UnormPack16),
+                      (16, 0x7c00, 0x3e0, 0x1f, 0x8000) => Some(Format::A1R5G5B5UnormPack16),
+                      _ => None,
+                  });
+    }
+
+    if flags & DDPF_LUMINANCE != 0 {
+        return Ok(match (bit_count, r_mask, a_mask) {
+                      (8, 0xff, 0) => Some(Format::R8Unorm),
+                      (16, 0xffff, 0) => Some(Format::R16Unorm),
+                      _ => None,
+                  });
+    }
+
+    Ok(None)
+}
+
+// Returns the format corresponding to a `DXGI_FORMAT` value.
+fn dxgi_format_to_format(format: u32) -> Option<Format> {
+    Some(match format {
+             2 => Format::R32G32B32A32Sfloat,
+             3 => Format::R32G32B32A32Uint,
+             4 => Format::R32G32B32A32Sint,
+             6 => Format::R32G32B32Sfloat,
+             7 => Format::R32G32B32Uint,
+             8 => Format::R32G32B32Sint,
+             10 => Format::R16G16B16A16Sfloat,
+             11 => Format::R16G16B16A16Unorm,
+             12 => Format::R16G16B16A16Uint,
+             13 => Format::R16G16B16A16Snorm,
+             14 => Format::R16G16B16A16Sint,
+             16 => Format::R32G32Sfloat,
+             17 => Format::R32G32Uint,
+             18 => Format::R32G32Sint,
+             24 => Format::A2B10G10R10UnormPack32,
+             25 => Format::A2B10G10R10UintPack32,
+             26 => Format::B10G11R11UfloatPack32,
+             28 => Format::R8G8B8A8Unorm,
+             29 => Format::R8G8B8A8Srgb,
+             30 => Format::R8G8B8A8Uint,
+             31 => Format::R8G8B8A8Snorm,
+             32 => Format::R8G8B8A8Sint,
+             34 => Format::R16G16Sfloat,
+             35 => Format::R16G16Unorm,
+             36 => Format::R16G16Uint,
+             37 => Format::R16G16Snorm,
+             38 => Format::R16G16Sint,
+             41 => Format::R32Sfloat,
+             42 => Format::R32Uint,
+             43 => Format::R32Sint,
+             49 => Format::R8G8Unorm,
+             50 => Format::R8G8Uint,
+             51 => Format::R8G8Snorm,
+             52 => Format::R8G8Sint,
+             54 => Format::R16Sfloat,
+             56 => Format::R16Unorm,
+             57 => Format::R16Uint,
+             58 => Format::R16Snorm,
+             59 => Format::R16Sint,
+             61 => Format::R8Unorm,
+             62 => Format::R8Uint,
+             63 => Format::R8Snorm,
+             64 => Format::R8Sint,
+             67 => Format::E5B9G9R9UfloatPack32,
+             71 => Format::BC1_RGBAUnormBlock,
+             72 => Format::BC1_RGBASrgbBlock,
+             74 => Format::BC2UnormBlock,
+             75 => Format::BC2SrgbBlock,
+             77 => Format::BC3UnormBlock,
+             78 => Format::BC3SrgbBlock,
+             80 => Format::BC4UnormBlock,
+             81 => Format::BC4SnormBlock,
+             83 => Format::BC5UnormBlock,
+             84 => Format::BC5SnormBlock,
+             85 => Format::R5G6B5UnormPack16,
+             86 => Format::A1R5G5B5UnormPack16,
+             87 => Format::B8G8R8A8Unorm,
+             91 => Format::B8G8R8A8Srgb,
+             95 => Format::BC6HUfloatBlock,
+             96 => Format::BC6HSfloatBlock,
+             98 => Format::BC7UnormBlock,
+             99 => Format::BC7SrgbBlock,
+             _ => return None,
+         })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DDS_MAGIC;
+    use super::parse_dds;
+    use format::Format;
+    use image::Dimensions;
+    use image::loader::LoadError;
+
+    // Builds the magic and the `DDS_HEADER` of a file.
+    fn header(width: u32, height: u32, mipmap_levels: u32, pixel_format: [u32; 8], caps2: u32)
+              -> Vec<u8> {
+        let mut fields = vec![0u32; 31];
+        fields[0] = 124;
+        fields[1] = 0x1007 | 0x20000;
+        fields[2] = height;
+        fields[3] = width;
+        fields[6] = mipmap_levels;
+        fields[18 .. 26].copy_from_slice(&pixel_format);
+        fields[27] = caps2;
+
+        let mut out = DDS_MAGIC.to_vec();
+        for field in fields {
+            out.extend_from_slice(&[field as u8, (field >> 8) as u8, (field >> 16) as u8,
+                                    (field >> 24) as u8]);
+        }
+        out
+    }
+
+    #[test]
+    fn layers_regrouped_by_level() {
+        // A 2x2 RGBA cubemap with two levels. Each face holds 4 texels then 1 texel.
+        let pixel_format = [32, 0x41, 0, 32, 0xff, 0xff00, 0xff0000, 0xff000000];
+        let mut file = header(2, 2, 2, pixel_format, 0x200 | 0xfc00);
+        for face in 0 .. 6u8 {
+            file.extend_from_slice(&[face; 16]);
+            file.extend_from_slice(&[face + 100; 4]);
+        }
+
+        let texture = parse_dds(&file).unwrap();
+        assert_eq!(texture.format(), Format::R8G8B8A8Unorm);
+        assert_eq!(texture.dimensions(), Dimensions::Cubemap { size: 2 });
+
+        let level0 = texture.level_data(0).unwrap();
+        assert_eq!(level0.len(), 6 * 16);
+        assert_eq!(&level0[16 .. 32], &[1; 16][..]);
+        assert_eq!(texture.level_data(1).unwrap(), &[100, 100, 100, 100, 101, 101, 101, 101,
+                                                     102, 102, 102, 102, 103, 103, 103, 103,
+                                                     104, 104, 104, 104, 105, 105, 105, 105][..]);
+    }
+
+    #[test]
+    fn dx10_array() {
+        let pixel_format = [32, 0x4, 0x30315844, 0, 0, 0, 0, 0];
+        let mut file = header(8, 8, 1, pixel_format, 0);
+        for &field in &[98, 3, 0, 3, 0] {
+            file.extend_from_slice(&[field as u8, (field >> 8) as u8, 0, 0]);
+        }
+        file.extend_from_slice(&[0; 3 * 4 * 16]);
+
+        let texture = parse_dds(&file).unwrap();
+        assert_eq!(texture.format(), Format::BC7UnormBlock);
+        assert_eq!(texture.dimensions(),
+                   Dimensions::Dim2dArray {
+                       width: 8,
+                       height: 8,
+                       array_layers: 3,
+                   });
+    }
+
+    #[test]
+    fn truncated() {
+        let pixel_format = [32, 0x4, 0, 0, 0, 0, 0, 0];
+        let mut file = header(8, 8, 1, pixel_format, 0);
+        file[84 .. 88].copy_from_slice(b"DXT1");
+        file.extend_from_slice(&[0; 16]);
+
+        match parse_dds(&file) {
+            Err(LoadError::Truncated) => (),
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn huge_level_not_allocated() {
+        // The header announces a 65536x65536 RGBA texture, but the file has no content.
+        let pixel_format = [32, 0x41, 0, 32, 0xff, 0xff00, 0xff0000, 0xff000000];
+        let file = header(65536, 65536, 1, pixel_format, 0);
+
+        match parse_dds(&file) {
+            Err(LoadError::Truncated) => (),
+            _ => panic!(),
+        }
+    }
+}
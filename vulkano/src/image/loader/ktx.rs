@@ -0,0 +1,385 @@
+// Copyright (c) 2017 The vulkano developers
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>,
+// at your option. All files in the project carrying such
+// notice may not be copied, modified, or distributed except
+// according to those terms.
+
+//! Parsing of KTX1 and KTX2 files.
+//!
+//! See https://registry.khronos.org/KTX/specs/1.0/ktxspec.v1.html and
+//! https://registry.khronos.org/KTX/specs/2.0/ktxspec.v2.html.
+
+use format::Format;
+
+use super::LoadError;
+use super::Reader;
+use super::Texture;
+use super::max_mipmap_levels;
+use super::texture_dimensions;
+
+pub(super) const KTX1_IDENTIFIER: [u8; 12] = [0xab, 0x4b, 0x54, 0x58, 0x20, 0x31, 0x31, 0xbb,
+                                              0x0d, 0x0a, 0x1a, 0x0a];
+pub(super) const KTX2_IDENTIFIER: [u8; 12] = [0xab, 0x4b, 0x54, 0x58, 0x20, 0x32, 0x30, 0xbb,
+                                              0x0d, 0x0a, 0x1a, 0x0a];
+
+// Value of the `endianness` field of a KTX1 file written with the same endianness as ours.
+const KTX1_ENDIANNESS: u32 = 0x04030201;
+
+/// Parses a KTX1 file.
+///
+/// The format of the texture is determined from the `glInternalFormat` field, which must be a
+/// sized internal format or a compressed format.
+pub fn parse_ktx(bytes: &[u8]) -> Result<Texture, LoadError> {
+    if !bytes.starts_with(&KTX1_IDENTIFIER) {
+        return Err(LoadError::UnrecognizedContainer);
+    }
+
+    let reader = Reader::new(bytes);
+
+    // All the fields of the header are 32 bits integers written with the endianness of the
+    // machine that produced the file.
+    let swap = match reader.u32(12)? {
+        KTX1_ENDIANNESS => false,
+        e if e.swap_bytes() == KTX1_ENDIANNESS => true,
+        _ => return Err(LoadError::InvalidHeader),
+    };
+    let field = |offset| -> Result<u32, LoadError> {
+        let value = reader.u32(offset)?;
+        Ok(if swap { value.swap_bytes() } else { value })
+    };
+
+    let gl_type_size = field(20)?;
+    let gl_internal_format = field(28)?;
+    let width = field(36)?;
+    let height = field(40)?;
+    let depth = field(44)?;
+    let array_elements = field(48)?;
+    let faces = field(52)?;
+    let mipmap_levels = field(56)?;
+    let key_value_bytes = field(60)?;
+
+    let format = gl_internal_format_to_format(gl_internal_format)
+        .ok_or(LoadError::UnsupportedFormat)?;
+    let dimensions = texture_dimensions(width, height, depth, array_elements, faces)?;
+
+    // A value of 0 means that the loader must generate the mipmaps.
+    let (mipmap_levels, provided_levels) = if mipmap_levels == 0 {
+        (max_mipmap_levels(dimensions), 1)
+    } else {
+        (mipmap_levels, mipmap_levels)
+    };
+    let mut texture = Texture::new(format, dimensions, mipmap_levels)?;
+
+    let block_extent = format.block_extent();
+    let block_size = format.block_size().unwrap();
+    let non_array_cubemap = faces == 6 && array_elements == 0;
+    let mut offset = 64 + key_value_bytes as usize;
+
+    for level in 0 .. provided_levels {
+        // Skip `imageSize`. The size of the level is known from the format and the dimensions.
+        offset += 4;
+
+        let extent = texture.level_extent(level);
+        let row_size = ((extent[0] + block_extent[0] - 1) / block_extent[0]) as usize * block_size;
+        let rows = ((extent[1] + block_extent[1] - 1) / block_extent[1]) as usize;
+        let images = dimensions.array_layers_with_cube() as usize * extent[2] as usize;
+
+        // Rows are padded to a multiple of 4 bytes, and so are faces of non-array cubemaps and
+        // levels. The padding can only make the level larger in the file.
+        let level_size = texture.level_size(level)?;
+        if level_size > bytes.len().saturating_sub(offset) {
+            return Err(LoadError::Truncated);
+        }
+        let mut data = Vec::with_capacity(level_size);
+        for image in 0 .. images {
+            for _ in 0 .. rows {
+                data.extend_from_slice(reader.bytes(offset, row_size)?);
+                offset += align4(row_size);
+            }
+
+            if non_array_cubemap && (image + 1) % extent[2] as usize == 0 {
+                offset = align4(offset);
+            }
+        }
+        offset = align4(offset);
+
+        if swap && gl_type_size >= 2 {
+            for element in data.chunks_mut(gl_type_size as usize) {
+                element.reverse();
+            }
+        }
+
+        texture.push_level(&data);
+    }
+
+    Ok(texture)
+}
+
+/// Parses a KTX2 file.
+///
+/// The format of the texture is determined from the `vkFormat` field.
+pub fn parse_ktx2(bytes: &[u8]) -> Result<Texture, LoadError> {
+    if !bytes.starts_with(&KTX2_IDENTIFIER) {
+        return Err(LoadError::UnrecognizedContainer);
+    }
+
+    let reader = Reader::new(bytes);
+
+    let vk_format = reader.u32(12)?;
+    let width = reader.u32(20)?;
+    let height = reader.u32(24)?;
+    let depth = reader.u32(28)?;
+    let layers = reader.u32(32)?;
+    let faces = reader.u32(36)?;
+    let mipmap_levels = reader.u32(40)?;
+    let supercompression_scheme = reader.u32(44)?;
+
+    if supercompression_scheme != 0 {
+        return Err(LoadError::UnsupportedSupercompression);
+    }
+
+    // A `vkFormat` of 0 means that the format is described by the data format descriptor, which
+    // isn't supported.
+    let format = Format::from_num(vk_format).ok_or(LoadError::UnsupportedFormat)?;
+    let dimensions = texture_dimensions(width, height, depth, layers, faces)?;
+
+    // A value of 0 means that the loader must generate the mipmaps.
+    let (mipmap_levels, provided_levels) = if mipmap_levels == 0 {
+        (max_mipmap_levels(dimensions), 1)
+    } else {
+        (mipmap_levels, mipmap_levels)
+    };
+    let mut texture = Texture::new(format, dimensions, mipmap_levels)?;
+
+    // The level index starts after the header and contains `byteOffset`, `byteLength` and
+    // `uncompressedByteLength` for each level, starting with the first level.
+    for level in 0 .. provided_levels {
+        let index = 80 + 24 * level as usize;
+        let offset = reader.u64(index)?;
+        let len = reader.u64(index + 8)?;
+
+        if len != texture.level_size(level)? as u64 {
+            return Err(LoadError::InvalidHeader);
+        }
+        if offset > usize::max_value() as u64 {
+            return Err(LoadError::Truncated);
+        }
+
+        let data = reader.bytes(offset as usize, len as usize)?;
+        texture.push_level(data);
+    }
+
+    Ok(texture)
+}
+
+#[inline]
+fn align4(value: usize) -> usize {
+    (value + 3) & !3
+}
+
+// Returns the format corresponding to the `glInternalFormat` field of a KTX1 file.
+fn gl_internal_format_to_format(format: u32) -> Option<Format> {
+    Some(match format {
+             0x8229 => Format::R8Unorm,             // GL_R8
+             0x8f94 => Format::R8Snorm,             // GL_R8_SNORM
+             0x8232 => Format::R8Uint,              // GL_R8UI
+             0x8231 => Format::R8Sint,              // GL_R8I
+             0x822b => Format::R8G8Unorm,           // GL_RG8
+             0x8f95 => Format::R8G8Snorm,           // GL_RG8_SNORM
+             0x8238 => Format::R8G8Uint,            // GL_RG8UI
+             0x8237 => Format::R8G8Sint,            // GL_RG8I
+             0x8051 => Format::R8G8B8Unorm,         // GL_RGB8
+             0x8c41 => Format::R8G8B8Srgb,          // GL_SRGB8
+             0x8f96 => Format::R8G8B8Snorm,         // GL_RGB8_SNORM
+             0x8d7d => Format::R8G8B8Uint,          // GL_RGB8UI
+             0x8d8f => Format::R8G8B8Sint,          // GL_RGB8I
+             0x8058 => Format::R8G8B8A8Unorm,       // GL_RGBA8
+             0x8c43 => Format::R8G8B8A8Srgb,        // GL_SRGB8_ALPHA8
+             0x8f97 => Format::R8G8B8A8Snorm,       // GL_RGBA8_SNORM
+             0x8d7c => Format::R8G8B8A8Uint,        // GL_RGBA8UI
+             0x8d8e => Format::R8G8B8A8Sint,        // GL_RGBA8I
+             0x822a => Format::R16Unorm,            // GL_R16
+             0x8f98 => Format::R16Snorm,            // GL_R16_SNORM
+             0x8234 => Format::R16Uint,             // GL_R16UI
+             0x8233 => Format::R16Sint,             // GL_R16I
+             0x822d => Format::R16Sfloat,           // GL_R16F
+             0x822c => Format::R16G16Unorm,         // GL_RG16
+             0x8f99 => Format::R16G16Snorm,         // GL_RG16_SNORM
+             0x823a => Format::R16G16Uint,          // GL_RG16UI
+             0x8239 => Format::R16G16Sint,          // GL_RG16I
+             0x822f => Format::R16G16Sfloat,        // GL_RG16F
+             0x881b => Format::R16G16B16Sfloat,     // GL_RGB16F
+             0x805b => Format::R16G16B16A16Unorm,   // GL_RGBA16
+             0x8f9b => Format::R16G16B16A16Snorm,   // GL_RGBA16_SNORM
+             0x8d76 => Format::R16G16B16A16Uint,    // GL_RGBA16UI
+             0x8d88 => Format::R16G16B16A16Sint,    // GL_RGBA16I
+             0x881a => Format::R16G16B16A16Sfloat,  // GL_RGBA16F
+             0x8236 => Format::R32Uint,             // GL_R32UI
+             0x8235 => Format::R32Sint,             // GL_R32I
+             0x822e => Format::R32Sfloat,           // GL_R32F
+             0x823c => Format::R32G32Uint,          // GL_RG32UI
+             0x823b => Format::R32G32Sint,          // GL_RG32I
+             0x8230 => Format::R32G32Sfloat,        // GL_RG32F
+             0x8d71 => Format::R32G32B32Uint,       // GL_RGB32UI
+             0x8d83 => Format::R32G32B32Sint,       // GL_RGB32I
+             0x8815 => Format::R32G32B32Sfloat,     // GL_RGB32F
+             0x8d70 => Format::R32G32B32A32Uint,    // GL_RGBA32UI
+             0x8d82 => Format::R32G32B32A32Sint,    // GL_RGBA32I
+             0x8814 => Format::R32G32B32A32Sfloat,  // GL_RGBA32F
+             0x8d62 => Format::R5G6B5UnormPack16,   // GL_RGB565
+             0x8056 => Format::R4G4B4A4UnormPack16, // GL_RGBA4
+             0x8057 => Format::R5G5B5A1UnormPack16, // GL_RGB5_A1
+             0x8059 => Format::A2B10G10R10UnormPack32, // GL_RGB10_A2
+             0x906f => Format::A2B10G10R10UintPack32, // GL_RGB10_A2UI
+             0x8c3a => Format::B10G11R11UfloatPack32, // GL_R11F_G11F_B10F
+             0x8c3d => Format::E5B9G9R9UfloatPack32, // GL_RGB9_E5
+             0x83f0 => Format::BC1_RGBUnormBlock,   // GL_COMPRESSED_RGB_S3TC_DXT1_EXT
+             0x83f1 => Format::BC1_RGBAUnormBlock,  // GL_COMPRESSED_RGBA_S3TC_DXT1_EXT
+             0x83f2 => Format::BC2UnormBlock,       // GL_COMPRESSED_RGBA_S3TC_DXT3_EXT
+             0x83f3 => Format::BC3UnormBlock,       // GL_COMPRESSED_RGBA_S3TC_DXT5_EXT
+             0x8c4c => Format::BC1_RGBSrgbBlock,    // GL_COMPRESSED_SRGB_S3TC_DXT1_EXT
+             0x8c4d => Format::BC1_RGBASrgbBlock,   // GL_COMPRESSED_SRGB_ALPHA_S3TC_DXT1_EXT
+             0x8c4e => Format::BC2SrgbBlock,        // GL_COMPRESSED_SRGB_ALPHA_S3TC_DXT3_EXT
+             0x8c4f => Format::BC3SrgbBlock,        // GL_COMPRESSED_SRGB_ALPHA_S3TC_DXT5_EXT
+             0x8dbb => Format::BC4UnormBlock,       // GL_COMPRESSED_RED_RGTC1
+             0x8dbc => Format::BC4SnormBlock,       // GL_COMPRESSED_SIGNED_RED_RGTC1
+             0x8dbd => Format::BC5UnormBlock,       // GL_COMPRESSED_RG_RGTC2
+             0x8dbe => Format::BC5SnormBlock,       // GL_COMPRESSED_SIGNED_RG_RGTC2
+             0x8e8c => Format::BC7UnormBlock,       // GL_COMPRESSED_RGBA_BPTC_UNORM
+             0x8e8d => Format::BC7SrgbBlock,        // GL_COMPRESSED_SRGB_ALPHA_BPTC_UNORM
+             0x8e8e => Format::BC6HSfloatBlock,     // GL_COMPRESSED_RGB_BPTC_SIGNED_FLOAT
+             0x8e8f => Format::BC6HUfloatBlock,     // GL_COMPRESSED_RGB_BPTC_UNSIGNED_FLOAT
+             0x8d64 => Format::ETC2_R8G8B8UnormBlock, // GL_ETC1_RGB8_OES
+             0x9274 => Format::ETC2_R8G8B8UnormBlock, // GL_COMPRESSED_RGB8_ETC2
+             0x9275 => Format::ETC2_R8G8B8SrgbBlock, // GL_COMPRESSED_SRGB8_ETC2
+             // GL_COMPRESSED_RGB8_PUNCHTHROUGH_ALPHA1_ETC2 and its sRGB version.
+             0x9276 => Format::ETC2_R8G8B8A1UnormBlock,
+             0x9277 => Format::ETC2_R8G8B8A1SrgbBlock,
+             0x9278 => Format::ETC2_R8G8B8A8UnormBlock, // GL_COMPRESSED_RGBA8_ETC2_EAC
+             0x9279 => Format::ETC2_R8G8B8A8SrgbBlock, // GL_COMPRESSED_SRGB8_ALPHA8_ETC2_EAC
+             0x9270 => Format::EAC_R11UnormBlock,   // GL_COMPRESSED_R11_EAC
+             0x9271 => Format::EAC_R11SnormBlock,   // GL_COMPRESSED_SIGNED_R11_EAC
+             0x9272 => Format::EAC_R11G11UnormBlock, // GL_COMPRESSED_RG11_EAC
+             0x9273 => Format::EAC_R11G11SnormBlock, // GL_COMPRESSED_SIGNED_RG11_EAC
+             // GL_COMPRESSED_RGBA_ASTC_*_KHR and GL_COMPRESSED_SRGB8_ALPHA8_ASTC_*_KHR.
+             0x93b0 => Format::ASTC_4x4UnormBlock,
+             0x93b1 => Format::ASTC_5x4UnormBlock,
+             0x93b2 => Format::ASTC_5x5UnormBlock,
+             0x93b3 => Format::ASTC_6x5UnormBlock,
+             0x93b4 => Format::ASTC_6x6UnormBlock,
+             0x93b5 => Format::ASTC_8x5UnormBlock,
+             0x93b6 => Format::ASTC_8x6UnormBlock,
+             0x93b7 => Format::ASTC_8x8UnormBlock,
+             0x93b8 => Format::ASTC_10x5UnormBlock,
+             0x93b9 => Format::ASTC_10x6UnormBlock,
+             0x93ba => Format::ASTC_10x8UnormBlock,
+             0x93bb => Format::ASTC_10x10UnormBlock,
+             0x93bc => Format::ASTC_12x10UnormBlock,
+             0x93bd => Format::ASTC_12x12UnormBlock,
+             0x93d0 => Format::ASTC_4x4SrgbBlock,
+             0x93d1 => Format::ASTC_5x4SrgbBlock,
+             0x93d2 => Format::ASTC_5x5SrgbBlock,
+             0x93d3 => Format::ASTC_6x5SrgbBlock,
+             0x93d4 => Format::ASTC_6x6SrgbBlock,
+             0x93d5 => Format::ASTC_8x5SrgbBlock,
+             0x93d6 => Format::ASTC_8x6SrgbBlock,
+             0x93d7 => Format::ASTC_8x8SrgbBlock,
+             0x93d8 => Format::ASTC_10x5SrgbBlock,
+             0x93d9 => Format::ASTC_10x6SrgbBlock,
+             0x93da => Format::ASTC_10x8SrgbBlock,
+             0x93db => Format::ASTC_10x10SrgbBlock,
+             0x93dc => Format::ASTC_12x10SrgbBlock,
+             0x93dd => Format::ASTC_12x12SrgbBlock,
+             _ => return None,
+         })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::KTX1_IDENTIFIER;
+    use super::KTX2_IDENTIFIER;
+    use super::parse_ktx;
+    use super::parse_ktx2;
+    use format::Format;
+    use image::Dimensions;
+    use image::loader::LoadError;
+
+    fn push_u32(out: &mut Vec<u8>, value: u32) {
+        out.extend_from_slice(&[value as u8, (value >> 8) as u8, (value >> 16) as u8,
+                                (value >> 24) as u8]);
+    }
+
+    #[test]
+    fn ktx1_rows_are_unpadded() {
+        // A 3x2 R8 texture with two levels, whose rows are padded to 4 bytes in the file.
+        let mut file = KTX1_IDENTIFIER.to_vec();
+        for &field in &[0x04030201, 0x1401, 1, 0x1903, 0x8229, 0x1903, 3, 2, 0, 0, 1, 2, 0] {
+            push_u32(&mut file, field);
+        }
+        push_u32(&mut file, 8);
+        file.extend_from_slice(&[1, 2, 3, 0, 4, 5, 6, 0]);
+        push_u32(&mut file, 4);
+        file.extend_from_slice(&[7, 0, 0, 0]);
+
+        let texture = parse_ktx(&file).unwrap();
+        assert_eq!(texture.format(), Format::R8Unorm);
+        assert_eq!(texture.dimensions(),
+                   Dimensions::Dim2d {
+                       width: 3,
+                       height: 2,
+                   });
+        assert_eq!(texture.mipmap_levels(), 2);
+        assert_eq!(texture.level_data(0), Some(&[1, 2, 3, 4, 5, 6][..]));
+        assert_eq!(texture.level_data(1), Some(&[7][..]));
+    }
+
+    #[test]
+    fn ktx1_generated_mipmaps() {
+        let mut file = KTX1_IDENTIFIER.to_vec();
+        for &field in &[0x04030201, 0x1401, 1, 0x1908, 0x8058, 0x1908, 4, 4, 0, 0, 1, 0, 0] {
+            push_u32(&mut file, field);
+        }
+        push_u32(&mut file, 64);
+        file.extend_from_slice(&[0; 64]);
+
+        let texture = parse_ktx(&file).unwrap();
+        assert_eq!(texture.mipmap_levels(), 3);
+        assert!(texture.level_data(0).is_some());
+        assert!(texture.level_data(1).is_none());
+    }
+
+    #[test]
+    fn ktx2_cubemap() {
+        // A 4x4 BC1 cubemap with a single level. Each face is a single block of 8 bytes.
+        let mut file = KTX2_IDENTIFIER.to_vec();
+        for &field in &[133, 1, 4, 4, 0, 0, 6, 1, 0] {
+            push_u32(&mut file, field);
+        }
+        file.extend_from_slice(&[0; 32]);
+        for &field in &[104, 0, 48, 0, 48, 0] {
+            push_u32(&mut file, field);
+        }
+        file.extend_from_slice(&(0 .. 48).collect::<Vec<u8>>());
+
+        let texture = parse_ktx2(&file).unwrap();
+        assert_eq!(texture.format(), Format::BC1_RGBAUnormBlock);
+        assert_eq!(texture.dimensions(), Dimensions::Cubemap { size: 4 });
+        assert_eq!(texture.level_data(0).unwrap().len(), 48);
+    }
+
+    #[test]
+    fn ktx2_supercompression() {
+        let mut file = KTX2_IDENTIFIER.to_vec();
+        for &field in &[37, 1, 4, 4, 0, 0, 1, 1, 2] {
+            push_u32(&mut file, field);
+        }
+
+        match parse_ktx2(&file) {
+            Err(LoadError::UnsupportedSupercompression) => (),
+            _ => panic!(),
+        }
+    }
+}
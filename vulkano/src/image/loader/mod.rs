@@ -0,0 +1,438 @@
+// Copyright (c) 2017 The vulkano developers
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>,
+// at your option. All files in the project carrying such
+// notice may not be copied, modified, or distributed except
+// according to those terms.
+
+//! Loading textures stored in KTX and DDS containers.
+//!
+//! This module is only available if the `image_loader` feature is enabled.
+//!
+//! KTX (versions 1 and 2) and DDS files contain the format, the dimensions and the content of
+//! every mipmap level and every array layer of a texture, often in a compressed format. The
+//! `parse` function (or `parse_ktx`, `parse_ktx2` and `parse_dds` if you know the container)
+//! reads such a file into a `Texture`, which can then be uploaded to an `ImmutableImage`. The
+//! `load` function does both at once.
+//!
+//! ```no_run
+//! use std::fs::File;
+//! use std::io::Read;
+//! use vulkano::image::loader;
+//! # let queue: std::sync::Arc<vulkano::device::Queue> = return;
+//!
+//! let mut bytes = Vec::new();
+//! File::open("texture.ktx").unwrap().read_to_end(&mut bytes).unwrap();
+//! let (image, upload_future) = loader::load(&bytes, queue.clone()).unwrap();
+//! ```
+//!
+//! # Supported textures
+//!
+//! - One-dimensional, two-dimensional and three-dimensional textures, arrays of textures, cubemaps
+//!   and arrays of cubemaps.
+//! - Uncompressed formats, and the formats of the BC, ETC2/EAC and ASTC families. Whether the
+//!   device supports a format is only known when uploading the texture. If it can't sample images
+//!   of this format, you will get a `LoadError::FormatNotSampleable` error.
+//! - If the container doesn't include mipmaps but asks for them to be generated (which is possible
+//!   with KTX files), they are generated when uploading the texture. See
+//!   `AutoCommandBufferBuilder::generate_mipmaps`.
+//!
+//! KTX2 files that use supercompression, and depth or stencil formats, aren't supported.
+
+use std::error;
+use std::fmt;
+use std::sync::Arc;
+
+use command_buffer::AutoCommandBuffer;
+use command_buffer::CommandBufferExecFuture;
+use device::Queue;
+use format::Format;
+use format::FormatTy;
+use image::Dimensions;
+use image::MipmapsCount;
+use image::immutable::ImmutableImage;
+use image::immutable::ImmutableImageFromBufferError;
+use sync::NowFuture;
+
+pub use self::dds::parse_dds;
+pub use self::ktx::parse_ktx;
+pub use self::ktx::parse_ktx2;
+
+mod dds;
+mod ktx;
+
+/// Future returned by `load` and `Texture::upload`, representing the upload of the texture.
+pub type LoadFuture = CommandBufferExecFuture<NowFuture, AutoCommandBuffer>;
+
+/// Parses a KTX1, KTX2 or DDS file, depending on its first bytes, and uploads its content to a
+/// new `ImmutableImage`.
+///
+/// This function returns two objects: the newly-created image, and a future representing the
+/// upload operation. In order to be allowed to use the image, you must either submit your
+/// operation after this future, or execute this future and wait for it to be finished before
+/// submitting your own operation.
+#[inline]
+pub fn load(bytes: &[u8], queue: Arc<Queue>)
+            -> Result<(Arc<ImmutableImage<Format>>, LoadFuture), LoadError> {
+    parse(bytes)?.upload(queue)
+}
+
+/// Parses a KTX1, KTX2 or DDS file, depending on its first bytes.
+pub fn parse(bytes: &[u8]) -> Result<Texture, LoadError> {
+    if bytes.starts_with(&ktx::KTX1_IDENTIFIER) {
+        parse_ktx(bytes)
+    } else if bytes.starts_with(&ktx::KTX2_IDENTIFIER) {
+        parse_ktx2(bytes)
+    } else if bytes.starts_with(&dds::DDS_MAGIC) {
+        parse_dds(bytes)
+    } else {
+        Err(LoadError::UnrecognizedContainer)
+    }
+}
+
+/// Content of a texture container, ready to be uploaded.
+///
+/// The content of each mipmap level is tightly packed, array layer after array layer. For
+/// cubemaps each array layer contains six faces in the order +X, -X, +Y, -Y, +Z, -Z, which is the
+/// order of the layers of a Vulkan cube image.
+#[derive(Debug, Clone)]
+pub struct Texture {
+    format: Format,
+    dimensions: Dimensions,
+    mipmap_levels: u32,
+    data: Vec<u8>,
+    // Offset and length within `data` of each mipmap level that the container provides. Can be
+    // less than `mipmap_levels` if the other levels must be generated.
+    levels: Vec<(usize, usize)>,
+}
+
+impl Texture {
+    // Builds an empty texture, after checking that the format and the dimensions are supported.
+    fn new(format: Format, dimensions: Dimensions, mipmap_levels: u32)
+           -> Result<Texture, LoadError> {
+        match format.ty() {
+            FormatTy::Float | FormatTy::Uint | FormatTy::Sint | FormatTy::Compressed => (),
+            _ => return Err(LoadError::UnsupportedFormat),
+        }
+
+        if dimensions.width() == 0 || dimensions.height() == 0 || dimensions.depth() == 0 ||
+            dimensions.array_layers() == 0 || mipmap_levels == 0 ||
+            mipmap_levels > max_mipmap_levels(dimensions)
+        {
+            return Err(LoadError::InvalidHeader);
+        }
+
+        Ok(Texture {
+               format: format,
+               dimensions: dimensions,
+               mipmap_levels: mipmap_levels,
+               data: Vec::new(),
+               levels: Vec::with_capacity(mipmap_levels as usize),
+           })
+    }
+
+    /// Returns the format of the texture.
+    #[inline]
+    pub fn format(&self) -> Format {
+        self.format
+    }
+
+    /// Returns the dimensions of the texture.
+    #[inline]
+    pub fn dimensions(&self) -> Dimensions {
+        self.dimensions
+    }
+
+    /// Returns the number of mipmap levels of the texture.
+    #[inline]
+    pub fn mipmap_levels(&self) -> u32 {
+        self.mipmap_levels
+    }
+
+    /// Returns the content of a mipmap level, or `None` if the container doesn't provide it and
+    /// it will be generated during the upload.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if `level` is out of range.
+    ///
+    #[inline]
+    pub fn level_data(&self, level: u32) -> Option<&[u8]> {
+        assert!(level < self.mipmap_levels);
+        self.levels
+            .get(level as usize)
+            .map(|&(offset, len)| &self.data[offset .. offset + len])
+    }
+
+    // Returns the number of bytes that all the layers of a mipmap level occupy, or an error if it
+    // doesn't fit in a `usize`.
+    fn level_size(&self, level: u32) -> Result<usize, LoadError> {
+        self.format
+            .region_size(self.level_extent(level))
+            .and_then(|size| size.checked_mul(self.dimensions.array_layers_with_cube() as usize))
+            .ok_or(LoadError::InvalidHeader)
+    }
+
+    // Returns the width, height and depth of a mipmap level.
+    fn level_extent(&self, level: u32) -> [u32; 3] {
+        self.dimensions
+            .to_image_dimensions()
+            .mipmap_dimensions(level)
+            .unwrap()
+            .width_height_depth()
+    }
+
+    // Appends the content of the next mipmap level, which must have the size of the level.
+    //
+    // Levels are tightly packed in `data`, which is the layout that `ImmutableImage::from_buffer`
+    // expects.
+    fn push_level(&mut self, level: &[u8]) {
+        debug_assert_eq!(Some(level.len()), self.level_size(self.levels.len() as u32).ok());
+
+        let offset = self.data.len();
+        self.data.extend_from_slice(level);
+        self.levels.push((offset, level.len()));
+    }
+
+    /// Uploads the texture to a new `ImmutableImage`.
+    ///
+    /// This function returns two objects: the newly-created image, and a future representing the
+    /// upload operation. In order to be allowed to use the image, you must either submit your
+    /// operation after this future, or execute this future and wait for it to be finished before
+    /// submitting your own operation.
+    pub fn upload(&self, queue: Arc<Queue>)
+                  -> Result<(Arc<ImmutableImage<Format>>, LoadFuture), LoadError> {
+        let format_properties = queue.device().physical_device().format_properties(self.format);
+        if !format_properties.optimal_tiling_features.sampled_image {
+            return Err(LoadError::FormatNotSampleable { format: self.format });
+        }
+
+        // If the container only provides the first level, `from_iter` generates the others.
+        let result = ImmutableImage::from_iter(self.data.iter().cloned(),
+                                               self.dimensions,
+                                               MipmapsCount::Specific(self.mipmap_levels),
+                                               self.format,
+                                               queue)?;
+        Ok(result)
+    }
+}
+
+// Builds the dimensions of a texture from the fields that KTX and DDS headers have in common.
+//
+// A height of 0 means a one-dimensional texture and a depth of 0 a two-dimensional texture.
+// `layers` is 0 if the texture isn't an array.
+fn texture_dimensions(width: u32, height: u32, depth: u32, layers: u32, faces: u32)
+                      -> Result<Dimensions, LoadError> {
+    if width == 0 || (height == 0 && depth != 0) {
+        return Err(LoadError::InvalidHeader);
+    }
+
+    Ok(match (height, depth, layers, faces) {
+           (0, _, 0, 1) => Dimensions::Dim1d { width: width },
+           (0, _, layers, 1) => {
+               Dimensions::Dim1dArray {
+                   width: width,
+                   array_layers: layers,
+               }
+           },
+           (height, 0, 0, 1) => {
+               Dimensions::Dim2d {
+                   width: width,
+                   height: height,
+               }
+           },
+           (height, 0, layers, 1) => {
+               Dimensions::Dim2dArray {
+                   width: width,
+                   height: height,
+                   array_layers: layers,
+               }
+           },
+           (height, depth, 0, 1) => {
+               Dimensions::Dim3d {
+                   width: width,
+                   height: height,
+                   depth: depth,
+               }
+           },
+           (height, 0, 0, 6) if height == width => Dimensions::Cubemap { size: width },
+           (height, 0, layers, 6) if height == width => {
+               Dimensions::CubemapArray {
+                   size: width,
+                   array_layers: layers,
+               }
+           },
+           _ => return Err(LoadError::InvalidHeader),
+       })
+}
+
+// Returns the number of levels of a complete mipmap chain.
+fn max_mipmap_levels(dimensions: Dimensions) -> u32 {
+    let dimensions = dimensions.to_image_dimensions();
+    (0 .. 32)
+        .take_while(|&level| dimensions.mipmap_dimensions(level).is_some())
+        .count() as u32
+}
+
+// Reads little-endian integers from a container, checking the bounds.
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    #[inline]
+    fn new(bytes: &'a [u8]) -> Reader<'a> {
+        Reader { bytes: bytes }
+    }
+
+    // Returns `len` bytes starting at `offset`.
+    #[inline]
+    fn bytes(&self, offset: usize, len: usize) -> Result<&'a [u8], LoadError> {
+        match offset.checked_add(len) {
+            Some(end) if end <= self.bytes.len() => Ok(&self.bytes[offset .. end]),
+            _ => Err(LoadError::Truncated),
+        }
+    }
+
+    #[inline]
+    fn u32(&self, offset: usize) -> Result<u32, LoadError> {
+        let b = self.bytes(offset, 4)?;
+        Ok(b[0] as u32 | (b[1] as u32) << 8 | (b[2] as u32) << 16 | (b[3] as u32) << 24)
+    }
+
+    #[inline]
+    fn u64(&self, offset: usize) -> Result<u64, LoadError> {
+        Ok(self.u32(offset)? as u64 | (self.u32(offset + 4)? as u64) << 32)
+    }
+}
+
+/// Error that can happen when loading a texture.
+#[derive(Debug, Clone)]
+pub enum LoadError {
+    /// The data doesn't start with the identifier of a KTX1, KTX2 or DDS file.
+    UnrecognizedContainer,
+    /// The data ends before the end of the header or of the content of the texture.
+    Truncated,
+    /// The header of the container contains invalid or inconsistent values.
+    InvalidHeader,
+    /// The format of the texture has no corresponding `Format`, or textures of this format can't
+    /// be loaded.
+    UnsupportedFormat,
+    /// The KTX2 file uses supercompression.
+    UnsupportedSupercompression,
+    /// The device can't sample images of the format of the texture.
+    FormatNotSampleable {
+        /// The format of the texture.
+        format: Format,
+    },
+    /// Error while creating the image or uploading the texture to it.
+    ImmutableImageFromBufferError(ImmutableImageFromBufferError),
+}
+
+impl error::Error for LoadError {
+    #[inline]
+    fn description(&self) -> &str {
+        match *self {
+            LoadError::UnrecognizedContainer => {
+                "the data doesn't start with the identifier of a KTX1, KTX2 or DDS file"
+            },
+            LoadError::Truncated => {
+                "the data ends before the end of the header or of the content of the texture"
+            },
+            LoadError::InvalidHeader => {
+                "the header of the container contains invalid or inconsistent values"
+            },
+            LoadError::UnsupportedFormat => {
+                "the format of the texture has no corresponding `Format`, or textures of this \
+                 format can't be loaded"
+            },
+            LoadError::UnsupportedSupercompression => {
+                "the KTX2 file uses supercompression"
+            },
+            LoadError::FormatNotSampleable { .. } => {
+                "the device can't sample images of the format of the texture"
+            },
+            LoadError::ImmutableImageFromBufferError(_) => {
+                "error while creating the image or uploading the texture to it"
+            },
+        }
+    }
+
+    #[inline]
+    fn cause(&self) -> Option<&error::Error> {
+        match *self {
+            LoadError::ImmutableImageFromBufferError(ref err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for LoadError {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(fmt, "{}", error::Error::description(self))
+    }
+}
+
+impl From<ImmutableImageFromBufferError> for LoadError {
+    #[inline]
+    fn from(err: ImmutableImageFromBufferError) -> LoadError {
+        LoadError::ImmutableImageFromBufferError(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Texture;
+    use super::texture_dimensions;
+    use format::Format;
+    use image::Dimensions;
+
+    #[test]
+    fn dimensions_from_header() {
+        assert_eq!(texture_dimensions(8, 0, 0, 0, 1).unwrap(), Dimensions::Dim1d { width: 8 });
+        assert_eq!(texture_dimensions(8, 4, 0, 3, 1).unwrap(),
+                   Dimensions::Dim2dArray {
+                       width: 8,
+                       height: 4,
+                       array_layers: 3,
+                   });
+        assert_eq!(texture_dimensions(8, 8, 0, 2, 6).unwrap(),
+                   Dimensions::CubemapArray {
+                       size: 8,
+                       array_layers: 2,
+                   });
+        assert!(texture_dimensions(8, 4, 0, 0, 6).is_err());
+        assert!(texture_dimensions(8, 4, 2, 2, 1).is_err());
+    }
+
+    #[test]
+    fn compressed_level_size() {
+        let texture = Texture::new(Format::BC1_RGBAUnormBlock,
+                                   Dimensions::Dim2d {
+                                       width: 10,
+                                       height: 6,
+                                   },
+                                   4)
+            .unwrap();
+
+        // 3x2 blocks, then 2x1, 1x1 and 1x1.
+        assert_eq!(texture.level_size(0).unwrap(), 6 * 8);
+        assert_eq!(texture.level_size(1).unwrap(), 2 * 8);
+        assert_eq!(texture.level_size(2).unwrap(), 8);
+        assert_eq!(texture.level_size(3).unwrap(), 8);
+    }
+
+    #[test]
+    fn too_many_levels() {
+        let result = Texture::new(Format::R8G8B8A8Unorm,
+                                  Dimensions::Dim2d {
+                                      width: 4,
+                                      height: 4,
+                                  },
+                                  4);
+        assert!(result.is_err());
+    }
+}
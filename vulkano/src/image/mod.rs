@@ -46,9 +46,14 @@
 //! To be written.
 //!
 
+use std::cmp;
+use vk;
+
 pub use self::attachment::AttachmentImage;
 pub use self::immutable::ImmutableImage;
 pub use self::layout::ImageLayout;
+pub use self::sparse::SparseImage;
+pub use self::sparse::SparseImagePage;
 pub use self::storage::StorageImage;
 pub use self::swapchain::SwapchainImage;
 pub use self::sys::ImageCreationError;
@@ -56,15 +61,21 @@ pub use self::traits::ImageAccess;
 pub use self::traits::ImageInner;
 pub use self::traits::ImageViewAccess;
 pub use self::usage::ImageUsage;
+pub use self::view::ImageView;
+pub use self::view::ImageViewCreationError;
 
 pub mod attachment; // TODO: make private
 pub mod immutable; // TODO: make private
 mod layout;
+#[cfg(feature = "image_loader")]
+pub mod loader;
+mod sparse;
 mod storage;
 pub mod swapchain; // TODO: make private
 pub mod sys;
 pub mod traits;
 mod usage;
+mod view;
 
 /// Specifies how many mipmaps must be allocated.
 ///
@@ -114,23 +125,35 @@ pub struct Swizzle {
     pub a: ComponentSwizzle,
 }
 
+impl Swizzle {
+    /// Returns true if all the components return the value that they should normally have.
+    #[inline]
+    pub fn is_identity(&self) -> bool {
+        (self.r == ComponentSwizzle::Identity || self.r == ComponentSwizzle::Red) &&
+            (self.g == ComponentSwizzle::Identity || self.g == ComponentSwizzle::Green) &&
+            (self.b == ComponentSwizzle::Identity || self.b == ComponentSwizzle::Blue) &&
+            (self.a == ComponentSwizzle::Identity || self.a == ComponentSwizzle::Alpha)
+    }
+}
+
 /// Describes the value that an individual component must return when being accessed.
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[repr(u32)]
 pub enum ComponentSwizzle {
     /// Returns the value that this component should normally have.
-    Identity,
+    Identity = vk::COMPONENT_SWIZZLE_IDENTITY,
     /// Always return zero.
-    Zero,
+    Zero = vk::COMPONENT_SWIZZLE_ZERO,
     /// Always return one.
-    One,
+    One = vk::COMPONENT_SWIZZLE_ONE,
     /// Returns the value of the first component.
-    Red,
+    Red = vk::COMPONENT_SWIZZLE_R,
     /// Returns the value of the second component.
-    Green,
+    Green = vk::COMPONENT_SWIZZLE_G,
     /// Returns the value of the third component.
-    Blue,
+    Blue = vk::COMPONENT_SWIZZLE_B,
     /// Returns the value of the fourth component.
-    Alpha,
+    Alpha = vk::COMPONENT_SWIZZLE_A,
 }
 
 impl Default for ComponentSwizzle {
@@ -384,4 +407,241 @@ impl ImageDimensions {
             ImageDimensions::Dim3d { .. } => 1,
         }
     }
+
+    /// Returns the dimensions of the given mipmap level, where each level is half the dimensions
+    /// of the previous one, rounded down. The number of array layers doesn't change.
+    ///
+    /// Returns `None` if the level would be smaller than 1x1x1.
+    pub fn mipmap_dimensions(&self, level: u32) -> Option<ImageDimensions> {
+        let max_dim = cmp::max(cmp::max(self.width(), self.height()), self.depth());
+        if level >= 32 || (max_dim >> level) == 0 {
+            return None;
+        }
+
+        let halve = |dim: u32| cmp::max(1, dim >> level);
+
+        Some(match *self {
+                 ImageDimensions::Dim1d { width, array_layers } => {
+                     ImageDimensions::Dim1d {
+                         width: halve(width),
+                         array_layers: array_layers,
+                     }
+                 },
+                 ImageDimensions::Dim2d {
+                     width,
+                     height,
+                     array_layers,
+                     cubemap_compatible,
+                 } => {
+                     ImageDimensions::Dim2d {
+                         width: halve(width),
+                         height: halve(height),
+                         array_layers: array_layers,
+                         cubemap_compatible: cubemap_compatible,
+                     }
+                 },
+                 ImageDimensions::Dim3d {
+                     width,
+                     height,
+                     depth,
+                 } => {
+                     ImageDimensions::Dim3d {
+                         width: halve(width),
+                         height: halve(height),
+                         depth: halve(depth),
+                     }
+                 },
+             })
+    }
+
+    /// Returns the type of an image with these dimensions.
+    #[inline]
+    pub fn image_type(&self) -> ImageType {
+        match *self {
+            ImageDimensions::Dim1d { .. } => ImageType::Dim1d,
+            ImageDimensions::Dim2d { .. } => ImageType::Dim2d,
+            ImageDimensions::Dim3d { .. } => ImageType::Dim3d,
+        }
+    }
+}
+
+/// Number of dimensions of an image.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ImageType {
+    /// One-dimensional image. Only the width is meaningful, the height and depth are 1.
+    Dim1d,
+    /// Two-dimensional image. The depth is 1.
+    Dim2d,
+    /// Three-dimensional image. It can't have array layers.
+    Dim3d,
+}
+
+impl ImageType {
+    #[inline]
+    pub(crate) fn to_vulkan(&self) -> vk::ImageType {
+        match *self {
+            ImageType::Dim1d => vk::IMAGE_TYPE_1D,
+            ImageType::Dim2d => vk::IMAGE_TYPE_2D,
+            ImageType::Dim3d => vk::IMAGE_TYPE_3D,
+        }
+    }
+}
+
+/// Arrangement of the texels of an image in memory.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ImageTiling {
+    /// The texels are arranged in an implementation-dependent way that is efficient for the GPU.
+    Optimal,
+    /// The texels are stored row after row, which lets the CPU access them.
+    Linear,
+}
+
+impl ImageTiling {
+    #[inline]
+    pub(crate) fn to_vulkan(&self) -> vk::ImageTiling {
+        match *self {
+            ImageTiling::Optimal => vk::IMAGE_TILING_OPTIMAL,
+            ImageTiling::Linear => vk::IMAGE_TILING_LINEAR,
+        }
+    }
+}
+
+/// Flags that change how an image can be used after its creation.
+///
+/// All the values default to false.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ImageCreateFlags {
+    /// The image can be used to create cubemap views. Requires a two-dimensional image whose
+    /// width and height are equal and that has at least six array layers.
+    pub cube_compatible: bool,
+}
+
+impl ImageCreateFlags {
+    /// Builds an `ImageCreateFlags` with all values set to false.
+    #[inline]
+    pub fn none() -> ImageCreateFlags {
+        ImageCreateFlags { cube_compatible: false }
+    }
+
+    #[inline]
+    pub(crate) fn to_bits(&self) -> vk::ImageCreateFlags {
+        let mut result = 0;
+        if self.cube_compatible {
+            result |= vk::IMAGE_CREATE_CUBE_COMPATIBLE_BIT;
+        }
+        result
+    }
+}
+
+/// Set of numbers of samples per texel.
+///
+/// All the values default to false.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct SampleCounts {
+    pub sample1: bool,
+    pub sample2: bool,
+    pub sample4: bool,
+    pub sample8: bool,
+    pub sample16: bool,
+    pub sample32: bool,
+    pub sample64: bool,
+}
+
+impl SampleCounts {
+    /// Returns true if images with `samples` samples per texel are in the set.
+    ///
+    /// Always returns false if `samples` isn't a power of two between 1 and 64.
+    #[inline]
+    pub fn contains(&self, samples: u32) -> bool {
+        match samples {
+            1 => self.sample1,
+            2 => self.sample2,
+            4 => self.sample4,
+            8 => self.sample8,
+            16 => self.sample16,
+            32 => self.sample32,
+            64 => self.sample64,
+            _ => false,
+        }
+    }
+
+    #[inline]
+    pub(crate) fn from_bits(val: vk::SampleCountFlags) -> SampleCounts {
+        SampleCounts {
+            sample1: (val & vk::SAMPLE_COUNT_1_BIT) != 0,
+            sample2: (val & vk::SAMPLE_COUNT_2_BIT) != 0,
+            sample4: (val & vk::SAMPLE_COUNT_4_BIT) != 0,
+            sample8: (val & vk::SAMPLE_COUNT_8_BIT) != 0,
+            sample16: (val & vk::SAMPLE_COUNT_16_BIT) != 0,
+            sample32: (val & vk::SAMPLE_COUNT_32_BIT) != 0,
+            sample64: (val & vk::SAMPLE_COUNT_64_BIT) != 0,
+        }
+    }
+}
+
+/// Capabilities of the images of a given format, type, tiling and usage, as supported by a
+/// physical device.
+///
+/// Can be obtained with `PhysicalDevice::image_format_properties()`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ImageFormatProperties {
+    /// Maximum width, height and depth of an image.
+    pub max_extent: [u32; 3],
+
+    /// Maximum number of mipmap levels of an image.
+    pub max_mipmap_levels: u32,
+
+    /// Maximum number of array layers of an image.
+    pub max_array_layers: u32,
+
+    /// Numbers of samples per texel supported by an image.
+    pub sample_counts: SampleCounts,
+
+    /// Upper bound of the total size in bytes of an image, including all of its mipmaps and array
+    /// layers.
+    pub max_resource_size: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use image::ImageDimensions;
+    use image::SampleCounts;
+    use vk;
+
+    #[test]
+    fn mipmap_dimensions() {
+        let dims = ImageDimensions::Dim2d {
+            width: 283,
+            height: 175,
+            array_layers: 2,
+            cubemap_compatible: false,
+        };
+
+        assert_eq!(dims.mipmap_dimensions(0), Some(dims));
+        assert_eq!(dims.mipmap_dimensions(1),
+                   Some(ImageDimensions::Dim2d {
+                            width: 141,
+                            height: 87,
+                            array_layers: 2,
+                            cubemap_compatible: false,
+                        }));
+        assert_eq!(dims.mipmap_dimensions(8),
+                   Some(ImageDimensions::Dim2d {
+                            width: 1,
+                            height: 1,
+                            array_layers: 2,
+                            cubemap_compatible: false,
+                        }));
+        assert_eq!(dims.mipmap_dimensions(9), None);
+    }
+
+    #[test]
+    fn sample_counts_from_bits() {
+        let counts = SampleCounts::from_bits(vk::SAMPLE_COUNT_1_BIT | vk::SAMPLE_COUNT_4_BIT);
+        assert!(counts.contains(1));
+        assert!(!counts.contains(2));
+        assert!(counts.contains(4));
+        assert!(!counts.contains(3));
+        assert!(!counts.contains(0));
+    }
 }
@@ -0,0 +1,774 @@
+// Copyright (c) 2017 The vulkano developers
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>,
+// at your option. All files in the project carrying such
+// notice may not be copied, modified, or distributed except
+// according to those terms.
+
+//! Image whose memory is bound tile by tile.
+//!
+//! A `SparseImage` is created without any memory. Each mipmap level of each array layer is split
+//! into tiles whose dimensions are given by `granularity()`, except for the smallest mipmap levels
+//! which are grouped together in a *mip tail*. You call `bind` in order to make some tiles or mip
+//! tails resident, and `unbind` in order to release their memory. Both operations return a future
+//! that must be waited upon before the image is accessed by the GPU.
+//!
+//! Creating a sparse image requires the `sparse_binding` feature and the
+//! `sparse_residency_image2d` or `sparse_residency_image3d` feature to be enabled on the device,
+//! and binding requires a queue whose family supports sparse binding.
+
+use fnv::FnvHashMap;
+use fnv::FnvHashSet;
+use smallvec::SmallVec;
+use std::cmp;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+use buffer::sys::SparseLevel;
+use command_buffer::sys::UnsafeCommandBufferBuilderImageAspect;
+use device::Device;
+use device::Queue;
+use format::ClearValue;
+use format::FormatDesc;
+use format::FormatTy;
+use image::Dimensions;
+use image::ImageInner;
+use image::ImageLayout;
+use image::ImageUsage;
+use image::MipmapsCount;
+use image::sys::ImageCreationError;
+use image::sys::SparseImageMemoryRequirements;
+use image::sys::UnsafeImage;
+use image::sys::UnsafeImageView;
+use image::traits::ImageAccess;
+use image::traits::ImageClearValue;
+use image::traits::ImageContent;
+use image::traits::ImageViewAccess;
+use instance::QueueFamily;
+use memory::pool::AllocLayout;
+use memory::pool::MemoryPool;
+use memory::pool::StdMemoryPoolAlloc;
+use sync::AccessError;
+use sync::GpuFuture;
+use sync::NowFuture;
+use sync::Sharing;
+use sync::SparseBindError;
+use sync::SparseBindFuture;
+use sync::SparseBindOp;
+use sync::SparseGpuLock;
+use sync::bind_sparse;
+use sync::now;
+
+/// Image whose memory is bound tile by tile.
+///
+/// See the documentation of the module for more information.
+#[derive(Debug)]
+pub struct SparseImage<F> {
+    // Inner implementation.
+    image: UnsafeImage,
+
+    // We maintain a view of the whole image.
+    view: UnsafeImageView,
+
+    // Dimensions of the image view.
+    dimensions: Dimensions,
+
+    // Format.
+    format: F,
+
+    // How the color aspect of the image is split into tiles.
+    requirements: SparseImageMemoryRequirements,
+
+    // Size in bytes of the memory of a tile.
+    page_size: usize,
+
+    // Memory type used for the tiles and mip tails.
+    memory_type: u32,
+
+    // Memory bound to each page by the operations submitted so far.
+    pages: Mutex<FnvHashMap<SparseImagePage, Arc<StdMemoryPoolAlloc>>>,
+
+    // Pages whose bind operation is known to be finished.
+    resident: Arc<Mutex<FnvHashSet<SparseImagePage>>>,
+
+    // Queue families allowed to access this image.
+    queue_families: SmallVec<[u32; 4]>,
+
+    // Number of times this image is locked on the GPU side.
+    gpu_lock: SparseGpuLock,
+}
+
+/// Identifies a part of a `SparseImage` that can be bound or unbound.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum SparseImagePage {
+    /// A tile of a mipmap level that isn't part of the mip tail. The coordinates are in number of
+    /// tiles, not in texels.
+    Tile {
+        mip_level: u32,
+        array_layer: u32,
+        x: u32,
+        y: u32,
+        z: u32,
+    },
+
+    /// The mip tail of an array layer. If the image has a single mip tail for all its layers,
+    /// `array_layer` must be 0.
+    MipTail { array_layer: u32 },
+}
+
+impl<F> SparseImage<F> {
+    /// Creates a new sparse image with the given dimensions and format. No tile is resident at
+    /// first.
+    ///
+    /// Returns `FormatNotSupported` if the format is a depth or stencil format, or if the
+    /// implementation requires the metadata of the image to be bound. Neither is supported.
+    pub fn new<'a, Mi, I>(device: Arc<Device>, dimensions: Dimensions, format: F, mipmaps: Mi,
+                          usage: ImageUsage, queue_families: I)
+                          -> Result<Arc<SparseImage<F>>, ImageCreationError>
+        where F: FormatDesc,
+              Mi: Into<MipmapsCount>,
+              I: IntoIterator<Item = QueueFamily<'a>>
+    {
+        match format.format().ty() {
+            FormatTy::Depth | FormatTy::Stencil | FormatTy::DepthStencil => {
+                return Err(ImageCreationError::FormatNotSupported);
+            },
+            _ => (),
+        };
+
+        let queue_families = queue_families
+            .into_iter()
+            .map(|f| f.id())
+            .collect::<SmallVec<[u32; 4]>>();
+
+        let (image, mem_reqs) = unsafe {
+            let sharing = if queue_families.len() >= 2 {
+                Sharing::Concurrent(queue_families.iter().cloned())
+            } else {
+                Sharing::Exclusive
+            };
+
+            let sparse = SparseLevel {
+                sparse: true,
+                sparse_residency: true,
+                sparse_aliased: false,
+            };
+
+            UnsafeImage::new_sparse(device.clone(),
+                                    usage,
+                                    format.format(),
+                                    dimensions.to_image_dimensions(),
+                                    1,
+                                    mipmaps,
+                                    sharing,
+                                    sparse)?
+        };
+
+        let sparse_reqs = image.sparse_memory_requirements();
+        if sparse_reqs.iter().any(|r| r.metadata) {
+            return Err(ImageCreationError::FormatNotSupported);
+        }
+        let requirements = match sparse_reqs.into_iter().find(|r| r.color) {
+            Some(r) => r,
+            None => return Err(ImageCreationError::FormatNotSupported),
+        };
+
+        let mem_ty = {
+            let device_local = device
+                .physical_device()
+                .memory_types()
+                .filter(|t| (mem_reqs.memory_type_bits & (1 << t.id())) != 0)
+                .filter(|t| t.is_device_local());
+            let any = device
+                .physical_device()
+                .memory_types()
+                .filter(|t| (mem_reqs.memory_type_bits & (1 << t.id())) != 0);
+            device_local.chain(any).next().unwrap()
+        };
+
+        let view = unsafe {
+            UnsafeImageView::raw(&image,
+                                 dimensions.to_view_type(),
+                                 0 .. image.mipmap_levels(),
+                                 0 .. image.dimensions().array_layers())?
+        };
+
+        Ok(Arc::new(SparseImage {
+                        image: image,
+                        view: view,
+                        dimensions: dimensions,
+                        format: format,
+                        requirements: requirements,
+                        page_size: mem_reqs.alignment,
+                        memory_type: mem_ty.id(),
+                        pages: Mutex::new(FnvHashMap::default()),
+                        resident: Arc::new(Mutex::new(FnvHashSet::default())),
+                        queue_families: queue_families,
+                        gpu_lock: SparseGpuLock::new(),
+                    }))
+    }
+}
+
+impl<F> SparseImage<F> {
+    /// Returns the dimensions of the image.
+    #[inline]
+    pub fn dimensions(&self) -> Dimensions {
+        self.dimensions
+    }
+
+    /// Returns how the image is split into tiles and mip tails.
+    #[inline]
+    pub fn sparse_memory_requirements(&self) -> &SparseImageMemoryRequirements {
+        &self.requirements
+    }
+
+    /// Returns the width, height and depth in texels of a tile.
+    #[inline]
+    pub fn granularity(&self) -> [u32; 3] {
+        self.requirements.granularity
+    }
+
+    /// Returns the size in bytes of the memory of a tile.
+    #[inline]
+    pub fn page_size(&self) -> usize {
+        self.page_size
+    }
+
+    /// Returns the first mipmap level that is part of the mip tail. Equal to the number of
+    /// mipmap levels if the image has no mip tail.
+    #[inline]
+    pub fn mip_tail_first_lod(&self) -> u32 {
+        cmp::min(self.requirements.mip_tail_first_lod, self.image.mipmap_levels())
+    }
+
+    /// Returns the number of tiles in each dimension of a mipmap level.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if the mipmap level is part of the mip tail.
+    ///
+    #[inline]
+    pub fn num_tiles(&self, mip_level: u32) -> [u32; 3] {
+        assert!(mip_level < self.mip_tail_first_lod());
+
+        let extent = self.mip_level_extent(mip_level);
+        let gran = self.requirements.granularity;
+        [
+            (extent[0] + gran[0] - 1) / gran[0],
+            (extent[1] + gran[1] - 1) / gran[1],
+            (extent[2] + gran[2] - 1) / gran[2],
+        ]
+    }
+
+    /// Returns true if the given tile or mip tail is resident.
+    ///
+    /// A page becomes resident once the future returned by `bind` is known to be finished, for
+    /// example after waiting for a fence signaled after it. Similarly, it becomes non-resident
+    /// once the future returned by `unbind` is known to be finished.
+    #[inline]
+    pub fn is_resident(&self, page: SparseImagePage) -> bool {
+        self.resident.lock().unwrap().contains(&page)
+    }
+
+    /// Returns the number of tiles and mip tails that are currently resident.
+    #[inline]
+    pub fn num_resident_pages(&self) -> usize {
+        self.resident.lock().unwrap().len()
+    }
+
+    /// Returns the queue families this image can be used on.
+    // TODO: use a custom iterator
+    #[inline]
+    pub fn queue_families(&self) -> Vec<QueueFamily> {
+        self.queue_families
+            .iter()
+            .map(|&num| {
+                     self.image
+                         .device()
+                         .physical_device()
+                         .queue_family_by_id(num)
+                         .unwrap()
+                 })
+            .collect()
+    }
+
+    // Returns the dimensions in texels of a mipmap level.
+    fn mip_level_extent(&self, mip_level: u32) -> [u32; 3] {
+        let dims = self.image.dimensions();
+        [
+            cmp::max(1, dims.width() >> mip_level),
+            cmp::max(1, dims.height() >> mip_level),
+            cmp::max(1, dims.depth() >> mip_level),
+        ]
+    }
+
+    // Returns the offset and extent in texels of a tile, or the offset and size in bytes of a
+    // mip tail in the opaque memory of the image.
+    //
+    // Panics if the page is out of range.
+    fn page_region(&self, page: SparseImagePage) -> PageRegion {
+        match page {
+            SparseImagePage::Tile {
+                mip_level,
+                array_layer,
+                x,
+                y,
+                z,
+            } => {
+                assert!(array_layer < self.image.dimensions().array_layers());
+                let num_tiles = self.num_tiles(mip_level);
+                assert!(x < num_tiles[0] && y < num_tiles[1] && z < num_tiles[2]);
+
+                let extent = self.mip_level_extent(mip_level);
+                let gran = self.requirements.granularity;
+                let offset = [x * gran[0], y * gran[1], z * gran[2]];
+                PageRegion::Tile {
+                    offset: offset,
+                    extent: [
+                        cmp::min(gran[0], extent[0] - offset[0]),
+                        cmp::min(gran[1], extent[1] - offset[1]),
+                        cmp::min(gran[2], extent[2] - offset[2]),
+                    ],
+                }
+            },
+            SparseImagePage::MipTail { array_layer } => {
+                assert!(self.mip_tail_first_lod() < self.image.mipmap_levels());
+                let offset = if self.requirements.single_mip_tail {
+                    assert_eq!(array_layer, 0);
+                    self.requirements.mip_tail_offset
+                } else {
+                    assert!(array_layer < self.image.dimensions().array_layers());
+                    self.requirements.mip_tail_offset +
+                        array_layer as usize * self.requirements.mip_tail_stride
+                };
+                PageRegion::MipTail {
+                    offset: offset,
+                    size: self.requirements.mip_tail_size,
+                }
+            },
+        }
+    }
+}
+
+// Location of a page within the image.
+enum PageRegion {
+    Tile { offset: [u32; 3], extent: [u32; 3] },
+    MipTail { offset: usize, size: usize },
+}
+
+impl<F> SparseImage<F>
+    where F: 'static + Send + Sync
+{
+    /// Allocates memory for the given tiles and mip tails and binds it to the image.
+    ///
+    /// Pages that are already resident are left untouched.
+    ///
+    /// > **Note**: This is just a shortcut for `bind_after(vulkano::sync::now(), ...)`.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if one of the pages is out of range.
+    ///
+    #[inline]
+    pub fn bind<I>(me: &Arc<Self>, queue: Arc<Queue>, pages: I)
+                   -> Result<SparseBindFuture<NowFuture>, SparseBindError>
+        where I: IntoIterator<Item = SparseImagePage>
+    {
+        let device = queue.device().clone();
+        SparseImage::bind_after(me, now(device), queue, pages)
+    }
+
+    /// Allocates memory for the given tiles and mip tails and binds it to the image, after an
+    /// existing future.
+    ///
+    /// Pages that are already resident are left untouched.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if one of the pages is out of range.
+    /// - Panics if the device of the image is not the same as the device of the future.
+    ///
+    pub fn bind_after<Fu, I>(me: &Arc<Self>, future: Fu, queue: Arc<Queue>, pages: I)
+                             -> Result<SparseBindFuture<Fu>, SparseBindError>
+        where Fu: GpuFuture,
+              I: IntoIterator<Item = SparseImagePage>
+    {
+        let mut resident = me.pages.lock().unwrap();
+
+        let device = me.image.device();
+        let mem_ty = device
+            .physical_device()
+            .memory_type_by_id(me.memory_type)
+            .unwrap();
+
+        let mut new_pages = Vec::new();
+        let mut opaque_binds = Vec::new();
+        let mut image_binds = Vec::new();
+
+        for page in pages {
+            let region = me.page_region(page);
+
+            if resident.contains_key(&page) || new_pages.iter().any(|&(p, _)| p == page) {
+                continue;
+            }
+
+            match region {
+                PageRegion::Tile { offset, extent } => {
+                    let (mip_level, array_layer) = match page {
+                        SparseImagePage::Tile {
+                            mip_level,
+                            array_layer,
+                            ..
+                        } => (mip_level, array_layer),
+                        _ => unreachable!(),
+                    };
+
+                    let mem = MemoryPool::alloc(&Device::standard_pool(device),
+                                                mem_ty,
+                                                me.page_size,
+                                                me.page_size,
+                                                AllocLayout::Optimal)?;
+                    let mem = Arc::new(mem);
+                    image_binds.push((color_aspect(),
+                                      mip_level,
+                                      array_layer,
+                                      offset,
+                                      extent,
+                                      Some(mem.clone())));
+                    new_pages.push((page, mem));
+                },
+                PageRegion::MipTail { offset, size } => {
+                    let mem = MemoryPool::alloc(&Device::standard_pool(device),
+                                                mem_ty,
+                                                size,
+                                                me.page_size,
+                                                AllocLayout::Optimal)?;
+                    let mem = Arc::new(mem);
+                    opaque_binds.push((offset, size, Some(mem.clone())));
+                    new_pages.push((page, mem));
+                },
+            }
+        }
+
+        let op = SparseBindOp::Image {
+            image: me.clone(),
+            opaque_binds: opaque_binds,
+            image_binds: image_binds,
+        };
+        let mut future = bind_sparse(future, queue, op)?;
+
+        let bound = new_pages.iter().map(|&(page, _)| page).collect::<Vec<_>>();
+        for (page, mem) in new_pages {
+            resident.insert(page, mem);
+        }
+
+        let residency = me.resident.clone();
+        future.on_finished(move || {
+            let mut residency = residency.lock().unwrap();
+            for &page in bound.iter() {
+                residency.insert(page);
+            }
+        });
+
+        Ok(future)
+    }
+
+    /// Unbinds the memory of the given tiles and mip tails. The memory is freed once the GPU has
+    /// finished executing the operation.
+    ///
+    /// Pages that aren't resident are left untouched.
+    ///
+    /// > **Note**: This is just a shortcut for `unbind_after(vulkano::sync::now(), ...)`.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if one of the pages is out of range.
+    ///
+    #[inline]
+    pub fn unbind<I>(me: &Arc<Self>, queue: Arc<Queue>, pages: I)
+                     -> Result<SparseBindFuture<NowFuture>, SparseBindError>
+        where I: IntoIterator<Item = SparseImagePage>
+    {
+        let device = queue.device().clone();
+        SparseImage::unbind_after(me, now(device), queue, pages)
+    }
+
+    /// Unbinds the memory of the given tiles and mip tails after an existing future. The memory
+    /// is freed once the GPU has finished executing the operation.
+    ///
+    /// Pages that aren't resident are left untouched.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if one of the pages is out of range.
+    /// - Panics if the device of the image is not the same as the device of the future.
+    ///
+    pub fn unbind_after<Fu, I>(me: &Arc<Self>, future: Fu, queue: Arc<Queue>, pages: I)
+                               -> Result<SparseBindFuture<Fu>, SparseBindError>
+        where Fu: GpuFuture,
+              I: IntoIterator<Item = SparseImagePage>
+    {
+        let mut resident = me.pages.lock().unwrap();
+
+        let mut unbound = Vec::new();
+        let mut opaque_binds = Vec::new();
+        let mut image_binds = Vec::new();
+
+        for page in pages {
+            let region = me.page_region(page);
+
+            if !resident.contains_key(&page) || unbound.contains(&page) {
+                continue;
+            }
+
+            match region {
+                PageRegion::Tile { offset, extent } => {
+                    let (mip_level, array_layer) = match page {
+                        SparseImagePage::Tile {
+                            mip_level,
+                            array_layer,
+                            ..
+                        } => (mip_level, array_layer),
+                        _ => unreachable!(),
+                    };
+
+                    image_binds.push((color_aspect(), mip_level, array_layer, offset, extent,
+                                      None));
+                },
+                PageRegion::MipTail { offset, size } => {
+                    opaque_binds.push((offset, size, None));
+                },
+            }
+
+            unbound.push(page);
+        }
+
+        let op = SparseBindOp::Image {
+            image: me.clone(),
+            opaque_binds: opaque_binds,
+            image_binds: image_binds,
+        };
+        let mut future = bind_sparse(future, queue, op)?;
+
+        // The future holds the memory of the unbound pages until the GPU has finished unbinding.
+        for page in unbound.iter() {
+            let mem = resident.remove(page).unwrap();
+            future.keep_alive(mem);
+        }
+
+        let residency = me.resident.clone();
+        future.on_finished(move || {
+            let mut residency = residency.lock().unwrap();
+            for page in unbound.iter() {
+                residency.remove(page);
+            }
+        });
+
+        Ok(future)
+    }
+}
+
+#[inline]
+fn color_aspect() -> UnsafeCommandBufferBuilderImageAspect {
+    UnsafeCommandBufferBuilderImageAspect {
+        color: true,
+        depth: false,
+        stencil: false,
+    }
+}
+
+unsafe impl<F> ImageAccess for SparseImage<F>
+    where F: 'static + Send + Sync
+{
+    #[inline]
+    fn inner(&self) -> ImageInner {
+        ImageInner {
+            image: &self.image,
+            first_layer: 0,
+            num_layers: self.image.dimensions().array_layers() as usize,
+            first_mipmap_level: 0,
+            num_mipmap_levels: self.image.mipmap_levels() as usize,
+        }
+    }
+
+    #[inline]
+    fn initial_layout_requirement(&self) -> ImageLayout {
+        ImageLayout::General
+    }
+
+    #[inline]
+    fn final_layout_requirement(&self) -> ImageLayout {
+        ImageLayout::General
+    }
+
+    #[inline]
+    fn conflict_key(&self, _: u32, _: u32, _: u32, _: u32) -> u64 {
+        self.image.key()
+    }
+
+    #[inline]
+    fn try_gpu_lock(&self, exclusive: bool, _: &Queue) -> Result<(), AccessError> {
+        self.gpu_lock.try_gpu_lock(exclusive)
+    }
+
+    #[inline]
+    unsafe fn increase_gpu_lock(&self) {
+        self.gpu_lock.increase_gpu_lock()
+    }
+
+    #[inline]
+    unsafe fn unlock(&self) {
+        self.gpu_lock.unlock()
+    }
+}
+
+unsafe impl<F> ImageClearValue<F::ClearValue> for SparseImage<F>
+    where F: FormatDesc + 'static + Send + Sync
+{
+    #[inline]
+    fn decode(&self, value: F::ClearValue) -> Option<ClearValue> {
+        Some(self.format.decode_clear_value(value))
+    }
+}
+
+unsafe impl<P, F> ImageContent<P> for SparseImage<F>
+    where F: 'static + Send + Sync
+{
+    #[inline]
+    fn matches_format(&self) -> bool {
+        true // FIXME:
+    }
+}
+
+unsafe impl<F> ImageViewAccess for SparseImage<F>
+    where F: 'static + Send + Sync
+{
+    #[inline]
+    fn parent(&self) -> &ImageAccess {
+        self
+    }
+
+    #[inline]
+    fn dimensions(&self) -> Dimensions {
+        self.dimensions
+    }
+
+    #[inline]
+    fn inner(&self) -> &UnsafeImageView {
+        &self.view
+    }
+
+    #[inline]
+    fn descriptor_set_storage_image_layout(&self) -> ImageLayout {
+        ImageLayout::General
+    }
+
+    #[inline]
+    fn descriptor_set_combined_image_sampler_layout(&self) -> ImageLayout {
+        ImageLayout::General
+    }
+
+    #[inline]
+    fn descriptor_set_sampled_image_layout(&self) -> ImageLayout {
+        ImageLayout::General
+    }
+
+    #[inline]
+    fn descriptor_set_input_attachment_layout(&self) -> ImageLayout {
+        ImageLayout::General
+    }
+
+    #[inline]
+    fn identity_swizzle(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SparseImage;
+    use super::SparseImagePage;
+    use format::Format;
+    use image::Dimensions;
+    use image::ImageUsage;
+    use image::MipmapsCount;
+    use image::sys::ImageCreationError;
+    use sync::GpuFuture;
+
+    #[test]
+    fn missing_feature() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let usage = ImageUsage {
+            sampled: true,
+            ..ImageUsage::none()
+        };
+
+        match SparseImage::new(device,
+                               Dimensions::Dim2d {
+                                   width: 1024,
+                                   height: 1024,
+                               },
+                               Format::R8G8B8A8Unorm,
+                               MipmapsCount::Log2,
+                               usage,
+                               Some(queue.family())) {
+            Err(ImageCreationError::SparseBindingFeatureNotEnabled) => (),
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn bind_unbind() {
+        let (device, queue) = gfx_dev_and_queue!(sparse_binding, sparse_residency_image2d);
+
+        if !queue.family().supports_sparse_binding() {
+            return;
+        }
+
+        let usage = ImageUsage {
+            sampled: true,
+            ..ImageUsage::none()
+        };
+
+        let image = SparseImage::new(device,
+                                     Dimensions::Dim2d {
+                                         width: 1024,
+                                         height: 1024,
+                                     },
+                                     Format::R8G8B8A8Unorm,
+                                     MipmapsCount::Log2,
+                                     usage,
+                                     Some(queue.family()))
+            .unwrap();
+        assert_eq!(image.num_resident_pages(), 0);
+
+        let tile = SparseImagePage::Tile {
+            mip_level: 0,
+            array_layer: 0,
+            x: 0,
+            y: 0,
+            z: 0,
+        };
+
+        SparseImage::bind(&image, queue.clone(), Some(tile))
+            .unwrap()
+            .then_signal_fence_and_flush()
+            .unwrap()
+            .wait(None)
+            .unwrap();
+        assert!(image.is_resident(tile));
+        assert_eq!(image.num_resident_pages(), 1);
+
+        SparseImage::unbind(&image, queue.clone(), Some(tile))
+            .unwrap()
+            .then_signal_fence_and_flush()
+            .unwrap()
+            .wait(None)
+            .unwrap();
+        assert!(!image.is_resident(tile));
+        assert_eq!(image.num_resident_pages(), 0);
+    }
+}
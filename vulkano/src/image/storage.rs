@@ -67,10 +67,32 @@ pub struct StorageImage<F, A = Arc<StdMemoryPool>>
 
 impl<F> StorageImage<F> {
     /// Creates a new image with the given dimensions and format.
+    #[inline]
     pub fn new<'a, I>(device: Arc<Device>, dimensions: Dimensions, format: F, queue_families: I)
                       -> Result<Arc<StorageImage<F>>, ImageCreationError>
         where F: FormatDesc,
               I: IntoIterator<Item = QueueFamily<'a>>
+    {
+        StorageImage::new_impl(device, dimensions, format, queue_families, false)
+    }
+
+    /// Same as `new`, but `ImageView`s of the image can have a format that is different from
+    /// `format` but compatible with it.
+    #[inline]
+    pub fn with_mutable_format<'a, I>(device: Arc<Device>, dimensions: Dimensions, format: F,
+                                      queue_families: I)
+                                      -> Result<Arc<StorageImage<F>>, ImageCreationError>
+        where F: FormatDesc,
+              I: IntoIterator<Item = QueueFamily<'a>>
+    {
+        StorageImage::new_impl(device, dimensions, format, queue_families, true)
+    }
+
+    fn new_impl<'a, I>(device: Arc<Device>, dimensions: Dimensions, format: F, queue_families: I,
+                       mutable_format: bool)
+                       -> Result<Arc<StorageImage<F>>, ImageCreationError>
+        where F: FormatDesc,
+              I: IntoIterator<Item = QueueFamily<'a>>
     {
         let is_depth = match format.format().ty() {
             FormatTy::Depth => true,
@@ -103,15 +125,27 @@ impl<F> StorageImage<F> {
                 Sharing::Exclusive
             };
 
-            UnsafeImage::new(device.clone(),
-                             usage,
-                             format.format(),
-                             dimensions.to_image_dimensions(),
-                             1,
-                             1,
-                             Sharing::Exclusive::<Empty<u32>>,
-                             false,
-                             false)?
+            if mutable_format {
+                UnsafeImage::new_mutable_format(device.clone(),
+                                                usage,
+                                                format.format(),
+                                                dimensions.to_image_dimensions(),
+                                                1,
+                                                1,
+                                                Sharing::Exclusive::<Empty<u32>>,
+                                                false,
+                                                false)?
+            } else {
+                UnsafeImage::new(device.clone(),
+                                 usage,
+                                 format.format(),
+                                 dimensions.to_image_dimensions(),
+                                 1,
+                                 1,
+                                 Sharing::Exclusive::<Empty<u32>>,
+                                 false,
+                                 false)?
+            }
         };
 
         let mem_ty = {
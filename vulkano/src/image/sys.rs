@@ -21,12 +21,14 @@ use std::ops::Range;
 use std::ptr;
 use std::sync::Arc;
 
+use buffer::sys::SparseLevel;
 use device::Device;
 use format::Format;
 use format::FormatTy;
 use image::ImageDimensions;
 use image::ImageUsage;
 use image::MipmapsCount;
+use image::Swizzle;
 use image::ViewType;
 use memory::DeviceMemory;
 use memory::MemoryRequirements;
@@ -57,6 +59,9 @@ pub struct UnsafeImage {
     usage: vk::ImageUsageFlagBits,
     format: Format,
 
+    // True if views of this image can have a different format than the image.
+    mutable_format: bool,
+
     dimensions: ImageDimensions,
     samples: u32,
     mipmaps: u32,
@@ -99,14 +104,93 @@ impl UnsafeImage {
                               mipmaps.into(),
                               sharing,
                               linear_tiling,
-                              preinitialized_layout)
+                              preinitialized_layout,
+                              false,
+                              SparseLevel::none())
+    }
+
+    /// Same as `new`, but views of the image can be created with a format that is different from
+    /// `format` but compatible with it.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if one of the dimensions is 0.
+    /// - Panics if the number of mipmaps is 0.
+    /// - Panics if the number of samples is 0.
+    ///
+    #[inline]
+    pub unsafe fn new_mutable_format<'a, Mi, I>(device: Arc<Device>, usage: ImageUsage,
+                                                format: Format, dimensions: ImageDimensions,
+                                                num_samples: u32, mipmaps: Mi,
+                                                sharing: Sharing<I>, linear_tiling: bool,
+                                                preinitialized_layout: bool)
+                                                -> Result<(UnsafeImage, MemoryRequirements),
+                                                          ImageCreationError>
+        where Mi: Into<MipmapsCount>,
+              I: Iterator<Item = u32>
+    {
+        let sharing = match sharing {
+            Sharing::Exclusive => (vk::SHARING_MODE_EXCLUSIVE, SmallVec::<[u32; 8]>::new()),
+            Sharing::Concurrent(ids) => (vk::SHARING_MODE_CONCURRENT, ids.collect()),
+        };
+
+        UnsafeImage::new_impl(device,
+                              usage,
+                              format,
+                              dimensions,
+                              num_samples,
+                              mipmaps.into(),
+                              sharing,
+                              linear_tiling,
+                              preinitialized_layout,
+                              true,
+                              SparseLevel::none())
+    }
+
+    /// Creates a new image whose memory is bound with sparse binding operations. No memory is
+    /// allocated for it.
+    ///
+    /// The image uses optimal tiling and starts in the undefined layout.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if one of the dimensions is 0.
+    /// - Panics if the number of mipmaps is 0.
+    /// - Panics if the number of samples is 0.
+    ///
+    #[inline]
+    pub unsafe fn new_sparse<'a, Mi, I>(device: Arc<Device>, usage: ImageUsage, format: Format,
+                                        dimensions: ImageDimensions, num_samples: u32,
+                                        mipmaps: Mi, sharing: Sharing<I>, sparse: SparseLevel)
+                                        -> Result<(UnsafeImage, MemoryRequirements),
+                                                  ImageCreationError>
+        where Mi: Into<MipmapsCount>,
+              I: Iterator<Item = u32>
+    {
+        let sharing = match sharing {
+            Sharing::Exclusive => (vk::SHARING_MODE_EXCLUSIVE, SmallVec::<[u32; 8]>::new()),
+            Sharing::Concurrent(ids) => (vk::SHARING_MODE_CONCURRENT, ids.collect()),
+        };
+
+        UnsafeImage::new_impl(device,
+                              usage,
+                              format,
+                              dimensions,
+                              num_samples,
+                              mipmaps.into(),
+                              sharing,
+                              false,
+                              false,
+                              false,
+                              sparse)
     }
 
     // Non-templated version to avoid inlining and improve compile times.
     unsafe fn new_impl(device: Arc<Device>, usage: ImageUsage, format: Format,
                        dimensions: ImageDimensions, num_samples: u32, mipmaps: MipmapsCount,
                        (sh_mode, sh_indices): (vk::SharingMode, SmallVec<[u32; 8]>),
-                       linear_tiling: bool, preinitialized_layout: bool)
+                       linear_tiling: bool, preinitialized_layout: bool, mutable_format: bool,
+                       sparse: SparseLevel)
                        -> Result<(UnsafeImage, MemoryRequirements), ImageCreationError> {
         // TODO: doesn't check that the proper features are enabled
 
@@ -405,6 +489,46 @@ impl UnsafeImage {
             },
         };
 
+        // Checking sparse features.
+        if !sparse.sparse && (sparse.sparse_residency || sparse.sparse_aliased) {
+            return Err(ImageCreationError::SparseResidencyWithoutBinding);
+        }
+        if sparse.sparse && !device.enabled_features().sparse_binding {
+            return Err(ImageCreationError::SparseBindingFeatureNotEnabled);
+        }
+        if sparse.sparse_residency {
+            if linear_tiling {
+                return Err(ImageCreationError::SparseResidencyNotSupported);
+            }
+            let features = device.enabled_features();
+            let enabled = match ty {
+                vk::IMAGE_TYPE_2D => features.sparse_residency_image2d,
+                vk::IMAGE_TYPE_3D => features.sparse_residency_image3d,
+                _ => return Err(ImageCreationError::SparseResidencyNotSupported),
+            };
+            let enabled = enabled &&
+                match num_samples {
+                    1 => true,
+                    2 => features.sparse_residency2_samples,
+                    4 => features.sparse_residency4_samples,
+                    8 => features.sparse_residency8_samples,
+                    16 => features.sparse_residency16_samples,
+                    _ => false,
+                };
+            if !enabled {
+                return Err(ImageCreationError::SparseResidencyImageFeatureNotEnabled);
+            }
+        }
+        if sparse.sparse_aliased && !device.enabled_features().sparse_residency_aliased {
+            return Err(ImageCreationError::SparseResidencyAliasedFeatureNotEnabled);
+        }
+        let flags = flags | sparse_level_to_image_flags(sparse);
+        let flags = if mutable_format {
+            flags | vk::IMAGE_CREATE_MUTABLE_FORMAT_BIT
+        } else {
+            flags
+        };
+
         // Checking the dimensions against the limits.
         if array_layers > device.physical_device().limits().max_image_array_layers() {
             let err = ImageCreationError::UnsupportedDimensions { dimensions: dimensions };
@@ -462,7 +586,7 @@ impl UnsafeImage {
                                                                 ty,
                                                                 tiling,
                                                                 usage,
-                                                                0, /* TODO */
+                                                                flags,
                                                                 &mut output);
 
             match check_errors(r) {
@@ -513,7 +637,7 @@ impl UnsafeImage {
             let mut output = mem::uninitialized();
             check_errors(vk.CreateImage(device.internal_object(),
                                         &infos,
-                                        ptr::null(),
+                                        device.allocation_callbacks().as_ptr(),
                                         &mut output))?;
             output
         };
@@ -530,6 +654,7 @@ impl UnsafeImage {
             image: image,
             usage: usage,
             format: format,
+            mutable_format: mutable_format,
             dimensions: dimensions,
             samples: num_samples,
             mipmaps: mipmaps,
@@ -559,6 +684,7 @@ impl UnsafeImage {
             image: handle,
             usage: usage,
             format: format,
+            mutable_format: false,
             dimensions: dimensions,
             samples: samples,
             mipmaps: mipmaps,
@@ -588,6 +714,32 @@ impl UnsafeImage {
         Ok(())
     }
 
+    /// Queries the sparse memory requirements of the image. Calls
+    /// `vkGetImageSparseMemoryRequirements`.
+    ///
+    /// Returns one entry per aspect or group of aspects of the image. The list is empty if the
+    /// image wasn't created with sparse residency.
+    pub fn sparse_memory_requirements(&self) -> Vec<SparseImageMemoryRequirements> {
+        unsafe {
+            let vk = self.device.pointers();
+
+            let mut num = 0;
+            vk.GetImageSparseMemoryRequirements(self.device.internal_object(),
+                                                self.image,
+                                                &mut num,
+                                                ptr::null_mut());
+
+            let mut reqs = Vec::with_capacity(num as usize);
+            vk.GetImageSparseMemoryRequirements(self.device.internal_object(),
+                                                self.image,
+                                                &mut num,
+                                                reqs.as_mut_ptr());
+            reqs.set_len(num as usize);
+
+            reqs.into_iter().map(From::from).collect()
+        }
+    }
+
     #[inline]
     pub fn device(&self) -> &Arc<Device> {
         &self.device
@@ -598,6 +750,13 @@ impl UnsafeImage {
         self.format
     }
 
+    /// Returns true if views of this image can have a format that is different from the format
+    /// of the image.
+    #[inline]
+    pub fn mutable_format(&self) -> bool {
+        self.mutable_format
+    }
+
     #[inline]
     pub fn mipmap_levels(&self) -> u32 {
         self.mipmaps
@@ -723,6 +882,12 @@ impl UnsafeImage {
         (self.format_features & vk::FORMAT_FEATURE_SAMPLED_IMAGE_FILTER_LINEAR_BIT) != 0
     }
 
+    /// Returns true if the image can be used as a storage image.
+    #[inline]
+    pub fn supports_storage(&self) -> bool {
+        (self.format_features & vk::FORMAT_FEATURE_STORAGE_IMAGE_BIT) != 0
+    }
+
     #[inline]
     pub fn usage_transfer_source(&self) -> bool {
         (self.usage & vk::IMAGE_USAGE_TRANSFER_SRC_BIT) != 0
@@ -789,7 +954,9 @@ impl Drop for UnsafeImage {
 
         unsafe {
             let vk = self.device.pointers();
-            vk.DestroyImage(self.device.internal_object(), self.image, ptr::null());
+            vk.DestroyImage(self.device.internal_object(),
+                            self.image,
+                            self.device.allocation_callbacks().as_ptr());
         }
     }
 }
@@ -814,6 +981,16 @@ pub enum ImageCreationError {
     UnsupportedUsage,
     /// The `shader_storage_image_multisample` feature must be enabled to create such an image.
     ShaderStorageImageMultisampleFeatureNotEnabled,
+    /// Sparse binding was requested but the corresponding feature wasn't enabled.
+    SparseBindingFeatureNotEnabled,
+    /// Sparse residency was requested but the corresponding feature wasn't enabled.
+    SparseResidencyImageFeatureNotEnabled,
+    /// Sparse aliasing was requested but the corresponding feature wasn't enabled.
+    SparseResidencyAliasedFeatureNotEnabled,
+    /// Sparse residency or sparse aliasing was requested without sparse binding.
+    SparseResidencyWithoutBinding,
+    /// Sparse residency was requested for a 1D image or for an image with linear tiling.
+    SparseResidencyNotSupported,
 }
 
 impl error::Error for ImageCreationError {
@@ -836,6 +1013,21 @@ impl error::Error for ImageCreationError {
                 "the `shader_storage_image_multisample` feature must be enabled to create such \
                  an image"
             },
+            ImageCreationError::SparseBindingFeatureNotEnabled => {
+                "sparse binding was requested but the corresponding feature wasn't enabled"
+            },
+            ImageCreationError::SparseResidencyImageFeatureNotEnabled => {
+                "sparse residency was requested but the corresponding feature wasn't enabled"
+            },
+            ImageCreationError::SparseResidencyAliasedFeatureNotEnabled => {
+                "sparse aliasing was requested but the corresponding feature wasn't enabled"
+            },
+            ImageCreationError::SparseResidencyWithoutBinding => {
+                "sparse residency or sparse aliasing was requested without sparse binding"
+            },
+            ImageCreationError::SparseResidencyNotSupported => {
+                "sparse residency was requested for a 1D image or for an image with linear tiling"
+            },
         }
     }
 
@@ -873,6 +1065,84 @@ impl From<Error> for ImageCreationError {
     }
 }
 
+// Turns a `SparseLevel` into the flags to pass to `vkCreateImage`.
+#[inline]
+fn sparse_level_to_image_flags(sparse: SparseLevel) -> vk::ImageCreateFlagBits {
+    let mut result = 0;
+    if sparse.sparse {
+        result |= vk::IMAGE_CREATE_SPARSE_BINDING_BIT;
+    }
+    if sparse.sparse_residency {
+        result |= vk::IMAGE_CREATE_SPARSE_RESIDENCY_BIT;
+    }
+    if sparse.sparse_aliased {
+        result |= vk::IMAGE_CREATE_SPARSE_ALIASED_BIT;
+    }
+    result
+}
+
+/// Describes how the memory of an aspect of an image with sparse residency is split.
+///
+/// Obtained by calling `sparse_memory_requirements` on the image.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct SparseImageMemoryRequirements {
+    /// True if these requirements apply to the color aspect.
+    pub color: bool,
+    /// True if these requirements apply to the depth aspect.
+    pub depth: bool,
+    /// True if these requirements apply to the stencil aspect.
+    pub stencil: bool,
+    /// True if these requirements apply to the metadata aspect. The metadata must be bound
+    /// entirely before the image can be used.
+    pub metadata: bool,
+
+    /// Width, height and depth in texels of a single sparse block.
+    pub granularity: [u32; 3],
+    /// If true, all the array layers share the same mip tail.
+    pub single_mip_tail: bool,
+    /// If true, mipmap levels whose dimensions aren't a multiple of the granularity are part of
+    /// the mip tail.
+    pub aligned_mip_size: bool,
+    /// If true, the granularity doesn't correspond to the standard sparse block sizes.
+    pub nonstandard_block_size: bool,
+
+    /// First mipmap level that is part of the mip tail.
+    pub mip_tail_first_lod: u32,
+    /// Size in bytes of the mip tail of one array layer, or of the whole image if
+    /// `single_mip_tail` is true.
+    pub mip_tail_size: usize,
+    /// Offset in the opaque memory of the image of the mip tail of the first array layer.
+    pub mip_tail_offset: usize,
+    /// Offset in bytes between the mip tails of two successive array layers.
+    pub mip_tail_stride: usize,
+}
+
+#[doc(hidden)]
+impl From<vk::SparseImageMemoryRequirements> for SparseImageMemoryRequirements {
+    #[inline]
+    fn from(reqs: vk::SparseImageMemoryRequirements) -> SparseImageMemoryRequirements {
+        let aspects = reqs.formatProperties.aspectMask;
+        let flags = reqs.formatProperties.flags;
+        let granularity = reqs.formatProperties.imageGranularity;
+
+        SparseImageMemoryRequirements {
+            color: (aspects & vk::IMAGE_ASPECT_COLOR_BIT) != 0,
+            depth: (aspects & vk::IMAGE_ASPECT_DEPTH_BIT) != 0,
+            stencil: (aspects & vk::IMAGE_ASPECT_STENCIL_BIT) != 0,
+            metadata: (aspects & vk::IMAGE_ASPECT_METADATA_BIT) != 0,
+            granularity: [granularity.width, granularity.height, granularity.depth],
+            single_mip_tail: (flags & vk::SPARSE_IMAGE_FORMAT_SINGLE_MIPTAIL_BIT) != 0,
+            aligned_mip_size: (flags & vk::SPARSE_IMAGE_FORMAT_ALIGNED_MIP_SIZE_BIT) != 0,
+            nonstandard_block_size: (flags & vk::SPARSE_IMAGE_FORMAT_NONSTANDARD_BLOCK_SIZE_BIT) !=
+                0,
+            mip_tail_first_lod: reqs.imageMipTailFirstLod,
+            mip_tail_size: reqs.imageMipTailSize as usize,
+            mip_tail_offset: reqs.imageMipTailOffset as usize,
+            mip_tail_stride: reqs.imageMipTailStride as usize,
+        }
+    }
+}
+
 /// Describes the memory layout of an image with linear tiling.
 ///
 /// Obtained by calling `*_linear_layout` on the image.
@@ -903,13 +1173,37 @@ pub struct UnsafeImageView {
     usage: vk::ImageUsageFlagBits,
     identity_swizzle: bool,
     format: Format,
+    mipmap_levels: Range<u32>,
+    array_layers: Range<u32>,
 }
 
 impl UnsafeImageView {
     /// See the docs of new().
+    #[inline]
     pub unsafe fn raw(image: &UnsafeImage, ty: ViewType, mipmap_levels: Range<u32>,
                       array_layers: Range<u32>)
                       -> Result<UnsafeImageView, OomError> {
+        UnsafeImageView::raw_with_format_and_swizzle(image,
+                                                     ty,
+                                                     mipmap_levels,
+                                                     array_layers,
+                                                     image.format,
+                                                     Swizzle::default())
+    }
+
+    /// Same as `raw()`, but the view can have a format that is different from the image's and
+    /// can swizzle the components.
+    ///
+    /// # Safety
+    ///
+    /// - If `format` is not the format of the image, then the image must have been created with
+    ///   the mutable format flag and `format` must be compatible with the format of the image.
+    ///
+    pub unsafe fn raw_with_format_and_swizzle(image: &UnsafeImage, ty: ViewType,
+                                              mipmap_levels: Range<u32>,
+                                              array_layers: Range<u32>, format: Format,
+                                              swizzle: Swizzle)
+                                              -> Result<UnsafeImageView, OomError> {
         let vk = image.device.pointers();
 
         assert!(mipmap_levels.end > mipmap_levels.start);
@@ -954,13 +1248,13 @@ impl UnsafeImageView {
                 flags: 0, // reserved
                 image: image.internal_object(),
                 viewType: view_type,
-                format: image.format as u32,
+                format: format as u32,
                 components: vk::ComponentMapping {
-                    r: 0,
-                    g: 0,
-                    b: 0,
-                    a: 0,
-                }, // FIXME:
+                    r: swizzle.r as u32,
+                    g: swizzle.g as u32,
+                    b: swizzle.b as u32,
+                    a: swizzle.a as u32,
+                },
                 subresourceRange: vk::ImageSubresourceRange {
                     aspectMask: aspect_mask,
                     baseMipLevel: mipmap_levels.start,
@@ -973,7 +1267,7 @@ impl UnsafeImageView {
             let mut output = mem::uninitialized();
             check_errors(vk.CreateImageView(image.device.internal_object(),
                                             &infos,
-                                            ptr::null(),
+                                            image.device.allocation_callbacks().as_ptr(),
                                             &mut output))?;
             output
         };
@@ -982,8 +1276,10 @@ impl UnsafeImageView {
                view: view,
                device: image.device.clone(),
                usage: image.usage,
-               identity_swizzle: true, // FIXME:
-               format: image.format,
+               identity_swizzle: swizzle.is_identity(),
+               format: format,
+               mipmap_levels: mipmap_levels,
+               array_layers: array_layers,
            })
     }
 
@@ -1014,6 +1310,24 @@ impl UnsafeImageView {
         self.format
     }
 
+    /// Returns true if the view doesn't swizzle the components of the image.
+    #[inline]
+    pub fn identity_swizzle(&self) -> bool {
+        self.identity_swizzle
+    }
+
+    /// Returns the mipmap levels of the image that the view gives access to.
+    #[inline]
+    pub fn mipmap_levels(&self) -> Range<u32> {
+        self.mipmap_levels.clone()
+    }
+
+    /// Returns the array layers of the image that the view gives access to.
+    #[inline]
+    pub fn array_layers(&self) -> Range<u32> {
+        self.array_layers.clone()
+    }
+
     #[inline]
     pub fn usage_transfer_source(&self) -> bool {
         (self.usage & vk::IMAGE_USAGE_TRANSFER_SRC_BIT) != 0
@@ -1076,7 +1390,9 @@ impl Drop for UnsafeImageView {
     fn drop(&mut self) {
         unsafe {
             let vk = self.device.pointers();
-            vk.DestroyImageView(self.device.internal_object(), self.view, ptr::null());
+            vk.DestroyImageView(self.device.internal_object(),
+                                self.view,
+                                self.device.allocation_callbacks().as_ptr());
         }
     }
 }
@@ -1090,6 +1406,7 @@ mod tests {
     use super::ImageUsage;
     use super::UnsafeImage;
 
+    use buffer::sys::SparseLevel;
     use format::Format;
     use image::ImageDimensions;
     use sync::Sharing;
@@ -1414,4 +1731,76 @@ mod tests {
             _ => panic!(),
         };
     }
+
+    #[test]
+    fn sparse_residency_without_binding() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        let usage = ImageUsage {
+            sampled: true,
+            ..ImageUsage::none()
+        };
+
+        let sparse = SparseLevel {
+            sparse: false,
+            sparse_residency: true,
+            sparse_aliased: false,
+        };
+
+        let res = unsafe {
+            UnsafeImage::new_sparse(device,
+                                    usage,
+                                    Format::R8G8B8A8Unorm,
+                                    ImageDimensions::Dim2d {
+                                        width: 32,
+                                        height: 32,
+                                        array_layers: 1,
+                                        cubemap_compatible: false,
+                                    },
+                                    1,
+                                    1,
+                                    Sharing::Exclusive::<Empty<_>>,
+                                    sparse)
+        };
+
+        match res {
+            Err(ImageCreationError::SparseResidencyWithoutBinding) => (),
+            _ => panic!(),
+        };
+    }
+
+    #[test]
+    fn sparse_residency_1d() {
+        let (device, _) = gfx_dev_and_queue!(sparse_binding);
+
+        let usage = ImageUsage {
+            sampled: true,
+            ..ImageUsage::none()
+        };
+
+        let sparse = SparseLevel {
+            sparse: true,
+            sparse_residency: true,
+            sparse_aliased: false,
+        };
+
+        let res = unsafe {
+            UnsafeImage::new_sparse(device,
+                                    usage,
+                                    Format::R8G8B8A8Unorm,
+                                    ImageDimensions::Dim1d {
+                                        width: 32,
+                                        array_layers: 1,
+                                    },
+                                    1,
+                                    1,
+                                    Sharing::Exclusive::<Empty<_>>,
+                                    sparse)
+        };
+
+        match res {
+            Err(ImageCreationError::SparseResidencyNotSupported) => (),
+            _ => panic!(),
+        };
+    }
 }
@@ -11,6 +11,7 @@ use buffer::BufferAccess;
 use device::Queue;
 use format::ClearValue;
 use format::Format;
+use format::PossibleCompressedFormatDesc;
 use format::PossibleDepthFormatDesc;
 use format::PossibleDepthStencilFormatDesc;
 use format::PossibleFloatFormatDesc;
@@ -43,7 +44,7 @@ pub unsafe trait ImageAccess {
     #[inline]
     fn has_color(&self) -> bool {
         let format = self.format();
-        format.is_float() || format.is_uint() || format.is_sint()
+        format.is_float() || format.is_uint() || format.is_sint() || format.is_compressed()
     }
 
     /// Returns true if the image has a depth component. In other words, if it is a depth or a
@@ -0,0 +1,630 @@
+// Copyright (c) 2017 The vulkano developers
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>,
+// at your option. All files in the project carrying such
+// notice may not be copied, modified, or distributed except
+// according to those terms.
+
+use std::error;
+use std::fmt;
+use std::ops::Range;
+use std::sync::Arc;
+
+use format::Format;
+use format::FormatTy;
+use image::Dimensions;
+use image::ImageDimensions;
+use image::ImageLayout;
+use image::Swizzle;
+use image::ViewType;
+use image::sys::UnsafeImageView;
+use image::traits::AttachmentImageView;
+use image::traits::ImageAccess;
+use image::traits::ImageViewAccess;
+
+use OomError;
+
+/// A view of a subresource range of an image.
+///
+/// The image types of vulkano each provide a view of the whole image. An `ImageView` can instead
+/// view a range of mipmap levels and array layers of any image, for example a single mipmap
+/// level in order to write to it from a compute shader, or a single face of a cubemap as a 2D
+/// image.
+///
+/// It is also possible to swizzle the components of the view and, if the image has been created
+/// with a mutable format, to interpret its content with a different but compatible format.
+///
+/// # Example
+///
+/// ```no_run
+/// # use std::sync::Arc;
+/// # use vulkano::device::Queue;
+/// use vulkano::format::Format;
+/// use vulkano::image::Dimensions;
+/// use vulkano::image::ImageView;
+/// use vulkano::image::ImmutableImage;
+/// use vulkano::image::ViewType;
+///
+/// # let queue: Arc<Queue> = return;
+/// let dimensions = Dimensions::Cubemap { size: 256 };
+/// let image = ImmutableImage::new(queue.device().clone(), dimensions, Format::R8G8B8A8Unorm,
+///                                 Some(queue.family())).unwrap();
+///
+/// // A 2D view of the third face of the cubemap.
+/// let face = ImageView::new(image, ViewType::Dim2d, 0 .. 1, 2 .. 3).unwrap();
+/// ```
+pub struct ImageView<I> {
+    image: I,
+    view: UnsafeImageView,
+    dimensions: Dimensions,
+    first_mipmap_level: u32,
+    num_mipmap_levels: u32,
+}
+
+impl<I> ImageView<I>
+    where I: ImageAccess
+{
+    /// Creates a view of the given mipmap levels and array layers of `image`, with the format of
+    /// the image and without swizzling.
+    ///
+    /// The levels and layers are relative to the part of the image described by
+    /// `ImageAccess::inner()`.
+    #[inline]
+    pub fn new(image: I, ty: ViewType, mipmap_levels: Range<u32>, array_layers: Range<u32>)
+               -> Result<Arc<ImageView<I>>, ImageViewCreationError> {
+        let format = image.format();
+        ImageView::with_format_and_swizzle(image,
+                                           ty,
+                                           mipmap_levels,
+                                           array_layers,
+                                           format,
+                                           Swizzle::default())
+    }
+
+    /// Same as `new`, but the view uses `format` and swizzles its components with `swizzle`.
+    ///
+    /// If `format` is different from the format of the image, then the image must have been
+    /// created with a mutable format and `format` must be compatible with the format of the
+    /// image. Two uncompressed color formats are compatible if their texels have the same size,
+    /// and two compressed formats are compatible if they only differ by their sRGB-ness.
+    ///
+    /// Note that a view that doesn't have identity swizzling can't be used as a framebuffer
+    /// attachment.
+    pub fn with_format_and_swizzle(image: I, ty: ViewType, mipmap_levels: Range<u32>,
+                                   array_layers: Range<u32>, format: Format, swizzle: Swizzle)
+                                   -> Result<Arc<ImageView<I>>, ImageViewCreationError> {
+        let (view, dimensions) = {
+            let inner = image.inner();
+
+            if mipmap_levels.start >= mipmap_levels.end ||
+                mipmap_levels.end as usize > inner.num_mipmap_levels
+            {
+                return Err(ImageViewCreationError::MipmapLevelsOutOfRange);
+            }
+
+            if array_layers.start >= array_layers.end ||
+                array_layers.end as usize > inner.num_layers
+            {
+                return Err(ImageViewCreationError::ArrayLayersOutOfRange);
+            }
+
+            let image_format = inner.image.format();
+            if format != image_format {
+                if !inner.image.mutable_format() {
+                    return Err(ImageViewCreationError::FormatNotMutable);
+                }
+                if !formats_compatible(image_format, format) {
+                    return Err(ImageViewCreationError::IncompatibleFormat {
+                                   image_format: image_format,
+                                   view_format: format,
+                               });
+                }
+            }
+
+            let first_level = inner.first_mipmap_level as u32 + mipmap_levels.start;
+            let num_levels = mipmap_levels.end - mipmap_levels.start;
+            let first_layer = inner.first_layer as u32 + array_layers.start;
+            let num_layers = array_layers.end - array_layers.start;
+
+            let level_dims = inner
+                .image
+                .dimensions()
+                .mipmap_dimensions(first_level)
+                .unwrap();
+            let dimensions = match view_dimensions(level_dims, ty, num_layers) {
+                Some(d) => d,
+                None => return Err(ImageViewCreationError::IncompatibleViewType),
+            };
+
+            let view = unsafe {
+                UnsafeImageView::raw_with_format_and_swizzle(inner.image,
+                                                             ty,
+                                                             first_level ..
+                                                                 first_level + num_levels,
+                                                             first_layer ..
+                                                                 first_layer + num_layers,
+                                                             format,
+                                                             swizzle)?
+            };
+
+            (view, dimensions)
+        };
+
+        Ok(Arc::new(ImageView {
+                        image: image,
+                        view: view,
+                        dimensions: dimensions,
+                        first_mipmap_level: mipmap_levels.start,
+                        num_mipmap_levels: mipmap_levels.end - mipmap_levels.start,
+                    }))
+    }
+
+    /// Returns the image that this view was created from.
+    #[inline]
+    pub fn image(&self) -> &I {
+        &self.image
+    }
+
+    /// Returns the range of mipmap levels of the image covered by this view.
+    #[inline]
+    pub fn mipmap_levels(&self) -> Range<u32> {
+        self.first_mipmap_level .. self.first_mipmap_level + self.num_mipmap_levels
+    }
+
+    // Layout in which the image is when it is accessed by a shader, other than as a storage image.
+    #[inline]
+    fn shader_layout(&self) -> ImageLayout {
+        match self.image.final_layout_requirement() {
+            ImageLayout::General => ImageLayout::General,
+            _ => ImageLayout::ShaderReadOnlyOptimal,
+        }
+    }
+}
+
+unsafe impl<I> ImageViewAccess for ImageView<I>
+    where I: ImageAccess
+{
+    #[inline]
+    fn parent(&self) -> &ImageAccess {
+        &self.image
+    }
+
+    #[inline]
+    fn dimensions(&self) -> Dimensions {
+        self.dimensions
+    }
+
+    #[inline]
+    fn inner(&self) -> &UnsafeImageView {
+        &self.view
+    }
+
+    #[inline]
+    fn descriptor_set_storage_image_layout(&self) -> ImageLayout {
+        ImageLayout::General
+    }
+
+    #[inline]
+    fn descriptor_set_combined_image_sampler_layout(&self) -> ImageLayout {
+        self.shader_layout()
+    }
+
+    #[inline]
+    fn descriptor_set_sampled_image_layout(&self) -> ImageLayout {
+        self.shader_layout()
+    }
+
+    #[inline]
+    fn descriptor_set_input_attachment_layout(&self) -> ImageLayout {
+        self.shader_layout()
+    }
+
+    #[inline]
+    fn identity_swizzle(&self) -> bool {
+        self.view.identity_swizzle()
+    }
+}
+
+unsafe impl<I> AttachmentImageView for ImageView<I>
+    where I: ImageAccess
+{
+    fn accept(&self, initial_layout: ImageLayout, final_layout: ImageLayout) -> bool {
+        // Attachments must have identity swizzling and a single mipmap level.
+        if !self.identity_swizzle() || self.num_mipmap_levels != 1 {
+            return false;
+        }
+
+        if final_layout == ImageLayout::Undefined || final_layout == ImageLayout::Preinitialized {
+            return false;
+        }
+
+        let depth_stencil = self.image.has_depth() || self.image.has_stencil();
+        let valid = |layout| match layout {
+            ImageLayout::ColorAttachmentOptimal => !depth_stencil,
+            ImageLayout::DepthStencilAttachmentOptimal |
+            ImageLayout::DepthStencilReadOnlyOptimal => depth_stencil,
+            _ => true,
+        };
+
+        valid(initial_layout) && valid(final_layout)
+    }
+}
+
+impl<I> fmt::Debug for ImageView<I>
+    where I: fmt::Debug
+{
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        fmt.debug_struct("ImageView")
+            .field("image", &self.image)
+            .field("view", &self.view)
+            .field("dimensions", &self.dimensions)
+            .finish()
+    }
+}
+
+// Returns the dimensions of a view of type `ty` with `num_layers` array layers, or `None` if this
+// type of view can't be created from an image with the given dimensions.
+fn view_dimensions(dims: ImageDimensions, ty: ViewType, num_layers: u32) -> Option<Dimensions> {
+    Some(match (dims, ty) {
+             (ImageDimensions::Dim1d { width, .. }, ViewType::Dim1d) if num_layers == 1 => {
+                 Dimensions::Dim1d { width: width }
+             },
+             (ImageDimensions::Dim1d { width, .. }, ViewType::Dim1dArray) => {
+                 Dimensions::Dim1dArray {
+                     width: width,
+                     array_layers: num_layers,
+                 }
+             },
+             (ImageDimensions::Dim2d { width, height, .. }, ViewType::Dim2d)
+                 if num_layers == 1 => {
+                 Dimensions::Dim2d {
+                     width: width,
+                     height: height,
+                 }
+             },
+             (ImageDimensions::Dim2d { width, height, .. }, ViewType::Dim2dArray) => {
+                 Dimensions::Dim2dArray {
+                     width: width,
+                     height: height,
+                     array_layers: num_layers,
+                 }
+             },
+             (ImageDimensions::Dim2d {
+                  width,
+                  cubemap_compatible: true,
+                  ..
+              },
+              ViewType::Cubemap) if num_layers == 6 => {
+                 Dimensions::Cubemap { size: width }
+             },
+             (ImageDimensions::Dim2d {
+                  width,
+                  cubemap_compatible: true,
+                  ..
+              },
+              ViewType::CubemapArray) if num_layers % 6 == 0 => {
+                 Dimensions::CubemapArray {
+                     size: width,
+                     array_layers: num_layers / 6,
+                 }
+             },
+             (ImageDimensions::Dim3d {
+                  width,
+                  height,
+                  depth,
+              },
+              ViewType::Dim3d) => {
+                 Dimensions::Dim3d {
+                     width: width,
+                     height: height,
+                     depth: depth,
+                 }
+             },
+             _ => return None,
+         })
+}
+
+// Compatibility classes of the compressed formats, as defined by the Vulkan specification. A view
+// of a compressed image can only have a format of the same class.
+const COMPRESSED_CLASSES: &'static [&'static [Format]] = &[
+    &[Format::BC1_RGBUnormBlock, Format::BC1_RGBSrgbBlock],
+    &[Format::BC1_RGBAUnormBlock, Format::BC1_RGBASrgbBlock],
+    &[Format::BC2UnormBlock, Format::BC2SrgbBlock],
+    &[Format::BC3UnormBlock, Format::BC3SrgbBlock],
+    &[Format::BC4UnormBlock, Format::BC4SnormBlock],
+    &[Format::BC5UnormBlock, Format::BC5SnormBlock],
+    &[Format::BC6HUfloatBlock, Format::BC6HSfloatBlock],
+    &[Format::BC7UnormBlock, Format::BC7SrgbBlock],
+    &[Format::ETC2_R8G8B8UnormBlock, Format::ETC2_R8G8B8SrgbBlock],
+    &[Format::ETC2_R8G8B8A1UnormBlock, Format::ETC2_R8G8B8A1SrgbBlock],
+    &[Format::ETC2_R8G8B8A8UnormBlock, Format::ETC2_R8G8B8A8SrgbBlock],
+    &[Format::EAC_R11UnormBlock, Format::EAC_R11SnormBlock],
+    &[Format::EAC_R11G11UnormBlock, Format::EAC_R11G11SnormBlock],
+    &[Format::ASTC_4x4UnormBlock, Format::ASTC_4x4SrgbBlock],
+    &[Format::ASTC_5x4UnormBlock, Format::ASTC_5x4SrgbBlock],
+    &[Format::ASTC_5x5UnormBlock, Format::ASTC_5x5SrgbBlock],
+    &[Format::ASTC_6x5UnormBlock, Format::ASTC_6x5SrgbBlock],
+    &[Format::ASTC_6x6UnormBlock, Format::ASTC_6x6SrgbBlock],
+    &[Format::ASTC_8x5UnormBlock, Format::ASTC_8x5SrgbBlock],
+    &[Format::ASTC_8x6UnormBlock, Format::ASTC_8x6SrgbBlock],
+    &[Format::ASTC_8x8UnormBlock, Format::ASTC_8x8SrgbBlock],
+    &[Format::ASTC_10x5UnormBlock, Format::ASTC_10x5SrgbBlock],
+    &[Format::ASTC_10x6UnormBlock, Format::ASTC_10x6SrgbBlock],
+    &[Format::ASTC_10x8UnormBlock, Format::ASTC_10x8SrgbBlock],
+    &[Format::ASTC_10x10UnormBlock, Format::ASTC_10x10SrgbBlock],
+    &[Format::ASTC_12x10UnormBlock, Format::ASTC_12x10SrgbBlock],
+    &[Format::ASTC_12x12UnormBlock, Format::ASTC_12x12SrgbBlock],
+];
+
+// Returns true if a view of format `view` can be created from an image of format `image` that
+// has been created with a mutable format.
+fn formats_compatible(image: Format, view: Format) -> bool {
+    match (image.ty(), view.ty()) {
+        (FormatTy::Compressed, FormatTy::Compressed) => {
+            image == view ||
+                COMPRESSED_CLASSES
+                    .iter()
+                    .any(|class| class.contains(&image) && class.contains(&view))
+        },
+        (FormatTy::Float, FormatTy::Float) |
+        (FormatTy::Float, FormatTy::Uint) |
+        (FormatTy::Float, FormatTy::Sint) |
+        (FormatTy::Uint, FormatTy::Float) |
+        (FormatTy::Uint, FormatTy::Uint) |
+        (FormatTy::Uint, FormatTy::Sint) |
+        (FormatTy::Sint, FormatTy::Float) |
+        (FormatTy::Sint, FormatTy::Uint) |
+        (FormatTy::Sint, FormatTy::Sint) => image.size() == view.size(),
+        _ => image == view,
+    }
+}
+
+/// Error that can happen when creating an `ImageView`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ImageViewCreationError {
+    /// Not enough memory.
+    OomError(OomError),
+
+    /// The range of mipmap levels is empty or out of the range of the image.
+    MipmapLevelsOutOfRange,
+
+    /// The range of array layers is empty or out of the range of the image.
+    ArrayLayersOutOfRange,
+
+    /// The type of view isn't compatible with the dimensions of the image or with the number of
+    /// array layers.
+    IncompatibleViewType,
+
+    /// The format of the view is different from the format of the image, but the image wasn't
+    /// created with a mutable format.
+    FormatNotMutable,
+
+    /// The format of the view isn't compatible with the format of the image.
+    IncompatibleFormat {
+        /// Format of the image.
+        image_format: Format,
+        /// Format requested for the view.
+        view_format: Format,
+    },
+}
+
+impl error::Error for ImageViewCreationError {
+    #[inline]
+    fn description(&self) -> &str {
+        match *self {
+            ImageViewCreationError::OomError(_) => "not enough memory available",
+            ImageViewCreationError::MipmapLevelsOutOfRange => {
+                "the range of mipmap levels is empty or out of the range of the image"
+            },
+            ImageViewCreationError::ArrayLayersOutOfRange => {
+                "the range of array layers is empty or out of the range of the image"
+            },
+            ImageViewCreationError::IncompatibleViewType => {
+                "the type of view isn't compatible with the dimensions of the image or with the \
+                 number of array layers"
+            },
+            ImageViewCreationError::FormatNotMutable => {
+                "the format of the view is different from the format of the image, but the image \
+                 wasn't created with a mutable format"
+            },
+            ImageViewCreationError::IncompatibleFormat { .. } => {
+                "the format of the view isn't compatible with the format of the image"
+            },
+        }
+    }
+
+    #[inline]
+    fn cause(&self) -> Option<&error::Error> {
+        match *self {
+            ImageViewCreationError::OomError(ref err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ImageViewCreationError {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(fmt, "{}", error::Error::description(self))
+    }
+}
+
+impl From<OomError> for ImageViewCreationError {
+    #[inline]
+    fn from(err: OomError) -> ImageViewCreationError {
+        ImageViewCreationError::OomError(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use format::Format;
+    use image::ComponentSwizzle;
+    use image::Dimensions;
+    use image::ImageLayout;
+    use image::ImageView;
+    use image::ImageViewAccess;
+    use image::ImageViewCreationError;
+    use image::ImmutableImage;
+    use image::MipmapsCount;
+    use image::StorageImage;
+    use image::Swizzle;
+    use image::ViewType;
+    use image::traits::AttachmentImageView;
+
+    use super::formats_compatible;
+
+    #[test]
+    fn mipmap_level_view() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let image = ImmutableImage::with_mipmaps(device,
+                                                 Dimensions::Dim2d {
+                                                     width: 64,
+                                                     height: 32,
+                                                 },
+                                                 Format::R8G8B8A8Unorm,
+                                                 MipmapsCount::Log2,
+                                                 Some(queue.family()))
+            .unwrap();
+
+        let view = ImageView::new(image.clone(), ViewType::Dim2d, 2 .. 3, 0 .. 1).unwrap();
+        assert_eq!(view.dimensions(),
+                   Dimensions::Dim2d {
+                       width: 16,
+                       height: 8,
+                   });
+        assert_eq!(view.mipmap_levels(), 2 .. 3);
+
+        match ImageView::new(image, ViewType::Dim2d, 5 .. 8, 0 .. 1) {
+            Err(ImageViewCreationError::MipmapLevelsOutOfRange) => (),
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn cubemap_face_view() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let image = ImmutableImage::new(device,
+                                        Dimensions::Cubemap { size: 32 },
+                                        Format::R8G8B8A8Unorm,
+                                        Some(queue.family()))
+            .unwrap();
+
+        let face = ImageView::new(image.clone(), ViewType::Dim2d, 0 .. 1, 4 .. 5).unwrap();
+        assert_eq!(face.dimensions(),
+                   Dimensions::Dim2d {
+                       width: 32,
+                       height: 32,
+                   });
+
+        let cube = ImageView::new(image.clone(), ViewType::Cubemap, 0 .. 1, 0 .. 6).unwrap();
+        assert_eq!(cube.dimensions(), Dimensions::Cubemap { size: 32 });
+
+        match ImageView::new(image.clone(), ViewType::Dim2d, 0 .. 1, 0 .. 2) {
+            Err(ImageViewCreationError::IncompatibleViewType) => (),
+            _ => panic!(),
+        }
+        match ImageView::new(image, ViewType::Dim2dArray, 0 .. 1, 4 .. 7) {
+            Err(ImageViewCreationError::ArrayLayersOutOfRange) => (),
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn swizzle() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let image = StorageImage::new(device,
+                                      Dimensions::Dim2d {
+                                          width: 32,
+                                          height: 32,
+                                      },
+                                      Format::R8G8B8A8Unorm,
+                                      Some(queue.family()))
+            .unwrap();
+
+        let swizzle = Swizzle {
+            r: ComponentSwizzle::Blue,
+            b: ComponentSwizzle::Red,
+            ..Swizzle::default()
+        };
+        let view = ImageView::with_format_and_swizzle(image,
+                                                      ViewType::Dim2d,
+                                                      0 .. 1,
+                                                      0 .. 1,
+                                                      Format::R8G8B8A8Unorm,
+                                                      swizzle)
+            .unwrap();
+        assert!(!view.identity_swizzle());
+        assert!(!view.accept(ImageLayout::General, ImageLayout::General));
+    }
+
+    #[test]
+    fn mutable_format() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let dimensions = Dimensions::Dim2d {
+            width: 32,
+            height: 32,
+        };
+
+        let image = StorageImage::new(device.clone(),
+                                      dimensions,
+                                      Format::R8G8B8A8Unorm,
+                                      Some(queue.family()))
+            .unwrap();
+        match ImageView::with_format_and_swizzle(image,
+                                                 ViewType::Dim2d,
+                                                 0 .. 1,
+                                                 0 .. 1,
+                                                 Format::R32Uint,
+                                                 Swizzle::default()) {
+            Err(ImageViewCreationError::FormatNotMutable) => (),
+            _ => panic!(),
+        }
+
+        let image = StorageImage::with_mutable_format(device,
+                                                      dimensions,
+                                                      Format::R8G8B8A8Unorm,
+                                                      Some(queue.family()))
+            .unwrap();
+        let view = ImageView::with_format_and_swizzle(image.clone(),
+                                                      ViewType::Dim2d,
+                                                      0 .. 1,
+                                                      0 .. 1,
+                                                      Format::R32Uint,
+                                                      Swizzle::default())
+            .unwrap();
+        assert_eq!(view.format(), Format::R32Uint);
+        assert!(view.accept(ImageLayout::Undefined, ImageLayout::General));
+
+        match ImageView::with_format_and_swizzle(image,
+                                                 ViewType::Dim2d,
+                                                 0 .. 1,
+                                                 0 .. 1,
+                                                 Format::R16Uint,
+                                                 Swizzle::default()) {
+            Err(ImageViewCreationError::IncompatibleFormat { .. }) => (),
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn compatible_formats() {
+        assert!(formats_compatible(Format::R8G8B8A8Unorm, Format::B8G8R8A8Srgb));
+        assert!(formats_compatible(Format::R32Sfloat, Format::R16G16Uint));
+        assert!(!formats_compatible(Format::R32Sfloat, Format::R16Uint));
+        assert!(!formats_compatible(Format::D32Sfloat, Format::R32Sfloat));
+        assert!(formats_compatible(Format::BC1_RGBAUnormBlock, Format::BC1_RGBASrgbBlock));
+        assert!(formats_compatible(Format::ASTC_8x8SrgbBlock, Format::ASTC_8x8UnormBlock));
+        assert!(formats_compatible(Format::BC4UnormBlock, Format::BC4SnormBlock));
+        assert!(!formats_compatible(Format::BC2UnormBlock, Format::BC3UnormBlock));
+        assert!(!formats_compatible(Format::ASTC_8x8UnormBlock, Format::ASTC_8x6UnormBlock));
+        assert!(!formats_compatible(Format::BC1_RGBSrgbBlock, Format::BC1_RGBAUnormBlock));
+        assert!(!formats_compatible(Format::BC1_RGBUnormBlock, Format::R32G32Uint));
+    }
+}
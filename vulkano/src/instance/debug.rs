@@ -152,7 +152,7 @@ impl DebugCallback {
             let mut output = mem::uninitialized();
             check_errors(vk.CreateDebugReportCallbackEXT(instance.internal_object(),
                                                          &infos,
-                                                         ptr::null(),
+                                                         instance.allocation_callbacks().as_ptr(),
                                                          &mut output))?;
             output
         };
@@ -183,7 +183,7 @@ impl Drop for DebugCallback {
             let vk = self.instance.pointers();
             vk.DestroyDebugReportCallbackEXT(self.instance.internal_object(),
                                              self.debug_report_callback,
-                                             ptr::null());
+                                             self.instance.allocation_callbacks().as_ptr());
         }
     }
 }
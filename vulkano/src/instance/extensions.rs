@@ -356,6 +356,9 @@ device_extensions! {
     khr_display_swapchain => b"VK_KHR_display_swapchain",
     khr_sampler_mirror_clamp_to_edge => b"VK_KHR_sampler_mirror_clamp_to_edge",
     khr_maintenance1 => b"VK_KHR_maintenance1",
+    khr_descriptor_update_template => b"VK_KHR_descriptor_update_template",
+    khr_maintenance3 => b"VK_KHR_maintenance3",
+    ext_descriptor_indexing => b"VK_EXT_descriptor_indexing",
 }
 
 /// Error that can happen when loading the list of layers.
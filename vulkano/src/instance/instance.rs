@@ -20,6 +20,8 @@ use std::sync::Arc;
 
 use Error;
 use OomError;
+use alloc::Alloc;
+use alloc::AllocationCallbacks;
 use VulkanObject;
 use check_errors;
 use instance::loader;
@@ -27,6 +29,15 @@ use instance::loader::LoadingError;
 use vk;
 
 use features::Features;
+use format::Format;
+use format::FormatFeatures;
+use format::FormatProperties;
+use image::ImageCreateFlags;
+use image::ImageFormatProperties;
+use image::ImageTiling;
+use image::ImageType;
+use image::ImageUsage;
+use image::SampleCounts;
 use instance::{InstanceExtensions, RawInstanceExtensions};
 use version::Version;
 
@@ -81,7 +92,9 @@ use version::Version;
 // TODO: mention that extensions must be supported by layers as well
 pub struct Instance {
     instance: vk::Instance,
-    //alloc: Option<Box<Alloc + Send + Sync>>,
+    // Note that we need to double-box the allocator, as its address is passed to the Vulkan
+    // implementation and must not change when the `Instance` is moved.
+    alloc: Option<Box<Box<Alloc + Send + Sync>>>,
     physical_devices: Vec<PhysicalDeviceInfos>,
     vk: vk::InstancePointers,
     extensions: InstanceExtensions,
@@ -124,11 +137,37 @@ impl Instance {
             .map(|&layer| CString::new(layer).unwrap())
             .collect::<SmallVec<[_; 16]>>();
 
-        Instance::new_inner(app_infos, extensions.into(), layers)
+        Instance::new_inner(app_infos, extensions.into(), layers, None)
+    }
+
+    /// Same as `new`, but provides an allocator that will be used by the Vulkan implementation
+    /// whenever it needs to allocate memory on the host.
+    ///
+    /// The allocator is used for the instance itself, but also for all the devices created from
+    /// this instance and all the objects that belong to these devices.
+    ///
+    /// See the documentation of [the `alloc` module](../alloc/index.html) for more details.
+    ///
+    /// # Panic
+    ///
+    /// Same panics as `new`.
+    ///
+    pub fn with_alloc<'a, L, Ext>(app_infos: Option<&ApplicationInfo>, extensions: Ext,
+                                  layers: L, alloc: Box<Alloc + Send + Sync>)
+                                  -> Result<Arc<Instance>, InstanceCreationError>
+        where L: IntoIterator<Item = &'a &'a str>,
+              Ext: Into<RawInstanceExtensions>
+    {
+        let layers = layers
+            .into_iter()
+            .map(|&layer| CString::new(layer).unwrap())
+            .collect::<SmallVec<[_; 16]>>();
+
+        Instance::new_inner(app_infos, extensions.into(), layers, Some(Box::new(alloc)))
     }
 
     fn new_inner(app_infos: Option<&ApplicationInfo>, extensions: RawInstanceExtensions,
-                 layers: SmallVec<[CString; 16]>)
+                 layers: SmallVec<[CString; 16]>, alloc: Option<Box<Box<Alloc + Send + Sync>>>)
                  -> Result<Arc<Instance>, InstanceCreationError> {
         // TODO: For now there are still buggy drivers that will segfault if you don't pass any
         //       appinfos. Therefore for now we ensure that it can't be `None`.
@@ -222,7 +261,8 @@ impl Instance {
                 ppEnabledExtensionNames: extensions_list.as_ptr(),
             };
 
-            check_errors(entry_points.CreateInstance(&infos, ptr::null(), &mut output))?;
+            let callbacks = AllocationCallbacks::new(alloc.as_ref().map(|a| &**a));
+            check_errors(entry_points.CreateInstance(&infos, callbacks.as_ptr(), &mut output))?;
             output
         };
 
@@ -259,7 +299,7 @@ impl Instance {
 
         Ok(Arc::new(Instance {
                         instance: instance,
-                        //alloc: None,
+                        alloc: alloc,
                         physical_devices: physical_devices,
                         vk: vk,
                         extensions: extensions,
@@ -304,6 +344,7 @@ impl Instance {
             output.push(PhysicalDeviceInfos {
                             device: device,
                             properties: properties,
+                            descriptor_indexing_limits: None,
                             memory: memory,
                             queue_families: queue_families,
                             available_features: Features::from(available_features),
@@ -321,15 +362,36 @@ impl Instance {
         let mut output = Vec::with_capacity(physical_devices.len());
 
         for device in physical_devices.into_iter() {
-            let properties: vk::PhysicalDeviceProperties = unsafe {
+            // The features and properties of `VK_EXT_descriptor_indexing` can only be chained if
+            // the physical device supports the extension.
+            let supports_descriptor_indexing =
+                Instance::device_supports_extension(vk, device, b"VK_EXT_descriptor_indexing");
+
+            let (properties, descriptor_indexing_limits) = unsafe {
+                let mut descriptor_indexing = vk::PhysicalDeviceDescriptorIndexingPropertiesEXT {
+                    sType: vk::STRUCTURE_TYPE_PHYSICAL_DEVICE_DESCRIPTOR_INDEXING_PROPERTIES_EXT,
+                    pNext: ptr::null(),
+                    ..mem::zeroed()
+                };
+
                 let mut output = vk::PhysicalDeviceProperties2KHR {
                     sType: vk::STRUCTURE_TYPE_PHYSICAL_DEVICE_PROPERTIES_2_KHR,
-                    pNext: ptr::null_mut(),
+                    pNext: if supports_descriptor_indexing {
+                        &mut descriptor_indexing as *mut _ as *const _
+                    } else {
+                        ptr::null()
+                    },
                     properties: mem::uninitialized(),
                 };
 
                 vk.GetPhysicalDeviceProperties2KHR(device, &mut output);
-                output.properties
+
+                let descriptor_indexing_limits = if supports_descriptor_indexing {
+                    Some(DescriptorIndexingLimits::from(&descriptor_indexing))
+                } else {
+                    None
+                };
+                (output.properties, descriptor_indexing_limits)
             };
 
             let queue_families = unsafe {
@@ -365,34 +427,75 @@ impl Instance {
                 output.memoryProperties
             };
 
-            let available_features: vk::PhysicalDeviceFeatures = unsafe {
+            let available_features: Features = unsafe {
+                // The features of `VK_EXT_descriptor_indexing` are left to false if the physical
+                // device doesn't support the extension.
+                let mut descriptor_indexing = Features::none().descriptor_indexing();
+
                 let mut output = vk::PhysicalDeviceFeatures2KHR {
                     sType: vk::STRUCTURE_TYPE_PHYSICAL_DEVICE_FEATURES_2_KHR,
-                    pNext: ptr::null_mut(),
+                    pNext: if supports_descriptor_indexing {
+                        &mut descriptor_indexing as *mut _ as *const _
+                    } else {
+                        ptr::null()
+                    },
                     features: mem::uninitialized(),
                 };
                 vk.GetPhysicalDeviceFeatures2KHR(device, &mut output);
-                output.features
+
+                let mut features = Features::from(output.features);
+                if supports_descriptor_indexing {
+                    features.set_descriptor_indexing(&descriptor_indexing);
+                }
+                features
             };
 
             output.push(PhysicalDeviceInfos {
                             device: device,
                             properties: properties,
+                            descriptor_indexing_limits: descriptor_indexing_limits,
                             memory: memory,
                             queue_families: queue_families,
-                            available_features: Features::from(available_features),
+                            available_features: available_features,
                         });
         }
         output
     }
 
-    /*/// Same as `new`, but provides an allocator that will be used by the Vulkan library whenever
-    /// it needs to allocate memory on the host.
+    /// Returns true if the physical device supports the device extension with the given name.
     ///
-    /// Note that this allocator can be overriden when you create a `Device`, a `MemoryPool`, etc.
-    pub fn with_alloc(app_infos: Option<&ApplicationInfo>, alloc: Box<Alloc + Send + Sync>) -> Arc<Instance> {
-        unimplemented!()
-    }*/
+    /// Errors while enumerating the extensions are treated as the extension being unsupported.
+    fn device_supports_extension(vk: &vk::InstancePointers, device: vk::PhysicalDevice,
+                                 name: &[u8])
+                                 -> bool {
+        let properties: Vec<vk::ExtensionProperties> = unsafe {
+            let mut num = 0;
+            if check_errors(vk.EnumerateDeviceExtensionProperties(device,
+                                                                  ptr::null(),
+                                                                  &mut num,
+                                                                  ptr::null_mut()))
+                .is_err()
+            {
+                return false;
+            }
+
+            let mut properties = Vec::with_capacity(num as usize);
+            if check_errors(vk.EnumerateDeviceExtensionProperties(device,
+                                                                  ptr::null(),
+                                                                  &mut num,
+                                                                  properties.as_mut_ptr()))
+                .is_err()
+            {
+                return false;
+            }
+            properties.set_len(num as usize);
+            properties
+        };
+
+        properties
+            .iter()
+            .any(|p| unsafe { CStr::from_ptr(p.extensionName.as_ptr()) }.to_bytes() == name)
+    }
 
     /// Grants access to the Vulkan functions of the instance.
     #[inline]
@@ -400,6 +503,13 @@ impl Instance {
         &self.vk
     }
 
+    /// Returns the host allocation callbacks to pass to the Vulkan functions that create or
+    /// destroy objects that belong to this instance.
+    #[inline]
+    pub(crate) fn allocation_callbacks(&self) -> AllocationCallbacks {
+        AllocationCallbacks::new(self.alloc.as_ref().map(|a| &**a))
+    }
+
     /// Returns the list of extensions that have been loaded.
     ///
     /// This list is equal to what was passed to `Instance::new()`.
@@ -447,7 +557,8 @@ impl Drop for Instance {
     #[inline]
     fn drop(&mut self) {
         unsafe {
-            self.vk.DestroyInstance(self.instance, ptr::null());
+            self.vk
+                .DestroyInstance(self.instance, self.allocation_callbacks().as_ptr());
         }
     }
 }
@@ -578,6 +689,8 @@ impl From<Error> for InstanceCreationError {
 struct PhysicalDeviceInfos {
     device: vk::PhysicalDevice,
     properties: vk::PhysicalDeviceProperties,
+    // `None` if the limits of `VK_EXT_descriptor_indexing` couldn't be queried.
+    descriptor_indexing_limits: Option<DescriptorIndexingLimits>,
     queue_families: Vec<vk::QueueFamilyProperties>,
     memory: vk::PhysicalDeviceMemoryProperties,
     available_features: Features,
@@ -849,6 +962,106 @@ impl<'a> PhysicalDevice<'a> {
         &self.infos().properties.pipelineCacheUUID
     }
 
+    /// Returns the features supported by this physical device for the given format.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use vulkano::format::Format;
+    /// use vulkano::instance::PhysicalDevice;
+    ///
+    /// fn supports_blending(physical_device: PhysicalDevice) -> bool {
+    ///     physical_device.format_properties(Format::R16G16B16A16Sfloat)
+    ///         .optimal_tiling_features
+    ///         .color_attachment_blend
+    /// }
+    /// ```
+    pub fn format_properties(&self, format: Format) -> FormatProperties {
+        unsafe {
+            let vk_i = self.instance.pointers();
+            let mut output = mem::uninitialized();
+            vk_i.GetPhysicalDeviceFormatProperties(self.internal_object(),
+                                                   format as u32,
+                                                   &mut output);
+            FormatProperties::from_vulkan(&output)
+        }
+    }
+
+    /// Returns the capabilities of images of the given format, type, tiling and usage.
+    ///
+    /// Returns `None` if this combination isn't supported at all by the physical device.
+    pub fn image_format_properties(&self, format: Format, ty: ImageType, tiling: ImageTiling,
+                                   usage: ImageUsage, flags: ImageCreateFlags)
+                                   -> Result<Option<ImageFormatProperties>, OomError> {
+        let output = unsafe {
+            let vk_i = self.instance.pointers();
+            let mut output: vk::ImageFormatProperties = mem::uninitialized();
+            let r = vk_i.GetPhysicalDeviceImageFormatProperties(self.internal_object(),
+                                                                format as u32,
+                                                                ty.to_vulkan(),
+                                                                tiling.to_vulkan(),
+                                                                usage.to_usage_bits(),
+                                                                flags.to_bits(),
+                                                                &mut output);
+
+            match check_errors(r) {
+                Ok(_) => output,
+                Err(Error::FormatNotSupported) => return Ok(None),
+                Err(err) => return Err(err.into()),
+            }
+        };
+
+        Ok(Some(ImageFormatProperties {
+                    max_extent: [
+                        output.maxExtent.width,
+                        output.maxExtent.height,
+                        output.maxExtent.depth,
+                    ],
+                    max_mipmap_levels: output.maxMipLevels,
+                    max_array_layers: output.maxArrayLayers,
+                    sample_counts: SampleCounts::from_bits(output.sampleCounts),
+                    max_resource_size: output.maxResourceSize,
+                }))
+    }
+
+    /// Returns the first format of `candidates` that supports all of `features` when used with
+    /// the given tiling, or `None` if none of them does.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use vulkano::format::Format;
+    /// use vulkano::format::FormatFeatures;
+    /// use vulkano::image::ImageTiling;
+    /// use vulkano::instance::PhysicalDevice;
+    ///
+    /// fn depth_format(physical_device: PhysicalDevice) -> Format {
+    ///     let features = FormatFeatures {
+    ///         depth_stencil_attachment: true,
+    ///         .. FormatFeatures::none()
+    ///     };
+    ///
+    ///     let candidates = [Format::D32Sfloat, Format::X8_D24UnormPack32, Format::D16Unorm];
+    ///     physical_device
+    ///         .first_supported_format(candidates.iter().cloned(), ImageTiling::Optimal, &features)
+    ///         .expect("D16Unorm is always supported as a depth attachment")
+    /// }
+    /// ```
+    pub fn first_supported_format<I>(&self, candidates: I, tiling: ImageTiling,
+                                     features: &FormatFeatures)
+                                     -> Option<Format>
+        where I: IntoIterator<Item = Format>
+    {
+        candidates.into_iter().find(|&format| {
+            let properties = self.format_properties(format);
+            let supported = match tiling {
+                ImageTiling::Optimal => properties.optimal_tiling_features,
+                ImageTiling::Linear => properties.linear_tiling_features,
+            };
+            supported.is_superset_of(features)
+        })
+    }
+
     // Internal function to make it easier to get the infos of this device.
     #[inline]
     fn infos(&self) -> &'a PhysicalDeviceInfos {
@@ -1332,8 +1545,88 @@ limits_impl!{
     non_coherent_atom_size: u64 => nonCoherentAtomSize,
 }
 
+macro_rules! descriptor_indexing_limits_impl {
+    ($($name:ident => $target:ident,)*) => (
+        // Limits of `VK_EXT_descriptor_indexing`. They are copied out of the Vulkan struct, which
+        // contains a pointer and thus isn't `Send`.
+        struct DescriptorIndexingLimits {
+            $(
+                $name: u32,
+            )*
+        }
+
+        impl<'a> From<&'a vk::PhysicalDeviceDescriptorIndexingPropertiesEXT>
+            for DescriptorIndexingLimits
+        {
+            #[inline]
+            fn from(properties: &'a vk::PhysicalDeviceDescriptorIndexingPropertiesEXT)
+                    -> DescriptorIndexingLimits {
+                DescriptorIndexingLimits {
+                    $(
+                        $name: properties.$target,
+                    )*
+                }
+            }
+        }
+
+        impl<'a> Limits<'a> {
+            $(
+                /// Limit of `VK_EXT_descriptor_indexing`. Returns `None` if the physical device
+                /// doesn't support the extension, or if the
+                /// `khr_get_physical_device_properties2` extension wasn't enabled on the instance.
+                #[inline]
+                pub fn $name(&self) -> Option<u32> {
+                    self.device
+                        .infos()
+                        .descriptor_indexing_limits
+                        .as_ref()
+                        .map(|limits| limits.$name)
+                }
+            )*
+        }
+    )
+}
+
+descriptor_indexing_limits_impl!{
+    max_update_after_bind_descriptors_in_all_pools => maxUpdateAfterBindDescriptorsInAllPools,
+    max_per_stage_descriptor_update_after_bind_samplers =>
+        maxPerStageDescriptorUpdateAfterBindSamplers,
+    max_per_stage_descriptor_update_after_bind_uniform_buffers =>
+        maxPerStageDescriptorUpdateAfterBindUniformBuffers,
+    max_per_stage_descriptor_update_after_bind_storage_buffers =>
+        maxPerStageDescriptorUpdateAfterBindStorageBuffers,
+    max_per_stage_descriptor_update_after_bind_sampled_images =>
+        maxPerStageDescriptorUpdateAfterBindSampledImages,
+    max_per_stage_descriptor_update_after_bind_storage_images =>
+        maxPerStageDescriptorUpdateAfterBindStorageImages,
+    max_per_stage_descriptor_update_after_bind_input_attachments =>
+        maxPerStageDescriptorUpdateAfterBindInputAttachments,
+    max_per_stage_update_after_bind_resources => maxPerStageUpdateAfterBindResources,
+    max_descriptor_set_update_after_bind_samplers => maxDescriptorSetUpdateAfterBindSamplers,
+    max_descriptor_set_update_after_bind_uniform_buffers =>
+        maxDescriptorSetUpdateAfterBindUniformBuffers,
+    max_descriptor_set_update_after_bind_uniform_buffers_dynamic =>
+        maxDescriptorSetUpdateAfterBindUniformBuffersDynamic,
+    max_descriptor_set_update_after_bind_storage_buffers =>
+        maxDescriptorSetUpdateAfterBindStorageBuffers,
+    max_descriptor_set_update_after_bind_storage_buffers_dynamic =>
+        maxDescriptorSetUpdateAfterBindStorageBuffersDynamic,
+    max_descriptor_set_update_after_bind_sampled_images =>
+        maxDescriptorSetUpdateAfterBindSampledImages,
+    max_descriptor_set_update_after_bind_storage_images =>
+        maxDescriptorSetUpdateAfterBindStorageImages,
+    max_descriptor_set_update_after_bind_input_attachments =>
+        maxDescriptorSetUpdateAfterBindInputAttachments,
+}
+
 #[cfg(test)]
 mod tests {
+    use format::Format;
+    use format::FormatFeatures;
+    use image::ImageCreateFlags;
+    use image::ImageTiling;
+    use image::ImageType;
+    use image::ImageUsage;
     use instance;
 
     #[test]
@@ -1358,4 +1651,43 @@ mod tests {
         let by_id = phys.queue_family_by_id(queue_family.id()).unwrap();
         assert_eq!(by_id.id(), queue_family.id());
     }
+
+    #[test]
+    fn depth_format_supported() {
+        let instance = instance!();
+
+        let phys = match instance::PhysicalDevice::enumerate(&instance).next() {
+            Some(p) => p,
+            None => return,
+        };
+
+        // `D16Unorm` is guaranteed to be supported as a depth attachment.
+        let features = FormatFeatures {
+            depth_stencil_attachment: true,
+            ..FormatFeatures::none()
+        };
+        assert!(phys.format_properties(Format::D16Unorm)
+                    .optimal_tiling_features
+                    .is_superset_of(&features));
+
+        let candidates = [Format::R8G8B8A8Unorm, Format::D16Unorm];
+        assert_eq!(phys.first_supported_format(candidates.iter().cloned(),
+                                               ImageTiling::Optimal,
+                                               &features),
+                   Some(Format::D16Unorm));
+
+        let usage = ImageUsage {
+            depth_stencil_attachment: true,
+            ..ImageUsage::none()
+        };
+        let properties = phys.image_format_properties(Format::D16Unorm,
+                                                      ImageType::Dim2d,
+                                                      ImageTiling::Optimal,
+                                                      usage,
+                                                      ImageCreateFlags::none())
+            .unwrap()
+            .unwrap();
+        assert!(properties.max_extent[0] >= 4096);
+        assert!(properties.sample_counts.contains(1));
+    }
 }
@@ -75,6 +75,7 @@ mod tests;
 mod features;
 mod version;
 
+pub mod alloc;
 pub mod buffer;
 pub mod command_buffer;
 pub mod descriptor;
@@ -86,7 +86,7 @@ impl DeviceMemory {
             let mut output = mem::uninitialized();
             check_errors(vk.AllocateMemory(device.internal_object(),
                                            &infos,
-                                           ptr::null(),
+                                           device.allocation_callbacks().as_ptr(),
                                            &mut output))?;
             output
         };
@@ -180,7 +180,9 @@ impl Drop for DeviceMemory {
     fn drop(&mut self) {
         unsafe {
             let vk = self.device.pointers();
-            vk.FreeMemory(self.device.internal_object(), self.memory, ptr::null());
+            vk.FreeMemory(self.device.internal_object(),
+                          self.memory,
+                          self.device.allocation_callbacks().as_ptr());
         }
     }
 }
@@ -263,12 +265,13 @@ impl MappedDeviceMemory {
             .unwrap(); // TODO: error
 
         if !self.coherent {
+            let (offset, size) = self.atom_aligned_range(&range);
             let range = vk::MappedMemoryRange {
                 sType: vk::STRUCTURE_TYPE_MAPPED_MEMORY_RANGE,
                 pNext: ptr::null(),
                 memory: self.memory.internal_object(),
-                offset: range.start as u64,
-                size: (range.end - range.start) as u64,
+                offset: offset,
+                size: size,
             };
 
             // TODO: check result?
@@ -282,6 +285,26 @@ impl MappedDeviceMemory {
             range: range,
         }
     }
+
+    // Returns the offset and size to pass to `vkFlushMappedMemoryRanges` or
+    // `vkInvalidateMappedMemoryRanges` in order to cover `range`. Vulkan requires these values to
+    // be multiples of `nonCoherentAtomSize`, unless the range reaches the end of the memory.
+    fn atom_aligned_range(&self, range: &Range<usize>) -> (u64, u64) {
+        let atom_size = self.memory
+            .device()
+            .physical_device()
+            .limits()
+            .non_coherent_atom_size();
+
+        let start = range.start as u64 / atom_size * atom_size;
+        let end = (range.end as u64 + atom_size - 1) / atom_size * atom_size;
+
+        if end >= self.memory.size() as u64 {
+            (start, vk::WHOLE_SIZE)
+        } else {
+            (start, end - start)
+        }
+    }
 }
 
 impl AsRef<DeviceMemory> for MappedDeviceMemory {
@@ -376,12 +399,13 @@ impl<'a, T: ?Sized + 'a> Drop for CpuAccess<'a, T> {
         if !self.coherent {
             let vk = self.mem.as_ref().device().pointers();
 
+            let (offset, size) = self.mem.atom_aligned_range(&self.range);
             let range = vk::MappedMemoryRange {
                 sType: vk::STRUCTURE_TYPE_MAPPED_MEMORY_RANGE,
                 pNext: ptr::null(),
                 memory: self.mem.as_ref().internal_object(),
-                offset: self.range.start as u64,
-                size: (self.range.end - self.range.start) as u64,
+                offset: offset,
+                size: size,
             };
 
             // TODO: check result?
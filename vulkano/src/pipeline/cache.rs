@@ -13,8 +13,8 @@
 //! pipelines on the disk.
 //!
 //! You can create either an empty cache or a cache from some initial data. Whenever you create a
-//! graphics or compute pipeline, you have the possibility to pass a reference to that cache.
-//! TODO: ^ that's not the case yet
+//! graphics or compute pipeline, you have the possibility to pass a reference to that cache
+//! (see `GraphicsPipelineBuilder::build_with_cache` and `ComputePipeline::new_with_cache`).
 //! The Vulkan implementation will then look in the cache for an existing entry, or add one if it
 //! doesn't exist.
 //!
@@ -28,6 +28,7 @@ use std::ptr;
 use std::sync::Arc;
 
 use device::Device;
+use device::DeviceOwned;
 
 use OomError;
 use VulkanObject;
@@ -122,7 +123,7 @@ impl PipelineCache {
             let mut output = mem::uninitialized();
             check_errors(vk.CreatePipelineCache(device.internal_object(),
                                                 &infos,
-                                                ptr::null(),
+                                                device.allocation_callbacks().as_ptr(),
                                                 &mut output))?;
             output
         };
@@ -216,6 +217,13 @@ impl PipelineCache {
     }
 }
 
+unsafe impl DeviceOwned for PipelineCache {
+    #[inline]
+    fn device(&self) -> &Arc<Device> {
+        &self.device
+    }
+}
+
 unsafe impl VulkanObject for PipelineCache {
     type Object = vk::PipelineCache;
 
@@ -230,7 +238,9 @@ impl Drop for PipelineCache {
     fn drop(&mut self) {
         unsafe {
             let vk = self.device.pointers();
-            vk.DestroyPipelineCache(self.device.internal_object(), self.cache, ptr::null());
+            vk.DestroyPipelineCache(self.device.internal_object(),
+                                    self.cache,
+                                    self.device.allocation_callbacks().as_ptr());
         }
     }
 }
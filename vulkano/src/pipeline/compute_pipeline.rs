@@ -25,6 +25,7 @@ use descriptor::pipeline_layout::PipelineLayoutDescPcRange;
 use descriptor::pipeline_layout::PipelineLayoutNotSupersetError;
 use descriptor::pipeline_layout::PipelineLayoutSuperset;
 use descriptor::pipeline_layout::PipelineLayoutSys;
+use pipeline::cache::PipelineCache;
 use pipeline::shader::ComputeShaderEntryPoint;
 use pipeline::shader::SpecializationConstants;
 
@@ -56,18 +57,48 @@ struct Inner {
 
 impl ComputePipeline<()> {
     /// Builds a new `ComputePipeline`.
+    #[inline]
     pub fn new<Css, Csl>(
         device: Arc<Device>, shader: &ComputeShaderEntryPoint<Css, Csl>, specialization: &Css)
         -> Result<ComputePipeline<PipelineLayout<Csl>>, ComputePipelineCreationError>
         where Csl: PipelineLayoutDescNames + Clone,
               Css: SpecializationConstants
+    {
+        ComputePipeline::new_impl(device, shader, specialization, None)
+    }
+
+    /// Same as `new`, but uses a pipeline cache to accelerate the creation. The cache is updated
+    /// with the new pipeline.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if the pipeline cache doesn't belong to `device`.
+    ///
+    #[inline]
+    pub fn new_with_cache<Css, Csl>(
+        device: Arc<Device>, shader: &ComputeShaderEntryPoint<Css, Csl>, specialization: &Css,
+        cache: Arc<PipelineCache>)
+        -> Result<ComputePipeline<PipelineLayout<Csl>>, ComputePipelineCreationError>
+        where Csl: PipelineLayoutDescNames + Clone,
+              Css: SpecializationConstants
+    {
+        ComputePipeline::new_impl(device, shader, specialization, Some(cache))
+    }
+
+    fn new_impl<Css, Csl>(
+        device: Arc<Device>, shader: &ComputeShaderEntryPoint<Css, Csl>, specialization: &Css,
+        cache: Option<Arc<PipelineCache>>)
+        -> Result<ComputePipeline<PipelineLayout<Csl>>, ComputePipelineCreationError>
+        where Csl: PipelineLayoutDescNames + Clone,
+              Css: SpecializationConstants
     {
         unsafe {
             let pipeline_layout = shader.layout().clone().build(device.clone())?;
-            ComputePipeline::with_unchecked_pipeline_layout(device,
-                                                            shader,
-                                                            specialization,
-                                                            pipeline_layout)
+            ComputePipeline::with_unchecked_pipeline_layout_impl(device,
+                                                                 shader,
+                                                                 specialization,
+                                                                 pipeline_layout,
+                                                                 cache)
         }
     }
 }
@@ -77,6 +108,7 @@ impl<Pl> ComputePipeline<Pl> {
     ///
     /// An error will be returned if the pipeline layout isn't a superset of what the shader
     /// uses.
+    #[inline]
     pub fn with_pipeline_layout<Css, Csl>(
         device: Arc<Device>, shader: &ComputeShaderEntryPoint<Css, Csl>, specialization: &Css,
         pipeline_layout: Pl)
@@ -84,18 +116,58 @@ impl<Pl> ComputePipeline<Pl> {
         where Csl: PipelineLayoutDescNames + Clone,
               Css: SpecializationConstants,
               Pl: PipelineLayoutAbstract
+    {
+        ComputePipeline::with_pipeline_layout_impl(device,
+                                                   shader,
+                                                   specialization,
+                                                   pipeline_layout,
+                                                   None)
+    }
+
+    /// Same as `with_pipeline_layout`, but uses a pipeline cache to accelerate the creation. The
+    /// cache is updated with the new pipeline.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if the pipeline cache doesn't belong to `device`.
+    ///
+    #[inline]
+    pub fn with_pipeline_layout_with_cache<Css, Csl>(
+        device: Arc<Device>, shader: &ComputeShaderEntryPoint<Css, Csl>, specialization: &Css,
+        pipeline_layout: Pl, cache: Arc<PipelineCache>)
+        -> Result<ComputePipeline<Pl>, ComputePipelineCreationError>
+        where Csl: PipelineLayoutDescNames + Clone,
+              Css: SpecializationConstants,
+              Pl: PipelineLayoutAbstract
+    {
+        ComputePipeline::with_pipeline_layout_impl(device,
+                                                   shader,
+                                                   specialization,
+                                                   pipeline_layout,
+                                                   Some(cache))
+    }
+
+    fn with_pipeline_layout_impl<Css, Csl>(
+        device: Arc<Device>, shader: &ComputeShaderEntryPoint<Css, Csl>, specialization: &Css,
+        pipeline_layout: Pl, cache: Option<Arc<PipelineCache>>)
+        -> Result<ComputePipeline<Pl>, ComputePipelineCreationError>
+        where Csl: PipelineLayoutDescNames + Clone,
+              Css: SpecializationConstants,
+              Pl: PipelineLayoutAbstract
     {
         unsafe {
             PipelineLayoutSuperset::ensure_superset_of(&pipeline_layout, shader.layout())?;
-            ComputePipeline::with_unchecked_pipeline_layout(device,
-                                                            shader,
-                                                            specialization,
-                                                            pipeline_layout)
+            ComputePipeline::with_unchecked_pipeline_layout_impl(device,
+                                                                 shader,
+                                                                 specialization,
+                                                                 pipeline_layout,
+                                                                 cache)
         }
     }
 
     /// Same as `with_pipeline_layout`, but doesn't check whether the pipeline layout is a
     /// superset of what the shader expects.
+    #[inline]
     pub unsafe fn with_unchecked_pipeline_layout<Css, Csl>(
         device: Arc<Device>, shader: &ComputeShaderEntryPoint<Css, Csl>, specialization: &Css,
         pipeline_layout: Pl)
@@ -103,6 +175,44 @@ impl<Pl> ComputePipeline<Pl> {
         where Csl: PipelineLayoutDescNames + Clone,
               Css: SpecializationConstants,
               Pl: PipelineLayoutAbstract
+    {
+        ComputePipeline::with_unchecked_pipeline_layout_impl(device,
+                                                             shader,
+                                                             specialization,
+                                                             pipeline_layout,
+                                                             None)
+    }
+
+    /// Same as `with_unchecked_pipeline_layout`, but uses a pipeline cache to accelerate the
+    /// creation. The cache is updated with the new pipeline.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if the pipeline cache doesn't belong to `device`.
+    ///
+    #[inline]
+    pub unsafe fn with_unchecked_pipeline_layout_with_cache<Css, Csl>(
+        device: Arc<Device>, shader: &ComputeShaderEntryPoint<Css, Csl>, specialization: &Css,
+        pipeline_layout: Pl, cache: Arc<PipelineCache>)
+        -> Result<ComputePipeline<Pl>, ComputePipelineCreationError>
+        where Csl: PipelineLayoutDescNames + Clone,
+              Css: SpecializationConstants,
+              Pl: PipelineLayoutAbstract
+    {
+        ComputePipeline::with_unchecked_pipeline_layout_impl(device,
+                                                             shader,
+                                                             specialization,
+                                                             pipeline_layout,
+                                                             Some(cache))
+    }
+
+    unsafe fn with_unchecked_pipeline_layout_impl<Css, Csl>(
+        device: Arc<Device>, shader: &ComputeShaderEntryPoint<Css, Csl>, specialization: &Css,
+        pipeline_layout: Pl, cache: Option<Arc<PipelineCache>>)
+        -> Result<ComputePipeline<Pl>, ComputePipelineCreationError>
+        where Csl: PipelineLayoutDescNames + Clone,
+              Css: SpecializationConstants,
+              Pl: PipelineLayoutAbstract
     {
         let vk = device.pointers();
 
@@ -139,12 +249,20 @@ impl<Pl> ComputePipeline<Pl> {
                 basePipelineIndex: 0,
             };
 
+            let cache_handle = match cache {
+                Some(ref cache) => {
+                    assert_eq!(cache.device().internal_object(), device.internal_object());
+                    cache.internal_object()
+                },
+                None => 0,
+            };
+
             let mut output = mem::uninitialized();
             check_errors(vk.CreateComputePipelines(device.internal_object(),
-                                                   0,
+                                                   cache_handle,
                                                    1,
                                                    &infos,
-                                                   ptr::null(),
+                                                   device.allocation_callbacks().as_ptr(),
                                                    &mut output))?;
             output
         };
@@ -293,7 +411,9 @@ impl Drop for Inner {
     fn drop(&mut self) {
         unsafe {
             let vk = self.device.pointers();
-            vk.DestroyPipeline(self.device.internal_object(), self.pipeline, ptr::null());
+            vk.DestroyPipeline(self.device.internal_object(),
+                               self.pipeline,
+                               self.device.allocation_callbacks().as_ptr());
         }
     }
 }
@@ -376,6 +496,48 @@ impl From<Error> for ComputePipelineCreationError {
 
 #[cfg(test)]
 mod tests {
-    // TODO: test for basic creation
+    use std::ffi::CString;
+
+    use descriptor::pipeline_layout::EmptyPipelineDesc;
+    use pipeline::ComputePipeline;
+    use pipeline::cache::PipelineCache;
+    use tests::COMPUTE;
+    use tests::empty_shader_module;
+
     // TODO: test for pipeline layout error
+
+    #[test]
+    fn basic_create() {
+        let (device, _) = gfx_dev_and_queue!();
+        let module = empty_shader_module(device.clone(), COMPUTE);
+        let name = CString::new("main").unwrap();
+        let shader = unsafe { module.compute_shader_entry_point(&name, EmptyPipelineDesc) };
+
+        let _ = ComputePipeline::new(device, &shader, &()).unwrap();
+    }
+
+    #[test]
+    fn create_with_cache() {
+        let (device, _) = gfx_dev_and_queue!();
+        let module = empty_shader_module(device.clone(), COMPUTE);
+        let name = CString::new("main").unwrap();
+        let shader = unsafe { module.compute_shader_entry_point(&name, EmptyPipelineDesc) };
+        let cache = PipelineCache::empty(device.clone()).unwrap();
+
+        let _ = ComputePipeline::new_with_cache(device, &shader, &(), cache).unwrap();
+    }
+
+    #[test]
+    fn cache_device_mismatch() {
+        let (device1, _) = gfx_dev_and_queue!();
+        let (device2, _) = gfx_dev_and_queue!();
+        let module = empty_shader_module(device1.clone(), COMPUTE);
+        let name = CString::new("main").unwrap();
+        let shader = unsafe { module.compute_shader_entry_point(&name, EmptyPipelineDesc) };
+        let cache = PipelineCache::empty(device2).unwrap();
+
+        assert_should_panic!({
+            let _ = ComputePipeline::new_with_cache(device1, &shader, &(), cache);
+        });
+    }
 }
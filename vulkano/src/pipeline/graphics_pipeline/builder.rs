@@ -22,6 +22,7 @@ use pipeline::blend::AttachmentBlend;
 use pipeline::blend::AttachmentsBlend;
 use pipeline::blend::Blend;
 use pipeline::blend::LogicOp;
+use pipeline::cache::PipelineCache;
 use pipeline::depth_stencil::DepthStencil;
 use pipeline::graphics_pipeline::GraphicsPipeline;
 use pipeline::graphics_pipeline::GraphicsPipelineCreationError;
@@ -198,45 +199,56 @@ impl<'a,
           Rp: RenderPassAbstract + RenderPassSubpassInterface<Fo>
 {
     /// Builds the graphics pipeline.
-    // TODO: replace Box<PipelineLayoutAbstract> with a PipelineUnion struct without template params
+    #[inline]
     pub fn build(self, device: Arc<Device>)
                  -> Result<GraphicsPipeline<Vdef, Box<PipelineLayoutAbstract + Send + Sync>, Rp>,
                            GraphicsPipelineCreationError> {
+        self.build_impl(device, None)
+    }
+
+    /// Builds the graphics pipeline, using a pipeline cache to accelerate the creation.
+    ///
+    /// The cache is updated with the new pipeline.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if the cache doesn't belong to `device`.
+    ///
+    #[inline]
+    pub fn build_with_cache(self, device: Arc<Device>, cache: Arc<PipelineCache>)
+                            -> Result<GraphicsPipeline<Vdef,
+                                                       Box<PipelineLayoutAbstract + Send + Sync>,
+                                                       Rp>,
+                                      GraphicsPipelineCreationError> {
+        self.build_impl(device, Some(cache))
+    }
+
+    // TODO: replace Box<PipelineLayoutAbstract> with a PipelineUnion struct without template params
+    fn build_impl(self, device: Arc<Device>, cache: Option<Arc<PipelineCache>>)
+                  -> Result<GraphicsPipeline<Vdef, Box<PipelineLayoutAbstract + Send + Sync>, Rp>,
+                            GraphicsPipelineCreationError> {
         // TODO: return errors instead of panicking if missing param
-        GraphicsPipeline::with_tessellation_and_geometry(device,
-                                                         GraphicsPipelineParams {
-                                                             vertex_input: self.vertex_input,
-                                                             vertex_shader:
-                                                                 self.vertex_shader
-                                                                     .expect("Vertex shader not \
-                                                                              specified in the \
-                                                                              builder"),
-                                                             input_assembly: self.input_assembly,
-                                                             tessellation: self.tessellation,
-                                                             geometry_shader: self.geometry_shader,
-                                                             viewport:
-                                                                 self.viewport
-                                                                     .expect("Viewport state not \
-                                                                              specified in the \
-                                                                              builder"),
-                                                             raster: self.raster,
-                                                             multisample: self.multisample,
-                                                             fragment_shader:
-                                                                 self.fragment_shader
-                                                                     .expect("Fragment shader not \
-                                                                              specified in the \
-                                                                              builder"),
-                                                             depth_stencil: self.depth_stencil,
-                                                             blend: self.blend,
-                                                             render_pass:
-                                                                 self.render_pass
-                                                                     .expect("Render pass not \
-                                                                              specified in the \
-                                                                              builder"),
-                                                         })
-    }
-
-    // TODO: add build_with_cache method
+        let params = GraphicsPipelineParams {
+            vertex_input: self.vertex_input,
+            vertex_shader: self.vertex_shader
+                .expect("Vertex shader not specified in the builder"),
+            input_assembly: self.input_assembly,
+            tessellation: self.tessellation,
+            geometry_shader: self.geometry_shader,
+            viewport: self.viewport
+                .expect("Viewport state not specified in the builder"),
+            raster: self.raster,
+            multisample: self.multisample,
+            fragment_shader: self.fragment_shader
+                .expect("Fragment shader not specified in the builder"),
+            depth_stencil: self.depth_stencil,
+            blend: self.blend,
+            render_pass: self.render_pass
+                .expect("Render pass not specified in the builder"),
+        };
+
+        GraphicsPipeline::with_tessellation_and_geometry_impl(device, params, cache)
+    }
 }
 
 impl<'a,
@@ -993,3 +1005,82 @@ impl<'a, Vdef, Vsp, Vi, Vo, Vl, Tcs, Tci, Tco, Tcl, Tes, Tei, Teo, Tel, Gs, Gi,
         }
     }
 }*/
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::CString;
+
+    use descriptor::pipeline_layout::EmptyPipelineDesc;
+    use framebuffer::EmptySinglePassRenderPassDesc;
+    use framebuffer::RenderPassDesc;
+    use framebuffer::Subpass;
+    use pipeline::GraphicsPipeline;
+    use pipeline::cache::PipelineCache;
+    use pipeline::shader::EmptyShaderInterfaceDef;
+    use std::sync::Arc;
+    use tests::FRAGMENT;
+    use tests::VERTEX;
+    use tests::empty_shader_module;
+
+    #[test]
+    fn build_with_cache() {
+        let (device, _) = gfx_dev_and_queue!();
+        let vs = empty_shader_module(device.clone(), VERTEX);
+        let fs = empty_shader_module(device.clone(), FRAGMENT);
+        let name = CString::new("main").unwrap();
+        let render_pass = Arc::new(EmptySinglePassRenderPassDesc
+                                       .build_render_pass(device.clone())
+                                       .unwrap());
+        let cache = PipelineCache::empty(device.clone()).unwrap();
+
+        let _ = unsafe {
+            GraphicsPipeline::start()
+                .vertex_shader(vs.vertex_shader_entry_point(&name,
+                                                            EmptyShaderInterfaceDef,
+                                                            EmptyShaderInterfaceDef,
+                                                            EmptyPipelineDesc),
+                               ())
+                .viewports_dynamic_scissors_irrelevant(1)
+                .fragment_shader(fs.fragment_shader_entry_point(&name,
+                                                                EmptyShaderInterfaceDef,
+                                                                EmptyShaderInterfaceDef,
+                                                                EmptyPipelineDesc),
+                                 ())
+                .render_pass(Subpass::from(render_pass, 0).unwrap())
+                .build_with_cache(device, cache)
+                .unwrap()
+        };
+    }
+
+    #[test]
+    fn build_with_cache_device_mismatch() {
+        let (device1, _) = gfx_dev_and_queue!();
+        let (device2, _) = gfx_dev_and_queue!();
+        let vs = empty_shader_module(device1.clone(), VERTEX);
+        let fs = empty_shader_module(device1.clone(), FRAGMENT);
+        let name = CString::new("main").unwrap();
+        let render_pass = Arc::new(EmptySinglePassRenderPassDesc
+                                       .build_render_pass(device1.clone())
+                                       .unwrap());
+        let cache = PipelineCache::empty(device2).unwrap();
+
+        assert_should_panic!({
+            let _ = unsafe {
+                GraphicsPipeline::start()
+                    .vertex_shader(vs.vertex_shader_entry_point(&name,
+                                                                EmptyShaderInterfaceDef,
+                                                                EmptyShaderInterfaceDef,
+                                                                EmptyPipelineDesc),
+                                   ())
+                    .viewports_dynamic_scissors_irrelevant(1)
+                    .fragment_shader(fs.fragment_shader_entry_point(&name,
+                                                                    EmptyShaderInterfaceDef,
+                                                                    EmptyShaderInterfaceDef,
+                                                                    EmptyPipelineDesc),
+                                     ())
+                    .render_pass(Subpass::from(render_pass, 0).unwrap())
+                    .build_with_cache(device1, cache)
+            };
+        });
+    }
+}
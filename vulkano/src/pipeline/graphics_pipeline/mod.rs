@@ -54,6 +54,7 @@ use vk;
 
 use pipeline::blend::AttachmentsBlend;
 use pipeline::blend::Blend;
+use pipeline::cache::PipelineCache;
 use pipeline::depth_stencil::Compare;
 use pipeline::depth_stencil::DepthBounds;
 use pipeline::depth_stencil::DepthStencil;
@@ -249,7 +250,55 @@ impl<Vdef, Rp> GraphicsPipeline<Vdef, (), Rp>
                params: GraphicsPipelineParams<'a, Vdef, Vsp, Vi, Vo, Vl, (), (), (), EmptyPipelineDesc,
                                               (), (), (), EmptyPipelineDesc, (), (), (), EmptyPipelineDesc,
                                               Fs, Fi, Fo, Fl, Rp>)
-              -> Result<GraphicsPipeline<Vdef, PipelineLayout<PipelineLayoutDescUnion<Vl, Fl>>, Rp>, GraphicsPipelineCreationError>
+              -> Result<GraphicsPipeline<Vdef, PipelineLayout<PipelineLayoutDescUnion<Vl, Fl>>, Rp>,
+                        GraphicsPipelineCreationError>
+        where Vdef: VertexDefinition<Vi>,
+              Vl: PipelineLayoutDescNames + Clone,
+              Fl: PipelineLayoutDescNames + Clone,
+              Fi: ShaderInterfaceDefMatch<Vo>,
+              Fo: ShaderInterfaceDef,
+              Vo: ShaderInterfaceDef,
+              Rp: RenderPassSubpassInterface<Fo>,
+    {
+        GraphicsPipeline::new_impl(device, params, None)
+    }
+
+    /// Same as `new`, but uses a pipeline cache to accelerate the creation. The cache is updated
+    /// with the new pipeline.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if the pipeline cache doesn't belong to `device`.
+    ///
+    #[inline]
+    #[deprecated = "Use the GraphicsPipelineBuilder instead"]
+    pub fn new_with_cache<'a, Vsp, Vi, Vo, Vl, Fs, Fi, Fo, Fl>
+              (device: Arc<Device>,
+               params: GraphicsPipelineParams<'a, Vdef, Vsp, Vi, Vo, Vl, (), (), (),
+                                              EmptyPipelineDesc, (), (), (), EmptyPipelineDesc,
+                                              (), (), (), EmptyPipelineDesc, Fs, Fi, Fo, Fl, Rp>,
+               cache: Arc<PipelineCache>)
+              -> Result<GraphicsPipeline<Vdef, PipelineLayout<PipelineLayoutDescUnion<Vl, Fl>>, Rp>,
+                        GraphicsPipelineCreationError>
+        where Vdef: VertexDefinition<Vi>,
+              Vl: PipelineLayoutDescNames + Clone,
+              Fl: PipelineLayoutDescNames + Clone,
+              Fi: ShaderInterfaceDefMatch<Vo>,
+              Fo: ShaderInterfaceDef,
+              Vo: ShaderInterfaceDef,
+              Rp: RenderPassSubpassInterface<Fo>,
+    {
+        GraphicsPipeline::new_impl(device, params, Some(cache))
+    }
+
+    fn new_impl<'a, Vsp, Vi, Vo, Vl, Fs, Fi, Fo, Fl>
+              (device: Arc<Device>,
+               params: GraphicsPipelineParams<'a, Vdef, Vsp, Vi, Vo, Vl, (), (), (),
+                                              EmptyPipelineDesc, (), (), (), EmptyPipelineDesc,
+                                              (), (), (), EmptyPipelineDesc, Fs, Fi, Fo, Fl, Rp>,
+               cache: Option<Arc<PipelineCache>>)
+              -> Result<GraphicsPipeline<Vdef, PipelineLayout<PipelineLayoutDescUnion<Vl, Fl>>, Rp>,
+                        GraphicsPipelineCreationError>
         where Vdef: VertexDefinition<Vi>,
               Vl: PipelineLayoutDescNames + Clone,
               Fl: PipelineLayoutDescNames + Clone,
@@ -293,7 +342,7 @@ impl<Vdef, Rp> GraphicsPipeline<Vdef, (), Rp>
                                       _,
                                       _,
                                       _,
-                                      _>(device, params, pl)
+                                      _>(device, params, pl, cache)
     }
 
     /// Builds a new graphics pipeline object with a geometry shader.
@@ -310,7 +359,70 @@ impl<Vdef, Rp> GraphicsPipeline<Vdef, (), Rp>
                params: GraphicsPipelineParams<'a, Vdef, Vsp, Vi, Vo, Vl, (), (), (), EmptyPipelineDesc,
                                               (), (), (), EmptyPipelineDesc, Gsp, Gi, Go, Gl, Fs, Fi,
                                               Fo, Fl, Rp>)
-              -> Result<GraphicsPipeline<Vdef, PipelineLayout<PipelineLayoutDescUnion<PipelineLayoutDescUnion<Vl, Fl>, Gl>>, Rp>, GraphicsPipelineCreationError>
+              -> Result<GraphicsPipeline<Vdef,
+                                         PipelineLayout<PipelineLayoutDescUnion<
+                                             PipelineLayoutDescUnion<Vl, Fl>, Gl>>,
+                                         Rp>,
+                        GraphicsPipelineCreationError>
+        where Vdef: VertexDefinition<Vi>,
+              Vl: PipelineLayoutDescNames + Clone,
+              Fl: PipelineLayoutDescNames + Clone,
+              Gl: PipelineLayoutDescNames + Clone,
+              Gi: ShaderInterfaceDefMatch<Vo>,
+              Vo: ShaderInterfaceDef,
+              Fi: ShaderInterfaceDefMatch<Go> + ShaderInterfaceDefMatch<Vo>,
+              Fo: ShaderInterfaceDef,
+              Go: ShaderInterfaceDef,
+              Rp: RenderPassSubpassInterface<Fo>,
+    {
+        GraphicsPipeline::with_geometry_shader_impl(device, params, None)
+    }
+
+    /// Same as `with_geometry_shader`, but uses a pipeline cache to accelerate the creation. The
+    /// cache is updated with the new pipeline.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if the pipeline cache doesn't belong to `device`.
+    ///
+    #[inline]
+    #[deprecated = "Use the GraphicsPipelineBuilder instead"]
+    pub fn with_geometry_shader_with_cache<'a, Vsp, Vi, Vo, Vl, Gsp, Gi, Go, Gl, Fs, Fi, Fo, Fl>
+              (device: Arc<Device>,
+               params: GraphicsPipelineParams<'a, Vdef, Vsp, Vi, Vo, Vl, (), (), (),
+                                              EmptyPipelineDesc, (), (), (), EmptyPipelineDesc,
+                                              Gsp, Gi, Go, Gl, Fs, Fi, Fo, Fl, Rp>,
+               cache: Arc<PipelineCache>)
+              -> Result<GraphicsPipeline<Vdef,
+                                         PipelineLayout<PipelineLayoutDescUnion<
+                                             PipelineLayoutDescUnion<Vl, Fl>, Gl>>,
+                                         Rp>,
+                        GraphicsPipelineCreationError>
+        where Vdef: VertexDefinition<Vi>,
+              Vl: PipelineLayoutDescNames + Clone,
+              Fl: PipelineLayoutDescNames + Clone,
+              Gl: PipelineLayoutDescNames + Clone,
+              Gi: ShaderInterfaceDefMatch<Vo>,
+              Vo: ShaderInterfaceDef,
+              Fi: ShaderInterfaceDefMatch<Go> + ShaderInterfaceDefMatch<Vo>,
+              Fo: ShaderInterfaceDef,
+              Go: ShaderInterfaceDef,
+              Rp: RenderPassSubpassInterface<Fo>,
+    {
+        GraphicsPipeline::with_geometry_shader_impl(device, params, Some(cache))
+    }
+
+    fn with_geometry_shader_impl<'a, Vsp, Vi, Vo, Vl, Gsp, Gi, Go, Gl, Fs, Fi, Fo, Fl>
+              (device: Arc<Device>,
+               params: GraphicsPipelineParams<'a, Vdef, Vsp, Vi, Vo, Vl, (), (), (),
+                                              EmptyPipelineDesc, (), (), (), EmptyPipelineDesc,
+                                              Gsp, Gi, Go, Gl, Fs, Fi, Fo, Fl, Rp>,
+               cache: Option<Arc<PipelineCache>>)
+              -> Result<GraphicsPipeline<Vdef,
+                                         PipelineLayout<PipelineLayoutDescUnion<
+                                             PipelineLayoutDescUnion<Vl, Fl>, Gl>>,
+                                         Rp>,
+                        GraphicsPipelineCreationError>
         where Vdef: VertexDefinition<Vi>,
               Vl: PipelineLayoutDescNames + Clone,
               Fl: PipelineLayoutDescNames + Clone,
@@ -352,7 +464,7 @@ impl<Vdef, Rp> GraphicsPipeline<Vdef, (), Rp>
                     .union(params.geometry_shader.as_ref().unwrap().layout().clone())    // FIXME: unwrap()
                     .build(device.clone()).unwrap(); // TODO: error
 
-        GraphicsPipeline::new_inner(device.clone(), params, pl)
+        GraphicsPipeline::new_inner(device.clone(), params, pl, cache)
     }
 
     /// Builds a new graphics pipeline object with tessellation shaders.
@@ -371,7 +483,81 @@ impl<Vdef, Rp> GraphicsPipeline<Vdef, (), Rp>
                params: GraphicsPipelineParams<'a, Vdef, Vsp, Vi, Vo, Vl, Tcs, Tci, Tco, Tcl, Tes,
                                               Tei, Teo, Tel, (), (), (), EmptyPipelineDesc, Fs, Fi,
                                               Fo, Fl, Rp>)
-               -> Result<GraphicsPipeline<Vdef, PipelineLayout<PipelineLayoutDescUnion<PipelineLayoutDescUnion<PipelineLayoutDescUnion<Vl, Fl>, Tcl>, Tel>>, Rp>, GraphicsPipelineCreationError>
+              -> Result<GraphicsPipeline<Vdef,
+                                         PipelineLayout<PipelineLayoutDescUnion<
+                                             PipelineLayoutDescUnion<
+                                                 PipelineLayoutDescUnion<Vl, Fl>, Tcl>, Tel>>,
+                                         Rp>,
+                        GraphicsPipelineCreationError>
+        where Vdef: VertexDefinition<Vi>,
+              Vl: PipelineLayoutDescNames + Clone,
+              Fl: PipelineLayoutDescNames + Clone,
+              Tcl: PipelineLayoutDescNames + Clone,
+              Tel: PipelineLayoutDescNames + Clone,
+              Tci: ShaderInterfaceDefMatch<Vo>,
+              Tei: ShaderInterfaceDefMatch<Tco>,
+              Vo: ShaderInterfaceDef,
+              Tco: ShaderInterfaceDef,
+              Teo: ShaderInterfaceDef,
+              Fi: ShaderInterfaceDefMatch<Teo> + ShaderInterfaceDefMatch<Vo>,
+              Fo: ShaderInterfaceDef,
+              Rp: RenderPassAbstract + RenderPassSubpassInterface<Fo>,
+    {
+        GraphicsPipeline::with_tessellation_impl(device, params, None)
+    }
+
+    /// Same as `with_tessellation`, but uses a pipeline cache to accelerate the creation. The
+    /// cache is updated with the new pipeline.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if the pipeline cache doesn't belong to `device`.
+    ///
+    #[inline]
+    #[deprecated = "Use the GraphicsPipelineBuilder instead"]
+    pub fn with_tessellation_with_cache<'a, Vsp, Vi, Vo, Vl, Tcs, Tci, Tco, Tcl, Tes, Tei, Teo,
+                                        Tel, Fs, Fi, Fo, Fl>
+              (device: Arc<Device>,
+               params: GraphicsPipelineParams<'a, Vdef, Vsp, Vi, Vo, Vl, Tcs, Tci, Tco, Tcl, Tes,
+                                              Tei, Teo, Tel, (), (), (), EmptyPipelineDesc, Fs, Fi,
+                                              Fo, Fl, Rp>,
+               cache: Arc<PipelineCache>)
+              -> Result<GraphicsPipeline<Vdef,
+                                         PipelineLayout<PipelineLayoutDescUnion<
+                                             PipelineLayoutDescUnion<
+                                                 PipelineLayoutDescUnion<Vl, Fl>, Tcl>, Tel>>,
+                                         Rp>,
+                        GraphicsPipelineCreationError>
+        where Vdef: VertexDefinition<Vi>,
+              Vl: PipelineLayoutDescNames + Clone,
+              Fl: PipelineLayoutDescNames + Clone,
+              Tcl: PipelineLayoutDescNames + Clone,
+              Tel: PipelineLayoutDescNames + Clone,
+              Tci: ShaderInterfaceDefMatch<Vo>,
+              Tei: ShaderInterfaceDefMatch<Tco>,
+              Vo: ShaderInterfaceDef,
+              Tco: ShaderInterfaceDef,
+              Teo: ShaderInterfaceDef,
+              Fi: ShaderInterfaceDefMatch<Teo> + ShaderInterfaceDefMatch<Vo>,
+              Fo: ShaderInterfaceDef,
+              Rp: RenderPassAbstract + RenderPassSubpassInterface<Fo>,
+    {
+        GraphicsPipeline::with_tessellation_impl(device, params, Some(cache))
+    }
+
+    fn with_tessellation_impl<'a, Vsp, Vi, Vo, Vl, Tcs, Tci, Tco, Tcl, Tes, Tei, Teo, Tel, Fs,
+                              Fi, Fo, Fl>
+              (device: Arc<Device>,
+               params: GraphicsPipelineParams<'a, Vdef, Vsp, Vi, Vo, Vl, Tcs, Tci, Tco, Tcl, Tes,
+                                              Tei, Teo, Tel, (), (), (), EmptyPipelineDesc, Fs, Fi,
+                                              Fo, Fl, Rp>,
+               cache: Option<Arc<PipelineCache>>)
+              -> Result<GraphicsPipeline<Vdef,
+                                         PipelineLayout<PipelineLayoutDescUnion<
+                                             PipelineLayoutDescUnion<
+                                                 PipelineLayoutDescUnion<Vl, Fl>, Tcl>, Tel>>,
+                                         Rp>,
+                        GraphicsPipelineCreationError>
         where Vdef: VertexDefinition<Vi>,
               Vl: PipelineLayoutDescNames + Clone,
               Fl: PipelineLayoutDescNames + Clone,
@@ -423,7 +609,7 @@ impl<Vdef, Rp> GraphicsPipeline<Vdef, (), Rp>
                     .union(params.tessellation.as_ref().unwrap().tessellation_evaluation_shader.layout().clone())    // FIXME: unwrap()
                     .build(device.clone()).unwrap(); // TODO: error
 
-        GraphicsPipeline::new_inner(device, params, pl)
+        GraphicsPipeline::new_inner(device, params, pl, cache)
     }
 
     /// Builds a new graphics pipeline object with a geometry and tessellation shaders.
@@ -502,6 +688,155 @@ impl<Vdef, Rp> GraphicsPipeline<Vdef, (), Rp>
                       + ShaderInterfaceDefMatch<Vo>,
               Fo: ShaderInterfaceDef,
               Rp: RenderPassAbstract + RenderPassSubpassInterface<Fo>
+    {
+        GraphicsPipeline::with_tessellation_and_geometry_impl(device, params, None)
+    }
+
+    /// Same as `with_tessellation_and_geometry`, but uses a pipeline cache to accelerate the
+    /// creation. The cache is updated with the new pipeline.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if the pipeline cache doesn't belong to `device`.
+    ///
+    #[inline]
+    #[deprecated = "Use the GraphicsPipelineBuilder instead"]
+    pub fn with_tessellation_and_geometry_with_cache<'a,
+                                                     Vsp,
+                                                     Vi,
+                                                     Vo,
+                                                     Vl,
+                                                     Tcs,
+                                                     Tci,
+                                                     Tco,
+                                                     Tcl,
+                                                     Tes,
+                                                     Tei,
+                                                     Teo,
+                                                     Tel,
+                                                     Gsp,
+                                                     Gi,
+                                                     Go,
+                                                     Gl,
+                                                     Fs,
+                                                     Fi,
+                                                     Fo,
+                                                     Fl>(
+        device: Arc<Device>,
+        params: GraphicsPipelineParams<'a,
+                                       Vdef,
+                                       Vsp,
+                                       Vi,
+                                       Vo,
+                                       Vl,
+                                       Tcs,
+                                       Tci,
+                                       Tco,
+                                       Tcl,
+                                       Tes,
+                                       Tei,
+                                       Teo,
+                                       Tel,
+                                       Gsp,
+                                       Gi,
+                                       Go,
+                                       Gl,
+                                       Fs,
+                                       Fi,
+                                       Fo,
+                                       Fl,
+                                       Rp>,
+        cache: Arc<PipelineCache>)
+        -> Result<GraphicsPipeline<Vdef, Box<PipelineLayoutAbstract + Send + Sync>, Rp>,
+                  GraphicsPipelineCreationError>
+        where Vdef: VertexDefinition<Vi>,
+              Vl: PipelineLayoutDescNames + Clone + 'static + Send + Sync,
+              Fl: PipelineLayoutDescNames + Clone + 'static + Send + Sync,
+              Tcl: PipelineLayoutDescNames + Clone + 'static + Send + Sync,
+              Tel: PipelineLayoutDescNames + Clone + 'static + Send + Sync,
+              Gl: PipelineLayoutDescNames + Clone + 'static + Send + Sync,
+              Tci: ShaderInterfaceDefMatch<Vo>,
+              Tei: ShaderInterfaceDefMatch<Tco>,
+              Gi: ShaderInterfaceDefMatch<Teo> + ShaderInterfaceDefMatch<Vo>,
+              Vo: ShaderInterfaceDef,
+              Tco: ShaderInterfaceDef,
+              Teo: ShaderInterfaceDef,
+              Go: ShaderInterfaceDef,
+              Fi: ShaderInterfaceDefMatch<Go>
+                      + ShaderInterfaceDefMatch<Teo>
+                      + ShaderInterfaceDefMatch<Vo>,
+              Fo: ShaderInterfaceDef,
+              Rp: RenderPassAbstract + RenderPassSubpassInterface<Fo>
+    {
+        GraphicsPipeline::with_tessellation_and_geometry_impl(device, params, Some(cache))
+    }
+
+    fn with_tessellation_and_geometry_impl<'a,
+                                           Vsp,
+                                           Vi,
+                                           Vo,
+                                           Vl,
+                                           Tcs,
+                                           Tci,
+                                           Tco,
+                                           Tcl,
+                                           Tes,
+                                           Tei,
+                                           Teo,
+                                           Tel,
+                                           Gsp,
+                                           Gi,
+                                           Go,
+                                           Gl,
+                                           Fs,
+                                           Fi,
+                                           Fo,
+                                           Fl>(
+        device: Arc<Device>,
+        params: GraphicsPipelineParams<'a,
+                                       Vdef,
+                                       Vsp,
+                                       Vi,
+                                       Vo,
+                                       Vl,
+                                       Tcs,
+                                       Tci,
+                                       Tco,
+                                       Tcl,
+                                       Tes,
+                                       Tei,
+                                       Teo,
+                                       Tel,
+                                       Gsp,
+                                       Gi,
+                                       Go,
+                                       Gl,
+                                       Fs,
+                                       Fi,
+                                       Fo,
+                                       Fl,
+                                       Rp>,
+        cache: Option<Arc<PipelineCache>>)
+        -> Result<GraphicsPipeline<Vdef, Box<PipelineLayoutAbstract + Send + Sync>, Rp>,
+                  GraphicsPipelineCreationError>
+        where Vdef: VertexDefinition<Vi>,
+              Vl: PipelineLayoutDescNames + Clone + 'static + Send + Sync,
+              Fl: PipelineLayoutDescNames + Clone + 'static + Send + Sync,
+              Tcl: PipelineLayoutDescNames + Clone + 'static + Send + Sync,
+              Tel: PipelineLayoutDescNames + Clone + 'static + Send + Sync,
+              Gl: PipelineLayoutDescNames + Clone + 'static + Send + Sync,
+              Tci: ShaderInterfaceDefMatch<Vo>,
+              Tei: ShaderInterfaceDefMatch<Tco>,
+              Gi: ShaderInterfaceDefMatch<Teo> + ShaderInterfaceDefMatch<Vo>,
+              Vo: ShaderInterfaceDef,
+              Tco: ShaderInterfaceDef,
+              Teo: ShaderInterfaceDef,
+              Go: ShaderInterfaceDef,
+              Fi: ShaderInterfaceDefMatch<Go>
+                      + ShaderInterfaceDefMatch<Teo>
+                      + ShaderInterfaceDefMatch<Vo>,
+              Fo: ShaderInterfaceDef,
+              Rp: RenderPassAbstract + RenderPassSubpassInterface<Fo>
     {
         let pl;
 
@@ -603,7 +938,7 @@ impl<Vdef, Rp> GraphicsPipeline<Vdef, (), Rp>
             }
         }
 
-        GraphicsPipeline::new_inner(device, params, pl)
+        GraphicsPipeline::new_inner(device, params, pl, cache)
     }
 }
 
@@ -655,7 +990,7 @@ impl<Vdef, L, Rp> GraphicsPipeline<Vdef, L, Rp>
                                        Fo,
                                        Fl,
                                        Rp>,
-        pipeline_layout: L)
+        pipeline_layout: L, cache: Option<Arc<PipelineCache>>)
         -> Result<GraphicsPipeline<Vdef, L, Rp>, GraphicsPipelineCreationError>
         where Vdef: VertexDefinition<Vi>,
               Fo: ShaderInterfaceDef,
@@ -1311,12 +1646,20 @@ impl<Vdef, L, Rp> GraphicsPipeline<Vdef, L, Rp>
                 basePipelineIndex: -1, // TODO:
             };
 
+            let cache_handle = match cache {
+                Some(ref cache) => {
+                    assert_eq!(cache.device().internal_object(), device.internal_object());
+                    cache.internal_object()
+                },
+                None => 0,
+            };
+
             let mut output = mem::uninitialized();
             check_errors(vk.CreateGraphicsPipelines(device.internal_object(),
-                                                    0,
+                                                    cache_handle,
                                                     1,
                                                     &infos,
-                                                    ptr::null(),
+                                                    device.allocation_callbacks().as_ptr(),
                                                     &mut output))?;
             output
         };
@@ -1576,7 +1919,9 @@ impl Drop for Inner {
     fn drop(&mut self) {
         unsafe {
             let vk = self.device.pointers();
-            vk.DestroyPipeline(self.device.internal_object(), self.pipeline, ptr::null());
+            vk.DestroyPipeline(self.device.internal_object(),
+                               self.pipeline,
+                               self.device.allocation_callbacks().as_ptr());
         }
     }
 }
@@ -13,9 +13,10 @@
 //! code and can contain one or more entry points. Note that for the moment the official
 //! GLSL-to-SPIR-V compiler does not support multiple entry points.
 //!
-//! The vulkano library does not provide any functionnality that checks and introspects the SPIR-V
-//! code, therefore the whole shader-related API is unsafe. You are encouraged to use the
-//! `vulkano-shaders` crate that will generate Rust code that wraps around vulkano's shaders API.
+//! The `ShaderModule` API does not check or introspect the SPIR-V code, therefore it is unsafe.
+//! You are encouraged to use the `vulkano-shaders` crate that will generate Rust code that wraps
+//! around vulkano's shaders API, or the `reflect` module if your shaders are only known at
+//! runtime.
 
 use std::borrow::Cow;
 use std::error;
@@ -39,6 +40,8 @@ use check_errors;
 use device::Device;
 use vk;
 
+pub mod reflect;
+
 /// Contains SPIR-V code with one or more entry points.
 ///
 /// Note that it is advised to wrap around a `ShaderModule` with a struct that is different for
@@ -80,7 +83,7 @@ impl<P> ShaderModule<P>
             let mut output = mem::uninitialized();
             check_errors(vk.CreateShaderModule(device.internal_object(),
                                                &infos,
-                                               ptr::null(),
+                                               device.allocation_callbacks().as_ptr(),
                                                &mut output))?;
             output
         };
@@ -259,7 +262,9 @@ impl<P> Drop for ShaderModule<P>
     fn drop(&mut self) {
         unsafe {
             let vk = self.device.pointers();
-            vk.DestroyShaderModule(self.device.internal_object(), self.module, ptr::null());
+            vk.DestroyShaderModule(self.device.internal_object(),
+                                   self.module,
+                                   self.device.allocation_callbacks().as_ptr());
         }
     }
 }
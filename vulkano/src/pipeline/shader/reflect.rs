@@ -0,0 +1,2168 @@
+// Copyright (c) 2017 The vulkano developers
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>,
+// at your option. All files in the project carrying such
+// notice may not be copied, modified, or distributed except
+// according to those terms.
+
+//! Introspection of SPIR-V code at runtime.
+//!
+//! The `vulkano-shaders` crate generates the layout and the interface of shaders at compile time.
+//! When shaders are only known at runtime, for example because they are loaded from the disk or
+//! compiled by the application, this module can be used instead. `ShaderReflection` parses SPIR-V
+//! code and describes each of its entry points with a `RuntimePipelineLayoutDesc` and two
+//! `RuntimeShaderInterfaceDef`s.
+//!
+//! The easiest way to use it is through `ReflectedShaderModule`, which builds a shader module and
+//! hands out entry points that can be passed to `ComputePipeline::new` or to a graphics pipeline
+//! builder. Contrary to `ShaderModule`, this doesn't require any unsafe code, as the layout and
+//! the interfaces of the entry points are guaranteed to match the SPIR-V code.
+//!
+//! ```no_run
+//! use std::fs::File;
+//! use std::io::Read;
+//! use vulkano::pipeline::ComputePipeline;
+//! use vulkano::pipeline::shader::reflect::ReflectedShaderModule;
+//! # let device: std::sync::Arc<vulkano::device::Device> = return;
+//!
+//! let mut spirv = Vec::new();
+//! File::open("shader.spv").unwrap().read_to_end(&mut spirv).unwrap();
+//!
+//! let shader = ReflectedShaderModule::new(device.clone(), &spirv).unwrap();
+//! let entry_point = shader.compute_shader_entry_point("main").unwrap();
+//! let pipeline = ComputePipeline::new(device.clone(), &entry_point, &()).unwrap();
+//! ```
+//!
+//! # Limitations
+//!
+//! - Every descriptor of the module is part of the layout of every entry point, even if the entry
+//!   point doesn't use it.
+//! - Buffer descriptors are never dynamic, and the formats of texel buffers and storage images
+//!   are never specified.
+//! - Runtime-sized arrays of descriptors are described as arrays of one element. The actual number
+//!   of elements is determined by the layout of the set, for example with a `BindlessTable`.
+//! - Arrays of arrays of descriptors aren't supported.
+//! - Like with `vulkano-shaders`, only 32-bits components are supported for vectors in the
+//!   interfaces of the entry points.
+//!
+
+use std::borrow::Cow;
+use std::cmp;
+use std::error;
+use std::ffi::CStr;
+use std::ffi::CString;
+use std::fmt;
+use std::slice;
+use std::sync::Arc;
+use std::vec::IntoIter as VecIntoIter;
+
+use OomError;
+use descriptor::descriptor::DescriptorBufferContentDesc;
+use descriptor::descriptor::DescriptorBufferDesc;
+use descriptor::descriptor::DescriptorDesc;
+use descriptor::descriptor::DescriptorDescTy;
+use descriptor::descriptor::DescriptorImageDesc;
+use descriptor::descriptor::DescriptorImageDescArray;
+use descriptor::descriptor::DescriptorImageDescDimensions;
+use descriptor::descriptor::ShaderStages;
+use descriptor::pipeline_layout::PipelineLayoutDesc;
+use descriptor::pipeline_layout::PipelineLayoutDescNames;
+use descriptor::pipeline_layout::PipelineLayoutDescPcRange;
+use device::Device;
+use format::Format;
+use instance::Features;
+use pipeline::shader::ComputeShaderEntryPoint;
+use pipeline::shader::FragmentShaderEntryPoint;
+use pipeline::shader::GeometryShaderEntryPoint;
+use pipeline::shader::GeometryShaderExecutionMode;
+use pipeline::shader::ShaderInterfaceDef;
+use pipeline::shader::ShaderInterfaceDefEntry;
+use pipeline::shader::ShaderModule;
+use pipeline::shader::TessControlShaderEntryPoint;
+use pipeline::shader::TessEvaluationShaderEntryPoint;
+use pipeline::shader::VertexShaderEntryPoint;
+
+/// Shader module whose entry points are described by reflecting its SPIR-V code.
+///
+/// See the documentation of the `reflect` module for more info.
+#[derive(Debug)]
+pub struct ReflectedShaderModule {
+    module: Arc<ShaderModule>,
+    reflection: ShaderReflection,
+}
+
+impl ReflectedShaderModule {
+    /// Parses SPIR-V code and builds a shader module from it.
+    ///
+    /// Returns an error if the code requires a feature that isn't enabled on the device.
+    pub fn new(device: Arc<Device>, spirv: &[u8])
+               -> Result<ReflectedShaderModule, ReflectedShaderModuleCreationError> {
+        let reflection = ShaderReflection::new(spirv)?;
+
+        if !device
+            .enabled_features()
+            .superset_of(&reflection.required_features)
+        {
+            return Err(ReflectedShaderModuleCreationError::FeatureNotEnabled);
+        }
+
+        let module = unsafe { ShaderModule::new(device, spirv)? };
+
+        Ok(ReflectedShaderModule {
+               module: module,
+               reflection: reflection,
+           })
+    }
+
+    /// Returns the shader module.
+    #[inline]
+    pub fn module(&self) -> &Arc<ShaderModule> {
+        &self.module
+    }
+
+    /// Returns the description of the SPIR-V code of the module.
+    #[inline]
+    pub fn reflection(&self) -> &ShaderReflection {
+        &self.reflection
+    }
+
+    /// Returns the vertex shader entry point with the given name.
+    ///
+    /// Returns `None` if the module doesn't contain a vertex shader entry point with this name.
+    pub fn vertex_shader_entry_point<'a>(
+        &'a self, name: &str)
+        -> Option<VertexShaderEntryPoint<'a,
+                                         (),
+                                         RuntimeShaderInterfaceDef,
+                                         RuntimeShaderInterfaceDef,
+                                         RuntimePipelineLayoutDesc>> {
+        let ep = match self.find(name, EntryPointType::Vertex) {
+            Some(ep) => ep,
+            None => return None,
+        };
+
+        unsafe {
+            Some(self.module.vertex_shader_entry_point(&ep.name,
+                                                       ep.input.clone(),
+                                                       ep.output.clone(),
+                                                       ep.layout.clone()))
+        }
+    }
+
+    /// Returns the tessellation control shader entry point with the given name.
+    ///
+    /// Returns `None` if the module doesn't contain a tessellation control shader entry point
+    /// with this name.
+    pub fn tess_control_shader_entry_point<'a>(
+        &'a self, name: &str)
+        -> Option<TessControlShaderEntryPoint<'a,
+                                              (),
+                                              RuntimeShaderInterfaceDef,
+                                              RuntimeShaderInterfaceDef,
+                                              RuntimePipelineLayoutDesc>> {
+        let ep = match self.find(name, EntryPointType::TessellationControl) {
+            Some(ep) => ep,
+            None => return None,
+        };
+
+        unsafe {
+            Some(self.module.tess_control_shader_entry_point(&ep.name,
+                                                             ep.input.clone(),
+                                                             ep.output.clone(),
+                                                             ep.layout.clone()))
+        }
+    }
+
+    /// Returns the tessellation evaluation shader entry point with the given name.
+    ///
+    /// Returns `None` if the module doesn't contain a tessellation evaluation shader entry point
+    /// with this name.
+    pub fn tess_evaluation_shader_entry_point<'a>(
+        &'a self, name: &str)
+        -> Option<TessEvaluationShaderEntryPoint<'a,
+                                                 (),
+                                                 RuntimeShaderInterfaceDef,
+                                                 RuntimeShaderInterfaceDef,
+                                                 RuntimePipelineLayoutDesc>> {
+        let ep = match self.find(name, EntryPointType::TessellationEvaluation) {
+            Some(ep) => ep,
+            None => return None,
+        };
+
+        unsafe {
+            Some(self.module.tess_evaluation_shader_entry_point(&ep.name,
+                                                                ep.input.clone(),
+                                                                ep.output.clone(),
+                                                                ep.layout.clone()))
+        }
+    }
+
+    /// Returns the geometry shader entry point with the given name.
+    ///
+    /// Returns `None` if the module doesn't contain a geometry shader entry point with this name.
+    pub fn geometry_shader_entry_point<'a>(
+        &'a self, name: &str)
+        -> Option<GeometryShaderEntryPoint<'a,
+                                           (),
+                                           RuntimeShaderInterfaceDef,
+                                           RuntimeShaderInterfaceDef,
+                                           RuntimePipelineLayoutDesc>> {
+        let ep = match self.reflection.entry_point(name) {
+            Some(ep) => ep,
+            None => return None,
+        };
+
+        let primitives = match ep.ty {
+            EntryPointType::Geometry(primitives) => primitives,
+            _ => return None,
+        };
+
+        unsafe {
+            Some(self.module.geometry_shader_entry_point(&ep.name,
+                                                         primitives,
+                                                         ep.input.clone(),
+                                                         ep.output.clone(),
+                                                         ep.layout.clone()))
+        }
+    }
+
+    /// Returns the fragment shader entry point with the given name.
+    ///
+    /// Returns `None` if the module doesn't contain a fragment shader entry point with this name.
+    pub fn fragment_shader_entry_point<'a>(
+        &'a self, name: &str)
+        -> Option<FragmentShaderEntryPoint<'a,
+                                           (),
+                                           RuntimeShaderInterfaceDef,
+                                           RuntimeShaderInterfaceDef,
+                                           RuntimePipelineLayoutDesc>> {
+        let ep = match self.find(name, EntryPointType::Fragment) {
+            Some(ep) => ep,
+            None => return None,
+        };
+
+        unsafe {
+            Some(self.module.fragment_shader_entry_point(&ep.name,
+                                                         ep.input.clone(),
+                                                         ep.output.clone(),
+                                                         ep.layout.clone()))
+        }
+    }
+
+    /// Returns the compute shader entry point with the given name.
+    ///
+    /// Returns `None` if the module doesn't contain a compute shader entry point with this name.
+    pub fn compute_shader_entry_point<'a>(
+        &'a self, name: &str)
+        -> Option<ComputeShaderEntryPoint<'a, (), RuntimePipelineLayoutDesc>> {
+        let ep = match self.find(name, EntryPointType::Compute) {
+            Some(ep) => ep,
+            None => return None,
+        };
+
+        unsafe {
+            Some(self.module
+                     .compute_shader_entry_point(&ep.name, ep.layout.clone()))
+        }
+    }
+
+    // Returns the entry point with the given name, if it has the given type.
+    fn find(&self, name: &str, ty: EntryPointType) -> Option<&EntryPointReflection> {
+        self.reflection
+            .entry_point(name)
+            .and_then(|ep| if ep.ty == ty { Some(ep) } else { None })
+    }
+}
+
+/// Error that can happen when creating a `ReflectedShaderModule`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReflectedShaderModuleCreationError {
+    /// Error while reflecting the SPIR-V code.
+    ReflectError(ReflectError),
+
+    /// The SPIR-V code requires a feature that isn't enabled on the device.
+    FeatureNotEnabled,
+
+    /// Not enough memory.
+    OomError(OomError),
+}
+
+impl error::Error for ReflectedShaderModuleCreationError {
+    #[inline]
+    fn description(&self) -> &str {
+        match *self {
+            ReflectedShaderModuleCreationError::ReflectError(_) => {
+                "error while reflecting the SPIR-V code"
+            },
+            ReflectedShaderModuleCreationError::FeatureNotEnabled => {
+                "the SPIR-V code requires a feature that isn't enabled on the device"
+            },
+            ReflectedShaderModuleCreationError::OomError(_) => "not enough memory available",
+        }
+    }
+
+    #[inline]
+    fn cause(&self) -> Option<&error::Error> {
+        match *self {
+            ReflectedShaderModuleCreationError::ReflectError(ref err) => Some(err),
+            ReflectedShaderModuleCreationError::OomError(ref err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ReflectedShaderModuleCreationError {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(fmt, "{}", error::Error::description(self))
+    }
+}
+
+impl From<ReflectError> for ReflectedShaderModuleCreationError {
+    #[inline]
+    fn from(err: ReflectError) -> ReflectedShaderModuleCreationError {
+        ReflectedShaderModuleCreationError::ReflectError(err)
+    }
+}
+
+impl From<OomError> for ReflectedShaderModuleCreationError {
+    #[inline]
+    fn from(err: OomError) -> ReflectedShaderModuleCreationError {
+        ReflectedShaderModuleCreationError::OomError(err)
+    }
+}
+
+/// Description of the entry points of some SPIR-V code.
+#[derive(Debug, Clone)]
+pub struct ShaderReflection {
+    entry_points: Vec<EntryPointReflection>,
+    required_features: Features,
+}
+
+impl ShaderReflection {
+    /// Parses SPIR-V code and describes its entry points.
+    ///
+    /// The code can be either in little endian or in big endian order.
+    pub fn new(spirv: &[u8]) -> Result<ShaderReflection, ReflectError> {
+        let doc = Spirv::parse(spirv)?;
+
+        let mut required_features = Features::none();
+        for instruction in doc.instructions.iter() {
+            if let Instruction::Capability(capability) = *instruction {
+                add_capability_features(&mut required_features, capability)?;
+            }
+        }
+
+        let mut entry_points = Vec::new();
+        for instruction in doc.instructions.iter() {
+            let (execution, id, name, interface) = match *instruction {
+                Instruction::EntryPoint {
+                    execution,
+                    id,
+                    ref name,
+                    ref interface,
+                } => (execution, id, name, interface),
+                _ => continue,
+            };
+
+            let ty = match execution {
+                0 => EntryPointType::Vertex,
+                1 => EntryPointType::TessellationControl,
+                2 => EntryPointType::TessellationEvaluation,
+                3 => EntryPointType::Geometry(geometry_primitives(&doc, id)?),
+                4 => EntryPointType::Fragment,
+                5 => EntryPointType::Compute,
+                _ => return Err(ReflectError::UnsupportedExecutionModel),
+            };
+
+            // The inputs of the tessellation and geometry stages, and the outputs of the
+            // tessellation control stage, are arrays with one element per vertex.
+            let (array_in, array_out) = match ty {
+                EntryPointType::TessellationControl => (true, true),
+                EntryPointType::TessellationEvaluation => (true, false),
+                EntryPointType::Geometry(_) => (true, false),
+                _ => (false, false),
+            };
+
+            entry_points.push(EntryPointReflection {
+                                  name: name.clone(),
+                                  ty: ty,
+                                  layout: layout_desc(&doc, ty.stages())?,
+                                  input: interface_def(&doc,
+                                                       interface,
+                                                       STORAGE_CLASS_INPUT,
+                                                       array_in)?,
+                                  output: interface_def(&doc,
+                                                        interface,
+                                                        STORAGE_CLASS_OUTPUT,
+                                                        array_out)?,
+                              });
+        }
+
+        Ok(ShaderReflection {
+               entry_points: entry_points,
+               required_features: required_features,
+           })
+    }
+
+    /// Returns the list of entry points of the code.
+    #[inline]
+    pub fn entry_points(&self) -> slice::Iter<EntryPointReflection> {
+        self.entry_points.iter()
+    }
+
+    /// Returns the entry point with the given name, or `None` if there is none.
+    #[inline]
+    pub fn entry_point(&self, name: &str) -> Option<&EntryPointReflection> {
+        self.entry_points
+            .iter()
+            .find(|ep| ep.name.as_bytes() == name.as_bytes())
+    }
+
+    /// Returns the features that must be enabled on a device in order to use the code.
+    #[inline]
+    pub fn required_features(&self) -> &Features {
+        &self.required_features
+    }
+}
+
+/// Description of an entry point of some SPIR-V code.
+#[derive(Debug, Clone)]
+pub struct EntryPointReflection {
+    name: CString,
+    ty: EntryPointType,
+    layout: RuntimePipelineLayoutDesc,
+    input: RuntimeShaderInterfaceDef,
+    output: RuntimeShaderInterfaceDef,
+}
+
+impl EntryPointReflection {
+    /// Returns the name of the entry point.
+    #[inline]
+    pub fn name(&self) -> &CStr {
+        &self.name
+    }
+
+    /// Returns the shader stage of the entry point.
+    #[inline]
+    pub fn ty(&self) -> EntryPointType {
+        self.ty
+    }
+
+    /// Returns the pipeline layout used by the entry point.
+    #[inline]
+    pub fn layout(&self) -> &RuntimePipelineLayoutDesc {
+        &self.layout
+    }
+
+    /// Returns the input attributes of the entry point.
+    ///
+    /// For the tessellation and geometry stages, each element describes a single vertex.
+    #[inline]
+    pub fn input(&self) -> &RuntimeShaderInterfaceDef {
+        &self.input
+    }
+
+    /// Returns the output attributes of the entry point.
+    ///
+    /// For the tessellation control stage, each element describes a single vertex.
+    #[inline]
+    pub fn output(&self) -> &RuntimeShaderInterfaceDef {
+        &self.output
+    }
+}
+
+/// Shader stage of an entry point.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum EntryPointType {
+    Vertex,
+    TessellationControl,
+    TessellationEvaluation,
+    /// Geometry shader, with the kind of primitives that it expects.
+    Geometry(GeometryShaderExecutionMode),
+    Fragment,
+    Compute,
+}
+
+impl EntryPointType {
+    /// Returns the `ShaderStages` that only contain this stage.
+    #[inline]
+    pub fn stages(&self) -> ShaderStages {
+        let mut stages = ShaderStages::none();
+        match *self {
+            EntryPointType::Vertex => stages.vertex = true,
+            EntryPointType::TessellationControl => stages.tessellation_control = true,
+            EntryPointType::TessellationEvaluation => stages.tessellation_evaluation = true,
+            EntryPointType::Geometry(_) => stages.geometry = true,
+            EntryPointType::Fragment => stages.fragment = true,
+            EntryPointType::Compute => stages.compute = true,
+        }
+        stages
+    }
+}
+
+/// Pipeline layout description built by reflecting SPIR-V code.
+#[derive(Debug, Clone)]
+pub struct RuntimePipelineLayoutDesc {
+    // List of `(set, binding, descriptor)`.
+    descriptors: Vec<(usize, usize, DescriptorDesc)>,
+    push_constants: Vec<PipelineLayoutDescPcRange>,
+    // List of `(name, set, binding)`.
+    names: Vec<(String, usize, usize)>,
+}
+
+unsafe impl PipelineLayoutDesc for RuntimePipelineLayoutDesc {
+    #[inline]
+    fn num_sets(&self) -> usize {
+        self.descriptors
+            .iter()
+            .map(|&(set, _, _)| set + 1)
+            .max()
+            .unwrap_or(0)
+    }
+
+    #[inline]
+    fn num_bindings_in_set(&self, set: usize) -> Option<usize> {
+        if set >= self.num_sets() {
+            return None;
+        }
+
+        Some(self.descriptors
+                 .iter()
+                 .filter(|&&(s, _, _)| s == set)
+                 .map(|&(_, binding, _)| binding + 1)
+                 .max()
+                 .unwrap_or(0))
+    }
+
+    #[inline]
+    fn descriptor(&self, set: usize, binding: usize) -> Option<DescriptorDesc> {
+        self.descriptors
+            .iter()
+            .find(|&&(s, b, _)| s == set && b == binding)
+            .map(|&(_, _, ref desc)| desc.clone())
+    }
+
+    #[inline]
+    fn num_push_constants_ranges(&self) -> usize {
+        self.push_constants.len()
+    }
+
+    #[inline]
+    fn push_constants_range(&self, num: usize) -> Option<PipelineLayoutDescPcRange> {
+        self.push_constants.get(num).cloned()
+    }
+}
+
+unsafe impl PipelineLayoutDescNames for RuntimePipelineLayoutDesc {
+    #[inline]
+    fn descriptor_by_name(&self, name: &str) -> Option<(usize, usize)> {
+        self.names
+            .iter()
+            .find(|&&(ref n, _, _)| n == name)
+            .map(|&(_, set, binding)| (set, binding))
+    }
+}
+
+/// Shader interface definition built by reflecting SPIR-V code.
+#[derive(Debug, Clone)]
+pub struct RuntimeShaderInterfaceDef {
+    entries: Vec<ShaderInterfaceDefEntry>,
+}
+
+unsafe impl ShaderInterfaceDef for RuntimeShaderInterfaceDef {
+    type Iter = VecIntoIter<ShaderInterfaceDefEntry>;
+
+    #[inline]
+    fn elements(&self) -> Self::Iter {
+        self.entries.clone().into_iter()
+    }
+}
+
+/// Error that can happen when reflecting SPIR-V code.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ReflectError {
+    /// The code doesn't start with a SPIR-V header.
+    MissingHeader,
+
+    /// An instruction is truncated or has missing operands.
+    MalformedInstruction,
+
+    /// An instruction refers to an id that doesn't exist or that has an unexpected kind.
+    InvalidId {
+        /// The id.
+        id: u32,
+    },
+
+    /// The code requires a capability that isn't supported by Vulkan or by vulkano.
+    UnsupportedCapability {
+        /// The numerical value of the capability.
+        capability: u32,
+    },
+
+    /// An entry point uses an execution model that isn't supported by Vulkan.
+    UnsupportedExecutionModel,
+
+    /// A geometry shader entry point doesn't declare the kind of primitives that it expects.
+    MissingGeometryInputPrimitives,
+
+    /// A descriptor doesn't have a `Binding` decoration.
+    MissingBinding {
+        /// The id of the variable of the descriptor.
+        id: u32,
+    },
+
+    /// An input or output attribute doesn't have a `Location` decoration.
+    MissingLocation {
+        /// The id of the variable of the attribute.
+        id: u32,
+    },
+
+    /// A descriptor, attribute or push constants block uses a type that can't be described.
+    UnsupportedType {
+        /// The id of the type.
+        id: u32,
+    },
+
+    /// Two input or output attributes of an entry point overlap.
+    OverlappingLocations {
+        /// The first location of the attribute that overlaps another one.
+        location: u32,
+    },
+}
+
+impl error::Error for ReflectError {
+    #[inline]
+    fn description(&self) -> &str {
+        match *self {
+            ReflectError::MissingHeader => "the code doesn't start with a SPIR-V header",
+            ReflectError::MalformedInstruction => {
+                "an instruction is truncated or has missing operands"
+            },
+            ReflectError::InvalidId { .. } => {
+                "an instruction refers to an id that doesn't exist or that has an unexpected kind"
+            },
+            ReflectError::UnsupportedCapability { .. } => {
+                "the code requires a capability that isn't supported"
+            },
+            ReflectError::UnsupportedExecutionModel => {
+                "an entry point uses an execution model that isn't supported by Vulkan"
+            },
+            ReflectError::MissingGeometryInputPrimitives => {
+                "a geometry shader entry point doesn't declare the kind of primitives that it \
+                 expects"
+            },
+            ReflectError::MissingBinding { .. } => {
+                "a descriptor doesn't have a `Binding` decoration"
+            },
+            ReflectError::MissingLocation { .. } => {
+                "an input or output attribute doesn't have a `Location` decoration"
+            },
+            ReflectError::UnsupportedType { .. } => {
+                "a descriptor, attribute or push constants block uses a type that can't be \
+                 described"
+            },
+            ReflectError::OverlappingLocations { .. } => {
+                "two input or output attributes of an entry point overlap"
+            },
+        }
+    }
+}
+
+impl fmt::Display for ReflectError {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(fmt, "{}", error::Error::description(self))
+    }
+}
+
+const OP_NAME: u16 = 5;
+const OP_ENTRY_POINT: u16 = 15;
+const OP_EXECUTION_MODE: u16 = 16;
+const OP_CAPABILITY: u16 = 17;
+const OP_TYPE_INT: u16 = 21;
+const OP_TYPE_FLOAT: u16 = 22;
+const OP_TYPE_VECTOR: u16 = 23;
+const OP_TYPE_MATRIX: u16 = 24;
+const OP_TYPE_IMAGE: u16 = 25;
+const OP_TYPE_SAMPLER: u16 = 26;
+const OP_TYPE_SAMPLED_IMAGE: u16 = 27;
+const OP_TYPE_ARRAY: u16 = 28;
+const OP_TYPE_RUNTIME_ARRAY: u16 = 29;
+const OP_TYPE_STRUCT: u16 = 30;
+const OP_TYPE_POINTER: u16 = 32;
+const OP_CONSTANT: u16 = 43;
+const OP_SPEC_CONSTANT: u16 = 50;
+const OP_VARIABLE: u16 = 59;
+const OP_DECORATE: u16 = 71;
+const OP_MEMBER_DECORATE: u16 = 72;
+
+const STORAGE_CLASS_INPUT: u32 = 1;
+const STORAGE_CLASS_OUTPUT: u32 = 3;
+const STORAGE_CLASS_PUSH_CONSTANT: u32 = 9;
+const STORAGE_CLASS_STORAGE_BUFFER: u32 = 12;
+
+const DECORATION_BLOCK: u32 = 2;
+const DECORATION_BUFFER_BLOCK: u32 = 3;
+const DECORATION_ROW_MAJOR: u32 = 4;
+const DECORATION_ARRAY_STRIDE: u32 = 6;
+const DECORATION_MATRIX_STRIDE: u32 = 7;
+const DECORATION_BUILT_IN: u32 = 11;
+const DECORATION_NON_WRITABLE: u32 = 24;
+const DECORATION_LOCATION: u32 = 30;
+const DECORATION_BINDING: u32 = 33;
+const DECORATION_DESCRIPTOR_SET: u32 = 34;
+const DECORATION_OFFSET: u32 = 35;
+
+// Maximum number of nested types that are followed when walking a type. A type can't refer to
+// itself, so a deeper nesting means that the code is malformed.
+const MAX_TYPE_DEPTH: u32 = 64;
+
+// The instructions that are relevant for reflection. The other ones are ignored when parsing.
+#[derive(Debug, Clone)]
+enum Instruction {
+    Name { target_id: u32, name: String },
+    EntryPoint {
+        execution: u32,
+        id: u32,
+        name: CString,
+        interface: Vec<u32>,
+    },
+    ExecutionMode { target_id: u32, mode: u32 },
+    Capability(u32),
+    TypeInt {
+        result_id: u32,
+        width: u32,
+        signedness: bool,
+    },
+    TypeFloat { result_id: u32, width: u32 },
+    TypeVector {
+        result_id: u32,
+        component_id: u32,
+        count: u32,
+    },
+    TypeMatrix {
+        result_id: u32,
+        column_type_id: u32,
+        column_count: u32,
+    },
+    TypeImage {
+        result_id: u32,
+        dim: u32,
+        arrayed: bool,
+        ms: bool,
+        sampled: u32,
+    },
+    TypeSampler { result_id: u32 },
+    TypeSampledImage { result_id: u32, image_type_id: u32 },
+    TypeArray {
+        result_id: u32,
+        type_id: u32,
+        length_id: u32,
+    },
+    TypeRuntimeArray { result_id: u32, type_id: u32 },
+    TypeStruct {
+        result_id: u32,
+        member_types: Vec<u32>,
+    },
+    TypePointer {
+        result_id: u32,
+        storage_class: u32,
+        type_id: u32,
+    },
+    Constant { result_id: u32, data: Vec<u32> },
+    Variable {
+        result_type_id: u32,
+        result_id: u32,
+        storage_class: u32,
+    },
+    Decorate {
+        target_id: u32,
+        decoration: u32,
+        params: Vec<u32>,
+    },
+    MemberDecorate {
+        target_id: u32,
+        member: u32,
+        decoration: u32,
+        params: Vec<u32>,
+    },
+}
+
+impl Instruction {
+    // Returns the id of the type or constant defined by this instruction.
+    fn type_or_constant_id(&self) -> Option<u32> {
+        match *self {
+            Instruction::TypeInt { result_id, .. } |
+            Instruction::TypeFloat { result_id, .. } |
+            Instruction::TypeVector { result_id, .. } |
+            Instruction::TypeMatrix { result_id, .. } |
+            Instruction::TypeImage { result_id, .. } |
+            Instruction::TypeSampler { result_id } |
+            Instruction::TypeSampledImage { result_id, .. } |
+            Instruction::TypeArray { result_id, .. } |
+            Instruction::TypeRuntimeArray { result_id, .. } |
+            Instruction::TypeStruct { result_id, .. } |
+            Instruction::TypePointer { result_id, .. } |
+            Instruction::Constant { result_id, .. } => Some(result_id),
+            _ => None,
+        }
+    }
+}
+
+// Parsed SPIR-V code.
+struct Spirv {
+    instructions: Vec<Instruction>,
+}
+
+impl Spirv {
+    fn parse(spirv: &[u8]) -> Result<Spirv, ReflectError> {
+        if spirv.len() < 20 || spirv.len() % 4 != 0 {
+            return Err(ReflectError::MissingHeader);
+        }
+
+        // The magic number tells us whether the words are in little endian or big endian order.
+        let big_endian = if spirv.starts_with(&[0x07, 0x23, 0x02, 0x03]) {
+            true
+        } else if spirv.starts_with(&[0x03, 0x02, 0x23, 0x07]) {
+            false
+        } else {
+            return Err(ReflectError::MissingHeader);
+        };
+
+        let words = spirv
+            .chunks(4)
+            .map(|c| if big_endian {
+                     ((c[0] as u32) << 24) | ((c[1] as u32) << 16) | ((c[2] as u32) << 8) |
+                         c[3] as u32
+                 } else {
+                     ((c[3] as u32) << 24) | ((c[2] as u32) << 16) | ((c[1] as u32) << 8) |
+                         c[0] as u32
+                 })
+            .collect::<Vec<_>>();
+
+        let mut instructions = Vec::new();
+        let mut words = &words[5 ..];
+        while !words.is_empty() {
+            let word_count = (words[0] >> 16) as usize;
+            let opcode = (words[0] & 0xffff) as u16;
+
+            if word_count == 0 || word_count > words.len() {
+                return Err(ReflectError::MalformedInstruction);
+            }
+
+            if let Some(instruction) = decode_instruction(opcode, &words[1 .. word_count])? {
+                instructions.push(instruction);
+            }
+
+            words = &words[word_count ..];
+        }
+
+        Ok(Spirv { instructions: instructions })
+    }
+
+    // Returns the instruction that defines the given type or constant.
+    fn type_or_constant(&self, id: u32) -> Result<&Instruction, ReflectError> {
+        self.instructions
+            .iter()
+            .find(|i| i.type_or_constant_id() == Some(id))
+            .ok_or(ReflectError::InvalidId { id: id })
+    }
+
+    // Returns the type pointed to by a pointer type.
+    fn pointed_type(&self, pointer_id: u32) -> Result<u32, ReflectError> {
+        match *self.type_or_constant(pointer_id)? {
+            Instruction::TypePointer { type_id, .. } => Ok(type_id),
+            _ => Err(ReflectError::InvalidId { id: pointer_id }),
+        }
+    }
+
+    // Returns the value of an integer constant.
+    fn constant(&self, id: u32) -> Result<u64, ReflectError> {
+        match *self.type_or_constant(id)? {
+            Instruction::Constant { ref data, .. } => {
+                Ok(data.iter().rev().fold(0u64, |a, &b| (a << 32) | b as u64))
+            },
+            _ => Err(ReflectError::InvalidId { id: id }),
+        }
+    }
+
+    // Returns the parameters of a decoration of an id, or `None` if it's not decorated with it.
+    fn decoration(&self, id: u32, decoration: u32) -> Option<&[u32]> {
+        self.instructions
+            .iter()
+            .filter_map(|i| match *i {
+                            Instruction::Decorate {
+                                target_id,
+                                decoration: d,
+                                ref params,
+                            } if target_id == id && d == decoration => Some(&params[..]),
+                            _ => None,
+                        })
+            .next()
+    }
+
+    // Same as `decoration`, but for a member of a struct.
+    fn member_decoration(&self, id: u32, member: u32, decoration: u32) -> Option<&[u32]> {
+        self.instructions
+            .iter()
+            .filter_map(|i| match *i {
+                            Instruction::MemberDecorate {
+                                target_id,
+                                member: m,
+                                decoration: d,
+                                ref params,
+                            } if target_id == id && m == member && d == decoration => {
+                                Some(&params[..])
+                            },
+                            _ => None,
+                        })
+            .next()
+    }
+
+    // Returns the first parameter of a decoration.
+    fn decoration_value(&self, id: u32, decoration: u32) -> Option<u32> {
+        self.decoration(id, decoration)
+            .and_then(|params| params.first().cloned())
+    }
+
+    // Returns the name of an id, or `None` if it has no name.
+    fn name(&self, id: u32) -> Option<&str> {
+        self.instructions
+            .iter()
+            .filter_map(|i| match *i {
+                            Instruction::Name { target_id, ref name } if target_id == id => {
+                                Some(&name[..])
+                            },
+                            _ => None,
+                        })
+            .find(|name| !name.is_empty())
+    }
+}
+
+fn decode_instruction(opcode: u16, operands: &[u32])
+                      -> Result<Option<Instruction>, ReflectError> {
+    let op = |n: usize| {
+        operands
+            .get(n)
+            .cloned()
+            .ok_or(ReflectError::MalformedInstruction)
+    };
+
+    Ok(Some(match opcode {
+                OP_NAME => Instruction::Name {
+                    target_id: op(0)?,
+                    name: parse_string(&operands[1 ..])?.0.into_string().map_err(|_| {
+                        ReflectError::MalformedInstruction
+                    })?,
+                },
+                OP_ENTRY_POINT => {
+                    op(1)?;
+                    let (name, interface) = parse_string(&operands[2 ..])?;
+                    Instruction::EntryPoint {
+                        execution: op(0)?,
+                        id: op(1)?,
+                        name: name,
+                        interface: interface.to_owned(),
+                    }
+                },
+                OP_EXECUTION_MODE => Instruction::ExecutionMode {
+                    target_id: op(0)?,
+                    mode: op(1)?,
+                },
+                OP_CAPABILITY => Instruction::Capability(op(0)?),
+                OP_TYPE_INT => Instruction::TypeInt {
+                    result_id: op(0)?,
+                    width: op(1)?,
+                    signedness: op(2)? != 0,
+                },
+                OP_TYPE_FLOAT => Instruction::TypeFloat {
+                    result_id: op(0)?,
+                    width: op(1)?,
+                },
+                OP_TYPE_VECTOR => Instruction::TypeVector {
+                    result_id: op(0)?,
+                    component_id: op(1)?,
+                    count: op(2)?,
+                },
+                OP_TYPE_MATRIX => Instruction::TypeMatrix {
+                    result_id: op(0)?,
+                    column_type_id: op(1)?,
+                    column_count: op(2)?,
+                },
+                OP_TYPE_IMAGE => Instruction::TypeImage {
+                    result_id: op(0)?,
+                    dim: op(2)?,
+                    arrayed: op(4)? != 0,
+                    ms: op(5)? != 0,
+                    sampled: op(6)?,
+                },
+                OP_TYPE_SAMPLER => Instruction::TypeSampler { result_id: op(0)? },
+                OP_TYPE_SAMPLED_IMAGE => Instruction::TypeSampledImage {
+                    result_id: op(0)?,
+                    image_type_id: op(1)?,
+                },
+                OP_TYPE_ARRAY => Instruction::TypeArray {
+                    result_id: op(0)?,
+                    type_id: op(1)?,
+                    length_id: op(2)?,
+                },
+                OP_TYPE_RUNTIME_ARRAY => Instruction::TypeRuntimeArray {
+                    result_id: op(0)?,
+                    type_id: op(1)?,
+                },
+                OP_TYPE_STRUCT => Instruction::TypeStruct {
+                    result_id: op(0)?,
+                    member_types: operands[1 ..].to_owned(),
+                },
+                OP_TYPE_POINTER => Instruction::TypePointer {
+                    result_id: op(0)?,
+                    storage_class: op(1)?,
+                    type_id: op(2)?,
+                },
+                OP_CONSTANT | OP_SPEC_CONSTANT => {
+                    op(1)?;
+                    Instruction::Constant {
+                        result_id: op(1)?,
+                        data: operands[2 ..].to_owned(),
+                    }
+                },
+                OP_VARIABLE => Instruction::Variable {
+                    result_type_id: op(0)?,
+                    result_id: op(1)?,
+                    storage_class: op(2)?,
+                },
+                OP_DECORATE => {
+                    op(1)?;
+                    Instruction::Decorate {
+                        target_id: op(0)?,
+                        decoration: op(1)?,
+                        params: operands[2 ..].to_owned(),
+                    }
+                },
+                OP_MEMBER_DECORATE => {
+                    op(2)?;
+                    Instruction::MemberDecorate {
+                        target_id: op(0)?,
+                        member: op(1)?,
+                        decoration: op(2)?,
+                        params: operands[3 ..].to_owned(),
+                    }
+                },
+                _ => return Ok(None),
+            }))
+}
+
+// Parses a nul-terminated string, and returns it alongside with the words that follow it.
+fn parse_string(words: &[u32]) -> Result<(CString, &[u32]), ReflectError> {
+    let mut bytes = Vec::new();
+
+    for (num, &word) in words.iter().enumerate() {
+        for &shift in &[0, 8, 16, 24] {
+            let byte = (word >> shift) as u8;
+            if byte == 0 {
+                let string = CString::new(bytes).unwrap(); // Can't contain a nul byte.
+                return Ok((string, &words[num + 1 ..]));
+            }
+            bytes.push(byte);
+        }
+    }
+
+    Err(ReflectError::MalformedInstruction)
+}
+
+// Adds the features required by a capability to `features`.
+fn add_capability_features(features: &mut Features, capability: u32)
+                           -> Result<(), ReflectError> {
+    match capability {
+        // Matrix, Shader, InputAttachment, Sampled1D, Image1D, SampledBuffer, ImageBuffer,
+        // ImageQuery and DerivativeControl are always supported.
+        0 | 1 | 40 | 43 | 44 | 46 | 47 | 50 | 51 => (),
+        2 => features.geometry_shader = true,
+        3 => features.tessellation_shader = true,
+        10 => features.shader_f3264 = true,
+        11 => features.shader_int64 = true,
+        22 => features.shader_int16 = true,
+        23 | 24 => features.shader_tessellation_and_geometry_point_size = true,
+        25 => features.shader_image_gather_extended = true,
+        27 | 48 => features.shader_storage_image_multisample = true,
+        28 => features.shader_uniform_buffer_array_dynamic_indexing = true,
+        29 => features.shader_sampled_image_array_dynamic_indexing = true,
+        30 => features.shader_storage_buffer_array_dynamic_indexing = true,
+        31 => features.shader_storage_image_array_dynamic_indexing = true,
+        32 => features.shader_clip_distance = true,
+        33 => features.shader_cull_distance = true,
+        34 | 45 => features.image_cube_array = true,
+        35 | 52 => features.sample_rate_shading = true,
+        41 => features.shader_resource_residency = true,
+        42 => features.shader_resource_min_lod = true,
+        49 => features.shader_storage_image_extended_formats = true,
+        55 => features.shader_storage_image_read_without_format = true,
+        56 => features.shader_storage_image_write_without_format = true,
+        57 => features.multi_viewport = true,
+        // ShaderNonUniformEXT only requires the `VK_EXT_descriptor_indexing` extension.
+        5301 => (),
+        5302 => features.runtime_descriptor_array = true,
+        5303 => features.shader_input_attachment_array_dynamic_indexing = true,
+        5304 => features.shader_uniform_texel_buffer_array_dynamic_indexing = true,
+        5305 => features.shader_storage_texel_buffer_array_dynamic_indexing = true,
+        5306 => features.shader_uniform_buffer_array_non_uniform_indexing = true,
+        5307 => features.shader_sampled_image_array_non_uniform_indexing = true,
+        5308 => features.shader_storage_buffer_array_non_uniform_indexing = true,
+        5309 => features.shader_storage_image_array_non_uniform_indexing = true,
+        5310 => features.shader_input_attachment_array_non_uniform_indexing = true,
+        5311 => features.shader_uniform_texel_buffer_array_non_uniform_indexing = true,
+        5312 => features.shader_storage_texel_buffer_array_non_uniform_indexing = true,
+        _ => return Err(ReflectError::UnsupportedCapability { capability: capability }),
+    }
+
+    Ok(())
+}
+
+// Returns the kind of primitives expected by a geometry shader entry point.
+fn geometry_primitives(doc: &Spirv, entry_point_id: u32)
+                       -> Result<GeometryShaderExecutionMode, ReflectError> {
+    for instruction in doc.instructions.iter() {
+        let mode = match *instruction {
+            Instruction::ExecutionMode { target_id, mode } if target_id == entry_point_id => mode,
+            _ => continue,
+        };
+
+        match mode {
+            19 => return Ok(GeometryShaderExecutionMode::Points),
+            20 => return Ok(GeometryShaderExecutionMode::Lines),
+            21 => return Ok(GeometryShaderExecutionMode::LinesWithAdjacency),
+            22 => return Ok(GeometryShaderExecutionMode::Triangles),
+            23 => return Ok(GeometryShaderExecutionMode::TrianglesWithAdjacency),
+            _ => (),
+        }
+    }
+
+    Err(ReflectError::MissingGeometryInputPrimitives)
+}
+
+// Builds the layout of an entry point, from all the descriptors and push constants of the code.
+fn layout_desc(doc: &Spirv, stages: ShaderStages)
+               -> Result<RuntimePipelineLayoutDesc, ReflectError> {
+    let mut descriptors: Vec<(usize, usize, DescriptorDesc)> = Vec::new();
+    let mut names = Vec::new();
+    let mut push_constants: Option<(usize, usize)> = None;
+
+    for instruction in doc.instructions.iter() {
+        let (result_type_id, result_id, storage_class) = match *instruction {
+            Instruction::Variable {
+                result_type_id,
+                result_id,
+                storage_class,
+            } => (result_type_id, result_id, storage_class),
+            _ => continue,
+        };
+
+        if storage_class == STORAGE_CLASS_PUSH_CONSTANT {
+            let (start, end) = struct_range(doc, doc.pointed_type(result_type_id)?, 0)?;
+            push_constants = Some(match push_constants {
+                                      Some((s, e)) => (cmp::min(s, start), cmp::max(e, end)),
+                                      None => (start, end),
+                                  });
+            continue;
+        }
+
+        let set = match doc.decoration_value(result_id, DECORATION_DESCRIPTOR_SET) {
+            Some(set) => set as usize,
+            None => continue,
+        };
+
+        let binding = doc.decoration_value(result_id, DECORATION_BINDING)
+            .ok_or(ReflectError::MissingBinding { id: result_id })? as usize;
+
+        let (ty, readonly, array_count) = descriptor_infos(doc,
+                                                           doc.pointed_type(result_type_id)?,
+                                                           result_id,
+                                                           storage_class,
+                                                           false,
+                                                           0)?;
+
+        // Multiple variables can be bound to the same descriptor. We only keep the first one.
+        if descriptors
+            .iter()
+            .any(|&(s, b, _)| s == set && b == binding)
+        {
+            continue;
+        }
+
+        descriptors.push((set,
+                          binding,
+                          DescriptorDesc {
+                              ty: ty,
+                              array_count: array_count,
+                              stages: stages.clone(),
+                              readonly: readonly,
+                              immutable_samplers: Vec::new(),
+                          }));
+
+        if let Some(name) = doc.name(result_id) {
+            names.push((name.to_owned(), set, binding));
+        }
+    }
+
+    let push_constants = match push_constants {
+        Some((start, end)) if end > start => {
+            vec![
+                PipelineLayoutDescPcRange {
+                    offset: start,
+                    size: end - start,
+                    stages: stages,
+                },
+            ]
+        },
+        _ => Vec::new(),
+    };
+
+    Ok(RuntimePipelineLayoutDesc {
+           descriptors: descriptors,
+           push_constants: push_constants,
+           names: names,
+       })
+}
+
+// Returns the type of a descriptor, whether it's read-only and its number of array elements.
+//
+// See also section 14.5.2 of the Vulkan specs: Descriptor Set Interface
+fn descriptor_infos(doc: &Spirv, ty: u32, variable: u32, storage_class: u32,
+                    force_combined_image_sampled: bool, depth: u32)
+                    -> Result<(DescriptorDescTy, bool, u32), ReflectError> {
+    if depth > MAX_TYPE_DEPTH {
+        return Err(ReflectError::MalformedInstruction);
+    }
+
+    let non_writable = doc.decoration(variable, DECORATION_NON_WRITABLE)
+        .is_some();
+
+    match *doc.type_or_constant(ty)? {
+        Instruction::TypeStruct { ref member_types, .. } if !force_combined_image_sampled => {
+            let storage = if doc.decoration(ty, DECORATION_BUFFER_BLOCK).is_some() {
+                true
+            } else if doc.decoration(ty, DECORATION_BLOCK).is_some() {
+                storage_class == STORAGE_CLASS_STORAGE_BUFFER
+            } else {
+                return Err(ReflectError::UnsupportedType { id: ty });
+            };
+
+            let readonly = !storage || non_writable ||
+                (0 .. member_types.len() as u32).all(|member| {
+                    doc.member_decoration(ty, member, DECORATION_NON_WRITABLE)
+                        .is_some()
+                });
+
+            // The block is a struct. Its members aren't described, as the content of a buffer
+            // descriptor isn't checked against the buffers that are written to it.
+            let desc = DescriptorDescTy::Buffer(DescriptorBufferDesc {
+                                                    dynamic: Some(false),
+                                                    storage: storage,
+                                                    content: DescriptorBufferContentDesc::Struct {},
+                                                });
+
+            Ok((desc, readonly, 1))
+        },
+
+        Instruction::TypeImage {
+            dim,
+            arrayed,
+            ms,
+            sampled,
+            ..
+        } => {
+            let sampled = match sampled {
+                1 => true,
+                2 => false,
+                _ => return Err(ReflectError::UnsupportedType { id: ty }),
+            };
+
+            let array_layers = if arrayed {
+                DescriptorImageDescArray::Arrayed { max_layers: None }
+            } else {
+                DescriptorImageDescArray::NonArrayed
+            };
+
+            let dimensions = match dim {
+                0 => DescriptorImageDescDimensions::OneDimensional,
+                1 => DescriptorImageDescDimensions::TwoDimensional,
+                2 => DescriptorImageDescDimensions::ThreeDimensional,
+                3 => DescriptorImageDescDimensions::Cube,
+                // Texel buffer.
+                5 if !force_combined_image_sampled => {
+                    let desc = DescriptorDescTy::TexelBuffer {
+                        storage: !sampled,
+                        format: None,
+                    };
+                    return Ok((desc, sampled || non_writable, 1));
+                },
+                // Input attachment.
+                6 if !force_combined_image_sampled && !sampled => {
+                    let desc = DescriptorDescTy::InputAttachment {
+                        multisampled: ms,
+                        array_layers: array_layers,
+                    };
+                    return Ok((desc, true, 1));
+                },
+                _ => return Err(ReflectError::UnsupportedType { id: ty }),
+            };
+
+            let desc = DescriptorImageDesc {
+                sampled: sampled,
+                dimensions: dimensions,
+                format: None,
+                multisampled: ms,
+                array_layers: array_layers,
+            };
+
+            let desc = if force_combined_image_sampled {
+                DescriptorDescTy::CombinedImageSampler(desc)
+            } else {
+                DescriptorDescTy::Image(desc)
+            };
+
+            Ok((desc, sampled || non_writable, 1))
+        },
+
+        Instruction::TypeSampledImage { image_type_id, .. } if !force_combined_image_sampled => {
+            descriptor_infos(doc, image_type_id, variable, storage_class, true, depth + 1)
+        },
+
+        Instruction::TypeSampler { .. } if !force_combined_image_sampled => {
+            Ok((DescriptorDescTy::Sampler, true, 1))
+        },
+
+        Instruction::TypeArray {
+            type_id, length_id, ..
+        } if !force_combined_image_sampled => {
+            let (desc, readonly, len) = descriptor_infos(doc, type_id, variable, storage_class,
+                                                         false, depth + 1)?;
+            if len != 1 {
+                return Err(ReflectError::UnsupportedType { id: ty });
+            }
+
+            let len = doc.constant(length_id)?;
+            if len == 0 || len > u32::max_value() as u64 {
+                return Err(ReflectError::UnsupportedType { id: ty });
+            }
+
+            Ok((desc, readonly, len as u32))
+        },
+
+        // The actual number of elements of a runtime-sized array is determined by the layout of
+        // the set, for example with a `BindlessTable`.
+        Instruction::TypeRuntimeArray { type_id, .. } if !force_combined_image_sampled => {
+            let (desc, readonly, len) = descriptor_infos(doc, type_id, variable, storage_class,
+                                                         false, depth + 1)?;
+            if len != 1 {
+                return Err(ReflectError::UnsupportedType { id: ty });
+            }
+
+            Ok((desc, readonly, 1))
+        },
+
+        _ => Err(ReflectError::UnsupportedType { id: ty }),
+    }
+}
+
+// Builds the definition of the inputs or of the outputs of an entry point.
+//
+// If `array` is true, each attribute is expected to be an array whose elements are described
+// instead.
+fn interface_def(doc: &Spirv, interface: &[u32], storage_class: u32, array: bool)
+                 -> Result<RuntimeShaderInterfaceDef, ReflectError> {
+    let mut entries: Vec<ShaderInterfaceDefEntry> = Vec::new();
+
+    for &id in interface {
+        let result_type_id = doc.instructions
+            .iter()
+            .filter_map(|i| match *i {
+                            Instruction::Variable {
+                                result_type_id,
+                                result_id,
+                                storage_class: s,
+                            } if result_id == id && s == storage_class => Some(result_type_id),
+                            _ => None,
+                        })
+            .next();
+
+        let result_type_id = match result_type_id {
+            Some(ty) => ty,
+            None => continue,
+        };
+
+        if is_builtin(doc, id, 0)? {
+            continue;
+        }
+
+        let location = doc.decoration_value(id, DECORATION_LOCATION)
+            .ok_or(ReflectError::MissingLocation { id: id })?;
+        let (format, num_locations) = format_from_id(doc, result_type_id, array, 0)?;
+        let end = location
+            .checked_add(num_locations)
+            .ok_or(ReflectError::UnsupportedType { id: result_type_id })?;
+        let range = location .. end;
+
+        if entries
+            .iter()
+            .any(|e| e.location.start < range.end && range.start < e.location.end)
+        {
+            return Err(ReflectError::OverlappingLocations { location: location });
+        }
+
+        entries.push(ShaderInterfaceDefEntry {
+                         location: range,
+                         format: format,
+                         name: doc.name(id).map(|name| Cow::Owned(name.to_owned())),
+                     });
+    }
+
+    Ok(RuntimeShaderInterfaceDef { entries: entries })
+}
+
+// Returns true if a `BuiltIn` decoration is applied on an id, one of its members or the type it
+// refers to.
+fn is_builtin(doc: &Spirv, id: u32, depth: u32) -> Result<bool, ReflectError> {
+    if depth > MAX_TYPE_DEPTH {
+        return Err(ReflectError::MalformedInstruction);
+    }
+
+    for instruction in doc.instructions.iter() {
+        match *instruction {
+            Instruction::Decorate {
+                target_id,
+                decoration: DECORATION_BUILT_IN,
+                ..
+            } |
+            Instruction::MemberDecorate {
+                target_id,
+                decoration: DECORATION_BUILT_IN,
+                ..
+            } if target_id == id => {
+                return Ok(true);
+            },
+            _ => (),
+        }
+    }
+
+    for instruction in doc.instructions.iter() {
+        match *instruction {
+            Instruction::Variable {
+                result_type_id,
+                result_id,
+                ..
+            } if result_id == id => {
+                return is_builtin(doc, result_type_id, depth + 1);
+            },
+            Instruction::TypeArray { result_id, type_id, .. } |
+            Instruction::TypePointer { result_id, type_id, .. } if result_id == id => {
+                return is_builtin(doc, type_id, depth + 1);
+            },
+            Instruction::TypeStruct {
+                result_id,
+                ref member_types,
+            } if result_id == id => {
+                for &member in member_types.iter() {
+                    if is_builtin(doc, member, depth + 1)? {
+                        return Ok(true);
+                    }
+                }
+                return Ok(false);
+            },
+            _ => (),
+        }
+    }
+
+    Ok(false)
+}
+
+// Returns the format and the number of locations occupied by an attribute of the given type.
+fn format_from_id(doc: &Spirv, id: u32, array: bool, depth: u32)
+                  -> Result<(Format, u32), ReflectError> {
+    if depth > MAX_TYPE_DEPTH {
+        return Err(ReflectError::MalformedInstruction);
+    }
+
+    match *doc.type_or_constant(id)? {
+        Instruction::TypePointer { type_id, .. } => format_from_id(doc, type_id, array, depth + 1),
+
+        Instruction::TypeArray {
+            type_id, length_id, ..
+        } => {
+            if array {
+                return format_from_id(doc, type_id, false, depth + 1);
+            }
+
+            let (format, num_locations) = format_from_id(doc, type_id, false, depth + 1)?;
+            let len = doc.constant(length_id)?;
+            if len == 0 || len > u32::max_value() as u64 {
+                return Err(ReflectError::UnsupportedType { id: id });
+            }
+            let num_locations = num_locations
+                .checked_mul(len as u32)
+                .ok_or(ReflectError::UnsupportedType { id: id })?;
+            Ok((format, num_locations))
+        },
+
+        _ if array => Err(ReflectError::UnsupportedType { id: id }),
+
+        Instruction::TypeMatrix {
+            column_type_id,
+            column_count,
+            ..
+        } => {
+            let (format, num_locations) = format_from_id(doc, column_type_id, false, depth + 1)?;
+            let num_locations = num_locations
+                .checked_mul(column_count)
+                .ok_or(ReflectError::UnsupportedType { id: id })?;
+            Ok((format, num_locations))
+        },
+
+        Instruction::TypeVector {
+            component_id, count, ..
+        } => {
+            let format = match (doc.type_or_constant(component_id)?, count) {
+                (&Instruction::TypeFloat { width: 32, .. }, 1) => Format::R32Sfloat,
+                (&Instruction::TypeFloat { width: 32, .. }, 2) => Format::R32G32Sfloat,
+                (&Instruction::TypeFloat { width: 32, .. }, 3) => Format::R32G32B32Sfloat,
+                (&Instruction::TypeFloat { width: 32, .. }, 4) => Format::R32G32B32A32Sfloat,
+                (&Instruction::TypeInt {
+                     width: 32,
+                     signedness: true,
+                     ..
+                 },
+                 1) => Format::R32Sint,
+                (&Instruction::TypeInt {
+                     width: 32,
+                     signedness: true,
+                     ..
+                 },
+                 2) => Format::R32G32Sint,
+                (&Instruction::TypeInt {
+                     width: 32,
+                     signedness: true,
+                     ..
+                 },
+                 3) => Format::R32G32B32Sint,
+                (&Instruction::TypeInt {
+                     width: 32,
+                     signedness: true,
+                     ..
+                 },
+                 4) => Format::R32G32B32A32Sint,
+                (&Instruction::TypeInt {
+                     width: 32,
+                     signedness: false,
+                     ..
+                 },
+                 1) => Format::R32Uint,
+                (&Instruction::TypeInt {
+                     width: 32,
+                     signedness: false,
+                     ..
+                 },
+                 2) => Format::R32G32Uint,
+                (&Instruction::TypeInt {
+                     width: 32,
+                     signedness: false,
+                     ..
+                 },
+                 3) => Format::R32G32B32Uint,
+                (&Instruction::TypeInt {
+                     width: 32,
+                     signedness: false,
+                     ..
+                 },
+                 4) => Format::R32G32B32A32Uint,
+                _ => return Err(ReflectError::UnsupportedType { id: id }),
+            };
+
+            Ok((format, 1))
+        },
+
+        Instruction::TypeFloat { width, .. } => {
+            let format = match width {
+                32 => Format::R32Sfloat,
+                64 => Format::R64Sfloat,
+                _ => return Err(ReflectError::UnsupportedType { id: id }),
+            };
+
+            Ok((format, 1))
+        },
+
+        Instruction::TypeInt {
+            width, signedness, ..
+        } => {
+            let format = match (width, signedness) {
+                (8, true) => Format::R8Sint,
+                (8, false) => Format::R8Uint,
+                (16, true) => Format::R16Sint,
+                (16, false) => Format::R16Uint,
+                (32, true) => Format::R32Sint,
+                (32, false) => Format::R32Uint,
+                (64, true) => Format::R64Sint,
+                (64, false) => Format::R64Uint,
+                _ => return Err(ReflectError::UnsupportedType { id: id }),
+            };
+
+            Ok((format, 1))
+        },
+
+        _ => Err(ReflectError::UnsupportedType { id: id }),
+    }
+}
+
+// Returns the offset of the first byte and of the byte after the last one of a struct, according
+// to the `Offset` decorations of its members.
+fn struct_range(doc: &Spirv, id: u32, depth: u32) -> Result<(usize, usize), ReflectError> {
+    if depth > MAX_TYPE_DEPTH {
+        return Err(ReflectError::MalformedInstruction);
+    }
+
+    let member_types = match *doc.type_or_constant(id)? {
+        Instruction::TypeStruct { ref member_types, .. } => member_types,
+        _ => return Err(ReflectError::UnsupportedType { id: id }),
+    };
+
+    let mut start = None;
+    let mut end = 0;
+
+    for (member, &member_ty) in member_types.iter().enumerate() {
+        let member = member as u32;
+
+        let offset = match doc.member_decoration(id, member, DECORATION_OFFSET) {
+            Some(params) => *params.first().ok_or(ReflectError::MalformedInstruction)? as usize,
+            None => end,
+        };
+
+        // The stride between the columns (or rows) of a matrix is a decoration of the member.
+        let matrix_stride = doc.member_decoration(id, member, DECORATION_MATRIX_STRIDE)
+            .and_then(|params| params.first().cloned());
+        let size = match (doc.type_or_constant(member_ty)?, matrix_stride) {
+            (&Instruction::TypeMatrix {
+                 column_type_id,
+                 column_count,
+                 ..
+             },
+             Some(stride)) => {
+                let row_major = doc.member_decoration(id, member, DECORATION_ROW_MAJOR)
+                    .is_some();
+                let num = if row_major {
+                    match *doc.type_or_constant(column_type_id)? {
+                        Instruction::TypeVector { count, .. } => count,
+                        _ => return Err(ReflectError::UnsupportedType { id: column_type_id }),
+                    }
+                } else {
+                    column_count
+                };
+                (stride as usize)
+                    .checked_mul(num as usize)
+                    .ok_or(ReflectError::UnsupportedType { id: member_ty })?
+            },
+            _ => type_size(doc, member_ty, depth + 1)?,
+        };
+
+        let member_end = offset
+            .checked_add(size)
+            .ok_or(ReflectError::UnsupportedType { id: id })?;
+        start = Some(cmp::min(start.unwrap_or(offset), offset));
+        end = cmp::max(end, member_end);
+    }
+
+    Ok((start.unwrap_or(0), end))
+}
+
+// Returns the size in bytes of a type.
+fn type_size(doc: &Spirv, id: u32, depth: u32) -> Result<usize, ReflectError> {
+    if depth > MAX_TYPE_DEPTH {
+        return Err(ReflectError::MalformedInstruction);
+    }
+
+    let size = match *doc.type_or_constant(id)? {
+        Instruction::TypeInt { width, .. } |
+        Instruction::TypeFloat { width, .. } => Some(width as usize / 8),
+        Instruction::TypeVector {
+            component_id, count, ..
+        } => type_size(doc, component_id, depth + 1)?.checked_mul(count as usize),
+        Instruction::TypeMatrix {
+            column_type_id,
+            column_count,
+            ..
+        } => type_size(doc, column_type_id, depth + 1)?.checked_mul(column_count as usize),
+        Instruction::TypeArray {
+            type_id, length_id, ..
+        } => {
+            let stride = match doc.decoration_value(id, DECORATION_ARRAY_STRIDE) {
+                Some(stride) => stride as usize,
+                None => type_size(doc, type_id, depth + 1)?,
+            };
+            let len = doc.constant(length_id)?;
+            if len > usize::max_value() as u64 {
+                return Err(ReflectError::UnsupportedType { id: id });
+            }
+            stride.checked_mul(len as usize)
+        },
+        Instruction::TypeStruct { .. } => Some(struct_range(doc, id, depth + 1)?.1),
+        _ => None,
+    };
+
+    size.ok_or(ReflectError::UnsupportedType { id: id })
+}
+
+#[cfg(test)]
+mod tests {
+    use descriptor::descriptor::DescriptorDescTy;
+    use descriptor::descriptor::ShaderStages;
+    use descriptor::pipeline_layout::PipelineLayoutDesc;
+    use descriptor::pipeline_layout::PipelineLayoutDescNames;
+    use format::Format;
+    use instance::Features;
+    use pipeline::shader::GeometryShaderExecutionMode;
+    use pipeline::shader::ShaderInterfaceDef;
+    use pipeline::shader::reflect::EntryPointType;
+    use pipeline::shader::reflect::ReflectError;
+    use pipeline::shader::reflect::ShaderReflection;
+
+    // Builds an instruction from its opcode and operands.
+    fn inst(opcode: u16, operands: &[u32]) -> Vec<u32> {
+        let mut words = vec![((operands.len() as u32 + 1) << 16) | opcode as u32];
+        words.extend_from_slice(operands);
+        words
+    }
+
+    // Builds an instruction whose operands contain a string.
+    fn inst_str(opcode: u16, before: &[u32], string: &str, after: &[u32]) -> Vec<u32> {
+        let mut operands = before.to_owned();
+        let mut bytes = string.as_bytes().to_owned();
+        bytes.push(0);
+        while bytes.len() % 4 != 0 {
+            bytes.push(0);
+        }
+        operands.extend(bytes.chunks(4).map(|c| {
+            c[0] as u32 | (c[1] as u32) << 8 | (c[2] as u32) << 16 | (c[3] as u32) << 24
+        }));
+        operands.extend_from_slice(after);
+        inst(opcode, &operands)
+    }
+
+    // Builds little endian SPIR-V code from a list of instructions.
+    fn module(instructions: &[Vec<u32>]) -> Vec<u8> {
+        let mut words = vec![0x07230203, 0x00010000, 0, 100, 0];
+        for instruction in instructions {
+            words.extend_from_slice(instruction);
+        }
+
+        let mut bytes = Vec::new();
+        for word in words {
+            bytes.extend_from_slice(&[word as u8, (word >> 8) as u8, (word >> 16) as u8,
+                                      (word >> 24) as u8]);
+        }
+        bytes
+    }
+
+    // Compute shader with a read-only storage buffer named `data` at binding 1 of set 0, an
+    // array of four combined image samplers named `textures` at binding 0 of set 1, and push
+    // constants made of a `vec4` and a `float`.
+    fn compute_module(binding_decoration: bool) -> Vec<u8> {
+        let mut instructions = vec![
+            inst(17, &[1]),
+            inst(14, &[0, 1]),
+            inst_str(15, &[5, 10], "main", &[]),
+            inst(16, &[10, 17, 8, 8, 1]),
+            inst_str(5, &[20], "data", &[]),
+            inst_str(5, &[30], "textures", &[]),
+            inst(71, &[21, 3]),
+            inst(72, &[21, 0, 35, 0]),
+            inst(72, &[21, 0, 24]),
+            inst(71, &[20, 34, 0]),
+            inst(71, &[30, 34, 1]),
+            inst(71, &[30, 33, 0]),
+            inst(71, &[41, 2]),
+            inst(72, &[41, 0, 35, 0]),
+            inst(72, &[41, 1, 35, 16]),
+            inst(22, &[2, 32]),
+            inst(21, &[3, 32, 0]),
+            inst(23, &[4, 2, 4]),
+            inst(43, &[3, 5, 4]),
+            inst(30, &[21, 4]),
+            inst(32, &[22, 2, 21]),
+            inst(59, &[22, 20, 2]),
+            inst(25, &[31, 2, 1, 0, 0, 0, 1, 0]),
+            inst(27, &[32, 31]),
+            inst(28, &[33, 32, 5]),
+            inst(32, &[34, 0, 33]),
+            inst(59, &[34, 30, 0]),
+            inst(30, &[41, 4, 2]),
+            inst(32, &[42, 9, 41]),
+            inst(59, &[42, 40, 9]),
+        ];
+
+        if binding_decoration {
+            instructions.push(inst(71, &[20, 33, 1]));
+        }
+
+        module(&instructions)
+    }
+
+    // Vertex shader with a `vec3` input named `position` at location 0, a `mat4` input at
+    // location 1, a `vec4` output named `color` at location `color_location` and a `Position`
+    // built-in output.
+    fn vertex_module(color_location: u32) -> Vec<u8> {
+        module(&[
+            inst(17, &[1]),
+            inst_str(15, &[0, 10], "main", &[20, 21, 22, 23]),
+            inst_str(5, &[20], "position", &[]),
+            inst_str(5, &[22], "color", &[]),
+            inst(71, &[20, 30, 0]),
+            inst(71, &[21, 30, 1]),
+            inst(71, &[22, 30, color_location]),
+            inst(71, &[23, 11, 0]),
+            inst(22, &[2, 32]),
+            inst(23, &[4, 2, 4]),
+            inst(23, &[6, 2, 3]),
+            inst(24, &[7, 4, 4]),
+            inst(32, &[11, 1, 6]),
+            inst(32, &[12, 1, 7]),
+            inst(32, &[13, 3, 4]),
+            inst(32, &[14, 1, 4]),
+            inst(59, &[11, 20, 1]),
+            inst(59, &[12, 21, 1]),
+            inst(59, &[if color_location == 0 { 13 } else { 14 }, 22,
+                       if color_location == 0 { 3 } else { 1 }]),
+            inst(59, &[13, 23, 3]),
+        ])
+    }
+
+    #[test]
+    fn compute_layout() {
+        let reflection = ShaderReflection::new(&compute_module(true)).unwrap();
+        assert_eq!(reflection.required_features(), &Features::none());
+
+        let ep = reflection.entry_point("main").unwrap();
+        assert_eq!(ep.ty(), EntryPointType::Compute);
+        assert_eq!(ep.input().elements().len(), 0);
+        assert_eq!(ep.output().elements().len(), 0);
+
+        let layout = ep.layout();
+        assert_eq!(layout.num_sets(), 2);
+        assert_eq!(layout.num_bindings_in_set(0), Some(2));
+        assert_eq!(layout.num_bindings_in_set(1), Some(1));
+        assert_eq!(layout.num_bindings_in_set(2), None);
+        assert!(layout.descriptor(0, 0).is_none());
+
+        let data = layout.descriptor(0, 1).unwrap();
+        match data.ty {
+            DescriptorDescTy::Buffer(ref desc) => assert!(desc.storage),
+            _ => panic!(),
+        }
+        assert_eq!(data.array_count, 1);
+        assert!(data.readonly);
+        assert_eq!(data.stages, ShaderStages::compute());
+
+        let textures = layout.descriptor(1, 0).unwrap();
+        match textures.ty {
+            DescriptorDescTy::CombinedImageSampler(ref desc) => assert!(desc.sampled),
+            _ => panic!(),
+        }
+        assert_eq!(textures.array_count, 4);
+
+        assert_eq!(layout.descriptor_by_name("data"), Some((0, 1)));
+        assert_eq!(layout.descriptor_by_name("textures"), Some((1, 0)));
+        assert_eq!(layout.descriptor_by_name("foo"), None);
+
+        assert_eq!(layout.num_push_constants_ranges(), 1);
+        let range = layout.push_constants_range(0).unwrap();
+        assert_eq!(range.offset, 0);
+        assert_eq!(range.size, 20);
+        assert_eq!(range.stages, ShaderStages::compute());
+    }
+
+    #[test]
+    fn vertex_interface() {
+        let reflection = ShaderReflection::new(&vertex_module(0)).unwrap();
+        assert!(reflection.entry_point("foo").is_none());
+
+        let ep = reflection.entry_point("main").unwrap();
+        assert_eq!(ep.ty(), EntryPointType::Vertex);
+        assert_eq!(ep.layout().num_sets(), 0);
+
+        let input = ep.input().elements().collect::<Vec<_>>();
+        assert_eq!(input.len(), 2);
+        assert_eq!(input[0].location, 0 .. 1);
+        assert_eq!(input[0].format, Format::R32G32B32Sfloat);
+        assert_eq!(input[0].name.as_ref().map(|n| &n[..]), Some("position"));
+        assert_eq!(input[1].location, 1 .. 5);
+        assert_eq!(input[1].format, Format::R32G32B32A32Sfloat);
+        assert!(input[1].name.is_none());
+
+        // The built-in output is ignored.
+        let output = ep.output().elements().collect::<Vec<_>>();
+        assert_eq!(output.len(), 1);
+        assert_eq!(output[0].location, 0 .. 1);
+        assert_eq!(output[0].format, Format::R32G32B32A32Sfloat);
+    }
+
+    #[test]
+    fn geometry_primitives() {
+        let spirv = module(&[inst(17, &[1]),
+                             inst(17, &[2]),
+                             inst_str(15, &[3, 10], "main", &[]),
+                             inst(16, &[10, 22])]);
+        let reflection = ShaderReflection::new(&spirv).unwrap();
+        assert!(reflection.required_features().geometry_shader);
+        assert_eq!(reflection.entry_point("main").unwrap().ty(),
+                   EntryPointType::Geometry(GeometryShaderExecutionMode::Triangles));
+
+        let spirv = module(&[inst(17, &[1]),
+                             inst(17, &[2]),
+                             inst_str(15, &[3, 10], "main", &[])]);
+        match ShaderReflection::new(&spirv) {
+            Err(ReflectError::MissingGeometryInputPrimitives) => (),
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn missing_header() {
+        match ShaderReflection::new(&[]) {
+            Err(ReflectError::MissingHeader) => (),
+            _ => panic!(),
+        }
+
+        match ShaderReflection::new(&[0; 20]) {
+            Err(ReflectError::MissingHeader) => (),
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn truncated_instruction() {
+        let mut spirv = module(&[inst(17, &[1])]);
+        spirv.truncate(spirv.len() - 4);
+
+        match ShaderReflection::new(&spirv) {
+            Err(ReflectError::MalformedInstruction) => (),
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn unsupported_capability() {
+        // The `Kernel` capability.
+        match ShaderReflection::new(&module(&[inst(17, &[6])])) {
+            Err(ReflectError::UnsupportedCapability { capability: 6 }) => (),
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn missing_binding() {
+        match ShaderReflection::new(&compute_module(false)) {
+            Err(ReflectError::MissingBinding { id: 20 }) => (),
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn overlapping_locations() {
+        match ShaderReflection::new(&vertex_module(4)) {
+            Err(ReflectError::OverlappingLocations { .. }) => (),
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn runtime_descriptor_array() {
+        // A runtime-sized array of samplers at binding 2 of set 0.
+        let spirv = module(&[inst(17, &[1]),
+                             inst(17, &[5302]),
+                             inst(17, &[5301]),
+                             inst_str(15, &[5, 10], "main", &[]),
+                             inst(71, &[20, 34, 0]),
+                             inst(71, &[20, 33, 2]),
+                             inst(26, &[2]),
+                             inst(29, &[3, 2]),
+                             inst(32, &[4, 0, 3]),
+                             inst(59, &[4, 20, 0])]);
+        let reflection = ShaderReflection::new(&spirv).unwrap();
+        assert_eq!(reflection.required_features(),
+                   &Features { runtime_descriptor_array: true, ..Features::none() });
+
+        let layout = reflection.entry_point("main").unwrap().layout();
+        let samplers = layout.descriptor(0, 2).unwrap();
+        match samplers.ty {
+            DescriptorDescTy::Sampler => (),
+            _ => panic!(),
+        }
+        assert_eq!(samplers.array_count, 1);
+    }
+
+    #[test]
+    fn locations_overflow() {
+        // A `vec4` input at the last location.
+        let spirv = module(&[inst(17, &[1]),
+                             inst_str(15, &[0, 10], "main", &[20]),
+                             inst(71, &[20, 30, 0xffffffff]),
+                             inst(22, &[2, 32]),
+                             inst(23, &[4, 2, 4]),
+                             inst(32, &[11, 1, 4]),
+                             inst(59, &[11, 20, 1])]);
+        match ShaderReflection::new(&spirv) {
+            Err(ReflectError::UnsupportedType { .. }) => (),
+            _ => panic!(),
+        }
+
+        // An input array of 2^30 `mat4`s, which would need 2^32 locations.
+        let spirv = module(&[inst(17, &[1]),
+                             inst_str(15, &[0, 10], "main", &[20]),
+                             inst(71, &[20, 30, 0]),
+                             inst(22, &[2, 32]),
+                             inst(23, &[4, 2, 4]),
+                             inst(24, &[7, 4, 4]),
+                             inst(21, &[3, 32, 0]),
+                             inst(43, &[3, 5, 0x40000000]),
+                             inst(28, &[8, 7, 5]),
+                             inst(32, &[11, 1, 8]),
+                             inst(59, &[11, 20, 1])]);
+        match ShaderReflection::new(&spirv) {
+            Err(ReflectError::UnsupportedType { id: 8 }) => (),
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn cyclic_type() {
+        // An input whose type is a pointer to itself.
+        let spirv = module(&[inst(17, &[1]),
+                             inst_str(15, &[0, 10], "main", &[20]),
+                             inst(71, &[20, 30, 0]),
+                             inst(32, &[11, 1, 11]),
+                             inst(59, &[11, 20, 1])]);
+        match ShaderReflection::new(&spirv) {
+            Err(ReflectError::MalformedInstruction) => (),
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn reflect_basic_shaders() {
+        let vs = ShaderReflection::new(&BASIC_VS).unwrap();
+        let vs = vs.entry_point("main").unwrap();
+        assert_eq!(vs.ty(), EntryPointType::Vertex);
+        assert_eq!(vs.layout().num_sets(), 0);
+        let input = vs.input().elements().collect::<Vec<_>>();
+        assert_eq!(input.len(), 1);
+        assert_eq!(input[0].location, 0 .. 1);
+        assert_eq!(input[0].format, Format::R32G32Sfloat);
+        assert_eq!(vs.output().elements().len(), 0);
+
+        let fs = ShaderReflection::new(&BASIC_FS).unwrap();
+        let fs = fs.entry_point("main").unwrap();
+        assert_eq!(fs.ty(), EntryPointType::Fragment);
+        assert_eq!(fs.input().elements().len(), 0);
+        let output = fs.output().elements().collect::<Vec<_>>();
+        assert_eq!(output.len(), 1);
+        assert_eq!(output[0].location, 0 .. 1);
+        assert_eq!(output[0].format, Format::R32G32B32A32Sfloat);
+    }
+
+    /*
+        #version 450
+
+        #extension GL_ARB_separate_shader_objects : enable
+        #extension GL_ARB_shading_language_420pack : enable
+
+        layout(location = 0) in vec2 position;
+
+        void main() {
+            gl_Position = vec4(position, 0.0, 1.0);
+        }
+    */
+    const BASIC_VS: [u8; 912] = [3, 2, 35, 7, 0, 0, 1, 0, 1, 0, 8, 0, 27, 0, 0, 0, 0, 0, 0, 0, 17,
+                                 0, 2, 0, 1, 0, 0, 0, 17, 0, 2, 0, 32, 0, 0, 0, 17, 0, 2, 0, 33, 0,
+                                 0, 0, 11, 0, 6, 0, 1, 0, 0, 0, 71, 76, 83, 76, 46, 115, 116, 100,
+                                 46, 52, 53, 48, 0, 0, 0, 0, 14, 0, 3, 0, 0, 0, 0, 0, 1, 0, 0, 0,
+                                 15, 0, 7, 0, 0, 0, 0, 0, 4, 0, 0, 0, 109, 97, 105, 110, 0, 0, 0,
+                                 0, 13, 0, 0, 0, 18, 0, 0, 0, 3, 0, 3, 0, 2, 0, 0, 0, 194, 1, 0,
+                                 0, 4, 0, 9, 0, 71, 76, 95, 65, 82, 66, 95, 115, 101, 112, 97,
+                                 114, 97, 116, 101, 95, 115, 104, 97, 100, 101, 114, 95, 111, 98,
+                                 106, 101, 99, 116, 115, 0, 0, 4, 0, 9, 0, 71, 76, 95, 65, 82, 66,
+                                 95, 115, 104, 97, 100, 105, 110, 103, 95, 108, 97, 110, 103, 117,
+                                 97, 103, 101, 95, 52, 50, 48, 112, 97, 99, 107, 0, 5, 0, 4, 0, 4,
+                                 0, 0, 0, 109, 97, 105, 110, 0, 0, 0, 0, 5, 0, 6, 0, 11, 0, 0, 0,
+                                 103, 108, 95, 80, 101, 114, 86, 101, 114, 116, 101, 120, 0, 0, 0,
+                                 0, 6, 0, 6, 0, 11, 0, 0, 0, 0, 0, 0, 0, 103, 108, 95, 80, 111,
+                                 115, 105, 116, 105, 111, 110, 0, 6, 0, 7, 0, 11, 0, 0, 0, 1, 0,
+                                 0, 0, 103, 108, 95, 80, 111, 105, 110, 116, 83, 105, 122, 101, 0,
+                                 0, 0, 0, 6, 0, 7, 0, 11, 0, 0, 0, 2, 0, 0, 0, 103, 108, 95, 67,
+                                 108, 105, 112, 68, 105, 115, 116, 97, 110, 99, 101, 0, 6, 0, 7,
+                                 0, 11, 0, 0, 0, 3, 0, 0, 0, 103, 108, 95, 67, 117, 108, 108, 68,
+                                 105, 115, 116, 97, 110, 99, 101, 0, 5, 0, 3, 0, 13, 0, 0, 0, 0, 0,
+                                 0, 0, 5, 0, 5, 0, 18, 0, 0, 0, 112, 111, 115, 105, 116, 105, 111,
+                                 110, 0, 0, 0, 0, 72, 0, 5, 0, 11, 0, 0, 0, 0, 0, 0, 0, 11, 0, 0,
+                                 0, 0, 0, 0, 0, 72, 0, 5, 0, 11, 0, 0, 0, 1, 0, 0, 0, 11, 0, 0, 0,
+                                 1, 0, 0, 0, 72, 0, 5, 0, 11, 0, 0, 0, 2, 0, 0, 0, 11, 0, 0, 0, 3,
+                                 0, 0, 0, 72, 0, 5, 0, 11, 0, 0, 0, 3, 0, 0, 0, 11, 0, 0, 0, 4, 0,
+                                 0, 0, 71, 0, 3, 0, 11, 0, 0, 0, 2, 0, 0, 0, 71, 0, 4, 0, 18, 0, 0,
+                                 0, 30, 0, 0, 0, 0, 0, 0, 0, 19, 0, 2, 0, 2, 0, 0, 0, 33, 0, 3, 0,
+                                 3, 0, 0, 0, 2, 0, 0, 0, 22, 0, 3, 0, 6, 0, 0, 0, 32, 0, 0, 0, 23,
+                                 0, 4, 0, 7, 0, 0, 0, 6, 0, 0, 0, 4, 0, 0, 0, 21, 0, 4, 0, 8, 0, 0,
+                                 0, 32, 0, 0, 0, 0, 0, 0, 0, 43, 0, 4, 0, 8, 0, 0, 0, 9, 0, 0, 0,
+                                 1, 0, 0, 0, 28, 0, 4, 0, 10, 0, 0, 0, 6, 0, 0, 0, 9, 0, 0, 0, 30,
+                                 0, 6, 0, 11, 0, 0, 0, 7, 0, 0, 0, 6, 0, 0, 0, 10, 0, 0, 0, 10, 0,
+                                 0, 0, 32, 0, 4, 0, 12, 0, 0, 0, 3, 0, 0, 0, 11, 0, 0, 0, 59, 0,
+                                 4, 0, 12, 0, 0, 0, 13, 0, 0, 0, 3, 0, 0, 0, 21, 0, 4, 0, 14, 0,
+                                 0, 0, 32, 0, 0, 0, 1, 0, 0, 0, 43, 0, 4, 0, 14, 0, 0, 0, 15, 0,
+                                 0, 0, 0, 0, 0, 0, 23, 0, 4, 0, 16, 0, 0, 0, 6, 0, 0, 0, 2, 0, 0,
+                                 0, 32, 0, 4, 0, 17, 0, 0, 0, 1, 0, 0, 0, 16, 0, 0, 0, 59, 0, 4,
+                                 0, 17, 0, 0, 0, 18, 0, 0, 0, 1, 0, 0, 0, 43, 0, 4, 0, 6, 0, 0,
+                                 0, 20, 0, 0, 0, 0, 0, 0, 0, 43, 0, 4, 0, 6, 0, 0, 0, 21, 0, 0,
+                                 0, 0, 0, 128, 63, 32, 0, 4, 0, 25, 0, 0, 0, 3, 0, 0, 0, 7, 0, 0,
+                                 0, 54, 0, 5, 0, 2, 0, 0, 0, 4, 0, 0, 0, 0, 0, 0, 0, 3, 0, 0, 0,
+                                 248, 0, 2, 0, 5, 0, 0, 0, 61, 0, 4, 0, 16, 0, 0, 0, 19, 0, 0, 0,
+                                 18, 0, 0, 0, 81, 0, 5, 0, 6, 0, 0, 0, 22, 0, 0, 0, 19, 0, 0, 0,
+                                 0, 0, 0, 0, 81, 0, 5, 0, 6, 0, 0, 0, 23, 0, 0, 0, 19, 0, 0, 0, 1,
+                                 0, 0, 0, 80, 0, 7, 0, 7, 0, 0, 0, 24, 0, 0, 0, 22, 0, 0, 0, 23,
+                                 0, 0, 0, 20, 0, 0, 0, 21, 0, 0, 0, 65, 0, 5, 0, 25, 0, 0, 0, 26,
+                                 0, 0, 0, 13, 0, 0, 0, 15, 0, 0, 0, 62, 0, 3, 0, 26, 0, 0, 0, 24,
+                                 0, 0, 0, 253, 0, 1, 0, 56, 0, 1, 0];
+
+    /*
+        #version 450
+
+        #extension GL_ARB_separate_shader_objects : enable
+        #extension GL_ARB_shading_language_420pack : enable
+
+        layout(location = 0) out vec4 f_color;
+
+        void main() {
+            f_color = vec4(1.0, 0.0, 0.0, 1.0);
+        }
+    */
+    const BASIC_FS: [u8; 420] = [3, 2, 35, 7, 0, 0, 1, 0, 1, 0, 8, 0, 13, 0, 0, 0, 0, 0, 0, 0, 17,
+                                 0, 2, 0, 1, 0, 0, 0, 11, 0, 6, 0, 1, 0, 0, 0, 71, 76, 83, 76, 46,
+                                 115, 116, 100, 46, 52, 53,48, 0, 0, 0, 0, 14, 0, 3, 0, 0, 0, 0,
+                                 0, 1, 0, 0, 0, 15, 0, 6, 0, 4, 0, 0, 0, 4, 0, 0, 0, 109, 97,
+                                 105, 110, 0, 0, 0, 0, 9, 0, 0, 0, 16, 0, 3, 0, 4, 0, 0, 0, 7, 0,
+                                 0, 0, 3, 0, 3, 0, 2, 0, 0, 0, 194, 1, 0, 0, 4, 0, 9, 0, 71, 76,
+                                 95, 65, 82, 66, 95, 115, 101, 112, 97, 114, 97, 116, 101, 95,
+                                 115, 104, 97, 100, 101, 114, 95, 111, 98, 106, 101, 99, 116, 115,
+                                 0, 0, 4, 0, 9, 0, 71, 76, 95, 65, 82, 66, 95, 115, 104, 97, 100,
+                                 105, 110, 103, 95, 108, 97, 110, 103, 117, 97, 103, 101, 95, 52,
+                                 50, 48, 112, 97, 99, 107, 0, 5, 0, 4, 0, 4, 0, 0, 0, 109, 97,
+                                 105, 110, 0, 0, 0, 0, 5, 0, 4, 0, 9, 0, 0, 0, 102, 95, 99, 111,
+                                 108, 111, 114, 0, 71, 0, 4, 0, 9, 0, 0, 0, 30, 0, 0, 0, 0, 0,
+                                 0, 0, 19, 0, 2, 0, 2, 0, 0, 0, 33, 0, 3, 0, 3, 0, 0, 0, 2, 0, 0,
+                                 0, 22, 0, 3, 0, 6, 0, 0, 0, 32, 0, 0, 0, 23, 0, 4, 0, 7, 0, 0,
+                                 0, 6, 0, 0, 0, 4, 0, 0, 0, 32, 0, 4, 0, 8, 0, 0, 0, 3, 0, 0, 0,
+                                 7, 0, 0, 0, 59, 0, 4, 0, 8, 0, 0, 0, 9, 0, 0, 0, 3, 0, 0, 0, 43,
+                                 0, 4, 0, 6, 0, 0, 0, 10, 0, 0, 0, 0, 0, 128, 63, 43, 0, 4, 0, 6,
+                                 0, 0, 0, 11, 0, 0, 0, 0, 0, 0, 0, 44, 0, 7, 0, 7, 0, 0, 0, 12, 0,
+                                 0, 0, 10, 0, 0, 0, 11, 0, 0, 0, 11, 0, 0, 0, 10, 0, 0, 0, 54, 0,
+                                 5, 0, 2, 0, 0, 0, 4, 0, 0, 0, 0, 0, 0, 0, 3, 0, 0, 0, 248, 0, 2,
+                                 0, 5, 0, 0, 0, 62, 0, 3, 0, 9, 0, 0, 0, 12, 0, 0, 0, 253, 0, 1,
+                                 0, 56, 0, 1, 0];
+}
@@ -68,7 +68,7 @@ impl<P> UnsafeQueryPool<P>
             let vk = device.pointers();
             check_errors(vk.CreateQueryPool(device.internal_object(),
                                             &infos,
-                                            ptr::null(),
+                                            device.allocation_callbacks().as_ptr(),
                                             &mut output))?;
             output
         };
@@ -181,7 +181,9 @@ impl<P> Drop for UnsafeQueryPool<P>
     fn drop(&mut self) {
         unsafe {
             let vk = self.device.pointers();
-            vk.DestroyQueryPool(self.device.internal_object(), self.pool, ptr::null());
+            vk.DestroyQueryPool(self.device.internal_object(),
+                                self.pool,
+                                self.device.allocation_callbacks().as_ptr());
         }
     }
 }
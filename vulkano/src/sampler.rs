@@ -43,6 +43,29 @@
 //!                                      0.0, 100.0).unwrap();;
 //! ```
 //!
+//! Samplers can also be created with a `SamplerBuilder`, which provides default values for all
+//! the parameters and returns an error instead of panicking if they are invalid:
+//!
+//! ```
+//! use vulkano::sampler::Filter;
+//! use vulkano::sampler::Sampler;
+//! use vulkano::sampler::SamplerAddressMode;
+//!
+//! # let device: std::sync::Arc<vulkano::device::Device> = return;
+//! let _sampler = Sampler::start()
+//!     .filter(Filter::Nearest)
+//!     .address_mode(SamplerAddressMode::ClampToEdge)
+//!     .build(device.clone())
+//!     .unwrap();
+//! ```
+//!
+//! # Sharing samplers
+//!
+//! The number of samplers that can exist simultaneously on a device is limited (see the
+//! `max_sampler_allocation_count` limit). Instead of creating a new sampler for each material,
+//! use `SamplerBuilder::build_cached`. It returns the existing sampler if a sampler with the same
+//! parameters is still alive on the same device, and creates a new one otherwise.
+//!
 //! # About border colors
 //!
 //! One of the possible values of `SamplerAddressMode` and `UnnormalizedSamplerAddressMode` is
@@ -73,6 +96,7 @@ use OomError;
 use VulkanObject;
 use check_errors;
 use device::Device;
+use device::DeviceOwned;
 use vk;
 
 pub use pipeline::depth_stencil::Compare;
@@ -89,6 +113,25 @@ pub struct Sampler {
 }
 
 impl Sampler {
+    /// Starts the building process of a sampler. Returns a builder object whose parameters have
+    /// default values, and that you can modify before calling `build` or `build_cached`.
+    #[inline]
+    pub fn start() -> SamplerBuilder {
+        SamplerBuilder {
+            mag_filter: Filter::Linear,
+            min_filter: Filter::Linear,
+            mipmap_mode: MipmapMode::Nearest,
+            address_u: SamplerAddressMode::Repeat,
+            address_v: SamplerAddressMode::Repeat,
+            address_w: SamplerAddressMode::Repeat,
+            mip_lod_bias: 0.0,
+            max_anisotropy: 1.0,
+            min_lod: 0.0,
+            max_lod: 1_000.0,
+            compare: None,
+        }
+    }
+
     /// Shortcut for creating a sampler with linear sampling, linear mipmaps, and with the repeat
     /// mode for borders.
     ///
@@ -228,127 +271,24 @@ impl Sampler {
                 -> Result<Arc<Sampler>, SamplerCreationError> {
         assert!(max_anisotropy >= 1.0);
         assert!(min_lod <= max_lod);
-
-        // Check max anisotropy.
-        if max_anisotropy > 1.0 {
-            if !device.enabled_features().sampler_anisotropy {
-                return Err(SamplerCreationError::SamplerAnisotropyFeatureNotEnabled);
-            }
-
-            let limit = device.physical_device().limits().max_sampler_anisotropy();
-            if max_anisotropy > limit {
-                return Err(SamplerCreationError::AnisotropyLimitExceeded {
-                               requested: max_anisotropy,
-                               maximum: limit,
-                           });
-            }
-        }
-
-        // Check mip_lod_bias value.
-        {
-            let limit = device.physical_device().limits().max_sampler_lod_bias();
-            if mip_lod_bias > limit {
-                return Err(SamplerCreationError::MipLodBiasLimitExceeded {
-                               requested: mip_lod_bias,
-                               maximum: limit,
-                           });
-            }
-        }
-
-        // Check MirrorClampToEdge extension support
-        if [address_u, address_v, address_w]
-            .iter()
-            .any(|&mode| mode == SamplerAddressMode::MirrorClampToEdge)
-        {
-            if !device.loaded_extensions().khr_sampler_mirror_clamp_to_edge {
-                return Err(SamplerCreationError::SamplerMirrorClampToEdgeExtensionNotEnabled);
-            }
-        }
-
-        // Handling border color.
-        let border_color = address_u.border_color();
-        let border_color = match (border_color, address_v.border_color()) {
-            (Some(b1), Some(b2)) => {
-                assert_eq!(b1, b2);
-                Some(b1)
-            },
-            (None, b) => b,
-            (b, None) => b,
-        };
-        let border_color = match (border_color, address_w.border_color()) {
-            (Some(b1), Some(b2)) => {
-                assert_eq!(b1, b2);
-                Some(b1)
-            },
-            (None, b) => b,
-            (b, None) => b,
+        assert!(common_border_color(&[address_u, address_v, address_w]).is_ok(),
+                "All the `ClampToBorder` address modes must use the same border color");
+
+        let builder = SamplerBuilder {
+            mag_filter: mag_filter,
+            min_filter: min_filter,
+            mipmap_mode: mipmap_mode,
+            address_u: address_u,
+            address_v: address_v,
+            address_w: address_w,
+            mip_lod_bias: mip_lod_bias,
+            max_anisotropy: max_anisotropy,
+            min_lod: min_lod,
+            max_lod: max_lod,
+            compare: compare,
         };
 
-        let vk = device.pointers();
-        let sampler = unsafe {
-            let infos = vk::SamplerCreateInfo {
-                sType: vk::STRUCTURE_TYPE_SAMPLER_CREATE_INFO,
-                pNext: ptr::null(),
-                flags: 0, // reserved
-                magFilter: mag_filter as u32,
-                minFilter: min_filter as u32,
-                mipmapMode: mipmap_mode as u32,
-                addressModeU: address_u.to_vk(),
-                addressModeV: address_v.to_vk(),
-                addressModeW: address_w.to_vk(),
-                mipLodBias: mip_lod_bias,
-                anisotropyEnable: if max_anisotropy > 1.0 {
-                    vk::TRUE
-                } else {
-                    vk::FALSE
-                },
-                maxAnisotropy: max_anisotropy,
-                compareEnable: if compare.is_some() {
-                    vk::TRUE
-                } else {
-                    vk::FALSE
-                },
-                compareOp: compare.map(|c| c as u32).unwrap_or(0),
-                minLod: min_lod,
-                maxLod: max_lod,
-                borderColor: border_color.map(|b| b as u32).unwrap_or(0),
-                unnormalizedCoordinates: vk::FALSE,
-            };
-
-            let mut output = mem::uninitialized();
-            check_errors(vk.CreateSampler(device.internal_object(),
-                                          &infos,
-                                          ptr::null(),
-                                          &mut output))?;
-            output
-        };
-
-        Ok(Arc::new(Sampler {
-                        sampler: sampler,
-                        device: device.clone(),
-                        compare_mode: compare.is_some(),
-                        unnormalized: false,
-                        usable_with_float_formats: match border_color {
-                            Some(BorderColor::FloatTransparentBlack) => true,
-                            Some(BorderColor::FloatOpaqueBlack) => true,
-                            Some(BorderColor::FloatOpaqueWhite) => true,
-                            Some(_) => false,
-                            None => true,
-                        },
-                        usable_with_int_formats: compare.is_none() &&
-                            match border_color {
-                                Some(BorderColor::IntTransparentBlack) => true,
-                                Some(BorderColor::IntOpaqueBlack) => true,
-                                Some(BorderColor::IntOpaqueWhite) => true,
-                                Some(_) => false,
-                                None => true,
-                            },
-                        usable_with_swizzling: match border_color {
-                            Some(BorderColor::FloatOpaqueBlack) => false,
-                            Some(BorderColor::IntOpaqueBlack) => false,
-                            _ => true,
-                        },
-                    }))
+        builder.build(device)
     }
 
     /// Creates a sampler with unnormalized coordinates. This means that texture coordinates won't
@@ -405,7 +345,7 @@ impl Sampler {
             let mut output = mem::uninitialized();
             check_errors(vk.CreateSampler(device.internal_object(),
                                           &infos,
-                                          ptr::null(),
+                                          device.allocation_callbacks().as_ptr(),
                                           &mut output))?;
             output
         };
@@ -471,6 +411,13 @@ impl Sampler {
     }
 }
 
+unsafe impl DeviceOwned for Sampler {
+    #[inline]
+    fn device(&self) -> &Arc<Device> {
+        &self.device
+    }
+}
+
 unsafe impl VulkanObject for Sampler {
     type Object = vk::Sampler;
 
@@ -492,11 +439,332 @@ impl Drop for Sampler {
     fn drop(&mut self) {
         unsafe {
             let vk = self.device.pointers();
-            vk.DestroySampler(self.device.internal_object(), self.sampler, ptr::null());
+            vk.DestroySampler(self.device.internal_object(),
+                              self.sampler,
+                              self.device.allocation_callbacks().as_ptr());
         }
     }
 }
 
+/// Prototype for a `Sampler`.
+///
+/// Obtained with `Sampler::start()`. The default values of the parameters are:
+///
+/// - `Linear` for the magnification and minification filters, and `Nearest` for the mipmap mode.
+/// - `Repeat` for the three address modes.
+/// - No mip lod bias and no anisotropic filtering.
+/// - A lod range of `0.0` to `1000.0`, which means that all the mipmap levels can be used.
+/// - No depth comparison.
+///
+/// Contrary to `Sampler::new`, invalid parameters make `build` return an error instead of
+/// panicking.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct SamplerBuilder {
+    mag_filter: Filter,
+    min_filter: Filter,
+    mipmap_mode: MipmapMode,
+    address_u: SamplerAddressMode,
+    address_v: SamplerAddressMode,
+    address_w: SamplerAddressMode,
+    mip_lod_bias: f32,
+    max_anisotropy: f32,
+    min_lod: f32,
+    max_lod: f32,
+    compare: Option<Compare>,
+}
+
+impl SamplerBuilder {
+    /// Sets how the implementation should sample from the image when it is larger than the
+    /// original.
+    #[inline]
+    pub fn mag_filter(mut self, filter: Filter) -> Self {
+        self.mag_filter = filter;
+        self
+    }
+
+    /// Sets how the implementation should sample from the image when it is smaller than the
+    /// original.
+    #[inline]
+    pub fn min_filter(mut self, filter: Filter) -> Self {
+        self.min_filter = filter;
+        self
+    }
+
+    /// Sets both the magnification and the minification filters.
+    #[inline]
+    pub fn filter(self, filter: Filter) -> Self {
+        self.mag_filter(filter).min_filter(filter)
+    }
+
+    /// Sets how the implementation should choose which mipmap to use.
+    #[inline]
+    pub fn mipmap_mode(mut self, mode: MipmapMode) -> Self {
+        self.mipmap_mode = mode;
+        self
+    }
+
+    /// Sets the behavior when sampling outside of the `[0.0, 1.0]` range of the first texture
+    /// coordinate.
+    #[inline]
+    pub fn address_mode_u(mut self, mode: SamplerAddressMode) -> Self {
+        self.address_u = mode;
+        self
+    }
+
+    /// Sets the behavior when sampling outside of the `[0.0, 1.0]` range of the second texture
+    /// coordinate.
+    #[inline]
+    pub fn address_mode_v(mut self, mode: SamplerAddressMode) -> Self {
+        self.address_v = mode;
+        self
+    }
+
+    /// Sets the behavior when sampling outside of the `[0.0, 1.0]` range of the third texture
+    /// coordinate.
+    #[inline]
+    pub fn address_mode_w(mut self, mode: SamplerAddressMode) -> Self {
+        self.address_w = mode;
+        self
+    }
+
+    /// Sets the same address mode for the three texture coordinates.
+    #[inline]
+    pub fn address_mode(self, mode: SamplerAddressMode) -> Self {
+        self.address_mode_u(mode).address_mode_v(mode).address_mode_w(mode)
+    }
+
+    /// Sets the value to add to the level of detail computed by the implementation. Its absolute
+    /// value must not exceed the `max_sampler_lod_bias` limit.
+    #[inline]
+    pub fn mip_lod_bias(mut self, bias: f32) -> Self {
+        self.mip_lod_bias = bias;
+        self
+    }
+
+    /// Sets the maximum anisotropy. Must be superior or equal to 1.0. If superior to 1.0, the
+    /// implementation will use anisotropic filtering, which requires the `sampler_anisotropy`
+    /// feature to be enabled and the value to not exceed the `max_sampler_anisotropy` limit.
+    #[inline]
+    pub fn max_anisotropy(mut self, max_anisotropy: f32) -> Self {
+        self.max_anisotropy = max_anisotropy;
+        self
+    }
+
+    /// Sets the minimum and the maximum mipmap level to use. `min_lod` must be inferior or equal
+    /// to `max_lod`.
+    #[inline]
+    pub fn lod(mut self, min_lod: f32, max_lod: f32) -> Self {
+        self.min_lod = min_lod;
+        self.max_lod = max_lod;
+        self
+    }
+
+    /// Turns the sampler into a compare-mode sampler. See `Sampler::compare`.
+    #[inline]
+    pub fn compare(mut self, compare: Compare) -> Self {
+        self.compare = Some(compare);
+        self
+    }
+
+    /// Creates a new sampler with the parameters of the builder.
+    pub fn build(self, device: Arc<Device>) -> Result<Arc<Sampler>, SamplerCreationError> {
+        let SamplerBuilder {
+            mag_filter,
+            min_filter,
+            mipmap_mode,
+            address_u,
+            address_v,
+            address_w,
+            mip_lod_bias,
+            max_anisotropy,
+            min_lod,
+            max_lod,
+            compare,
+        } = self;
+
+        if !(max_anisotropy >= 1.0) {
+            return Err(SamplerCreationError::InvalidMaxAnisotropy { requested: max_anisotropy });
+        }
+
+        if !(min_lod <= max_lod) {
+            return Err(SamplerCreationError::InvalidLodRange {
+                           min_lod: min_lod,
+                           max_lod: max_lod,
+                       });
+        }
+
+        // Check max anisotropy.
+        if max_anisotropy > 1.0 {
+            if !device.enabled_features().sampler_anisotropy {
+                return Err(SamplerCreationError::SamplerAnisotropyFeatureNotEnabled);
+            }
+
+            let limit = device.physical_device().limits().max_sampler_anisotropy();
+            if max_anisotropy > limit {
+                return Err(SamplerCreationError::AnisotropyLimitExceeded {
+                               requested: max_anisotropy,
+                               maximum: limit,
+                           });
+            }
+        }
+
+        // Check mip_lod_bias value.
+        {
+            let limit = device.physical_device().limits().max_sampler_lod_bias();
+            if mip_lod_bias.abs() > limit {
+                return Err(SamplerCreationError::MipLodBiasLimitExceeded {
+                               requested: mip_lod_bias,
+                               maximum: limit,
+                           });
+            }
+        }
+
+        // Check MirrorClampToEdge extension support
+        if [address_u, address_v, address_w]
+            .iter()
+            .any(|&mode| mode == SamplerAddressMode::MirrorClampToEdge)
+        {
+            if !device.loaded_extensions().khr_sampler_mirror_clamp_to_edge {
+                return Err(SamplerCreationError::SamplerMirrorClampToEdgeExtensionNotEnabled);
+            }
+        }
+
+        // Handling border color.
+        let border_color = common_border_color(&[address_u, address_v, address_w])?;
+
+        let vk = device.pointers();
+        let sampler = unsafe {
+            let infos = vk::SamplerCreateInfo {
+                sType: vk::STRUCTURE_TYPE_SAMPLER_CREATE_INFO,
+                pNext: ptr::null(),
+                flags: 0, // reserved
+                magFilter: mag_filter as u32,
+                minFilter: min_filter as u32,
+                mipmapMode: mipmap_mode as u32,
+                addressModeU: address_u.to_vk(),
+                addressModeV: address_v.to_vk(),
+                addressModeW: address_w.to_vk(),
+                mipLodBias: mip_lod_bias,
+                anisotropyEnable: if max_anisotropy > 1.0 {
+                    vk::TRUE
+                } else {
+                    vk::FALSE
+                },
+                maxAnisotropy: max_anisotropy,
+                compareEnable: if compare.is_some() {
+                    vk::TRUE
+                } else {
+                    vk::FALSE
+                },
+                compareOp: compare.map(|c| c as u32).unwrap_or(0),
+                minLod: min_lod,
+                maxLod: max_lod,
+                borderColor: border_color.map(|b| b as u32).unwrap_or(0),
+                unnormalizedCoordinates: vk::FALSE,
+            };
+
+            let mut output = mem::uninitialized();
+            check_errors(vk.CreateSampler(device.internal_object(),
+                                          &infos,
+                                          device.allocation_callbacks().as_ptr(),
+                                          &mut output))?;
+            output
+        };
+
+        Ok(Arc::new(Sampler {
+                        sampler: sampler,
+                        device: device.clone(),
+                        compare_mode: compare.is_some(),
+                        unnormalized: false,
+                        usable_with_float_formats: match border_color {
+                            Some(BorderColor::FloatTransparentBlack) => true,
+                            Some(BorderColor::FloatOpaqueBlack) => true,
+                            Some(BorderColor::FloatOpaqueWhite) => true,
+                            Some(_) => false,
+                            None => true,
+                        },
+                        usable_with_int_formats: compare.is_none() &&
+                            match border_color {
+                                Some(BorderColor::IntTransparentBlack) => true,
+                                Some(BorderColor::IntOpaqueBlack) => true,
+                                Some(BorderColor::IntOpaqueWhite) => true,
+                                Some(_) => false,
+                                None => true,
+                            },
+                        usable_with_swizzling: match border_color {
+                            Some(BorderColor::FloatOpaqueBlack) => false,
+                            Some(BorderColor::IntOpaqueBlack) => false,
+                            _ => true,
+                        },
+                    }))
+    }
+
+    /// Same as `build`, except that if a sampler with the same parameters has already been
+    /// created with `build_cached` on the same device and is still alive, it is returned instead
+    /// of creating a new one.
+    ///
+    /// The device only holds weak references to the samplers, which are destroyed as usual once
+    /// all the `Arc`s returned by this function have been dropped.
+    pub fn build_cached(self, device: Arc<Device>)
+                        -> Result<Arc<Sampler>, SamplerCreationError> {
+        let key = self.key();
+        let mut cache = device.sampler_cache().lock().unwrap();
+
+        if let Some(sampler) = cache.get(&key).and_then(|s| s.upgrade()) {
+            return Ok(sampler);
+        }
+
+        let sampler = self.build(device.clone())?;
+
+        // Remove the entries of the samplers that have been destroyed.
+        cache.retain(|_, s| s.upgrade().is_some());
+        cache.insert(key, Arc::downgrade(&sampler));
+        Ok(sampler)
+    }
+
+    // Builds the key that identifies the parameters in the sampler cache of the device.
+    fn key(&self) -> SamplerKey {
+        SamplerKey {
+            filters: [self.mag_filter, self.min_filter],
+            mipmap_mode: self.mipmap_mode,
+            address: [self.address_u, self.address_v, self.address_w],
+            mip_lod_bias: self.mip_lod_bias.to_bits(),
+            max_anisotropy: self.max_anisotropy.to_bits(),
+            lod: [self.min_lod.to_bits(), self.max_lod.to_bits()],
+            compare: self.compare.map(|c| c as u32),
+        }
+    }
+}
+
+// Parameters of a sampler, used as a key in the sampler cache of the device. The floating-point
+// values are stored as their bits so that the key can implement `Eq` and `Hash`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct SamplerKey {
+    filters: [Filter; 2],
+    mipmap_mode: MipmapMode,
+    address: [SamplerAddressMode; 3],
+    mip_lod_bias: u32,
+    max_anisotropy: u32,
+    lod: [u32; 2],
+    compare: Option<u32>,
+}
+
+// Returns the border color shared by all the `ClampToBorder` address modes of the list, or an
+// error if they don't use the same color.
+fn common_border_color(modes: &[SamplerAddressMode])
+                       -> Result<Option<BorderColor>, SamplerCreationError> {
+    let mut border_color = None;
+
+    for color in modes.iter().filter_map(|m| m.border_color()) {
+        match border_color {
+            Some(c) if c != color => return Err(SamplerCreationError::BorderColorMismatch),
+            _ => border_color = Some(color),
+        }
+    }
+
+    Ok(border_color)
+}
+
 /// Describes how the color of each pixel should be determined.
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 #[repr(u32)]
@@ -671,6 +939,23 @@ pub enum SamplerCreationError {
     /// Using `MirrorClampToEdge` requires enabling the `VK_KHR_sampler_mirror_clamp_to_edge`
     /// extension when creating the device.
     SamplerMirrorClampToEdgeExtensionNotEnabled,
+
+    /// The maximum anisotropy must be superior or equal to 1.0.
+    InvalidMaxAnisotropy {
+        /// The value that was requested.
+        requested: f32,
+    },
+
+    /// The minimum lod must be inferior or equal to the maximum lod.
+    InvalidLodRange {
+        /// The minimum lod that was requested.
+        min_lod: f32,
+        /// The maximum lod that was requested.
+        max_lod: f32,
+    },
+
+    /// Multiple `ClampToBorder` address modes were passed with different border colors.
+    BorderColorMismatch,
 }
 
 impl error::Error for SamplerCreationError {
@@ -685,6 +970,12 @@ impl error::Error for SamplerCreationError {
             SamplerCreationError::MipLodBiasLimitExceeded { .. } => "mip lod bias limit exceeded",
             SamplerCreationError::SamplerMirrorClampToEdgeExtensionNotEnabled =>
                 "the device extension `VK_KHR_sampler_mirror_clamp_to_edge` is not enabled",
+            SamplerCreationError::InvalidMaxAnisotropy { .. } =>
+                "the maximum anisotropy is inferior to 1.0",
+            SamplerCreationError::InvalidLodRange { .. } =>
+                "the minimum lod is superior to the maximum lod",
+            SamplerCreationError::BorderColorMismatch =>
+                "the `ClampToBorder` address modes don't use the same border color",
         }
     }
 
@@ -726,6 +1017,7 @@ impl From<Error> for SamplerCreationError {
 #[cfg(test)]
 mod tests {
     use sampler;
+    use std::sync::Arc;
 
     #[test]
     fn create_regular() {
@@ -943,4 +1235,71 @@ mod tests {
             _ => panic!(),
         }
     }
+
+    #[test]
+    fn builder_defaults() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let s = sampler::Sampler::start().build(device).unwrap();
+        assert!(!s.compare_mode());
+        assert!(!s.is_unnormalized());
+    }
+
+    #[test]
+    fn builder_invalid_lod_range() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let r = sampler::Sampler::start().lod(5.0, 2.0).build(device);
+
+        match r {
+            Err(sampler::SamplerCreationError::InvalidLodRange { .. }) => (),
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn builder_invalid_max_anisotropy() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let r = sampler::Sampler::start().max_anisotropy(0.5).build(device);
+
+        match r {
+            Err(sampler::SamplerCreationError::InvalidMaxAnisotropy { .. }) => (),
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn builder_different_borders() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let b1 = sampler::BorderColor::IntTransparentBlack;
+        let b2 = sampler::BorderColor::FloatOpaqueWhite;
+
+        let r = sampler::Sampler::start()
+            .address_mode_u(sampler::SamplerAddressMode::ClampToBorder(b1))
+            .address_mode_v(sampler::SamplerAddressMode::ClampToBorder(b2))
+            .build(device);
+
+        match r {
+            Err(sampler::SamplerCreationError::BorderColorMismatch) => (),
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn build_cached() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let builder = sampler::Sampler::start().filter(sampler::Filter::Nearest);
+        let s1 = builder.build_cached(device.clone()).unwrap();
+        let s2 = builder.build_cached(device.clone()).unwrap();
+        assert!(Arc::ptr_eq(&s1, &s2));
+
+        let s3 = builder
+            .address_mode(sampler::SamplerAddressMode::ClampToEdge)
+            .build_cached(device.clone())
+            .unwrap();
+        assert!(!Arc::ptr_eq(&s1, &s3));
+    }
 }
@@ -92,7 +92,7 @@ impl Surface {
             let mut output = mem::uninitialized();
             check_errors(vk.CreateDisplayPlaneSurfaceKHR(instance.internal_object(),
                                                          &infos,
-                                                         ptr::null(),
+                                                         instance.allocation_callbacks().as_ptr(),
                                                          &mut output))?;
             output
         };
@@ -132,7 +132,7 @@ impl Surface {
             let mut output = mem::uninitialized();
             check_errors(vk.CreateWin32SurfaceKHR(instance.internal_object(),
                                                   &infos,
-                                                  ptr::null(),
+                                                  instance.allocation_callbacks().as_ptr(),
                                                   &mut output))?;
             output
         };
@@ -172,7 +172,7 @@ impl Surface {
             let mut output = mem::uninitialized();
             check_errors(vk.CreateXcbSurfaceKHR(instance.internal_object(),
                                                 &infos,
-                                                ptr::null(),
+                                                instance.allocation_callbacks().as_ptr(),
                                                 &mut output))?;
             output
         };
@@ -212,7 +212,7 @@ impl Surface {
             let mut output = mem::uninitialized();
             check_errors(vk.CreateXlibSurfaceKHR(instance.internal_object(),
                                                  &infos,
-                                                 ptr::null(),
+                                                 instance.allocation_callbacks().as_ptr(),
                                                  &mut output))?;
             output
         };
@@ -253,7 +253,7 @@ impl Surface {
             let mut output = mem::uninitialized();
             check_errors(vk.CreateWaylandSurfaceKHR(instance.internal_object(),
                                                     &infos,
-                                                    ptr::null(),
+                                                    instance.allocation_callbacks().as_ptr(),
                                                     &mut output))?;
             output
         };
@@ -294,7 +294,7 @@ impl Surface {
             let mut output = mem::uninitialized();
             check_errors(vk.CreateMirSurfaceKHR(instance.internal_object(),
                                                 &infos,
-                                                ptr::null(),
+                                                instance.allocation_callbacks().as_ptr(),
                                                 &mut output))?;
             output
         };
@@ -331,7 +331,7 @@ impl Surface {
             let mut output = mem::uninitialized();
             check_errors(vk.CreateAndroidSurfaceKHR(instance.internal_object(),
                                                     &infos,
-                                                    ptr::null(),
+                                                    instance.allocation_callbacks().as_ptr(),
                                                     &mut output))?;
             output
         };
@@ -369,7 +369,7 @@ impl Surface {
             let mut output = mem::uninitialized();
             check_errors(vk.CreateIOSSurfaceMVK(instance.internal_object(),
                                                 &infos,
-                                                ptr::null(),
+                                                instance.allocation_callbacks().as_ptr(),
                                                 &mut output))?;
             output
         };
@@ -407,7 +407,7 @@ impl Surface {
             let mut output = mem::uninitialized();
             check_errors(vk.CreateMacOSSurfaceMVK(instance.internal_object(),
                                                   &infos,
-                                                  ptr::null(),
+                                                  instance.allocation_callbacks().as_ptr(),
                                                   &mut output))?;
             output
         };
@@ -444,7 +444,7 @@ impl Surface {
             let mut output = mem::uninitialized();
             check_errors(vk.CreateViSurfaceNN(instance.internal_object(),
                                               &infos,
-                                              ptr::null(),
+                                              instance.allocation_callbacks().as_ptr(),
                                               &mut output))?;
             output
         };
@@ -603,7 +603,9 @@ impl Drop for Surface {
     fn drop(&mut self) {
         unsafe {
             let vk = self.instance.pointers();
-            vk.DestroySurfaceKHR(self.instance.internal_object(), self.surface, ptr::null());
+            vk.DestroySurfaceKHR(self.instance.internal_object(),
+                                 self.surface,
+                                 self.instance.allocation_callbacks().as_ptr());
         }
     }
 }
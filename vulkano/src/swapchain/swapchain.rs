@@ -43,6 +43,7 @@ use swapchain::SurfaceTransform;
 use sync::AccessCheckError;
 use sync::AccessError;
 use sync::AccessFlagBits;
+use sync::Fence;
 use sync::FlushError;
 use sync::GpuFuture;
 use sync::PipelineStages;
@@ -353,7 +354,7 @@ impl Swapchain {
             let mut output = mem::uninitialized();
             check_errors(vk.CreateSwapchainKHR(device.internal_object(),
                                                &infos,
-                                               ptr::null(),
+                                               device.allocation_callbacks().as_ptr(),
                                                &mut output))?;
             output
         };
@@ -530,7 +531,9 @@ impl Drop for Swapchain {
     fn drop(&mut self) {
         unsafe {
             let vk = self.device.pointers();
-            vk.DestroySwapchainKHR(self.device.internal_object(), self.swapchain, ptr::null());
+            vk.DestroySwapchainKHR(self.device.internal_object(),
+                                   self.swapchain,
+                                   self.device.allocation_callbacks().as_ptr());
             self.surface.flag().store(false, Ordering::Release);
         }
     }
@@ -978,6 +981,11 @@ unsafe impl<P> GpuFuture for PresentFuture<P>
         self.previous.signal_finished();
     }
 
+    #[inline]
+    unsafe fn attach_fence(&self, fence: &Arc<Fence>) {
+        self.previous.attach_fence(fence)
+    }
+
     #[inline]
     fn queue_change_allowed(&self) -> bool {
         false
@@ -8,7 +8,6 @@
 // according to those terms.
 
 use std::mem;
-use std::ptr;
 use std::sync::Arc;
 
 use OomError;
@@ -49,7 +48,7 @@ impl Event {
             let vk = device.pointers();
             check_errors(vk.CreateEvent(device.internal_object(),
                                         &INFOS,
-                                        ptr::null(),
+                                        device.allocation_callbacks().as_ptr(),
                                         &mut output))?;
             output
         };
@@ -152,7 +151,9 @@ impl Drop for Event {
     fn drop(&mut self) {
         unsafe {
             let vk = self.device.pointers();
-            vk.DestroyEvent(self.device.internal_object(), self.event, ptr::null());
+            vk.DestroyEvent(self.device.internal_object(),
+                            self.event,
+                            self.device.allocation_callbacks().as_ptr());
         }
     }
 }
@@ -77,7 +77,7 @@ impl<D> Fence<D>
             let mut output = mem::uninitialized();
             check_errors(vk.CreateFence(device.internal_object(),
                                         &infos,
-                                        ptr::null(),
+                                        device.allocation_callbacks().as_ptr(),
                                         &mut output))?;
             output
         };
@@ -284,7 +284,9 @@ impl<D> Drop for Fence<D>
     fn drop(&mut self) {
         unsafe {
             let vk = self.device.pointers();
-            vk.DestroyFence(self.device.internal_object(), self.fence, ptr::null());
+            vk.DestroyFence(self.device.internal_object(),
+                            self.fence,
+                            self.device.allocation_callbacks().as_ptr());
         }
     }
 }
@@ -37,7 +37,7 @@ pub fn then_signal_fence<F>(future: F, behavior: FenceSignalFutureBehavior) -> F
 
     assert!(future.queue().is_some()); // TODO: document
 
-    let fence = Fence::new(device.clone()).unwrap();
+    let fence = Arc::new(Fence::new(device.clone()).unwrap());
     FenceSignalFuture {
         device: device,
         state: Mutex::new(FenceSignalFutureState::Pending(future, fence)),
@@ -104,17 +104,17 @@ pub struct FenceSignalFuture<F>
 // been dropped).
 enum FenceSignalFutureState<F> {
     // Newly-created. Not submitted yet.
-    Pending(F, Fence),
+    Pending(F, Arc<Fence>),
 
     // Partially submitted to the queue. Only happens in situations where submitting requires two
     // steps, and when the first step succeeded while the second step failed.
     //
     // Note that if there's ever a submit operation that needs three steps we will need to rework
     // this code, as it was designed for two-step operations only.
-    PartiallyFlushed(F, Fence),
+    PartiallyFlushed(F, Arc<Fence>),
 
     // Submitted to the queue.
-    Flushed(F, Fence),
+    Flushed(F, Arc<Fence>),
 
     // The submission is finished. The previous future and the fence have been cleaned.
     Cleaned,
@@ -233,8 +233,9 @@ impl<F> FenceSignalFuture<F>
                 },
                 SubmitAnyBuilder::SemaphoresWait(sem) => {
                     debug_assert!(!partially_flushed);
-                    let b: SubmitCommandBufferBuilder = sem.into();
+                    let mut b: SubmitCommandBufferBuilder = sem.into();
                     debug_assert!(!b.has_fence());
+                    b.set_fence_signal(&fence);
                     b.submit(&queue).map_err(|err| OutcomeErr::Full(err.into()))
                 },
                 SubmitAnyBuilder::CommandBuffer(mut cb_builder) => {
@@ -281,6 +282,9 @@ impl<F> FenceSignalFuture<F>
             // Restore the state before returning.
             match result {
                 Ok(()) => {
+                    // The resources locked by the submission can now be waited upon with the
+                    // fence.
+                    previous.attach_fence(&fence);
                     **state = FenceSignalFutureState::Flushed(previous, fence);
                     Ok(())
                 },
@@ -360,6 +364,11 @@ unsafe impl<F> GpuFuture for FenceSignalFuture<F>
         }
     }
 
+    #[inline]
+    unsafe fn attach_fence(&self, _: &Arc<Fence>) {
+        // The previous future has already been given our own fence when we were flushed.
+    }
+
     #[inline]
     fn queue_change_allowed(&self) -> bool {
         match self.behavior {
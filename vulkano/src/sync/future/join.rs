@@ -18,6 +18,7 @@ use image::ImageAccess;
 use image::ImageLayout;
 use sync::AccessCheckError;
 use sync::AccessFlagBits;
+use sync::Fence;
 use sync::FlushError;
 use sync::GpuFuture;
 use sync::PipelineStages;
@@ -168,6 +169,12 @@ unsafe impl<A, B> GpuFuture for JoinFuture<A, B>
         self.second.signal_finished();
     }
 
+    #[inline]
+    unsafe fn attach_fence(&self, fence: &Arc<Fence>) {
+        self.first.attach_fence(fence);
+        self.second.attach_fence(fence);
+    }
+
     #[inline]
     fn queue_change_allowed(&self) -> bool {
         self.first.queue_change_allowed() && self.second.queue_change_allowed()
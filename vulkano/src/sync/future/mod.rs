@@ -28,6 +28,7 @@ use swapchain;
 use swapchain::PresentFuture;
 use swapchain::Swapchain;
 use sync::AccessFlagBits;
+use sync::Fence;
 use sync::FenceWaitError;
 use sync::PipelineStages;
 
@@ -35,11 +36,14 @@ pub use self::fence_signal::{FenceSignalFuture, FenceSignalFutureBehavior};
 pub use self::join::JoinFuture;
 pub use self::now::{NowFuture, now};
 pub use self::semaphore_signal::SemaphoreSignalFuture;
+pub use self::sparse_bind::{SparseBindError, SparseBindFuture};
+pub(crate) use self::sparse_bind::{SparseBindOp, SparseGpuLock, bind_sparse};
 
 mod now;
 mod fence_signal;
 mod join;
 mod semaphore_signal;
+mod sparse_bind;
 
 /// Represents an event that will happen on the GPU in the future.
 ///
@@ -132,6 +136,16 @@ pub unsafe trait GpuFuture: DeviceOwned {
                           queue: &Queue)
                           -> Result<Option<(PipelineStages, AccessFlagBits)>, AccessCheckError>;
 
+    /// Informs the future that the submission it is part of has been submitted, and that `fence`
+    /// will be signaled once the GPU has finished executing it.
+    ///
+    /// Called by `FenceSignalFuture` after a successful flush, so that the resources locked by
+    /// this future can be waited upon by the CPU. The implementation should forward the call to
+    /// the futures and command buffers it holds. The default implementation does nothing.
+    #[inline]
+    unsafe fn attach_fence(&self, fence: &Arc<Fence>) {
+    }
+
     /// Joins this future with another one, representing the moment when both events have happened.
     // TODO: handle errors
     fn join<F>(self, other: F) -> JoinFuture<Self, F>
@@ -264,6 +278,11 @@ unsafe impl<F: ?Sized> GpuFuture for Box<F>
         (**self).signal_finished()
     }
 
+    #[inline]
+    unsafe fn attach_fence(&self, fence: &Arc<Fence>) {
+        (**self).attach_fence(fence)
+    }
+
     #[inline]
     fn queue_change_allowed(&self) -> bool {
         (**self).queue_change_allowed()
@@ -23,6 +23,7 @@ use image::ImageAccess;
 use image::ImageLayout;
 use sync::AccessCheckError;
 use sync::AccessFlagBits;
+use sync::Fence;
 use sync::FlushError;
 use sync::GpuFuture;
 use sync::PipelineStages;
@@ -131,6 +132,11 @@ unsafe impl<F> GpuFuture for SemaphoreSignalFuture<F>
         self.previous.signal_finished();
     }
 
+    #[inline]
+    unsafe fn attach_fence(&self, fence: &Arc<Fence>) {
+        self.previous.attach_fence(fence)
+    }
+
     #[inline]
     fn queue_change_allowed(&self) -> bool {
         true
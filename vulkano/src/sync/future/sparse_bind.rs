@@ -0,0 +1,559 @@
+// Copyright (c) 2017 The vulkano developers
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>,
+// at your option. All files in the project carrying such
+// notice may not be copied, modified, or distributed except
+// according to those terms.
+
+use std::error;
+use std::fmt;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+
+use buffer::BufferAccess;
+use command_buffer::submit::SubmitAnyBuilder;
+use command_buffer::submit::SubmitBindSparseBatchBuilder;
+use command_buffer::submit::SubmitBindSparseBufferBindBuilder;
+use command_buffer::submit::SubmitBindSparseBuilder;
+use command_buffer::submit::SubmitBindSparseImageBindBuilder;
+use command_buffer::submit::SubmitBindSparseImageOpaqueBindBuilder;
+use command_buffer::submit::SubmitSemaphoresWaitBuilder;
+use command_buffer::sys::UnsafeCommandBufferBuilderImageAspect;
+use device::Device;
+use device::DeviceOwned;
+use device::Queue;
+use image::ImageAccess;
+use image::ImageLayout;
+use memory::pool::MemoryPoolAlloc;
+use memory::pool::StdMemoryPoolAlloc;
+use sync::AccessCheckError;
+use sync::AccessError;
+use sync::AccessFlagBits;
+use sync::Fence;
+use sync::FlushError;
+use sync::GpuFuture;
+use sync::PipelineStages;
+use sync::Semaphore;
+
+use OomError;
+use VulkanObject;
+
+/// Represents a sparse binding operation that modifies the memory bound to a `SparseBuffer` or a
+/// `SparseImage`.
+///
+/// Obtained by calling `bind` or `unbind` on one of these resources. The resource is locked
+/// exclusively until the future is destroyed.
+#[must_use = "Dropping this object will immediately block the thread until the GPU has finished \
+              processing the submission"]
+pub struct SparseBindFuture<F>
+    where F: GpuFuture
+{
+    previous: F,
+    queue: Arc<Queue>,
+    op: SparseBindOp,
+    // Signaled when the bind operation is finished. Subsequent submissions wait on it.
+    semaphore: Semaphore,
+    // Used only if the previous future is a command buffer submission or a sparse binding
+    // operation that must be submitted first.
+    previous_semaphore: Semaphore,
+    // Memory that was unbound by the operation, and that must be kept alive until the GPU has
+    // finished executing it.
+    unbound_memory: Mutex<Vec<Arc<StdMemoryPoolAlloc>>>,
+    // Called once the GPU has finished executing the operation, in order to update the residency
+    // of the resource.
+    on_finished: Mutex<Option<Box<Fn() + Send + Sync>>>,
+    // True if the bind operation has already been submitted.
+    // If flush is called multiple times, we want to block so that only one flushing is executed.
+    // Therefore we use a `Mutex<bool>` and not an `AtomicBool`.
+    submitted: Mutex<bool>,
+    finished: AtomicBool,
+}
+
+/// The actual bind operation performed by a `SparseBindFuture`.
+///
+/// The memory being bound is held by the operation, so that it stays alive at least until the GPU
+/// has finished executing it.
+pub(crate) enum SparseBindOp {
+    Buffer {
+        buffer: Arc<BufferAccess + Send + Sync>,
+        // Offset, size and memory. No memory means unbind.
+        binds: Vec<(usize, usize, Option<Arc<StdMemoryPoolAlloc>>)>,
+    },
+    Image {
+        image: Arc<ImageAccess + Send + Sync>,
+        // Offset, size and memory of the mip tails. No memory means unbind.
+        opaque_binds: Vec<(usize, usize, Option<Arc<StdMemoryPoolAlloc>>)>,
+        // Aspect, mip level, array layer, offset, extent and memory. No memory means unbind.
+        image_binds: Vec<(UnsafeCommandBufferBuilderImageAspect,
+                          u32,
+                          u32,
+                          [u32; 3],
+                          [u32; 3],
+                          Option<Arc<StdMemoryPoolAlloc>>)>,
+    },
+}
+
+impl SparseBindOp {
+    fn try_gpu_lock(&self, exclusive: bool, queue: &Queue) -> Result<(), AccessError> {
+        match *self {
+            SparseBindOp::Buffer { ref buffer, .. } => buffer.try_gpu_lock(exclusive, queue),
+            SparseBindOp::Image { ref image, .. } => image.try_gpu_lock(exclusive, queue),
+        }
+    }
+
+    unsafe fn increase_gpu_lock(&self) {
+        match *self {
+            SparseBindOp::Buffer { ref buffer, .. } => buffer.increase_gpu_lock(),
+            SparseBindOp::Image { ref image, .. } => image.increase_gpu_lock(),
+        }
+    }
+
+    unsafe fn unlock(&self) {
+        match *self {
+            SparseBindOp::Buffer { ref buffer, .. } => buffer.unlock(),
+            SparseBindOp::Image { ref image, .. } => image.unlock(),
+        }
+    }
+
+    // Adds the operation to a batch.
+    unsafe fn add_to_batch<'a>(&'a self, batch: &mut SubmitBindSparseBatchBuilder<'a>) {
+        match *self {
+            SparseBindOp::Buffer { ref buffer, ref binds } => {
+                if !binds.is_empty() {
+                    let mut builder =
+                        SubmitBindSparseBufferBindBuilder::new(buffer.inner().buffer);
+                    for &(offset, size, ref memory) in binds.iter() {
+                        match *memory {
+                            Some(ref mem) => builder.add_bind(offset, size, mem.memory(),
+                                                              mem.offset()),
+                            None => builder.add_unbind(offset, size),
+                        }
+                    }
+                    batch.add_buffer(builder);
+                }
+            },
+            SparseBindOp::Image {
+                ref image,
+                ref opaque_binds,
+                ref image_binds,
+            } => {
+                if !opaque_binds.is_empty() {
+                    let mut builder =
+                        SubmitBindSparseImageOpaqueBindBuilder::new(image.inner().image);
+                    for &(offset, size, ref memory) in opaque_binds.iter() {
+                        match *memory {
+                            Some(ref mem) => builder.add_bind(offset, size, mem.memory(),
+                                                              mem.offset(), false),
+                            None => builder.add_unbind(offset, size),
+                        }
+                    }
+                    batch.add_image_opaque(builder);
+                }
+
+                if !image_binds.is_empty() {
+                    let mut builder = SubmitBindSparseImageBindBuilder::new(image.inner().image);
+                    for &(aspect, mip_level, array_layer, offset, extent, ref memory) in
+                        image_binds.iter()
+                    {
+                        match *memory {
+                            Some(ref mem) => builder.add_bind(aspect, mip_level, array_layer,
+                                                              offset, extent, mem.memory(),
+                                                              mem.offset()),
+                            None => builder.add_unbind(aspect, mip_level, array_layer, offset,
+                                                       extent),
+                        }
+                    }
+                    batch.add_image(builder);
+                }
+            },
+        }
+    }
+}
+
+/// Builds a new sparse bind future. Locks the resource of `op` exclusively.
+pub(crate) fn bind_sparse<F>(future: F, queue: Arc<Queue>, op: SparseBindOp)
+                             -> Result<SparseBindFuture<F>, SparseBindError>
+    where F: GpuFuture
+{
+    assert_eq!(future.device().internal_object(),
+               queue.device().internal_object());
+
+    if !queue.family().supports_sparse_binding() {
+        return Err(SparseBindError::SparseBindingNotSupported);
+    }
+
+    let device = queue.device().clone();
+    let semaphore = Semaphore::new(device.clone())?;
+    let previous_semaphore = Semaphore::new(device)?;
+
+    let prev_access = match op {
+        SparseBindOp::Buffer { ref buffer, .. } => {
+            future.check_buffer_access(&**buffer, true, &queue)
+        },
+        SparseBindOp::Image { ref image, .. } => {
+            future.check_image_access(&**image, ImageLayout::Undefined, true, &queue)
+        },
+    };
+
+    match prev_access {
+        Ok(_) => unsafe {
+            op.increase_gpu_lock();
+        },
+        Err(AccessCheckError::Unknown) => op.try_gpu_lock(true, &queue)?,
+        Err(AccessCheckError::Denied(err)) => return Err(err.into()),
+    }
+
+    Ok(SparseBindFuture {
+           previous: future,
+           queue: queue,
+           op: op,
+           semaphore: semaphore,
+           previous_semaphore: previous_semaphore,
+           unbound_memory: Mutex::new(Vec::new()),
+           on_finished: Mutex::new(None),
+           submitted: Mutex::new(false),
+           finished: AtomicBool::new(false),
+       })
+}
+
+impl<F> SparseBindFuture<F>
+    where F: GpuFuture
+{
+    // Keeps the memory alive until the GPU has finished executing the operation.
+    #[inline]
+    pub(crate) fn keep_alive(&mut self, memory: Arc<StdMemoryPoolAlloc>) {
+        self.unbound_memory.get_mut().unwrap().push(memory);
+    }
+
+    // Sets the function to call once the GPU has finished executing the operation.
+    #[inline]
+    pub(crate) fn on_finished<C>(&mut self, callback: C)
+        where C: Fn() + Send + Sync + 'static
+    {
+        *self.on_finished.get_mut().unwrap() = Some(Box::new(callback));
+    }
+
+    // Called once the operation and everything it waits for are known to be finished.
+    fn finish(&self) {
+        if let Some(callback) = self.on_finished.lock().unwrap().take() {
+            callback();
+        }
+
+        // The unbound memory is no longer used by any queue and can be freed.
+        self.unbound_memory.lock().unwrap().clear();
+    }
+}
+
+unsafe impl<F> GpuFuture for SparseBindFuture<F>
+    where F: GpuFuture
+{
+    #[inline]
+    fn cleanup_finished(&mut self) {
+        self.previous.cleanup_finished();
+    }
+
+    #[inline]
+    unsafe fn build_submission(&self) -> Result<SubmitAnyBuilder, FlushError> {
+        // Flushing the bind operation, since it must always be submitted before the operations
+        // that wait on it.
+        self.flush()?;
+
+        let mut sem = SubmitSemaphoresWaitBuilder::new();
+        sem.add_wait_semaphore(&self.semaphore);
+        Ok(SubmitAnyBuilder::SemaphoresWait(sem))
+    }
+
+    fn flush(&self) -> Result<(), FlushError> {
+        unsafe {
+            let mut submitted = self.submitted.lock().unwrap();
+
+            if *submitted {
+                return Ok(());
+            }
+
+            let mut builder = SubmitBindSparseBuilder::new();
+
+            let mut batch = match self.previous.build_submission()? {
+                SubmitAnyBuilder::Empty => {
+                    SubmitBindSparseBatchBuilder::new()
+                },
+                SubmitAnyBuilder::SemaphoresWait(sem) => {
+                    sem.into()
+                },
+                SubmitAnyBuilder::CommandBuffer(mut cb_builder) => {
+                    debug_assert_eq!(cb_builder.num_signal_semaphores(), 0);
+                    cb_builder.add_signal_semaphore(&self.previous_semaphore);
+                    cb_builder.submit(&self.previous.queue().unwrap())?;
+                    let mut batch = SubmitBindSparseBatchBuilder::new();
+                    batch.add_wait_semaphore(&self.previous_semaphore);
+                    batch
+                },
+                SubmitAnyBuilder::QueuePresent(present) => {
+                    present.submit(&self.previous.queue().unwrap())?;
+                    SubmitBindSparseBatchBuilder::new()
+                },
+                SubmitAnyBuilder::BindSparse(previous) => {
+                    // Batches start in order, but can finish in a different order. Merging the
+                    // previous batches into a single one lets us wait for all of them.
+                    debug_assert!(!previous.has_fence());
+                    let mut prev_batch = previous.into_single_batch();
+                    prev_batch.add_signal_semaphore(&self.previous_semaphore);
+                    let mut prev_builder = SubmitBindSparseBuilder::new();
+                    prev_builder.add(prev_batch);
+                    prev_builder.submit(&self.previous.queue().unwrap())?;
+                    let mut batch = SubmitBindSparseBatchBuilder::new();
+                    batch.add_wait_semaphore(&self.previous_semaphore);
+                    batch
+                },
+            };
+
+            self.op.add_to_batch(&mut batch);
+            batch.add_signal_semaphore(&self.semaphore);
+            builder.add(batch);
+            builder.submit(&self.queue)?;
+
+            // Only write `true` here in order to try again next time if an error occurs.
+            *submitted = true;
+            Ok(())
+        }
+    }
+
+    #[inline]
+    unsafe fn signal_finished(&self) {
+        debug_assert!(*self.submitted.lock().unwrap());
+        self.finished.store(true, Ordering::SeqCst);
+        self.previous.signal_finished();
+        self.finish();
+    }
+
+    #[inline]
+    unsafe fn attach_fence(&self, fence: &Arc<Fence>) {
+        self.previous.attach_fence(fence)
+    }
+
+    #[inline]
+    fn queue_change_allowed(&self) -> bool {
+        true
+    }
+
+    #[inline]
+    fn queue(&self) -> Option<Arc<Queue>> {
+        Some(self.queue.clone())
+    }
+
+    #[inline]
+    fn check_buffer_access(
+        &self, buffer: &BufferAccess, exclusive: bool, queue: &Queue)
+        -> Result<Option<(PipelineStages, AccessFlagBits)>, AccessCheckError> {
+        if let SparseBindOp::Buffer { buffer: ref ours, .. } = self.op {
+            if ours.inner().buffer.internal_object() == buffer.inner().buffer.internal_object() {
+                return Ok(None);
+            }
+        }
+
+        self.previous
+            .check_buffer_access(buffer, exclusive, queue)
+            .map(|_| None)
+    }
+
+    #[inline]
+    fn check_image_access(&self, image: &ImageAccess, layout: ImageLayout, exclusive: bool,
+                          queue: &Queue)
+                          -> Result<Option<(PipelineStages, AccessFlagBits)>, AccessCheckError> {
+        if let SparseBindOp::Image { image: ref ours, .. } = self.op {
+            if ours.inner().image.internal_object() == image.inner().image.internal_object() {
+                // Binding memory doesn't change the layout of the image.
+                let allowed = ours.initial_layout_requirement();
+                if layout != ImageLayout::Undefined && layout != allowed {
+                    return Err(AccessCheckError::Denied(AccessError::UnexpectedImageLayout {
+                                                            allowed: allowed,
+                                                            requested: layout,
+                                                        }));
+                }
+
+                return Ok(None);
+            }
+        }
+
+        self.previous
+            .check_image_access(image, layout, exclusive, queue)
+            .map(|_| None)
+    }
+}
+
+unsafe impl<F> DeviceOwned for SparseBindFuture<F>
+    where F: GpuFuture
+{
+    #[inline]
+    fn device(&self) -> &Arc<Device> {
+        self.queue.device()
+    }
+}
+
+impl<F> Drop for SparseBindFuture<F>
+    where F: GpuFuture
+{
+    fn drop(&mut self) {
+        unsafe {
+            if !*self.finished.get_mut() {
+                // TODO: handle errors?
+                self.flush().unwrap();
+                // Block until the queue finished.
+                self.queue.wait().unwrap();
+                self.previous.signal_finished();
+                self.finish();
+            }
+
+            self.op.unlock();
+        }
+    }
+}
+
+/// Number of times a `SparseBuffer` or a `SparseImage` is locked on the GPU side.
+#[derive(Debug)]
+pub(crate) struct SparseGpuLock {
+    access: Mutex<GpuAccess>,
+}
+
+#[derive(Debug, Copy, Clone)]
+enum GpuAccess {
+    None,
+    NonExclusive { num: u32 },
+    Exclusive { num: u32 },
+}
+
+impl SparseGpuLock {
+    /// Builds a new lock in the unlocked state.
+    #[inline]
+    pub(crate) fn new() -> SparseGpuLock {
+        SparseGpuLock { access: Mutex::new(GpuAccess::None) }
+    }
+
+    /// Implementation of `try_gpu_lock` for the resource.
+    pub(crate) fn try_gpu_lock(&self, exclusive: bool) -> Result<(), AccessError> {
+        let mut lock = self.access.lock().unwrap();
+        match &mut *lock {
+            a @ &mut GpuAccess::None => {
+                if exclusive {
+                    *a = GpuAccess::Exclusive { num: 1 };
+                } else {
+                    *a = GpuAccess::NonExclusive { num: 1 };
+                }
+
+                Ok(())
+            },
+            &mut GpuAccess::NonExclusive { ref mut num } => {
+                if exclusive {
+                    Err(AccessError::AlreadyInUse)
+                } else {
+                    *num += 1;
+                    Ok(())
+                }
+            },
+            &mut GpuAccess::Exclusive { .. } => {
+                Err(AccessError::AlreadyInUse)
+            },
+        }
+    }
+
+    /// Implementation of `increase_gpu_lock` for the resource.
+    pub(crate) unsafe fn increase_gpu_lock(&self) {
+        let mut lock = self.access.lock().unwrap();
+        match *lock {
+            GpuAccess::None => panic!(),
+            GpuAccess::NonExclusive { ref mut num } => {
+                debug_assert!(*num >= 1);
+                *num += 1;
+            },
+            GpuAccess::Exclusive { ref mut num } => {
+                debug_assert!(*num >= 1);
+                *num += 1;
+            },
+        }
+    }
+
+    /// Implementation of `unlock` for the resource.
+    pub(crate) unsafe fn unlock(&self) {
+        let mut lock = self.access.lock().unwrap();
+        let new_value = match *lock {
+            GpuAccess::None => panic!("Tried to unlock a resource that isn't locked"),
+            GpuAccess::NonExclusive { num } => {
+                assert!(num >= 1);
+                if num == 1 {
+                    GpuAccess::None
+                } else {
+                    GpuAccess::NonExclusive { num: num - 1 }
+                }
+            },
+            GpuAccess::Exclusive { num } => {
+                assert!(num >= 1);
+                if num == 1 {
+                    GpuAccess::None
+                } else {
+                    GpuAccess::Exclusive { num: num - 1 }
+                }
+            },
+        };
+        *lock = new_value;
+    }
+}
+
+/// Error that can happen when binding or unbinding sparse memory.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SparseBindError {
+    /// Not enough memory.
+    OomError(OomError),
+
+    /// Access to the resource has been denied.
+    AccessError(AccessError),
+
+    /// The queue family of the queue doesn't support sparse binding operations.
+    SparseBindingNotSupported,
+}
+
+impl error::Error for SparseBindError {
+    #[inline]
+    fn description(&self) -> &str {
+        match *self {
+            SparseBindError::OomError(_) => "not enough memory",
+            SparseBindError::AccessError(_) => "access to the resource has been denied",
+            SparseBindError::SparseBindingNotSupported => {
+                "the queue family of the queue doesn't support sparse binding operations"
+            },
+        }
+    }
+
+    #[inline]
+    fn cause(&self) -> Option<&error::Error> {
+        match *self {
+            SparseBindError::OomError(ref err) => Some(err),
+            SparseBindError::AccessError(ref err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for SparseBindError {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(fmt, "{}", error::Error::description(self))
+    }
+}
+
+impl From<OomError> for SparseBindError {
+    #[inline]
+    fn from(err: OomError) -> SparseBindError {
+        SparseBindError::OomError(err)
+    }
+}
+
+impl From<AccessError> for SparseBindError {
+    #[inline]
+    fn from(err: AccessError) -> SparseBindError {
+        SparseBindError::AccessError(err)
+    }
+}
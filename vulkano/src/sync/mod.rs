@@ -117,11 +117,17 @@ pub use self::future::GpuFuture;
 pub use self::future::JoinFuture;
 pub use self::future::NowFuture;
 pub use self::future::SemaphoreSignalFuture;
+pub use self::future::SparseBindError;
+pub use self::future::SparseBindFuture;
 pub use self::future::now;
 pub use self::pipeline::AccessFlagBits;
 pub use self::pipeline::PipelineStages;
 pub use self::semaphore::Semaphore;
 
+pub(crate) use self::future::SparseBindOp;
+pub(crate) use self::future::SparseGpuLock;
+pub(crate) use self::future::bind_sparse;
+
 mod event;
 mod fence;
 mod future;
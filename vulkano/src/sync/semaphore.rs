@@ -8,7 +8,6 @@
 // according to those terms.
 
 use std::mem;
-use std::ptr;
 use std::sync::Arc;
 
 use OomError;
@@ -49,7 +48,7 @@ impl<D> Semaphore<D>
             let mut output = mem::uninitialized();
             check_errors(vk.CreateSemaphore(device.internal_object(),
                                             &INFOS,
-                                            ptr::null(),
+                                            device.allocation_callbacks().as_ptr(),
                                             &mut output))?;
             output
         };
@@ -86,7 +85,9 @@ impl<D> Drop for Semaphore<D>
     fn drop(&mut self) {
         unsafe {
             let vk = self.device.pointers();
-            vk.DestroySemaphore(self.device.internal_object(), self.semaphore, ptr::null());
+            vk.DestroySemaphore(self.device.internal_object(),
+                                self.semaphore,
+                                self.device.allocation_callbacks().as_ptr());
         }
     }
 }
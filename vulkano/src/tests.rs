@@ -9,12 +9,37 @@
 
 #![cfg(test)]
 
+use std::iter;
+use std::sync::Arc;
+
+use descriptor::descriptor::DescriptorBufferContentDesc;
+use descriptor::descriptor::DescriptorBufferDesc;
+use descriptor::descriptor::DescriptorDesc;
+use descriptor::descriptor::DescriptorDescTy;
+use descriptor::descriptor::ShaderStages;
+use descriptor::descriptor_set::UnsafeDescriptorSetLayout;
+use descriptor::pipeline_layout::PipelineLayoutDesc;
+use descriptor::pipeline_layout::PipelineLayoutDescNames;
+use descriptor::pipeline_layout::PipelineLayoutDescPcRange;
+use device::Device;
+use pipeline::shader::ShaderModule;
+
 /// Creates an instance or returns if initialization fails.
 macro_rules! instance {
     () => ({
         use instance;
 
-        match instance::Instance::new(None, &instance::InstanceExtensions::none(), None) {
+        // The features and properties of the device extensions can only be queried with
+        // `khr_get_physical_device_properties2`, which is enabled if it is supported.
+        let extensions = match instance::InstanceExtensions::supported_by_core() {
+            Ok(supported) => instance::InstanceExtensions {
+                khr_get_physical_device_properties2: supported.khr_get_physical_device_properties2,
+                .. instance::InstanceExtensions::none()
+            },
+            Err(_) => return
+        };
+
+        match instance::Instance::new(None, &extensions, None) {
             Ok(i) => i,
             Err(_) => return
         }
@@ -22,8 +47,10 @@ macro_rules! instance {
 }
 
 /// Creates a device and a queue for graphics operations.
+///
+/// The device extensions to enable can be passed with `extensions: [...];` before the features.
 macro_rules! gfx_dev_and_queue {
-    ($($feature:ident),*) => ({
+    (extensions: [$($extension:ident),*]; $($feature:ident),*) => ({
         use instance;
         use device::Device;
         use device::DeviceExtensions;
@@ -41,7 +68,17 @@ macro_rules! gfx_dev_and_queue {
             None => return
         };
 
-        let extensions = DeviceExtensions::none();
+        let extensions = DeviceExtensions {
+            $(
+                $extension: true,
+            )*
+            .. DeviceExtensions::none()
+        };
+
+        // If the physical device doesn't support the requested extensions, just return.
+        if DeviceExtensions::supported_by_device(physical).intersection(&extensions) != extensions {
+            return;
+        }
 
         let features = Features {
             $(
@@ -64,6 +101,8 @@ macro_rules! gfx_dev_and_queue {
 
         (device, queues.next().unwrap())
     });
+
+    ($($feature:ident),*) => (gfx_dev_and_queue!(extensions: []; $($feature),*));
 }
 
 macro_rules! assert_should_panic {
@@ -97,3 +136,130 @@ macro_rules! assert_should_panic {
         }
     });
 }
+
+/// Pipeline layout with a single descriptor set and no push constants, for the tests that need a
+/// layout without a shader.
+#[derive(Debug, Clone)]
+pub struct TestLayout {
+    /// Descriptor of each binding of set 0, or `None` if the binding is empty.
+    pub descriptors: Vec<Option<DescriptorDesc>>,
+    /// Names of the bindings, for `descriptor_by_name`.
+    pub names: Vec<(&'static str, usize)>,
+}
+
+impl TestLayout {
+    /// Builds a layout whose set 0 contains `descriptors`, without any name.
+    pub fn new(descriptors: Vec<Option<DescriptorDesc>>) -> TestLayout {
+        TestLayout {
+            descriptors: descriptors,
+            names: Vec::new(),
+        }
+    }
+}
+
+unsafe impl PipelineLayoutDesc for TestLayout {
+    fn num_sets(&self) -> usize {
+        1
+    }
+
+    fn num_bindings_in_set(&self, set: usize) -> Option<usize> {
+        match set {
+            0 => Some(self.descriptors.len()),
+            _ => None,
+        }
+    }
+
+    fn descriptor(&self, set: usize, binding: usize) -> Option<DescriptorDesc> {
+        match set {
+            0 => self.descriptors.get(binding).and_then(|d| d.clone()),
+            _ => None,
+        }
+    }
+
+    fn num_push_constants_ranges(&self) -> usize {
+        0
+    }
+
+    fn push_constants_range(&self, _: usize) -> Option<PipelineLayoutDescPcRange> {
+        None
+    }
+}
+
+unsafe impl PipelineLayoutDescNames for TestLayout {
+    fn descriptor_by_name(&self, name: &str) -> Option<(usize, usize)> {
+        self.names
+            .iter()
+            .find(|&&(n, _)| n == name)
+            .map(|&(_, binding)| (0, binding))
+    }
+}
+
+/// Returns a read-only descriptor of type `ty` with `array_count` elements, accessed by all the
+/// shader stages.
+pub fn test_descriptor(ty: DescriptorDescTy, array_count: u32) -> DescriptorDesc {
+    DescriptorDesc {
+        ty: ty,
+        array_count: array_count,
+        stages: ShaderStages::all(),
+        readonly: true,
+        immutable_samplers: Vec::new(),
+    }
+}
+
+/// Returns the type of a uniform buffer descriptor.
+pub fn uniform_buffer_ty(dynamic: bool) -> DescriptorDescTy {
+    DescriptorDescTy::Buffer(DescriptorBufferDesc {
+                                 dynamic: Some(dynamic),
+                                 storage: false,
+                                 content: DescriptorBufferContentDesc::F32,
+                             })
+}
+
+/// Builds a set layout made of a single uniform buffer at binding 0, accessed by all the graphics
+/// stages.
+pub fn uniform_buffer_layout(device: Arc<Device>) -> Arc<UnsafeDescriptorSetLayout> {
+    let desc = DescriptorDesc {
+        ty: uniform_buffer_ty(false),
+        array_count: 1,
+        stages: ShaderStages::all_graphics(),
+        readonly: true,
+        immutable_samplers: Vec::new(),
+    };
+
+    Arc::new(UnsafeDescriptorSetLayout::new(device, iter::once(Some(desc))).unwrap())
+}
+
+/// SPIR-V execution model of a vertex shader.
+pub const VERTEX: u32 = 0;
+/// SPIR-V execution model of a fragment shader.
+pub const FRAGMENT: u32 = 4;
+/// SPIR-V execution model of a compute shader.
+pub const COMPUTE: u32 = 5;
+
+/// Builds a shader module whose `main` entry point has the given execution model, takes no input,
+/// produces no output and does nothing.
+pub fn empty_shader_module(device: Arc<Device>, execution_model: u32) -> Arc<ShaderModule> {
+    let mut words = vec![0x07230203, 0x00010000, 0, 5, 0];
+    // OpCapability Shader, OpMemoryModel Logical GLSL450 and OpEntryPoint with the name "main".
+    words.extend_from_slice(&[2 << 16 | 17, 1, 3 << 16 | 14, 0, 1]);
+    words.extend_from_slice(&[5 << 16 | 15, execution_model, 1, 0x6e69616d, 0]);
+    match execution_model {
+        // OpExecutionMode OriginUpperLeft.
+        FRAGMENT => words.extend_from_slice(&[3 << 16 | 16, 1, 7]),
+        // OpExecutionMode LocalSize 1 1 1.
+        COMPUTE => words.extend_from_slice(&[6 << 16 | 16, 1, 17, 1, 1, 1]),
+        _ => (),
+    }
+    // OpTypeVoid, OpTypeFunction, then a function made of OpLabel and OpReturn.
+    words.extend_from_slice(&[2 << 16 | 19, 2, 3 << 16 | 33, 3, 2]);
+    words.extend_from_slice(&[5 << 16 | 54, 2, 1, 0, 3, 2 << 16 | 248, 4, 1 << 16 | 253,
+                              1 << 16 | 56]);
+
+    let mut spirv = Vec::with_capacity(words.len() * 4);
+    for word in words {
+        spirv.extend_from_slice(&[word as u8, (word >> 8) as u8, (word >> 16) as u8,
+                                  (word >> 24) as u8]);
+    }
+
+    unsafe { ShaderModule::new(device, &spirv).unwrap() }
+}